@@ -0,0 +1,71 @@
+//! Document preparation and content retention throughput.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use constella_core::tracking::diff::SnapshotStore;
+use constella_core::{IndexManager, SettingsManager};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use tempfile::TempDir;
+
+const PREPARED_FILES: usize = 1_000;
+
+fn prepare_documents(c: &mut Criterion) {
+    let data_dir = TempDir::new().expect("Failed to create data directory");
+    let root = TempDir::new().expect("Failed to create fixture root");
+    let settings = Arc::new(SettingsManager::load(data_dir.path().join("settings.json")));
+    let indexer = IndexManager::new(data_dir.path(), settings).expect("Failed to create indexer");
+
+    let paths: Vec<PathBuf> = (0..PREPARED_FILES)
+        .map(|i| {
+            let path = root.path().join(format!("file_{}.txt", i));
+            fs::write(&path, format!("contents of file {}", i)).unwrap();
+            path
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("document_preparation");
+    group.throughput(Throughput::Elements(PREPARED_FILES as u64));
+    group.bench_function("create_document", |b| {
+        b.iter(|| {
+            for path in &paths {
+                indexer.create_document(path).unwrap();
+            }
+        });
+    });
+    group.finish();
+}
+
+fn content_retention(c: &mut Criterion) {
+    let snapshot_dir = TempDir::new().expect("Failed to create snapshot directory");
+    let root = TempDir::new().expect("Failed to create fixture root");
+    let store = SnapshotStore::new(snapshot_dir.path(), true).expect("Failed to create snapshot store");
+
+    let mut group = c.benchmark_group("content_extraction");
+    for size in [4 * 1024usize, 64 * 1024, 256 * 1024] {
+        let path = root.path().join(format!("sample_{}.txt", size));
+        let line = "The quick brown fox jumps over the lazy dog. 0123456789\n";
+        let mut content = line.repeat(size / line.len() + 1).into_bytes();
+        content.truncate(size);
+
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let mut generation = 0u8;
+            b.iter_batched(
+                || {
+                    // Change the content so every iteration compresses a new copy
+                    generation = generation.wrapping_add(1);
+                    content[0] = b'a' + generation % 26;
+                    fs::write(&path, &content).unwrap();
+                },
+                |_| store.record(&path, size as u64).unwrap(),
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(indexing, prepare_documents, content_retention);
+criterion_main!(indexing);
@@ -0,0 +1,132 @@
+//! Query parsing and search latency over a large synthetic index.
+//!
+//! The index is filled straight through tantivy before the `IndexManager` opens a
+//! writer of its own, so setup stays in seconds even at a million documents.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use constella_core::{IndexManager, SettingsManager};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tantivy::collector::TopDocs;
+use tantivy::{doc, Index};
+use tempfile::TempDir;
+
+const SYNTHETIC_DOCS: u64 = 1_000_000;
+
+const WORDS: &[&str] = &[
+    "report", "invoice", "photo", "draft", "notes", "backup", "budget", "slides",
+    "contract", "summary", "readme", "config", "export", "archive", "resume", "plan",
+];
+const EXTENSIONS: &[&str] = &["txt", "pdf", "jpg", "md", "docx", "rs", "json", "zip"];
+
+const QUERIES: &[(&str, &str)] = &[
+    ("common_term", "report"),
+    ("rare_term", "file_999999"),
+    ("two_terms", "budget pdf"),
+    ("phrase", "\"invoice 42\""),
+    ("boolean", "+photo -jpg"),
+];
+
+fn build_index(data_dir: &TempDir) -> IndexManager {
+    let settings = Arc::new(SettingsManager::load(data_dir.path().join("settings.json")));
+    let indexer = IndexManager::new(data_dir.path(), settings).expect("Failed to create indexer");
+
+    let index = Index::open_in_dir(data_dir.path().join("search_index")).expect("Failed to open index");
+    let schema = index.schema();
+    let path = schema.get_field("path").unwrap();
+    let path_exact = schema.get_field("path_exact").unwrap();
+    let parent = schema.get_field("parent").unwrap();
+    let modified = schema.get_field("modified").unwrap();
+    let size = schema.get_field("size").unwrap();
+    let depth = schema.get_field("depth").unwrap();
+
+    let mut writer = index.writer(500_000_000).expect("Failed to create writer");
+    for i in 0..SYNTHETIC_DOCS {
+        let word = WORDS[(i % WORDS.len() as u64) as usize];
+        let extension = EXTENSIONS[(i / 7 % EXTENSIONS.len() as u64) as usize];
+        // Between one and five folders deep, so depth filters have something to prune
+        let dir = format!("/synthetic/dir_{}{}", i % 1000, "/nested".repeat((i % 5) as usize));
+        let file_path = format!("{}/{} {} file_{}.{}", dir, word, i % 100, i, extension);
+        writer.add_document(doc!(
+            path => file_path.as_str(),
+            path_exact => file_path.as_str(),
+            parent => dir.as_str(),
+            modified => 1_700_000_000 + i,
+            size => i * 37 % 10_000_000,
+            depth => Path::new(&file_path).components().count() as u64,
+        )).expect("Failed to add document");
+    }
+    writer.commit().expect("Failed to commit synthetic index");
+    writer.wait_merging_threads().expect("Failed to finish merges");
+
+    indexer
+}
+
+fn benches(c: &mut Criterion) {
+    let data_dir = TempDir::new().expect("Failed to create data directory");
+    let indexer = build_index(&data_dir);
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to build runtime");
+
+    let mut parsing = c.benchmark_group("query_parsing");
+    for (name, query) in QUERIES {
+        parsing.bench_with_input(BenchmarkId::from_parameter(name), query, |b, query| {
+            b.iter(|| indexer.parse_query(query).unwrap());
+        });
+    }
+    parsing.finish();
+
+    let reader = runtime.block_on(indexer.get_reader()).expect("Failed to open reader");
+    // The synthetic documents were committed behind the indexer's back
+    reader.reload().expect("Failed to reload reader");
+    let searcher = reader.searcher();
+    let mut collection = c.benchmark_group("top_docs");
+    for (name, query) in QUERIES {
+        let parsed = indexer.parse_query(query).unwrap();
+        collection.bench_function(*name, |b| {
+            b.iter(|| searcher.search(&parsed, &TopDocs::with_limit(100)).unwrap());
+        });
+    }
+    collection.finish();
+
+    // End to end, including stored field retrieval and JSON conversion
+    let mut search = c.benchmark_group("search");
+    for (name, query) in QUERIES {
+        search.bench_with_input(BenchmarkId::from_parameter(name), query, |b, query| {
+            b.to_async(&runtime).iter(|| indexer.search(query));
+        });
+    }
+    search.finish();
+
+    // `depth:` filters on the stored depth field. The alternative, dropping
+    // hits by path after the search, has to over-fetch and fetch every
+    // candidate's stored path, and still can't promise a full page.
+    let mut depth_filter = c.benchmark_group("depth_filter");
+    depth_filter.bench_function("stored_field", |b| {
+        b.to_async(&runtime).iter(|| indexer.search("report depth:<=3"));
+    });
+    let path_field = searcher.schema().get_field("path").unwrap();
+    let parsed = indexer.parse_query("report").unwrap();
+    depth_filter.bench_function("post_filter", |b| {
+        b.iter(|| {
+            let hits = searcher.search(&parsed, &TopDocs::with_limit(1_000)).unwrap();
+            hits.into_iter()
+                .filter(|(_, address)| {
+                    let doc = searcher.doc(*address).unwrap();
+                    let path = doc.get_first(path_field).and_then(|value| value.as_text()).unwrap_or_default();
+                    // Depth 3 counted from `/`, which is itself a component
+                    Path::new(path).components().count() <= 4
+                })
+                .take(100)
+                .count()
+        });
+    });
+    depth_filter.finish();
+}
+
+criterion_group! {
+    name = search;
+    config = Criterion::default().sample_size(50);
+    targets = benches
+}
+criterion_main!(search);
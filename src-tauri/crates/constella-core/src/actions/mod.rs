@@ -0,0 +1,213 @@
+//! What can be done with a search result, so the command palette and
+//! keyboard navigation offer exactly what the backend can carry out. Each
+//! action applies to some kinds of result and may have a shortcut in
+//! Tauri accelerator syntax (`CmdOrCtrl+Enter`).
+
+use std::path::{Path, PathBuf};
+use log::info;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::audit::{AuditAction, AuditLog};
+use crate::extract::photo;
+use crate::file_system::os_path;
+use crate::tracking::UserAction;
+use crate::IndexManager;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum ResultKind {
+    File,
+    Folder,
+    Photo,
+}
+
+impl ResultKind {
+    pub fn of(path: &Path, is_dir: bool) -> Self {
+        if is_dir {
+            Self::Folder
+        } else if photo::is_photo(path) {
+            Self::Photo
+        } else {
+            Self::File
+        }
+    }
+}
+
+const ALL: &[ResultKind] = &[ResultKind::File, ResultKind::Folder, ResultKind::Photo];
+const FILES: &[ResultKind] = &[ResultKind::File, ResultKind::Photo];
+
+struct Action {
+    id: &'static str,
+    label: &'static str,
+    shortcut: Option<&'static str>,
+    kinds: &'static [ResultKind],
+    /// Works on a selection of several results at once.
+    multiple: bool,
+}
+
+/// Every action, in the order the palette lists them.
+const ACTIONS: &[Action] = &[
+    Action { id: "open", label: "Open", shortcut: Some("Enter"), kinds: ALL, multiple: true },
+    Action { id: "reveal", label: "Show in folder", shortcut: Some("CmdOrCtrl+Enter"), kinds: ALL, multiple: true },
+    Action { id: "preview", label: "Preview", shortcut: Some("Space"), kinds: FILES, multiple: false },
+    Action { id: "copy_path", label: "Copy path", shortcut: Some("CmdOrCtrl+Shift+C"), kinds: ALL, multiple: true },
+    Action { id: "find_identical", label: "Find identical files", shortcut: Some("CmdOrCtrl+I"), kinds: FILES, multiple: false },
+    Action { id: "star", label: "Star", shortcut: Some("CmdOrCtrl+D"), kinds: FILES, multiple: true },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ActionInfo {
+    pub id: String,
+    pub label: String,
+    pub shortcut: Option<String>,
+    pub kinds: Vec<ResultKind>,
+    pub multiple: bool,
+}
+
+impl From<&Action> for ActionInfo {
+    fn from(action: &Action) -> Self {
+        Self {
+            id: action.id.to_string(),
+            label: action.label.to_string(),
+            shortcut: action.shortcut.map(str::to_string),
+            kinds: action.kinds.to_vec(),
+            multiple: action.multiple,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ActionFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ActionOutcome {
+    /// Text for the UI to put on the clipboard.
+    pub clipboard: Option<String>,
+    /// Paths to show: a viewable preview, or the files found.
+    pub paths: Vec<String>,
+    pub failed: Vec<ActionFailure>,
+}
+
+/// The actions offered for `kind`, or every action when `None`.
+pub fn list(kind: Option<ResultKind>) -> Vec<ActionInfo> {
+    ACTIONS.iter()
+        .filter(|action| kind.map_or(true, |kind| action.kinds.contains(&kind)))
+        .map(ActionInfo::from)
+        .collect()
+}
+
+/// Carries out action `action_id` on `paths`. `remember` is false for
+/// incognito windows, whose opens and stars leave the suggestions alone;
+/// they are audited either way. A path the action fails on is
+/// reported in the outcome and doesn't stop the others.
+pub async fn execute(
+    indexer: &IndexManager,
+    audit: &AuditLog,
+    action_id: &str,
+    paths: &[PathBuf],
+    remember: bool,
+) -> Result<ActionOutcome, String> {
+    let action = ACTIONS.iter()
+        .find(|action| action.id == action_id)
+        .ok_or_else(|| format!("Unknown action {}", action_id))?;
+    if paths.is_empty() {
+        return Err("No results selected".to_string());
+    }
+    if paths.len() > 1 && !action.multiple {
+        return Err(format!("{} works on one result at a time", action.label));
+    }
+    for path in paths {
+        let kind = indexer.result_kind(path)?;
+        if !action.kinds.contains(&kind) {
+            return Err(format!("{} isn't available for {}", action.label, path.display()));
+        }
+    }
+
+    let mut outcome = ActionOutcome::default();
+    if action.id == "copy_path" {
+        let lines: Vec<_> = paths.iter().map(|path| path.to_string_lossy()).collect();
+        outcome.clipboard = Some(lines.join("\n"));
+        return Ok(outcome);
+    }
+    for path in paths {
+        let done = match action.id {
+            "open" => match open_path(path) {
+                Ok(()) => record(indexer, audit, path, UserAction::Open, remember).await,
+                Err(e) => Err(e),
+            },
+            "reveal" => reveal_path(path),
+            "preview" => match indexer.photo_preview(path).await {
+                Ok(preview) => {
+                    let shown = preview.unwrap_or_else(|| path.clone());
+                    outcome.paths.push(os_path::encode(&shown));
+                    record(indexer, audit, path, UserAction::Preview, remember).await
+                }
+                Err(e) => Err(e),
+            },
+            "star" => record(indexer, audit, path, UserAction::Star, remember).await,
+            "find_identical" => indexer.find_identical(path).await
+                .map(|identical| outcome.paths.extend(identical)),
+            _ => unreachable!("every action is handled"),
+        };
+        if let Err(error) = done {
+            outcome.failed.push(ActionFailure { path: os_path::encode(path), error });
+        }
+    }
+    info!("Ran {} on {} results, {} failed", action.id, paths.len(), outcome.failed.len());
+    Ok(outcome)
+}
+
+/// Audits `action` on `path` and, when `remember` is set, lets it raise
+/// the file's importance.
+async fn record(indexer: &IndexManager, audit: &AuditLog, path: &Path, action: UserAction, remember: bool) -> Result<(), String> {
+    audit.append(AuditAction::from(action), path, None)?;
+    if remember {
+        indexer.change_tracker().record_user_action(path, action).await?;
+    }
+    Ok(())
+}
+
+/// Opens `path` in its default app.
+pub fn open_path(path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let mut command = std::process::Command::new("open");
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let mut command = std::process::Command::new("xdg-open");
+    command.arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+/// Shows `path` selected in the file manager; on Linux, where that isn't
+/// standard, opens the folder containing it.
+pub fn reveal_path(path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let spawned = std::process::Command::new("open").arg("-R").arg(path).spawn();
+    #[cfg(windows)]
+    let spawned = {
+        let mut select = std::ffi::OsString::from("/select,");
+        select.push(path);
+        std::process::Command::new("explorer").arg(select).spawn()
+    };
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let spawned = std::process::Command::new("xdg-open")
+        .arg(path.parent().unwrap_or(path))
+        .spawn();
+    spawned
+        .map(|_| ())
+        .map_err(|e| format!("Failed to show {}: {}", path.display(), e))
+}
@@ -0,0 +1,208 @@
+//! Append-only log of the file actions taken through the app, for users
+//! who need a record of what was opened, changed or removed. Each entry
+//! is one JSON line carrying the hash of the entry before it, so editing,
+//! reordering or dropping a line breaks the chain from that point on and
+//! `read` reports where. Nothing here rewrites or truncates the file.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::warn;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::tracking::UserAction;
+
+/// `previous_hash` of the first entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum AuditAction {
+    Open,
+    Preview,
+    Star,
+    TagEdit,
+    Delete,
+    Move,
+    /// An earlier version restored over the file.
+    Restore,
+    /// Zipped into a share bundle, named in the detail.
+    Export,
+}
+
+impl From<UserAction> for AuditAction {
+    fn from(action: UserAction) -> Self {
+        match action {
+            UserAction::Open => AuditAction::Open,
+            UserAction::Preview => AuditAction::Preview,
+            UserAction::Star => AuditAction::Star,
+            UserAction::TagEdit => AuditAction::TagEdit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct AuditEntry {
+    #[ts(type = "number")]
+    pub sequence: u64,
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub timestamp: u64,
+    pub action: AuditAction,
+    pub path: String,
+    /// Where a file was moved to, or which version was restored.
+    pub detail: Option<String>,
+    pub previous_hash: String,
+    /// BLAKE3 of the entry with this field empty.
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        let unhashed = AuditEntry { hash: String::new(), ..self.clone() };
+        let json = serde_json::to_string(&unhashed).unwrap_or_default();
+        blake3::hash(json.as_bytes()).to_hex().to_string()
+    }
+}
+
+/// Entries to return, by timestamp; open ends are unbounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct AuditRange {
+    #[ts(type = "number | null")]
+    pub from: Option<u64>,
+    #[ts(type = "number | null")]
+    pub to: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditEntry>,
+    /// Whether the whole chain checked out, not just the returned entries.
+    pub intact: bool,
+    /// Line number (1-based) of the first entry that doesn't follow from
+    /// the one before it.
+    #[ts(type = "number | null")]
+    pub broken_at_line: Option<u64>,
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    // Sequence number and hash of the last entry; also serializes appends
+    tail: Mutex<(u64, String)>,
+}
+
+impl AuditLog {
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let last = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|log| {
+                let line = log.lines().rev().find(|line| !line.trim().is_empty())?.to_string();
+                match serde_json::from_str::<AuditEntry>(&line) {
+                    Ok(entry) => Some(entry),
+                    Err(e) => {
+                        warn!("Failed to parse the last audit log entry at {:?}: {}", path, e);
+                        None
+                    }
+                }
+            });
+        let tail = last.map_or((0, GENESIS_HASH.to_string()), |entry| (entry.sequence + 1, entry.hash));
+
+        Self {
+            path,
+            tail: Mutex::new(tail),
+        }
+    }
+
+    /// Appends an entry for `action` on `path` and returns it.
+    pub fn append(&self, action: AuditAction, path: &Path, detail: Option<String>) -> Result<AuditEntry, String> {
+        let mut tail = self.tail.lock();
+        let mut entry = AuditEntry {
+            sequence: tail.0,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            action,
+            path: path.to_string_lossy().into_owned(),
+            detail,
+            previous_hash: tail.1.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&self.path)
+            .map_err(|e| format!("Failed to open audit log: {}", e))?;
+        writeln!(file, "{}", line)
+            .and_then(|_| file.sync_data())
+            .map_err(|e| format!("Failed to append to audit log: {}", e))?;
+
+        *tail = (entry.sequence + 1, entry.hash.clone());
+        Ok(entry)
+    }
+
+    /// Entries within `range`, oldest first, after checking the whole chain.
+    pub fn read(&self, range: AuditRange) -> Result<AuditLogPage, String> {
+        let log = match std::fs::read_to_string(&self.path) {
+            Ok(log) => log,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("Failed to read audit log: {}", e)),
+        };
+
+        let mut entries = Vec::new();
+        let mut broken_at_line = None;
+        let mut expected = (0, GENESIS_HASH.to_string());
+        for (number, line) in log.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let entry = serde_json::from_str::<AuditEntry>(line).ok();
+            let follows = entry.as_ref().is_some_and(|entry| {
+                entry.sequence == expected.0 && entry.previous_hash == expected.1 && entry.hash == entry.compute_hash()
+            });
+            if !follows && broken_at_line.is_none() {
+                broken_at_line = Some(number as u64 + 1);
+            }
+            let Some(entry) = entry else {
+                continue;
+            };
+            expected = (entry.sequence + 1, entry.hash.clone());
+            if range.from.map_or(true, |from| entry.timestamp >= from) && range.to.map_or(true, |to| entry.timestamp <= to) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(AuditLogPage {
+            entries,
+            intact: broken_at_line.is_none(),
+            broken_at_line,
+        })
+    }
+
+    /// Copies the log, as is, to `destination`.
+    pub fn export(&self, destination: impl AsRef<Path>) -> Result<(), String> {
+        let destination = destination.as_ref();
+        // Hold off appends so the copy ends on a whole entry
+        let _tail = self.tail.lock();
+        if !self.path.exists() {
+            return std::fs::write(destination, "")
+                .map_err(|e| format!("Failed to export audit log to {}: {}", destination.display(), e));
+        }
+        std::fs::copy(&self.path, destination)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to export audit log to {}: {}", destination.display(), e))
+    }
+}
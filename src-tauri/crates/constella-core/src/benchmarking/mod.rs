@@ -1,5 +1,5 @@
 use std::time::{Instant, Duration};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs::{self, OpenOptions, File};
 use std::io::Write;
 use chrono::Local;
@@ -58,10 +58,8 @@ pub struct Benchmarker {
 }
 
 impl Benchmarker {
-    pub fn new() -> Self {
-        let app_dir = tauri::api::path::app_data_dir(&tauri::Config::default())
-            .expect("Failed to get app data directory");
-        let log_path = app_dir.join("benchmarks");
+    pub fn new(app_data_dir: &Path) -> Self {
+        let log_path = app_data_dir.join("benchmarks");
         fs::create_dir_all(&log_path).expect("Failed to create benchmark directory");
         
         let mut sys = System::new_all();
@@ -144,6 +142,7 @@ impl Benchmarker {
         let mut file = OpenOptions::new()
             .write(true)
             .create(true)
+            .truncate(true)
             .open(&file_path)
             .expect("Failed to create benchmark file");
             
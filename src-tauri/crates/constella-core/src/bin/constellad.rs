@@ -0,0 +1,173 @@
+//! Background indexing daemon. Keeps the index fresh while the GUI is closed
+//! and serves search and control requests to it over local IPC.
+//!
+//! Run directly (`constellad`), under systemd/launchd with the units in
+//! `packaging/`, or as a Windows service with `constellad --service`.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use log::{info, warn};
+use tokio::sync::Notify;
+use constella_core::daemon::DaemonServer;
+use constella_core::indexing::IndexManager;
+use constella_core::journal::OperationJournal;
+use constella_core::organize::Organizer;
+use constella_core::persistence::{spawn_tracker_persistence, PersistenceManager};
+use constella_core::settings::SettingsManager;
+use constella_core::versioning::VersionStore;
+use constella_core::volumes;
+use constella_core::watcher::{ChangeType, FileSystemWatcher};
+
+fn app_data_dir() -> PathBuf {
+    constella_core::utils::default_data_dir()
+        .expect("Failed to get app data directory")
+}
+
+/// Starts the indexer core and serves clients until `shutdown` is notified
+/// or a client sends a shutdown request.
+async fn run(shutdown: Option<Arc<Notify>>) -> Result<(), String> {
+    let app_data_dir = app_data_dir();
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let settings = Arc::new(SettingsManager::load(app_data_dir.join("settings.json")));
+
+    let indexer = Arc::new(IndexManager::new(&app_data_dir, settings.clone())?);
+    if let Err(e) = indexer.follow_volumes(&volumes::mounted_volumes()).await {
+        warn!("Failed to follow moved volumes: {}", e);
+    }
+    indexer.load_monitor().spawn_sampler();
+    indexer.power_monitor().spawn_sampler();
+
+    let persistence = Arc::new(PersistenceManager::new(&app_data_dir)
+        .map_err(|e| format!("Failed to create persistence manager: {}", e))?);
+    spawn_tracker_persistence(persistence.clone(), indexer.change_tracker());
+
+    let versions = Arc::new(VersionStore::new(app_data_dir.join("versions"), settings.get().versioning_enabled)
+        .map_err(|e| format!("Failed to create version store: {}", e))?);
+
+    let (change_tx, mut change_rx) = tokio::sync::mpsc::channel(100);
+    let watcher = FileSystemWatcher::new(change_tx, indexer.load_monitor())
+        .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+    let watcher = Arc::new(parking_lot::Mutex::new(watcher));
+    for root in settings.get().watched_roots {
+        info!("Watching {:?}", root);
+        if let Err(e) = watcher.lock().watch(&root) {
+            warn!("Failed to watch {:?}: {}", root, e);
+        }
+    }
+
+    let journal = OperationJournal::load(&app_data_dir);
+    let organizer = Organizer::load(&app_data_dir, settings.clone());
+    let indexer_for_changes = indexer.clone();
+    tokio::spawn(async move {
+        while let Some(changes) = change_rx.recv().await {
+            versions.snapshot_changes(&changes).await;
+            if let Err(e) = indexer_for_changes.apply_changes(&changes).await {
+                warn!("Failed to apply filesystem changes to index: {}", e);
+            }
+            let changed: Vec<PathBuf> = changes.into_iter()
+                .filter(|(_, change)| !matches!(change, ChangeType::Deleted))
+                .map(|(path, _)| path)
+                .collect();
+            if let Err(e) = organizer.organize(&indexer_for_changes, &journal, &changed).await {
+                warn!("Failed to organize changed files: {}", e);
+            }
+        }
+    });
+
+    let server = DaemonServer::new(indexer.clone(), watcher)?;
+    if let Some(shutdown) = shutdown {
+        let handle = server.shutdown_handle();
+        tokio::spawn(async move {
+            shutdown.notified().await;
+            handle.notify_one();
+        });
+    }
+    server.serve(&app_data_dir).await?;
+
+    let states = indexer.change_tracker().export_states().await;
+    persistence.save_state(&states).await
+        .map_err(|e| format!("Failed to save tracking state: {}", e))
+}
+
+fn main() {
+    env_logger::init();
+
+    #[cfg(windows)]
+    if std::env::args().any(|arg| arg == "--service") {
+        if let Err(e) = service::start() {
+            eprintln!("Failed to start service dispatcher: {}", e);
+        }
+        return;
+    }
+
+    info!("Starting Constella daemon");
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create runtime");
+    if let Err(e) = runtime.block_on(run(None)) {
+        eprintln!("Daemon failed: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(windows)]
+mod service {
+    use std::ffi::OsString;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::Notify;
+    use windows_service::{define_windows_service, service_dispatcher};
+    use windows_service::service::{
+        ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+
+    pub const SERVICE_NAME: &str = "ConstellaDaemon";
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    pub fn start() -> windows_service::Result<()> {
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        let shutdown = Arc::new(Notify::new());
+        let stop = shutdown.clone();
+        let status_handle = match service_control_handler::register(SERVICE_NAME, move |control| {
+            match control {
+                ServiceControl::Stop | ServiceControl::Shutdown => {
+                    stop.notify_one();
+                    ServiceControlHandlerResult::NoError
+                }
+                ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+                _ => ServiceControlHandlerResult::NotImplemented,
+            }
+        }) {
+            Ok(handle) => handle,
+            Err(e) => {
+                log::error!("Failed to register service control handler: {}", e);
+                return;
+            }
+        };
+
+        let status = |state, accept| ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: accept,
+            exit_code: ServiceExitCode::Win32(0),
+            checkpoint: 0,
+            wait_hint: Duration::from_secs(10),
+            process_id: None,
+        };
+        let _ = status_handle.set_service_status(status(
+            ServiceState::Running,
+            ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+        ));
+
+        let runtime = tokio::runtime::Runtime::new().expect("Failed to create runtime");
+        if let Err(e) = runtime.block_on(super::run(Some(shutdown))) {
+            log::error!("Daemon failed: {}", e);
+        }
+
+        let _ = status_handle.set_service_status(status(ServiceState::Stopped, ServiceControlAccept::empty()));
+    }
+}
@@ -0,0 +1,178 @@
+//! Share bundles: selected results zipped up with a manifest recording
+//! where each file came from, its BLAKE3 hash and its tags, so whoever
+//! receives the bundle can tell what they got and check it arrived intact.
+//! Files go under `files/` in the archive and the manifest is
+//! `manifest.json` beside them. Entries switch to zip64 as needed, so
+//! neither a file nor the bundle is limited to 4 GiB.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use chrono::{DateTime, Datelike, Local, Timelike};
+use log::info;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+use crate::IndexManager;
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct BundleManifest {
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub created: u64,
+    pub note: Option<String>,
+    pub files: Vec<BundleFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct BundleFile {
+    /// Where it is in the archive.
+    pub name: String,
+    pub original_path: String,
+    #[ts(type = "number")]
+    pub size: u64,
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub modified: u64,
+    pub blake3: String,
+    /// Its image labels and the collections it is in.
+    pub tags: Vec<String>,
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Deflated, stamped with `modified`, and zip64 when `size` needs it.
+fn entry_options(modified: DateTime<Local>, size: u64) -> FileOptions {
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(size >= u32::MAX as u64);
+    // Zip times can't go outside 1980 to 2107
+    let year = modified.year().clamp(1980, 2107) as u16;
+    match zip::DateTime::from_date_and_time(
+        year,
+        modified.month() as u8,
+        modified.day() as u8,
+        modified.hour() as u8,
+        modified.minute() as u8,
+        modified.second() as u8,
+    ) {
+        Ok(time) => options.last_modified_time(time),
+        Err(_) => options,
+    }
+}
+
+/// Copies `data` into the current entry of `zip`, calling `inspect` on
+/// each piece of it along the way.
+fn copy_into<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    data: &mut impl Read,
+    mut inspect: impl FnMut(&[u8]),
+) -> std::io::Result<()> {
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let n = data.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        inspect(&buffer[..n]);
+        zip.write_all(&buffer[..n])?;
+    }
+}
+
+/// `files/` plus the file name of `path`, numbered when another file in
+/// the bundle already has it.
+fn archive_name(path: &Path, taken: &mut HashSet<String>) -> Result<String, String> {
+    let name = path.file_name()
+        .ok_or_else(|| format!("Can't bundle {}", path.display()))?
+        .to_string_lossy();
+    let stem = Path::new(name.as_ref()).file_stem().map_or(name.clone(), |stem| stem.to_string_lossy());
+    let extension = Path::new(name.as_ref()).extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let mut candidate = format!("files/{}", name);
+    let mut n = 2;
+    while !taken.insert(candidate.clone()) {
+        candidate = format!("files/{} ({}){}", stem, n, extension);
+        n += 1;
+    }
+    Ok(candidate)
+}
+
+/// Zips `paths` with a manifest into `dest`, which must not exist yet.
+/// Only files can be bundled; any path that can't be read fails the whole
+/// bundle, so a bundle never quietly leaves something out.
+pub fn export(indexer: &IndexManager, paths: &[PathBuf], dest: &Path, note: Option<String>) -> Result<BundleManifest, String> {
+    if paths.is_empty() {
+        return Err("No results selected".to_string());
+    }
+    if dest.exists() {
+        return Err(format!("{} already exists", dest.display()));
+    }
+    let tmp = dest.with_extension("zip.tmp");
+    let written = write(indexer, paths, &tmp, note)
+        .and_then(|manifest| {
+            std::fs::rename(&tmp, dest)
+                .map_err(|e| format!("Failed to save bundle to {}: {}", dest.display(), e))?;
+            Ok(manifest)
+        });
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    let manifest = written?;
+    info!("Bundled {} files into {:?}", manifest.files.len(), dest);
+    Ok(manifest)
+}
+
+fn write(indexer: &IndexManager, paths: &[PathBuf], tmp: &Path, note: Option<String>) -> Result<BundleManifest, String> {
+    let out = File::create(tmp)
+        .map_err(|e| format!("Failed to create {}: {}", tmp.display(), e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(out));
+    let mut taken = HashSet::new();
+    let mut manifest = BundleManifest { created: unix_seconds(SystemTime::now()), note, files: Vec::with_capacity(paths.len()) };
+    for path in paths {
+        let mut file = File::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let metadata = file.metadata()
+            .map_err(|e| format!("Failed to get metadata for {}: {}", path.display(), e))?;
+        if !metadata.is_file() {
+            return Err(format!("{} isn't a file", path.display()));
+        }
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+        let name = archive_name(path, &mut taken)?;
+        let mut hasher = blake3::Hasher::new();
+        zip.start_file(name.as_str(), entry_options(DateTime::<Local>::from(modified), metadata.len()))
+            .map_err(|e| format!("Failed to bundle {}: {}", path.display(), e))?;
+        copy_into(&mut zip, &mut file, |chunk| {
+            hasher.update(chunk);
+        })
+        .map_err(|e| format!("Failed to bundle {}: {}", path.display(), e))?;
+        manifest.files.push(BundleFile {
+            name,
+            original_path: path.to_string_lossy().into_owned(),
+            size: metadata.len(),
+            modified: unix_seconds(modified),
+            blake3: hasher.finalize().to_hex().to_string(),
+            tags: indexer.file_tags(path)?,
+        });
+    }
+
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize bundle manifest: {}", e))?;
+    zip.start_file(MANIFEST_NAME, entry_options(Local::now(), json.len() as u64))
+        .map_err(std::io::Error::from)
+        .and_then(|_| zip.write_all(&json))
+        .and_then(|_| zip.finish().map_err(std::io::Error::from))
+        .and_then(|out| out.into_inner().map_err(|e| e.into_error()))
+        .and_then(|file| file.sync_all())
+        .map_err(|e| format!("Failed to write bundle: {}", e))?;
+    Ok(manifest)
+}
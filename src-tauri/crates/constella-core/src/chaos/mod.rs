@@ -0,0 +1,117 @@
+//! Debug-only fault injection for exercising the pipeline's recovery paths.
+//!
+//! Set `CONSTELLA_CHAOS` to a failure rate between 0 and 1 (e.g. `0.05`) and
+//! each injection point fails with that probability. `CONSTELLA_CHAOS_SEED`
+//! makes a run reproducible and `CONSTELLA_CHAOS_FAULTS` limits injection to
+//! a comma-separated list of faults (`commit_error,writer_panic`). Release
+//! builds never inject anything, whatever the environment says.
+
+use std::sync::OnceLock;
+use log::warn;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    /// A document in a batch is rejected by the writer.
+    PoisonedBatch,
+    /// The commit after a batch fails.
+    CommitError,
+    /// The watcher can't hand a batch of changes to its consumer.
+    ChannelDisconnect,
+    /// The code holding the index writer panics.
+    WriterPanic,
+}
+
+impl Fault {
+    fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "poisoned_batch" => Some(Fault::PoisonedBatch),
+            "commit_error" => Some(Fault::CommitError),
+            "channel_disconnect" => Some(Fault::ChannelDisconnect),
+            "writer_panic" => Some(Fault::WriterPanic),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChaosConfig {
+    pub rate: f64,
+    pub seed: u64,
+    /// Faults to inject; empty means all of them.
+    pub faults: Vec<Fault>,
+}
+
+struct ChaosState {
+    config: ChaosConfig,
+    rng: fastrand::Rng,
+    injected: u64,
+}
+
+static STATE: OnceLock<Mutex<Option<ChaosState>>> = OnceLock::new();
+
+fn state() -> &'static Mutex<Option<ChaosState>> {
+    STATE.get_or_init(|| Mutex::new(config_from_env().map(ChaosState::new)))
+}
+
+impl ChaosState {
+    fn new(config: ChaosConfig) -> Self {
+        warn!(
+            "Chaos mode enabled: injecting {:?} at rate {} (seed {})",
+            if config.faults.is_empty() { "all faults".to_string() } else { format!("{:?}", config.faults) },
+            config.rate,
+            config.seed
+        );
+        Self {
+            rng: fastrand::Rng::with_seed(config.seed),
+            config,
+            injected: 0,
+        }
+    }
+}
+
+fn config_from_env() -> Option<ChaosConfig> {
+    let rate: f64 = std::env::var("CONSTELLA_CHAOS").ok()?.parse().ok()?;
+    let seed = std::env::var("CONSTELLA_CHAOS_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(|| fastrand::u64(..));
+    let faults = std::env::var("CONSTELLA_CHAOS_FAULTS")
+        .map(|names| names.split(',').filter_map(Fault::parse).collect())
+        .unwrap_or_default();
+    Some(ChaosConfig { rate: rate.clamp(0.0, 1.0), seed, faults })
+}
+
+/// Replaces the environment-derived configuration; `None` turns chaos off.
+pub fn configure(config: Option<ChaosConfig>) {
+    if cfg!(debug_assertions) {
+        *state().lock() = config.map(ChaosState::new);
+    }
+}
+
+/// Decides whether `fault` should happen at this injection point.
+pub fn inject(fault: Fault) -> bool {
+    if !cfg!(debug_assertions) {
+        return false;
+    }
+    let mut state = state().lock();
+    let Some(state) = state.as_mut() else {
+        return false;
+    };
+    if !state.config.faults.is_empty() && !state.config.faults.contains(&fault) {
+        return false;
+    }
+    if state.rng.f64() >= state.config.rate {
+        return false;
+    }
+    state.injected += 1;
+    warn!("Chaos: injecting {:?}", fault);
+    true
+}
+
+/// How many faults have been injected since chaos was configured.
+pub fn injected() -> u64 {
+    state().lock().as_ref().map_or(0, |state| state.injected)
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use log::{info, warn};
+use serde::{Serialize, Deserialize};
+use crate::file_system::os_path;
+use crate::indexing::{IndexedFile, IndexManager};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct CompareOptions {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Hash files whose size matches even when their modification times agree.
+    #[serde(default)]
+    pub verify_content: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DirectoryComparison {
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    pub differing: Vec<String>,
+    pub identical: usize,
+}
+
+struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    fn new(options: &CompareOptions) -> Result<Self, String> {
+        Ok(Self {
+            include: build_glob_set(&options.include)?,
+            exclude: build_glob_set(&options.exclude)?,
+        })
+    }
+
+    fn matches(&self, relative: &Path) -> bool {
+        if let Some(include) = &self.include {
+            if !include.is_match(relative) {
+                return false;
+            }
+        }
+        if let Some(exclude) = &self.exclude {
+            if exclude.is_match(relative) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>, String> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| format!("Invalid glob pattern '{}': {}", pattern, e))?;
+        builder.add(glob);
+    }
+    builder.build()
+        .map(Some)
+        .map_err(|e| format!("Failed to build glob set: {}", e))
+}
+
+fn relative_map(root: &Path, files: Vec<IndexedFile>, filter: &PathFilter) -> HashMap<PathBuf, IndexedFile> {
+    files.into_iter()
+        .filter_map(|file| {
+            let relative = os_path::decode(&file.path).strip_prefix(root).ok()?.to_path_buf();
+            if filter.matches(&relative) {
+                Some((relative, file))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+async fn hash_file(path: &str) -> Option<blake3::Hash> {
+    match tokio::fs::read(path).await {
+        Ok(content) => Some(blake3::hash(&content)),
+        Err(e) => {
+            warn!("Failed to hash {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Compares two indexed directories using stored metadata, falling back to
+/// content hashes only when size and modification time can't settle it.
+pub async fn compare_directories(
+    indexer: &IndexManager,
+    a: impl AsRef<Path>,
+    b: impl AsRef<Path>,
+    options: &CompareOptions,
+) -> Result<DirectoryComparison, String> {
+    let (a, b) = (a.as_ref(), b.as_ref());
+    info!("Comparing directories {:?} and {:?}", a, b);
+    let filter = PathFilter::new(options)?;
+
+    let files_a = relative_map(a, indexer.documents_under(a).await?, &filter);
+    let mut files_b = relative_map(b, indexer.documents_under(b).await?, &filter);
+
+    let mut comparison = DirectoryComparison::default();
+    for (relative, file_a) in files_a {
+        let file_b = match files_b.remove(&relative) {
+            Some(file_b) => file_b,
+            None => {
+                comparison.only_in_a.push(relative.to_string_lossy().into_owned());
+                continue;
+            }
+        };
+
+        let same = if file_a.size != file_b.size {
+            false
+        } else if file_a.modified == file_b.modified && !options.verify_content {
+            true
+        } else {
+            match (hash_file(&file_a.path).await, hash_file(&file_b.path).await) {
+                (Some(hash_a), Some(hash_b)) => hash_a == hash_b,
+                _ => false,
+            }
+        };
+
+        if same {
+            comparison.identical += 1;
+        } else {
+            comparison.differing.push(relative.to_string_lossy().into_owned());
+        }
+    }
+    comparison.only_in_b = files_b.into_keys()
+        .map(|relative| relative.to_string_lossy().into_owned())
+        .collect();
+
+    comparison.only_in_a.sort();
+    comparison.only_in_b.sort();
+    comparison.differing.sort();
+
+    info!(
+        "Comparison complete: {} only in A, {} only in B, {} differing, {} identical",
+        comparison.only_in_a.len(),
+        comparison.only_in_b.len(),
+        comparison.differing.len(),
+        comparison.identical
+    );
+    Ok(comparison)
+}
@@ -0,0 +1,405 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use sysinfo::{Pid, PidExt, System, SystemExt};
+use serde::{Serialize, Deserialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use log::{error, info, warn};
+use crate::indexing::IndexManager;
+use crate::indexing::shedding::SheddingMeasure;
+use crate::search::{SearchFacets, SearchOptions, SearchResponse};
+use crate::utils;
+use crate::watcher::FileSystemWatcher;
+
+const CONNECTION_FILE: &str = "daemon.json";
+const LEASE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Written by the daemon so clients on the same account can find it. The
+/// token keeps other local users from driving the daemon over loopback.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionInfo {
+    pub port: u16,
+    pub token: String,
+    pub pid: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonRequest {
+    Ping,
+    Search {
+        query: String,
+        #[serde(default)]
+        options: SearchOptions,
+    },
+    StartIndexing { directory: String },
+    CancelIndexing,
+    PauseIndexing,
+    ResumeIndexing,
+    Status,
+    Watch { directory: String },
+    Unwatch { directory: String },
+    /// Asks the daemon to commit and release the index writer to process `pid`.
+    AcquireWriter { pid: u32 },
+    /// Returns a writer lease; the daemon reopens its writer and catches up.
+    ReleaseWriter { lease_id: u64 },
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonResponse {
+    Pong { version: String },
+    SearchResults {
+        results: Vec<serde_json::Value>,
+        index_completeness: f32,
+        #[serde(default)]
+        facets: Option<SearchFacets>,
+        #[serde(default)]
+        shed: Vec<SheddingMeasure>,
+        #[serde(default)]
+        snapshot: Option<u64>,
+    },
+    Status {
+        state: String,
+        total_files: usize,
+        processed_files: usize,
+        current_file: String,
+        files_per_second: f32,
+        elapsed_seconds: u64,
+        #[serde(default)]
+        skipped_files: usize,
+    },
+    WriterLease { lease_id: u64 },
+    Ok,
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Lease {
+    id: u64,
+    pid: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    token: String,
+    request: DaemonRequest,
+}
+
+pub fn connection_file(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(CONNECTION_FILE)
+}
+
+/// Serves the indexer core to local clients over newline-delimited JSON on
+/// a loopback socket.
+pub struct DaemonServer {
+    indexer: Arc<IndexManager>,
+    watcher: Arc<parking_lot::Mutex<FileSystemWatcher>>,
+    token: String,
+    shutdown: Arc<Notify>,
+    lease: tokio::sync::Mutex<Option<Lease>>,
+    next_lease_id: AtomicU64,
+}
+
+impl DaemonServer {
+    pub fn new(indexer: Arc<IndexManager>, watcher: Arc<parking_lot::Mutex<FileSystemWatcher>>) -> Result<Self, String> {
+        Ok(Self {
+            indexer,
+            watcher,
+            token: utils::random_token()?,
+            shutdown: Arc::new(Notify::new()),
+            lease: tokio::sync::Mutex::new(None),
+            next_lease_id: AtomicU64::new(1),
+        })
+    }
+
+    /// Notifying this stops `serve`, e.g. from a service control handler.
+    pub fn shutdown_handle(&self) -> Arc<Notify> {
+        self.shutdown.clone()
+    }
+
+    /// Binds the socket, publishes the connection file and serves until a
+    /// `Shutdown` request arrives.
+    pub async fn serve(self, app_data_dir: &Path) -> Result<(), String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await
+            .map_err(|e| format!("Failed to bind daemon socket: {}", e))?;
+        let port = listener.local_addr()
+            .map_err(|e| format!("Failed to read daemon address: {}", e))?
+            .port();
+
+        let info = ConnectionInfo {
+            port,
+            token: self.token.clone(),
+            pid: std::process::id(),
+        };
+        let connection_path = connection_file(app_data_dir);
+        let json = serde_json::to_string(&info)
+            .map_err(|e| format!("Failed to serialize connection info: {}", e))?;
+        // Replaced rather than rewritten, so an old file's permissions don't carry over
+        match std::fs::remove_file(&connection_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!("Failed to replace connection file: {}", e));
+            }
+            _ => {}
+        }
+        utils::create_private(&connection_path)
+            .and_then(|mut file| file.write_all(json.as_bytes()).and_then(|_| file.sync_all()))
+            .map_err(|e| format!("Failed to write connection file: {}", e))?;
+        info!("Daemon listening on 127.0.0.1:{}", port);
+
+        let server = Arc::new(self);
+        server.spawn_lease_watchdog();
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, _) = match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            warn!("Failed to accept daemon connection: {}", e);
+                            continue;
+                        }
+                    };
+                    let server = server.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = server.handle_connection(stream).await {
+                            warn!("Daemon connection error: {}", e);
+                        }
+                    });
+                }
+                _ = server.shutdown.notified() => break,
+            }
+        }
+
+        let _ = std::fs::remove_file(&connection_path);
+        info!("Daemon stopped");
+        Ok(())
+    }
+
+    async fn handle_connection(&self, stream: TcpStream) -> Result<(), String> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+            let response = match serde_json::from_str::<Envelope>(&line) {
+                Ok(envelope) if envelope.token == self.token => {
+                    let shutdown = matches!(envelope.request, DaemonRequest::Shutdown);
+                    let response = self.dispatch(envelope.request).await;
+                    if shutdown {
+                        self.shutdown.notify_one();
+                    }
+                    response
+                }
+                Ok(_) => DaemonResponse::Error { message: "Invalid token".to_string() },
+                Err(e) => DaemonResponse::Error { message: format!("Malformed request: {}", e) },
+            };
+
+            let mut json = serde_json::to_string(&response).map_err(|e| e.to_string())?;
+            json.push('\n');
+            writer.write_all(json.as_bytes()).await.map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    async fn dispatch(&self, request: DaemonRequest) -> DaemonResponse {
+        let result = match request {
+            DaemonRequest::Ping => return DaemonResponse::Pong {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            DaemonRequest::Search { query, options } => {
+                return match self.indexer.search_response(&query, &options).await {
+                    Ok(SearchResponse { results, index_completeness, facets, shed, snapshot }) => {
+                        DaemonResponse::SearchResults { results, index_completeness, facets, shed, snapshot }
+                    }
+                    Err(message) => DaemonResponse::Error { message },
+                };
+            }
+            DaemonRequest::StartIndexing { directory } => {
+                let indexer = self.indexer.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = indexer.start_indexing(&directory).await {
+                        error!("Daemon indexing of {} failed: {}", directory, e);
+                    }
+                });
+                Ok(())
+            }
+            DaemonRequest::CancelIndexing => self.indexer.cancel().await,
+            DaemonRequest::PauseIndexing => self.indexer.pause_indexing().await,
+            DaemonRequest::ResumeIndexing => self.indexer.resume_indexing().await,
+            DaemonRequest::Status => {
+                let state = self.indexer.get_state();
+                return DaemonResponse::Status {
+                    state: state.state,
+                    total_files: state.total_files,
+                    processed_files: state.processed_files,
+                    current_file: state.current_file,
+                    files_per_second: state.files_per_second,
+                    elapsed_seconds: state.elapsed_seconds,
+                    skipped_files: state.skipped_files,
+                };
+            }
+            DaemonRequest::Watch { directory } => self.watcher.lock().watch(&directory)
+                .map_err(|e| format!("Failed to watch {}: {}", directory, e)),
+            DaemonRequest::Unwatch { directory } => self.watcher.lock().unwatch(&directory)
+                .map_err(|e| format!("Failed to unwatch {}: {}", directory, e)),
+            DaemonRequest::AcquireWriter { pid } => {
+                return match self.grant_lease(pid).await {
+                    Ok(lease_id) => DaemonResponse::WriterLease { lease_id },
+                    Err(message) => DaemonResponse::Error { message },
+                };
+            }
+            DaemonRequest::ReleaseWriter { lease_id } => self.end_lease(Some(lease_id)).await,
+            DaemonRequest::Shutdown => Ok(()),
+        };
+
+        match result {
+            Ok(()) => DaemonResponse::Ok,
+            Err(message) => DaemonResponse::Error { message },
+        }
+    }
+}
+
+impl DaemonServer {
+    async fn grant_lease(&self, pid: u32) -> Result<u64, String> {
+        let mut lease = self.lease.lock().await;
+        if let Some(current) = *lease {
+            return Err(format!("Index writer already leased to process {}", current.pid));
+        }
+        if self.indexer.get_state().state == "indexing" || self.indexer.get_state().state == "scanning" {
+            return Err("The daemon is indexing; try again when it finishes".to_string());
+        }
+
+        self.indexer.suspend_writes().await?;
+        let id = self.next_lease_id.fetch_add(1, Ordering::SeqCst);
+        *lease = Some(Lease { id, pid });
+        info!("Index writer leased to process {} (lease {})", pid, id);
+        Ok(id)
+    }
+
+    /// Ends the current lease. `None` forces it, e.g. when the holder died.
+    async fn end_lease(&self, lease_id: Option<u64>) -> Result<(), String> {
+        let mut lease = self.lease.lock().await;
+        match (*lease, lease_id) {
+            (None, _) => return Ok(()),
+            (Some(current), Some(id)) if current.id != id => {
+                return Err(format!("Lease {} is not the active lease", id));
+            }
+            _ => {}
+        }
+        *lease = None;
+        drop(lease);
+
+        let summary = self.indexer.resume_writes().await?;
+        info!("Index writer returned to daemon, caught up: {:?}", summary);
+        Ok(())
+    }
+
+    /// Reclaims the writer if the process holding the lease exits without
+    /// returning it.
+    fn spawn_lease_watchdog(self: &Arc<Self>) {
+        let server = self.clone();
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(LEASE_CHECK_INTERVAL);
+            loop {
+                timer.tick().await;
+                let holder = *server.lease.lock().await;
+                if let Some(lease) = holder {
+                    let mut system = System::new();
+                    if !system.refresh_process(Pid::from_u32(lease.pid)) {
+                        warn!("Lease holder {} exited without releasing the writer, reclaiming", lease.pid);
+                        if let Err(e) = server.end_lease(None).await {
+                            error!("Failed to reclaim index writer: {}", e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Client side of the daemon protocol, used by the GUI when a daemon is running.
+#[derive(Debug, Clone)]
+pub struct DaemonClient {
+    info: ConnectionInfo,
+    lease_id: Arc<parking_lot::Mutex<Option<u64>>>,
+    leased: Arc<AtomicBool>,
+}
+
+impl DaemonClient {
+    /// Finds a running daemon via its connection file and checks it answers.
+    pub async fn discover(app_data_dir: &Path) -> Option<Self> {
+        let json = std::fs::read_to_string(connection_file(app_data_dir)).ok()?;
+        let info: ConnectionInfo = serde_json::from_str(&json).ok()?;
+        let client = Self {
+            info,
+            lease_id: Arc::new(parking_lot::Mutex::new(None)),
+            leased: Arc::new(AtomicBool::new(false)),
+        };
+        match client.request(DaemonRequest::Ping).await {
+            Ok(DaemonResponse::Pong { version }) => {
+                info!("Connected to daemon {} on port {}", version, client.info.port);
+                Some(client)
+            }
+            _ => None,
+        }
+    }
+
+    /// True while this process holds the index writer on the daemon's behalf.
+    pub fn holds_writer(&self) -> bool {
+        self.leased.load(Ordering::SeqCst)
+    }
+
+    /// Asks the daemon to hand over the index writer.
+    pub async fn acquire_writer(&self) -> Result<(), String> {
+        match self.request(DaemonRequest::AcquireWriter { pid: std::process::id() }).await? {
+            DaemonResponse::WriterLease { lease_id } => {
+                *self.lease_id.lock() = Some(lease_id);
+                self.leased.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+            other => Err(format!("Unexpected daemon response: {:?}", other)),
+        }
+    }
+
+    /// Hands the index writer back. The caller must have released its own
+    /// writer first so the daemon can take the lock.
+    pub async fn release_writer(&self) -> Result<(), String> {
+        let lease_id = match self.lease_id.lock().take() {
+            Some(lease_id) => lease_id,
+            None => return Ok(()),
+        };
+        self.leased.store(false, Ordering::SeqCst);
+        self.request(DaemonRequest::ReleaseWriter { lease_id }).await.map(|_| ())
+    }
+
+    pub async fn request(&self, request: DaemonRequest) -> Result<DaemonResponse, String> {
+        let stream = TcpStream::connect(("127.0.0.1", self.info.port)).await
+            .map_err(|e| format!("Failed to connect to daemon: {}", e))?;
+        let (reader, mut writer) = stream.into_split();
+
+        let envelope = Envelope {
+            token: self.info.token.clone(),
+            request,
+        };
+        let mut json = serde_json::to_string(&envelope)
+            .map_err(|e| format!("Failed to serialize daemon request: {}", e))?;
+        json.push('\n');
+        writer.write_all(json.as_bytes()).await
+            .map_err(|e| format!("Failed to send daemon request: {}", e))?;
+
+        let line = BufReader::new(reader).lines().next_line().await
+            .map_err(|e| format!("Failed to read daemon response: {}", e))?
+            .ok_or_else(|| "Daemon closed the connection".to_string())?;
+        let response: DaemonResponse = serde_json::from_str(&line)
+            .map_err(|e| format!("Malformed daemon response: {}", e))?;
+
+        match response {
+            DaemonResponse::Error { message } => Err(message),
+            response => Ok(response),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Events pushed to the frontend and other clients. Every event goes out in
+//! the same envelope, `{v, kind, job_id, payload}`, where `v` is bumped
+//! whenever a payload changes shape incompatibly.
+
+use serde::Serialize;
+use crate::indexing::{IndexState, IndexerState};
+use crate::indexing::priority::PriorityCompletion;
+use crate::indexing::reconcile::ReconcileProgress;
+use crate::instance::Launch;
+use crate::jobs::{JobId, JobInfo};
+use ts_rs::TS;
+
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum EventKind {
+    IndexingProgress,
+    PriorityComplete,
+    ReconcileProgress,
+    JobUpdate,
+    /// Another launch of the app was handed to this instance.
+    InstanceLaunch,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct Event {
+    pub v: u32,
+    pub kind: EventKind,
+    /// Set for events about one job.
+    #[ts(type = "number | null")]
+    pub job_id: Option<JobId>,
+    pub payload: EventPayload,
+}
+
+/// The payload for each `EventKind`, in the same order.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(untagged)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum EventPayload {
+    IndexingProgress(IndexingProgress),
+    PriorityComplete(PriorityCompletion),
+    ReconcileProgress(ReconcileProgress),
+    JobUpdate(JobInfo),
+    InstanceLaunch(Launch),
+}
+
+impl Event {
+    fn new(kind: EventKind, job_id: Option<JobId>, payload: EventPayload) -> Self {
+        Self { v: EVENT_SCHEMA_VERSION, kind, job_id, payload }
+    }
+
+    pub fn indexing_progress(state: IndexerState) -> Self {
+        Self::new(EventKind::IndexingProgress, None, EventPayload::IndexingProgress(state.into()))
+    }
+
+    pub fn priority_complete(completion: PriorityCompletion) -> Self {
+        Self::new(EventKind::PriorityComplete, None, EventPayload::PriorityComplete(completion))
+    }
+
+    pub fn reconcile_progress(progress: ReconcileProgress) -> Self {
+        Self::new(EventKind::ReconcileProgress, None, EventPayload::ReconcileProgress(progress))
+    }
+
+    pub fn job_update(job: JobInfo) -> Self {
+        Self::new(EventKind::JobUpdate, Some(job.id), EventPayload::JobUpdate(job))
+    }
+
+    pub fn instance_launch(launch: Launch) -> Self {
+        Self::new(EventKind::InstanceLaunch, None, EventPayload::InstanceLaunch(launch))
+    }
+
+    /// The event name to emit on. Job updates get one per job so clients
+    /// can follow just the jobs they started.
+    pub fn channel(&self) -> String {
+        match (self.kind, self.job_id) {
+            (EventKind::JobUpdate, Some(id)) => format!("job:{}", id),
+            (EventKind::IndexingProgress, _) => "indexing_progress".to_string(),
+            (EventKind::PriorityComplete, _) => "priority_complete".to_string(),
+            (EventKind::ReconcileProgress, _) => "reconcile_progress".to_string(),
+            (EventKind::JobUpdate, None) => "job_update".to_string(),
+            (EventKind::InstanceLaunch, _) => "instance_launch".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct IndexingProgress {
+    pub state: IndexState,
+    pub stats: IndexingStats,
+    pub current_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct IndexingStats {
+    pub total_files: usize,
+    pub processed_files: usize,
+    /// Processed files left alone because they hadn't changed.
+    pub skipped_files: usize,
+    pub percent_complete: f32,
+    pub files_per_second: f32,
+    #[ts(type = "number")]
+    pub elapsed_seconds: u64,
+    #[ts(type = "number | null")]
+    pub estimated_remaining_seconds: Option<u64>,
+    /// Files under "index first" folders still to go, and when they should be done.
+    pub priority_remaining_files: usize,
+    #[ts(type = "number | null")]
+    pub priority_estimated_remaining_seconds: Option<u64>,
+}
+
+impl From<IndexerState> for IndexingProgress {
+    fn from(state: IndexerState) -> Self {
+        Self {
+            state: match state.state.as_str() {
+                "idle" => IndexState::Idle,
+                "scanning" => IndexState::Scanning,
+                "indexing" => IndexState::Indexing,
+                "paused" => IndexState::Paused,
+                "completed" => IndexState::Completed,
+                "cancelled" => IndexState::Cancelled,
+                "error" => IndexState::Error(state.current_file.clone()),
+                _ => IndexState::Error("Unknown state".to_string()),
+            },
+            stats: IndexingStats {
+                total_files: state.total_files,
+                processed_files: state.processed_files,
+                skipped_files: state.skipped_files,
+                percent_complete: if state.total_files > 0 {
+                    (state.processed_files as f32 / state.total_files as f32) * 100.0
+                } else {
+                    0.0
+                },
+                files_per_second: state.files_per_second,
+                elapsed_seconds: state.elapsed_seconds,
+                estimated_remaining_seconds: if state.files_per_second > 0.0 {
+                    let remaining_files = state.total_files.saturating_sub(state.processed_files);
+                    Some((remaining_files as f32 / state.files_per_second) as u64)
+                } else {
+                    None
+                },
+                priority_remaining_files: state.priority_total.saturating_sub(state.priority_processed),
+                priority_estimated_remaining_seconds: if state.files_per_second > 0.0 {
+                    let remaining_files = state.priority_total.saturating_sub(state.priority_processed);
+                    Some((remaining_files as f32 / state.files_per_second) as u64)
+                } else {
+                    None
+                },
+            },
+            current_file: state.current_file,
+        }
+    }
+}
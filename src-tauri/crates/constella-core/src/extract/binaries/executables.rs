@@ -0,0 +1,127 @@
+//! Product, company and version of executables and libraries: from the
+//! version resource of Windows (PE) files, and from the Info.plist of macOS
+//! (Mach-O) ones, embedded or in the app bundle. Linux (ELF) files record
+//! none of these.
+
+use super::{clean, normalize_version, u16_le, u32_le, BinaryKind, BinaryMetadata};
+
+const MACH_O_MAGICS: &[[u8; 4]] = &[
+    [0xFE, 0xED, 0xFA, 0xCE],
+    [0xFE, 0xED, 0xFA, 0xCF],
+    [0xCE, 0xFA, 0xED, 0xFE],
+    [0xCF, 0xFA, 0xED, 0xFE],
+    // Universal binaries
+    [0xCA, 0xFE, 0xBA, 0xBE],
+];
+
+/// Only this much of a Mach-O file is searched for an embedded Info.plist,
+/// which the linker puts near the start.
+const PLIST_SEARCH_LIMIT: usize = 4 * 1024 * 1024;
+/// Longest version resource value read, in UTF-16 units.
+const MAX_VALUE_LENGTH: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutableFormat {
+    Pe,
+    Elf,
+    MachO,
+}
+
+pub fn format(bytes: &[u8]) -> Option<ExecutableFormat> {
+    let magic = bytes.get(..4)?;
+    if magic.starts_with(b"MZ") {
+        Some(ExecutableFormat::Pe)
+    } else if magic == b"\x7FELF" {
+        Some(ExecutableFormat::Elf)
+    } else if MACH_O_MAGICS.iter().any(|mach_o| mach_o == magic) {
+        Some(ExecutableFormat::MachO)
+    } else {
+        None
+    }
+}
+
+pub fn metadata(bytes: &[u8]) -> Option<BinaryMetadata> {
+    let mut metadata = BinaryMetadata::new(BinaryKind::Executable);
+    match format(bytes)? {
+        ExecutableFormat::Pe => {
+            if let Some(resources) = pe_resources(bytes) {
+                let value = |key: &str| version_value(resources, key);
+                metadata.product = value("ProductName").or_else(|| value("FileDescription"));
+                metadata.company = value("CompanyName");
+                metadata.version = value("ProductVersion")
+                    .or_else(|| value("FileVersion"))
+                    .as_deref()
+                    .and_then(normalize_version);
+            }
+        }
+        ExecutableFormat::MachO => {
+            let searched = &bytes[..bytes.len().min(PLIST_SEARCH_LIMIT)];
+            if let Some(plist) = embedded_plist(searched) {
+                apply_info_plist(&mut metadata, &plist);
+            }
+        }
+        ExecutableFormat::Elf => {}
+    }
+    Some(metadata)
+}
+
+/// Fills in what `metadata` is missing from an app bundle's Info.plist.
+pub fn apply_info_plist(metadata: &mut BinaryMetadata, plist: &str) {
+    let value = |key: &str| plist_string(plist, key);
+    if metadata.product.is_none() {
+        metadata.product = value("CFBundleDisplayName").or_else(|| value("CFBundleName"));
+    }
+    if metadata.version.is_none() {
+        metadata.version = value("CFBundleShortVersionString")
+            .or_else(|| value("CFBundleVersion"))
+            .as_deref()
+            .and_then(normalize_version);
+    }
+}
+
+/// The raw data of the `.rsrc` section of a PE file.
+fn pe_resources(bytes: &[u8]) -> Option<&[u8]> {
+    let header = u32_le(bytes, 0x3C)? as usize;
+    if bytes.get(header..header + 4)? != b"PE\0\0" {
+        return None;
+    }
+    let sections = u16_le(bytes, header + 6)? as usize;
+    let optional_header = u16_le(bytes, header + 20)? as usize;
+    let table = header + 24 + optional_header;
+    (0..sections)
+        .map(|i| table + i * 40)
+        .find(|&section| bytes.get(section..section + 8).is_some_and(|name| name.starts_with(b".rsrc")))
+        .and_then(|section| {
+            let size = u32_le(bytes, section + 16)? as usize;
+            let start = u32_le(bytes, section + 20)? as usize;
+            bytes.get(start..start.checked_add(size)?)
+        })
+}
+
+/// The value of `key` in a version resource's string table. Each entry is
+/// a header of three u16s, the UTF-16 key, then the value, 32-bit aligned.
+fn version_value(resources: &[u8], key: &str) -> Option<String> {
+    let pattern: Vec<u8> = key.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect();
+    let at = resources.windows(pattern.len()).position(|window| window == pattern)?;
+    let length = (u16_le(resources, at.checked_sub(4)?)? as usize).min(MAX_VALUE_LENGTH);
+    let start = (at + pattern.len() + 3) & !3;
+    let units: Vec<u16> = resources.get(start..start + length * 2)?
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    clean(String::from_utf16_lossy(&units))
+}
+
+fn embedded_plist(bytes: &[u8]) -> Option<String> {
+    let start = bytes.windows(6).position(|window| window == b"<plist")?;
+    let end = bytes[start..].windows(8).position(|window| window == b"</plist>")? + start + 8;
+    Some(String::from_utf8_lossy(&bytes[start..end]).into_owned())
+}
+
+/// The string value of `key` in an XML property list.
+fn plist_string(plist: &str, key: &str) -> Option<String> {
+    let after_key = plist.split_once(&format!("<key>{}</key>", key))?.1.trim_start();
+    let value = after_key.strip_prefix("<string>")?.split_once("</string>")?.0;
+    clean(value.replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&"))
+}
@@ -0,0 +1,93 @@
+//! Family, maker and version from the `name` table of TrueType and
+//! OpenType fonts, and of the first font in a collection.
+
+use super::{clean, normalize_version, u16_be, u32_be, BinaryKind, BinaryMetadata};
+
+const FAMILY: u16 = 1;
+const VERSION: u16 = 5;
+const MANUFACTURER: u16 = 8;
+const TYPOGRAPHIC_FAMILY: u16 = 16;
+
+const PLATFORM_UNICODE: u16 = 0;
+const PLATFORM_MAC: u16 = 1;
+const PLATFORM_WINDOWS: u16 = 3;
+const ENGLISH_US: u16 = 0x409;
+
+pub fn metadata(bytes: &[u8]) -> Option<BinaryMetadata> {
+    let font = match bytes.get(..4)? {
+        b"ttcf" => u32_be(bytes, 12)? as usize,
+        [0, 1, 0, 0] | b"OTTO" | b"true" => 0,
+        _ => return None,
+    };
+    let tables = u16_be(bytes, font + 4)? as usize;
+    let name_table = (0..tables)
+        .map(|i| font + 12 + i * 16)
+        .find(|&record| bytes.get(record..record + 4) == Some(b"name"))
+        .and_then(|record| u32_be(bytes, record + 8))? as usize;
+
+    let names = NameTable::read(bytes, name_table)?;
+    Some(BinaryMetadata {
+        kind: BinaryKind::Font,
+        product: names.get(TYPOGRAPHIC_FAMILY).or_else(|| names.get(FAMILY)),
+        company: names.get(MANUFACTURER),
+        version: names.get(VERSION).as_deref().and_then(normalize_version),
+    })
+}
+
+struct NameRecord {
+    platform: u16,
+    language: u16,
+    name: u16,
+    start: usize,
+    length: usize,
+}
+
+struct NameTable<'a> {
+    bytes: &'a [u8],
+    records: Vec<NameRecord>,
+}
+
+impl<'a> NameTable<'a> {
+    fn read(bytes: &'a [u8], table: usize) -> Option<Self> {
+        let count = u16_be(bytes, table + 2)? as usize;
+        let strings = table + u16_be(bytes, table + 4)? as usize;
+        let records = (0..count)
+            .map(|i| {
+                let record = table + 6 + i * 12;
+                Some(NameRecord {
+                    platform: u16_be(bytes, record)?,
+                    language: u16_be(bytes, record + 4)?,
+                    name: u16_be(bytes, record + 6)?,
+                    length: u16_be(bytes, record + 8)? as usize,
+                    start: strings + u16_be(bytes, record + 10)? as usize,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self { bytes, records })
+    }
+
+    /// Name `id`, in US English from the Windows records when the font has
+    /// them, which it nearly always does.
+    fn get(&self, id: u16) -> Option<String> {
+        let preference = |record: &NameRecord| match (record.platform, record.language) {
+            (PLATFORM_WINDOWS, ENGLISH_US) => 0,
+            (PLATFORM_WINDOWS, _) => 1,
+            (PLATFORM_UNICODE, _) => 2,
+            _ => 3,
+        };
+        self.records.iter()
+            .filter(|record| record.name == id)
+            .filter(|record| matches!(record.platform, PLATFORM_UNICODE | PLATFORM_MAC | PLATFORM_WINDOWS))
+            .min_by_key(|record| preference(record))
+            .and_then(|record| {
+                let text = self.bytes.get(record.start..record.start + record.length)?;
+                clean(if record.platform == PLATFORM_MAC {
+                    // Mac Roman, which matches Latin-1 where it matters for names
+                    text.iter().map(|&byte| byte as char).collect()
+                } else {
+                    let units: Vec<u16> = text.chunks_exact(2).map(|unit| u16::from_be_bytes([unit[0], unit[1]])).collect();
+                    String::from_utf16_lossy(&units)
+                })
+            })
+    }
+}
@@ -0,0 +1,153 @@
+//! Package names of installers. MSI files are compound files whose summary
+//! information names the product and its maker; other formats (DMG, pkg,
+//! deb, rpm) are named after their file.
+
+use super::{clean, u16_le, u32_le, BinaryKind, BinaryMetadata};
+
+const COMPOUND_FILE_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+const SUMMARY_INFORMATION: &str = "\u{5}SummaryInformation";
+
+// Sector numbers with special meanings
+const END_OF_CHAIN: u32 = 0xFFFF_FFFE;
+const FREE_SECTOR: u32 = 0xFFFF_FFFF;
+
+/// Chains longer than this are treated as corrupt.
+const MAX_CHAIN_LENGTH: usize = 1 << 20;
+
+const PROPERTY_SUBJECT: u32 = 3;
+const PROPERTY_AUTHOR: u32 = 4;
+const VT_LPSTR: u32 = 0x1E;
+
+pub fn metadata(bytes: &[u8]) -> BinaryMetadata {
+    let mut metadata = BinaryMetadata::new(BinaryKind::Installer);
+    if let Some(summary) = CompoundFile::open(bytes).and_then(|file| file.stream(SUMMARY_INFORMATION)) {
+        // MSI summaries put the product in Subject and its maker in Author
+        metadata.product = summary_property(&summary, PROPERTY_SUBJECT);
+        metadata.company = summary_property(&summary, PROPERTY_AUTHOR);
+    }
+    metadata
+}
+
+/// A string property from the first section of an OLE property set.
+fn summary_property(summary: &[u8], id: u32) -> Option<String> {
+    let section = u32_le(summary, 44)? as usize;
+    let count = u32_le(summary, section + 4)? as usize;
+    let offset = (0..count.min(256))
+        .map(|i| section + 8 + i * 8)
+        .find(|&entry| u32_le(summary, entry) == Some(id))
+        .and_then(|entry| u32_le(summary, entry + 4))? as usize;
+    let value = section + offset;
+    if u32_le(summary, value)? != VT_LPSTR {
+        return None;
+    }
+    let length = u32_le(summary, value + 4)? as usize;
+    let text = summary.get(value + 8..(value + 8).checked_add(length)?)?;
+    clean(String::from_utf8_lossy(text).into_owned())
+}
+
+/// Just enough of the compound file format to read one stream by name.
+struct CompoundFile<'a> {
+    bytes: &'a [u8],
+    sector_size: usize,
+    mini_sector_size: usize,
+    mini_stream_cutoff: usize,
+    fat: Vec<u32>,
+}
+
+impl<'a> CompoundFile<'a> {
+    fn open(bytes: &'a [u8]) -> Option<Self> {
+        if !bytes.starts_with(COMPOUND_FILE_MAGIC) {
+            return None;
+        }
+        let sector_size = 1usize.checked_shl(u16_le(bytes, 0x1E)?.into())?;
+        let mini_sector_size = 1usize.checked_shl(u16_le(bytes, 0x20)?.into())?;
+        if !(512..=4096).contains(&sector_size) {
+            return None;
+        }
+        let mut file = Self {
+            bytes,
+            sector_size,
+            mini_sector_size,
+            mini_stream_cutoff: u32_le(bytes, 0x38)? as usize,
+            fat: Vec::new(),
+        };
+
+        // The first 109 FAT sectors are listed in the header, the rest in a
+        // chain of DIFAT sectors
+        let mut fat_sectors: Vec<u32> = (0..109).filter_map(|i| u32_le(bytes, 0x4C + i * 4)).collect();
+        let mut difat = u32_le(bytes, 0x44)?;
+        let per_sector = sector_size / 4;
+        while difat != END_OF_CHAIN && difat != FREE_SECTOR && fat_sectors.len() < MAX_CHAIN_LENGTH {
+            let sector = file.sector(difat)?;
+            fat_sectors.extend((0..per_sector - 1).filter_map(|i| u32_le(sector, i * 4)));
+            difat = u32_le(sector, (per_sector - 1) * 4)?;
+        }
+        file.fat = fat_sectors.into_iter()
+            .filter(|&sector| sector != FREE_SECTOR)
+            .filter_map(|sector| file.sector(sector))
+            .flat_map(|sector| sector.chunks_exact(4).map(|entry| u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]])))
+            .collect();
+        Some(file)
+    }
+
+    fn sector(&self, sector: u32) -> Option<&'a [u8]> {
+        let start = (sector as usize).checked_add(1)?.checked_mul(self.sector_size)?;
+        self.bytes.get(start..start.checked_add(self.sector_size)?)
+    }
+
+    /// Sector numbers of the chain starting at `start`.
+    fn chain(&self, table: &[u32], start: u32) -> Vec<u32> {
+        let mut chain = Vec::new();
+        let mut sector = start;
+        while sector != END_OF_CHAIN && (sector as usize) < table.len() && chain.len() < MAX_CHAIN_LENGTH {
+            chain.push(sector);
+            sector = table[sector as usize];
+        }
+        chain
+    }
+
+    fn read(&self, start: u32) -> Vec<u8> {
+        self.chain(&self.fat, start)
+            .into_iter()
+            .filter_map(|sector| self.sector(sector))
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    /// The stream called `name`, from anywhere in the directory.
+    fn stream(&self, name: &str) -> Option<Vec<u8>> {
+        let directory = self.read(u32_le(self.bytes, 0x30)?);
+        let entries: Vec<&[u8]> = directory.chunks_exact(128).collect();
+        let root = entries.first()?;
+        let entry = entries.iter().find(|entry| {
+            let length = (u16_le(entry, 64).unwrap_or(0) as usize).min(64);
+            let units: Vec<u16> = entry[..length].chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]])).collect();
+            String::from_utf16_lossy(&units).trim_end_matches('\0') == name
+        })?;
+        let start = u32_le(entry, 116)?;
+        let size = u32_le(entry, 120)? as usize;
+
+        let data = if size < self.mini_stream_cutoff {
+            // Small streams live in the mini stream, which is the root
+            // entry's data, cut into mini sectors
+            let mini_stream = self.read(u32_le(root, 116)?);
+            let mini_fat: Vec<u32> = self.read(u32_le(self.bytes, 0x3C)?)
+                .chunks_exact(4)
+                .map(|entry| u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]))
+                .collect();
+            self.chain(&mini_fat, start)
+                .into_iter()
+                .filter_map(|sector| {
+                    let start = sector as usize * self.mini_sector_size;
+                    mini_stream.get(start..start + self.mini_sector_size)
+                })
+                .flatten()
+                .copied()
+                .collect()
+        } else {
+            self.read(start)
+        };
+        Some(data.into_iter().take(size).collect())
+    }
+}
@@ -0,0 +1,163 @@
+//! Names, makers and versions of fonts, executables and installers, which
+//! would otherwise be indexed by file name alone. Each format is read from
+//! its own headers; where a format records nothing useful (ELF, DMG), the
+//! version and name come from the file name, as release files are usually
+//! named like `Constella-2.3.1-x64.dmg`.
+
+pub mod executables;
+pub mod fonts;
+pub mod installers;
+
+use std::path::Path;
+use serde::Serialize;
+use ts_rs::TS;
+
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "otc"];
+const EXECUTABLE_EXTENSIONS: &[&str] = &["exe", "dll", "sys", "so", "dylib", "bin"];
+const INSTALLER_EXTENSIONS: &[&str] = &["msi", "msix", "dmg", "pkg", "deb", "rpm", "appimage"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum BinaryKind {
+    Font,
+    Executable,
+    Installer,
+}
+
+impl BinaryKind {
+    /// Value of the `kind` field, for `kind:installer` and the like.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Font => "font",
+            Self::Executable => "executable",
+            Self::Installer => "installer",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        [Self::Font, Self::Executable, Self::Installer].into_iter().find(|candidate| candidate.as_str() == kind)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct BinaryMetadata {
+    pub kind: BinaryKind,
+    /// Font family, product or package name.
+    pub product: Option<String>,
+    pub company: Option<String>,
+    /// Without a leading "v", e.g. "2.3.1".
+    pub version: Option<String>,
+}
+
+impl BinaryMetadata {
+    pub fn new(kind: BinaryKind) -> Self {
+        Self { kind, product: None, company: None, version: None }
+    }
+
+    /// The metadata as text for the content field, so free-text searches
+    /// like `constella installer 2.3.1` find the file.
+    pub fn text(&self) -> String {
+        [self.product.as_deref(), self.company.as_deref(), Some(self.kind.as_str()), self.version.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// What `path` would be by its name. Files without an extension are
+/// candidates for executables, pending a look at their headers.
+pub fn kind(path: &Path) -> Option<BinaryKind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    let extension = path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if FONT_EXTENSIONS.contains(&extension.as_str()) {
+        Some(BinaryKind::Font)
+    } else if INSTALLER_EXTENSIONS.contains(&extension.as_str()) {
+        Some(BinaryKind::Installer)
+    } else if extension.is_empty() || EXECUTABLE_EXTENSIONS.contains(&extension.as_str()) || name.contains(".so.") {
+        Some(BinaryKind::Executable)
+    } else {
+        None
+    }
+}
+
+/// Metadata of the file at `path` with content `bytes`, or `None` when it
+/// isn't a font, executable or installer after all.
+pub fn metadata(path: &Path, bytes: &[u8]) -> Option<BinaryMetadata> {
+    let mut metadata = match kind(path)? {
+        BinaryKind::Font => fonts::metadata(bytes)?,
+        BinaryKind::Executable => executables::metadata(bytes)?,
+        BinaryKind::Installer => installers::metadata(bytes),
+    };
+
+    let name = path.file_name()?.to_string_lossy();
+    let (name_product, name_version) = split_release_name(&name);
+    if metadata.version.is_none() {
+        metadata.version = name_version;
+    }
+    if metadata.product.is_none() && metadata.kind == BinaryKind::Installer {
+        metadata.product = name_product;
+    }
+    Some(metadata)
+}
+
+/// `2.3.1` from "v2.3.1", "2, 3, 1, 0" or "Version 2.3.1; build 7".
+pub fn normalize_version(version: &str) -> Option<String> {
+    let version = version.trim();
+    let version = version.strip_prefix("Version").unwrap_or(version).trim_start();
+    let version = version.strip_prefix(['v', 'V']).unwrap_or(version);
+    let version: String = version.replace(", ", ".")
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+        .collect();
+    version.starts_with(|c: char| c.is_ascii_digit()).then_some(version)
+}
+
+/// The product name and version in a release file name, e.g. "Constella"
+/// and "2.3.1" from `Constella-2.3.1-x64.dmg` or `constella_v2.3.1.msi`,
+/// or "libconstella" and "2.3.1" from `libconstella.so.2.3.1`.
+pub fn split_release_name(name: &str) -> (Option<String>, Option<String>) {
+    if let Some((library, version)) = name.split_once(".so.") {
+        return (Some(library.to_string()), Some(version.to_string()));
+    }
+    let stem = match name.rsplit_once('.') {
+        Some((stem, extension)) if !extension.starts_with(|c: char| c.is_ascii_digit()) => stem,
+        _ => name,
+    };
+    let parts: Vec<&str> = stem.split(['-', '_', ' ']).collect();
+    let Some(at) = parts.iter().position(|part| {
+        let part = part.strip_prefix(['v', 'V']).unwrap_or(part);
+        part.contains('.') && part.split('.').all(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+    }) else {
+        return (None, None);
+    };
+    let product = parts[..at].join(" ");
+    let version = parts[at].trim_start_matches(['v', 'V']).to_string();
+    ((!product.is_empty()).then_some(product), Some(version))
+}
+
+fn u16_le(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at.checked_add(2)?)?.try_into().ok()?))
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at.checked_add(4)?)?.try_into().ok()?))
+}
+
+fn u16_be(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(at..at.checked_add(2)?)?.try_into().ok()?))
+}
+
+fn u32_be(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at.checked_add(4)?)?.try_into().ok()?))
+}
+
+/// Text with surrounding whitespace and NULs removed, or `None` if empty.
+fn clean(text: String) -> Option<String> {
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
@@ -0,0 +1,255 @@
+//! Keys of configuration files (JSON, YAML, TOML and INI-style) as
+//! flattened dotted paths, like `database.password`. They are indexed apart
+//! from the values, by every trailing part of the path, so `key:password`,
+//! `key:database.password` and `key:prod.database.password` all find a
+//! `password` set under `prod.database`. The parsers are forgiving line
+//! readers rather than full implementations of each format; a file they
+//! can't make sense of just yields fewer keys.
+
+use std::collections::HashSet;
+use serde_json::Value;
+use super::Extracted;
+
+/// Keys kept per file.
+const MAX_KEYS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+    /// INI files, `.properties` and the like: `[section]` headers and
+    /// `key = value` or `key: value` lines.
+    Ini,
+}
+
+impl ConfigFormat {
+    pub fn for_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "ini" | "cfg" | "conf" | "properties" => Some(Self::Ini),
+            _ => None,
+        }
+    }
+}
+
+/// The file as it is, along with its keys.
+pub fn extract(format: ConfigFormat, text: &str) -> Extracted {
+    Extracted {
+        text: text.to_string(),
+        keys: keys(format, text),
+        ..Extracted::default()
+    }
+}
+
+/// Dotted paths of the keys set in `text`, each once, in file order.
+pub fn keys(format: ConfigFormat, text: &str) -> Vec<String> {
+    let keys = match format {
+        ConfigFormat::Json => serde_json::from_str::<Value>(text)
+            .map(|json| {
+                let mut keys = Vec::new();
+                json_keys(&json, "", &mut keys);
+                keys
+            })
+            .unwrap_or_default(),
+        ConfigFormat::Yaml => yaml_keys(text),
+        ConfigFormat::Toml => toml_keys(text),
+        ConfigFormat::Ini => ini_keys(text),
+    };
+    let mut seen = HashSet::new();
+    keys.into_iter()
+        .filter(|key| seen.insert(key.clone()))
+        .take(MAX_KEYS)
+        .collect()
+}
+
+/// The lowercased terms `key` is indexed under: the whole path and every
+/// trailing part of it, e.g. `database.password` and `password`.
+pub fn key_terms(key: &str) -> impl Iterator<Item = String> + '_ {
+    std::iter::once(0)
+        .chain(key.match_indices('.').map(|(at, _)| at + 1))
+        .map(move |start| key[start..].to_lowercase())
+        .filter(|term| !term.is_empty())
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// Keys of objects, including those inside arrays, which add nothing to
+/// the path.
+fn json_keys(value: &Value, prefix: &str, keys: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let path = join(prefix, key);
+                keys.push(path.clone());
+                json_keys(value, &path, keys);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                json_keys(item, prefix, keys);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Keys of block mappings, nested by indentation. List items add nothing
+/// to the path; flow mappings (`{a: 1}`) and block scalars are skipped.
+fn yaml_keys(text: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    // Indentation and name of each key the current line is nested under
+    let mut parents: Vec<(usize, String)> = Vec::new();
+    let mut block_scalar_indent = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("---") || trimmed.starts_with("...") {
+            continue;
+        }
+        let mut indent = line.len() - line.trim_start().len();
+        if let Some(block_indent) = block_scalar_indent {
+            if indent > block_indent {
+                continue;
+            }
+            block_scalar_indent = None;
+        }
+
+        // The key of `- key: value` sits where it would without the dash
+        let mut rest = trimmed;
+        while let Some(item) = rest.strip_prefix('-').filter(|item| item.is_empty() || item.starts_with(' ')) {
+            let item_start = item.trim_start();
+            indent += rest.len() - item_start.len();
+            rest = item_start;
+        }
+        let Some((key, value)) = yaml_key(rest) else {
+            continue;
+        };
+
+        while parents.last().is_some_and(|(parent_indent, _)| *parent_indent >= indent) {
+            parents.pop();
+        }
+        let path = join(parents.last().map_or("", |(_, path)| path.as_str()), &key);
+        keys.push(path.clone());
+        parents.push((indent, path));
+        if value.trim_start().starts_with(['|', '>']) {
+            block_scalar_indent = Some(indent);
+        }
+    }
+    keys
+}
+
+/// The key and the rest of a `key: value` line.
+fn yaml_key(line: &str) -> Option<(String, &str)> {
+    if let Some(quote) = line.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let end = line[1..].find(quote)? + 1;
+        let value = line[end + 1..].trim_start().strip_prefix(':')?;
+        return Some((line[1..end].to_string(), value));
+    }
+    if line.starts_with(['{', '[', '?']) {
+        return None;
+    }
+    let colon = line.find(": ").or_else(|| line.strip_suffix(':').map(str::len))?;
+    let key = line[..colon].trim();
+    (!key.is_empty()).then(|| (key.to_string(), &line[colon + 1..]))
+}
+
+/// Table headers and `key = value` lines, including the keys of inline
+/// tables one level deep. Multi-line strings are skipped.
+fn toml_keys(text: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut table = String::new();
+    let mut multiline_string: Option<&str> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(delimiter) = multiline_string {
+            if line.contains(delimiter) {
+                multiline_string = None;
+            }
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header.trim_start_matches('[');
+            let Some(end) = header.find(']') else {
+                continue;
+            };
+            table = dotted_key(&header[..end]);
+            keys.push(table.clone());
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let path = join(&table, &dotted_key(key));
+        keys.push(path.clone());
+
+        let value = value.trim();
+        if let Some(inline) = value.strip_prefix('{').and_then(|inline| inline.split('}').next()) {
+            for (key, _) in inline.split(',').filter_map(|pair| pair.split_once('=')) {
+                keys.push(join(&path, &dotted_key(key)));
+            }
+        }
+        for delimiter in ["\"\"\"", "'''"] {
+            if value.matches(delimiter).count() % 2 == 1 {
+                multiline_string = Some(delimiter);
+            }
+        }
+    }
+    keys
+}
+
+/// A TOML key like `a."b.c".d` as a dotted path, with quotes removed.
+fn dotted_key(key: &str) -> String {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut quote = None;
+    for c in key.trim().chars() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), c) => part.push(c),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '.') => parts.push(std::mem::take(&mut part)),
+            (None, c) if !c.is_whitespace() => part.push(c),
+            _ => {}
+        }
+    }
+    parts.push(part);
+    parts.join(".")
+}
+
+/// `[section]` headers and the keys of `key = value` and `key: value`
+/// lines under them.
+fn ini_keys(text: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut section = String::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with([';', '#', '!']) {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|header| header.strip_suffix(']')) {
+            section = header.trim().to_string();
+            keys.push(section.clone());
+            continue;
+        }
+        let Some(separator) = line.find(['=', ':']) else {
+            continue;
+        };
+        let key = line[..separator].trim();
+        if !key.is_empty() {
+            keys.push(join(&section, key));
+        }
+    }
+    keys
+}
@@ -0,0 +1,125 @@
+//! Markdown headings and the outline they form.
+
+use super::Extracted;
+
+/// The text of a markdown document along with its headings.
+pub fn extract(markdown: &str) -> Extracted {
+    Extracted {
+        text: markdown.to_string(),
+        headings: headings(markdown),
+        ..Extracted::default()
+    }
+}
+
+/// ATX (`## Title`) and setext (underlined) headings, outside code fences
+/// and front matter, as `Parent > Child` paths.
+pub fn headings(markdown: &str) -> Vec<String> {
+    let mut outline = Outline::default();
+    let mut lines = markdown.lines().peekable();
+    if lines.peek().is_some_and(|line| line.trim_end() == "---") {
+        lines.next();
+        for line in lines.by_ref() {
+            if matches!(line.trim_end(), "---" | "...") {
+                break;
+            }
+        }
+    }
+
+    let mut fence: Option<String> = None;
+    let mut previous: Option<&str> = None;
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some(marker) = &fence {
+            if trimmed.starts_with(marker.as_str()) && trimmed.chars().all(|c| marker.starts_with(c)) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = fence_marker(line) {
+            fence = Some(marker);
+            previous = None;
+            continue;
+        }
+
+        if let Some((level, title)) = atx_heading(line) {
+            outline.push(level, title);
+            previous = None;
+        } else if let (Some(level), Some(title)) = (setext_level(line), previous) {
+            outline.push(level, title);
+            previous = None;
+        } else {
+            previous = (!trimmed.is_empty()).then_some(trimmed);
+        }
+    }
+    outline.headings
+}
+
+/// Headings enclosing the current line, and every path seen so far.
+#[derive(Default)]
+struct Outline {
+    open: Vec<(usize, String)>,
+    headings: Vec<String>,
+}
+
+impl Outline {
+    fn push(&mut self, level: usize, title: &str) {
+        let title = title.trim();
+        if title.is_empty() {
+            return;
+        }
+        while self.open.last().is_some_and(|(open_level, _)| *open_level >= level) {
+            self.open.pop();
+        }
+        self.open.push((level, title.to_string()));
+        let path: Vec<&str> = self.open.iter().map(|(_, title)| title.as_str()).collect();
+        self.headings.push(path.join(" > "));
+    }
+}
+
+/// At most three spaces of indentation, like markdown itself allows.
+fn unindented(line: &str) -> Option<&str> {
+    let rest = line.trim_start_matches(' ');
+    (line.len() - rest.len() <= 3).then_some(rest)
+}
+
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let rest = unindented(line)?;
+    let level = rest.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let title = &rest[level..];
+    if !title.is_empty() && !title.starts_with([' ', '\t']) {
+        return None;
+    }
+    // A closing run of #s is decoration
+    let title = title.trim();
+    let without_closing = title.trim_end_matches('#');
+    let title = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        without_closing
+    } else {
+        title
+    };
+    Some((level, title))
+}
+
+fn setext_level(line: &str) -> Option<usize> {
+    let underline = unindented(line)?.trim_end();
+    if underline.is_empty() {
+        None
+    } else if underline.chars().all(|c| c == '=') {
+        Some(1)
+    } else if underline.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// The run of backticks or tildes opening a code fence on `line`.
+fn fence_marker(line: &str) -> Option<String> {
+    let rest = unindented(line)?;
+    let fence_char = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let marker: String = rest.chars().take_while(|c| *c == fence_char).collect();
+    (marker.len() >= 3).then_some(marker)
+}
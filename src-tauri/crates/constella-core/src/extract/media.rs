@@ -0,0 +1,98 @@
+//! Running time of MP4-family videos and audio, from the `mvhd` box inside
+//! their `moov` box. Only box headers are read on the way there, so the
+//! media data itself is never touched. Also strips of keyframes, so video
+//! results can be told apart without opening a player.
+
+use std::path::Path;
+use std::process::Command;
+
+const MP4_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "m4a", "3gp"];
+
+/// Files whose running time `duration_ms` can read.
+pub fn has_duration(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| MP4_EXTENSIONS.contains(&extension.as_str()))
+}
+
+/// Running time in milliseconds of the MP4 file in `bytes`, when its
+/// header says.
+pub fn duration_ms(bytes: &[u8]) -> Option<u64> {
+    let moov = find_box(bytes, b"moov")?;
+    let mvhd = find_box(moov, b"mvhd")?;
+    // Version 1 headers have 64-bit times after the version and flags
+    let (timescale, duration) = match *mvhd.first()? {
+        1 => (read_u32(mvhd, 20)?, read_u64(mvhd, 24)?),
+        _ => (read_u32(mvhd, 12)?, u64::from(read_u32(mvhd, 16)?)),
+    };
+    // All ones means unknown
+    if timescale == 0 || duration == u64::MAX || duration == u64::from(u32::MAX) {
+        return None;
+    }
+    u64::try_from(u128::from(duration) * 1000 / u128::from(timescale)).ok()
+}
+
+/// Frames in a keyframe strip, side by side.
+pub const KEYFRAME_STRIP_FRAMES: u32 = 5;
+/// Width of each frame in a keyframe strip, in pixels.
+pub const KEYFRAME_WIDTH: u32 = 160;
+
+/// Makes keyframe strips of videos.
+pub trait KeyframeExtractor: Send + Sync {
+    /// A JPEG of `KEYFRAME_STRIP_FRAMES` keyframes of the video at `video`,
+    /// spread over its `duration_ms` when known, or `None` when it has no
+    /// frames to show.
+    fn keyframe_strip(&self, video: &Path, duration_ms: Option<u64>) -> Result<Option<Vec<u8>>, String>;
+}
+
+/// Makes keyframe strips with the `ffmpeg` found on the `PATH`.
+pub struct FfmpegKeyframes;
+
+impl KeyframeExtractor for FfmpegKeyframes {
+    fn keyframe_strip(&self, video: &Path, duration_ms: Option<u64>) -> Result<Option<Vec<u8>>, String> {
+        // Without a duration the strip is of the first keyframes
+        let spread = match duration_ms.filter(|duration| *duration > 0) {
+            Some(duration) => format!("fps={}/{:.3},", KEYFRAME_STRIP_FRAMES, duration as f64 / 1000.0),
+            None => String::new(),
+        };
+        let filters = format!("{}scale={}:-2,tile={}x1", spread, KEYFRAME_WIDTH, KEYFRAME_STRIP_FRAMES);
+        // Only keyframes are decoded, so long videos cost no more than short ones
+        let output = Command::new("ffmpeg")
+            .args(["-v", "error", "-nostdin", "-skip_frame", "nokey", "-i"])
+            .arg(video)
+            .args(["-vf", &filters, "-frames:v", "1", "-f", "image2pipe", "-c:v", "mjpeg", "-"])
+            .output()
+            .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+        // ffmpeg fails on files without a video stream
+        if !output.status.success() || output.stdout.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(output.stdout))
+    }
+}
+
+/// Contents of the first box of `kind` among the boxes in `bytes`.
+fn find_box<'a>(mut bytes: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    while bytes.len() >= 8 {
+        let (header, size) = match read_u32(bytes, 0)? {
+            1 => (16, read_u64(bytes, 8)?),
+            // Runs to the end of the file
+            0 => (8, bytes.len() as u64),
+            size => (8, u64::from(size)),
+        };
+        let size = usize::try_from(size).ok().filter(|size| (header..=bytes.len()).contains(size))?;
+        if &bytes[4..8] == kind {
+            return Some(&bytes[header..size]);
+        }
+        bytes = &bytes[size..];
+    }
+    None
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
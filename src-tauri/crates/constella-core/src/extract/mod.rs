@@ -0,0 +1,90 @@
+//! Turns file contents into the text that gets indexed. Most text files are
+//! indexed as they are; structured formats have their noise stripped and
+//! their section headings or configuration keys pulled out.
+
+pub mod binaries;
+pub mod config;
+pub mod markdown;
+pub mod media;
+pub mod music;
+pub mod notebook;
+pub mod photo;
+pub mod sqlite;
+pub mod subtitles;
+
+use std::path::Path;
+use config::ConfigFormat;
+use subtitles::Cue;
+
+/// Notebooks are mostly outputs, which are dropped, so they may be this many
+/// times the content size limit.
+const NOTEBOOK_SIZE_FACTOR: u64 = 16;
+
+/// What gets indexed for a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Extracted {
+    pub text: String,
+    /// Section headings, each with the ones it sits under, e.g.
+    /// `Results > Figures`.
+    pub headings: Vec<String>,
+    /// Timed lines, for subtitles and the videos they belong to.
+    pub cues: Vec<Cue>,
+    /// Dotted paths of configuration keys, e.g. `database.password`.
+    pub keys: Vec<String>,
+}
+
+impl Extracted {
+    pub fn plain(text: String) -> Self {
+        Self { text, ..Self::default() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    PlainText,
+    Markdown,
+    Notebook,
+    Subtitles,
+    Config(ConfigFormat),
+}
+
+impl Format {
+    /// How the content of `path` is read, or `None` when it isn't indexed.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if let Some(config) = ConfigFormat::for_extension(&extension) {
+            return Some(Self::Config(config));
+        }
+        match extension.as_str() {
+            "md" | "markdown" | "mdown" | "mkd" => Some(Self::Markdown),
+            "ipynb" => Some(Self::Notebook),
+            "srt" | "vtt" => Some(Self::Subtitles),
+            _ => mime_guess::from_path(path)
+                .first()
+                .filter(|mime| mime.type_() == mime_guess::mime::TEXT)
+                .map(|_| Self::PlainText),
+        }
+    }
+
+    /// The largest file whose content is indexed, given the configured limit.
+    pub fn size_limit(self, content_max_file_size: u64) -> u64 {
+        match self {
+            Self::Notebook => content_max_file_size.saturating_mul(NOTEBOOK_SIZE_FACTOR),
+            Self::PlainText | Self::Markdown | Self::Subtitles | Self::Config(_) => content_max_file_size,
+        }
+    }
+
+    pub fn extract(self, bytes: &[u8]) -> Extracted {
+        let text = String::from_utf8_lossy(bytes);
+        match self {
+            Self::PlainText => Extracted::plain(text.into_owned()),
+            Self::Markdown => markdown::extract(&text),
+            // Not valid notebook JSON after all; index it as it is
+            Self::Notebook => notebook::extract(&text).unwrap_or_else(|| Extracted::plain(text.into_owned())),
+            Self::Subtitles => subtitles::transcript(subtitles::parse(&text)),
+            Self::Config(format) => config::extract(format, &text),
+        }
+    }
+}
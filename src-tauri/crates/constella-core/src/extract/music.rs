@@ -0,0 +1,253 @@
+//! Tags of music files: ID3 (v2.2 to v2.4, falling back to v1) in MP3s,
+//! and Vorbis comments in FLAC, Ogg Vorbis and Opus. Only the tag blocks
+//! at the start and end of a file are read, never the audio.
+
+use std::path::Path;
+use serde::Serialize;
+use ts_rs::TS;
+
+/// MP3s with ID3 tags, then the formats with Vorbis comments.
+pub const MUSIC_EXTENSIONS: &[&str] = &["mp3", "flac", "ogg", "oga", "opus"];
+
+/// How far into an Ogg file its comment header is looked for.
+const OGG_COMMENT_SEARCH: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct MusicTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// The artist an album is filed under, when it differs from a track's.
+    pub album_artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    /// Position on the album, without the track count.
+    pub track: Option<u32>,
+}
+
+impl MusicTags {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Keeps the first value of each tag, ignoring blank ones.
+    fn set(&mut self, key: &str, value: &str) {
+        let value = value.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+        if value.is_empty() {
+            return;
+        }
+        let slot = match key {
+            "title" => &mut self.title,
+            "artist" => &mut self.artist,
+            "album_artist" => &mut self.album_artist,
+            "album" => &mut self.album,
+            "genre" => &mut self.genre,
+            "track" => {
+                if self.track.is_none() {
+                    // "3/12" on the disc, or just "3"
+                    self.track = value.split('/').next().and_then(|track| track.trim().parse().ok());
+                }
+                return;
+            }
+            _ => return,
+        };
+        if slot.is_none() {
+            *slot = Some(value.to_string());
+        }
+    }
+}
+
+/// Files `tags` can read.
+pub fn is_music(path: &Path) -> bool {
+    let extension = path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    MUSIC_EXTENSIONS.contains(&extension.as_str())
+}
+
+/// Tags of the music file in `bytes`, whichever kind it has.
+pub fn tags(bytes: &[u8]) -> MusicTags {
+    let mut tags = MusicTags::default();
+    if bytes.starts_with(b"ID3") {
+        read_id3v2(bytes, &mut tags);
+    } else if let Some(blocks) = bytes.strip_prefix(b"fLaC") {
+        read_flac(blocks, &mut tags);
+    } else if bytes.starts_with(b"OggS") {
+        read_ogg(bytes, &mut tags);
+    }
+    // ID3v1 fills in whatever a v2 tag left out
+    if bytes.len() >= 128 && bytes[bytes.len() - 128..].starts_with(b"TAG") {
+        read_id3v1(&bytes[bytes.len() - 128..], &mut tags);
+    }
+    tags
+}
+
+fn read_id3v2(bytes: &[u8], tags: &mut MusicTags) {
+    let Some(header) = bytes.get(..10) else {
+        return;
+    };
+    let version = header[3];
+    let flags = header[5];
+    let end = (10 + syncsafe(&header[6..10])).min(bytes.len());
+    let mut at = 10;
+    // The extended header counts itself in v2.4 but not in v2.3
+    if flags & 0x40 != 0 {
+        match (version, bytes.get(10..14)) {
+            (4, Some(size)) => at += syncsafe(size),
+            (3, Some(size)) => at += 4 + u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize,
+            _ => return,
+        }
+    }
+
+    let (id_length, header_length) = if version == 2 { (3, 6) } else { (4, 10) };
+    while at + header_length <= end {
+        let id = &bytes[at..at + id_length];
+        // Padding fills the rest of the tag
+        if id[0] == 0 {
+            break;
+        }
+        let size_bytes = &bytes[at + id_length..at + id_length + if version == 2 { 3 } else { 4 }];
+        let size = match version {
+            2 => u32::from_be_bytes([0, size_bytes[0], size_bytes[1], size_bytes[2]]) as usize,
+            3 => u32::from_be_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]]) as usize,
+            _ => syncsafe(size_bytes),
+        };
+        let start = at + header_length;
+        let Some(frame) = bytes.get(start..start + size).filter(|_| start + size <= end) else {
+            break;
+        };
+        let key = match id {
+            b"TIT2" | b"TT2" => "title",
+            b"TPE1" | b"TP1" => "artist",
+            b"TPE2" | b"TP2" => "album_artist",
+            b"TALB" | b"TAL" => "album",
+            b"TCON" | b"TCO" => "genre",
+            b"TRCK" | b"TRK" => "track",
+            _ => "",
+        };
+        if !key.is_empty() {
+            let value = id3_text(frame);
+            let value = if key == "genre" { id3_genre(&value) } else { value };
+            tags.set(key, &value);
+        }
+        at = start + size;
+    }
+}
+
+/// Sizes in ID3v2 headers use seven bits of each byte.
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |size, byte| (size << 7) | (*byte & 0x7F) as usize)
+}
+
+/// The first string of a text frame, in the encoding its first byte names.
+fn id3_text(frame: &[u8]) -> String {
+    let Some((&encoding, text)) = frame.split_first() else {
+        return String::new();
+    };
+    let text = match encoding {
+        0 => text.iter().map(|byte| *byte as char).collect::<String>(),
+        1 | 2 => {
+            let (big_endian, text) = match text {
+                [0xFE, 0xFF, rest @ ..] => (true, rest),
+                [0xFF, 0xFE, rest @ ..] => (false, rest),
+                _ => (encoding == 2, text),
+            };
+            let units: Vec<u16> = text.chunks_exact(2)
+                .map(|pair| if big_endian { u16::from_be_bytes([pair[0], pair[1]]) } else { u16::from_le_bytes([pair[0], pair[1]]) })
+                .take_while(|unit| *unit != 0)
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(text).into_owned(),
+    };
+    text.split('\0').next().unwrap_or_default().to_string()
+}
+
+/// Genres given as a number in parentheses, `(17)Rock`, keep their text;
+/// bare numbers are left as they are.
+fn id3_genre(value: &str) -> String {
+    match value.strip_prefix('(').and_then(|rest| rest.split_once(')')) {
+        Some((number, text)) if number.chars().all(|c| c.is_ascii_digit()) && !text.is_empty() => text.to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn read_id3v1(tag: &[u8], tags: &mut MusicTags) {
+    let field = |range: std::ops::Range<usize>| -> String {
+        tag[range].iter().take_while(|byte| **byte != 0).map(|byte| *byte as char).collect()
+    };
+    tags.set("title", &field(3..33));
+    tags.set("artist", &field(33..63));
+    tags.set("album", &field(63..93));
+    // v1.1 puts the track in the last byte of the comment
+    if tag[125] == 0 && tag[126] != 0 {
+        tags.set("track", &tag[126].to_string());
+    }
+}
+
+fn read_flac(mut blocks: &[u8], tags: &mut MusicTags) {
+    while blocks.len() >= 4 {
+        let last = blocks[0] & 0x80 != 0;
+        let kind = blocks[0] & 0x7F;
+        let length = u32::from_be_bytes([0, blocks[1], blocks[2], blocks[3]]) as usize;
+        let Some(block) = blocks.get(4..4 + length) else {
+            return;
+        };
+        if kind == 4 {
+            read_vorbis_comments(block, tags);
+            return;
+        }
+        if last {
+            return;
+        }
+        blocks = &blocks[4 + length..];
+    }
+}
+
+fn read_ogg(bytes: &[u8], tags: &mut MusicTags) {
+    let head = &bytes[..bytes.len().min(OGG_COMMENT_SEARCH)];
+    for marker in [&b"\x03vorbis"[..], &b"OpusTags"[..]] {
+        if let Some(at) = head.windows(marker.len()).position(|window| window == marker) {
+            read_vorbis_comments(&bytes[at + marker.len()..], tags);
+            return;
+        }
+    }
+}
+
+/// A vendor string, then `KEY=value` comments, all with little-endian lengths.
+fn read_vorbis_comments(block: &[u8], tags: &mut MusicTags) {
+    let read_length = |at: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(block.get(at..at + 4)?.try_into().ok()?) as usize)
+    };
+    let Some(vendor) = read_length(0) else {
+        return;
+    };
+    let mut at = 4 + vendor;
+    let Some(count) = read_length(at) else {
+        return;
+    };
+    at += 4;
+    for _ in 0..count {
+        let Some(length) = read_length(at) else {
+            return;
+        };
+        let Some(comment) = block.get(at + 4..at + 4 + length) else {
+            return;
+        };
+        at += 4 + length;
+        let comment = String::from_utf8_lossy(comment);
+        let Some((key, value)) = comment.split_once('=') else {
+            continue;
+        };
+        let key = match key.to_ascii_uppercase().as_str() {
+            "TITLE" => "title",
+            "ARTIST" => "artist",
+            "ALBUMARTIST" | "ALBUM ARTIST" => "album_artist",
+            "ALBUM" => "album",
+            "GENRE" => "genre",
+            "TRACKNUMBER" => "track",
+            _ => continue,
+        };
+        tags.set(key, value);
+    }
+}
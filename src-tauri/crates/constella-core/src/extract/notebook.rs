@@ -0,0 +1,64 @@
+//! Jupyter notebooks: the source of their cells and their text outputs,
+//! without the JSON around them or the base64 images and other rich
+//! outputs that make up most of a saved notebook.
+
+use serde_json::Value;
+use super::{markdown, Extracted};
+
+/// Cell text and the headings of markdown cells, or `None` when `json`
+/// isn't a notebook.
+pub fn extract(json: &str) -> Option<Extracted> {
+    let notebook: Value = serde_json::from_str(json).ok()?;
+    // nbformat 4 keeps cells at the top level, older versions in worksheets
+    let cells = match notebook.get("cells") {
+        Some(cells) => cells.as_array()?.iter().collect::<Vec<_>>(),
+        None => notebook.get("worksheets")?.as_array()?.iter()
+            .filter_map(|worksheet| worksheet.get("cells")?.as_array())
+            .flatten()
+            .collect(),
+    };
+
+    let mut text = String::new();
+    let mut markdown_cells = String::new();
+    for cell in cells {
+        // Version 3 code cells call their source "input"
+        let source = multiline(cell.get("source").or_else(|| cell.get("input")));
+        if cell.get("cell_type").and_then(Value::as_str) == Some("markdown") {
+            markdown_cells.push_str(&source);
+            markdown_cells.push_str("\n\n");
+        }
+        push_block(&mut text, &source);
+
+        for output in cell.get("outputs").and_then(Value::as_array).into_iter().flatten() {
+            let output_text = output.get("text")
+                .or_else(|| output.get("data").and_then(|data| data.get("text/plain")));
+            push_block(&mut text, &multiline(output_text));
+        }
+    }
+
+    Some(Extracted {
+        text,
+        headings: markdown::headings(&markdown_cells),
+        ..Extracted::default()
+    })
+}
+
+/// Notebook strings are either one string or a list of lines.
+fn multiline(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+fn push_block(text: &mut String, block: &str) {
+    let block = block.trim();
+    if block.is_empty() {
+        return;
+    }
+    if !text.is_empty() {
+        text.push_str("\n\n");
+    }
+    text.push_str(block);
+}
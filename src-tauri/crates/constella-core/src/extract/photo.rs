@@ -0,0 +1,290 @@
+//! When a photo was taken, with what camera and at what size, and the JPEG
+//! preview cameras embed in their raw files. JPEG, HEIC and the TIFF-based
+//! raw formats (CR2, NEF, ARW) are read directly: mime guessing doesn't know
+//! most of them, and decoding them would take a full image library.
+
+use std::collections::HashSet;
+use std::path::Path;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::Serialize;
+use ts_rs::TS;
+
+const RAW_EXTENSIONS: &[&str] = &["cr2", "nef", "arw"];
+const HEIF_EXTENSIONS: &[&str] = &["heic", "heif"];
+pub const PHOTO_EXTENSIONS: &[&str] = &["jpg", "jpeg", "tif", "tiff", "heic", "heif", "cr2", "nef", "arw"];
+
+/// Files chaining more IFDs than this are read only this far.
+const MAX_IFDS: usize = 32;
+
+const IMAGE_WIDTH: u16 = 0x0100;
+const IMAGE_HEIGHT: u16 = 0x0101;
+const STRIP_OFFSETS: u16 = 0x0111;
+const STRIP_BYTE_COUNTS: u16 = 0x0117;
+const MAKE: u16 = 0x010F;
+const MODEL: u16 = 0x0110;
+const DATE_TIME: u16 = 0x0132;
+const SUB_IFDS: u16 = 0x014A;
+const JPEG_OFFSET: u16 = 0x0201;
+const JPEG_LENGTH: u16 = 0x0202;
+const EXIF_IFD: u16 = 0x8769;
+const DATE_TIME_ORIGINAL: u16 = 0x9003;
+const OFFSET_TIME_ORIGINAL: u16 = 0x9011;
+const PIXEL_X_DIMENSION: u16 = 0xA002;
+const PIXEL_Y_DIMENSION: u16 = 0xA003;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PhotoMetadata {
+    /// When the photo was taken, in Unix seconds.
+    #[ts(type = "number | null")]
+    pub taken: Option<u64>,
+    /// Make and model, e.g. "Canon EOS R6".
+    pub camera: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl PhotoMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+pub fn is_photo(path: &Path) -> bool {
+    PHOTO_EXTENSIONS.contains(&extension(path).as_str())
+}
+
+/// Camera raw files, which carry a JPEG preview of themselves.
+pub fn is_raw(path: &Path) -> bool {
+    RAW_EXTENSIONS.contains(&extension(path).as_str())
+}
+
+pub fn is_heif(path: &Path) -> bool {
+    HEIF_EXTENSIONS.contains(&extension(path).as_str())
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// What the EXIF data and, for HEIC, the image properties say about a photo.
+pub fn metadata(bytes: &[u8]) -> PhotoMetadata {
+    let mut metadata = PhotoMetadata::default();
+    if let Some(tiff) = Tiff::find(bytes) {
+        let ifds = tiff.ifds();
+        let find = |tag: u16| ifds.iter().flatten().find(|entry| entry.tag == tag);
+        let text = |tag: u16| find(tag).and_then(|entry| tiff.text(entry));
+        let number = |tag: u16| find(tag).and_then(|entry| tiff.number(entry));
+
+        metadata.taken = text(DATE_TIME_ORIGINAL)
+            .or_else(|| text(DATE_TIME))
+            .and_then(|taken| parse_exif_date(&taken, text(OFFSET_TIME_ORIGINAL).as_deref()));
+        metadata.camera = camera_name(text(MAKE), text(MODEL));
+
+        let dimensions = number(PIXEL_X_DIMENSION).zip(number(PIXEL_Y_DIMENSION)).or_else(|| {
+            // Raw files describe their preview and sensor data in separate
+            // IFDs; the largest is the photo itself
+            ifds.iter()
+                .filter_map(|ifd| {
+                    let number = |tag: u16| ifd.iter().find(|entry| entry.tag == tag).and_then(|entry| tiff.number(entry));
+                    number(IMAGE_WIDTH).zip(number(IMAGE_HEIGHT))
+                })
+                .max_by_key(|&(width, height)| u64::from(width) * u64::from(height))
+        });
+        (metadata.width, metadata.height) = dimensions.unzip();
+    }
+    if let Some((width, height)) = heif_dimensions(bytes) {
+        (metadata.width, metadata.height) = (Some(width), Some(height));
+    }
+    metadata
+}
+
+/// The largest JPEG embedded in a raw file or EXIF data.
+pub fn preview(bytes: &[u8]) -> Option<&[u8]> {
+    let tiff = Tiff::find(bytes)?;
+    let ifds = tiff.ifds();
+    ifds.iter()
+        .flat_map(|ifd| {
+            let number = |tag: u16| ifd.iter().find(|entry| entry.tag == tag).and_then(|entry| tiff.number(entry));
+            [(JPEG_OFFSET, JPEG_LENGTH), (STRIP_OFFSETS, STRIP_BYTE_COUNTS)]
+                .into_iter()
+                .filter_map(move |(offset, length)| Some((number(offset)? as usize, number(length)? as usize)))
+        })
+        .filter_map(|(offset, length)| tiff.data.get(offset..offset.checked_add(length)?))
+        .filter(|jpeg| jpeg.starts_with(&[0xFF, 0xD8]))
+        .max_by_key(|jpeg| jpeg.len())
+}
+
+/// `make` is usually repeated at the start of `model`, sometimes in full
+/// ("Canon", "Canon EOS R6") and sometimes not ("NIKON CORPORATION", "NIKON D850").
+fn camera_name(make: Option<String>, model: Option<String>) -> Option<String> {
+    match (make, model) {
+        (Some(make), Some(model)) => {
+            let brand = make.split_whitespace().next().unwrap_or_default().to_lowercase();
+            if model.to_lowercase().starts_with(&brand) {
+                Some(model)
+            } else {
+                Some(format!("{} {}", make, model))
+            }
+        }
+        (make, model) => model.or(make),
+    }
+}
+
+/// EXIF dates, like `2023:03:14 09:30:00`, are local to where the photo
+/// was taken; without a recorded offset they're read as local here.
+fn parse_exif_date(date: &str, offset: Option<&str>) -> Option<u64> {
+    let taken = match offset {
+        Some(offset) => DateTime::parse_from_str(&format!("{} {}", date, offset), "%Y:%m:%d %H:%M:%S %:z").ok()?.timestamp(),
+        None => {
+            let naive = NaiveDateTime::parse_from_str(date, "%Y:%m:%d %H:%M:%S").ok()?;
+            Local.from_local_datetime(&naive).earliest()?.timestamp()
+        }
+    };
+    u64::try_from(taken).ok()
+}
+
+/// Size of the largest image in a HEIF file, from its `ispe` properties.
+fn heif_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.get(4..8) != Some(b"ftyp") {
+        return None;
+    }
+    let read = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    bytes.windows(4)
+        .enumerate()
+        .filter(|(_, window)| *window == b"ispe")
+        .filter_map(|(at, _)| {
+            // Box size and type, then version and flags
+            if at < 4 || read(at - 4)? < 20 {
+                return None;
+            }
+            Some((read(at + 8)?, read(at + 12)?))
+        })
+        .max_by_key(|&(width, height)| u64::from(width) * u64::from(height))
+}
+
+/// One field of an IFD, with the offset of its four value bytes.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    value: usize,
+}
+
+/// TIFF-structured data: a raw file, or the EXIF block of a JPEG or HEIC.
+/// Offsets in it count from its header.
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn find(bytes: &'a [u8]) -> Option<Self> {
+        if let Some(tiff) = Self::at(bytes) {
+            return Some(tiff);
+        }
+        let start = bytes.windows(6).position(|window| window == b"Exif\0\0")? + 6;
+        Self::at(&bytes[start..])
+    }
+
+    fn at(data: &'a [u8]) -> Option<Self> {
+        match data.get(..4)? {
+            b"II*\0" => Some(Self { data, little_endian: true }),
+            b"MM\0*" => Some(Self { data, little_endian: false }),
+            _ => None,
+        }
+    }
+
+    fn u16(&self, at: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(at..at.checked_add(2)?)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(at..at.checked_add(4)?)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    /// Every IFD reachable from the header: the main chain, the EXIF IFD
+    /// and sub-IFDs, each read once.
+    fn ifds(&self) -> Vec<Vec<Entry>> {
+        let mut ifds = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending: Vec<usize> = self.u32(4).map(|offset| offset as usize).into_iter().collect();
+        while let Some(offset) = pending.pop() {
+            if offset == 0 || ifds.len() >= MAX_IFDS || !visited.insert(offset) {
+                continue;
+            }
+            let Some((entries, next)) = self.ifd(offset) else {
+                continue;
+            };
+            pending.push(next);
+            for entry in entries.iter().rev() {
+                if entry.tag == EXIF_IFD || entry.tag == SUB_IFDS {
+                    pending.extend(self.numbers(entry).into_iter().rev().map(|offset| offset as usize));
+                }
+            }
+            ifds.push(entries);
+        }
+        ifds
+    }
+
+    /// The entries of the IFD at `offset`, and the offset of the next one.
+    fn ifd(&self, offset: usize) -> Option<(Vec<Entry>, usize)> {
+        let count = self.u16(offset)? as usize;
+        let entries = (0..count)
+            .map(|i| {
+                let at = offset + 2 + i * 12;
+                Some(Entry { tag: self.u16(at)?, kind: self.u16(at + 2)?, count: self.u32(at + 4)?, value: at + 8 })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let next = self.u32(offset + 2 + count * 12).unwrap_or(0) as usize;
+        Some((entries, next))
+    }
+
+    /// Where the values of `entry` start: in the entry itself when they fit.
+    fn values(&self, entry: &Entry) -> Option<usize> {
+        let size = match entry.kind {
+            1 | 2 | 7 => 1,
+            3 => 2,
+            4 | 13 => 4,
+            _ => return None,
+        };
+        if size * entry.count as usize <= 4 {
+            Some(entry.value)
+        } else {
+            self.u32(entry.value).map(|offset| offset as usize)
+        }
+    }
+
+    fn numbers(&self, entry: &Entry) -> Vec<u32> {
+        let Some(start) = self.values(entry) else {
+            return Vec::new();
+        };
+        (0..entry.count as usize)
+            .map_while(|i| match entry.kind {
+                3 => self.u16(start + i * 2).map(u32::from),
+                4 | 13 => self.u32(start + i * 4),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn number(&self, entry: &Entry) -> Option<u32> {
+        self.numbers(entry).first().copied()
+    }
+
+    fn text(&self, entry: &Entry) -> Option<String> {
+        if entry.kind != 2 {
+            return None;
+        }
+        let start = self.values(entry)?;
+        let bytes = self.data.get(start..start.checked_add(entry.count as usize)?)?;
+        let text = String::from_utf8_lossy(bytes);
+        let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+        (!text.is_empty()).then(|| text.to_string())
+    }
+}
@@ -0,0 +1,268 @@
+//! Table and column names of SQLite databases, read straight from the file
+//! rather than through SQLite: nothing is locked, no journal or WAL file is
+//! created, and the file is never written. Only the schema table
+//! (`sqlite_master`) is walked, so the cost doesn't grow with the data.
+//! Schema changes still in an uncheckpointed WAL aren't seen.
+
+use std::path::Path;
+use serde::Serialize;
+use ts_rs::TS;
+
+const MAGIC: &[u8] = b"SQLite format 3\0";
+const EXTENSIONS: &[&str] = &["sqlite", "sqlite3", "db", "db3", "sdb"];
+const HEADER_SIZE: usize = 100;
+
+const INTERIOR_TABLE_PAGE: u8 = 0x05;
+const LEAF_TABLE_PAGE: u8 = 0x0D;
+
+/// Pages visited, and overflow pages followed per record, before the file
+/// is treated as corrupt.
+const MAX_PAGES: usize = 10_000;
+/// SQLite's own limit on b-tree depth (`BTCURSOR_MAX_DEPTH`); even 512-byte
+/// pages keep every real database far shallower.
+const MAX_DEPTH: usize = 20;
+/// Tables read; schemas with more are cut off.
+const MAX_TABLES: usize = 1_000;
+
+/// Words that start a table constraint rather than a column definition.
+const CONSTRAINTS: &[&str] = &["constraint", "primary", "unique", "check", "foreign"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DatabaseTable {
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+/// Whether `path` is named like a database file; the header decides.
+pub fn is_database(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.as_str()))
+}
+
+/// The tables of the SQLite database `bytes`, or `None` when it isn't one.
+/// SQLite's own tables are left out.
+pub fn tables(bytes: &[u8]) -> Option<Vec<DatabaseTable>> {
+    if !bytes.starts_with(MAGIC) {
+        return None;
+    }
+    let page_size = match u16_be(bytes, 16)? {
+        1 => 65536,
+        size if size >= 512 && size.is_power_of_two() => size as usize,
+        _ => return None,
+    };
+    let usable = page_size.checked_sub(*bytes.get(20)? as usize)?;
+    let file = DatabaseFile { bytes, page_size, usable };
+
+    let records = file.table_records(1)?;
+    Some(records.iter()
+        .filter_map(|record| {
+            let values = parse_record(record)?;
+            let text = |i: usize| values.get(i).and_then(|value| value.as_deref());
+            if text(0)? != "table" {
+                return None;
+            }
+            let name = text(1)?;
+            if name.starts_with("sqlite_") {
+                return None;
+            }
+            Some(DatabaseTable {
+                name: name.to_string(),
+                columns: text(4).map(column_names).unwrap_or_default(),
+            })
+        })
+        .take(MAX_TABLES)
+        .collect())
+}
+
+/// The tables as text for the content field, so free-text searches for a
+/// table or column name find the database.
+pub fn text(tables: &[DatabaseTable]) -> String {
+    tables.iter()
+        .flat_map(|table| std::iter::once(table.name.as_str()).chain(table.columns.iter().map(String::as_str)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+struct DatabaseFile<'a> {
+    bytes: &'a [u8],
+    page_size: usize,
+    usable: usize,
+}
+
+impl<'a> DatabaseFile<'a> {
+    fn page(&self, number: u32) -> Option<&'a [u8]> {
+        let start = (number as usize).checked_sub(1)?.checked_mul(self.page_size)?;
+        self.bytes.get(start..start.checked_add(self.page_size)?)
+    }
+
+    /// The payload of every record in the table b-tree rooted at page
+    /// `root`, in rowid order.
+    fn table_records(&self, root: u32) -> Option<Vec<Vec<u8>>> {
+        let mut records = Vec::new();
+        let mut visited = 0;
+        // Pages still to read, with their depth. Children go on in reverse
+        // so the leftmost is read first.
+        let mut pending = vec![(root, 1)];
+        while let Some((number, depth)) = pending.pop() {
+            visited += 1;
+            if depth > MAX_DEPTH {
+                return None;
+            }
+            let page = self.page(number)?;
+            // The first page starts with the file header
+            let header = if number == 1 { HEADER_SIZE } else { 0 };
+            let kind = *page.get(header)?;
+            let cells = u16_be(page, header + 3)? as usize;
+            let cell_pointers = header + if kind == INTERIOR_TABLE_PAGE { 12 } else { 8 };
+            match kind {
+                INTERIOR_TABLE_PAGE => {
+                    pending.push((u32_be(page, header + 8)?, depth + 1));
+                    for i in (0..cells).rev() {
+                        let cell = u16_be(page, cell_pointers + i * 2)? as usize;
+                        pending.push((u32_be(page, cell)?, depth + 1));
+                    }
+                }
+                LEAF_TABLE_PAGE => {
+                    for i in 0..cells {
+                        let cell = u16_be(page, cell_pointers + i * 2)? as usize;
+                        records.push(self.payload(page, cell)?);
+                    }
+                }
+                _ => return None,
+            }
+            // Every page waiting is read eventually, so count it now
+            if visited + pending.len() > MAX_PAGES {
+                return None;
+            }
+        }
+        Some(records)
+    }
+
+    /// The payload of the leaf cell at `cell`, following overflow pages.
+    fn payload(&self, page: &[u8], cell: usize) -> Option<Vec<u8>> {
+        let (size, read) = varint(page.get(cell..)?)?;
+        let (_, rowid_size) = varint(page.get(cell + read..)?)?;
+        let start = cell + read + rowid_size;
+        let size = usize::try_from(size).ok()?;
+
+        // How much of the payload is stored in the page itself, per the file format
+        let max_local = self.usable - 35;
+        let min_local = (self.usable - 12) * 32 / 255 - 23;
+        let local = if size <= max_local {
+            size
+        } else {
+            let local = min_local + (size - min_local) % (self.usable - 4);
+            if local <= max_local { local } else { min_local }
+        };
+        let mut payload = page.get(start..start + local)?.to_vec();
+        let mut overflow = if local < size { u32_be(page, start + local)? } else { 0 };
+        let mut followed = 0;
+        while payload.len() < size && overflow != 0 && followed < MAX_PAGES {
+            let next = self.page(overflow)?;
+            let wanted = (size - payload.len()).min(self.usable - 4);
+            payload.extend_from_slice(next.get(4..4 + wanted)?);
+            overflow = u32_be(next, 0)?;
+            followed += 1;
+        }
+        Some(payload)
+    }
+}
+
+/// The text values of a record, with `None` for values of other types.
+fn parse_record(record: &[u8]) -> Option<Vec<Option<String>>> {
+    let (header_size, mut at) = varint(record)?;
+    let header_size = usize::try_from(header_size).ok()?;
+    let mut body = header_size;
+    let mut values = Vec::new();
+    while at < header_size {
+        let (serial_type, read) = varint(record.get(at..)?)?;
+        at += read;
+        let length = match serial_type {
+            0 | 8 | 9 => 0,
+            1..=4 => serial_type as usize,
+            5 => 6,
+            6 | 7 => 8,
+            n if n >= 12 => ((n - 12) / 2) as usize,
+            _ => return None,
+        };
+        let value = record.get(body..body.checked_add(length)?)?;
+        let is_text = serial_type >= 13 && serial_type % 2 == 1;
+        values.push(is_text.then(|| String::from_utf8_lossy(value).into_owned()));
+        body += length;
+    }
+    Some(values)
+}
+
+/// A SQLite variable-length integer and how many bytes it took.
+fn varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().take(9).enumerate() {
+        if i == 8 {
+            return Some(((value << 8) | byte as u64, 9));
+        }
+        value = (value << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Column names from a `CREATE TABLE` or `CREATE VIRTUAL TABLE` statement.
+pub fn column_names(sql: &str) -> Vec<String> {
+    let Some(start) = sql.find('(') else {
+        return Vec::new();
+    };
+    let Some(end) = sql.rfind(')').filter(|&end| end > start) else {
+        return Vec::new();
+    };
+
+    // Split the definitions at commas outside parentheses and quotes
+    let mut definitions = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut current = String::new();
+    for c in sql[start + 1..end].chars() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                definitions.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    definitions.push(current);
+
+    definitions.iter()
+        .filter_map(|definition| {
+            let definition = definition.trim();
+            let name = match definition.chars().next()? {
+                open @ ('"' | '`' | '[') => {
+                    let close = if open == '[' { ']' } else { open };
+                    definition[1..].split(close).next()?
+                }
+                _ => definition.split(|c: char| c.is_whitespace() || c == '(').next()?,
+            };
+            let is_constraint = CONSTRAINTS.contains(&name.to_lowercase().as_str()) && !definition.starts_with(['"', '`', '[']);
+            // Options of virtual tables, like `tokenize=porter`, aren't columns
+            (!name.is_empty() && !is_constraint && !name.contains('=')).then(|| name.to_string())
+        })
+        .collect()
+}
+
+fn u16_be(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(at..at.checked_add(2)?)?.try_into().ok()?))
+}
+
+fn u32_be(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at.checked_add(4)?)?.try_into().ok()?))
+}
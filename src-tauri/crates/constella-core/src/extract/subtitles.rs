@@ -0,0 +1,142 @@
+//! SubRip (`.srt`) and WebVTT (`.vtt`) subtitles, parsed into timed cues so
+//! dialogue searches can point at the moment a line is spoken.
+
+use std::path::Path;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use super::Extracted;
+
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mkv", "webm", "mov", "avi", "wmv", "mpg", "mpeg"];
+const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "vtt"];
+
+/// One timed line of a transcript.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct Cue {
+    #[ts(type = "number")]
+    pub start_ms: u64,
+    #[ts(type = "number")]
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// Reads the subtitle streams embedded in video containers.
+pub trait SubtitleTrackReader: Send + Sync {
+    /// The first subtitle stream of the video at `video` as SubRip or
+    /// WebVTT, or `None` when it has none.
+    fn read_track(&self, video: &Path) -> Result<Option<String>, String>;
+}
+
+/// Reads embedded subtitles with the `ffmpeg` found on the `PATH`.
+pub struct FfmpegSubtitles;
+
+impl SubtitleTrackReader for FfmpegSubtitles {
+    fn read_track(&self, video: &Path) -> Result<Option<String>, String> {
+        let output = Command::new("ffmpeg")
+            .args(["-v", "error", "-nostdin", "-i"])
+            .arg(video)
+            .args(["-map", "0:s:0", "-f", "webvtt", "-"])
+            .output()
+            .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+        // ffmpeg fails when there is no subtitle stream to map
+        if !output.status.success() || output.stdout.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+    }
+}
+
+pub fn is_video(path: &Path) -> bool {
+    has_extension(path, VIDEO_EXTENSIONS)
+}
+
+pub fn is_subtitles(path: &Path) -> bool {
+    has_extension(path, SUBTITLE_EXTENSIONS)
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| extensions.contains(&extension.as_str()))
+}
+
+/// Whether `subtitles` belongs to `video`: it sits next to it and is named
+/// after it, like `movie.srt` or `movie.en.vtt` for `movie.mp4`.
+pub fn belongs_to(subtitles: &Path, video: &Path) -> bool {
+    let (Some(name), Some(stem)) = (subtitles.file_name(), video.file_stem()) else {
+        return false;
+    };
+    subtitles.parent() == video.parent()
+        && is_subtitles(subtitles)
+        && name.to_string_lossy().starts_with(&format!("{}.", stem.to_string_lossy()))
+}
+
+/// A transcript made of `cues`, indexed as their text.
+pub fn transcript(cues: Vec<Cue>) -> Extracted {
+    let text = cues.iter().map(|cue| cue.text.as_str()).collect::<Vec<_>>().join("\n");
+    Extracted { text, cues, ..Extracted::default() }
+}
+
+/// The cues of SubRip or WebVTT `subtitles`, skipping headers, notes and
+/// style blocks, with formatting tags removed from their text.
+pub fn parse(subtitles: &str) -> Vec<Cue> {
+    let subtitles = subtitles.replace("\r\n", "\n");
+    let mut cues = Vec::new();
+    for block in subtitles.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some(timing) = lines.next() else {
+            continue;
+        };
+        let Some((start, end)) = timing.split_once("-->") else {
+            continue;
+        };
+        // WebVTT puts cue settings after the end time
+        let (Some(start_ms), Some(end_ms)) = (
+            parse_timestamp(start.trim()),
+            end.split_whitespace().next().and_then(parse_timestamp),
+        ) else {
+            continue;
+        };
+        let text = lines.map(strip_tags)
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !text.is_empty() {
+            cues.push(Cue { start_ms, end_ms, text });
+        }
+    }
+    cues
+}
+
+/// `hh:mm:ss,mmm`, `hh:mm:ss.mmm` or `mm:ss.mmm`, in milliseconds.
+pub fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let (clock, millis) = timestamp.split_once([',', '.']).unwrap_or((timestamp, "0"));
+    let millis: u64 = millis.parse().ok()?;
+    let parts = clock.split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let seconds = match parts.as_slice() {
+        [hours, minutes, seconds] => hours * 3600 + minutes * 60 + seconds,
+        [minutes, seconds] => minutes * 60 + seconds,
+        _ => return None,
+    };
+    Some(seconds * 1000 + millis)
+}
+
+/// Removes `<i>`-style markup and `{\an8}`-style positioning overrides.
+fn strip_tags(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut closing = None;
+    for c in line.chars() {
+        match (closing, c) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (None, _) => text.push(c),
+            (Some(end), _) if c == end => closing = None,
+            (Some(_), _) => {}
+        }
+    }
+    text
+}
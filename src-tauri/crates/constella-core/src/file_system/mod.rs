@@ -3,22 +3,136 @@ use std::fs;
 use std::time::SystemTime;
 use mime_guess::from_path;
 use log::{info, warn, debug};
-use tokio::sync::{mpsc, Semaphore};
+use tokio::sync::Semaphore;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use memmap2::Mmap;
-use std::io::Read;
-use parking_lot::Mutex;
-use std::collections::VecDeque;
+use std::io::{self, Read};
 use tokio::task;
 use ignore::WalkBuilder;
 use crossbeam_channel::bounded;
 
+pub mod origin;
+pub mod os_path;
+
 const BATCH_SIZE: usize = 100_000; // Increased batch size for better performance
-const MAX_CONCURRENT_READS: usize = 4_000; // Increased concurrent reads
 const READ_BUFFER_SIZE: usize = 128 * 1024; // Increased to 128KB buffer
 const CHANNEL_SIZE: usize = 200_000; // Larger channel size for better throughput
 
+/// The parts of a file's metadata the indexing pipeline relies on, detached
+/// from `std::fs::Metadata` so providers other than the OS can produce it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMetadata {
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+    pub is_file: bool,
+    pub is_dir: bool,
+}
+
+impl From<&fs::Metadata> for FileMetadata {
+    fn from(metadata: &fs::Metadata) -> Self {
+        Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            created: metadata.created().ok(),
+            is_file: metadata.is_file(),
+            is_dir: metadata.is_dir(),
+        }
+    }
+}
+
+/// Filesystem access used by the scanner, indexer and change tracker.
+/// `OsFileSystem` is the real thing; tests swap in in-memory or
+/// failure-injecting providers to drive the error paths deterministically.
+pub trait FileSystemProvider: Send + Sync {
+    /// Metadata for `path`, following symlinks.
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata>;
+
+    /// Entire content of the file at `path`.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Every file below `root`, following symlinks. Entries that can't be
+    /// visited are reported as errors rather than silently dropped.
+    fn walk(&self, root: &Path) -> Vec<io::Result<PathBuf>>;
+
+    /// The files and directories directly inside `dir`.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// The URL a downloaded file was saved from, if the system recorded one.
+    fn origin_url(&self, _path: &Path) -> Option<String> {
+        None
+    }
+
+    /// Content of the file at `path` for scanning in place. The OS maps it
+    /// into memory instead of reading it all up front.
+    fn map(&self, path: &Path) -> io::Result<FileBytes> {
+        self.read(path).map(FileBytes::Owned)
+    }
+}
+
+/// File content either read into memory or mapped from disk.
+pub enum FileBytes {
+    Owned(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl std::ops::Deref for FileBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileBytes::Owned(bytes) => bytes,
+            FileBytes::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsFileSystem;
+
+impl FileSystemProvider for OsFileSystem {
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        fs::metadata(path).map(|metadata| FileMetadata::from(&metadata))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn walk(&self, root: &Path) -> Vec<io::Result<PathBuf>> {
+        walkdir::WalkDir::new(root)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Ok(entry) if entry.file_type().is_file() => Some(Ok(entry.into_path())),
+                Ok(_) => None,
+                Err(e) => Some(Err(e.into())),
+            })
+            .collect()
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn origin_url(&self, path: &Path) -> Option<String> {
+        origin::origin_url(path)
+    }
+
+    fn map(&self, path: &Path) -> io::Result<FileBytes> {
+        let file = fs::File::open(path)?;
+        // Empty files can't be mapped on every platform
+        if file.metadata()?.len() == 0 {
+            return Ok(FileBytes::Owned(Vec::new()));
+        }
+        // Safety: the map is only read, and only for as long as one scan
+        unsafe { Mmap::map(&file) }.map(FileBytes::Mapped)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FileInfo {
     pub path: PathBuf,
@@ -51,35 +165,20 @@ impl FileInfo {
     }
 }
 
-struct WorkQueue {
-    queue: Mutex<VecDeque<PathBuf>>,
-    total: Arc<AtomicUsize>,
+pub struct FileSystem {
+    semaphore: Arc<Semaphore>,
 }
 
-impl WorkQueue {
-    fn new() -> Self {
-        Self {
-            queue: Mutex::new(VecDeque::with_capacity(BATCH_SIZE)),
-            total: Arc::new(AtomicUsize::new(0)),
-        }
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-pub struct FileSystem {
-    work_queue: Arc<WorkQueue>,
-    semaphore: Arc<Semaphore>,
-    sender: mpsc::Sender<PathBuf>,
-    total_files: Arc<AtomicUsize>,
-}
-
 impl FileSystem {
     pub fn new() -> Self {
-        let (sender, _) = mpsc::channel(1000); // Bounded channel for backpressure
         Self {
-            work_queue: Arc::new(WorkQueue::new()),
             semaphore: Arc::new(Semaphore::new(num_cpus::get() * 2)),
-            sender,
-            total_files: Arc::new(AtomicUsize::new(0))
         }
     }
 
@@ -0,0 +1,51 @@
+//! Where a downloaded file came from, as recorded by the browser that saved
+//! it: the Zone.Identifier stream on Windows, `kMDItemWhereFroms` on macOS
+//! and `user.xdg.origin.url` elsewhere.
+
+use std::path::Path;
+
+#[cfg(windows)]
+pub fn origin_url(path: &Path) -> Option<String> {
+    let stream = format!("{}:Zone.Identifier", path.display());
+    let zone = std::fs::read_to_string(stream).ok()?;
+    let value = |key: &str| {
+        zone.lines()
+            .find_map(|line| line.trim().strip_prefix(key))
+            .map(|url| url.trim().to_string())
+            .filter(|url| url.starts_with("http"))
+    };
+    value("HostUrl=").or_else(|| value("ReferrerUrl="))
+}
+
+#[cfg(target_os = "macos")]
+pub fn origin_url(path: &Path) -> Option<String> {
+    // A binary plist of URLs; the first one is where the file itself came from
+    let plist = xattr::get(path, "com.apple.metadata:kMDItemWhereFroms").ok()??;
+    let start = plist.windows(4).position(|window| window == b"http")?;
+    let url: Vec<u8> = plist[start..].iter()
+        .take_while(|byte| byte.is_ascii_graphic())
+        .copied()
+        .collect();
+    String::from_utf8(url).ok()
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn origin_url(path: &Path) -> Option<String> {
+    let url = xattr::get(path, "user.xdg.origin.url").ok()??;
+    String::from_utf8(url).ok()
+}
+
+#[cfg(not(any(windows, unix)))]
+pub fn origin_url(_path: &Path) -> Option<String> {
+    None
+}
+
+/// The host of `url`, lowercased and without a leading `www.`.
+pub fn domain(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map(|(_, rest)| rest)?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.to_lowercase();
+    let host = host.strip_prefix("www.").map(str::to_string).unwrap_or(host);
+    (!host.is_empty()).then_some(host)
+}
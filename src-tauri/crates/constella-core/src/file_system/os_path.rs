@@ -0,0 +1,77 @@
+//! Paths as strings, without losing any. A path that is valid Unicode,
+//! emoji and zero-width characters included, is its own string. Any other
+//! path (bytes that aren't UTF-8 on Unix, unpaired surrogates on Windows)
+//! becomes its lossy display form, a NUL, and its platform-encoded bytes
+//! in hex. No platform allows NUL in a path, so the two can't be confused,
+//! and everything that opens, moves or reads files decodes the original.
+
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Separates the display form of a path from its original bytes.
+pub const RAW_SEPARATOR: char = '\0';
+
+/// `path` as a string that `decode` turns back into exactly `path`.
+pub fn encode(path: &Path) -> String {
+    if let Some(text) = path.to_str() {
+        return text.to_string();
+    }
+    let mut encoded = path.to_string_lossy().into_owned();
+    encoded.push(RAW_SEPARATOR);
+    for unit in platform_units(path) {
+        let _ = write!(encoded, "{:0width$x}", unit, width = UNIT_DIGITS);
+    }
+    encoded
+}
+
+/// The path `encoded` stands for. Strings that aren't encoded paths are
+/// taken as they are.
+pub fn decode(encoded: &str) -> PathBuf {
+    let Some((display, raw)) = encoded.split_once(RAW_SEPARATOR) else {
+        return PathBuf::from(encoded);
+    };
+    let units: Option<Vec<Unit>> = raw.as_bytes()
+        .chunks(UNIT_DIGITS)
+        .map(|digits| std::str::from_utf8(digits).ok().and_then(|digits| Unit::from_str_radix(digits, 16).ok()))
+        .collect();
+    match units {
+        Some(units) if raw.len() % UNIT_DIGITS == 0 => PathBuf::from(from_platform_units(units)),
+        _ => PathBuf::from(display),
+    }
+}
+
+/// The part of `encoded` to show people.
+pub fn display(encoded: &str) -> &str {
+    encoded.split_once(RAW_SEPARATOR).map_or(encoded, |(display, _)| display)
+}
+
+#[cfg(unix)]
+type Unit = u8;
+#[cfg(windows)]
+type Unit = u16;
+const UNIT_DIGITS: usize = std::mem::size_of::<Unit>() * 2;
+
+#[cfg(unix)]
+fn platform_units(path: &Path) -> Vec<Unit> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn from_platform_units(units: Vec<Unit>) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(units)
+}
+
+#[cfg(windows)]
+fn platform_units(path: &Path) -> Vec<Unit> {
+    use std::os::windows::ffi::OsStrExt;
+    path.as_os_str().encode_wide().collect()
+}
+
+#[cfg(windows)]
+fn from_platform_units(units: Vec<Unit>) -> OsString {
+    use std::os::windows::ffi::OsStringExt;
+    OsString::from_wide(&units)
+}
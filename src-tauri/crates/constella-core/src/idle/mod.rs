@@ -0,0 +1,171 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+use serde::Serialize;
+use log::{info, warn};
+use crate::indexing::IndexManager;
+use crate::settings::SettingsManager;
+use ts_rs::TS;
+
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+const COLD_RESCAN_LIMIT: usize = 5_000;
+
+/// Heavy maintenance work that only runs while nobody is using the machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum DeferredJob {
+    SegmentOptimization,
+    ChecksumVerification,
+    ColdRescan,
+    Transcription,
+    HealthCheck,
+}
+
+impl DeferredJob {
+    const ALL: [DeferredJob; 5] = [
+        DeferredJob::SegmentOptimization,
+        DeferredJob::ChecksumVerification,
+        DeferredJob::ColdRescan,
+        DeferredJob::Transcription,
+        DeferredJob::HealthCheck,
+    ];
+
+    fn min_interval(self) -> Duration {
+        match self {
+            DeferredJob::SegmentOptimization => Duration::from_secs(24 * 60 * 60),
+            DeferredJob::ChecksumVerification => Duration::from_secs(24 * 60 * 60),
+            DeferredJob::ColdRescan => Duration::from_secs(6 * 60 * 60),
+            // Checks back often; an empty queue makes it a no-op
+            DeferredJob::Transcription => Duration::from_secs(60),
+            DeferredJob::HealthCheck => Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct IdleStatus {
+    #[ts(type = "number")]
+    pub idle_seconds: u64,
+    pub is_idle: bool,
+    pub running_job: Option<DeferredJob>,
+    #[ts(type = "Array<[DeferredJob, number]>")]
+    pub last_completed: Vec<(DeferredJob, u64)>,
+}
+
+/// Seconds since the last keyboard or mouse input, as reported by the OS.
+pub fn idle_seconds() -> u64 {
+    match user_idle::UserIdle::get_time() {
+        Ok(idle) => idle.as_seconds(),
+        Err(e) => {
+            warn!("Failed to read user idle time: {}", e);
+            0
+        }
+    }
+}
+
+pub struct IdleScheduler {
+    settings: Arc<SettingsManager>,
+    running_job: RwLock<Option<DeferredJob>>,
+    last_run: RwLock<Vec<(DeferredJob, Instant, u64)>>,
+}
+
+impl IdleScheduler {
+    pub fn new(settings: Arc<SettingsManager>) -> Self {
+        Self {
+            settings,
+            running_job: RwLock::new(None),
+            last_run: RwLock::new(Vec::new()),
+        }
+    }
+
+    fn idle_threshold(&self) -> u64 {
+        self.settings.get().idle_threshold_minutes * 60
+    }
+
+    pub fn is_user_idle(&self) -> bool {
+        idle_seconds() >= self.idle_threshold()
+    }
+
+    pub fn status(&self) -> IdleStatus {
+        let idle = idle_seconds();
+        IdleStatus {
+            idle_seconds: idle,
+            is_idle: idle >= self.idle_threshold(),
+            running_job: *self.running_job.read(),
+            last_completed: self.last_run.read()
+                .iter()
+                .map(|(job, _, at)| (*job, *at))
+                .collect(),
+        }
+    }
+
+    fn next_due_job(&self) -> Option<DeferredJob> {
+        let last_run = self.last_run.read();
+        DeferredJob::ALL.into_iter().find(|job| {
+            last_run.iter()
+                .find(|(ran, _, _)| ran == job)
+                .map(|(_, at, _)| at.elapsed() >= job.min_interval())
+                .unwrap_or(true)
+        })
+    }
+
+    fn mark_completed(&self, job: DeferredJob) {
+        let completed_at = chrono::Utc::now().timestamp().max(0) as u64;
+        let mut last_run = self.last_run.write();
+        last_run.retain(|(ran, _, _)| *ran != job);
+        last_run.push((job, Instant::now(), completed_at));
+    }
+
+    /// Polls the OS idle timer and runs due jobs one at a time while the user
+    /// is away. Jobs check back between work items and stop as soon as input
+    /// resumes; an interrupted job stays due and resumes at the next idle spell.
+    pub fn spawn(self: &Arc<Self>, indexer: Arc<IndexManager>) {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(IDLE_POLL_INTERVAL);
+            loop {
+                timer.tick().await;
+                if !scheduler.is_user_idle() {
+                    continue;
+                }
+                let job = match scheduler.next_due_job() {
+                    Some(job) => job,
+                    None => continue,
+                };
+
+                info!("User idle, running deferred job {:?}", job);
+                *scheduler.running_job.write() = Some(job);
+                let should_continue = || scheduler.is_user_idle();
+
+                let result = match job {
+                    DeferredJob::SegmentOptimization => indexer.optimize_segments().await,
+                    DeferredJob::ChecksumVerification => indexer.verify_checksums(should_continue)
+                        .await
+                        .map(|mismatched| info!("Checksum verification found {} changed files", mismatched)),
+                    DeferredJob::ColdRescan => indexer.rescan_cold_files(COLD_RESCAN_LIMIT, should_continue)
+                        .await
+                        .map(|summary| info!("Cold rescan: {:?}", summary)),
+                    DeferredJob::Transcription => indexer.transcribe_pending(should_continue)
+                        .await
+                        .map(|transcribed| info!("Transcribed {} audio files", transcribed)),
+                    DeferredJob::HealthCheck => indexer.check_health()
+                        .await
+                        .map(|health| info!("Index health check scored {}", health.score)),
+                };
+                *scheduler.running_job.write() = None;
+
+                match result {
+                    Ok(()) if scheduler.is_user_idle() => scheduler.mark_completed(job),
+                    Ok(()) => info!("User returned, pausing deferred job {:?}", job),
+                    Err(e) => {
+                        warn!("Deferred job {:?} failed: {}", job, e);
+                        // Don't retry a failing job on every poll
+                        scheduler.mark_completed(job);
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,52 @@
+//! Names and versions of fonts, executables and installers for the index.
+
+use std::path::Path;
+use log::warn;
+use tantivy::Document;
+use crate::extract::binaries::executables::{self, ExecutableFormat};
+use crate::extract::binaries::{self, BinaryKind, BinaryMetadata};
+use super::IndexManager;
+
+impl IndexManager {
+    /// Metadata of the font, executable or installer at `path`, when the
+    /// power policy allows reading it.
+    pub(super) fn binary_metadata(&self, path: &Path, size: u64) -> Option<BinaryMetadata> {
+        binaries::kind(path)?;
+        if size == 0 || !self.power.content_extraction_allowed() {
+            return None;
+        }
+        // Installers run to gigabytes, but only their headers are read
+        let bytes = match self.fs.map(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Failed to read {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        let mut metadata = binaries::metadata(path, &bytes)?;
+
+        // The executable of `Name.app/Contents/MacOS/Name` is described by
+        // `Name.app/Contents/Info.plist`
+        if executables::format(&bytes) == Some(ExecutableFormat::MachO) && metadata.product.is_none() {
+            let contents = path.parent().filter(|parent| parent.ends_with("Contents/MacOS")).and_then(Path::parent);
+            if let Some(plist) = contents.and_then(|contents| self.fs.read(&contents.join("Info.plist")).ok()) {
+                executables::apply_info_plist(&mut metadata, &String::from_utf8_lossy(&plist));
+            }
+        }
+        Some(metadata)
+    }
+
+    /// The binary metadata stored with `retrieved_doc`, if any.
+    pub(super) fn stored_binary(&self, retrieved_doc: &Document) -> Option<BinaryMetadata> {
+        let kind = retrieved_doc.get_all(self.kind_field)
+            .filter_map(|f| f.as_text())
+            .find_map(BinaryKind::parse)?;
+        let text = |field| retrieved_doc.get_first(field).and_then(|f| f.as_text()).map(str::to_string);
+        Some(BinaryMetadata {
+            kind,
+            product: text(self.product_field),
+            company: text(self.company_field),
+            version: text(self.version_field),
+        })
+    }
+}
@@ -0,0 +1,229 @@
+//! Raw byte-pattern search for power users: hex patterns with `??`
+//! wildcards, scanned straight out of memory-mapped files rather than the
+//! index. The index only narrows down which files to scan, by extension,
+//! size and folder, and every search stops once its time budget runs out.
+
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tantivy::collector::DocSetCollector;
+use tantivy::query::{BooleanQuery, Query, RangeQuery};
+use ts_rs::TS;
+use crate::file_system::os_path;
+use super::IndexManager;
+
+const MAX_PATTERN_LEN: usize = 1_024;
+/// How many bytes are scanned between checks of the time budget.
+const BUDGET_CHECK_INTERVAL: usize = 4 << 20;
+
+/// A byte pattern; `None` matches any byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BytePattern {
+    bytes: Vec<Option<u8>>,
+    // The first fixed byte, which candidate offsets are found by
+    anchor: usize,
+}
+
+impl BytePattern {
+    /// Parses hex like `4D 5A ?? 00` or `4d5a??00`.
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let digits: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+        if digits.len() % 2 != 0 {
+            return Err(format!("Byte pattern {:?} has an odd number of hex digits", pattern));
+        }
+        let bytes = digits.chunks(2)
+            .map(|pair| {
+                if pair == ['?', '?'] {
+                    return Ok(None);
+                }
+                match (pair[0].to_digit(16), pair[1].to_digit(16)) {
+                    (Some(high), Some(low)) => Ok(Some((high * 16 + low) as u8)),
+                    _ => Err(format!("Invalid byte \"{}{}\" in pattern: use two hex digits or ??", pair[0], pair[1])),
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if bytes.len() > MAX_PATTERN_LEN {
+            return Err(format!("Byte pattern is longer than {} bytes", MAX_PATTERN_LEN));
+        }
+        let anchor = bytes.iter().position(Option::is_some)
+            .ok_or_else(|| "Byte pattern needs at least one byte that isn't ??".to_string())?;
+        Ok(Self { bytes, anchor })
+    }
+
+    fn matches_at(&self, content: &[u8], start: usize) -> bool {
+        self.bytes.iter()
+            .zip(&content[start..start + self.bytes.len()])
+            .all(|(expected, actual)| expected.map_or(true, |expected| expected == *actual))
+    }
+
+    /// Offsets where the pattern starts in `content`, up to `limit` of them.
+    /// The flag is set when `deadline` passed before the scan finished.
+    pub fn find_in(&self, content: &[u8], limit: usize, deadline: Instant) -> (Vec<u64>, bool) {
+        let mut offsets = Vec::new();
+        if content.len() < self.bytes.len() {
+            return (offsets, false);
+        }
+        let anchor_byte = self.bytes[self.anchor].unwrap_or_default();
+        let last_start = content.len() - self.bytes.len();
+
+        for chunk_start in (0..=last_start).step_by(BUDGET_CHECK_INTERVAL) {
+            if Instant::now() >= deadline {
+                return (offsets, true);
+            }
+            let chunk_end = (chunk_start + BUDGET_CHECK_INTERVAL).min(last_start + 1);
+            let anchors = &content[chunk_start + self.anchor..chunk_end + self.anchor];
+            for (i, _) in anchors.iter().enumerate().filter(|(_, byte)| **byte == anchor_byte) {
+                let start = chunk_start + i;
+                if self.matches_at(content, start) {
+                    offsets.push(start as u64);
+                    if offsets.len() >= limit {
+                        return (offsets, false);
+                    }
+                }
+            }
+        }
+        (offsets, false)
+    }
+}
+
+/// Which files to scan for which pattern. An extension or a maximum size
+/// is required, so a search never reads every indexed file.
+#[derive(Debug, Clone, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ByteSearchRequest {
+    /// Hex bytes with `??` for any byte, e.g. `4D 5A ?? 00`.
+    pub pattern: String,
+    /// Only scan below this folder.
+    pub root: Option<PathBuf>,
+    /// Only scan files with these extensions, compared case-insensitively.
+    pub extensions: Vec<String>,
+    #[ts(type = "number | null")]
+    pub min_size: Option<u64>,
+    #[ts(type = "number | null")]
+    pub max_size: Option<u64>,
+    /// The whole search stops after this long, returning what it found.
+    #[ts(type = "number")]
+    pub time_budget_ms: u64,
+    pub max_offsets_per_file: usize,
+}
+
+impl Default for ByteSearchRequest {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            root: None,
+            extensions: Vec::new(),
+            min_size: None,
+            max_size: None,
+            time_budget_ms: 5_000,
+            max_offsets_per_file: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ByteMatch {
+    pub path: String,
+    #[ts(type = "Array<number>")]
+    pub offsets: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ByteSearchResult {
+    pub matches: Vec<ByteMatch>,
+    /// Files that passed the filters.
+    pub candidates: usize,
+    pub files_scanned: usize,
+    /// Files that couldn't be read.
+    pub failed: usize,
+    /// Set when the time budget ran out before every candidate was scanned.
+    pub timed_out: bool,
+}
+
+impl IndexManager {
+    /// Scans the indexed files matching the request's filters for its byte
+    /// pattern. Only available once turned on in settings.
+    pub async fn search_bytes(&self, request: &ByteSearchRequest) -> Result<ByteSearchResult, String> {
+        if !self.settings.get().byte_search_enabled {
+            return Err("Byte pattern search is turned off in settings".to_string());
+        }
+        if request.extensions.is_empty() && request.max_size.is_none() {
+            return Err("Byte pattern search needs an extension or maximum size to narrow down files".to_string());
+        }
+        if request.max_offsets_per_file == 0 {
+            return Err("max_offsets_per_file must be at least 1".to_string());
+        }
+        let pattern = BytePattern::parse(&request.pattern)?;
+        let deadline = Instant::now() + Duration::from_millis(request.time_budget_ms);
+        let candidates = self.byte_search_candidates(request)?;
+
+        let fs = self.fs.clone();
+        let limit = request.max_offsets_per_file;
+        tokio::task::spawn_blocking(move || {
+            let mut result = ByteSearchResult { candidates: candidates.len(), ..ByteSearchResult::default() };
+            for path in candidates {
+                if Instant::now() >= deadline {
+                    result.timed_out = true;
+                    break;
+                }
+                let Ok(content) = fs.map(&os_path::decode(&path)) else {
+                    result.failed += 1;
+                    continue;
+                };
+                let (offsets, timed_out) = pattern.find_in(&content, limit, deadline);
+                result.files_scanned += 1;
+                if !offsets.is_empty() {
+                    result.matches.push(ByteMatch { path, offsets });
+                }
+                if timed_out {
+                    result.timed_out = true;
+                    break;
+                }
+            }
+            result
+        })
+        .await
+        .map_err(|e| format!("Byte pattern search failed: {}", e))
+    }
+
+    /// Indexed paths passing the request's folder, size and extension
+    /// filters, in path order.
+    fn byte_search_candidates(&self, request: &ByteSearchRequest) -> Result<Vec<String>, String> {
+        let sizes: Box<dyn Query> = Box::new(RangeQuery::new_u64_bounds(
+            "size".to_string(),
+            request.min_size.map_or(Bound::Unbounded, Bound::Included),
+            request.max_size.map_or(Bound::Unbounded, Bound::Included),
+        ));
+        let query: Box<dyn Query> = match &request.root {
+            Some(root) => Box::new(BooleanQuery::intersection(vec![Box::new(self.files_under_query(root)), sizes])),
+            None => sizes,
+        };
+
+        let searcher = self.reader.searcher();
+        let addresses = searcher.search(query.as_ref(), &DocSetCollector)
+            .map_err(|e| format!("Failed to collect files to scan: {}", e))?;
+        let mut paths = Vec::new();
+        for doc_address in addresses {
+            let retrieved_doc = searcher.doc(doc_address)
+                .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+            let Some(path) = self.doc_path(&retrieved_doc) else {
+                continue;
+            };
+            let extension = Path::new(&path).extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if request.extensions.is_empty()
+                || request.extensions.iter().any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(&extension))
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+}
@@ -0,0 +1,139 @@
+//! A compact log of files recently added to or updated in the index by
+//! incremental updates, for a "what's new" feed. Each path appears once,
+//! at its latest change; full index runs aren't logged, since everything
+//! they touch would look new.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::warn;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::file_system::os_path;
+
+/// Oldest entries are dropped beyond this many, whatever their age.
+const MAX_RECENT_CHANGES: usize = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum ChangeKind {
+    Added,
+    Updated,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct RecentChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub changed_at: u64,
+    #[ts(type = "number")]
+    pub size: u64,
+}
+
+pub struct RecentChanges {
+    path: PathBuf,
+    // Oldest first
+    entries: RwLock<VecDeque<RecentChange>>,
+}
+
+impl RecentChanges {
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse recent changes at {:?}, starting over: {}", path, e);
+                VecDeque::new()
+            }),
+            Err(_) => VecDeque::new(),
+        };
+
+        Self {
+            path,
+            entries: RwLock::new(entries),
+        }
+    }
+
+    /// Points the entry of a file that moved from `old` at `new`.
+    pub(crate) fn rebase(&self, old: &Path, new: &Path) {
+        let (old, new) = (os_path::encode(old), os_path::encode(new));
+        let mut entries = self.entries.write();
+        let Some(entry) = entries.iter_mut().find(|entry| entry.path == old) else {
+            return;
+        };
+        entry.path = new;
+        if let Err(e) = self.save(&entries) {
+            warn!("{}", e);
+        }
+    }
+
+    /// Logs a batch of changes and drops entries older than `retention_days`.
+    /// A file added and then edited inside the window stays "added".
+    pub fn record(&self, changes: &[(PathBuf, ChangeKind, u64)], removed: &[PathBuf], retention_days: u32) {
+        if changes.is_empty() && removed.is_empty() {
+            return;
+        }
+        let now = now();
+        let mut entries = self.entries.write();
+        for path in removed {
+            let path = os_path::encode(path);
+            entries.retain(|entry| entry.path != path);
+        }
+        for (path, kind, size) in changes {
+            let path = os_path::encode(path);
+            let mut kind = *kind;
+            if let Some(position) = entries.iter().position(|entry| entry.path == path) {
+                if let Some(previous) = entries.remove(position) {
+                    if previous.kind == ChangeKind::Added {
+                        kind = ChangeKind::Added;
+                    }
+                }
+            }
+            entries.push_back(RecentChange { path, kind, changed_at: now, size: *size });
+        }
+
+        let cutoff = now.saturating_sub(u64::from(retention_days) * 86_400);
+        while entries.front().is_some_and(|entry| entry.changed_at < cutoff) || entries.len() > MAX_RECENT_CHANGES {
+            entries.pop_front();
+        }
+
+        if let Err(e) = self.save(&entries) {
+            warn!("{}", e);
+        }
+    }
+
+    /// Up to `limit` changes within the last `retention_days`, newest first,
+    /// limited to `kinds` unless it is empty.
+    pub fn recent(&self, limit: usize, kinds: &[ChangeKind], retention_days: u32) -> Vec<RecentChange> {
+        let cutoff = now().saturating_sub(u64::from(retention_days) * 86_400);
+        self.entries.read()
+            .iter()
+            .rev()
+            .take_while(|entry| entry.changed_at >= cutoff)
+            .filter(|entry| kinds.is_empty() || kinds.contains(&entry.kind))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn save(&self, entries: &VecDeque<RecentChange>) -> Result<(), String> {
+        let json = serde_json::to_string(entries)
+            .map_err(|e| format!("Failed to serialize recent changes: {}", e))?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write recent changes: {}", e))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to replace recent changes: {}", e))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,180 @@
+//! Text files too large to index whole, like multi-gigabyte logs and CSVs,
+//! indexed as a series of chunk documents next to the file's own. Chunks
+//! have no path of their own, only the file they were cut from, so
+//! listings and folder stats never see them; searches fold each matching
+//! chunk back into its file and snippet that chunk.
+
+use std::ops::{Bound, Range};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use serde::{Deserialize, Serialize};
+use tantivy::collector::TopDocs;
+use tantivy::query::{BooleanQuery, ConstScoreQuery, Occur, Query, RangeQuery, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Term};
+use tantivy::{Document, Searcher};
+use ts_rs::TS;
+use crate::extract::Extracted;
+use super::IndexManager;
+
+/// Chunks committed at a time, so a huge file is never held in memory whole.
+const CHUNKS_PER_COMMIT: usize = 64;
+
+/// Which chunk of a large file a search hit matched in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ChunkLocation {
+    #[ts(type = "number")]
+    pub index: u64,
+    /// Byte offset of the chunk in the file.
+    #[ts(type = "number")]
+    pub offset: u64,
+    #[ts(type = "number")]
+    pub length: u64,
+}
+
+impl ChunkLocation {
+    fn range(&self) -> Option<Range<usize>> {
+        let start = usize::try_from(self.offset).ok()?;
+        Some(start..start.checked_add(usize::try_from(self.length).ok()?)?)
+    }
+}
+
+/// Byte ranges splitting `bytes` into chunks of about `chunk_size`. Each
+/// ends after its last line break, or failing that on a character
+/// boundary, so no line or character is split when it can be helped.
+pub fn chunk_ranges(bytes: &[u8], chunk_size: usize) -> Vec<Range<usize>> {
+    let chunk_size = chunk_size.max(1);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < bytes.len() {
+        let mut end = start.saturating_add(chunk_size).min(bytes.len());
+        if end < bytes.len() {
+            if let Some(line_end) = bytes[start..end].iter().rposition(|&byte| byte == b'\n') {
+                end = start + line_end + 1;
+            } else {
+                while end > start + 1 && (bytes[end] & 0xC0) == 0x80 {
+                    end -= 1;
+                }
+            }
+        }
+        ranges.push(start..end);
+        start = end;
+    }
+    ranges
+}
+
+impl IndexManager {
+    fn chunk_term(&self, path: &Path) -> Term {
+        Term::from_field_text(self.chunk_of_field, &self.stored_path(path))
+    }
+
+    /// Terms matching every document indexed for `path`: the file's own and
+    /// those of its chunks.
+    pub(super) fn document_terms(&self, path: &Path) -> [Term; 2] {
+        [self.path_term(path), self.chunk_term(path)]
+    }
+
+    /// Indexes `path` in chunks when it is a text file too large to index
+    /// whole, committing as it goes. Returns how many chunks were indexed.
+    pub(super) async fn index_chunks(&self, path: &Path, size: u64) -> Result<usize, String> {
+        let config = self.settings.get().indexing;
+        if !config.is_chunked(path, size) || !self.power.content_extraction_allowed() {
+            return Ok(0);
+        }
+        let modified = self.fs.metadata(path)
+            .ok()
+            .and_then(|metadata| metadata.modified)
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|modified| modified.as_secs())
+            .unwrap_or_default();
+        let bytes = self.fs.map(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let stored_path = self.stored_path(path);
+        let ranges = chunk_ranges(&bytes, usize::try_from(config.chunk_size).unwrap_or(usize::MAX));
+        let count = ranges.len();
+        let mut batch = Vec::with_capacity(CHUNKS_PER_COMMIT.min(count));
+        let mut findings = Vec::new();
+        let mut first_line = 1;
+        for (index, range) in ranges.into_iter().enumerate() {
+            let text = String::from_utf8_lossy(&bytes[range.clone()]).into_owned();
+            let lines = text.matches('\n').count();
+            let text = self.chunk_without_secrets(path, text, first_line, &mut findings);
+            first_line += lines;
+            let mut doc = Document::default();
+            doc.add_text(self.chunk_of_field, &stored_path);
+            doc.add_u64(self.chunk_field, index as u64);
+            doc.add_u64(self.chunk_offset_field, range.start as u64);
+            doc.add_u64(self.chunk_length_field, range.len() as u64);
+            // Ranks chunks by recency like their file
+            doc.add_u64(self.modified_field, modified);
+            doc.add_text(self.content_field, &text);
+            batch.push(doc);
+            if batch.len() >= CHUNKS_PER_COMMIT {
+                self.commit_batch(&mut batch).await?;
+            }
+        }
+        if !batch.is_empty() {
+            self.commit_batch(&mut batch).await?;
+        }
+        if !findings.is_empty() {
+            self.secret_findings.record(path, findings);
+        }
+        Ok(count)
+    }
+
+    /// Matches chunks whose text matches `text_query`. Filters and scopes
+    /// can't be applied to chunks directly, as chunks don't carry their
+    /// file's fields; `hit_file` checks their file instead.
+    pub(super) fn chunks_matching(&self, text_query: Box<dyn Query>) -> Box<dyn Query> {
+        let chunks = RangeQuery::new_str_bounds("chunk_of".to_string(), Bound::Unbounded, Bound::Unbounded);
+        Box::new(BooleanQuery::new(vec![
+            (Occur::Must, text_query),
+            (Occur::Must, Box::new(ConstScoreQuery::new(Box::new(chunks), 0.0))),
+        ]))
+    }
+
+    /// The file document a hit stands for: the hit itself, or for a chunk
+    /// the file it was cut from along with where the chunk sits in it.
+    /// `None` when a chunk's file isn't indexed (yet) or fails `file_filter`.
+    pub(super) fn hit_file(
+        &self,
+        searcher: &Searcher,
+        retrieved_doc: Document,
+        file_filter: Option<&dyn Query>,
+    ) -> Result<Option<(Document, Option<ChunkLocation>)>, String> {
+        let Some(path) = self.doc_path_in(&retrieved_doc, self.chunk_of_field) else {
+            return Ok(Some((retrieved_doc, None)));
+        };
+        let stored = |field: Field| retrieved_doc.get_first(field).and_then(|f| f.as_u64()).unwrap_or_default();
+        let location = ChunkLocation {
+            index: stored(self.chunk_field),
+            offset: stored(self.chunk_offset_field),
+            length: stored(self.chunk_length_field),
+        };
+
+        let file: Box<dyn Query> = Box::new(TermQuery::new(self.path_term(Path::new(&path)), IndexRecordOption::Basic));
+        let query: Box<dyn Query> = match file_filter {
+            Some(filter) => Box::new(BooleanQuery::intersection(vec![file, filter.box_clone()])),
+            None => file,
+        };
+        let top_docs = searcher.search(query.as_ref(), &TopDocs::with_limit(1))
+            .map_err(|e| format!("Failed to look up {}: {}", path, e))?;
+        let Some((_, doc_address)) = top_docs.into_iter().next() else {
+            return Ok(None);
+        };
+        let file_doc = searcher.doc(doc_address)
+            .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+        Ok(Some((file_doc, Some(location))))
+    }
+
+    /// The text of one chunk of `path`, for its snippet.
+    pub(super) fn chunk_content(&self, path: &Path, location: &ChunkLocation) -> Option<Extracted> {
+        if !self.power.content_extraction_allowed() {
+            return None;
+        }
+        let bytes = self.fs.map(path).ok()?;
+        let chunk = bytes.get(location.range()?)?;
+        Some(Extracted::plain(String::from_utf8_lossy(chunk).into_owned()))
+    }
+}
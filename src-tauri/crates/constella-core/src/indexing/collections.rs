@@ -0,0 +1,260 @@
+//! Named collections of results the user puts together by hand, like
+//! "Tax 2023", kept next to the index rather than in it. A collection can
+//! be searched on its own, through `SearchOptions::collection`, and
+//! exported as a folder of links or copies. Collections follow files the
+//! app moves and folders that are remapped, and every change to them is
+//! written ahead to a log so a crash can't corrupt them.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tantivy::query::{BooleanQuery, Query, TermQuery};
+use tantivy::schema::IndexRecordOption;
+use ts_rs::TS;
+use crate::actions::ActionFailure;
+use crate::persistence::wal::WalStore;
+use crate::file_system::os_path;
+use super::IndexManager;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct CollectionSummary {
+    pub name: String,
+    pub items: usize,
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub created: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum CollectionExport {
+    Symlinks,
+    Copies,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct CollectionExportReport {
+    /// What was created in the export folder.
+    pub exported: Vec<String>,
+    pub failed: Vec<ActionFailure>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct Collection {
+    created: u64,
+    /// In the order they were added.
+    items: Vec<String>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+pub(crate) struct CollectionStore {
+    store: WalStore<String, Collection>,
+}
+
+impl CollectionStore {
+    /// Opens the collections kept at `base`, importing the `collections.json`
+    /// next to it that earlier versions wrote.
+    pub(crate) fn load(base: impl AsRef<Path>) -> Result<Self, String> {
+        let base = base.as_ref();
+        let store = WalStore::open(base)?;
+        let legacy = base.with_extension("json");
+        if store.is_empty() {
+            if let Ok(json) = std::fs::read_to_string(&legacy) {
+                match serde_json::from_str::<BTreeMap<String, Collection>>(&json) {
+                    Ok(collections) => {
+                        store.replace(collections)?;
+                        if let Err(e) = std::fs::remove_file(&legacy) {
+                            warn!("Failed to remove {:?}: {}", legacy, e);
+                        }
+                    }
+                    Err(e) => warn!("Failed to parse collections at {:?}, starting over: {}", legacy, e),
+                }
+            }
+        }
+        Ok(Self { store })
+    }
+
+    /// Points items at or below `old` at the same place below `new`.
+    pub(crate) fn rebase(&self, old: &Path, new: &Path) {
+        let rebased = self.store.update(|collections| {
+            let affected: Vec<String> = collections.iter()
+                .filter(|(_, collection)| collection.items.iter().any(|item| os_path::decode(item).starts_with(old)))
+                .map(|(name, _)| name.clone())
+                .collect();
+            for name in &affected {
+                let Some(collection) = collections.get_mut(name) else {
+                    continue;
+                };
+                for item in &mut collection.items {
+                    if let Ok(relative) = os_path::decode(item).strip_prefix(old) {
+                        // Joining nothing would leave a trailing separator
+                        let rebased = if relative.as_os_str().is_empty() { new.to_path_buf() } else { new.join(relative) };
+                        *item = os_path::encode(&rebased);
+                    }
+                }
+            }
+            Ok(())
+        });
+        if let Err(e) = rebased {
+            warn!("{}", e);
+        }
+    }
+}
+
+fn summary(name: &str, collection: &Collection) -> CollectionSummary {
+    CollectionSummary { name: name.to_string(), items: collection.items.len(), created: collection.created }
+}
+
+impl IndexManager {
+    /// Adds `paths` to collection `name`, creating it if need be. Paths
+    /// already in it stay where they are.
+    pub fn add_to_collection(&self, name: &str, paths: &[PathBuf]) -> Result<CollectionSummary, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Collections need a name".to_string());
+        }
+        let added = self.collections.store.update(|collections| {
+            let collection = collections.get_or_insert_with(name.to_string(), || Collection { created: now(), items: Vec::new() });
+            for path in paths {
+                let path = os_path::encode(path);
+                if !collection.items.contains(&path) {
+                    collection.items.push(path);
+                }
+            }
+            Ok(summary(name, collection))
+        })?;
+        info!("Collection {} has {} items", name, added.items);
+        Ok(added)
+    }
+
+    pub fn remove_from_collection(&self, name: &str, paths: &[PathBuf]) -> Result<CollectionSummary, String> {
+        let removed: HashSet<String> = paths.iter().map(|path| os_path::encode(path)).collect();
+        self.collections.store.update(|collections| {
+            let collection = collections.get_mut(name)
+                .ok_or_else(|| format!("No collection named {}", name))?;
+            collection.items.retain(|item| !removed.contains(item));
+            Ok(summary(name, collection))
+        })
+    }
+
+    /// Forgets collection `name`; the files in it stay where they are.
+    pub fn delete_collection(&self, name: &str) -> Result<(), String> {
+        self.collections.store.update(|collections| match collections.remove(name) {
+            Some(_) => Ok(()),
+            None => Err(format!("No collection named {}", name)),
+        })?;
+        info!("Deleted collection {}", name);
+        Ok(())
+    }
+
+    /// Every collection, by name.
+    pub fn list_collections(&self) -> Vec<CollectionSummary> {
+        self.collections.store.read()
+            .iter()
+            .map(|(name, collection)| summary(name, collection))
+            .collect()
+    }
+
+    /// The paths in collection `name`, in the order they were added.
+    pub fn collection_items(&self, name: &str) -> Result<Vec<String>, String> {
+        self.collections.store.read()
+            .get(name)
+            .map(|collection| collection.items.clone())
+            .ok_or_else(|| format!("No collection named {}", name))
+    }
+
+    /// Names of the collections holding `path` itself.
+    pub(super) fn collections_containing(&self, path: &Path) -> Vec<String> {
+        let path = os_path::encode(path);
+        self.collections.store.read()
+            .iter()
+            .filter(|(_, collection)| collection.items.contains(&path))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Matches the files in collection `name`, and everything below the
+    /// folders in it.
+    pub(super) fn collection_scope(&self, name: &str) -> Result<Box<dyn Query>, String> {
+        let items = self.collection_items(name)?;
+        let mut clauses: Vec<Box<dyn Query>> = Vec::with_capacity(items.len() * 2);
+        for item in &items {
+            let path = &os_path::decode(item);
+            clauses.push(Box::new(TermQuery::new(self.path_term(path), IndexRecordOption::Basic)));
+            clauses.push(Box::new(self.files_under_query(path)));
+        }
+        Ok(Box::new(BooleanQuery::union(clauses)))
+    }
+
+    /// Fills `folder` with a link to, or a copy of, each item of collection
+    /// `name` that still exists. Items sharing a name are numbered, and
+    /// nothing already in `folder` is replaced.
+    pub fn export_collection(&self, name: &str, folder: &Path, export: CollectionExport) -> Result<CollectionExportReport, String> {
+        let items = self.collection_items(name)?;
+        std::fs::create_dir_all(folder)
+            .map_err(|e| format!("Failed to create {}: {}", folder.display(), e))?;
+
+        let mut report = CollectionExportReport::default();
+        for item in items {
+            let source = &os_path::decode(&item);
+            let exported = std::fs::metadata(source)
+                .map_err(|e| format!("Failed to find {}: {}", item, e))
+                .and_then(|metadata| {
+                    let target = free_name(folder, source)?;
+                    let made = match export {
+                        CollectionExport::Symlinks => link(source, &target, metadata.is_dir()),
+                        CollectionExport::Copies => crate::journal::copy_path(source, &target, metadata.is_dir()),
+                    };
+                    made.map(|_| target)
+                        .map_err(|e| format!("Failed to export {}: {}", item, e))
+                });
+            match exported {
+                Ok(target) => report.exported.push(target.to_string_lossy().into_owned()),
+                Err(error) => report.failed.push(ActionFailure { path: item, error }),
+            }
+        }
+        info!("Exported collection {} to {:?}, {} failed", name, folder, report.failed.len());
+        Ok(report)
+    }
+}
+
+/// `folder` joined with the name of `source`, numbered when taken:
+/// `notes.txt`, `notes (2).txt`, ...
+fn free_name(folder: &Path, source: &Path) -> Result<PathBuf, String> {
+    let name = source.file_name().ok_or_else(|| format!("Can't export {}", source.display()))?;
+    let stem = Path::new(name).file_stem().unwrap_or(name).to_string_lossy();
+    let extension = Path::new(name).extension().map(|extension| format!(".{}", extension.to_string_lossy()));
+    let mut target = folder.join(name);
+    let mut n = 2;
+    while std::fs::symlink_metadata(&target).is_ok() {
+        target = folder.join(format!("{} ({}){}", stem, n, extension.as_deref().unwrap_or("")));
+        n += 1;
+    }
+    Ok(target)
+}
+
+#[cfg(unix)]
+fn link(source: &Path, target: &Path, _is_dir: bool) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+#[cfg(windows)]
+fn link(source: &Path, target: &Path, is_dir: bool) -> std::io::Result<()> {
+    if is_dir {
+        std::os::windows::fs::symlink_dir(source, target)
+    } else {
+        std::os::windows::fs::symlink_file(source, target)
+    }
+}
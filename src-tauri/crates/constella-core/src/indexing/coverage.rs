@@ -0,0 +1,149 @@
+//! Compares what is on disk under a root with what the index holds for it,
+//! so users can check the index really covers what they expect.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::extract::Format;
+use crate::settings::IndexingConfig;
+use crate::file_system::os_path;
+use super::IndexManager;
+use ts_rs::TS;
+
+/// Skipped subtrees listed in a report; the counts still cover everything.
+const MAX_REPORTED_SUBTREES: usize = 100;
+/// Walk errors listed in a report.
+const MAX_REPORTED_ERRORS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum SkipReason {
+    /// Hidden and system paths, and those matching the exclusion globs.
+    Excluded,
+    /// Eligible files with no document, e.g. unreadable or added since the
+    /// last index pass.
+    NotIndexed,
+    /// Indexed by name only; the text is over the content size limit.
+    ContentTooLarge,
+    /// Indexed by name only, being in a dependency or build folder.
+    DependencyFolder,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct SkippedSubtree {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct CoverageReport {
+    pub root: PathBuf,
+    pub files_on_disk: usize,
+    pub indexed_documents: usize,
+    pub excluded: usize,
+    pub not_indexed: usize,
+    pub content_too_large: usize,
+    pub dependency_folder: usize,
+    /// Documents for files that no longer exist.
+    pub stale_documents: usize,
+    /// Entries the walk couldn't visit, typically for lack of permission.
+    pub unreadable: usize,
+    pub unreadable_errors: Vec<String>,
+    /// Directories holding skipped files, most affected first.
+    pub skipped: Vec<SkippedSubtree>,
+}
+
+impl IndexManager {
+    /// Walks `root` and reports which of its files the index is missing, and why.
+    pub async fn coverage(&self, root: impl AsRef<Path>) -> Result<CoverageReport, String> {
+        let root = root.as_ref().to_path_buf();
+        let fs = self.fs.clone();
+        let walk_root = root.clone();
+        let entries = tokio::task::spawn_blocking(move || fs.walk(&walk_root))
+            .await
+            .map_err(|e| format!("Failed to walk {}: {}", root.display(), e))?;
+
+        let indexed: HashSet<PathBuf> = self.documents_under(&root).await?
+            .into_iter()
+            .map(|file| os_path::decode(&file.path))
+            .collect();
+
+        let mut report = CoverageReport {
+            root: root.clone(),
+            files_on_disk: 0,
+            indexed_documents: indexed.len(),
+            excluded: 0,
+            not_indexed: 0,
+            content_too_large: 0,
+            dependency_folder: 0,
+            stale_documents: 0,
+            unreadable: 0,
+            unreadable_errors: Vec::new(),
+            skipped: Vec::new(),
+        };
+        let mut subtrees: BTreeMap<(PathBuf, SkipReason), usize> = BTreeMap::new();
+        let mut on_disk = HashSet::new();
+        let exclusions = self.exclusions();
+        let config = self.settings.get().indexing;
+
+        for entry in entries {
+            let path = match entry {
+                Ok(path) => path,
+                Err(e) => {
+                    report.unreadable += 1;
+                    if report.unreadable_errors.len() < MAX_REPORTED_ERRORS {
+                        report.unreadable_errors.push(e.to_string());
+                    }
+                    continue;
+                }
+            };
+            report.files_on_disk += 1;
+
+            let reason = if exclusions.is_excluded(&path) {
+                report.excluded += 1;
+                Some(SkipReason::Excluded)
+            } else if !indexed.contains(&path) {
+                report.not_indexed += 1;
+                Some(SkipReason::NotIndexed)
+            } else if config.in_dependency_folder(&path) {
+                report.dependency_folder += 1;
+                Some(SkipReason::DependencyFolder)
+            } else if self.is_over_content_limit(&path, &config) {
+                report.content_too_large += 1;
+                Some(SkipReason::ContentTooLarge)
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                let parent = path.parent().unwrap_or(&root).to_path_buf();
+                *subtrees.entry((parent, reason)).or_default() += 1;
+            }
+            on_disk.insert(path);
+        }
+
+        report.stale_documents = indexed.difference(&on_disk).count();
+        report.skipped = subtrees.into_iter()
+            .map(|((path, reason), files)| SkippedSubtree { path, reason, files })
+            .collect();
+        report.skipped.sort_by_key(|subtree| std::cmp::Reverse(subtree.files));
+        report.skipped.truncate(MAX_REPORTED_SUBTREES);
+        Ok(report)
+    }
+
+    /// Whether `path` is a text file whose content is too big to index,
+    /// even in chunks.
+    fn is_over_content_limit(&self, path: &Path, config: &IndexingConfig) -> bool {
+        Format::for_path(path).is_some_and(|format| {
+            self.fs.metadata(path)
+                .map(|metadata| {
+                    metadata.len > format.size_limit(config.content_max_file_size)
+                        && !config.is_chunked(path, metadata.len)
+                })
+                .unwrap_or(false)
+        })
+    }
+}
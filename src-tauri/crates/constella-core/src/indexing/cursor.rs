@@ -0,0 +1,254 @@
+//! Cursors for walking every result of a search a page at a time, e.g. for
+//! exports. A cursor pins the index snapshot it was opened on and resumes
+//! after the last hit it returned, so deep pages cost the same as the first
+//! and results don't shift while indexing carries on.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tantivy::collector::{Count, TopDocs};
+use tantivy::query::Query;
+use tantivy::{DocAddress, DocId, Score, Searcher, SegmentId, SegmentReader};
+use ts_rs::TS;
+use crate::search::{RankingWeights, ResultFields, SearchOptions};
+use crate::search::boosts::BoostMatcher;
+use super::{IndexManager, PreparedQuery};
+use super::shedding::SheddingMeasure;
+
+pub type CursorId = u64;
+
+/// Cursors left alone this long are closed, releasing their snapshot.
+const CURSOR_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// Opening more than this many closes the least recently used.
+const MAX_OPEN_CURSORS: usize = 16;
+pub const MAX_PAGE_SIZE: usize = 1_000;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct SearchCursor {
+    #[ts(type = "number")]
+    pub id: CursorId,
+    /// Matches in the snapshot, counting any that file type boosts hide and
+    /// each matching chunk of a large file.
+    pub total_hits: usize,
+    /// Load shedding measures the pages are subject to.
+    pub shed: Vec<SheddingMeasure>,
+    /// Generation of the index snapshot the cursor pages through.
+    #[ts(type = "number")]
+    pub snapshot: u64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct SearchPage {
+    #[ts(type = "Array<unknown>")]
+    pub results: Vec<serde_json::Value>,
+    /// Set on the last page; the cursor is closed after it.
+    pub done: bool,
+}
+
+/// Where a hit sits in cursor order: score descending, then doc address.
+/// Addresses are stable because the cursor keeps its snapshot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SortKey {
+    score: Score,
+    segment_ord: u32,
+    doc: DocId,
+}
+
+impl SortKey {
+    fn is_after(&self, last: &SortKey) -> bool {
+        self.score < last.score
+            || (self.score == last.score && (self.segment_ord, self.doc) > (last.segment_ord, last.doc))
+    }
+}
+
+struct CursorState {
+    searcher: Searcher,
+    query: Box<dyn Query>,
+    file_filter: Option<Box<dyn Query>>,
+    // Files already returned, so hits on more of their chunks are skipped
+    seen: HashSet<String>,
+    fields: ResultFields,
+    weights: RankingWeights,
+    // Fixed when the cursor opens so recency scores don't drift between pages
+    now: u64,
+    last: Option<SortKey>,
+    last_used: Instant,
+}
+
+pub(crate) struct SearchCursors {
+    next_id: AtomicU64,
+    open: Mutex<HashMap<CursorId, CursorState>>,
+}
+
+impl SearchCursors {
+    pub(crate) fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            open: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn insert(&self, state: CursorState) -> CursorId {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut open = self.open.lock();
+        open.retain(|_, cursor| cursor.last_used.elapsed() < CURSOR_IDLE_TIMEOUT);
+        while open.len() >= MAX_OPEN_CURSORS {
+            let Some(oldest) = open.iter().min_by_key(|(_, cursor)| cursor.last_used).map(|(id, _)| *id) else {
+                break;
+            };
+            open.remove(&oldest);
+        }
+        open.insert(id, state);
+        id
+    }
+
+    // Taken out while a page is read so other cursors aren't held up
+    fn take(&self, id: CursorId) -> Option<CursorState> {
+        let mut open = self.open.lock();
+        open.retain(|_, cursor| cursor.last_used.elapsed() < CURSOR_IDLE_TIMEOUT);
+        open.remove(&id)
+    }
+
+    fn put_back(&self, id: CursorId, mut state: CursorState) {
+        state.last_used = Instant::now();
+        self.open.lock().insert(id, state);
+    }
+
+    fn remove(&self, id: CursorId) -> bool {
+        self.open.lock().remove(&id).is_some()
+    }
+}
+
+impl IndexManager {
+    /// Starts paging through every match for `query` in the index as it is
+    /// now. Results are ranked by text relevance and recency; file type
+    /// boosts only hide files, and clicks aren't taken into account.
+    pub async fn open_search_cursor(&self, query: &str, options: &SearchOptions) -> Result<SearchCursor, String> {
+        let (searcher, snapshot) = self.snapshot_searcher(options.snapshot)?;
+        let PreparedQuery { query, file_filter } = self.prepare_query(&searcher, query, options)?;
+        let total_hits = searcher.search(query.as_ref(), &Count)
+            .map_err(|e| format!("Failed to count matches: {}", e))?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let id = self.cursors.insert(CursorState {
+            searcher,
+            query,
+            file_filter,
+            seen: HashSet::new(),
+            fields: self.result_fields(options.fields),
+            weights: self.ranking_weights(),
+            now,
+            last: None,
+            last_used: Instant::now(),
+        });
+        Ok(SearchCursor { id, total_hits, shed: self.shed_measures(), snapshot })
+    }
+
+    /// The next `page_size` results of `cursor`.
+    pub async fn next_page(&self, cursor: CursorId, page_size: usize) -> Result<SearchPage, String> {
+        if page_size == 0 || page_size > MAX_PAGE_SIZE {
+            return Err(format!("Page size must be between 1 and {}, got {}", MAX_PAGE_SIZE, page_size));
+        }
+        let mut state = self.cursors.take(cursor)
+            .ok_or_else(|| format!("No open search cursor with id {}", cursor))?;
+
+        let page = self.read_page(&mut state, page_size);
+        // A failed page leaves the cursor where it was, so it can be retried
+        if !matches!(page, Ok(SearchPage { done: true, .. })) {
+            self.cursors.put_back(cursor, state);
+        }
+        page
+    }
+
+    /// Closes `cursor` before it runs out or times out.
+    pub fn close_search_cursor(&self, cursor: CursorId) -> Result<(), String> {
+        if self.cursors.remove(cursor) {
+            Ok(())
+        } else {
+            Err(format!("No open search cursor with id {}", cursor))
+        }
+    }
+
+    fn read_page(&self, state: &mut CursorState, page_size: usize) -> Result<SearchPage, String> {
+        let boosts = BoostMatcher::new(&self.settings.get().file_type_boosts)?;
+        let mut last = state.last;
+        let mut hits = Vec::with_capacity(page_size);
+        let mut done = false;
+        // Hidden file types leave gaps, so keep fetching until the page is full
+        while hits.len() < page_size && !done {
+            let wanted = page_size - hits.len();
+            let batch = hits_after(state, last, wanted)?;
+            done = batch.len() < wanted;
+            for (key, doc_address) in batch {
+                last = Some(key);
+                let retrieved_doc = state.searcher.doc(doc_address)
+                    .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+                let Some((retrieved_doc, chunk)) = self.hit_file(&state.searcher, retrieved_doc, state.file_filter.as_deref())? else {
+                    continue;
+                };
+                let Some(path) = self.doc_path(&retrieved_doc) else {
+                    continue;
+                };
+                if boosts.multiplier(Path::new(&path)) == 0.0 || !state.seen.insert(path.clone()) {
+                    continue;
+                }
+                let mut doc = self.result_document(&retrieved_doc, &path, key.score, state.fields);
+                if let Some(chunk) = chunk {
+                    doc.insert("chunk".to_string(), serde_json::to_value(chunk)
+                        .map_err(|e| format!("Failed to serialize chunk: {}", e))?);
+                }
+                hits.push((key.score, serde_json::Value::Object(doc)));
+            }
+        }
+
+        if state.fields >= ResultFields::Snippets {
+            self.add_snippets(&state.searcher, state.query.as_ref(), &mut hits)?;
+        }
+        state.last = last;
+        Ok(SearchPage {
+            results: hits.into_iter().map(|(_, doc)| doc).collect(),
+            done,
+        })
+    }
+}
+
+/// Up to `limit` hits following `last` in cursor order.
+fn hits_after(state: &CursorState, last: Option<SortKey>, limit: usize) -> Result<Vec<(SortKey, DocAddress)>, String> {
+    let weights = state.weights;
+    let now = state.now;
+    let segment_ords: HashMap<SegmentId, u32> = state.searcher.segment_readers().iter()
+        .enumerate()
+        .map(|(ord, segment_reader)| (segment_reader.segment_id(), ord as u32))
+        .collect();
+    let collector = TopDocs::with_limit(limit).tweak_score(move |segment_reader: &SegmentReader| {
+        let segment_ord = segment_ords.get(&segment_reader.segment_id()).copied().unwrap_or_default();
+        let modified = segment_reader.fast_fields().u64("modified").ok();
+        move |doc: DocId, score: Score| {
+            let modified = modified.as_ref()
+                .and_then(|column| column.first(doc))
+                .unwrap_or_default();
+            let key = SortKey { score: score * weights.recency_multiplier(modified, now), segment_ord, doc };
+            // Hits already returned rank below every hit still to come
+            let after = last.map_or(true, |last| key.is_after(&last));
+            (after, key.score, Reverse(segment_ord), Reverse(doc))
+        }
+    });
+    let top_docs = state.searcher.search(state.query.as_ref(), &collector)
+        .map_err(|e| format!("Failed to execute search: {}", e))?;
+
+    Ok(top_docs.into_iter()
+        .filter(|((after, ..), _)| *after)
+        .map(|((_, score, Reverse(segment_ord), Reverse(doc)), doc_address)| {
+            (SortKey { score, segment_ord, doc }, doc_address)
+        })
+        .collect())
+}
@@ -0,0 +1,27 @@
+//! Table and column names of SQLite databases for the index.
+
+use std::path::Path;
+use log::warn;
+use crate::extract::sqlite::{self, DatabaseTable};
+use super::IndexManager;
+
+/// Value of the `kind` field for databases, for `kind:database`.
+pub const DATABASE_KIND: &str = "database";
+
+impl IndexManager {
+    /// Tables of the SQLite database at `path`, when the power policy
+    /// allows reading it. Only the schema pages are touched.
+    pub(super) fn database_tables(&self, path: &Path, size: u64) -> Option<Vec<DatabaseTable>> {
+        if !sqlite::is_database(path) || size == 0 || !self.power.content_extraction_allowed() {
+            return None;
+        }
+        let bytes = match self.fs.map(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Failed to read {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        sqlite::tables(&bytes)
+    }
+}
@@ -0,0 +1,177 @@
+//! Finds indexed files with identical content. Sizes come from the index,
+//! so only files sharing a size with another file are read and hashed.
+//! Hashes are kept for as long as a file's indexed size and modified time
+//! stay the same, so repeated lookups don't read the same files again.
+
+use std::collections::HashMap;
+use std::path::Path;
+use log::warn;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tantivy::collector::DocSetCollector;
+use tantivy::query::RangeQuery;
+use crate::file_system::os_path;
+use super::IndexManager;
+use ts_rs::TS;
+
+/// Past this many cached hashes the cache starts over.
+const MAX_CACHED_HASHES: usize = 100_000;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DuplicateGroup {
+    #[ts(type = "number")]
+    pub size: u64,
+    pub hash: String,
+    pub paths: Vec<String>,
+}
+
+/// Content hashes by path, with the size and modified time they were
+/// computed for.
+pub(crate) struct ContentHashes {
+    hashes: Mutex<HashMap<String, (u64, u64, blake3::Hash)>>,
+}
+
+impl ContentHashes {
+    pub(crate) fn new() -> Self {
+        Self {
+            hashes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, path: &str, size: u64, modified: u64) -> Option<blake3::Hash> {
+        match self.hashes.lock().get(path) {
+            Some(&(cached_size, cached_modified, hash)) if cached_size == size && cached_modified == modified => Some(hash),
+            _ => None,
+        }
+    }
+
+    fn insert(&self, path: &str, size: u64, modified: u64, hash: blake3::Hash) {
+        let mut hashes = self.hashes.lock();
+        if hashes.len() >= MAX_CACHED_HASHES {
+            hashes.clear();
+        }
+        hashes.insert(path.to_string(), (size, modified, hash));
+    }
+}
+
+impl IndexManager {
+    /// Groups the non-empty indexed files under `root` by content. Stops
+    /// early, returning the groups found so far, once `should_continue`
+    /// returns false; `progress` is told how many candidates of the total
+    /// have been hashed.
+    pub async fn find_duplicates(
+        &self,
+        root: impl AsRef<Path>,
+        should_continue: impl Fn() -> bool,
+        progress: impl Fn(usize, usize),
+    ) -> Result<Vec<DuplicateGroup>, String> {
+        let mut by_size: HashMap<u64, Vec<(String, u64)>> = HashMap::new();
+        for file in self.documents_under(root).await? {
+            if file.size > 0 {
+                by_size.entry(file.size).or_default().push((file.path, file.modified));
+            }
+        }
+        let candidates: Vec<(u64, (String, u64))> = by_size.into_iter()
+            .filter(|(_, files)| files.len() > 1)
+            .flat_map(|(size, files)| files.into_iter().map(move |file| (size, file)))
+            .collect();
+
+        let total = candidates.len();
+        let mut by_content: HashMap<(u64, blake3::Hash), Vec<String>> = HashMap::new();
+        for (checked, (size, (path, modified))) in candidates.into_iter().enumerate() {
+            if !should_continue() {
+                break;
+            }
+            let hash = self.cached_hash(&path, size, modified).await;
+            if let Some(hash) = hash {
+                by_content.entry((size, hash)).or_default().push(path);
+            }
+            progress(checked + 1, total);
+        }
+
+        let mut groups: Vec<DuplicateGroup> = by_content.into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|((size, hash), mut paths)| {
+                paths.sort();
+                DuplicateGroup { size, hash: hash.to_hex().to_string(), paths }
+            })
+            .collect();
+        // Largest wasted space first
+        groups.sort_by(|a, b| {
+            let wasted = |group: &DuplicateGroup| group.size * (group.paths.len() as u64 - 1);
+            wasted(b).cmp(&wasted(a)).then_with(|| a.paths.cmp(&b.paths))
+        });
+        Ok(groups)
+    }
+
+    /// Indexed files with the same content as the file at `path`, which
+    /// needn't be indexed itself. Only files of the same size are hashed.
+    /// Empty files are never matched.
+    pub async fn find_identical(&self, path: impl AsRef<Path>) -> Result<Vec<String>, String> {
+        let path = path.as_ref();
+        let metadata = self.fs.metadata(path)
+            .map_err(|e| format!("Failed to get metadata for {}: {}", path.display(), e))?;
+        if !metadata.is_file {
+            return Err(format!("{} is not a file", path.display()));
+        }
+        if metadata.len == 0 {
+            return Ok(Vec::new());
+        }
+        let target = self.hash_content(path).await
+            .ok_or_else(|| format!("Failed to read {}", path.display()))?;
+
+        let searcher = self.reader.searcher();
+        let same_size = RangeQuery::new_u64("size".to_string(), metadata.len..metadata.len + 1);
+        let addresses = searcher.search(&same_size, &DocSetCollector)
+            .map_err(|e| format!("Failed to find files of the same size: {}", e))?;
+
+        let path_str = os_path::encode(path);
+        let mut identical = Vec::new();
+        for doc_address in addresses {
+            let retrieved_doc = searcher.doc(doc_address)
+                .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+            let Some(candidate) = self.doc_path(&retrieved_doc) else {
+                continue;
+            };
+            if candidate == path_str {
+                continue;
+            }
+            let modified = retrieved_doc.get_first(self.modified_field)
+                .and_then(|f| f.as_u64())
+                .unwrap_or_default();
+            if self.cached_hash(&candidate, metadata.len, modified).await == Some(target) {
+                identical.push(candidate);
+            }
+        }
+        identical.sort();
+        Ok(identical)
+    }
+
+    /// The content hash of an indexed file, read from disk only when the
+    /// file changed since it was last hashed.
+    async fn cached_hash(&self, path: &str, size: u64, modified: u64) -> Option<blake3::Hash> {
+        if let Some(hash) = self.content_hashes.get(path, size, modified) {
+            return Some(hash);
+        }
+        let hash = self.hash_content(&os_path::decode(path)).await?;
+        self.content_hashes.insert(path, size, modified, hash);
+        Some(hash)
+    }
+
+    async fn hash_content(&self, path: &Path) -> Option<blake3::Hash> {
+        let fs = self.fs.clone();
+        let owned = path.to_path_buf();
+        let hashed = tokio::task::spawn_blocking(move || fs.read(&owned).map(|content| blake3::hash(&content)))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result.map_err(|e| e.to_string()));
+        match hashed {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                warn!("Failed to hash {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+}
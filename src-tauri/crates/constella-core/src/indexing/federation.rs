@@ -0,0 +1,178 @@
+//! Searching several indexes as one: the main index, shards and scratch
+//! indexes. The query goes to every selected index at once, results are
+//! merged by score and labelled with the index they came from, and the
+//! whole search answers within a latency budget. Indexes that haven't
+//! answered by then are left out and reported, so a slow disk or a huge
+//! shard can't hold up everything else.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use log::warn;
+use serde::Serialize;
+use tokio::time::Instant;
+use ts_rs::TS;
+use crate::search::{ResultFields, SearchFacets, SearchOptions, SearchResponse};
+use super::shedding::SheddingMeasure;
+use super::{IndexManager, SEARCH_RESULT_LIMIT};
+
+/// How long a federated search waits for its slowest index by default.
+pub const DEFAULT_LATENCY_BUDGET: Duration = Duration::from_secs(2);
+
+pub struct FederationMember {
+    pub label: String,
+    pub index: Arc<IndexManager>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct FederationFailure {
+    pub index: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct FederatedResponse {
+    /// Merged by score; each result's `index` is the label of the index it
+    /// came from.
+    #[ts(type = "Array<unknown>")]
+    pub results: Vec<serde_json::Value>,
+    /// Indexes whose results are in, in the order they answered.
+    pub answered: Vec<String>,
+    /// Indexes still searching when the budget ran out.
+    pub timed_out: Vec<String>,
+    pub failed: Vec<FederationFailure>,
+    /// Lowest of the answering indexes.
+    pub index_completeness: f32,
+    pub facets: Option<SearchFacets>,
+    pub shed: Vec<SheddingMeasure>,
+}
+
+impl FederatedResponse {
+    /// Whether results from some index are missing.
+    pub fn is_partial(&self) -> bool {
+        !self.timed_out.is_empty() || !self.failed.is_empty()
+    }
+}
+
+/// Orders results of equal score the same way every time.
+fn tie_break(result: &serde_json::Value) -> (Option<&str>, Option<&str>) {
+    (result["index"].as_str(), result["id"].as_str())
+}
+
+/// Combines searches of several indexes into one response, by score.
+/// Results are labelled with the index they came from when it has one.
+pub(super) fn merge_responses(responses: Vec<(Option<String>, SearchResponse)>, fields: ResultFields) -> SearchResponse {
+    let mut results = Vec::new();
+    let mut index_completeness: f32 = 100.0;
+    let mut facets: Option<(BTreeMap<String, usize>, BTreeMap<String, usize>)> = None;
+    let mut shed = Vec::new();
+    for (label, response) in responses {
+        for mut result in response.results {
+            if let (Some(label), Some(object)) = (&label, result.as_object_mut()) {
+                object.insert("index".to_string(), serde_json::Value::from(label.as_str()));
+            }
+            results.push(result);
+        }
+        index_completeness = index_completeness.min(response.index_completeness);
+        if let Some(index_facets) = response.facets {
+            let (file_types, categories) = facets.get_or_insert_with(Default::default);
+            for facet in index_facets.file_types {
+                *file_types.entry(facet.value).or_default() += facet.count;
+            }
+            for facet in index_facets.categories {
+                *categories.entry(facet.value).or_default() += facet.count;
+            }
+        }
+        shed.extend(response.shed);
+    }
+
+    let score = |result: &serde_json::Value| result["score"].as_f64().unwrap_or_default();
+    results.sort_by(|a, b| score(b).total_cmp(&score(a)).then_with(|| tie_break(a).cmp(&tie_break(b))));
+    results.truncate(SEARCH_RESULT_LIMIT);
+    if fields == ResultFields::Paths {
+        for result in &mut results {
+            let mut kept = serde_json::Map::new();
+            for key in ["id", "path", "index"] {
+                if let Some(value) = result.get(key) {
+                    kept.insert(key.to_string(), value.clone());
+                }
+            }
+            *result = serde_json::Value::Object(kept);
+        }
+    }
+    SearchResponse {
+        results,
+        index_completeness,
+        facets: facets.map(|(file_types, categories)| SearchFacets::from_counts(file_types, categories)),
+        shed: SheddingMeasure::ALL.into_iter().filter(|measure| shed.contains(measure)).collect(),
+        snapshot: None,
+    }
+}
+
+/// Searches `members` concurrently, giving up on any that haven't answered
+/// within `budget`.
+pub async fn federated_search(
+    members: Vec<FederationMember>,
+    query: &str,
+    options: &SearchOptions,
+    budget: Duration,
+) -> Result<FederatedResponse, String> {
+    if members.is_empty() {
+        return Err("No indexes selected to search".to_string());
+    }
+    // Merging needs scores, which path-only results leave out
+    let mut member_options = options.clone();
+    member_options.fields = options.fields.max(ResultFields::Metadata);
+    // Snapshots and offsets belong to a single index
+    member_options.snapshot = None;
+    member_options.offset = 0;
+    let deadline = Instant::now() + budget;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut pending: Vec<String> = Vec::with_capacity(members.len());
+    for member in members {
+        pending.push(member.label.clone());
+        let (tx, query, options) = (tx.clone(), query.to_string(), member_options.clone());
+        tokio::spawn(async move {
+            let response = member.index.search_response(&query, &options).await;
+            let _ = tx.send((member.label, response));
+        });
+    }
+    drop(tx);
+
+    let mut answered = Vec::new();
+    let mut responses = Vec::new();
+    let mut failed = Vec::new();
+    // A timeout still takes what's ready, so check the deadline first
+    while !pending.is_empty() && Instant::now() < deadline {
+        let Ok(Some((label, response))) = tokio::time::timeout_at(deadline, rx.recv()).await else {
+            break;
+        };
+        pending.retain(|index| *index != label);
+        match response {
+            Ok(response) => {
+                answered.push(label.clone());
+                responses.push((label, response));
+            }
+            Err(error) => failed.push(FederationFailure { index: label, error }),
+        }
+    }
+    if !pending.is_empty() {
+        warn!("Federated search left out {} indexes over the {:?} budget", pending.len(), budget);
+    }
+
+    let merged = merge_responses(
+        responses.into_iter().map(|(label, response)| (Some(label), response)).collect(),
+        options.fields,
+    );
+    Ok(FederatedResponse {
+        results: merged.results,
+        answered,
+        timed_out: pending,
+        failed,
+        index_completeness: merged.index_completeness,
+        facets: merged.facets,
+        shed: merged.shed,
+    })
+}
@@ -0,0 +1,272 @@
+//! An overall health score for the index, from 0 to 100, and concrete
+//! things to do about whatever drags it down. The score weighs how much of
+//! each root is indexed, how many documents point at files that are gone,
+//! how much couldn't be read, how fragmented the segments are and how much
+//! room is left on the disk holding the index. It is checked while the
+//! machine is idle, and on request.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::info;
+use parking_lot::RwLock;
+use serde::Serialize;
+use sysinfo::{DiskExt, System, SystemExt};
+use ts_rs::TS;
+use super::IndexManager;
+
+/// Segments beyond this many start to slow searches down.
+const HEALTHY_SEGMENTS: usize = 10;
+/// Free space below this share of the disk counts against the score.
+const HEALTHY_FREE_SHARE: f64 = 0.2;
+/// Fewer files than this in a dependency folder or over the content limit
+/// isn't worth a recommendation.
+const RECOMMEND_MIN_FILES: usize = 100;
+/// Recommendations are made once a share this large of a root is missing
+/// or stale.
+const RECOMMEND_MIN_SHARE: f64 = 0.05;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum RecommendedAction {
+    /// Add `pattern` to the exclusion globs.
+    Exclude { pattern: String },
+    /// Turn on `chunk_large_files` for text files over the content limit.
+    ChunkLargeFiles { root: PathBuf },
+    /// Index `root` again for the files it's missing.
+    Reindex { root: PathBuf },
+    /// Reconcile `root` to drop documents of files that are gone.
+    Reconcile { root: PathBuf },
+    /// Give the app access to what it couldn't read under `root`.
+    GrantAccess { root: PathBuf },
+    /// Merge the index segments.
+    Optimize,
+    /// Make room on the disk holding the index.
+    FreeDiskSpace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct Recommendation {
+    pub action: RecommendedAction,
+    /// e.g. "Exclude node_modules (saves ~2.1 GB)".
+    pub title: String,
+    /// Estimated index space it frees up.
+    #[ts(type = "number | null")]
+    pub saves_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct IndexHealth {
+    pub score: u8,
+    /// Each factor from 0, worst, to 1, best.
+    pub coverage: f32,
+    pub freshness: f32,
+    pub readability: f32,
+    pub compactness: f32,
+    pub disk_headroom: f32,
+    #[ts(type = "number")]
+    pub indexed_documents: u64,
+    #[ts(type = "number")]
+    pub deleted_documents: u64,
+    pub segments: usize,
+    #[ts(type = "number")]
+    pub index_bytes: u64,
+    #[ts(type = "number | null")]
+    pub free_disk_bytes: Option<u64>,
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub checked_at: u64,
+    /// Most worthwhile first.
+    pub recommendations: Vec<Recommendation>,
+}
+
+pub(crate) struct HealthMonitor {
+    index_dir: PathBuf,
+    last: RwLock<Option<IndexHealth>>,
+}
+
+impl HealthMonitor {
+    pub(crate) fn new(index_dir: &Path) -> Self {
+        Self { index_dir: index_dir.to_path_buf(), last: RwLock::new(None) }
+    }
+
+    fn index_bytes(&self) -> u64 {
+        let Ok(entries) = std::fs::read_dir(&self.index_dir) else {
+            return 0;
+        };
+        entries.flatten()
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
+    /// Free and total bytes of the disk the index is on.
+    fn disk_space(&self) -> Option<(u64, u64)> {
+        let mut system = System::new();
+        system.refresh_disks_list();
+        system.disks().iter()
+            .filter(|disk| self.index_dir.starts_with(disk.mount_point()))
+            .max_by_key(|disk| disk.mount_point().components().count())
+            .map(|disk| (disk.available_space(), disk.total_space()))
+    }
+}
+
+fn share(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+impl IndexManager {
+    /// The health found by the last check, if there was one since startup.
+    pub fn last_health(&self) -> Option<IndexHealth> {
+        self.health.last.read().clone()
+    }
+
+    /// Walks every indexed root to score the index and work out what would
+    /// improve it. Slow on large trees, which is why it mostly runs idle.
+    pub async fn check_health(&self) -> Result<IndexHealth, String> {
+        let config = self.settings.get().indexing;
+        let roots = self.indexed_roots();
+        let (mut eligible, mut missing, mut stale, mut unreadable, mut visited) = (0, 0, 0, 0, 0);
+        let mut recommendations = Vec::new();
+        let mut dependency_files: BTreeMap<String, usize> = BTreeMap::new();
+        for root in &roots {
+            let report = self.coverage(root).await?;
+            let root_eligible = report.files_on_disk - report.excluded;
+            eligible += root_eligible;
+            missing += report.not_indexed;
+            stale += report.stale_documents;
+            unreadable += report.unreadable;
+            visited += report.files_on_disk + report.unreadable;
+
+            if share(report.not_indexed, root_eligible) >= RECOMMEND_MIN_SHARE {
+                recommendations.push(Recommendation {
+                    action: RecommendedAction::Reindex { root: root.clone() },
+                    title: format!("Reindex {} ({} files missing)", root.display(), report.not_indexed),
+                    saves_bytes: None,
+                });
+            }
+            if share(report.stale_documents, report.indexed_documents) >= RECOMMEND_MIN_SHARE {
+                recommendations.push(Recommendation {
+                    action: RecommendedAction::Reconcile { root: root.clone() },
+                    title: format!("Reconcile {} ({} files no longer exist)", root.display(), report.stale_documents),
+                    saves_bytes: None,
+                });
+            }
+            if report.unreadable > 0 {
+                recommendations.push(Recommendation {
+                    action: RecommendedAction::GrantAccess { root: root.clone() },
+                    title: format!("Allow access to {} ({} entries couldn't be read)", root.display(), report.unreadable),
+                    saves_bytes: None,
+                });
+            }
+            if !config.chunk_large_files && report.content_too_large >= RECOMMEND_MIN_FILES {
+                recommendations.push(Recommendation {
+                    action: RecommendedAction::ChunkLargeFiles { root: root.clone() },
+                    title: format!("Enable content indexing of large files in {} ({} searchable by name only)", root.display(), report.content_too_large),
+                    saves_bytes: None,
+                });
+            }
+
+            for file in self.documents_under(root).await? {
+                let folder = Path::new(&file.path).components().find_map(|component| match component {
+                    Component::Normal(name) => config.dependency_folders.iter().find(|folder| name == folder.as_str()),
+                    _ => None,
+                });
+                if let Some(folder) = folder {
+                    *dependency_files.entry(folder.clone()).or_default() += 1;
+                }
+            }
+        }
+
+        let searcher = self.reader.searcher();
+        let segments = searcher.segment_readers().len();
+        let indexed_documents = searcher.num_docs();
+        let deleted_documents: u64 = searcher.segment_readers().iter()
+            .map(|segment| u64::from(segment.num_deleted_docs()))
+            .sum();
+        let index_bytes = self.health.index_bytes();
+        let bytes_per_document = index_bytes.checked_div(indexed_documents).unwrap_or(0);
+        for (folder, files) in dependency_files.into_iter().filter(|(_, files)| *files >= RECOMMEND_MIN_FILES) {
+            let pattern = format!("**/{}/**", folder);
+            if config.exclude.contains(&pattern) {
+                continue;
+            }
+            let saves = bytes_per_document * files as u64;
+            recommendations.push(Recommendation {
+                action: RecommendedAction::Exclude { pattern },
+                title: format!("Exclude {} (saves ~{})", folder, human_bytes(saves)),
+                saves_bytes: Some(saves),
+            });
+        }
+
+        let compactness = (1.0 - share(deleted_documents as usize, (indexed_documents + deleted_documents) as usize))
+            * (HEALTHY_SEGMENTS as f64 / segments.max(HEALTHY_SEGMENTS) as f64);
+        if compactness < 0.8 {
+            recommendations.push(Recommendation {
+                action: RecommendedAction::Optimize,
+                title: format!("Optimize the index ({} segments)", segments),
+                saves_bytes: None,
+            });
+        }
+        let disk_space = self.health.disk_space();
+        let disk_headroom = disk_space.map_or(1.0, |(free, total)| (share(free as usize, total as usize) / HEALTHY_FREE_SHARE).min(1.0));
+        if disk_headroom < 0.5 {
+            recommendations.push(Recommendation {
+                action: RecommendedAction::FreeDiskSpace,
+                title: format!("Free up disk space ({} left)", human_bytes(disk_space.map_or(0, |(free, _)| free))),
+                saves_bytes: None,
+            });
+        }
+        recommendations.sort_by_key(|recommendation| std::cmp::Reverse(recommendation.saves_bytes));
+
+        let coverage = if eligible == 0 { 1.0 } else { 1.0 - share(missing, eligible) };
+        let freshness = 1.0 - share(stale, indexed_documents as usize);
+        let readability = 1.0 - share(unreadable, visited);
+        let score = 35.0 * coverage + 20.0 * freshness + 15.0 * readability + 15.0 * compactness + 15.0 * disk_headroom;
+        let health = IndexHealth {
+            score: score.round().clamp(0.0, 100.0) as u8,
+            coverage: coverage as f32,
+            freshness: freshness as f32,
+            readability: readability as f32,
+            compactness: compactness as f32,
+            disk_headroom: disk_headroom as f32,
+            indexed_documents,
+            deleted_documents,
+            segments,
+            index_bytes,
+            free_disk_bytes: disk_space.map(|(free, _)| free),
+            checked_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            recommendations,
+        };
+        info!("Index health {}, {} recommendations", health.score, health.recommendations.len());
+        *self.health.last.write() = Some(health.clone());
+        Ok(health)
+    }
+
+    /// What would improve the index, from the last check or a new one.
+    pub async fn get_recommendations(&self) -> Result<Vec<Recommendation>, String> {
+        match self.last_health() {
+            Some(health) => Ok(health.recommendations),
+            None => self.check_health().await.map(|health| health.recommendations),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! What a full index run can leave alone. A run over a folder indexed
+//! before with the same document settings only touches files whose size,
+//! modification time or (for files with a hash on record) content changed
+//! since the change tracker last saw them, plus the videos whose subtitle
+//! files did. Documents of files that are gone or now excluded are
+//! removed. When the settings that shape documents changed, everything is
+//! indexed afresh.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use crate::file_system::FileMetadata;
+use super::upgrade::INDEX_FORMAT_VERSION;
+use super::IndexManager;
+
+/// Fingerprint of the document settings the index was last rebuilt
+/// with, kept next to the other data.
+const FINGERPRINT_FILE: &str = "index_fingerprint";
+
+pub(super) fn load_fingerprint(data_dir: &Path) -> Option<String> {
+    std::fs::read_to_string(data_dir.join(FINGERPRINT_FILE)).ok()
+}
+
+/// Files of a run that can be skipped, and documents to drop before it.
+pub(super) struct RunPlan {
+    /// Unchanged files, with the metadata they were checked against.
+    pub unchanged: HashMap<PathBuf, FileMetadata>,
+    /// Indexed files that changed or are no longer part of the run.
+    pub stale: Vec<PathBuf>,
+}
+
+impl IndexManager {
+    /// Hash of the settings that change what goes into a document, and of
+    /// the index format.
+    pub(super) fn document_settings_fingerprint(&self) -> String {
+        let settings = self.settings.get();
+        let shaping = serde_json::json!({
+            "format": INDEX_FORMAT_VERSION,
+            "indexing": settings.indexing,
+            "transcription": settings.transcription,
+            "image_labeling": settings.image_labeling,
+            "secret_scanning_enabled": settings.secret_scanning_enabled,
+        });
+        blake3::hash(shaping.to_string().as_bytes()).to_hex().to_string()
+    }
+
+    /// Whether the index was last rebuilt with the current document
+    /// settings, and no rebuild is scheduled.
+    pub(super) fn built_with_current_settings(&self) -> bool {
+        !self.rebuild_scheduled()
+            && self.built_with.lock().as_deref() == Some(self.document_settings_fingerprint().as_str())
+    }
+
+    /// Notes that the index, just emptied, only gets documents built with
+    /// the current settings from now on.
+    pub(super) fn record_built_with(&self) {
+        let fingerprint = self.document_settings_fingerprint();
+        // In-memory indexes start empty every time
+        if self.index_dir.is_some() {
+            if let Err(e) = std::fs::write(self.data_dir.join(FINGERPRINT_FILE), &fingerprint) {
+                warn!("Failed to record the index settings fingerprint: {}", e);
+            }
+        }
+        *self.built_with.lock() = Some(fingerprint);
+    }
+
+    /// Sorts the `paths` of a run against the files already `indexed`.
+    pub(super) async fn plan_run(&self, paths: &[PathBuf], indexed: &HashSet<PathBuf>) -> RunPlan {
+        let mut unchanged = HashMap::new();
+        let mut stale = Vec::new();
+        for path in paths.iter().filter(|path| indexed.contains(*path)) {
+            match self.fs.metadata(path) {
+                Ok(metadata) if self.tracker.same_file(path, path, &metadata).await => {
+                    unchanged.insert(path.clone(), metadata);
+                }
+                _ => stale.push(path.clone()),
+            }
+        }
+        let in_run: HashSet<&PathBuf> = paths.iter().collect();
+        let gone: Vec<PathBuf> = indexed.iter()
+            .filter(|path| !in_run.contains(path))
+            .cloned()
+            .collect();
+
+        // Videos are indexed with the subtitle files next to them
+        let touched = paths.iter().filter(|path| !unchanged.contains_key(*path)).chain(&gone);
+        let videos: Vec<PathBuf> = touched.flat_map(|path| self.videos_with_subtitles(path)).collect();
+        for video in videos {
+            if unchanged.remove(&video).is_some() {
+                stale.push(video);
+            }
+        }
+        for path in &gone {
+            self.tracker.forget(path).await;
+        }
+        stale.extend(gone);
+        info!("{} indexed files unchanged, {} to drop or replace", unchanged.len(), stale.len());
+        RunPlan { unchanged, stale }
+    }
+
+    /// Removes the documents of `paths`, and their chunks, from the index.
+    pub(super) async fn drop_documents(&self, paths: &[PathBuf]) -> Result<(), String> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        self.write_with_retry(|writer, _| {
+            for path in paths {
+                for term in self.document_terms(path) {
+                    writer.delete_term(term);
+                }
+            }
+            Ok(())
+        }).await?;
+        self.secret_findings.forget(paths);
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+//! Optional manifest of the files making up the on-disk index, so changes
+//! made behind the app's back (a sync tool, a bad USB stick, someone
+//! editing files) can be caught before search trusts them. Each file is
+//! hashed with BLAKE3 and the hashes are rolled up into one root hash,
+//! signed with a key kept beside the manifest. The signature catches edits
+//! to the manifest by anything that doesn't have the key; it isn't meant
+//! to stand up to someone who does.
+//!
+//! The manifest is brought up to date after every commit. Merges that
+//! finish after the last commit, or a crash, can leave it behind the index;
+//! that shows up as a mismatch too, and a rebuild settles it either way.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use super::IndexManager;
+
+/// Rewritten by tantivy on every commit and merge; the other files are
+/// never changed once written.
+const META_FILE: &str = "meta.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum IntegrityStatus {
+    /// Turned off in settings, or the index isn't on disk.
+    Disabled,
+    /// No manifest has been written yet.
+    NotRecorded,
+    Intact,
+    /// Index files differ from the manifest; the index should be rebuilt.
+    Mismatch,
+    /// The manifest itself was changed or its key is gone.
+    InvalidSignature,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct IntegrityReport {
+    pub status: IntegrityStatus,
+    /// Unix seconds the manifest was last written.
+    #[ts(type = "number | null")]
+    pub recorded_at: Option<u64>,
+    pub checked_files: usize,
+    /// Index files whose content no longer matches.
+    pub changed: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+impl IntegrityReport {
+    fn empty(status: IntegrityStatus) -> Self {
+        Self { status, recorded_at: None, checked_files: 0, changed: Vec::new(), missing: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ManifestEntry {
+    size: u64,
+    hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexManifest {
+    recorded_at: u64,
+    files: BTreeMap<String, ManifestEntry>,
+    root: String,
+    signature: String,
+}
+
+fn root_hash(files: &BTreeMap<String, ManifestEntry>) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    for (name, entry) in files {
+        hasher.update(name.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(entry.hash.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.finalize()
+}
+
+pub(super) fn hash_file(path: &Path) -> Result<String, String> {
+    let mut hasher = blake3::Hasher::new();
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to hash {}: {}", path.display(), e))?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Where the manifest and its key live, for an index in `index_dir`.
+pub(crate) struct IndexIntegrity {
+    index_dir: PathBuf,
+    manifest_path: PathBuf,
+    key_path: PathBuf,
+    // Last manifest written, so unchanged segment files aren't hashed again
+    last: parking_lot::Mutex<Option<IndexManifest>>,
+}
+
+impl IndexIntegrity {
+    pub(crate) fn new(app_data_dir: &Path, index_dir: &Path) -> Self {
+        Self {
+            index_dir: index_dir.to_path_buf(),
+            manifest_path: app_data_dir.join("index_manifest.json"),
+            key_path: app_data_dir.join("index_manifest.key"),
+            last: parking_lot::Mutex::new(None),
+        }
+    }
+
+    /// Index files by name; tantivy's lock and bookkeeping files start
+    /// with a dot and change on their own.
+    fn index_files(&self) -> Result<Vec<(String, u64)>, String> {
+        let entries = std::fs::read_dir(&self.index_dir)
+            .map_err(|e| format!("Failed to read index directory: {}", e))?;
+        let mut files = Vec::new();
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_file() && !name.starts_with('.') {
+                files.push((name, metadata.len()));
+            }
+        }
+        Ok(files)
+    }
+
+    fn key(&self, create: bool) -> Result<Option<[u8; 32]>, String> {
+        match std::fs::read_to_string(&self.key_path) {
+            Ok(hex) => blake3::Hash::from_hex(hex.trim())
+                .map(|key| Some(*key.as_bytes()))
+                .map_err(|e| format!("Failed to parse index manifest key: {}", e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && create => {
+                let mut key = [0u8; 32];
+                getrandom::getrandom(&mut key)
+                    .map_err(|e| format!("Failed to generate index manifest key: {}", e))?;
+                let mut options = std::fs::OpenOptions::new();
+                options.write(true).create_new(true);
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::OpenOptionsExt;
+                    options.mode(0o600);
+                }
+                let mut file = options.open(&self.key_path)
+                    .map_err(|e| format!("Failed to create index manifest key: {}", e))?;
+                std::io::Write::write_all(&mut file, blake3::Hash::from(key).to_hex().as_bytes())
+                    .map_err(|e| format!("Failed to write index manifest key: {}", e))?;
+                Ok(Some(key))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read index manifest key: {}", e)),
+        }
+    }
+
+    fn record(&self) -> Result<(), String> {
+        let key = self.key(true)?.ok_or("Failed to create index manifest key")?;
+        let mut last = self.last.lock();
+        let mut files = BTreeMap::new();
+        for (name, size) in self.index_files()? {
+            let known = last.as_ref()
+                .and_then(|manifest| manifest.files.get(&name))
+                .filter(|entry| name != META_FILE && entry.size == size);
+            let entry = match known {
+                Some(entry) => entry.clone(),
+                None => ManifestEntry { size, hash: hash_file(&self.index_dir.join(&name))? },
+            };
+            files.insert(name, entry);
+        }
+
+        let root = root_hash(&files);
+        let manifest = IndexManifest {
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            signature: blake3::keyed_hash(&key, root.as_bytes()).to_hex().to_string(),
+            root: root.to_hex().to_string(),
+            files,
+        };
+        let json = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| format!("Failed to serialize index manifest: {}", e))?;
+        let tmp = self.manifest_path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, &self.manifest_path))
+            .map_err(|e| format!("Failed to save index manifest: {}", e))?;
+        *last = Some(manifest);
+        Ok(())
+    }
+
+    fn verify(&self) -> Result<IntegrityReport, String> {
+        let manifest = match std::fs::read_to_string(&self.manifest_path) {
+            Ok(json) => serde_json::from_str::<IndexManifest>(&json).ok(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(IntegrityReport::empty(IntegrityStatus::NotRecorded));
+            }
+            Err(e) => return Err(format!("Failed to read index manifest: {}", e)),
+        };
+        let signed = |manifest: &IndexManifest| -> Result<bool, String> {
+            let Some(key) = self.key(false)? else {
+                return Ok(false);
+            };
+            let root = root_hash(&manifest.files);
+            // Comparing `blake3::Hash`es is constant-time
+            Ok(root.to_hex().as_str() == manifest.root
+                && blake3::Hash::from_hex(&manifest.signature).is_ok_and(|signature| signature == blake3::keyed_hash(&key, root.as_bytes())))
+        };
+        let manifest = match manifest {
+            Some(manifest) if signed(&manifest)? => manifest,
+            _ => return Ok(IntegrityReport::empty(IntegrityStatus::InvalidSignature)),
+        };
+
+        let mut report = IntegrityReport::empty(IntegrityStatus::Intact);
+        report.recorded_at = Some(manifest.recorded_at);
+        for (name, entry) in &manifest.files {
+            let path = self.index_dir.join(name);
+            if !path.exists() {
+                report.missing.push(name.clone());
+                continue;
+            }
+            report.checked_files += 1;
+            if hash_file(&path)? != entry.hash {
+                report.changed.push(name.clone());
+            }
+        }
+        if !report.changed.is_empty() || !report.missing.is_empty() {
+            report.status = IntegrityStatus::Mismatch;
+        }
+        *self.last.lock() = Some(manifest);
+        Ok(report)
+    }
+}
+
+impl IndexManager {
+    /// Brings the index manifest up to date, when turned on in settings.
+    pub fn record_index_manifest(&self) {
+        let Some(integrity) = self.integrity.as_ref() else {
+            return;
+        };
+        if !self.settings.get().index_integrity_manifest {
+            return;
+        }
+        if let Err(e) = integrity.record() {
+            warn!("Failed to record index manifest: {}", e);
+        }
+    }
+
+    /// Checks the index files against the manifest, rehashing all of them.
+    pub fn verify_index_integrity(&self) -> Result<IntegrityReport, String> {
+        let Some(integrity) = self.integrity.as_ref() else {
+            return Ok(IntegrityReport::empty(IntegrityStatus::Disabled));
+        };
+        if !self.settings.get().index_integrity_manifest {
+            return Ok(IntegrityReport::empty(IntegrityStatus::Disabled));
+        }
+        let report = integrity.verify()?;
+        match report.status {
+            IntegrityStatus::Mismatch => warn!(
+                "Index files differ from the manifest: {} changed, {} missing",
+                report.changed.len(),
+                report.missing.len()
+            ),
+            IntegrityStatus::InvalidSignature => warn!("Index manifest signature doesn't match"),
+            _ => info!("Index integrity: {:?}", report.status),
+        }
+        Ok(report)
+    }
+
+    /// Turns the manifest on or off; turning it on records the index as it
+    /// is now.
+    pub fn set_index_integrity(&self, enabled: bool) -> Result<IntegrityReport, String> {
+        self.settings.update(|settings| settings.index_integrity_manifest = enabled)?;
+        if enabled {
+            let integrity = self.integrity.as_ref().ok_or("The index isn't stored on disk")?;
+            integrity.record()?;
+        }
+        self.verify_index_integrity()
+    }
+}
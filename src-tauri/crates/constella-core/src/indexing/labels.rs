@@ -0,0 +1,44 @@
+//! Attaches image labels to documents when labeling is turned on.
+
+use std::path::Path;
+use log::warn;
+use crate::extract::photo;
+use crate::labeling::{heuristic_labels, is_image, normalize};
+use super::screenshots::is_screenshot;
+use super::IndexManager;
+
+impl IndexManager {
+    /// Labels for the image at `path`, from the classifier and from `text`
+    /// recognized in it. Empty for other files, when labeling is off or
+    /// when the power policy doesn't allow reading file contents.
+    pub(super) fn image_labels(&self, path: &Path, size: u64, text: Option<&str>) -> Vec<String> {
+        let settings = self.settings.get().image_labeling;
+        if !settings.enabled || !is_image(path) {
+            return Vec::new();
+        }
+        let mut labels = heuristic_labels(is_screenshot(path), text);
+
+        if self.classifier.is_available()
+            && size > 0
+            && size <= settings.max_file_size
+            && self.power.content_extraction_allowed()
+        {
+            match self.fs.read(path) {
+                Ok(image) => {
+                    // Classifiers can't read raw files, but can read their previews
+                    let image = if photo::is_raw(path) {
+                        photo::preview(&image).unwrap_or(&image)
+                    } else {
+                        &image
+                    };
+                    match self.classifier.classify(image) {
+                        Ok(classified) => labels.extend(classified),
+                        Err(e) => warn!("{}", e),
+                    }
+                }
+                Err(e) => warn!("Failed to read image {}: {}", path.display(), e),
+            }
+        }
+        normalize(labels)
+    }
+}
@@ -0,0 +1,262 @@
+//! Directory listings served from the index, so the app can browse indexed
+//! trees without touching the disk. Files come from their `parent` term;
+//! subdirectories are read off the term dictionary, since only files are
+//! indexed. Paths the index knows nothing about are read live instead.
+
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::path::{Path, MAIN_SEPARATOR};
+use std::time::UNIX_EPOCH;
+use serde::{Deserialize, Serialize};
+use tantivy::collector::DocSetCollector;
+use tantivy::query::TermQuery;
+use tantivy::schema::{IndexRecordOption, Term};
+use tantivy::{DocSet, Searcher, TERMINATED};
+use ts_rs::TS;
+use crate::file_system::os_path;
+use super::IndexManager;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum SortBy {
+    #[default]
+    Name,
+    Size,
+    Modified,
+    Type,
+}
+
+/// Directories always come before files; the order applies within each.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DirectorySort {
+    pub by: SortBy,
+    pub descending: bool,
+}
+
+/// Which children to list. Size and date filters only apply to files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DirectoryFilters {
+    /// Only files with one of these extensions, compared case-insensitively.
+    /// Setting it leaves subdirectories out.
+    pub extensions: Vec<String>,
+    #[ts(type = "number | null")]
+    pub min_size: Option<u64>,
+    #[ts(type = "number | null")]
+    pub max_size: Option<u64>,
+    /// Unix seconds.
+    #[ts(type = "number | null")]
+    pub modified_after: Option<u64>,
+    pub files_only: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DirectoryEntry {
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+    /// Unknown for directories listed from the index.
+    #[ts(type = "number | null")]
+    pub size: Option<u64>,
+    #[ts(type = "number | null")]
+    pub modified: Option<u64>,
+    /// Lowercased extension; empty for directories and files without one.
+    pub file_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DirectoryListing {
+    pub path: String,
+    pub entries: Vec<DirectoryEntry>,
+    /// False when the directory isn't indexed and was read from disk.
+    pub from_index: bool,
+}
+
+impl DirectoryFilters {
+    fn matches(&self, entry: &DirectoryEntry) -> bool {
+        if entry.is_dir {
+            return !self.files_only && self.extensions.is_empty();
+        }
+        if !self.extensions.is_empty()
+            && !self.extensions.iter()
+                .any(|extension| extension.trim_start_matches('.').eq_ignore_ascii_case(&entry.file_type))
+        {
+            return false;
+        }
+        let size = entry.size.unwrap_or_default();
+        let modified = entry.modified.unwrap_or_default();
+        self.min_size.map_or(true, |min| size >= min)
+            && self.max_size.map_or(true, |max| size <= max)
+            && self.modified_after.map_or(true, |after| modified > after)
+    }
+}
+
+impl DirectorySort {
+    fn compare(&self, a: &DirectoryEntry, b: &DirectoryEntry) -> Ordering {
+        let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        let order = match self.by {
+            SortBy::Name => by_name(),
+            SortBy::Size => a.size.cmp(&b.size).then_with(by_name),
+            SortBy::Modified => a.modified.cmp(&b.modified).then_with(by_name),
+            SortBy::Type => a.file_type.cmp(&b.file_type).then_with(by_name),
+        };
+        let order = if self.descending { order.reverse() } else { order };
+        b.is_dir.cmp(&a.is_dir).then(order)
+    }
+}
+
+impl IndexManager {
+    /// The children of `dir`, from the index when it has any and otherwise
+    /// from disk.
+    pub async fn list_directory(
+        &self,
+        dir: impl AsRef<Path>,
+        sort: DirectorySort,
+        filters: &DirectoryFilters,
+    ) -> Result<DirectoryListing, String> {
+        let dir = dir.as_ref();
+        let searcher = self.reader.searcher();
+        let mut entries = self.indexed_files_in(&searcher, dir)?;
+        entries.extend(self.indexed_subdirectories_of(&searcher, dir)?);
+
+        let from_index = !entries.is_empty();
+        if !from_index {
+            entries = self.read_directory_live(dir)?;
+        }
+        entries.retain(|entry| filters.matches(entry));
+        entries.sort_by(|a, b| sort.compare(a, b));
+
+        Ok(DirectoryListing {
+            path: os_path::encode(dir),
+            entries,
+            from_index,
+        })
+    }
+
+    fn indexed_files_in(&self, searcher: &Searcher, dir: &Path) -> Result<Vec<DirectoryEntry>, String> {
+        let term = Term::from_field_text(self.parent_field, &self.stored_path(dir));
+        let addresses = searcher.search(&TermQuery::new(term, IndexRecordOption::Basic), &DocSetCollector)
+            .map_err(|e| format!("Failed to list {}: {}", dir.display(), e))?;
+
+        let mut entries = Vec::with_capacity(addresses.len());
+        for doc_address in addresses {
+            let retrieved_doc = searcher.doc(doc_address)
+                .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+            let Some(path) = self.doc_path(&retrieved_doc) else {
+                continue;
+            };
+            let metadata = self.document_metadata(&path, &retrieved_doc);
+            entries.push(DirectoryEntry {
+                file_type: file_type(Path::new(&path)),
+                path: metadata.path,
+                name: metadata.name,
+                is_dir: false,
+                size: Some(metadata.size),
+                modified: Some(metadata.modified),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Subdirectories holding indexed files at any depth, found by scanning
+    /// the `parent` terms that start with `dir`.
+    fn indexed_subdirectories_of(&self, searcher: &Searcher, dir: &Path) -> Result<Vec<DirectoryEntry>, String> {
+        let (prefix, end) = descendant_bounds(Path::new(&self.stored_path(dir)));
+
+        let mut names = BTreeSet::new();
+        for segment_reader in searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(self.parent_field)
+                .map_err(|e| format!("Failed to open directory terms: {}", e))?;
+            let mut terms = inverted_index.terms().range()
+                .ge(prefix.as_bytes())
+                .lt(end.as_bytes())
+                .into_stream()
+                .map_err(|e| format!("Failed to read directory terms: {}", e))?;
+            while terms.advance() {
+                let Ok(parent) = std::str::from_utf8(terms.key()) else {
+                    continue;
+                };
+                let Some(child) = parent[prefix.len()..].split(MAIN_SEPARATOR).next() else {
+                    continue;
+                };
+                if child.is_empty() || names.contains(child) {
+                    continue;
+                }
+                // Deleted documents keep their terms until a merge
+                let mut postings = inverted_index.read_postings_from_terminfo(terms.value(), IndexRecordOption::Basic)
+                    .map_err(|e| format!("Failed to read directory postings: {}", e))?;
+                let alive = segment_reader.alive_bitset();
+                let mut has_live_doc = false;
+                while postings.doc() != TERMINATED {
+                    if alive.map_or(true, |alive| alive.is_alive(postings.doc())) {
+                        has_live_doc = true;
+                        break;
+                    }
+                    postings.advance();
+                }
+                if has_live_doc {
+                    names.insert(child.to_string());
+                }
+            }
+        }
+
+        Ok(names.into_iter()
+            .map(|name| DirectoryEntry {
+                path: os_path::encode(&dir.join(&name)),
+                name,
+                is_dir: true,
+                size: None,
+                modified: None,
+                file_type: String::new(),
+            })
+            .collect())
+    }
+
+    fn read_directory_live(&self, dir: &Path) -> Result<Vec<DirectoryEntry>, String> {
+        let children = self.fs.read_dir(dir)
+            .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+        Ok(children.into_iter()
+            .filter_map(|path| {
+                // Skip children that vanish or can't be read; the rest still list
+                let metadata = self.fs.metadata(&path).ok()?;
+                let modified = metadata.modified
+                    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                    .map(|modified| modified.as_secs());
+                Some(DirectoryEntry {
+                    name: path.file_name()?.to_string_lossy().to_string(),
+                    file_type: if metadata.is_dir { String::new() } else { file_type(&path) },
+                    path: os_path::encode(&path),
+                    is_dir: metadata.is_dir,
+                    size: (!metadata.is_dir).then_some(metadata.len),
+                    modified,
+                })
+            })
+            .collect())
+    }
+}
+
+/// The range of `parent` values below `dir`: everything from `dir/`
+/// (inclusive) up to the first key past that prefix (exclusive).
+pub(super) fn descendant_bounds(dir: &Path) -> (String, String) {
+    let mut prefix = os_path::encode(dir);
+    if !prefix.ends_with(MAIN_SEPARATOR) {
+        prefix.push(MAIN_SEPARATOR);
+    }
+    // The separator is ASCII, so swapping it for the next character ends the range
+    let mut end = prefix.clone();
+    end.pop();
+    end.push((MAIN_SEPARATOR as u8 + 1) as char);
+    (prefix, end)
+}
+
+fn file_type(path: &Path) -> String {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
@@ -0,0 +1,123 @@
+//! Metadata lookups for known paths, straight from the index without
+//! running a search.
+
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use tantivy::collector::TopDocs;
+use tantivy::query::TermQuery;
+use tantivy::schema::IndexRecordOption;
+use tantivy::{Document, Term};
+use ts_rs::TS;
+use crate::actions::ResultKind;
+use crate::extract::photo::PhotoMetadata;
+use crate::file_system::os_path;
+use super::IndexManager;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DocumentMetadata {
+    /// The same id search results carry.
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    #[ts(type = "number")]
+    pub size: u64,
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub modified: u64,
+    /// Date, camera and dimensions, for photos that record them.
+    pub photo: Option<PhotoMetadata>,
+}
+
+/// Opaque id of the result stored under `stored_path`. It stays the same
+/// across searches, reindexing and remapped folders, and changes only when
+/// the file moves.
+pub(crate) fn result_id(stored_path: &str) -> String {
+    blake3::hash(stored_path.as_bytes()).to_hex()[..16].to_string()
+}
+
+impl IndexManager {
+    /// What kind of result `path` is, for the actions offered on it.
+    pub fn result_kind(&self, path: &Path) -> Result<ResultKind, String> {
+        let metadata = self.fs.metadata(path)
+            .map_err(|e| format!("Failed to get metadata for {}: {}", path.display(), e))?;
+        Ok(ResultKind::of(path, metadata.is_dir))
+    }
+
+    /// Indexed metadata for each of `paths`, in the same order, with `None`
+    /// for paths that aren't in the index.
+    pub async fn get_documents(&self, paths: &[String]) -> Result<Vec<Option<DocumentMetadata>>, String> {
+        let searcher = self.reader.searcher();
+        paths.iter()
+            .map(|path| {
+                let query = TermQuery::new(self.path_term(Path::new(path)), IndexRecordOption::Basic);
+                let top_docs = searcher.search(&query, &TopDocs::with_limit(1))
+                    .map_err(|e| format!("Failed to look up {}: {}", path, e))?;
+                let Some((_, doc_address)) = top_docs.into_iter().next() else {
+                    return Ok(None);
+                };
+                let retrieved_doc = searcher.doc(doc_address)
+                    .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+                Ok(Some(self.document_metadata(path, &retrieved_doc)))
+            })
+            .collect()
+    }
+
+    /// What `path` is tagged with: the labels recognized in it, if it's an
+    /// indexed photo, and the collections it is in.
+    pub fn file_tags(&self, path: &Path) -> Result<Vec<String>, String> {
+        let searcher = self.reader.searcher();
+        let query = TermQuery::new(self.path_term(path), IndexRecordOption::Basic);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(1))
+            .map_err(|e| format!("Failed to look up {}: {}", path.display(), e))?;
+        let mut tags = Vec::new();
+        if let Some((_, doc_address)) = top_docs.into_iter().next() {
+            let retrieved_doc = searcher.doc(doc_address)
+                .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+            tags.extend(retrieved_doc.get_all(self.labels_field)
+                .filter_map(|f| f.as_text())
+                .map(str::to_string));
+        }
+        tags.extend(self.collections_containing(path));
+        Ok(tags)
+    }
+
+    pub(crate) fn document_metadata(&self, path: &str, retrieved_doc: &Document) -> DocumentMetadata {
+        DocumentMetadata {
+            id: result_id(retrieved_doc.get_first(self.path_field).and_then(|f| f.as_text()).unwrap_or(path)),
+            path: path.to_string(),
+            name: retrieved_doc.get_first(self.name_field)
+                .and_then(|f| f.as_text())
+                .or_else(|| Path::new(path).file_name().and_then(|n| n.to_str()))
+                .unwrap_or_default()
+                .to_string(),
+            size: retrieved_doc.get_first(self.size_field)
+                .and_then(|f| f.as_u64())
+                .unwrap_or_default(),
+            modified: retrieved_doc.get_first(self.modified_field)
+                .and_then(|f| f.as_u64())
+                .unwrap_or_default(),
+            photo: self.stored_photo(retrieved_doc),
+        }
+    }
+
+    /// The current path of each result in `ids`, failing on ids that
+    /// aren't in the index (anymore).
+    pub fn resolve_ids(&self, ids: &[String]) -> Result<Vec<PathBuf>, String> {
+        let searcher = self.reader.searcher();
+        ids.iter()
+            .map(|id| {
+                let query = TermQuery::new(Term::from_field_text(self.doc_id_field, id), IndexRecordOption::Basic);
+                let top_docs = searcher.search(&query, &TopDocs::with_limit(1))
+                    .map_err(|e| format!("Failed to look up result {}: {}", id, e))?;
+                let (_, doc_address) = top_docs.into_iter().next()
+                    .ok_or_else(|| format!("No result with id {}", id))?;
+                let retrieved_doc = searcher.doc(doc_address)
+                    .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+                self.doc_path(&retrieved_doc)
+                    .map(|path| os_path::decode(&path))
+                    .ok_or_else(|| "Document missing path field".to_string())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,265 @@
+//! Searches for media browsers: images and videos only, each with what a
+//! grid of thumbnails needs in one call. Thumbnails are only ever handed
+//! out by searches, never made; raw and HEIC photos get theirs from the
+//! preview cache once `photo_preview` has made them, and videos once
+//! `video_preview` has made their keyframe strip.
+
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tantivy::collector::Count;
+use tantivy::query::{AllQuery, BooleanQuery, ConstScoreQuery, Query, TermQuery};
+use tantivy::schema::{Facet, IndexRecordOption, Term};
+use ts_rs::TS;
+use crate::extract::{media, photo, subtitles};
+use crate::file_system::os_path;
+use crate::search::{type_facet, type_filter_facet, SearchOptions};
+use super::cursor::MAX_PAGE_SIZE;
+use super::{lookup, IndexManager, PreparedQuery};
+
+const DEFAULT_MEDIA_PAGE_SIZE: usize = 100;
+
+/// Images the webview shows as they are, so they are their own thumbnail.
+const VIEWABLE_IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp", "svg", "avif", "ico"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum MediaKind {
+    Image,
+    Video,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct MediaSearchOptions {
+    /// Only these kinds; both when empty.
+    pub kinds: Vec<MediaKind>,
+    /// Only media below this folder.
+    pub root: Option<PathBuf>,
+    /// Only media in this collection, and below its folders.
+    pub collection: Option<String>,
+    /// Skip this many hits, for the pages after the first.
+    pub offset: usize,
+    /// Hits per page; 100 when unset, and at most 1000.
+    #[ts(type = "number | null")]
+    pub limit: Option<usize>,
+    /// Page through the index as it was at this `MediaPage::snapshot`.
+    #[ts(type = "number | null")]
+    pub snapshot: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct MediaItem {
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    pub kind: MediaKind,
+    #[ts(type = "number")]
+    pub size: u64,
+    #[ts(type = "number")]
+    pub modified: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// When a photo was taken, in Unix seconds.
+    #[ts(type = "number | null")]
+    pub taken: Option<u64>,
+    /// Running time of a video, in milliseconds.
+    #[ts(type = "number | null")]
+    pub duration_ms: Option<u64>,
+    /// An image file the webview can show for this item: the file itself,
+    /// or its cached preview, a keyframe strip for videos. `None` until a
+    /// raw or HEIC photo or a video has been previewed.
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct MediaPage {
+    pub items: Vec<MediaItem>,
+    /// Media matching the search in the snapshot, across all pages.
+    pub total: usize,
+    /// Offset of the next page; `None` on the last.
+    pub next_offset: Option<usize>,
+    /// Generation of the index snapshot the page came from.
+    #[ts(type = "number")]
+    pub snapshot: u64,
+}
+
+impl MediaKind {
+    fn of(path: &Path) -> Option<Self> {
+        if photo::is_photo(path) {
+            return Some(Self::Image);
+        }
+        if subtitles::is_video(path) {
+            return Some(Self::Video);
+        }
+        match type_facet(path).to_path().get(1) {
+            Some(&"image") => Some(Self::Image),
+            Some(&"video") => Some(Self::Video),
+            _ => None,
+        }
+    }
+
+    /// The `type` facets files of this kind sit under, including the
+    /// photo and video formats with no such MIME type.
+    fn facets(self) -> Vec<Facet> {
+        let (category, extensions) = match self {
+            Self::Image => ("image", photo::PHOTO_EXTENSIONS),
+            Self::Video => ("video", subtitles::VIDEO_EXTENSIONS),
+        };
+        let mut facets = vec![Facet::from_path(["type", category])];
+        facets.extend(extensions.iter().map(|extension| type_filter_facet(extension)));
+        facets
+    }
+}
+
+impl IndexManager {
+    /// Running time of the video or audio file at `path`, when the power
+    /// policy allows reading it.
+    pub(super) fn media_duration(&self, path: &Path, size: u64) -> Option<u64> {
+        if !media::has_duration(path) || size == 0 || !self.power.content_extraction_allowed() {
+            return None;
+        }
+        // Only box headers are read, however long the video
+        match self.fs.map(path) {
+            Ok(bytes) => media::duration_ms(&bytes),
+            Err(e) => {
+                warn!("Failed to read {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// A JPEG strip of keyframes of the video at `path`, from the preview
+    /// cache when the video, or a copy of it, was previewed before. `None`
+    /// for other files, for videos without frames to show, and without a
+    /// keyframe extractor.
+    pub async fn video_preview(&self, path: impl AsRef<Path>) -> Result<Option<PathBuf>, String> {
+        let path = path.as_ref();
+        let Some(keyframes) = self.keyframes.as_ref().filter(|_| subtitles::is_video(path)) else {
+            return Ok(None);
+        };
+        let metadata = self.fs.metadata(path)
+            .map_err(|e| format!("Failed to get metadata for {}: {}", path.display(), e))?;
+        let modified = metadata.modified
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |modified| modified.as_secs());
+        let source = path.to_string_lossy();
+        if let Some(cached) = self.previews.lookup(&source, metadata.len, modified) {
+            return Ok(Some(cached));
+        }
+
+        let bytes = self.fs.map(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let hash = blake3::hash(&bytes).to_hex().to_string();
+        let cached = self.previews.path_for(&hash)?;
+        if !cached.exists() {
+            let duration = media::has_duration(path).then(|| media::duration_ms(&bytes)).flatten();
+            let Some(strip) = keyframes.keyframe_strip(path, duration)? else {
+                return Ok(None);
+            };
+            std::fs::write(&cached, strip)
+                .map_err(|e| format!("Failed to write preview: {}", e))?;
+        }
+        self.previews.record(&source, metadata.len, modified, &hash, self.settings.get().preview_cache_max_bytes);
+        Ok(Some(cached))
+    }
+
+    /// Images and videos matching `query`, a page at a time. An empty
+    /// query lists all of them, most recently modified first.
+    pub async fn search_media(&self, query: &str, options: &MediaSearchOptions) -> Result<MediaPage, String> {
+        let (searcher, snapshot) = self.snapshot_searcher(options.snapshot)?;
+        let search_options = SearchOptions {
+            root: options.root.clone(),
+            collection: options.collection.clone(),
+            snapshot: Some(snapshot),
+            ..SearchOptions::default()
+        };
+        let PreparedQuery { query: text_query, file_filter } = self.prepare_query(&searcher, query, &search_options)?;
+        let text_query: Box<dyn Query> = if query.trim().is_empty() && file_filter.is_none() {
+            Box::new(AllQuery)
+        } else {
+            text_query
+        };
+
+        let kinds = if options.kinds.is_empty() {
+            vec![MediaKind::Image, MediaKind::Video]
+        } else {
+            options.kinds.clone()
+        };
+        let media: Vec<Box<dyn Query>> = kinds.iter()
+            .flat_map(|kind| kind.facets())
+            .map(|facet| -> Box<dyn Query> {
+                Box::new(TermQuery::new(Term::from_facet(self.type_field, &facet), IndexRecordOption::Basic))
+            })
+            .collect();
+        // Chunks carry no type, so only whole files are left
+        let query = BooleanQuery::intersection(vec![
+            text_query,
+            Box::new(ConstScoreQuery::new(Box::new(BooleanQuery::union(media)), 0.0)),
+        ]);
+
+        let total = searcher.search(&query, &Count)
+            .map_err(|e| format!("Failed to count media: {}", e))?;
+        let limit = options.limit.unwrap_or(DEFAULT_MEDIA_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let top_docs = self.ranked_top_docs(&searcher, &query, options.offset + limit)?;
+        let mut items = Vec::with_capacity(limit);
+        for (_, doc_address) in top_docs.into_iter().skip(options.offset) {
+            let retrieved_doc = searcher.doc(doc_address)
+                .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+            let Some(path) = self.doc_path(&retrieved_doc) else {
+                continue;
+            };
+            let decoded = os_path::decode(&path);
+            let Some(kind) = kinds.iter().copied().find(|kind| MediaKind::of(&decoded) == Some(*kind)) else {
+                continue;
+            };
+            let stored = retrieved_doc.get_first(self.path_field).and_then(|f| f.as_text()).unwrap_or(&path);
+            let size = retrieved_doc.get_first(self.size_field).and_then(|f| f.as_u64()).unwrap_or_default();
+            let modified = retrieved_doc.get_first(self.modified_field).and_then(|f| f.as_u64()).unwrap_or_default();
+            let photo = self.stored_photo(&retrieved_doc).unwrap_or_default();
+            items.push(MediaItem {
+                id: lookup::result_id(stored),
+                name: retrieved_doc.get_first(self.name_field)
+                    .and_then(|f| f.as_text())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| decoded.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()),
+                kind,
+                size,
+                modified,
+                width: photo.width,
+                height: photo.height,
+                taken: photo.taken,
+                duration_ms: retrieved_doc.get_first(self.duration_field).and_then(|f| f.as_u64()),
+                thumbnail: self.thumbnail(&decoded, &path, size, modified),
+                path,
+            });
+        }
+
+        let end = options.offset + limit;
+        Ok(MediaPage {
+            items,
+            total,
+            next_offset: (end < total).then_some(end),
+            snapshot,
+        })
+    }
+
+    /// What the webview can show for the image or video at `path` without
+    /// making anything.
+    fn thumbnail(&self, path: &Path, encoded: &str, size: u64, modified: u64) -> Option<String> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        if VIEWABLE_IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            return Some(encoded.to_string());
+        }
+        if !photo::is_raw(path) && !photo::is_heif(path) && !subtitles::is_video(path) {
+            return None;
+        }
+        self.previews.lookup(&path.to_string_lossy(), size, modified)
+            .map(|preview| os_path::encode(&preview))
+    }
+}
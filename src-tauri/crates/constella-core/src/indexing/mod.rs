@@ -0,0 +1,2302 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::fs;
+use parking_lot::RwLock;
+use tokio::runtime::RuntimeFlavor;
+use tokio::sync::{watch, Mutex};
+use log::{info, error, warn};
+use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, schema::*, Document, DocAddress, DocId, DocSet, Score, Searcher, SegmentReader, TERMINATED};
+use tantivy::postings::Postings;
+use tantivy::query::{AllQuery, BooleanQuery, DisjunctionMaxQuery, Query, QueryParser, RangeQuery, TermQuery};
+use tantivy::SnippetGenerator;
+use tantivy::tokenizer::TokenizerManager;
+use tantivy::collector::{DocSetCollector, FacetCollector, TopDocs};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use crate::watcher::ChangeType;
+use crate::tracking::ChangeTracker;
+use crate::tracking::load::LoadMonitor;
+use crate::power::PowerMonitor;
+use crate::settings::SettingsManager;
+use crate::search::{type_facet, type_filter_facet, MatchedTerm, RankingWeights, ResultFields, ScoreExplanation, SearchFacets, SearchOptions, SearchResponse, FILE_CATEGORIES};
+use crate::search::analytics::{query_terms, ZeroResultCause, ZeroResultLog, ZeroResultQuery};
+use crate::search::boosts::BoostMatcher;
+use crate::search::filters::{extract_filters, DepthFilter, FilterContext};
+use crate::search::learning::ClickLearning;
+use crate::search::noise::{content_analyzer, tokenize, CONTENT_TOKENIZER};
+use crate::search::rewrite::rewrite_query;
+use crate::secrets::SecretFindings;
+use crate::file_system::{os_path, FileSystemProvider, OsFileSystem};
+use crate::scanner::{FileScanner, PathExclusions};
+use crate::tracking::diff::{FileDiffReport, SnapshotStore};
+use std::time::{Duration, UNIX_EPOCH, SystemTime};
+use std::panic::AssertUnwindSafe;
+use crate::chaos::{self, Fault};
+use crate::extract::{config, sqlite, Extracted, Format};
+use crate::extract::media::{FfmpegKeyframes, KeyframeExtractor};
+use crate::extract::subtitles::{self, Cue, FfmpegSubtitles, SubtitleTrackReader};
+use incremental::RunPlan;
+use priority::{PathQueue, PriorityCompletion};
+use screenshots::{is_screenshot, MAX_SCREENSHOT_OCR_SIZE, SCREENSHOT_KIND};
+use shedding::SheddingMeasure;
+use upgrade::IndexOrigin;
+use crate::labeling::{CommandClassifier, ImageClassifier};
+use crate::ocr::TextRecognizer;
+use crate::transcription::{Transcriber, WhisperCpp};
+use changelog::{ChangeKind, RecentChange, RecentChanges};
+use databases::DATABASE_KIND;
+use chunks::ChunkLocation;
+use serde_json;
+use serde::Serialize;
+use ts_rs::TS;
+
+pub mod binaries;
+pub mod byte_search;
+pub mod changelog;
+pub mod chunks;
+pub mod collections;
+pub mod coverage;
+pub mod cursor;
+pub mod databases;
+pub mod duplicates;
+pub mod federation;
+pub mod health;
+pub mod incremental;
+pub mod integrity;
+pub mod labels;
+pub mod listing;
+pub mod lookup;
+pub mod media;
+pub mod moves;
+pub mod music;
+pub mod overlay;
+pub mod organize;
+pub mod path_info;
+pub mod paths;
+pub mod photos;
+pub mod pinned;
+pub mod pii;
+pub mod preview;
+pub mod preview_cache;
+pub mod priority;
+pub mod profiles;
+pub mod reconcile;
+pub mod relocate;
+pub mod repos;
+pub mod screenshots;
+pub mod secrets;
+pub mod shards;
+pub mod shedding;
+pub mod transcription;
+pub mod transcripts;
+pub mod triage;
+pub mod upgrade;
+pub mod volumes;
+#[cfg(feature = "ram-index")]
+pub mod scratch;
+
+const INDEX_BUFFER_SIZE: usize = 100_000_000; // 100MB buffer for better performance
+const COMMIT_BATCH_SIZE: usize = 10_000; // Larger batches for better throughput
+// Longest a full index run keeps documents uncommitted, so searches see partial results
+const PARTIAL_COMMIT_INTERVAL: Duration = Duration::from_secs(2);
+// Used instead while shedding load on a very large index
+const SLOW_COMMIT_BATCH_SIZE: usize = 100_000;
+const SLOW_COMMIT_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_RETRY_ATTEMPTS: usize = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const SEARCH_RESULT_LIMIT: usize = 100;
+// Extra candidates fetched when file type boosts may reorder or hide results
+const BOOSTED_CANDIDATE_FACTOR: usize = 4;
+// Matching subtitle lines listed with a snippet
+const MAX_TRANSCRIPT_LINES: usize = 5;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum IndexState {
+    Idle,
+    Scanning,
+    Indexing,
+    Paused,
+    Completed,
+    Cancelled,
+    Error(String),
+}
+
+/// Where the index data lives.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexBacking {
+    /// `search_index` under the app data directory, or the configured
+    /// `index_location`.
+    #[default]
+    Disk,
+    /// Held in memory and gone when the manager is dropped.
+    #[cfg(feature = "ram-index")]
+    Ram,
+}
+
+pub struct IndexOptions {
+    pub backing: IndexBacking,
+    pub fs: Arc<dyn FileSystemProvider>,
+    /// Reads the text of screenshots; without one they're indexed by name.
+    pub ocr: Option<Arc<dyn TextRecognizer>>,
+    /// Reads subtitle streams embedded in videos, when turned on in settings.
+    pub subtitles: Option<Arc<dyn SubtitleTrackReader>>,
+    /// Makes the keyframe strips video previews show; without one videos
+    /// have no preview.
+    pub keyframes: Option<Arc<dyn KeyframeExtractor>>,
+    /// Transcribes audio once turned on in settings; whisper.cpp as
+    /// configured there when unset.
+    pub transcriber: Option<Arc<dyn Transcriber>>,
+    /// Labels images once turned on in settings; the program configured
+    /// there when unset.
+    pub classifier: Option<Arc<dyn ImageClassifier>>,
+    /// In portable mode, the volume the app runs from; paths on it are
+    /// stored relative to it.
+    pub portable_root: Option<PathBuf>,
+    /// Set on a shard of a hash-sharded index: only its share of the files
+    /// is indexed by full runs.
+    pub shard: Option<shards::ShardSlot>,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        Self {
+            backing: IndexBacking::default(),
+            fs: Arc::new(OsFileSystem),
+            ocr: None,
+            subtitles: Some(Arc::new(FfmpegSubtitles)),
+            keyframes: Some(Arc::new(FfmpegKeyframes)),
+            transcriber: None,
+            classifier: None,
+            portable_root: None,
+            shard: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexerState {
+    pub total_files: usize,
+    pub processed_files: usize,
+    pub current_file: String,
+    pub state: String,
+    pub files_per_second: f32,
+    pub elapsed_seconds: u64,
+    pub start_time: SystemTime,
+    /// Files under "index first" folders in this run, and how many are done.
+    pub priority_total: usize,
+    pub priority_processed: usize,
+    /// Files of this run left alone because they hadn't changed; counted
+    /// in `processed_files` too.
+    pub skipped_files: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexedFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: u64,
+}
+
+/// Outcome of applying one batch of incremental changes to the index.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateSummary {
+    pub indexed: usize,
+    pub skipped: usize,
+    pub removed: usize,
+    /// Files deleted in one place and created in another, carried over
+    /// rather than indexed afresh.
+    pub moved: usize,
+}
+
+/// A search ready to run against the index.
+pub(crate) struct PreparedQuery {
+    pub query: Box<dyn Query>,
+    /// What the file of a matching chunk must match too, when the search has
+    /// filters; chunks don't carry the fields they would apply to.
+    pub file_filter: Option<Box<dyn Query>>,
+}
+
+pub struct IndexManager {
+    index: Index,
+    // Shared by every search; reloaded after each commit
+    reader: IndexReader,
+    writer: Arc<Mutex<Option<IndexWriter>>>,
+    state: Arc<RwLock<IndexerState>>,
+    path_field: Field,
+    path_exact_field: Field,
+    doc_id_field: Field,
+    parent_field: Field,
+    name_field: Field,
+    // What sort of file a document is, e.g. "screenshot"
+    kind_field: Field,
+    // Domain a download came from, e.g. "github.com"
+    source_field: Field,
+    // Lowercased name of the git repository a file is in, for `repo:` filters
+    repo_field: Field,
+    repo_root_field: Field,
+    labels_field: Field,
+    type_field: Field,
+    content_field: Field,
+    // Section headings of markdown files and notebooks, as `Parent > Child` paths
+    headings_field: Field,
+    modified_field: Field,
+    // Creation time, falling back to the modified time where unknown
+    created_field: Field,
+    size_field: Field,
+    // Number of components in the path, for `depth:` filters
+    depth_field: Field,
+    // When a photo was taken, from its EXIF data, for `taken:` filters
+    taken_field: Field,
+    // Camera make and model, searchable with `camera:`
+    camera_field: Field,
+    width_field: Field,
+    height_field: Field,
+    duration_field: Field,
+    // Tags of music files, searchable with `artist:`, `album:` and so on
+    title_field: Field,
+    artist_field: Field,
+    album_artist_field: Field,
+    album_field: Field,
+    genre_field: Field,
+    track_field: Field,
+    // Font family, product or package name of fonts, executables and installers
+    product_field: Field,
+    company_field: Field,
+    // Their version, e.g. "2.3.1", matched exactly with `version:`
+    version_field: Field,
+    // Set on the chunks of a large file, to the file's path
+    chunk_of_field: Field,
+    chunk_field: Field,
+    chunk_offset_field: Field,
+    chunk_length_field: Field,
+    // Lowercased table and column names of databases, for `table:` and `column:`
+    table_field: Field,
+    column_field: Field,
+    // Configuration keys and their trailing parts, lowercased, for `key:`
+    key_field: Field,
+    last_update: Arc<RwLock<Option<UpdateSummary>>>,
+    snapshots: Arc<SnapshotStore>,
+    // Cached JPEGs of photos the webview can't show, see `photo_preview`
+    previews: preview_cache::PreviewCache,
+    learning: ClickLearning,
+    // Hand-picked sets of results, see `add_to_collection`
+    collections: collections::CollectionStore,
+    zero_results: ZeroResultLog,
+    recent_changes: RecentChanges,
+    // Files found to contain likely credentials, kept apart from the index
+    secret_findings: SecretFindings,
+    paths: paths::PathMap,
+    // Score and recommendations from the last health check
+    health: health::HealthMonitor,
+    // Hash manifest of the index files; `None` for in-memory indexes
+    integrity: Option<integrity::IndexIntegrity>,
+    data_dir: PathBuf,
+    // `None` for in-memory indexes
+    index_dir: Option<PathBuf>,
+    // Fingerprint of the settings the indexed documents were built with
+    built_with: parking_lot::Mutex<Option<String>>,
+    tracker: Arc<ChangeTracker>,
+    load_monitor: Arc<LoadMonitor>,
+    power: Arc<PowerMonitor>,
+    settings: Arc<SettingsManager>,
+    // Set while another process holds the index writer
+    writes_suspended: AtomicBool,
+    pending_changes: parking_lot::Mutex<Vec<(PathBuf, ChangeType)>>,
+    overlay: overlay::MutationOverlay,
+    fs: Arc<dyn FileSystemProvider>,
+    ocr: Option<Arc<dyn TextRecognizer>>,
+    subtitles: Option<Arc<dyn SubtitleTrackReader>>,
+    keyframes: Option<Arc<dyn KeyframeExtractor>>,
+    transcriber: Arc<dyn Transcriber>,
+    classifier: Arc<dyn ImageClassifier>,
+    transcription: transcription::TranscriptionQueue,
+    progress: watch::Sender<IndexerState>,
+    paused: AtomicBool,
+    cancel_requested: AtomicBool,
+    reconciliation: reconcile::Reconciliation,
+    // Bumped whenever the "index first" folders change mid-run
+    priority_generation: AtomicU64,
+    priority_complete: watch::Sender<Option<PriorityCompletion>>,
+    cursors: cursor::SearchCursors,
+    content_hashes: duplicates::ContentHashes,
+    repositories: repos::Repositories,
+    shard: Option<shards::ShardSlot>,
+    pinned: pinned::PinnedSnapshots,
+}
+
+impl IndexManager {
+    pub fn new(app_data_dir: &Path, settings: Arc<SettingsManager>) -> Result<Self, String> {
+        Self::with_options(app_data_dir, settings, IndexOptions::default())
+    }
+
+    /// Like `new`, but reads the indexed tree through `fs` instead of the OS.
+    pub fn with_file_system(
+        app_data_dir: &Path,
+        settings: Arc<SettingsManager>,
+        fs: Arc<dyn FileSystemProvider>,
+    ) -> Result<Self, String> {
+        Self::with_options(app_data_dir, settings, IndexOptions { fs, ..IndexOptions::default() })
+    }
+
+    pub fn with_options(
+        app_data_dir: &Path,
+        settings: Arc<SettingsManager>,
+        options: IndexOptions,
+    ) -> Result<Self, String> {
+        info!("Creating new IndexManager instance ({:?} backed)", options.backing);
+        let fs = options.fs;
+        let ocr = options.ocr;
+        let subtitles = options.subtitles;
+        let keyframes = options.keyframes;
+        let transcriber = options.transcriber
+            .unwrap_or_else(|| Arc::new(WhisperCpp::new(settings.clone())));
+        let classifier = options.classifier
+            .unwrap_or_else(|| Arc::new(CommandClassifier::new(settings.clone())));
+        let mut schema_builder = Schema::builder();
+
+        let path_field = schema_builder.add_text_field("path", TEXT | STORED);
+        // Untokenized copy of the path so individual documents can be replaced or deleted
+        let path_exact_field = schema_builder.add_text_field("path_exact", STRING);
+        // Stable result id, a hash of the stored path, to look results up by
+        let doc_id_field = schema_builder.add_text_field("doc_id", STRING);
+        // Containing directory, for listing a directory's children
+        let parent_field = schema_builder.add_text_field("parent", STRING);
+        let name_field = schema_builder.add_text_field("name", TEXT | STORED);
+        let kind_field = schema_builder.add_text_field("kind", STRING | STORED);
+        let source_field = schema_builder.add_text_field("source", STRING | STORED);
+        let repo_field = schema_builder.add_text_field("repo", STRING);
+        let repo_root_field = schema_builder.add_text_field("repo_root", STORED);
+        // Coarse image labels, for `label:` filters
+        let labels_field = schema_builder.add_text_field("labels", STRING | STORED);
+        // File type as `/type/<category>/<extension>`, for counts and `type:` filters
+        let type_field = schema_builder.add_facet_field("type", FacetOptions::default());
+        // Text content of small text files, searchable but not stored
+        let content_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(CONTENT_TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        );
+        let content_field = schema_builder.add_text_field("content", content_options);
+        let headings_field = schema_builder.add_text_field("headings", TEXT);
+        let modified_field = schema_builder.add_u64_field("modified", STORED | FAST);
+        let created_field = schema_builder.add_u64_field("created", STORED | FAST);
+        let size_field = schema_builder.add_u64_field("size", STORED | FAST);
+        let depth_field = schema_builder.add_u64_field("depth", FAST);
+        let taken_field = schema_builder.add_u64_field("taken", STORED | FAST);
+        let camera_field = schema_builder.add_text_field("camera", TEXT | STORED);
+        let width_field = schema_builder.add_u64_field("width", STORED);
+        let height_field = schema_builder.add_u64_field("height", STORED);
+        // Running time of videos and audio, in milliseconds
+        let duration_field = schema_builder.add_u64_field("duration", STORED);
+        let title_field = schema_builder.add_text_field("title", TEXT | STORED);
+        let artist_field = schema_builder.add_text_field("artist", TEXT | STORED);
+        let album_artist_field = schema_builder.add_text_field("album_artist", TEXT | STORED);
+        let album_field = schema_builder.add_text_field("album", TEXT | STORED);
+        let genre_field = schema_builder.add_text_field("genre", TEXT | STORED);
+        let track_field = schema_builder.add_u64_field("track", STORED);
+        let product_field = schema_builder.add_text_field("product", TEXT | STORED);
+        let company_field = schema_builder.add_text_field("company", TEXT | STORED);
+        let version_field = schema_builder.add_text_field("version", STRING | STORED);
+        let chunk_of_field = schema_builder.add_text_field("chunk_of", STRING | STORED);
+        let chunk_field = schema_builder.add_u64_field("chunk", STORED);
+        let chunk_offset_field = schema_builder.add_u64_field("chunk_offset", STORED);
+        let chunk_length_field = schema_builder.add_u64_field("chunk_length", STORED);
+        let table_field = schema_builder.add_text_field("table", STRING | STORED);
+        let column_field = schema_builder.add_text_field("column", STRING);
+        let key_field = schema_builder.add_text_field("key", STRING);
+
+        let schema = schema_builder.build();
+        info!("Schema built with fields: path, path_exact, parent, name, kind, source, repo, repo_root, labels, type, content, headings, modified, created, size, depth, taken, camera, width, height, duration, title, artist, album_artist, album, genre, track, product, company, version, chunk_of, chunk, chunk_offset, chunk_length, table, column, key");
+
+        // Shards stay in their own folders
+        let location = settings.get().index_location.filter(|_| options.shard.is_none());
+        let index_dir = (options.backing == IndexBacking::Disk)
+            .then(|| location.clone().unwrap_or_else(|| app_data_dir.join("search_index")));
+        let index = match options.backing {
+            IndexBacking::Disk => {
+                let index_path = index_dir.as_deref().unwrap_or(app_data_dir);
+                if location.is_some() && !index_path.exists() {
+                    return Err(format!("The index folder {} is missing; is its disk connected?", index_path.display()));
+                }
+                std::fs::create_dir_all(index_path)
+                    .map_err(|e| format!("Failed to create index directory: {}", e))?;
+                relocate::finish_index_move(app_data_dir, index_path);
+                let (index, origin) = open_or_create_index(index_path, schema)?;
+                upgrade::check_index_format(app_data_dir, origin);
+                index
+            }
+            #[cfg(feature = "ram-index")]
+            IndexBacking::Ram => Index::create_in_ram(schema),
+        };
+        index.tokenizers().register(CONTENT_TOKENIZER, content_analyzer(&[]));
+        let reader = index.reader_builder()
+            .reload_policy(ReloadPolicy::OnCommit)
+            .try_into()
+            .map_err(|e| format!("Failed to create index reader: {}", e))?;
+
+        let snapshots = SnapshotStore::new(app_data_dir.join("snapshots"), settings.get().diff_retention_enabled)
+            .map_err(|e| format!("Failed to create snapshot directory: {}", e))?;
+        let collections = collections::CollectionStore::load(app_data_dir.join("collections"))?;
+        let load_monitor = Arc::new(LoadMonitor::new());
+
+        let initial_state = IndexerState {
+            total_files: 0,
+            processed_files: 0,
+            current_file: String::new(),
+            state: "idle".to_string(),
+            files_per_second: 0.0,
+            elapsed_seconds: 0,
+            start_time: SystemTime::now(),
+            priority_total: 0,
+            priority_processed: 0,
+            skipped_files: 0,
+        };
+        let (progress, _) = watch::channel(initial_state.clone());
+        let (priority_complete, _) = watch::channel(None);
+
+        Ok(Self {
+            index,
+            reader,
+            writer: Arc::new(Mutex::new(None)),
+            state: Arc::new(RwLock::new(initial_state)),
+            path_field,
+            path_exact_field,
+            doc_id_field,
+            parent_field,
+            name_field,
+            kind_field,
+            source_field,
+            repo_field,
+            repo_root_field,
+            labels_field,
+            type_field,
+            content_field,
+            headings_field,
+            modified_field,
+            created_field,
+            size_field,
+            depth_field,
+            taken_field,
+            camera_field,
+            width_field,
+            height_field,
+            duration_field,
+            title_field,
+            artist_field,
+            album_artist_field,
+            album_field,
+            genre_field,
+            track_field,
+            product_field,
+            company_field,
+            version_field,
+            chunk_of_field,
+            chunk_field,
+            chunk_offset_field,
+            chunk_length_field,
+            table_field,
+            column_field,
+            key_field,
+            last_update: Arc::new(RwLock::new(None)),
+            snapshots: Arc::new(snapshots),
+            previews: preview_cache::PreviewCache::new(app_data_dir.join("previews")),
+            learning: ClickLearning::load(app_data_dir.join("learning.json")),
+            collections,
+            zero_results: ZeroResultLog::load(app_data_dir.join("zero_results.json")),
+            recent_changes: RecentChanges::load(app_data_dir.join("recent_changes.json")),
+            secret_findings: SecretFindings::load(app_data_dir.join("secret_findings.json")),
+            paths: paths::PathMap::new(options.portable_root, app_data_dir.join("path_remaps.json")),
+            health: health::HealthMonitor::new(&index_dir.clone().unwrap_or_else(|| app_data_dir.join("search_index"))),
+            integrity: index_dir.as_deref().map(|index_dir| integrity::IndexIntegrity::new(app_data_dir, index_dir)),
+            data_dir: app_data_dir.to_path_buf(),
+            built_with: parking_lot::Mutex::new(index_dir.as_ref().and_then(|_| incremental::load_fingerprint(app_data_dir))),
+            index_dir,
+            tracker: Arc::new(ChangeTracker::new(load_monitor.clone(), fs.clone())),
+            load_monitor,
+            power: Arc::new(PowerMonitor::new(settings.clone())),
+            settings,
+            writes_suspended: AtomicBool::new(false),
+            pending_changes: parking_lot::Mutex::new(Vec::new()),
+            overlay: overlay::MutationOverlay::new(),
+            fs,
+            ocr,
+            subtitles,
+            keyframes,
+            transcriber,
+            classifier,
+            transcription: transcription::TranscriptionQueue::load(app_data_dir.join("transcripts.json")),
+            progress,
+            paused: AtomicBool::new(false),
+            cancel_requested: AtomicBool::new(false),
+            reconciliation: reconcile::Reconciliation::new(),
+            priority_generation: AtomicU64::new(0),
+            priority_complete,
+            cursors: cursor::SearchCursors::new(),
+            content_hashes: duplicates::ContentHashes::new(),
+            repositories: repos::Repositories::new(),
+            shard: options.shard,
+            pinned: pinned::PinnedSnapshots::new(),
+        })
+    }
+
+    pub fn get_state(&self) -> IndexerState {
+        let mut state = self.state.write();
+        let elapsed = state.start_time.elapsed().unwrap_or_default();
+        let elapsed_secs = elapsed.as_secs();
+        
+        // Calculate files per second
+        if elapsed_secs > 0 {
+            state.files_per_second = state.processed_files as f32 / elapsed_secs as f32;
+        } else {
+            state.files_per_second = 0.0;
+        }
+        
+        state.elapsed_seconds = elapsed_secs;
+        state.clone()
+    }
+
+    pub fn change_tracker(&self) -> Arc<ChangeTracker> {
+        self.tracker.clone()
+    }
+
+    pub fn load_monitor(&self) -> Arc<LoadMonitor> {
+        self.load_monitor.clone()
+    }
+
+    pub fn power_monitor(&self) -> Arc<PowerMonitor> {
+        self.power.clone()
+    }
+
+    /// Retains the indexed content of `path` for diffs when the power policy allows reading it.
+    async fn retain_content(&self, path: &Path, size: u64) {
+        if !self.snapshots.is_enabled() || !self.power.content_extraction_allowed() {
+            return;
+        }
+        let delay = self.power.extraction_delay();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        if let Err(e) = self.snapshots.record(path, size) {
+            warn!("Failed to retain previous version of {:?}: {}", path, e);
+        }
+    }
+
+    pub async fn get_reader(&self) -> tantivy::Result<tantivy::IndexReader> {
+        Ok(self.reader.clone())
+    }
+
+    /// Makes the latest commit visible to searches right away instead of
+    /// waiting for the reader to notice it.
+    fn refresh_reader(&self) {
+        if let Err(e) = self.reader.reload() {
+            warn!("Failed to reload index reader: {}", e);
+        }
+    }
+
+    pub async fn update_state<F>(&self, update_fn: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut IndexerState),
+    {
+        let mut state = self.state.write();
+        update_fn(&mut state);
+
+        // Ensure processed files never exceeds total files
+        if state.processed_files > state.total_files {
+            state.processed_files = state.total_files;
+        }
+
+        // Update files per second
+        let elapsed = state.start_time.elapsed().unwrap_or_default();
+        let elapsed_secs = elapsed.as_secs();
+        if elapsed_secs > 0 {
+            state.files_per_second = state.processed_files as f32 / elapsed_secs as f32;
+        }
+
+        state.elapsed_seconds = elapsed_secs;
+        self.progress.send_replace(state.clone());
+        Ok(())
+    }
+
+    /// Receives every state change of the current and future indexing runs.
+    /// Intermediate updates are coalesced if the receiver falls behind.
+    pub fn subscribe_progress(&self) -> watch::Receiver<IndexerState> {
+        self.progress.subscribe()
+    }
+
+    /// Waits while indexing is paused, reflecting the pause in the state.
+    /// Returns false once cancellation has been requested.
+    async fn checkpoint(&self) -> Result<bool, String> {
+        // Indexing a file doesn't yield, so give whoever drives the run a
+        // chance to pause or cancel it between files
+        tokio::task::yield_now().await;
+        if self.paused.load(Ordering::SeqCst) && !self.cancel_requested.load(Ordering::SeqCst) {
+            info!("Indexing paused");
+            self.update_state(|state| state.state = "paused".to_string()).await?;
+            while self.paused.load(Ordering::SeqCst) && !self.cancel_requested.load(Ordering::SeqCst) {
+                tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+            }
+            info!("Indexing resumed");
+            self.update_state(|state| state.state = "indexing".to_string()).await?;
+        }
+        Ok(!self.cancel_requested.load(Ordering::SeqCst))
+    }
+
+    fn is_running(&self) -> bool {
+        matches!(self.state.read().state.as_str(), "scanning" | "indexing" | "paused")
+    }
+
+    pub async fn pause_indexing(&self) -> Result<(), String> {
+        self.paused.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub async fn resume_indexing(&self) -> Result<(), String> {
+        self.paused.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Indexes everything under `path`, which becomes the one indexed
+    /// root; documents from other folders are dropped.
+    pub async fn start_indexing(&self, path: impl AsRef<str>) -> Result<(), String> {
+        let path = path.as_ref().to_string();
+        info!("=== STARTING INDEXING PROCESS ===");
+        info!("Target directory: {}", path);
+        if self.writes_suspended() {
+            return Err("The index writer is held by another Constella process".to_string());
+        }
+        
+        self.paused.store(false, Ordering::SeqCst);
+        self.cancel_requested.store(false, Ordering::SeqCst);
+
+        // Reset state and start scanning phase
+        self.update_state(|state| {
+            state.total_files = 0;
+            state.processed_files = 0;
+            state.priority_total = 0;
+            state.priority_processed = 0;
+            state.skipped_files = 0;
+            state.current_file = format!("Scanning {}", path);
+            state.state = "scanning".to_string();
+            state.start_time = SystemTime::now();
+        }).await?;
+
+        let root = PathBuf::from(&path);
+        if let Err(e) = self.settings.update(|settings| settings.indexed_roots = vec![root]) {
+            warn!("Failed to record indexed root: {}", e);
+        }
+
+        // Repositories may have come and gone since the last run
+        self.repositories.clear();
+
+        // Keep what's indexed when it was built with the same settings
+        let indexed: HashSet<PathBuf> = if self.built_with_current_settings() {
+            self.documents_under("").await?
+                .into_iter()
+                .map(|file| os_path::decode(&file.path))
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        if indexed.is_empty() {
+            info!("Clearing existing index");
+            self.secret_findings.clear();
+            self.ensure_writer().await?;
+            let mut writer_guard = self.writer.lock().await;
+            if let Some(writer) = writer_guard.as_mut() {
+                writer.delete_all_documents()
+                    .map_err(|e| format!("Failed to clear index: {}", e))?;
+                run_blocking(|| writer.commit())
+                    .map_err(|e| format!("Failed to commit index clearing: {}", e))?;
+            }
+            drop(writer_guard);
+            self.refresh_reader();
+            self.record_index_manifest();
+            self.record_built_with();
+        }
+
+        // PHASE 1: Scanning
+        info!("=== PHASE 1: SCANNING ===");
+        info!("Starting scan of directory: {}", path);
+        let scanner = FileScanner::with_provider(self.fs.clone())
+            .with_exclusions(self.exclusions());
+        let total_files = scanner.scan_directory(&path).await;
+        info!("Initial scan completed, found {} files", total_files);
+        
+        if total_files == 0 {
+            error!("No files found in directory: {}", path);
+            let plan = self.plan_run(&[], &indexed).await;
+            self.drop_documents(&plan.stale).await?;
+            self.update_state(|state| {
+                state.state = "completed".to_string();
+                state.current_file = "No files found".to_string();
+            }).await?;
+            return Ok(());
+        }
+
+        // PHASE 2: Initialize indexing
+        info!("=== PHASE 2: INDEXING ===");
+        info!("Preparing to index {} files", total_files);
+        self.update_state(|state| {
+            state.total_files = total_files;
+            state.processed_files = 0;
+            state.state = "indexing".to_string();
+            state.start_time = SystemTime::now(); // Reset timer for indexing phase
+            state.current_file = "Starting indexing...".to_string();
+        }).await?;
+
+        // Initialize writer for indexing
+        self.ensure_writer().await?;
+
+        // PHASE 3: Process files
+        info!("=== PHASE 3: COLLECTING PATHS ===");
+        let mut batch = Vec::with_capacity(COMMIT_BATCH_SIZE);
+        let mut processed = 0;
+
+        // Get all paths to process
+        let mut paths = scanner.collect_paths(&path);
+        let total = paths.len();
+        info!("Collected {} paths to index", total);
+
+        if total != total_files {
+            warn!("Path count mismatch: scan found {}, but collected {}", total_files, total);
+        }
+        if let Some(shard) = self.shard {
+            paths.retain(|file| shard.owns(file));
+            let owned = paths.len();
+            info!("Shard {} of {} owns {} of them", shard.index + 1, shard.count, owned);
+            self.update_state(move |state| state.total_files = owned).await?;
+        }
+        let RunPlan { mut unchanged, stale } = self.plan_run(&paths, &indexed).await;
+        self.drop_documents(&stale).await?;
+
+        // Process each file, "index first" folders ahead of the rest
+        info!("=== PHASE 4: INDEXING FILES ===");
+        let mut priority_generation = self.priority_generation.load(Ordering::SeqCst);
+        let mut queue = PathQueue::new(paths, &self.priority_folders());
+        let mut priority_processed = 0;
+        let mut skipped = 0;
+        let priority_total = queue.prioritized_remaining();
+        self.update_state(move |state| state.priority_total = priority_total).await?;
+        let mut cancelled = false;
+        let mut last_commit = std::time::Instant::now();
+        let mut slow_commits = self.sheds(SheddingMeasure::SlowCommits);
+        loop {
+            if !self.checkpoint().await? {
+                cancelled = true;
+                break;
+            }
+            let generation = self.priority_generation.load(Ordering::SeqCst);
+            if generation != priority_generation {
+                priority_generation = generation;
+                queue.reprioritize(&self.priority_folders());
+                let priority_total = priority_processed + queue.prioritized_remaining();
+                self.update_state(move |state| state.priority_total = priority_total).await?;
+            }
+            let Some((path, prioritized)) = queue.pop() else {
+                break;
+            };
+            if prioritized {
+                priority_processed += 1;
+            }
+            if let Some(metadata) = unchanged.remove(&path) {
+                self.tracker.update_state(&path, &metadata, false).await;
+                skipped += 1;
+                processed += 1;
+                self.update_state(move |state| {
+                    state.processed_files = processed;
+                    state.priority_processed = priority_processed;
+                    state.skipped_files = skipped;
+                }).await?;
+                if prioritized && queue.prioritized_remaining() == 0 {
+                    self.complete_priority_folders(&mut batch, priority_processed).await?;
+                }
+                continue;
+            }
+            let path_str = path.to_string_lossy().into_owned();
+            info!("Processing file: {}", path_str);
+            
+            // Create and add document
+            match self.create_document(&path) {
+                Ok(doc) => {
+                    let size = doc.get_first(self.size_field)
+                        .and_then(|f| f.as_u64())
+                        .unwrap_or_default();
+                    self.retain_content(&path, size).await;
+                    if let Ok(metadata) = self.fs.metadata(&path) {
+                        self.tracker.update_state(&path, &metadata, true).await;
+                    }
+
+                    batch.push(doc);
+                    if let Err(e) = self.index_chunks(&path, size).await {
+                        warn!("Failed to index chunks of {}: {}", path_str, e);
+                    }
+                    processed += 1;
+
+                    // Update state
+                    self.update_state(move |state| {
+                        state.processed_files = processed;
+                        state.priority_processed = priority_processed;
+                        state.current_file = path_str.clone();
+                    }).await?;
+
+                    // Back off while the machine is busy with other work
+                    let delay = self.load_monitor.throttle_delay();
+                    if !delay.is_zero() {
+                        tokio::time::sleep(delay).await;
+                    }
+
+                    // Commit batch if needed, or often enough that the run is searchable as it goes
+                    let (batch_size, interval) = if slow_commits {
+                        (SLOW_COMMIT_BATCH_SIZE, SLOW_COMMIT_INTERVAL)
+                    } else {
+                        (COMMIT_BATCH_SIZE, PARTIAL_COMMIT_INTERVAL)
+                    };
+                    if batch.len() >= batch_size || last_commit.elapsed() >= interval {
+                        info!("Committing batch of {} documents", batch.len());
+                        if let Err(e) = self.commit_batch(&mut batch).await {
+                            return self.fail_indexing(format!("Failed to commit batch: {}", e)).await;
+                        }
+                        last_commit = std::time::Instant::now();
+                        slow_commits = self.sheds(SheddingMeasure::SlowCommits);
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to create document for {}: {}", path_str, e);
+                }
+            }
+
+            if prioritized && queue.prioritized_remaining() == 0 {
+                self.complete_priority_folders(&mut batch, priority_processed).await?;
+            }
+        }
+
+        // Commit any remaining documents
+        if !batch.is_empty() {
+            info!("Committing final batch of {} documents", batch.len());
+            if let Err(e) = self.commit_batch(&mut batch).await {
+                return self.fail_indexing(format!("Failed to commit final batch: {}", e)).await;
+            }
+        }
+
+        // Final state update
+        if cancelled {
+            self.update_state(|state| {
+                state.state = "cancelled".to_string();
+                state.processed_files = processed;
+                state.current_file = format!("Cancelled after {} files", processed);
+            }).await?;
+            info!("=== INDEXING CANCELLED ===");
+            info!("Files kept in the index: {}/{}", processed, total);
+            return Ok(());
+        }
+        self.update_state(|state| {
+            state.state = "completed".to_string();
+            state.processed_files = processed;
+            state.current_file = "Indexing completed".to_string();
+        }).await?;
+        self.unschedule_rebuild();
+
+        info!("=== INDEXING COMPLETED ===");
+        info!("Total files processed: {}/{} ({} unchanged)", processed, total, skipped);
+        Ok(())
+    }
+
+    /// Commits everything indexed so far so the prioritized folders are
+    /// searchable, then announces it.
+    async fn complete_priority_folders(&self, batch: &mut Vec<Document>, files: usize) -> Result<(), String> {
+        if let Err(e) = self.commit_batch(batch).await {
+            return self.fail_indexing(format!("Failed to commit prioritized folders: {}", e)).await;
+        }
+        let completion = PriorityCompletion {
+            folders: self.priority_folders(),
+            files,
+            elapsed_seconds: self.state.read().start_time.elapsed().unwrap_or_default().as_secs(),
+        };
+        info!("Prioritized folders indexed: {:?}", completion);
+        self.priority_complete.send_replace(Some(completion));
+        Ok(())
+    }
+
+    /// Folders whose files are indexed before everything else, in order.
+    pub fn priority_folders(&self) -> Vec<PathBuf> {
+        self.settings.get().priority_folders
+    }
+
+    /// Marks `folder` to be indexed first, also reordering a run in progress.
+    pub fn prioritize_folder(&self, folder: impl AsRef<Path>) -> Result<Vec<PathBuf>, String> {
+        let folder = folder.as_ref().to_path_buf();
+        let settings = self.settings.update(|settings| {
+            if !settings.priority_folders.contains(&folder) {
+                settings.priority_folders.push(folder);
+            }
+        })?;
+        self.priority_generation.fetch_add(1, Ordering::SeqCst);
+        Ok(settings.priority_folders)
+    }
+
+    pub fn deprioritize_folder(&self, folder: impl AsRef<Path>) -> Result<Vec<PathBuf>, String> {
+        let folder = folder.as_ref();
+        let settings = self.settings.update(|settings| settings.priority_folders.retain(|f| f != folder))?;
+        self.priority_generation.fetch_add(1, Ordering::SeqCst);
+        Ok(settings.priority_folders)
+    }
+
+    pub fn subscribe_priority_complete(&self) -> watch::Receiver<Option<PriorityCompletion>> {
+        self.priority_complete.subscribe()
+    }
+
+    /// Records `message` as the outcome of the current run and returns it as an error.
+    async fn fail_indexing(&self, message: String) -> Result<(), String> {
+        error!("{}", message);
+        self.update_state(|state| {
+            state.state = "error".to_string();
+            state.current_file = message.clone();
+        }).await?;
+        Err(message)
+    }
+
+    /// Adds `batch` to the index in place of what's there for the same
+    /// files, so indexing a file again never doubles it.
+    async fn commit_batch(&self, batch: &mut Vec<Document>) -> Result<(), String> {
+        let docs = std::mem::take(batch);
+        let replaced = self.replaced_terms(&docs);
+        self.write_with_retry(|writer, last_attempt| {
+            for term in &replaced {
+                writer.delete_term(term.clone());
+            }
+            let rejected = add_documents(writer, &docs);
+            if rejected > 0 && !last_attempt {
+                return Err(format!("{} of {} documents were rejected", rejected, docs.len()));
+            }
+            Ok(())
+        }).await
+    }
+
+    /// Terms of the documents `docs` replace: a file's own document, and
+    /// all chunks of a file whose first chunk is among them or that is no
+    /// longer large enough to chunk. Later chunks of a file are committed
+    /// after its first and add to it.
+    fn replaced_terms(&self, docs: &[Document]) -> Vec<Term> {
+        let config = self.settings.get().indexing;
+        let mut terms = Vec::with_capacity(docs.len());
+        for doc in docs {
+            if let Some(stored) = doc.get_first(self.path_exact_field).and_then(|f| f.as_text()) {
+                terms.push(Term::from_field_text(self.path_exact_field, stored));
+                // A file indexed in chunks before may have shrunk since
+                let size = doc.get_first(self.size_field).and_then(|f| f.as_u64()).unwrap_or_default();
+                let chunked = self.doc_path(doc)
+                    .is_some_and(|path| config.is_chunked(&os_path::decode(&path), size));
+                if !chunked {
+                    terms.push(Term::from_field_text(self.chunk_of_field, stored));
+                }
+                continue;
+            }
+            let first_chunk = doc.get_first(self.chunk_field).and_then(|f| f.as_u64()) == Some(0);
+            if let Some(stored) = doc.get_first(self.chunk_of_field).and_then(|f| f.as_text()).filter(|_| first_chunk) {
+                terms.push(Term::from_field_text(self.chunk_of_field, stored));
+            }
+        }
+        terms
+    }
+
+    /// Text of `path` for the content field, if it is a small text file, a
+    /// video with subtitles or transcribed audio, and the power policy
+    /// allows reading file contents right now.
+    fn extract_content(&self, path: &Path, size: u64) -> Option<Extracted> {
+        if subtitles::is_video(path) {
+            return self.video_transcript(path);
+        }
+        if self.settings.get().transcription.is_audio(path) {
+            return self.audio_transcript(path);
+        }
+        let format = Format::for_path(path)?;
+        let max_size = format.size_limit(self.settings.get().indexing.content_max_file_size);
+        if size == 0 || size > max_size || !self.power.content_extraction_allowed() {
+            return None;
+        }
+        match self.fs.read(path) {
+            Ok(bytes) => Some(format.extract(&bytes)),
+            Err(e) => {
+                warn!("Failed to read content of {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Text recognized in the screenshot at `path`, when a recognizer is
+    /// configured, screenshot OCR is on and the power policy allows it.
+    fn recognize_screenshot(&self, path: &Path, size: u64) -> Option<String> {
+        let ocr = self.ocr.as_ref()?;
+        if !self.settings.get().indexing.ocr_screenshots
+            || size == 0
+            || size > MAX_SCREENSHOT_OCR_SIZE
+            || !self.power.content_extraction_allowed()
+        {
+            return None;
+        }
+        let image = match self.fs.read(path) {
+            Ok(image) => image,
+            Err(e) => {
+                warn!("Failed to read screenshot {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        match ocr.recognize(&image) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                warn!("Failed to recognize text in {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Runs `write` against the index writer and commits, rolling back and
+    /// retrying with exponential backoff when either step fails. A panic
+    /// while the writer is held drops it, so the next attempt starts with a
+    /// fresh writer. `write` is told when it is on its last attempt.
+    async fn write_with_retry<F>(&self, mut write: F) -> Result<(), String>
+    where
+        F: FnMut(&mut IndexWriter, bool) -> Result<(), String>,
+    {
+        let mut attempt = 0;
+        loop {
+            let last_attempt = attempt + 1 >= MAX_RETRY_ATTEMPTS;
+            self.ensure_writer().await?;
+
+            let result = {
+                let mut writer_guard = self.writer.lock().await;
+                let writer = match writer_guard.as_mut() {
+                    Some(writer) => writer,
+                    None => return Ok(()),
+                };
+                let outcome = run_blocking(|| std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    if chaos::inject(Fault::WriterPanic) {
+                        panic!("injected index writer panic");
+                    }
+                    write(writer, last_attempt)?;
+                    if chaos::inject(Fault::CommitError) {
+                        return Err("injected commit failure".to_string());
+                    }
+                    writer.commit()
+                        .map(|_| ())
+                        .map_err(|e| format!("Failed to commit: {}", e))
+                })));
+
+                match outcome {
+                    Ok(Ok(())) => Ok(()),
+                    Ok(Err(e)) => {
+                        // Throw away whatever the failed attempt left uncommitted
+                        if let Err(rollback_error) = writer.rollback() {
+                            warn!("Failed to roll back index writer, recreating it: {}", rollback_error);
+                            *writer_guard = None;
+                        }
+                        Err(e)
+                    }
+                    Err(_) => {
+                        error!("Index writer panicked, recreating it");
+                        *writer_guard = None;
+                        Err("Index writer panicked".to_string())
+                    }
+                }
+            };
+
+            match result {
+                Ok(()) => {
+                    self.refresh_reader();
+                    self.record_index_manifest();
+                    return Ok(());
+                }
+                Err(e) if !last_attempt => {
+                    let delay = RETRY_BASE_DELAY * 2u32.pow(attempt as u32);
+                    warn!(
+                        "Index write failed (attempt {}/{}), retrying in {:?}: {}",
+                        attempt + 1, MAX_RETRY_ATTEMPTS, delay, e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Builds the index document for `path` from its metadata.
+    pub fn create_document(&self, path: impl AsRef<std::path::Path>) -> Result<Document, String> {
+        self.build_document(path.as_ref(), None)
+    }
+
+    /// Builds the document for `path`, taking image labels and photo
+    /// details from `previous`, the document of the same file elsewhere,
+    /// when given rather than working them out again.
+    fn build_document(&self, path: &Path, previous: Option<&Document>) -> Result<Document, String> {
+        let mut doc = Document::default();
+        
+        // Get file metadata
+        let metadata = self.fs.metadata(path)
+            .map_err(|e| format!("Failed to get metadata for {}: {}", path.display(), e))?;
+        
+        // Add path
+        let stored_path = self.stored_path(path);
+        doc.add_text(self.path_field, &stored_path);
+        doc.add_text(self.path_exact_field, &stored_path);
+        doc.add_text(self.doc_id_field, lookup::result_id(&stored_path));
+        if let Some(parent) = path.parent() {
+            doc.add_text(self.parent_field, self.stored_path(parent));
+        }
+        if let Some(name) = path.file_name() {
+            doc.add_text(self.name_field, name.to_string_lossy().as_ref());
+        }
+        doc.add_facet(self.type_field, type_facet(path));
+        
+        // Add modified time
+        let modified = metadata.modified
+            .ok_or_else(|| format!("Failed to get modified time for {}", path.display()))?
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| format!("Failed to calculate duration: {}", e))?
+            .as_secs();
+        doc.add_u64(self.modified_field, modified);
+        let created = metadata.created
+            .and_then(|created| created.duration_since(UNIX_EPOCH).ok())
+            .map_or(modified, |created| created.as_secs());
+        doc.add_u64(self.created_field, created);
+        
+        // Add file size
+        doc.add_u64(self.size_field, metadata.len);
+        doc.add_u64(self.depth_field, path.components().count() as u64);
+
+        if let Some(domain) = self.download_source(path) {
+            doc.add_text(self.source_field, &domain);
+        }
+        let lean = self.sheds(SheddingMeasure::LeanDocuments);
+        if let Some(repo_root) = self.repository_root(path) {
+            doc.add_text(self.repo_field, repos::repository_name(&repo_root).to_lowercase());
+            if !lean {
+                doc.add_text(self.repo_root_field, self.stored_path(&repo_root));
+            }
+        }
+
+        let screenshot = is_screenshot(path);
+        if screenshot {
+            doc.add_text(self.kind_field, SCREENSHOT_KIND);
+        }
+        let in_dependency_folder = self.settings.get().indexing.in_dependency_folder(path);
+        let content = if in_dependency_folder {
+            None
+        } else if screenshot {
+            self.recognize_screenshot(path, metadata.len).map(Extracted::plain)
+        } else {
+            self.extract_content(path, metadata.len)
+        };
+        let content = self.without_secrets(path, content);
+        if let Some(content) = &content {
+            doc.add_text(self.content_field, &content.text);
+            for heading in &content.headings {
+                doc.add_text(self.headings_field, heading);
+            }
+            for term in content.keys.iter().flat_map(|key| config::key_terms(key)) {
+                doc.add_text(self.key_field, term);
+            }
+        }
+        let photo = match previous {
+            Some(previous) => self.stored_photo(previous),
+            None => self.photo_metadata(path, metadata.len),
+        };
+        if let Some(photo) = photo.filter(|_| !in_dependency_folder) {
+            if let Some(taken) = photo.taken {
+                doc.add_u64(self.taken_field, taken);
+            }
+            if let Some(camera) = &photo.camera {
+                doc.add_text(self.camera_field, camera);
+            }
+            if let (Some(width), Some(height), false) = (photo.width, photo.height, lean) {
+                doc.add_u64(self.width_field, width.into());
+                doc.add_u64(self.height_field, height.into());
+            }
+        }
+        let duration = match previous {
+            Some(previous) => previous.get_first(self.duration_field).and_then(|f| f.as_u64()),
+            None => self.media_duration(path, metadata.len),
+        };
+        if let Some(duration) = duration.filter(|_| !in_dependency_folder) {
+            doc.add_u64(self.duration_field, duration);
+        }
+        let music = match previous {
+            Some(previous) => self.stored_music(previous),
+            None => self.music_tags(path, metadata.len),
+        };
+        if let Some(music) = music.filter(|_| !in_dependency_folder) {
+            self.add_music_tags(&mut doc, &music);
+        }
+        if let Some(binary) = self.binary_metadata(path, metadata.len).filter(|_| !in_dependency_folder) {
+            doc.add_text(self.kind_field, binary.kind.as_str());
+            if let Some(product) = &binary.product {
+                doc.add_text(self.product_field, product);
+            }
+            if let Some(company) = &binary.company {
+                doc.add_text(self.company_field, company);
+            }
+            if let Some(version) = &binary.version {
+                doc.add_text(self.version_field, version);
+            }
+            if content.is_none() {
+                doc.add_text(self.content_field, binary.text());
+            }
+        }
+        if let Some(tables) = self.database_tables(path, metadata.len).filter(|_| !in_dependency_folder) {
+            doc.add_text(self.kind_field, DATABASE_KIND);
+            for table in &tables {
+                doc.add_text(self.table_field, table.name.to_lowercase());
+                for column in &table.columns {
+                    doc.add_text(self.column_field, column.to_lowercase());
+                }
+            }
+            if content.is_none() {
+                doc.add_text(self.content_field, sqlite::text(&tables));
+            }
+        }
+        if !in_dependency_folder {
+            let labels = match previous {
+                Some(previous) => previous.get_all(self.labels_field)
+                    .filter_map(|value| value.as_text())
+                    .map(str::to_string)
+                    .collect(),
+                None => self.image_labels(path, metadata.len, content.as_ref().map(|content| content.text.as_str())),
+            };
+            for label in labels {
+                doc.add_text(self.labels_field, &label);
+            }
+        }
+        
+        Ok(doc)
+    }
+
+    async fn ensure_writer(&self) -> Result<(), String> {
+        if self.writes_suspended() {
+            return Err("The index writer is held by another Constella process".to_string());
+        }
+        let mut writer_guard = self.writer.lock().await;
+        if writer_guard.is_none() {
+            *writer_guard = Some(self.index.writer_with_num_threads(4, INDEX_BUFFER_SIZE)
+                .map_err(|e| format!("Failed to create writer: {}", e))?);
+        }
+        Ok(())
+    }
+
+    pub fn writes_suspended(&self) -> bool {
+        self.writes_suspended.load(Ordering::SeqCst)
+    }
+
+    /// Commits and drops the index writer so its lock file is released.
+    pub async fn release_writer(&self) -> Result<(), String> {
+        let mut writer_guard = self.writer.lock().await;
+        if let Some(mut writer) = writer_guard.take() {
+            writer.commit()
+                .map_err(|e| format!("Failed to commit before releasing writer: {}", e))?;
+            self.refresh_reader();
+            writer.wait_merging_threads()
+                .map_err(|e| format!("Failed to finish merges before releasing writer: {}", e))?;
+            self.record_index_manifest();
+            info!("Index writer released");
+        }
+        Ok(())
+    }
+
+    /// Hands the writer to another process. Watcher changes arriving in the
+    /// meantime are queued and applied by `resume_writes`.
+    pub async fn suspend_writes(&self) -> Result<(), String> {
+        self.writes_suspended.store(true, Ordering::SeqCst);
+        if let Err(e) = self.release_writer().await {
+            self.writes_suspended.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Takes the writer back and applies changes queued while suspended.
+    pub async fn resume_writes(&self) -> Result<UpdateSummary, String> {
+        self.writes_suspended.store(false, Ordering::SeqCst);
+        // Pick up whatever the other process committed in the meantime
+        self.refresh_reader();
+        let pending = std::mem::take(&mut *self.pending_changes.lock());
+        info!("Resuming index writes with {} queued changes", pending.len());
+        if pending.is_empty() {
+            return Ok(UpdateSummary::default());
+        }
+        self.apply_changes(&pending).await
+    }
+
+    /// Applies a batch of watcher-reported changes, consulting the change
+    /// tracker so files whose size, mtime and (for hot files) hash are
+    /// unchanged are skipped without touching the index.
+    pub async fn apply_changes(&self, changes: &[(PathBuf, ChangeType)]) -> Result<UpdateSummary, String> {
+        let mut summary = UpdateSummary::default();
+        if self.writes_suspended() {
+            self.pending_changes.lock().extend_from_slice(changes);
+            return Ok(summary);
+        }
+        let mut removals = Vec::new();
+        let mut additions = Vec::new();
+        let exclusions = self.exclusions();
+
+        // Videos are indexed with the subtitle files next to them
+        let mut changes = changes.to_vec();
+        let videos: Vec<PathBuf> = changes.iter()
+            .flat_map(|(path, _)| self.videos_with_subtitles(path))
+            .collect();
+        for video in videos {
+            if !changes.iter().any(|(path, _)| *path == video) {
+                self.tracker.forget(&video).await;
+                changes.push((video, ChangeType::Modified));
+            }
+        }
+
+        let mut moved = Vec::new();
+        for (from, to) in self.detect_moves(&changes).await {
+            let Ok(metadata) = self.fs.metadata(&to) else {
+                continue;
+            };
+            match self.carry_move(&from, &to).await {
+                Ok(doc) => {
+                    removals.push(from.clone());
+                    additions.push((to.clone(), doc, ChangeKind::Added, metadata.len));
+                    moved.push((from, to));
+                }
+                Err(e) => warn!("Indexing {} afresh: {}", to.display(), e),
+            }
+        }
+        changes.retain(|(path, _)| !moved.iter().any(|(from, to)| path == from || path == to));
+
+        for (path, change) in &changes {
+            if let ChangeType::Renamed(from) = change {
+                removals.push(from.clone());
+            }
+            if let ChangeType::Deleted = change {
+                removals.push(path.clone());
+                continue;
+            }
+            if exclusions.is_excluded(path) {
+                continue;
+            }
+
+            let metadata = match self.fs.metadata(path) {
+                Ok(metadata) if metadata.is_file => metadata,
+                Ok(_) => continue,
+                Err(_) => {
+                    // Gone again before we got to it
+                    removals.push(path.clone());
+                    continue;
+                }
+            };
+
+            if !self.tracker.should_reindex(path, &metadata).await {
+                self.tracker.update_state(path, &metadata, false).await;
+                summary.skipped += 1;
+                continue;
+            }
+
+            match self.create_document(path) {
+                Ok(doc) => {
+                    self.retain_content(path, metadata.len).await;
+                    self.tracker.update_state(path, &metadata, true).await;
+                    let kind = match change {
+                        ChangeType::Modified => ChangeKind::Updated,
+                        _ => ChangeKind::Added,
+                    };
+                    additions.push((path.clone(), doc, kind, metadata.len));
+                }
+                Err(e) => error!("Failed to create document for {:?}: {}", path, e),
+            }
+        }
+
+        if removals.is_empty() && additions.is_empty() {
+            info!("Incremental update: skipped {} unchanged files", summary.skipped);
+            *self.last_update.write() = Some(summary.clone());
+            return Ok(summary);
+        }
+
+        let mut added_paths = Vec::with_capacity(additions.len());
+        let mut docs = Vec::with_capacity(additions.len());
+        let mut logged = Vec::with_capacity(additions.len());
+        for (path, doc, kind, size) in additions {
+            logged.push((path.clone(), kind, size));
+            added_paths.push(path);
+            docs.push(doc);
+        }
+        let mut rejected = 0;
+        let committed = self.write_with_retry(|writer, last_attempt| {
+            for path in removals.iter().chain(&added_paths) {
+                for term in self.document_terms(path) {
+                    writer.delete_term(term);
+                }
+            }
+            rejected = add_documents(writer, &docs);
+            if rejected > 0 && !last_attempt {
+                return Err(format!("{} of {} documents were rejected", rejected, docs.len()));
+            }
+            Ok(())
+        }).await;
+        if let Err(e) = committed {
+            // Make the next pass retry these rather than trust the tracker
+            for path in &added_paths {
+                self.tracker.forget(path).await;
+            }
+            return Err(format!("Failed to commit incremental update: {}", e));
+        }
+        self.overlay.settle(&removals.iter().chain(&added_paths).collect::<Vec<_>>());
+        // A file that comes back at the same path is new to the index again
+        for path in removals.iter().filter(|path| !added_paths.contains(path)) {
+            self.tracker.forget(path).await;
+        }
+        summary.moved += moved.len();
+        summary.removed += removals.len() - moved.len();
+        self.secret_findings.forget(&removals);
+        summary.indexed += (docs.len() - rejected).saturating_sub(moved.len());
+        for (path, _, size) in &logged {
+            if let Err(e) = self.index_chunks(path, *size).await {
+                warn!("Failed to index chunks of {}: {}", path.display(), e);
+            }
+        }
+        // Moved files keep their place in the feed
+        logged.retain(|(path, _, _)| !moved.iter().any(|(_, to)| to == path));
+        self.recent_changes.record(&logged, &removals, self.settings.get().recent_changes_days);
+
+        info!(
+            "Incremental update: {} indexed, {} moved, {} skipped, {} removed",
+            summary.indexed, summary.moved, summary.skipped, summary.removed
+        );
+        *self.last_update.write() = Some(summary.clone());
+        Ok(summary)
+    }
+
+    pub fn segment_count(&self) -> Result<usize, String> {
+        self.index.searchable_segment_ids()
+            .map(|ids| ids.len())
+            .map_err(|e| format!("Failed to list segments: {}", e))
+    }
+
+    /// Merges all searchable segments into one. The merge itself can't be
+    /// interrupted, so callers should only start it when the machine is idle.
+    pub async fn optimize_segments(&self) -> Result<(), String> {
+        let segment_ids = self.index.searchable_segment_ids()
+            .map_err(|e| format!("Failed to list segments: {}", e))?;
+        if segment_ids.len() < 2 {
+            return Ok(());
+        }
+
+        info!("Merging {} segments", segment_ids.len());
+        self.ensure_writer().await?;
+        let merge = {
+            let mut writer_guard = self.writer.lock().await;
+            match writer_guard.as_mut() {
+                Some(writer) => writer.merge(&segment_ids),
+                None => return Ok(()),
+            }
+        };
+        merge.await.map_err(|e| format!("Failed to merge segments: {}", e))?;
+        Ok(())
+    }
+
+    /// Re-hashes tracked files with a stored hash and reindexes any whose
+    /// content changed without their size or mtime changing. Stops early
+    /// once `should_continue` returns false.
+    pub async fn verify_checksums(&self, should_continue: impl Fn() -> bool) -> Result<usize, String> {
+        let mut changes = Vec::new();
+        for path in self.tracker.hashed_paths().await {
+            if !should_continue() {
+                break;
+            }
+            if self.tracker.hash_mismatch(&path).await {
+                warn!("Content of {:?} changed without a metadata change", path);
+                self.tracker.forget(&path).await;
+                changes.push((path, ChangeType::Modified));
+            }
+        }
+
+        let mismatched = changes.len();
+        if !changes.is_empty() {
+            self.apply_changes(&changes).await?;
+        }
+        Ok(mismatched)
+    }
+
+    /// Re-checks the files that have gone longest without a look, in small
+    /// batches so the work can stop as soon as `should_continue` says so.
+    pub async fn rescan_cold_files(&self, limit: usize, should_continue: impl Fn() -> bool) -> Result<UpdateSummary, String> {
+        const RESCAN_BATCH_SIZE: usize = 100;
+        let mut total = UpdateSummary::default();
+
+        for chunk in self.tracker.coldest_paths(limit).await.chunks(RESCAN_BATCH_SIZE) {
+            if !should_continue() {
+                break;
+            }
+            let changes: Vec<(PathBuf, ChangeType)> = chunk.iter()
+                .map(|path| {
+                    let change = if path.exists() { ChangeType::Modified } else { ChangeType::Deleted };
+                    (path.clone(), change)
+                })
+                .collect();
+
+            let summary = self.apply_changes(&changes).await?;
+            total.indexed += summary.indexed;
+            total.skipped += summary.skipped;
+            total.removed += summary.removed;
+        }
+        Ok(total)
+    }
+
+    pub fn last_update(&self) -> Option<UpdateSummary> {
+        self.last_update.read().clone()
+    }
+
+    /// The configured exclusions. Patterns are validated when they are set,
+    /// so a bad one here means a hand-edited settings file.
+    pub fn exclusions(&self) -> PathExclusions {
+        let config = self.settings.get().indexing;
+        PathExclusions::new(&config.exclude)
+            .unwrap_or_else(|e| {
+                warn!("Ignoring exclusion patterns: {}", e);
+                PathExclusions::default()
+            })
+            .with_gitignores(&config.gitignore_repos, self.fs.as_ref())
+    }
+
+    /// Directories the index is meant to cover: the last full index run's
+    /// root plus every watched root.
+    pub fn indexed_roots(&self) -> Vec<PathBuf> {
+        let settings = self.settings.get();
+        let mut roots: Vec<PathBuf> = settings.indexed_roots.into_iter()
+            .chain(settings.watched_roots)
+            .collect();
+        roots.sort();
+        roots.dedup();
+        let nested: Vec<bool> = roots.iter()
+            .map(|root| roots.iter().any(|other| other != root && root.starts_with(other)))
+            .collect();
+        roots.into_iter()
+            .zip(nested)
+            .filter(|(_, nested)| !nested)
+            .map(|(root, _)| root)
+            .collect()
+    }
+
+    /// Parses a user query against the name, path and content fields,
+    /// boosted by the configured ranking weights. Stopwords are dropped
+    /// from the content field only.
+    pub fn parse_query(&self, query: &str) -> Result<Box<dyn Query>, String> {
+        self.parse_query_skipping(query, &[])
+    }
+
+    /// Like `parse_query`, also leaving `noise_terms` out of the content field.
+    fn parse_query_skipping(&self, query: &str, noise_terms: &[String]) -> Result<Box<dyn Query>, String> {
+        let settings = self.settings.get();
+        let weights = settings.ranking;
+        let mut skipped = settings.stopwords.stopwords;
+        skipped.extend_from_slice(noise_terms);
+
+        let tokenizers = TokenizerManager::default();
+        tokenizers.register(CONTENT_TOKENIZER, content_analyzer(&skipped));
+        let mut query_parser = QueryParser::new(
+            self.index.schema(),
+            vec![self.name_field, self.path_field, self.content_field, self.headings_field],
+            tokenizers,
+        );
+        query_parser.set_field_boost(self.name_field, weights.name_boost);
+        query_parser.set_field_boost(self.path_field, weights.path_boost);
+        query_parser.set_field_boost(self.content_field, weights.content_boost);
+        query_parser.set_field_boost(self.headings_field, weights.headings_boost);
+        query_parser.parse_query(query)
+            .map_err(|e| format!("Failed to parse query: {}", e))
+    }
+
+    /// Query terms that appear in so many documents' content that they say
+    /// next to nothing about relevance. When every term is that common they
+    /// are all kept, so the query still matches on content.
+    pub fn noise_terms(&self, searcher: &Searcher, query: &str) -> Vec<String> {
+        let config = self.settings.get().stopwords;
+        let num_docs = searcher.num_docs();
+        if !config.noise_detection_enabled(num_docs) {
+            return Vec::new();
+        }
+
+        let terms = tokenize(&mut content_analyzer(&config.stopwords), query);
+        let noisy: Vec<String> = terms.iter()
+            .filter(|term| {
+                let doc_freq = searcher.doc_freq(&Term::from_field_text(self.content_field, term))
+                    .unwrap_or_default();
+                doc_freq as f32 / num_docs as f32 >= config.noise_threshold
+            })
+            .cloned()
+            .collect();
+        if noisy.len() == terms.len() {
+            return Vec::new();
+        }
+        noisy
+    }
+
+    /// The ranking weights searches are currently scored with.
+    pub fn ranking_weights(&self) -> RankingWeights {
+        self.settings.get().ranking
+    }
+
+    /// The top `limit` matches for `query`, with BM25 scores scaled by the
+    /// recency boost.
+    fn ranked_top_docs(&self, searcher: &Searcher, query: &dyn Query, limit: usize) -> Result<Vec<(Score, DocAddress)>, String> {
+        let weights = self.ranking_weights();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let collector = TopDocs::with_limit(limit).tweak_score(move |segment_reader: &SegmentReader| {
+            let modified = segment_reader.fast_fields().u64("modified").ok();
+            move |doc: DocId, score: Score| {
+                let modified = modified.as_ref()
+                    .and_then(|column| column.first(doc))
+                    .unwrap_or_default();
+                score * weights.recency_multiplier(modified, now)
+            }
+        });
+        searcher.search(query, &collector)
+            .map_err(|e| format!("Failed to execute search: {}", e))
+    }
+
+    pub async fn search(&self, query: &str) -> Result<Vec<serde_json::Value>, String> {
+        self.search_with_options(query, &SearchOptions::default()).await
+    }
+
+    /// Searches like `search_with_options` and reports how much of a full
+    /// index run in progress the results could have come from.
+    pub async fn search_response(&self, query: &str, options: &SearchOptions) -> Result<SearchResponse, String> {
+        self.run_search(query, options)
+    }
+
+    /// Percentage of the files found by the current full index run that are
+    /// already searchable; 100 when no run is in progress.
+    pub fn index_completeness(&self) -> f32 {
+        let state = self.state.read();
+        match state.state.as_str() {
+            "scanning" => 0.0,
+            "indexing" | "paused" if state.total_files > 0 => {
+                let searchable = self.reader.searcher().num_docs() as f32;
+                (searchable / state.total_files as f32 * 100.0).min(100.0)
+            }
+            _ => 100.0,
+        }
+    }
+
+    pub async fn search_with_options(&self, query: &str, options: &SearchOptions) -> Result<Vec<serde_json::Value>, String> {
+        self.run_search(query, options).map(|response| response.results)
+    }
+
+    /// Pulls out filter expressions, applies the rewrite table and drops
+    /// stopwords and noise terms before parsing `query`.
+    fn prepare_query(&self, searcher: &Searcher, query: &str, options: &SearchOptions) -> Result<PreparedQuery, String> {
+        let settings = self.settings.get();
+        let filtered = extract_filters(query, &FilterContext::now(settings.date_locale.as_deref()))?;
+        let query = if options.skip_rewrites {
+            filtered.text.clone()
+        } else {
+            rewrite_query(&filtered.text, &settings.query_rewrites)
+        };
+        let noise_terms = self.noise_terms(searcher, &query);
+        let text_query = self.parse_query_skipping(&query, &noise_terms)?;
+
+        let mut scopes: Vec<Box<dyn Query>> = Vec::new();
+        if let Some(root) = &options.root {
+            scopes.push(Box::new(self.files_under_query(root)));
+        }
+        if let Some(collection) = &options.collection {
+            scopes.push(self.collection_scope(collection)?);
+        }
+        if !filtered.repos.is_empty() {
+            let repos: Vec<Box<dyn Query>> = filtered.repos.iter()
+                .map(|repo| -> Box<dyn Query> {
+                    Box::new(TermQuery::new(Term::from_field_text(self.repo_field, repo), IndexRecordOption::Basic))
+                })
+                .collect();
+            scopes.push(Box::new(BooleanQuery::union(repos)));
+        }
+        for label in &filtered.labels {
+            scopes.push(Box::new(TermQuery::new(Term::from_field_text(self.labels_field, label), IndexRecordOption::Basic)));
+        }
+        if !filtered.types.is_empty() {
+            let types: Vec<Box<dyn Query>> = filtered.types.iter()
+                .map(|file_type| -> Box<dyn Query> {
+                    Box::new(TermQuery::new(Term::from_facet(self.type_field, &type_filter_facet(file_type)), IndexRecordOption::Basic))
+                })
+                .collect();
+            scopes.push(Box::new(BooleanQuery::union(types)));
+        }
+        if let Some(depth) = filtered.depth {
+            let roots = match &options.root {
+                Some(root) => vec![root.clone()],
+                None => self.indexed_roots(),
+            };
+            scopes.push(self.depth_query(depth, &roots));
+        }
+        if let Some(profile) = settings.active_profile() {
+            scopes.extend(self.profile_scopes(profile));
+        }
+        if filtered.filters.is_empty() && scopes.is_empty() {
+            return Ok(PreparedQuery { query: text_query, file_filter: None });
+        }
+
+        // Chunks of large files are matched on their text alone and checked
+        // against the filters through their file
+        let file_filter = filtered.apply(Box::new(AllQuery), scopes.iter().map(|scope| scope.box_clone()).collect());
+        let query: Box<dyn Query> = if filtered.text.trim().is_empty() {
+            filtered.apply(text_query, scopes)
+        } else {
+            let chunks = self.chunks_matching(text_query.box_clone());
+            Box::new(DisjunctionMaxQuery::new(vec![filtered.apply(text_query, scopes), chunks]))
+        };
+        Ok(PreparedQuery { query, file_filter: Some(file_filter) })
+    }
+
+    /// Counts the files `query` matches per category and extension, over
+    /// every match rather than just the returned results.
+    fn count_file_types(&self, searcher: &Searcher, query: &dyn Query) -> Result<SearchFacets, String> {
+        // A collector can't count a facet and its descendants at once, so
+        // categories and the extensions within them are counted separately
+        let mut by_category = FacetCollector::for_field("type");
+        by_category.add_facet("/type");
+        let mut by_extension = FacetCollector::for_field("type");
+        for category in FILE_CATEGORIES {
+            by_extension.add_facet(Facet::from_path(["type", *category]));
+        }
+        let (category_counts, counts) = searcher.search(query, &(by_category, by_extension))
+            .map_err(|e| format!("Failed to count file types: {}", e))?;
+
+        let segment = |facet: &Facet| facet.to_path().last().map(|segment| segment.to_string()).unwrap_or_default();
+        let mut categories = Vec::new();
+        let mut extensions: HashMap<String, usize> = HashMap::new();
+        for (category, total) in category_counts.get("/type") {
+            let mut with_extension = 0;
+            for (extension, count) in counts.get(category.clone()) {
+                *extensions.entry(segment(extension)).or_default() += count as usize;
+                with_extension += count;
+            }
+            // Files without an extension sit on the category itself
+            if total > with_extension {
+                *extensions.entry(String::new()).or_default() += (total - with_extension) as usize;
+            }
+            categories.push((segment(category), total as usize));
+        }
+        Ok(SearchFacets::from_counts(extensions, categories))
+    }
+
+    /// Matches files within `depth` of whichever of `roots` they are under.
+    /// Depth is stored per document as the number of path components, so
+    /// this is a range query per root offset by the root's own depth.
+    fn depth_query(&self, depth: DepthFilter, roots: &[PathBuf]) -> Box<dyn Query> {
+        let shifted = |root: &Path| {
+            let offset = root.components().count() as u64;
+            let shift = |bound: Bound<u64>| match bound {
+                Bound::Included(depth) => Bound::Included(depth + offset),
+                Bound::Excluded(depth) => Bound::Excluded(depth + offset),
+                Bound::Unbounded => Bound::Unbounded,
+            };
+            RangeQuery::new_u64_bounds("depth".to_string(), shift(depth.lower), shift(depth.upper))
+        };
+        // Without a root to count from, count from the top of the filesystem
+        if roots.is_empty() {
+            return Box::new(shifted(Path::new(std::path::MAIN_SEPARATOR_STR)));
+        }
+        let per_root: Vec<Box<dyn Query>> = roots.iter()
+            .map(|root| -> Box<dyn Query> {
+                Box::new(BooleanQuery::intersection(vec![
+                    Box::new(self.files_under_query(root)),
+                    Box::new(shifted(root)),
+                ]))
+            })
+            .collect();
+        Box::new(BooleanQuery::union(per_root))
+    }
+
+    fn run_search(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<SearchResponse, String> {
+        // Searches only ever touch the shared reader, never the writer lock
+        let (searcher, snapshot) = self.snapshot_searcher(options.snapshot)?;
+        let original_query = query;
+        let PreparedQuery { query, file_filter } = self.prepare_query(&searcher, query, options)?;
+        
+        let settings = self.settings.get();
+        let boosts = BoostMatcher::new(&settings.file_type_boosts)?;
+        let clicks = if settings.learn_from_clicks {
+            self.learning.clicks_for(original_query)
+        } else {
+            HashMap::new()
+        };
+        let wanted = options.offset + SEARCH_RESULT_LIMIT;
+        let candidates = if boosts.is_empty() && clicks.is_empty() {
+            wanted
+        } else {
+            wanted * BOOSTED_CANDIDATE_FACTOR
+        };
+        let top_docs = self.ranked_top_docs(&searcher, query.as_ref(), candidates)?;
+        
+        let mut hits: Vec<(Score, serde_json::Value)> = Vec::with_capacity(top_docs.len());
+        // Where each file's best hit is, as chunks of one file can match many times
+        let mut hit_positions: HashMap<String, usize> = HashMap::new();
+        for (score, doc_address) in top_docs {
+            let retrieved_doc = searcher.doc(doc_address)
+                .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+            let Some((retrieved_doc, chunk)) = self.hit_file(&searcher, retrieved_doc, file_filter.as_deref())? else {
+                continue;
+            };
+            
+            let path = self.doc_path(&retrieved_doc)
+                .ok_or_else(|| "Document missing path field".to_string())?;
+            if let Some(&position) = hit_positions.get(&path) {
+                // Point the file's hit at a matching chunk if it has none yet
+                if let (Some(chunk), Some(doc)) = (chunk, hits[position].1.as_object_mut()) {
+                    if !doc.contains_key("chunk") {
+                        doc.insert("chunk".to_string(), serde_json::to_value(chunk)
+                            .map_err(|e| format!("Failed to serialize chunk: {}", e))?);
+                    }
+                }
+                continue;
+            }
+            
+            let path_buf = os_path::decode(&path);
+            let file_type_multiplier = boosts.multiplier(&path_buf);
+            if file_type_multiplier == 0.0 {
+                continue;
+            }
+            let click_multiplier = settings.ranking
+                .click_multiplier(clicks.get(&path).copied().unwrap_or_default());
+            let score = score * file_type_multiplier * click_multiplier;
+            
+            let mut doc = self.result_document(&retrieved_doc, &path, score, options.fields);
+            if let Some(chunk) = chunk {
+                doc.insert("chunk".to_string(), serde_json::to_value(chunk)
+                    .map_err(|e| format!("Failed to serialize chunk: {}", e))?);
+            }
+
+            if options.explain {
+                let modified = retrieved_doc.get_first(self.modified_field)
+                    .and_then(|f| f.as_u64())
+                    .unwrap_or_default();
+                let mut explanation = self.explain_hit(&searcher, query.as_ref(), doc_address, score, modified)?;
+                explanation.file_type_multiplier = file_type_multiplier;
+                explanation.click_multiplier = click_multiplier;
+                doc.insert("explain".to_string(), serde_json::to_value(explanation)
+                    .map_err(|e| format!("Failed to serialize explanation: {}", e))?);
+            }
+            
+            hit_positions.insert(path, hits.len());
+            hits.push((score, serde_json::Value::Object(doc)));
+        }
+        
+        // Boosts and clicks can reorder candidates, so re-rank before cutting down to the limit.
+        // The sort is stable, so ties keep the collector's document order: equal scores come
+        // back in the same order every time, and pages of one snapshot split them the same way
+        hits.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let facets = if options.facets {
+            Some(self.count_file_types(&searcher, query.as_ref())?)
+        } else {
+            None
+        };
+        if hits.is_empty() && options.offset == 0 && !options.incognito && self.records_history() {
+            self.zero_results.record(original_query);
+        }
+        hits.drain(..options.offset.min(hits.len()));
+        hits.truncate(SEARCH_RESULT_LIMIT);
+        if self.result_fields(options.fields) >= ResultFields::Snippets {
+            self.add_snippets(&searcher, query.as_ref(), &mut hits)?;
+        }
+        let mut results: Vec<serde_json::Value> = hits.into_iter().map(|(_, doc)| doc).collect();
+        self.overlay_pending(original_query, options.fields, options.offset, &mut results);
+        Ok(SearchResponse {
+            results,
+            index_completeness: self.index_completeness(),
+            facets,
+            shed: self.shed_measures(),
+            snapshot: Some(snapshot),
+        })
+    }
+
+    /// The JSON for one hit, with as many of its stored fields as `fields` asks for.
+    fn result_document(
+        &self,
+        retrieved_doc: &Document,
+        path: &str,
+        score: Score,
+        fields: ResultFields,
+    ) -> serde_json::Map<String, serde_json::Value> {
+        let mut doc = serde_json::Map::new();
+        let stored = retrieved_doc.get_first(self.path_field).and_then(|f| f.as_text()).unwrap_or(path);
+        doc.insert("id".to_string(), serde_json::Value::String(lookup::result_id(stored)));
+        doc.insert("path".to_string(), serde_json::Value::String(path.to_string()));
+        if fields < ResultFields::Metadata {
+            return doc;
+        }
+
+        let modified = retrieved_doc.get_first(self.modified_field)
+            .and_then(|f| f.as_u64())
+            .unwrap_or_default();
+        
+        let size = retrieved_doc.get_first(self.size_field)
+            .and_then(|f| f.as_u64())
+            .unwrap_or_default();
+        
+        let name = retrieved_doc.get_first(self.name_field)
+            .and_then(|f| f.as_text())
+            .or_else(|| Path::new(path).file_name().and_then(|n| n.to_str()))
+            .unwrap_or_default();
+        
+        doc.insert("name".to_string(), serde_json::Value::String(name.to_string()));
+        doc.insert("size".to_string(), serde_json::Value::Number(serde_json::Number::from(size)));
+        doc.insert("modified".to_string(), serde_json::Value::Number(serde_json::Number::from(modified)));
+        let kinds: Vec<serde_json::Value> = retrieved_doc.get_all(self.kind_field)
+            .filter_map(|f| f.as_text())
+            .map(|kind| serde_json::Value::String(kind.to_string()))
+            .collect();
+        if !kinds.is_empty() {
+            doc.insert("kinds".to_string(), serde_json::Value::Array(kinds));
+        }
+        if let Some(photo) = self.stored_photo(retrieved_doc) {
+            if let Ok(photo) = serde_json::to_value(photo) {
+                doc.insert("photo".to_string(), photo);
+            }
+        }
+        if let Some(music) = self.stored_music(retrieved_doc) {
+            if let Ok(music) = serde_json::to_value(music) {
+                doc.insert("music".to_string(), music);
+            }
+        }
+        if let Some(binary) = self.stored_binary(retrieved_doc) {
+            if let Ok(binary) = serde_json::to_value(binary) {
+                doc.insert("binary".to_string(), binary);
+            }
+        }
+        let tables: Vec<serde_json::Value> = retrieved_doc.get_all(self.table_field)
+            .filter_map(|f| f.as_text())
+            .map(|table| serde_json::Value::String(table.to_string()))
+            .collect();
+        if !tables.is_empty() {
+            doc.insert("tables".to_string(), serde_json::Value::Array(tables));
+        }
+        let labels: Vec<serde_json::Value> = retrieved_doc.get_all(self.labels_field)
+            .filter_map(|f| f.as_text())
+            .map(|label| serde_json::Value::String(label.to_string()))
+            .collect();
+        if !labels.is_empty() {
+            doc.insert("labels".to_string(), serde_json::Value::Array(labels));
+        }
+        if let Some(repo_root) = self.doc_path_in(retrieved_doc, self.repo_root_field) {
+            if let Ok(repo) = serde_json::to_value(self.repository_info(path, &repo_root)) {
+                doc.insert("repo".to_string(), repo);
+            }
+        }
+        
+        // Convert score to f64 and handle the Option with a default value
+        if let Some(score_num) = serde_json::Number::from_f64(score as f64) {
+            doc.insert("score".to_string(), serde_json::Value::Number(score_num));
+        } else {
+            doc.insert("score".to_string(), serde_json::Value::Number(serde_json::Number::from(0)));
+        }
+        doc
+    }
+
+    /// Highlights the matched terms in each hit's text, in the matching
+    /// chunk for large files, or in its path for files whose contents
+    /// aren't indexed.
+    fn add_snippets(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        hits: &mut [(Score, serde_json::Value)],
+    ) -> Result<(), String> {
+        let content_snippets = SnippetGenerator::create(searcher, query, self.content_field)
+            .map_err(|e| format!("Failed to create snippet generator: {}", e))?;
+        let path_snippets = SnippetGenerator::create(searcher, query, self.path_field)
+            .map_err(|e| format!("Failed to create snippet generator: {}", e))?;
+        for (_, doc) in hits.iter_mut() {
+            let Some(doc) = doc.as_object_mut() else {
+                continue;
+            };
+            let path = doc.get("path").and_then(|path| path.as_str()).unwrap_or_default().to_string();
+            let size = doc.get("size").and_then(|size| size.as_u64()).unwrap_or_default();
+            let chunk = doc.get("chunk").and_then(|chunk| serde_json::from_value::<ChunkLocation>(chunk.clone()).ok());
+            let content = match chunk {
+                Some(chunk) => self.chunk_content(&os_path::decode(&path), &chunk),
+                None => self.extract_content(&os_path::decode(&path), size),
+            };
+            let snippet = content.as_ref()
+                .map(|content| content_snippets.snippet(&self.redact_secrets(content.text.clone())))
+                .filter(|snippet| !snippet.is_empty())
+                .unwrap_or_else(|| path_snippets.snippet(&path));
+            doc.insert("snippet".to_string(), serde_json::Value::String(snippet.to_html()));
+
+            // Timed lines of subtitles and videos where the match is spoken
+            let lines: Vec<Cue> = content.iter()
+                .flat_map(|content| &content.cues)
+                .filter_map(|cue| {
+                    let snippet = content_snippets.snippet(&cue.text);
+                    (!snippet.highlighted().is_empty()).then(|| Cue { text: snippet.to_html(), ..cue.clone() })
+                })
+                .take(MAX_TRANSCRIPT_LINES)
+                .collect();
+            if !lines.is_empty() {
+                doc.insert("transcript".to_string(), serde_json::to_value(lines)
+                    .map_err(|e| format!("Failed to serialize transcript: {}", e))?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Breaks a hit's score down into the matched terms, field boosts and
+    /// recency multiplier that produced it.
+    fn explain_hit(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        doc_address: DocAddress,
+        final_score: Score,
+        modified: u64,
+    ) -> Result<ScoreExplanation, String> {
+        let explanation = query.explain(searcher, doc_address)
+            .map_err(|e| format!("Failed to explain score: {}", e))?;
+        let weights = self.ranking_weights();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut terms = Vec::new();
+        query.query_terms(&mut |term, _| terms.push(term.clone()));
+
+        let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+        let schema = self.index.schema();
+        let mut matched_terms = Vec::new();
+        for term in terms {
+            let inverted_index = segment_reader.inverted_index(term.field())
+                .map_err(|e| format!("Failed to open postings: {}", e))?;
+            let postings = inverted_index.read_postings(&term, IndexRecordOption::WithFreqs)
+                .map_err(|e| format!("Failed to read postings: {}", e))?;
+            let Some(mut postings) = postings else {
+                continue;
+            };
+            if postings.seek(doc_address.doc_id) == TERMINATED || postings.doc() != doc_address.doc_id {
+                continue;
+            }
+            let field = schema.get_field_name(term.field()).to_string();
+            matched_terms.push(MatchedTerm {
+                boost: weights.field_boost(&field),
+                field,
+                term: term.value().as_str().unwrap_or_default().to_string(),
+                term_frequency: postings.term_freq(),
+            });
+        }
+
+        Ok(ScoreExplanation {
+            text_score: explanation.value(),
+            recency_multiplier: weights.recency_multiplier(modified, now),
+            file_type_multiplier: 1.0,
+            click_multiplier: 1.0,
+            final_score,
+            matched_terms,
+            details: serde_json::to_value(&explanation)
+                .map_err(|e| format!("Failed to serialize explanation: {}", e))?,
+        })
+    }
+
+    /// Returns every indexed document located under `root`, as recorded at the last commit.
+    pub async fn documents_under(&self, root: impl AsRef<Path>) -> Result<Vec<IndexedFile>, String> {
+        let root = root.as_ref();
+        let reader = self.get_reader().await
+            .map_err(|e| format!("Failed to get reader: {}", e))?;
+        let searcher = reader.searcher();
+
+        let addresses = searcher.search(&AllQuery, &DocSetCollector)
+            .map_err(|e| format!("Failed to collect documents: {}", e))?;
+
+        let mut files = Vec::new();
+        for doc_address in addresses {
+            let retrieved_doc = searcher.doc(doc_address)
+                .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+
+            let path = match self.doc_path(&retrieved_doc) {
+                Some(path) => path,
+                None => continue,
+            };
+            if !Path::new(&path).starts_with(root) {
+                continue;
+            }
+
+            files.push(IndexedFile {
+                path,
+                size: retrieved_doc.get_first(self.size_field)
+                    .and_then(|f| f.as_u64())
+                    .unwrap_or_default(),
+                modified: retrieved_doc.get_first(self.modified_field)
+                    .and_then(|f| f.as_u64())
+                    .unwrap_or_default(),
+            });
+        }
+
+        Ok(files)
+    }
+
+    /// Notes that `path` was opened from the results for `query`; returns
+    /// how often it has been picked for that query so far.
+    pub fn record_result_click(&self, query: &str, path: &str) -> Result<u32, String> {
+        if !self.settings.get().learn_from_clicks || !self.records_history() {
+            return Ok(0);
+        }
+        self.learning.record(query, path)
+    }
+
+    /// Files recently added or updated by incremental updates, newest
+    /// first, within the active profile.
+    pub fn recent_changes(&self, limit: usize, kinds: &[ChangeKind]) -> Vec<RecentChange> {
+        let settings = self.settings.get();
+        let Some(profile) = settings.active_profile() else {
+            return self.recent_changes.recent(limit, kinds, settings.recent_changes_days);
+        };
+        self.recent_changes.recent(usize::MAX, kinds, settings.recent_changes_days)
+            .into_iter()
+            .filter(|change| profile.is_visible(Path::new(&change.path)))
+            .take(limit)
+            .collect()
+    }
+
+    pub fn clear_learning_data(&self) -> Result<(), String> {
+        self.learning.clear()
+    }
+
+    /// Queries that found nothing, most frequent first, each with the
+    /// configuration problems that could explain it.
+    pub async fn zero_result_queries(&self) -> Result<Vec<ZeroResultQuery>, String> {
+        let num_docs = self.get_reader().await
+            .map_err(|e| format!("Failed to get reader: {}", e))?
+            .searcher()
+            .num_docs();
+
+        let mut report = Vec::new();
+        for entry in self.zero_results.entries() {
+            let likely_causes = self.diagnose_zero_results(&entry.query, num_docs).await?;
+            report.push(ZeroResultQuery {
+                query: entry.query,
+                count: entry.count,
+                last_seen: entry.last_seen,
+                likely_causes,
+            });
+        }
+        Ok(report)
+    }
+
+    async fn diagnose_zero_results(&self, query: &str, num_docs: u64) -> Result<Vec<ZeroResultCause>, String> {
+        if num_docs == 0 {
+            return Ok(vec![ZeroResultCause::IndexEmpty]);
+        }
+
+        let mut causes = Vec::new();
+        let exclusions = self.exclusions();
+        if !self.power.content_extraction_allowed() {
+            causes.push(ZeroResultCause::ContentNotIndexed {
+                reason: "file contents aren't indexed under the current power policy".to_string(),
+            });
+        }
+        for term in query_terms(query) {
+            let path = Path::new(term);
+            if exclusions.is_excluded(path) {
+                causes.push(ZeroResultCause::PathExcluded { term: term.to_string() });
+            } else if path.is_absolute() && path.is_dir() {
+                if self.documents_under(path).await?.is_empty() {
+                    causes.push(ZeroResultCause::RootNotIndexed { path: path.to_path_buf() });
+                }
+            } else {
+                let extension = term.trim_start_matches('.');
+                let known_type = mime_guess::from_ext(extension).first().is_some();
+                if known_type && Format::for_path(&Path::new("file").with_extension(extension)).is_none() {
+                    causes.push(ZeroResultCause::ContentNotIndexed {
+                        reason: format!("only the text of text files is indexed, not the contents of {} files", term),
+                    });
+                }
+            }
+        }
+        Ok(causes)
+    }
+
+    /// Shows what changed in `path` since the last index pass retained a copy of it.
+    pub fn get_file_diff(&self, path: impl AsRef<Path>) -> Result<FileDiffReport, String> {
+        self.snapshots.diff(path.as_ref())
+    }
+
+    pub fn set_diff_retention(&self, enabled: bool) {
+        info!("Previous-version retention {}", if enabled { "enabled" } else { "disabled" });
+        self.snapshots.set_enabled(enabled);
+    }
+
+    /// Stops a running index pass after the current file. What was indexed
+    /// up to that point is committed and kept.
+    pub async fn cancel(&self) -> Result<(), String> {
+        if !self.is_running() {
+            return Ok(());
+        }
+        info!("Cancellation requested");
+        self.cancel_requested.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+} 
+
+/// Runs blocking index work. On a multi-threaded runtime the worker hands
+/// its other tasks, searches included, to the rest of the pool meanwhile.
+fn run_blocking<T>(work: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => tokio::task::block_in_place(work),
+        _ => work(),
+    }
+}
+
+/// Adds `docs` to `writer`, returning how many were rejected.
+fn add_documents(writer: &mut IndexWriter, docs: &[Document]) -> usize {
+    let mut rejected = 0;
+    for (i, doc) in docs.iter().enumerate() {
+        let result = if i == 0 && chaos::inject(Fault::PoisonedBatch) {
+            Err("injected poisoned document".to_string())
+        } else {
+            writer.add_document(doc.clone()).map(|_| ()).map_err(|e| e.to_string())
+        };
+        if let Err(e) = result {
+            error!("Failed to add document: {}", e);
+            rejected += 1;
+        }
+    }
+    rejected
+}
+
+/// Opens the index at `index_path`, rebuilding it from scratch when the
+/// on-disk schema no longer matches the one this build expects.
+fn open_or_create_index(index_path: &Path, schema: Schema) -> Result<(Index, IndexOrigin), String> {
+    let mut origin = IndexOrigin::Created;
+    if index_path.join("meta.json").exists() {
+        info!("Opening existing index at {:?}", index_path);
+        let index = Index::open_in_dir(index_path)
+            .map_err(|e| format!("Failed to open existing index: {}", e))?;
+        if index.schema() == schema {
+            return Ok((index, IndexOrigin::Existing));
+        }
+
+        warn!("Index schema changed, rebuilding index at {:?}", index_path);
+        drop(index);
+        fs::remove_dir_all(index_path)
+            .map_err(|e| format!("Failed to remove outdated index: {}", e))?;
+        fs::create_dir_all(index_path)
+            .map_err(|e| format!("Failed to create index directory: {}", e))?;
+        origin = IndexOrigin::Recreated;
+    }
+
+    info!("Creating new index at {:?}", index_path);
+    Index::create_in_dir(index_path, schema)
+        .map(|index| (index, origin))
+        .map_err(|e| format!("Failed to create index: {}", e))
+}
@@ -0,0 +1,96 @@
+//! Keeps the index in step with moved files: those the app moves or
+//! deletes itself, without waiting for the watcher or a reconciliation,
+//! and those the watcher reports as a deletion in one place and a creation
+//! in another, as when folders get reorganized.
+
+use std::path::{Path, PathBuf};
+use log::warn;
+use tantivy::collector::TopDocs;
+use tantivy::query::TermQuery;
+use tantivy::schema::IndexRecordOption;
+use tantivy::Document;
+use crate::watcher::ChangeType;
+use crate::file_system::os_path;
+use super::{IndexManager, UpdateSummary};
+
+impl IndexManager {
+    /// Updates the index after each of `moves`, in order, was carried out.
+    /// Either end may be a folder. Files only get indexed at their new
+    /// place when it is under an indexed root; collections follow them
+    /// either way.
+    pub async fn index_moves(&self, moves: &[(PathBuf, PathBuf)]) -> Result<UpdateSummary, String> {
+        let roots = self.indexed_roots();
+        let mut changes = Vec::new();
+        for (from, to) in moves {
+            self.collections.rebase(from, to);
+            changes.push((from.clone(), ChangeType::Deleted));
+            if self.fs.metadata(to).is_ok_and(|metadata| metadata.is_dir) {
+                let indexed = self.documents_under(from).await?;
+                changes.extend(indexed.into_iter().map(|file| (os_path::decode(&file.path), ChangeType::Deleted)));
+            }
+            if roots.iter().any(|root| to.starts_with(root)) {
+                changes.extend(self.fs.walk(to).into_iter().flatten().map(|path| (path, ChangeType::Created)));
+            }
+        }
+        self.apply_changes(&changes).await
+    }
+
+    /// Pairs deletions in `changes` with creations of the same file
+    /// elsewhere: same name, size and modification time, and same content
+    /// when its hash is on record. Each pair is `(from, to)`.
+    pub(super) async fn detect_moves(&self, changes: &[(PathBuf, ChangeType)]) -> Vec<(PathBuf, PathBuf)> {
+        let exclusions = self.exclusions();
+        let created: Vec<&PathBuf> = changes.iter()
+            .filter(|(path, change)| matches!(change, ChangeType::Created) && !exclusions.is_excluded(path))
+            .map(|(path, _)| path)
+            .collect();
+        let mut moves: Vec<(PathBuf, PathBuf)> = Vec::new();
+        if created.is_empty() {
+            return moves;
+        }
+        for (from, change) in changes {
+            if !matches!(change, ChangeType::Deleted) || self.fs.metadata(from).is_ok() {
+                continue;
+            }
+            for to in &created {
+                if to.file_name() != from.file_name() || moves.iter().any(|(_, taken)| taken == *to) {
+                    continue;
+                }
+                let Ok(metadata) = self.fs.metadata(to) else {
+                    continue;
+                };
+                if metadata.is_file && self.tracker.same_file(from, to, &metadata).await {
+                    moves.push((from.clone(), (*to).clone()));
+                    break;
+                }
+            }
+        }
+        moves
+    }
+
+    /// The document of a file moved from `from` to `to`, built from its
+    /// old one so its image labels and photo details carry over. What else
+    /// is kept about the file moves with it: change tracking and its
+    /// importance score, collections, the retained copy and any transcript.
+    pub(super) async fn carry_move(&self, from: &Path, to: &Path) -> Result<Document, String> {
+        let searcher = self.reader.searcher();
+        let query = TermQuery::new(self.path_term(from), IndexRecordOption::Basic);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(1))
+            .map_err(|e| format!("Failed to look up {}: {}", from.display(), e))?;
+        let (_, doc_address) = top_docs.into_iter().next()
+            .ok_or_else(|| format!("{} isn't indexed", from.display()))?;
+        let previous = searcher.doc(doc_address)
+            .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+
+        // The transcript has to be in place before the document is built
+        self.transcription.rebase(from, to);
+        let doc = self.build_document(to, Some(&previous))?;
+        self.tracker.rebase(from, to).await;
+        self.collections.rebase(from, to);
+        self.recent_changes.rebase(from, to);
+        if let Err(e) = self.snapshots.rename(from, to) {
+            warn!("Failed to keep the retained copy of {}: {}", from.display(), e);
+        }
+        Ok(doc)
+    }
+}
@@ -0,0 +1,224 @@
+//! Music tags for the index, and a library browser on top of them: tracks
+//! grouped by artist, album or genre, read from stored tags without
+//! touching the files.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tantivy::collector::DocSetCollector;
+use tantivy::query::{AllQuery, BooleanQuery, ConstScoreQuery, Query, TermQuery};
+use tantivy::schema::{IndexRecordOption, Term};
+use tantivy::Document;
+use ts_rs::TS;
+use crate::extract::music::{self, MusicTags, MUSIC_EXTENSIONS};
+use crate::file_system::os_path;
+use crate::search::{type_filter_facet, SearchOptions};
+use super::{lookup, IndexManager, PreparedQuery};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum MusicGrouping {
+    #[default]
+    Artist,
+    /// Albums are told apart by their album artist, or their artist.
+    Album,
+    Genre,
+}
+
+/// Which tracks to browse. Tag filters match whole values, ignoring case.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct MusicFilters {
+    /// Search text and filters, as typed in the search box; every track
+    /// when empty.
+    pub query: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    /// Only tracks below this folder.
+    pub root: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct MusicTrack {
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    pub tags: MusicTags,
+    #[ts(type = "number | null")]
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct MusicGroup {
+    /// The artist, album or genre; `None` for tracks without that tag.
+    pub name: Option<String>,
+    /// Who an album is by, for album groups.
+    pub artist: Option<String>,
+    /// In album order: by album, then track number, then title.
+    pub tracks: Vec<MusicTrack>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct MusicLibrary {
+    /// Sorted by name, ignoring case, with untagged tracks last.
+    pub groups: Vec<MusicGroup>,
+    pub tracks: usize,
+}
+
+impl MusicFilters {
+    fn matches(&self, tags: &MusicTags) -> bool {
+        let matches = |wanted: &Option<String>, value: &Option<String>| match wanted {
+            Some(wanted) => value.as_ref().is_some_and(|value| value.to_lowercase() == wanted.trim().to_lowercase()),
+            None => true,
+        };
+        matches(&self.artist, &tags.artist) && matches(&self.album, &tags.album) && matches(&self.genre, &tags.genre)
+    }
+}
+
+impl MusicTrack {
+    fn album_artist(&self) -> Option<&String> {
+        self.tags.album_artist.as_ref().or(self.tags.artist.as_ref())
+    }
+
+    /// Album, then track number, then title or name, ignoring case.
+    fn album_order(&self) -> (String, u32, String) {
+        let title = self.tags.title.as_deref().unwrap_or(&self.name);
+        (
+            self.tags.album.as_deref().unwrap_or_default().to_lowercase(),
+            self.tags.track.unwrap_or(u32::MAX),
+            title.to_lowercase(),
+        )
+    }
+}
+
+impl IndexManager {
+    /// Tags of the music file at `path`, when the power policy allows
+    /// reading it.
+    pub(super) fn music_tags(&self, path: &Path, size: u64) -> Option<MusicTags> {
+        if !music::is_music(path) || size == 0 || !self.power.content_extraction_allowed() {
+            return None;
+        }
+        // Tags sit at the start and end; the audio between is never read
+        let bytes = match self.fs.map(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Failed to read {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        let tags = music::tags(&bytes);
+        (!tags.is_empty()).then_some(tags)
+    }
+
+    pub(super) fn add_music_tags(&self, doc: &mut Document, tags: &MusicTags) {
+        let text_tags = [
+            (self.title_field, &tags.title),
+            (self.artist_field, &tags.artist),
+            (self.album_artist_field, &tags.album_artist),
+            (self.album_field, &tags.album),
+            (self.genre_field, &tags.genre),
+        ];
+        for (field, value) in text_tags {
+            if let Some(value) = value {
+                doc.add_text(field, value);
+            }
+        }
+        if let Some(track) = tags.track {
+            doc.add_u64(self.track_field, track.into());
+        }
+    }
+
+    /// The music tags stored with `retrieved_doc`, if any.
+    pub(super) fn stored_music(&self, retrieved_doc: &Document) -> Option<MusicTags> {
+        let text = |field| retrieved_doc.get_first(field).and_then(|f| f.as_text()).map(str::to_string);
+        let tags = MusicTags {
+            title: text(self.title_field),
+            artist: text(self.artist_field),
+            album_artist: text(self.album_artist_field),
+            album: text(self.album_field),
+            genre: text(self.genre_field),
+            track: retrieved_doc.get_first(self.track_field).and_then(|f| f.as_u64()).map(|track| track as u32),
+        };
+        (!tags.is_empty()).then_some(tags)
+    }
+
+    /// Music files matching `filters`, grouped by `group_by`. Untagged
+    /// tracks are included, grouped by themselves.
+    pub async fn browse_music(&self, group_by: MusicGrouping, filters: &MusicFilters) -> Result<MusicLibrary, String> {
+        let searcher = self.reader.searcher();
+        let options = SearchOptions { root: filters.root.clone(), ..SearchOptions::default() };
+        let PreparedQuery { query: text_query, file_filter } = self.prepare_query(&searcher, &filters.query, &options)?;
+        let text_query: Box<dyn Query> = if filters.query.trim().is_empty() && file_filter.is_none() {
+            Box::new(AllQuery)
+        } else {
+            text_query
+        };
+        let music: Vec<Box<dyn Query>> = MUSIC_EXTENSIONS.iter()
+            .map(|extension| -> Box<dyn Query> {
+                Box::new(TermQuery::new(Term::from_facet(self.type_field, &type_filter_facet(extension)), IndexRecordOption::Basic))
+            })
+            .collect();
+        let query = BooleanQuery::intersection(vec![
+            text_query,
+            Box::new(ConstScoreQuery::new(Box::new(BooleanQuery::union(music)), 0.0)),
+        ]);
+        let addresses = searcher.search(&query, &DocSetCollector)
+            .map_err(|e| format!("Failed to find music: {}", e))?;
+
+        // Keyed by whether the tag is missing, then name and artist
+        // lowercased, so untagged tracks sort last
+        let mut groups: BTreeMap<(bool, String, String), MusicGroup> = BTreeMap::new();
+        let mut count = 0;
+        for address in addresses {
+            let retrieved_doc = searcher.doc(address)
+                .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+            let Some(path) = self.doc_path(&retrieved_doc) else {
+                continue;
+            };
+            let tags = self.stored_music(&retrieved_doc).unwrap_or_default();
+            if !filters.matches(&tags) {
+                continue;
+            }
+            let stored = retrieved_doc.get_first(self.path_field).and_then(|f| f.as_text()).unwrap_or(&path);
+            let track = MusicTrack {
+                id: lookup::result_id(stored),
+                name: retrieved_doc.get_first(self.name_field)
+                    .and_then(|f| f.as_text())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| os_path::decode(&path).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()),
+                duration_ms: retrieved_doc.get_first(self.duration_field).and_then(|f| f.as_u64()),
+                path,
+                tags,
+            };
+
+            let (name, artist) = match group_by {
+                MusicGrouping::Artist => (track.tags.artist.clone(), None),
+                MusicGrouping::Album => (track.tags.album.clone(), track.album_artist().cloned()),
+                MusicGrouping::Genre => (track.tags.genre.clone(), None),
+            };
+            let key = (
+                name.is_none(),
+                name.as_deref().unwrap_or_default().to_lowercase(),
+                artist.as_deref().unwrap_or_default().to_lowercase(),
+            );
+            groups.entry(key)
+                .or_insert_with(|| MusicGroup { name, artist, tracks: Vec::new() })
+                .tracks
+                .push(track);
+            count += 1;
+        }
+
+        let mut groups: Vec<MusicGroup> = groups.into_values().collect();
+        for group in &mut groups {
+            group.tracks.sort_by_cached_key(|track| track.album_order());
+        }
+        Ok(MusicLibrary { groups, tracks: count })
+    }
+}
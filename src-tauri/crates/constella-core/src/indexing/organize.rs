@@ -0,0 +1,86 @@
+//! Finds the indexed files organize rules apply to.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tantivy::collector::DocSetCollector;
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, QueryClone, TermQuery};
+use tantivy::schema::{IndexRecordOption, Term};
+use crate::organize::{OrganizeRule, PlannedMove};
+use crate::file_system::os_path;
+use super::IndexManager;
+
+impl IndexManager {
+    /// Where `rules` would move indexed files, each file by the first rule
+    /// it matches. Only `candidates` are considered when given. A query
+    /// matching a chunk of a large file matches the file. Files already in
+    /// their destination are left alone, so organized files don't move
+    /// again when they are indexed there.
+    pub async fn plan_organize(&self, rules: &[OrganizeRule], candidates: Option<&[PathBuf]>) -> Result<Vec<PlannedMove>, String> {
+        let searcher = self.reader.searcher();
+        let scope = candidates.map(|paths| {
+            let terms: Vec<Box<dyn Query>> = paths.iter()
+                .flat_map(|path| [
+                    self.path_term(path),
+                    Term::from_field_text(self.chunk_of_field, &self.stored_path(path)),
+                ])
+                .map(|term| Box::new(TermQuery::new(term, IndexRecordOption::Basic)) as Box<dyn Query>)
+                .collect();
+            BooleanQuery::union(terms)
+        });
+
+        let mut claimed = HashSet::new();
+        let mut planned = Vec::new();
+        for rule in rules {
+            let matcher = rule.matcher()?;
+            let text_query: Box<dyn Query> = match rule.query.as_deref().map(str::trim) {
+                Some(query) if !query.is_empty() => self.parse_query(query)
+                    .map_err(|e| format!("Invalid query in rule {}: {}", rule.name, e))?,
+                _ => Box::new(AllQuery),
+            };
+            let mut clauses = vec![(Occur::Must, text_query)];
+            if let Some(scope) = &scope {
+                clauses.push((Occur::Must, scope.box_clone()));
+            }
+            let addresses = searcher.search(&BooleanQuery::new(clauses), &DocSetCollector)
+                .map_err(|e| format!("Failed to run rule {}: {}", rule.name, e))?;
+
+            let mut matched = Vec::new();
+            for address in addresses {
+                let doc = searcher.doc(address)
+                    .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+                let Some(path) = self.doc_path(&doc).or_else(|| self.doc_path_in(&doc, self.chunk_of_field)) else {
+                    continue;
+                };
+                let path = os_path::decode(&path);
+                let in_scope = rule.folder.as_ref().map_or(true, |folder| path.starts_with(folder));
+                let name_matches = path.file_name().is_some_and(|name| matcher.is_match(Path::new(name)));
+                if in_scope && name_matches && !claimed.contains(&path) {
+                    matched.push(path);
+                }
+            }
+            matched.sort();
+            matched.dedup();
+
+            for path in matched {
+                // The index may be behind; only existing files are moved
+                let Ok(metadata) = self.fs.metadata(&path) else {
+                    continue;
+                };
+                if metadata.is_dir {
+                    continue;
+                }
+                let modified = metadata.modified
+                    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |modified| modified.as_secs());
+                let folder = rule.destination_for(&path, modified)?;
+                claimed.insert(path.clone());
+                if path.parent() == Some(folder.as_path()) {
+                    continue;
+                }
+                planned.push(PlannedMove { rule: rule.name.clone(), path, folder });
+            }
+        }
+        Ok(planned)
+    }
+}
@@ -0,0 +1,145 @@
+//! Files the app deleted, moved or renamed, kept in memory until a commit
+//! makes the change durable in the index. Searches consult them so the
+//! results right after such an action already reflect it, even while the
+//! index writer is held elsewhere or a background service's watcher has
+//! yet to catch up.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+use crate::file_system::os_path;
+use crate::search::ResultFields;
+use super::IndexManager;
+
+/// Mutations no commit has settled by then are dropped; by then the
+/// watcher of whichever process holds the writer has caught up.
+const PENDING_MUTATION_TTL: Duration = Duration::from_secs(2 * 60);
+
+enum Mutation {
+    Deleted,
+    MovedTo(PathBuf),
+}
+
+struct PendingMutation {
+    path: PathBuf,
+    mutation: Mutation,
+    at: Instant,
+}
+
+pub(crate) struct MutationOverlay {
+    // Oldest first; later mutations of the same path win
+    pending: RwLock<Vec<PendingMutation>>,
+}
+
+impl MutationOverlay {
+    pub(crate) fn new() -> Self {
+        Self { pending: RwLock::new(Vec::new()) }
+    }
+
+    fn push(&self, path: &Path, mutation: Mutation) {
+        let mut pending = self.pending.write();
+        pending.retain(|entry| entry.at.elapsed() < PENDING_MUTATION_TTL);
+        pending.push(PendingMutation { path: path.to_path_buf(), mutation, at: Instant::now() });
+    }
+
+    /// Forgets the mutations of paths a commit just removed from or added
+    /// to the index.
+    pub(crate) fn settle(&self, committed: &[&PathBuf]) {
+        let mut pending = self.pending.write();
+        if !pending.is_empty() {
+            pending.retain(|entry| entry.at.elapsed() < PENDING_MUTATION_TTL && !committed.contains(&&entry.path));
+        }
+    }
+}
+
+/// Where `path` is now as far as `pending` mutations go: `None` when it
+/// was deleted, itself when nothing happened to it.
+fn current(pending: &[PendingMutation], path: &Path) -> Option<PathBuf> {
+    let mut current = path.to_path_buf();
+    for entry in pending.iter().filter(|entry| entry.at.elapsed() < PENDING_MUTATION_TTL) {
+        let Ok(rest) = current.strip_prefix(&entry.path) else {
+            continue;
+        };
+        match &entry.mutation {
+            Mutation::Deleted => return None,
+            Mutation::MovedTo(to) if rest.as_os_str().is_empty() => current = to.clone(),
+            Mutation::MovedTo(to) => current = to.join(rest),
+        }
+    }
+    Some(current)
+}
+
+impl IndexManager {
+    /// Makes searches leave out `path`, a file or folder, until the index
+    /// catches up with its deletion.
+    pub fn record_deleted(&self, path: &Path) {
+        self.overlay.push(path, Mutation::Deleted);
+    }
+
+    /// Makes searches show `from`, a file or folder, at `to` until the
+    /// index catches up with the move.
+    pub fn record_moved(&self, from: &Path, to: &Path) {
+        self.overlay.push(from, Mutation::MovedTo(to.to_path_buf()));
+    }
+
+    /// Brings `results` of `query` in line with pending mutations: deleted
+    /// files are left out and moved ones shown where they are now. Files
+    /// renamed to a name with every word of the query in it are added
+    /// when the index doesn't have them yet.
+    pub fn overlay_pending(&self, query: &str, fields: ResultFields, offset: usize, results: &mut Vec<serde_json::Value>) {
+        let pending = self.overlay.pending.read();
+        if pending.is_empty() {
+            return;
+        }
+        results.retain_mut(|result| {
+            let Some(path) = result["path"].as_str().map(os_path::decode) else {
+                return true;
+            };
+            let Some(current) = current(&pending, &path) else {
+                return false;
+            };
+            if current != path {
+                if let Some(object) = result.as_object_mut() {
+                    object.insert("path".to_string(), os_path::encode(&current).into());
+                    if object.contains_key("name") {
+                        let name = current.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                        object.insert("name".to_string(), name.into());
+                    }
+                }
+            }
+            true
+        });
+
+        let words: Vec<String> = query.split_whitespace()
+            .filter(|word| !word.contains(':'))
+            .map(str::to_lowercase)
+            .collect();
+        if offset > 0 || words.is_empty() {
+            return;
+        }
+        for entry in pending.iter() {
+            let Mutation::MovedTo(to) = &entry.mutation else {
+                continue;
+            };
+            let Some(name) = to.file_name().map(|name| name.to_string_lossy().to_lowercase()) else {
+                continue;
+            };
+            let path = os_path::encode(to);
+            let matches = words.iter().all(|word| name.contains(word.as_str()));
+            let listed = results.iter().any(|result| result["path"].as_str() == Some(path.as_str()));
+            if !matches || listed || current(&pending, to).as_deref() != Some(to.as_path()) {
+                continue;
+            }
+            if !self.fs.metadata(to).is_ok_and(|metadata| metadata.is_file) {
+                continue;
+            }
+            let mut result = serde_json::Map::new();
+            result.insert("path".to_string(), path.into());
+            if fields > ResultFields::Paths {
+                result.insert("name".to_string(), to.file_name().unwrap_or_default().to_string_lossy().into_owned().into());
+                result.insert("score".to_string(), 0.into());
+            }
+            results.push(serde_json::Value::Object(result));
+        }
+    }
+}
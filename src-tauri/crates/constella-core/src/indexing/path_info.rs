@@ -0,0 +1,98 @@
+//! Per-folder statistics for each level of a path, for breadcrumbs.
+
+use std::ops::Bound;
+use std::path::Path;
+use serde::Serialize;
+use tantivy::collector::{Count, DocSetCollector};
+use tantivy::query::{BooleanQuery, Query, RangeQuery, TermQuery};
+use tantivy::schema::{IndexRecordOption, Term};
+use tantivy::Searcher;
+use ts_rs::TS;
+use super::listing::descendant_bounds;
+use super::IndexManager;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct FolderStats {
+    pub path: String,
+    pub name: String,
+    /// Indexed files anywhere below the folder.
+    pub file_count: usize,
+    #[ts(type = "number")]
+    pub total_size: u64,
+    /// Newest modification time among those files, in unix seconds.
+    #[ts(type = "number | null")]
+    pub last_modified: Option<u64>,
+}
+
+impl IndexManager {
+    /// Stats for every folder from the root down to `path`, or down to the
+    /// folder containing it when `path` is an indexed file.
+    pub async fn get_path_info(&self, path: impl AsRef<Path>) -> Result<Vec<FolderStats>, String> {
+        let path = path.as_ref();
+        let searcher = self.reader.searcher();
+        let is_indexed_file = {
+            let query = TermQuery::new(self.path_term(path), IndexRecordOption::Basic);
+            searcher.search(&query, &Count)
+                .map_err(|e| format!("Failed to look up {}: {}", path.display(), e))? > 0
+        };
+        let deepest = if is_indexed_file { path.parent() } else { Some(path) };
+        let Some(deepest) = deepest else {
+            return Ok(Vec::new());
+        };
+
+        let mut folders: Vec<&Path> = deepest.ancestors().collect();
+        folders.reverse();
+        folders.into_iter()
+            .map(|folder| self.folder_stats(&searcher, folder))
+            .collect()
+    }
+
+    /// Matches every indexed file anywhere below `folder`.
+    pub(super) fn files_under_query(&self, folder: &Path) -> BooleanQuery {
+        let stored = self.stored_path(folder);
+        let (prefix, end) = descendant_bounds(Path::new(&stored));
+        let direct: Box<dyn Query> = Box::new(TermQuery::new(
+            Term::from_field_text(self.parent_field, &stored),
+            IndexRecordOption::Basic,
+        ));
+        let nested: Box<dyn Query> = Box::new(RangeQuery::new_str_bounds(
+            "parent".to_string(),
+            Bound::Included(prefix.as_str()),
+            Bound::Excluded(end.as_str()),
+        ));
+        BooleanQuery::union(vec![direct, nested])
+    }
+
+    fn folder_stats(&self, searcher: &Searcher, folder: &Path) -> Result<FolderStats, String> {
+        let folder_str = folder.to_string_lossy();
+        let addresses = searcher.search(&self.files_under_query(folder), &DocSetCollector)
+            .map_err(|e| format!("Failed to collect files under {}: {}", folder.display(), e))?;
+
+        // Open each segment's columns once rather than per document
+        let columns = searcher.segment_readers().iter()
+            .map(|segment_reader| {
+                let fast_fields = segment_reader.fast_fields();
+                (fast_fields.u64("size").ok(), fast_fields.u64("modified").ok())
+            })
+            .collect::<Vec<_>>();
+        let mut total_size = 0;
+        let mut last_modified = None;
+        for doc_address in &addresses {
+            let (size, modified) = &columns[doc_address.segment_ord as usize];
+            total_size += size.as_ref().and_then(|column| column.first(doc_address.doc_id)).unwrap_or_default();
+            let modified = modified.as_ref().and_then(|column| column.first(doc_address.doc_id));
+            last_modified = last_modified.max(modified);
+        }
+
+        Ok(FolderStats {
+            path: folder_str.to_string(),
+            name: folder.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| folder_str.to_string()),
+            file_count: addresses.len(),
+            total_size,
+            last_modified,
+        })
+    }
+}
@@ -0,0 +1,182 @@
+//! How file paths are written to the index. Paths are stored as they are,
+//! except in portable mode, where paths on the app's own volume are stored
+//! relative to it (`portable:/Documents/notes.txt`) so the index still
+//! finds them when the volume is mounted somewhere else. Everything that
+//! reads or looks up paths in the index goes through here.
+//!
+//! Paths that aren't valid Unicode are kept whole in the encoding of
+//! `os_path`, so their files can still be opened from results.
+//!
+//! When a folder moves, `remap_root` records where it went instead of
+//! rewriting its documents: paths keep their old form in the index and
+//! are translated on the way in and out.
+
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use log::{info, warn};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Term;
+use tantivy::Document;
+use ts_rs::TS;
+use crate::file_system::os_path;
+use super::IndexManager;
+
+/// Stands in for the volume root in stored paths.
+pub const PORTABLE_PREFIX: &str = "portable:";
+
+/// A folder whose documents are stored under `from` but live under `to`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PathRemap {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Replaces the longest of the `prefixes` that `path` starts with.
+fn swap_prefix<'a>(path: &Path, prefixes: impl Iterator<Item = (&'a Path, &'a Path)>) -> Option<PathBuf> {
+    prefixes
+        .filter_map(|(prefix, replacement)| path.strip_prefix(prefix).ok().map(|rest| (prefix, replacement, rest)))
+        .max_by_key(|(prefix, _, _)| prefix.as_os_str().len())
+        .map(|(_, replacement, rest)| if rest.as_os_str().is_empty() { replacement.to_path_buf() } else { replacement.join(rest) })
+}
+
+#[derive(Debug)]
+pub(crate) struct PathMap {
+    portable_root: Option<PathBuf>,
+    remaps_path: PathBuf,
+    remaps: RwLock<Vec<PathRemap>>,
+}
+
+impl PathMap {
+    pub(crate) fn new(portable_root: Option<PathBuf>, remaps_path: PathBuf) -> Self {
+        let remaps = match std::fs::read_to_string(&remaps_path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse path remaps at {:?}, ignoring them: {}", remaps_path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self { portable_root, remaps_path, remaps: RwLock::new(remaps) }
+    }
+
+    pub(crate) fn remaps(&self) -> Vec<PathRemap> {
+        self.remaps.read().clone()
+    }
+
+    /// Records that what was under `from` is now under `to`, folding it
+    /// into earlier remaps that pointed at `from`.
+    fn add_remap(&self, from: &Path, to: &Path) -> Result<Vec<PathRemap>, String> {
+        let mut remaps = self.remaps.write();
+        let mut updated = remaps.clone();
+        let mut folded = false;
+        for remap in updated.iter_mut() {
+            if let Ok(rest) = remap.to.strip_prefix(from) {
+                let folded_here = rest.as_os_str().is_empty();
+                let new_to = to.join(rest);
+                remap.to = new_to;
+                folded |= folded_here;
+            }
+        }
+        if !folded {
+            updated.push(PathRemap { from: from.to_path_buf(), to: to.to_path_buf() });
+        }
+        // A folder moved back to where its documents are stored needs no remap
+        updated.retain(|remap| remap.from != remap.to);
+
+        let json = serde_json::to_string_pretty(&updated)
+            .map_err(|e| format!("Failed to serialize path remaps: {}", e))?;
+        let tmp = self.remaps_path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, &self.remaps_path))
+            .map_err(|e| format!("Failed to save path remaps: {}", e))?;
+        *remaps = updated.clone();
+        Ok(updated)
+    }
+
+    /// The form of `path` kept in the index.
+    pub(crate) fn stored(&self, path: &Path) -> String {
+        let remapped = swap_prefix(path, self.remaps.read().iter().map(|remap| (remap.to.as_path(), remap.from.as_path())));
+        let path = remapped.as_deref().unwrap_or(path);
+        let relative = self.portable_root.as_ref()
+            .and_then(|root| path.strip_prefix(root).ok());
+        match relative {
+            Some(relative) if relative.as_os_str().is_empty() => PORTABLE_PREFIX.to_string(),
+            Some(relative) => format!("{}{}{}", PORTABLE_PREFIX, MAIN_SEPARATOR, os_path::encode(relative)),
+            None => os_path::encode(path),
+        }
+    }
+
+    /// Where a stored path is now.
+    pub(crate) fn current(&self, stored: &str) -> String {
+        let path = match (self.portable_root.as_ref(), stored.strip_prefix(PORTABLE_PREFIX)) {
+            (Some(root), Some(relative)) => root.join(os_path::decode(relative.trim_start_matches(MAIN_SEPARATOR))),
+            _ => os_path::decode(stored),
+        };
+        let remaps = self.remaps.read();
+        if remaps.is_empty() {
+            return os_path::encode(&path);
+        }
+        let current = swap_prefix(&path, remaps.iter().map(|remap| (remap.from.as_path(), remap.to.as_path())));
+        os_path::encode(current.as_deref().unwrap_or(&path))
+    }
+}
+
+impl IndexManager {
+    /// The volume whose paths are stored relative to it, in portable mode.
+    pub fn portable_root(&self) -> Option<&Path> {
+        self.paths.portable_root.as_deref()
+    }
+
+    /// Folders that moved since they were indexed.
+    pub fn path_remaps(&self) -> Vec<PathRemap> {
+        self.paths.remaps()
+    }
+
+    /// Points everything indexed under `old_prefix` at `new_prefix`, for a
+    /// folder that was moved or a drive whose letter changed, without
+    /// reindexing it. Settings, change tracking and collections follow
+    /// along; files that changed in the move are picked up by the next
+    /// reconciliation.
+    pub async fn remap_root(&self, old_prefix: impl AsRef<Path>, new_prefix: impl AsRef<Path>) -> Result<Vec<PathRemap>, String> {
+        let (old_prefix, new_prefix) = (old_prefix.as_ref(), new_prefix.as_ref());
+        if !old_prefix.is_absolute() || !new_prefix.is_absolute() {
+            return Err("Both folders must be absolute paths".to_string());
+        }
+        if old_prefix == new_prefix {
+            return Err("The folder hasn't moved".to_string());
+        }
+        let is_dir = self.fs.metadata(new_prefix).map(|metadata| metadata.is_dir).unwrap_or(false);
+        if !is_dir {
+            return Err(format!("{} isn't a folder", new_prefix.display()));
+        }
+
+        let remaps = self.paths.add_remap(old_prefix, new_prefix)?;
+        self.settings.update(|settings| {
+            settings.rebase_paths(old_prefix, new_prefix);
+        })?;
+        self.tracker.rebase(old_prefix, new_prefix).await;
+        self.collections.rebase(old_prefix, new_prefix);
+        info!("Remapped {:?} to {:?}", old_prefix, new_prefix);
+        Ok(remaps)
+    }
+
+    pub(super) fn stored_path(&self, path: &Path) -> String {
+        self.paths.stored(path)
+    }
+
+    /// Current path of a document, from its stored `path` field.
+    pub(super) fn doc_path(&self, doc: &Document) -> Option<String> {
+        self.doc_path_in(doc, self.path_field)
+    }
+
+    /// Current form of a path read from `field` of a document.
+    pub(super) fn doc_path_in(&self, doc: &Document, field: tantivy::schema::Field) -> Option<String> {
+        doc.get_first(field)
+            .and_then(|f| f.as_text())
+            .map(|stored| self.paths.current(stored))
+    }
+
+    pub(super) fn path_term(&self, path: &Path) -> Term {
+        Term::from_field_text(self.path_exact_field, &self.stored_path(path))
+    }
+}
@@ -0,0 +1,97 @@
+//! Photo metadata for the index, and viewable previews of photos the
+//! webview can't show itself: camera raw files and HEIC.
+
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use log::warn;
+use tantivy::Document;
+use crate::extract::photo::{self, PhotoMetadata};
+use super::IndexManager;
+
+impl IndexManager {
+    /// Date, camera and dimensions of the photo at `path`, when the power
+    /// policy allows reading it.
+    pub(super) fn photo_metadata(&self, path: &Path, size: u64) -> Option<PhotoMetadata> {
+        if !photo::is_photo(path) || size == 0 || !self.power.content_extraction_allowed() {
+            return None;
+        }
+        // Raw files run to tens of megabytes, but only their headers are read
+        let bytes = match self.fs.map(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Failed to read photo {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        let metadata = photo::metadata(&bytes);
+        (!metadata.is_empty()).then_some(metadata)
+    }
+
+    /// The photo metadata stored with `retrieved_doc`, if any.
+    pub(super) fn stored_photo(&self, retrieved_doc: &Document) -> Option<PhotoMetadata> {
+        let photo = PhotoMetadata {
+            taken: retrieved_doc.get_first(self.taken_field).and_then(|f| f.as_u64()),
+            camera: retrieved_doc.get_first(self.camera_field).and_then(|f| f.as_text()).map(str::to_string),
+            width: retrieved_doc.get_first(self.width_field).and_then(|f| f.as_u64()).map(|width| width as u32),
+            height: retrieved_doc.get_first(self.height_field).and_then(|f| f.as_u64()).map(|height| height as u32),
+        };
+        (!photo.is_empty()).then_some(photo)
+    }
+
+    /// A JPEG of the raw or HEIC photo at `path`, from the preview cache
+    /// when the photo, or a copy of it, was previewed before. `None` for
+    /// other files and for photos with nothing to show.
+    pub async fn photo_preview(&self, path: impl AsRef<Path>) -> Result<Option<PathBuf>, String> {
+        let path = path.as_ref();
+        if !photo::is_raw(path) && !photo::is_heif(path) {
+            return Ok(None);
+        }
+        let metadata = self.fs.metadata(path)
+            .map_err(|e| format!("Failed to get metadata for {}: {}", path.display(), e))?;
+        let modified = metadata.modified
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |modified| modified.as_secs());
+        let source = path.to_string_lossy();
+        if let Some(cached) = self.previews.lookup(&source, metadata.len, modified) {
+            return Ok(Some(cached));
+        }
+
+        let bytes = self.fs.map(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let hash = blake3::hash(&bytes).to_hex().to_string();
+        let cached = self.previews.path_for(&hash)?;
+        if !cached.exists() {
+            match photo::preview(&bytes) {
+                Some(jpeg) => std::fs::write(&cached, jpeg)
+                    .map_err(|e| format!("Failed to write preview: {}", e))?,
+                None if photo::is_heif(path) => {
+                    if !convert_heif(path, &cached)? {
+                        return Ok(None);
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+        self.previews.record(&source, metadata.len, modified, &hash, self.settings.get().preview_cache_max_bytes);
+        Ok(Some(cached))
+    }
+}
+
+/// HEIC photos rarely embed a JPEG, so the system converts them where it can.
+#[cfg(target_os = "macos")]
+fn convert_heif(path: &Path, output: &Path) -> Result<bool, String> {
+    let status = std::process::Command::new("sips")
+        .args(["-s", "format", "jpeg", "-Z", "2048"])
+        .arg(path)
+        .arg("--out")
+        .arg(output)
+        .output()
+        .map_err(|e| format!("Failed to run sips: {}", e))?
+        .status;
+    Ok(status.success())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn convert_heif(_path: &Path, _output: &Path) -> Result<bool, String> {
+    Ok(false)
+}
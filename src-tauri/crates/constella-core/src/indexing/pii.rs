@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::pii::{self, DirectoryPii, PiiInventory};
+use crate::file_system::os_path;
+use super::IndexManager;
+
+impl IndexManager {
+    /// Counts likely personal data in the content of every indexed file
+    /// under `root`, per directory. Must be turned on in settings first.
+    /// Stops early, returning what was counted so far, once
+    /// `should_continue` returns false; `progress` is told how many files
+    /// of the total have been looked at.
+    pub async fn pii_inventory(
+        &self,
+        root: impl AsRef<Path>,
+        should_continue: impl Fn() -> bool,
+        progress: impl Fn(usize, usize),
+    ) -> Result<PiiInventory, String> {
+        let settings = self.settings.get();
+        if !settings.pii_inventory.enabled {
+            return Err("PII inventory is turned off in settings".to_string());
+        }
+        let regions = settings.pii_inventory.effective_regions(settings.date_locale.as_deref());
+        let root = root.as_ref();
+        let files = self.documents_under(root).await?;
+
+        let total = files.len();
+        let mut files_scanned = 0;
+        let mut directories: HashMap<String, DirectoryPii> = HashMap::new();
+        for (checked, file) in files.into_iter().enumerate() {
+            if !should_continue() {
+                break;
+            }
+            let path = &os_path::decode(&file.path);
+            let content = if settings.indexing.in_dependency_folder(path) {
+                None
+            } else {
+                self.extract_content(path, file.size)
+            };
+            progress(checked + 1, total);
+            let Some(content) = content else {
+                continue;
+            };
+            files_scanned += 1;
+
+            let directory = path.parent().unwrap_or(path).to_string_lossy().into_owned();
+            let entry = directories.entry(directory.clone()).or_insert_with(|| DirectoryPii {
+                directory,
+                files_scanned: 0,
+                files_with_pii: 0,
+                counts: Default::default(),
+            });
+            entry.files_scanned += 1;
+            let counts = pii::count(&content.text, &regions);
+            if !counts.is_empty() {
+                entry.files_with_pii += 1;
+            }
+            for (category, found) in counts {
+                *entry.counts.entry(category).or_default() += found;
+            }
+        }
+
+        let mut directories: Vec<DirectoryPii> = directories.into_values()
+            .filter(|directory| directory.files_with_pii > 0)
+            .collect();
+        let matches = |directory: &DirectoryPii| directory.counts.values().sum::<u64>();
+        directories.sort_by(|a, b| matches(b).cmp(&matches(a)).then_with(|| a.directory.cmp(&b.directory)));
+
+        Ok(PiiInventory {
+            root: root.to_string_lossy().into_owned(),
+            regions,
+            generated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            files_scanned,
+            directories,
+        })
+    }
+}
@@ -0,0 +1,78 @@
+//! Index snapshots that searches can come back to. Every search reports
+//! the generation of the snapshot it ran on, and asking for that
+//! generation again through `SearchOptions::snapshot` searches the same
+//! documents, however many commits a burst of changes has made since. That
+//! keeps the pages of a result list consistent with each other while, say,
+//! a large checkout is being indexed.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+use tantivy::Searcher;
+use super::IndexManager;
+
+/// Snapshots not searched for this long are let go.
+const PIN_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// Pinning more than this many lets go of the least recently used.
+const MAX_PINNED: usize = 8;
+
+struct Pin {
+    searcher: Searcher,
+    last_used: Instant,
+}
+
+pub(crate) struct PinnedSnapshots {
+    pins: Mutex<HashMap<u64, Pin>>,
+}
+
+impl PinnedSnapshots {
+    pub(crate) fn new() -> Self {
+        Self { pins: Mutex::new(HashMap::new()) }
+    }
+
+    /// Keeps `searcher` around and returns its generation.
+    fn pin(&self, searcher: &Searcher) -> u64 {
+        let generation = searcher.generation().generation_id();
+        let mut pins = self.pins.lock();
+        pins.retain(|_, pin| pin.last_used.elapsed() < PIN_IDLE_TIMEOUT);
+        if !pins.contains_key(&generation) {
+            while pins.len() >= MAX_PINNED {
+                let Some(oldest) = pins.iter().min_by_key(|(_, pin)| pin.last_used).map(|(id, _)| *id) else {
+                    break;
+                };
+                pins.remove(&oldest);
+            }
+        }
+        pins.insert(generation, Pin { searcher: searcher.clone(), last_used: Instant::now() });
+        generation
+    }
+
+    fn get(&self, generation: u64) -> Option<Searcher> {
+        let mut pins = self.pins.lock();
+        pins.retain(|_, pin| pin.last_used.elapsed() < PIN_IDLE_TIMEOUT);
+        let pin = pins.get_mut(&generation)?;
+        pin.last_used = Instant::now();
+        Some(pin.searcher.clone())
+    }
+}
+
+impl IndexManager {
+    /// The snapshot to search: generation `snapshot` when given, which
+    /// fails once it has been let go, or else the latest, pinned so later
+    /// searches can ask for it.
+    pub(super) fn snapshot_searcher(&self, snapshot: Option<u64>) -> Result<(Searcher, u64), String> {
+        match snapshot {
+            Some(generation) => {
+                let searcher = self.pinned.get(generation)
+                    .or_else(|| Some(self.reader.searcher()).filter(|searcher| searcher.generation().generation_id() == generation))
+                    .ok_or_else(|| format!("Snapshot {} is no longer available; search again for current results", generation))?;
+                Ok((searcher, generation))
+            }
+            None => {
+                let searcher = self.reader.searcher();
+                let generation = self.pinned.pin(&searcher);
+                Ok((searcher, generation))
+            }
+        }
+    }
+}
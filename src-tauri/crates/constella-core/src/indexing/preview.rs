@@ -0,0 +1,109 @@
+//! Dry runs of indexing configuration changes: what a new set of
+//! exclusions or content limits would do to the index, without touching it.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use serde::Serialize;
+use tantivy::collector::DocSetCollector;
+use tantivy::query::AllQuery;
+use crate::extract::Format;
+use crate::scanner::PathExclusions;
+use crate::settings::IndexingConfig;
+use crate::file_system::os_path;
+use super::IndexManager;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ConfigChangePreview {
+    /// Indexed documents the new exclusions would remove.
+    pub documents_removed: usize,
+    /// Files under the indexed roots that are excluded now but wouldn't be.
+    pub files_newly_eligible: usize,
+    /// Indexed text files whose content the new size limit or dependency
+    /// folders would start indexing.
+    pub content_added: usize,
+    /// Indexed text files whose content the new size limit or dependency
+    /// folders would drop.
+    pub content_removed: usize,
+    /// A few of the affected paths, for showing alongside the counts.
+    pub sample_removed: Vec<PathBuf>,
+    pub sample_newly_eligible: Vec<PathBuf>,
+    pub roots: Vec<PathBuf>,
+}
+
+/// Paths listed per category in a preview.
+const SAMPLE_SIZE: usize = 20;
+
+impl IndexManager {
+    /// Reports what applying `config` would change. Nothing is written.
+    pub async fn preview_config_change(&self, config: &IndexingConfig) -> Result<ConfigChangePreview, String> {
+        let current = self.settings.get().indexing;
+        let current_exclusions = self.exclusions();
+        let new_exclusions = PathExclusions::new(&config.exclude)?;
+        let roots = self.indexed_roots();
+        let mut preview = ConfigChangePreview {
+            roots: roots.clone(),
+            ..ConfigChangePreview::default()
+        };
+
+        let reader = self.get_reader().await
+            .map_err(|e| format!("Failed to get reader: {}", e))?;
+        let searcher = reader.searcher();
+        let addresses = searcher.search(&AllQuery, &DocSetCollector)
+            .map_err(|e| format!("Failed to collect documents: {}", e))?;
+
+        let mut indexed = HashSet::with_capacity(addresses.len());
+        for doc_address in addresses {
+            let doc = searcher.doc(doc_address)
+                .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+            let Some(path) = self.doc_path(&doc) else {
+                continue;
+            };
+            let path = os_path::decode(&path);
+
+            if new_exclusions.is_excluded(&path) {
+                preview.documents_removed += 1;
+                if preview.sample_removed.len() < SAMPLE_SIZE {
+                    preview.sample_removed.push(path.clone());
+                }
+            } else if let Some(format) = Format::for_path(&path) {
+                let size = doc.get_first(self.size_field)
+                    .and_then(|f| f.as_u64())
+                    .unwrap_or_default();
+                let had_content = (size <= format.size_limit(current.content_max_file_size) || current.is_chunked(&path, size))
+                    && !current.in_dependency_folder(&path);
+                let gets_content = (size <= format.size_limit(config.content_max_file_size) || config.is_chunked(&path, size))
+                    && !config.in_dependency_folder(&path);
+                if gets_content && !had_content {
+                    preview.content_added += 1;
+                } else if had_content && !gets_content {
+                    preview.content_removed += 1;
+                }
+            }
+            indexed.insert(path);
+        }
+
+        for root in &roots {
+            let fs = self.fs.clone();
+            let walk_root = root.clone();
+            let entries = tokio::task::spawn_blocking(move || fs.walk(&walk_root))
+                .await
+                .map_err(|e| format!("Failed to walk {}: {}", root.display(), e))?;
+            for path in entries.into_iter().flatten() {
+                if current_exclusions.is_excluded(&path)
+                    && !new_exclusions.is_excluded(&path)
+                    && !indexed.contains(&path)
+                {
+                    preview.files_newly_eligible += 1;
+                    if preview.sample_newly_eligible.len() < SAMPLE_SIZE {
+                        preview.sample_newly_eligible.push(path);
+                    }
+                }
+            }
+        }
+
+        Ok(preview)
+    }
+}
+
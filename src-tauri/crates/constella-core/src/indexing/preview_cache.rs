@@ -0,0 +1,198 @@
+//! Generated previews kept on disk, keyed by the content hash of the file
+//! they were made from, so copies of a photo or video share one preview. The content
+//! each path had when last seen is remembered too, so an unchanged file,
+//! say on a network share, isn't read again just to find its preview.
+//! Previews over the size limit in settings are evicted, least recently
+//! used first.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{info, warn};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use super::IndexManager;
+
+const INDEX_FILE: &str = "index.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PreviewCacheUsage {
+    pub files: usize,
+    #[ts(type = "number")]
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Source {
+    size: u64,
+    modified: u64,
+    hash: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct CacheIndex {
+    /// What each path held when its preview was last asked for.
+    sources: HashMap<String, Source>,
+    /// When each preview was last used, in unix milliseconds.
+    used: HashMap<String, u64>,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+pub(crate) struct PreviewCache {
+    dir: PathBuf,
+    index: Mutex<CacheIndex>,
+}
+
+impl PreviewCache {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        let index = match std::fs::read_to_string(dir.join(INDEX_FILE)) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse preview cache index, starting empty: {}", e);
+                CacheIndex::default()
+            }),
+            Err(_) => CacheIndex::default(),
+        };
+        Self { dir, index: Mutex::new(index) }
+    }
+
+    fn file(&self, hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.jpg", hash))
+    }
+
+    /// The preview of `path` if the file hasn't changed since it was made.
+    pub(crate) fn lookup(&self, path: &str, size: u64, modified: u64) -> Option<PathBuf> {
+        let mut index = self.index.lock();
+        let source = index.sources.get(path)?;
+        if source.size != size || source.modified != modified {
+            return None;
+        }
+        let hash = source.hash.clone();
+        let file = self.file(&hash);
+        if !file.exists() {
+            return None;
+        }
+        // Kept in memory; written out with the next change to the cache
+        index.used.insert(hash, now_millis());
+        Some(file)
+    }
+
+    /// Where the preview of content `hash` goes, whether or not it exists yet.
+    pub(crate) fn path_for(&self, hash: &str) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create preview directory: {}", e))?;
+        Ok(self.file(hash))
+    }
+
+    /// Notes that `path` has content `hash`, whose preview was just used,
+    /// and evicts old previews until the cache fits in `max_bytes`.
+    pub(crate) fn record(&self, path: &str, size: u64, modified: u64, hash: &str, max_bytes: u64) {
+        let mut index = self.index.lock();
+        index.sources.insert(path.to_string(), Source { size, modified, hash: hash.to_string() });
+        index.used.insert(hash.to_string(), now_millis());
+
+        let mut previews = self.previews();
+        let mut total: u64 = previews.iter().map(|(_, bytes)| bytes).sum();
+        if total > max_bytes {
+            previews.sort_by_key(|(name, _)| index.used.get(name).copied().unwrap_or(0));
+            let mut evicted = 0;
+            for (old, bytes) in previews {
+                if total <= max_bytes {
+                    break;
+                }
+                if old == hash {
+                    continue;
+                }
+                if let Err(e) = std::fs::remove_file(self.file(&old)) {
+                    warn!("Failed to evict preview {}: {}", old, e);
+                    continue;
+                }
+                index.used.remove(&old);
+                total -= bytes;
+                evicted += 1;
+            }
+            let CacheIndex { sources, used } = &mut *index;
+            sources.retain(|_, source| used.contains_key(&source.hash));
+            info!("Evicted {} previews, {} bytes left", evicted, total);
+        }
+        if let Err(e) = self.save(&index) {
+            warn!("{}", e);
+        }
+    }
+
+    /// Every preview file by content hash, with its size.
+    fn previews(&self) -> Vec<(String, u64)> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries.flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().and_then(|extension| extension.to_str()) != Some("jpg") {
+                    return None;
+                }
+                let hash = path.file_stem()?.to_str()?.to_string();
+                Some((hash, entry.metadata().ok()?.len()))
+            })
+            .collect()
+    }
+
+    fn save(&self, index: &CacheIndex) -> Result<(), String> {
+        let json = serde_json::to_string(index)
+            .map_err(|e| format!("Failed to serialize preview cache index: {}", e))?;
+        let path = self.dir.join(INDEX_FILE);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, &path))
+            .map_err(|e| format!("Failed to save preview cache index: {}", e))
+    }
+
+    pub(crate) fn usage(&self) -> PreviewCacheUsage {
+        let previews = self.previews();
+        PreviewCacheUsage {
+            files: previews.len(),
+            bytes: previews.iter().map(|(_, bytes)| bytes).sum(),
+        }
+    }
+
+    /// Deletes every preview; returns what they took up.
+    pub(crate) fn clear(&self) -> Result<PreviewCacheUsage, String> {
+        let mut index = self.index.lock();
+        let mut cleared = PreviewCacheUsage::default();
+        for (hash, bytes) in self.previews() {
+            std::fs::remove_file(self.file(&hash))
+                .map_err(|e| format!("Failed to remove preview {}: {}", hash, e))?;
+            cleared.files += 1;
+            cleared.bytes += bytes;
+        }
+        *index = CacheIndex::default();
+        match std::fs::remove_file(self.dir.join(INDEX_FILE)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!("Failed to remove preview cache index: {}", e));
+            }
+            _ => {}
+        }
+        info!("Cleared {} previews ({} bytes)", cleared.files, cleared.bytes);
+        Ok(cleared)
+    }
+}
+
+impl IndexManager {
+    /// How much the preview cache takes up.
+    pub fn preview_cache_usage(&self) -> PreviewCacheUsage {
+        self.previews.usage()
+    }
+
+    /// Empties the preview cache; previews are made again as needed.
+    pub fn clear_preview_cache(&self) -> Result<PreviewCacheUsage, String> {
+        self.previews.clear()
+    }
+}
@@ -0,0 +1,61 @@
+//! "Index first" folders: files under them are indexed ahead of the rest of
+//! a run so they become searchable early.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Reported once every file under the prioritized folders has been committed.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PriorityCompletion {
+    pub folders: Vec<PathBuf>,
+    pub files: usize,
+    #[ts(type = "number")]
+    pub elapsed_seconds: u64,
+}
+
+/// The remaining paths of an index run, prioritized folders first in the
+/// order they were marked.
+pub(crate) struct PathQueue {
+    prioritized: VecDeque<PathBuf>,
+    rest: VecDeque<PathBuf>,
+}
+
+impl PathQueue {
+    pub(crate) fn new(paths: Vec<PathBuf>, folders: &[PathBuf]) -> Self {
+        let mut queue = Self {
+            prioritized: VecDeque::new(),
+            rest: paths.into(),
+        };
+        queue.reprioritize(folders);
+        queue
+    }
+
+    /// Re-sorts the remaining paths after the prioritized folders changed.
+    pub(crate) fn reprioritize(&mut self, folders: &[PathBuf]) {
+        let remaining = self.prioritized.drain(..).chain(self.rest.drain(..));
+        let (mut prioritized, rest): (Vec<PathBuf>, Vec<PathBuf>) = remaining
+            .partition(|path| priority_rank(path, folders).is_some());
+        prioritized.sort_by_key(|path| priority_rank(path, folders));
+        self.prioritized = prioritized.into();
+        self.rest = rest.into();
+    }
+
+    /// The next path, and whether it came from a prioritized folder.
+    pub(crate) fn pop(&mut self) -> Option<(PathBuf, bool)> {
+        if let Some(path) = self.prioritized.pop_front() {
+            return Some((path, true));
+        }
+        self.rest.pop_front().map(|path| (path, false))
+    }
+
+    pub(crate) fn prioritized_remaining(&self) -> usize {
+        self.prioritized.len()
+    }
+}
+
+fn priority_rank(path: &Path, folders: &[PathBuf]) -> Option<usize> {
+    folders.iter().position(|folder| path.starts_with(folder))
+}
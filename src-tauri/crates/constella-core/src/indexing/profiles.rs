@@ -0,0 +1,46 @@
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, TermQuery};
+use tantivy::schema::{IndexRecordOption, Term};
+use crate::profiles::Profile;
+use crate::tracking::ImportantFile;
+use super::IndexManager;
+
+impl IndexManager {
+    /// Restrictions the active profile puts on searches: its roots, and
+    /// leaving out its hidden labels.
+    pub(super) fn profile_scopes(&self, profile: &Profile) -> Vec<Box<dyn Query>> {
+        let mut scopes: Vec<Box<dyn Query>> = Vec::new();
+        if !profile.roots.is_empty() {
+            let roots: Vec<Box<dyn Query>> = profile.roots.iter()
+                .map(|root| -> Box<dyn Query> { Box::new(self.files_under_query(root)) })
+                .collect();
+            scopes.push(Box::new(BooleanQuery::union(roots)));
+        }
+        if !profile.hidden_labels.is_empty() {
+            let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, Box::new(AllQuery))];
+            for label in &profile.hidden_labels {
+                let term = Term::from_field_text(self.labels_field, label);
+                clauses.push((Occur::MustNot, Box::new(TermQuery::new(term, IndexRecordOption::Basic))));
+            }
+            scopes.push(Box::new(BooleanQuery::new(clauses)));
+        }
+        scopes
+    }
+
+    /// Whether searches and clicks are remembered in the active profile.
+    pub(super) fn records_history(&self) -> bool {
+        self.settings.get().active_profile().map_or(true, |profile| profile.record_history)
+    }
+
+    /// The files the user works with most, as suggestions, within the
+    /// active profile.
+    pub async fn suggested_files(&self, limit: usize) -> Vec<ImportantFile> {
+        let Some(profile) = self.settings.get().active_profile().cloned() else {
+            return self.tracker.top_important(limit).await;
+        };
+        self.tracker.top_important(usize::MAX).await
+            .into_iter()
+            .filter(|file| profile.is_visible(&file.path))
+            .take(limit)
+            .collect()
+    }
+}
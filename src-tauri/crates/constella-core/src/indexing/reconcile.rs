@@ -0,0 +1,236 @@
+//! Brings the index in line with changed exclusions or roots: documents
+//! that are now excluded or outside every root are removed, and files that
+//! have become eligible are indexed, without rebuilding everything. Files
+//! deleted while nothing was watching are removed the same way, also on
+//! their own through `prune_index`.
+
+use std::collections::HashSet;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use log::info;
+use serde::Serialize;
+use tokio::sync::watch;
+use crate::watcher::ChangeType;
+use crate::file_system::os_path;
+use super::IndexManager;
+use ts_rs::TS;
+
+/// Paths handed to `apply_changes` at a time; abort requests are honoured
+/// between batches.
+const RECONCILE_BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum ReconcilePhase {
+    Idle,
+    Planning,
+    Removing,
+    Adding,
+    Completed,
+    Aborted,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PruneSummary {
+    /// Indexed files looked for on disk.
+    pub checked: usize,
+    pub removed: usize,
+    /// Files kept because the root they're under can't be reached, e.g.
+    /// on a drive that isn't plugged in.
+    pub unreachable: usize,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ReconcileProgress {
+    pub phase: ReconcilePhase,
+    pub to_remove: usize,
+    pub removed: usize,
+    pub to_add: usize,
+    pub added: usize,
+}
+
+pub(crate) struct Reconciliation {
+    progress: watch::Sender<ReconcileProgress>,
+    running: AtomicBool,
+    abort_requested: AtomicBool,
+}
+
+impl Reconciliation {
+    pub(crate) fn new() -> Self {
+        let (progress, _) = watch::channel(ReconcileProgress {
+            phase: ReconcilePhase::Idle,
+            to_remove: 0,
+            removed: 0,
+            to_add: 0,
+            added: 0,
+        });
+        Self {
+            progress,
+            running: AtomicBool::new(false),
+            abort_requested: AtomicBool::new(false),
+        }
+    }
+
+    fn update(&self, update_fn: impl FnOnce(&mut ReconcileProgress)) {
+        self.progress.send_modify(update_fn);
+    }
+
+    fn should_stop(&self) -> bool {
+        self.abort_requested.load(Ordering::SeqCst)
+    }
+}
+
+impl IndexManager {
+    /// Removes documents the current exclusions and roots no longer cover and
+    /// indexes files that have become eligible. Only one run happens at a
+    /// time; an abort keeps whatever was already applied.
+    pub async fn reconcile(&self) -> Result<ReconcileProgress, String> {
+        let job = &self.reconciliation;
+        if job.running.swap(true, Ordering::SeqCst) {
+            return Err("Reconciliation is already running".to_string());
+        }
+        job.abort_requested.store(false, Ordering::SeqCst);
+        let result = self.run_reconciliation().await;
+        job.running.store(false, Ordering::SeqCst);
+        result
+    }
+
+    async fn run_reconciliation(&self) -> Result<ReconcileProgress, String> {
+        let job = &self.reconciliation;
+        job.update(|progress| {
+            *progress = ReconcileProgress {
+                phase: ReconcilePhase::Planning,
+                to_remove: 0,
+                removed: 0,
+                to_add: 0,
+                added: 0,
+            };
+        });
+
+        let exclusions = self.exclusions();
+        let roots = self.indexed_roots();
+        let indexed: HashSet<PathBuf> = self.documents_under("").await?
+            .into_iter()
+            .map(|file| os_path::decode(&file.path))
+            .collect();
+
+        // With no roots on record there is nothing to measure coverage against
+        let outside_roots = |path: &PathBuf| !roots.is_empty() && !roots.iter().any(|root| path.starts_with(root));
+        let (mut removals, kept): (Vec<PathBuf>, Vec<PathBuf>) = indexed.iter()
+            .cloned()
+            .partition(|path| exclusions.is_excluded(path) || outside_roots(path));
+        removals.extend(self.missing_files(kept.iter(), &roots).0);
+
+        let mut additions = Vec::new();
+        for root in &roots {
+            let fs = self.fs.clone();
+            let walk_root = root.clone();
+            let entries = tokio::task::spawn_blocking(move || fs.walk(&walk_root))
+                .await
+                .map_err(|e| format!("Failed to walk {}: {}", root.display(), e))?;
+            additions.extend(entries.into_iter()
+                .flatten()
+                .filter(|path| !indexed.contains(path) && !exclusions.is_excluded(path)));
+        }
+
+        info!("Reconciling index: {} to remove, {} to add", removals.len(), additions.len());
+        job.update(|progress| {
+            progress.phase = ReconcilePhase::Removing;
+            progress.to_remove = removals.len();
+            progress.to_add = additions.len();
+        });
+
+        for batch in removals.chunks(RECONCILE_BATCH_SIZE) {
+            if job.should_stop() {
+                return Ok(self.finish_reconciliation(ReconcilePhase::Aborted));
+            }
+            let changes: Vec<(PathBuf, ChangeType)> = batch.iter()
+                .map(|path| (path.clone(), ChangeType::Deleted))
+                .collect();
+            self.apply_changes(&changes).await?;
+            job.update(|progress| progress.removed += batch.len());
+        }
+
+        job.update(|progress| progress.phase = ReconcilePhase::Adding);
+        for batch in additions.chunks(RECONCILE_BATCH_SIZE) {
+            if job.should_stop() {
+                return Ok(self.finish_reconciliation(ReconcilePhase::Aborted));
+            }
+            let changes: Vec<(PathBuf, ChangeType)> = batch.iter()
+                .map(|path| (path.clone(), ChangeType::Created))
+                .collect();
+            self.apply_changes(&changes).await?;
+            job.update(|progress| progress.added += batch.len());
+        }
+
+        Ok(self.finish_reconciliation(ReconcilePhase::Completed))
+    }
+
+    fn finish_reconciliation(&self, phase: ReconcilePhase) -> ReconcileProgress {
+        info!("Reconciliation {:?}", phase);
+        self.reconciliation.update(|progress| progress.phase = phase);
+        self.reconciliation.progress.borrow().clone()
+    }
+
+    /// Stops a running reconciliation after its current batch.
+    pub fn abort_reconciliation(&self) {
+        if self.reconciliation.running.load(Ordering::SeqCst) {
+            info!("Reconciliation abort requested");
+            self.reconciliation.abort_requested.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn subscribe_reconcile_progress(&self) -> watch::Receiver<ReconcileProgress> {
+        self.reconciliation.progress.subscribe()
+    }
+
+    /// Removes the documents of indexed files that are no longer on disk.
+    pub async fn prune_index(&self) -> Result<PruneSummary, String> {
+        let indexed: HashSet<PathBuf> = self.documents_under("").await?
+            .into_iter()
+            .map(|file| os_path::decode(&file.path))
+            .collect();
+        let (missing, unreachable) = self.missing_files(indexed.iter(), &self.indexed_roots());
+        let mut summary = PruneSummary { checked: indexed.len(), unreachable, ..PruneSummary::default() };
+        for batch in missing.chunks(RECONCILE_BATCH_SIZE) {
+            let changes: Vec<(PathBuf, ChangeType)> = batch.iter()
+                .map(|path| (path.clone(), ChangeType::Deleted))
+                .collect();
+            self.apply_changes(&changes).await?;
+            for path in batch {
+                self.tracker.forget(path).await;
+            }
+            summary.removed += batch.len();
+        }
+        info!("Pruned {} of {} indexed files, kept {} that can't be reached", summary.removed, summary.checked, summary.unreachable);
+        Ok(summary)
+    }
+
+    /// The `paths` whose file is gone, and how many were left alone because
+    /// the root they're under is missing as a whole.
+    fn missing_files<'a>(&self, paths: impl Iterator<Item = &'a PathBuf>, roots: &[PathBuf]) -> (Vec<PathBuf>, usize) {
+        let reachable = |root: &Path| self.fs.metadata(root).is_ok_and(|metadata| metadata.is_dir);
+        let unreachable_roots: Vec<&PathBuf> = roots.iter().filter(|root| !reachable(root)).collect();
+        let mut missing = Vec::new();
+        let mut unreachable = 0;
+        for path in paths {
+            let gone = match self.fs.metadata(path) {
+                Ok(metadata) => !metadata.is_file,
+                Err(e) => e.kind() == ErrorKind::NotFound,
+            };
+            if !gone {
+                continue;
+            }
+            if unreachable_roots.iter().any(|root| path.starts_with(root)) {
+                unreachable += 1;
+            } else {
+                missing.push(path.clone());
+            }
+        }
+        (missing, unreachable)
+    }
+}
@@ -0,0 +1,212 @@
+//! Moving the on-disk index to another folder, e.g. on a bigger disk. The
+//! index files are copied while writes are held back, checked against the
+//! originals and opened from their new place before `index_location` is
+//! pointed at them; anything short of that removes the copy and leaves the
+//! index where it was. Searches keep using the old files until the app
+//! restarts, which then deletes them.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use serde::Serialize;
+use tantivy::Index;
+use ts_rs::TS;
+use super::{integrity, IndexManager};
+
+/// Names the folder a moved index was copied from, until it's deleted.
+const MOVED_FROM_FILE: &str = "index_moved_from";
+
+const COPY_CHUNK: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct IndexMove {
+    pub from: String,
+    pub to: String,
+    pub files: usize,
+    #[ts(type = "number")]
+    pub bytes: u64,
+    /// The index is read from its new folder once the app restarts; until
+    /// then changes to files are held back.
+    pub restart_required: bool,
+}
+
+/// Deletes the folder an earlier `move_index` copied the index out of, now
+/// that the index is read from `index_dir`.
+pub(super) fn finish_index_move(app_data_dir: &Path, index_dir: &Path) {
+    let marker = app_data_dir.join(MOVED_FROM_FILE);
+    let Ok(moved_from) = std::fs::read_to_string(&marker) else {
+        return;
+    };
+    let moved_from = PathBuf::from(moved_from);
+    // Only once the index really is where the settings say
+    if moved_from != index_dir && index_dir.join("meta.json").exists() {
+        match std::fs::remove_dir_all(&moved_from) {
+            Ok(()) => info!("Removed the index left behind in {:?}", moved_from),
+            Err(e) => warn!("Failed to remove the old index in {:?}: {}", moved_from, e),
+        }
+    }
+    if let Err(e) = std::fs::remove_file(&marker) {
+        warn!("Failed to remove {:?}: {}", marker, e);
+    }
+}
+
+/// Index files worth copying; tantivy's lock files are left behind.
+fn index_files(dir: &Path) -> Result<Vec<(PathBuf, u64)>, String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let metadata = entry.metadata()
+            .map_err(|e| format!("Failed to read {}: {}", entry.path().display(), e))?;
+        if metadata.is_file() && !name.ends_with(".lock") {
+            files.push((entry.path(), metadata.len()));
+        }
+    }
+    Ok(files)
+}
+
+/// Copies `source` to `target`, calling `progress` with the bytes copied
+/// so far after every chunk; stops when it returns `false`.
+fn copy_file(source: &Path, target: &Path, mut progress: impl FnMut(u64) -> bool) -> Result<(), String> {
+    let failed = |e: std::io::Error| format!("Failed to copy {}: {}", source.display(), e);
+    let mut from = File::open(source).map_err(failed)?;
+    let mut to = File::create(target).map_err(failed)?;
+    let mut buffer = vec![0; COPY_CHUNK];
+    let mut copied = 0;
+    loop {
+        let read = from.read(&mut buffer).map_err(failed)?;
+        if read == 0 {
+            break;
+        }
+        to.write_all(&buffer[..read]).map_err(failed)?;
+        copied += read as u64;
+        if !progress(copied) {
+            return Err("Moving the index was cancelled".to_string());
+        }
+    }
+    to.sync_all().map_err(failed)
+}
+
+impl IndexManager {
+    /// The folder the index is read from; `None` for in-memory indexes.
+    pub fn index_dir(&self) -> Option<&Path> {
+        self.index_dir.as_deref()
+    }
+
+    /// Copies the index into `new_location`, which must be empty or not
+    /// exist yet, and makes it the index folder from the next start on.
+    /// `progress` gets the bytes copied and the total, and cancels the
+    /// move by returning `false`. On any failure the copy is removed and
+    /// the index stays where it is.
+    pub async fn move_index(&self, new_location: &Path, mut progress: impl FnMut(u64, u64) -> bool) -> Result<IndexMove, String> {
+        let from = self.index_dir.clone()
+            .filter(|_| self.shard.is_none())
+            .ok_or_else(|| "Only an unsharded index on disk can be moved".to_string())?;
+        if self.writes_suspended() {
+            return Err("The index writer is held by another Constella process".to_string());
+        }
+        if self.data_dir.join(MOVED_FROM_FILE).exists() {
+            return Err("The index was already moved; restart to finish that first".to_string());
+        }
+        if !new_location.is_absolute() {
+            return Err(format!("{} isn't an absolute path", new_location.display()));
+        }
+        let canonical_from = from.canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", from.display(), e))?;
+        let canonical_to = new_location.parent()
+            .and_then(|parent| parent.canonicalize().ok())
+            .map(|parent| parent.join(new_location.file_name().unwrap_or_default()))
+            .unwrap_or_else(|| new_location.to_path_buf());
+        if canonical_to.starts_with(&canonical_from) {
+            return Err(format!("The index is already in {}", from.display()));
+        }
+        let created = !new_location.exists();
+        if !created {
+            let mut entries = std::fs::read_dir(new_location)
+                .map_err(|e| format!("Failed to read {}: {}", new_location.display(), e))?;
+            if entries.next().is_some() {
+                return Err(format!("{} isn't empty", new_location.display()));
+            }
+        }
+
+        // Hold writes back so the files don't change under the copy
+        self.suspend_writes().await?;
+        let moved = self.copy_index(&from, new_location, &mut progress).and_then(|moved| {
+            self.settings.update(|settings| settings.index_location = Some(new_location.to_path_buf()))?;
+            std::fs::write(self.data_dir.join(MOVED_FROM_FILE), from.to_string_lossy().as_bytes())
+                .map_err(|e| format!("Failed to note where the index was moved from: {}", e))?;
+            Ok(moved)
+        });
+        match moved {
+            Ok(moved) => {
+                info!("Moved the index from {:?} to {:?} ({} bytes); it's used from there after a restart", from, new_location, moved.bytes);
+                Ok(moved)
+            }
+            Err(e) => {
+                warn!("Failed to move the index to {:?}, rolling back: {}", new_location, e);
+                self.roll_back_move(&from, new_location, created);
+                self.resume_writes().await?;
+                Err(e)
+            }
+        }
+    }
+
+    fn copy_index(&self, from: &Path, to: &Path, progress: &mut impl FnMut(u64, u64) -> bool) -> Result<IndexMove, String> {
+        let files = index_files(from)?;
+        let total: u64 = files.iter().map(|(_, size)| size).sum();
+        std::fs::create_dir_all(to)
+            .map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+
+        let mut done = 0;
+        for (source, _) in &files {
+            let target = to.join(source.file_name().unwrap_or_default());
+            let mut copied = 0;
+            copy_file(source, &target, |now| {
+                done += now - copied;
+                copied = now;
+                progress(done, total)
+            })?;
+            if integrity::hash_file(source)? != integrity::hash_file(&target)? {
+                return Err(format!("The copy of {} doesn't match the original", source.display()));
+            }
+        }
+        let copy = Index::open_in_dir(to)
+            .map_err(|e| format!("Failed to open the moved index: {}", e))?;
+        if copy.schema() != self.index.schema() {
+            return Err("The moved index doesn't match the original".to_string());
+        }
+        progress(total, total);
+
+        Ok(IndexMove {
+            from: from.to_string_lossy().into_owned(),
+            to: to.to_string_lossy().into_owned(),
+            files: files.len(),
+            bytes: total,
+            restart_required: true,
+        })
+    }
+
+    /// Removes what a failed move copied and points the settings back at
+    /// `from`.
+    fn roll_back_move(&self, from: &Path, to: &Path, created: bool) {
+        let removed = if created {
+            std::fs::remove_dir_all(to)
+        } else {
+            std::fs::read_dir(to).and_then(|entries| {
+                entries.flatten().try_for_each(|entry| std::fs::remove_file(entry.path()))
+            })
+        };
+        if let Err(e) = removed {
+            warn!("Failed to remove the partial copy in {:?}: {}", to, e);
+        }
+        let default = self.data_dir.join("search_index");
+        let location = (from != default).then(|| from.to_path_buf());
+        if let Err(e) = self.settings.update(|settings| settings.index_location = location) {
+            warn!("{}", e);
+        }
+    }
+}
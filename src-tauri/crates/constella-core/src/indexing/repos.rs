@@ -0,0 +1,137 @@
+//! Git repository awareness. Files inside a working tree are tagged with
+//! its root when indexed, so results can show the repository, the path
+//! within it and the checked-out branch, and `repo:name` can narrow a
+//! search to one repository.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+use serde::Serialize;
+use ts_rs::TS;
+use super::IndexManager;
+
+/// Branches are re-read from `HEAD` once they are this old.
+const BRANCH_CACHE_TTL: Duration = Duration::from_secs(10);
+/// Past this many remembered directories the cache starts over.
+const MAX_CACHED_DIRECTORIES: usize = 100_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct RepositoryInfo {
+    /// Name of the repository's folder, which `repo:` filters on.
+    pub name: String,
+    pub root: String,
+    pub relative_path: String,
+    /// The checked-out branch, or the commit when `HEAD` is detached.
+    pub branch: Option<String>,
+}
+
+pub(crate) struct Repositories {
+    // Working tree root for each directory looked up, if it is in one
+    roots: Mutex<HashMap<PathBuf, Option<PathBuf>>>,
+    branches: Mutex<HashMap<PathBuf, (Instant, Option<String>)>>,
+}
+
+impl Repositories {
+    pub(crate) fn new() -> Self {
+        Self {
+            roots: Mutex::new(HashMap::new()),
+            branches: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Forgets where repositories are, e.g. before a full index run.
+    pub(crate) fn clear(&self) {
+        self.roots.lock().clear();
+        self.branches.lock().clear();
+    }
+}
+
+impl IndexManager {
+    /// The root of the git working tree containing `path`, if any.
+    pub(super) fn repository_root(&self, path: &Path) -> Option<PathBuf> {
+        let mut visited = Vec::new();
+        let mut found = None;
+        for dir in path.ancestors().skip(1) {
+            if let Some(cached) = self.repositories.roots.lock().get(dir) {
+                found = cached.clone();
+                break;
+            }
+            visited.push(dir.to_path_buf());
+            if self.fs.metadata(&dir.join(".git")).is_ok() {
+                found = Some(dir.to_path_buf());
+                break;
+            }
+        }
+
+        let mut roots = self.repositories.roots.lock();
+        if roots.len() + visited.len() > MAX_CACHED_DIRECTORIES {
+            roots.clear();
+        }
+        for dir in visited {
+            roots.insert(dir, found.clone());
+        }
+        found
+    }
+
+    /// Repository details for the indexed file at `path` in the working
+    /// tree at `root`.
+    pub(super) fn repository_info(&self, path: &str, root: &str) -> RepositoryInfo {
+        let root_path = Path::new(root);
+        RepositoryInfo {
+            name: repository_name(root_path),
+            root: root.to_string(),
+            relative_path: Path::new(path).strip_prefix(root_path)
+                .map(|relative| relative.to_string_lossy().to_string())
+                .unwrap_or_else(|_| path.to_string()),
+            branch: self.current_branch(root_path),
+        }
+    }
+
+    fn current_branch(&self, root: &Path) -> Option<String> {
+        if let Some((read_at, branch)) = self.repositories.branches.lock().get(root) {
+            if read_at.elapsed() < BRANCH_CACHE_TTL {
+                return branch.clone();
+            }
+        }
+        let branch = self.read_head(root).as_deref().and_then(branch_from_head);
+        self.repositories.branches.lock().insert(root.to_path_buf(), (Instant::now(), branch.clone()));
+        branch
+    }
+
+    fn read_head(&self, root: &Path) -> Option<String> {
+        let dot_git = root.join(".git");
+        // Worktrees and submodules have a `.git` file pointing at the real one
+        let git_dir = match self.fs.metadata(&dot_git) {
+            Ok(metadata) if metadata.is_file => {
+                let pointer = String::from_utf8(self.fs.read(&dot_git).ok()?).ok()?;
+                root.join(pointer.trim().strip_prefix("gitdir:")?.trim())
+            }
+            Ok(_) => dot_git,
+            Err(_) => return None,
+        };
+        String::from_utf8(self.fs.read(&git_dir.join("HEAD")).ok()?).ok()
+    }
+}
+
+/// The name a repository goes by: its folder name.
+pub(super) fn repository_name(root: &Path) -> String {
+    root.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().to_string())
+}
+
+/// The branch named in the contents of a `HEAD` file, or the abbreviated
+/// commit when it is detached.
+pub fn branch_from_head(head: &str) -> Option<String> {
+    let head = head.trim();
+    if let Some(reference) = head.strip_prefix("ref:") {
+        let reference = reference.trim();
+        return Some(reference.strip_prefix("refs/heads/").unwrap_or(reference).to_string());
+    }
+    if head.len() >= 7 && head.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(head[..7].to_string());
+    }
+    None
+}
@@ -0,0 +1,133 @@
+//! Throwaway in-memory indexes for one-off searches of a few folders, such
+//! as an extracted archive, kept apart from the persistent index.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use log::{info, warn};
+use parking_lot::Mutex;
+use serde::Serialize;
+use crate::scanner::FileScanner;
+use crate::settings::SettingsManager;
+use crate::watcher::ChangeType;
+use super::{IndexBacking, IndexManager, IndexOptions};
+use ts_rs::TS;
+
+/// Scratch indexes kept alive at once; creating another drops the oldest.
+const MAX_SCRATCH_INDEXES: usize = 8;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ScratchIndexInfo {
+    pub id: String,
+    pub roots: Vec<PathBuf>,
+    pub document_count: usize,
+}
+
+struct ScratchIndex {
+    info: ScratchIndexInfo,
+    manager: Arc<IndexManager>,
+}
+
+pub struct ScratchIndexes {
+    dir: PathBuf,
+    settings: Arc<SettingsManager>,
+    indexes: Mutex<VecDeque<ScratchIndex>>,
+    next_id: AtomicU64,
+}
+
+impl ScratchIndexes {
+    /// Scratch state lives under `dir`; anything left there by a previous
+    /// run is removed.
+    pub fn new(dir: impl AsRef<Path>, settings: Arc<SettingsManager>) -> Self {
+        let dir = dir.as_ref().to_path_buf();
+        if dir.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&dir) {
+                warn!("Failed to clear old scratch indexes at {:?}: {}", dir, e);
+            }
+        }
+        Self {
+            dir,
+            settings,
+            indexes: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Indexes the files in `paths` (directories are walked) into a new
+    /// in-memory index and returns its id.
+    pub async fn create(&self, paths: &[PathBuf]) -> Result<ScratchIndexInfo, String> {
+        if paths.is_empty() {
+            return Err("No paths given for the scratch index".to_string());
+        }
+        let id = format!("scratch-{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        let options = IndexOptions { backing: IndexBacking::Ram, ..IndexOptions::default() };
+        let manager = Arc::new(IndexManager::with_options(&self.dir.join(&id), self.settings.clone(), options)?);
+
+        let scanner = FileScanner::new();
+        let mut changes = Vec::new();
+        for path in paths {
+            if path.is_dir() {
+                changes.extend(scanner.collect_paths(path).into_iter().map(|file| (file, ChangeType::Created)));
+            } else if path.is_file() {
+                changes.push((path.clone(), ChangeType::Created));
+            } else {
+                return Err(format!("Path does not exist: {}", path.display()));
+            }
+        }
+        let summary = manager.apply_changes(&changes).await?;
+
+        let info = ScratchIndexInfo {
+            id,
+            roots: paths.to_vec(),
+            document_count: summary.indexed,
+        };
+        info!("Created scratch index {} with {} documents", info.id, info.document_count);
+
+        let mut indexes = self.indexes.lock();
+        if indexes.len() >= MAX_SCRATCH_INDEXES {
+            if let Some(evicted) = indexes.pop_front() {
+                info!("Dropping scratch index {} to make room", evicted.info.id);
+                self.cleanup(&evicted.info.id);
+            }
+        }
+        indexes.push_back(ScratchIndex { info: info.clone(), manager });
+        Ok(info)
+    }
+
+    pub async fn search(&self, id: &str, query: &str) -> Result<Vec<serde_json::Value>, String> {
+        let manager = self.get(id).ok_or_else(|| format!("No scratch index with id {}", id))?;
+        manager.search(query).await
+    }
+
+    /// The index behind scratch index `id`, e.g. to search it alongside
+    /// others.
+    pub fn get(&self, id: &str) -> Option<Arc<IndexManager>> {
+        self.indexes.lock()
+            .iter()
+            .find(|index| index.info.id == id)
+            .map(|index| index.manager.clone())
+    }
+
+    pub fn list(&self) -> Vec<ScratchIndexInfo> {
+        self.indexes.lock().iter().map(|index| index.info.clone()).collect()
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        let mut indexes = self.indexes.lock();
+        let position = indexes.iter()
+            .position(|index| index.info.id == id)
+            .ok_or_else(|| format!("No scratch index with id {}", id))?;
+        indexes.remove(position);
+        self.cleanup(id);
+        Ok(())
+    }
+
+    fn cleanup(&self, id: &str) {
+        let dir = self.dir.join(id);
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            warn!("Failed to remove scratch index directory {:?}: {}", dir, e);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Recognizes screenshots by their file name and folder, so they can be
+//! tagged with `kind:screenshot` and have their text recognized.
+
+use std::path::Path;
+
+/// Value of the `kind` field for screenshots.
+pub const SCREENSHOT_KIND: &str = "screenshot";
+
+/// Screenshots are small, so this is generous; anything larger is left alone.
+pub const MAX_SCREENSHOT_OCR_SIZE: u64 = 20 * 1024 * 1024;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "heic", "webp", "bmp"];
+
+/// How the screenshot tools of common platforms and locales name their
+/// files, lowercased.
+const NAME_PREFIXES: &[&str] = &[
+    "screenshot",
+    "screen shot",
+    "screen_shot",
+    "screen-shot",
+    "capture d’écran",
+    "capture d'écran",
+    "bildschirmfoto",
+    "schermafbeelding",
+    "captura de pantalla",
+    "istantanea schermo",
+    "снимок экрана",
+    "スクリーンショット",
+    "屏幕截图",
+    "cleanshot",
+    "snip",
+];
+
+/// Folders screenshot tools save into by default.
+const FOLDER_NAMES: &[&str] = &["screenshots", "screen shots", "screenshot", "captures"];
+
+pub fn is_screenshot(path: &Path) -> bool {
+    let is_image = path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false);
+    if !is_image {
+        return false;
+    }
+
+    let name = path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if NAME_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+        return true;
+    }
+    path.parent()
+        .and_then(|parent| parent.file_name())
+        .map(|folder| FOLDER_NAMES.contains(&folder.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
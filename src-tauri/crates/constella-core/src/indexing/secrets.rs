@@ -0,0 +1,67 @@
+use std::path::Path;
+use crate::extract::Extracted;
+use crate::secrets::{self, SecretFinding, SecretReport};
+use super::IndexManager;
+
+impl IndexManager {
+    fn secret_scanning_enabled(&self) -> bool {
+        self.settings.get().secret_scanning_enabled
+    }
+
+    /// `content` of `path` with likely secrets blanked out, recording where
+    /// they were found. `None` clears what was recorded for `path`.
+    pub(super) fn without_secrets(&self, path: &Path, content: Option<Extracted>) -> Option<Extracted> {
+        if !self.secret_scanning_enabled() {
+            return content;
+        }
+        let Some(mut content) = content else {
+            self.secret_findings.record(path, Vec::new());
+            return None;
+        };
+        let matches = secrets::scan(&content.text);
+        self.secret_findings.record(path, secrets::findings(path, &content.text, &matches, 1));
+        if !matches.is_empty() {
+            content.text = secrets::redact(&content.text, &matches);
+        }
+        Some(content)
+    }
+
+    /// `text` of a chunk of `path` with likely secrets blanked out. Its
+    /// findings, with lines counted from `first_line`, are added to
+    /// `findings`.
+    pub(super) fn chunk_without_secrets(
+        &self,
+        path: &Path,
+        text: String,
+        first_line: usize,
+        findings: &mut Vec<SecretFinding>,
+    ) -> String {
+        if !self.secret_scanning_enabled() {
+            return text;
+        }
+        let matches = secrets::scan(&text);
+        if matches.is_empty() {
+            return text;
+        }
+        findings.extend(secrets::findings(path, &text, &matches, first_line));
+        secrets::redact(&text, &matches)
+    }
+
+    /// Text about to be shown in a snippet, with likely secrets blanked out.
+    pub(super) fn redact_secrets(&self, text: String) -> String {
+        if !self.secret_scanning_enabled() {
+            return text;
+        }
+        let matches = secrets::scan(&text);
+        if matches.is_empty() {
+            text
+        } else {
+            secrets::redact(&text, &matches)
+        }
+    }
+
+    /// Files found to contain likely secrets, without the secrets.
+    pub fn secret_findings(&self) -> SecretReport {
+        self.secret_findings.report()
+    }
+}
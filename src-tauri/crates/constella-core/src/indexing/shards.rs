@@ -0,0 +1,298 @@
+//! Splitting the index across several tantivy indexes for very large
+//! corpora, so each shard's commits and segment merges stay small. Files
+//! go to a shard either by the indexed root they are under, or by a hash
+//! of their path. Searches run on every shard at once and the results are
+//! merged by score; scores are only comparable because every shard uses
+//! the same schema and ranking, so a shard with very different contents
+//! can still rank a little differently.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use log::{info, warn};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::search::{ResultFields, SearchOptions, SearchResponse};
+use crate::settings::SettingsManager;
+use crate::watcher::ChangeType;
+use super::federation::merge_responses;
+use super::{IndexManager, IndexOptions, IndexerState, UpdateSummary};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum ShardStrategy {
+    /// Each indexed root lives whole in one shard, the one with the fewest
+    /// roots when it is first indexed.
+    #[default]
+    ByRoot,
+    /// Files are spread evenly by a hash of their path; every shard scans
+    /// each root and keeps its share.
+    ByHash,
+}
+
+/// Changing the strategy or the number of shards takes a full reindex.
+/// While enabled, full index runs, file changes and searches go to the
+/// shards rather than the main index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ShardingSettings {
+    pub enabled: bool,
+    pub strategy: ShardStrategy,
+    pub shards: usize,
+}
+
+impl Default for ShardingSettings {
+    fn default() -> Self {
+        Self { enabled: false, strategy: ShardStrategy::ByRoot, shards: 4 }
+    }
+}
+
+/// Which share of the files a hash-sharded index keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardSlot {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl ShardSlot {
+    pub fn owns(&self, path: &Path) -> bool {
+        hash_shard(path, self.count) == self.index
+    }
+}
+
+fn hash_shard(path: &Path, count: usize) -> usize {
+    let hash = blake3::hash(path.to_string_lossy().as_bytes());
+    let mut prefix = [0; 8];
+    prefix.copy_from_slice(&hash.as_bytes()[..8]);
+    (u64::from_le_bytes(prefix) % count as u64) as usize
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ShardInfo {
+    pub index: usize,
+    #[ts(type = "number")]
+    pub documents: u64,
+    pub segments: usize,
+    /// Roots kept in this shard; empty when sharding by hash.
+    pub roots: Vec<PathBuf>,
+}
+
+pub struct ShardedIndex {
+    strategy: ShardStrategy,
+    shards: Vec<Arc<IndexManager>>,
+    /// Which shard each root went to, when sharding by root.
+    roots: RwLock<BTreeMap<PathBuf, usize>>,
+    roots_path: PathBuf,
+}
+
+impl ShardedIndex {
+    /// Opens the shards configured in settings under `data_dir/shards`.
+    pub fn open(data_dir: &Path, settings: Arc<SettingsManager>) -> Result<Self, String> {
+        Self::with_options(data_dir, settings, IndexOptions::default)
+    }
+
+    /// Like `open`, building each shard's options with `options`.
+    pub fn with_options(
+        data_dir: &Path,
+        settings: Arc<SettingsManager>,
+        options: impl Fn() -> IndexOptions,
+    ) -> Result<Self, String> {
+        let config = settings.get().sharding;
+        let count = config.shards.max(1);
+        let dir = data_dir.join("shards");
+        let shards = (0..count)
+            .map(|index| {
+                let slot = (config.strategy == ShardStrategy::ByHash).then_some(ShardSlot { index, count });
+                let options = IndexOptions { shard: slot, ..options() };
+                IndexManager::with_options(&dir.join(index.to_string()), settings.clone(), options)
+                    .map(Arc::new)
+                    .map_err(|e| format!("Failed to open shard {}: {}", index, e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let roots_path = dir.join("roots.json");
+        let roots = match std::fs::read_to_string(&roots_path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse shard roots at {:?}, starting over: {}", roots_path, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        info!("Opened {} shards, sharded {:?}", count, config.strategy);
+        Ok(Self { strategy: config.strategy, shards, roots: RwLock::new(roots), roots_path })
+    }
+
+    pub fn shards(&self) -> &[Arc<IndexManager>] {
+        &self.shards
+    }
+
+    fn save_roots(&self, roots: &BTreeMap<PathBuf, usize>) -> Result<(), String> {
+        let json = serde_json::to_string(roots)
+            .map_err(|e| format!("Failed to serialize shard roots: {}", e))?;
+        let tmp = self.roots_path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, &self.roots_path))
+            .map_err(|e| format!("Failed to save shard roots: {}", e))
+    }
+
+    /// The shard `root` is kept in, picking the one with the fewest roots
+    /// the first time.
+    fn assign_root(&self, root: &Path) -> Result<usize, String> {
+        let mut roots = self.roots.write();
+        if let Some(shard) = roots.get(root) {
+            return Ok(*shard);
+        }
+        let count = self.shards.len();
+        let mut load = vec![0; count];
+        for shard in roots.values().filter(|shard| **shard < count) {
+            load[*shard] += 1;
+        }
+        let shard = (0..load.len()).min_by_key(|shard| load[*shard]).unwrap_or(0);
+        roots.insert(root.to_path_buf(), shard);
+        self.save_roots(&roots)?;
+        Ok(shard)
+    }
+
+    /// The shard `path` belongs in, if any.
+    pub fn shard_of(&self, path: &Path) -> Option<usize> {
+        match self.strategy {
+            ShardStrategy::ByHash => Some(hash_shard(path, self.shards.len())),
+            ShardStrategy::ByRoot => self.roots.read()
+                .iter()
+                .filter(|(root, _)| path.starts_with(root))
+                .max_by_key(|(root, _)| root.components().count())
+                .map(|(_, shard)| *shard)
+                .filter(|shard| *shard < self.shards.len()),
+        }
+    }
+
+    /// Fully indexes `directory`: in its own shard when sharding by root,
+    /// or in every shard at once when sharding by hash.
+    pub async fn start_indexing(&self, directory: &str) -> Result<(), String> {
+        let targets: Vec<Arc<IndexManager>> = match self.strategy {
+            ShardStrategy::ByRoot => {
+                let shard = self.assign_root(Path::new(directory))?;
+                info!("Indexing {} into shard {}", directory, shard);
+                vec![self.shards[shard].clone()]
+            }
+            ShardStrategy::ByHash => self.shards.clone(),
+        };
+        let runs: Vec<_> = targets.into_iter()
+            .map(|shard| {
+                let directory = directory.to_string();
+                tokio::spawn(async move { shard.start_indexing(&directory).await })
+            })
+            .collect();
+        for run in runs {
+            run.await.map_err(|e| format!("Shard indexing panicked: {}", e))??;
+        }
+        Ok(())
+    }
+
+    /// Every shard's progress summed up, reporting the state and current
+    /// file of the shard with the most left to do.
+    pub fn get_state(&self) -> IndexerState {
+        let states: Vec<IndexerState> = self.shards.iter().map(|shard| shard.get_state()).collect();
+        let mut combined = states.iter()
+            .max_by_key(|state| state.total_files.saturating_sub(state.processed_files))
+            .cloned()
+            .unwrap_or_else(|| self.shards[0].get_state());
+        combined.total_files = states.iter().map(|state| state.total_files).sum();
+        combined.processed_files = states.iter().map(|state| state.processed_files).sum();
+        combined.priority_total = states.iter().map(|state| state.priority_total).sum();
+        combined.priority_processed = states.iter().map(|state| state.priority_processed).sum();
+        combined.skipped_files = states.iter().map(|state| state.skipped_files).sum();
+        combined.files_per_second = states.iter().map(|state| state.files_per_second).sum();
+        combined
+    }
+
+    /// Stops every shard's full index run, keeping what each indexed.
+    pub async fn cancel(&self) -> Result<(), String> {
+        for shard in &self.shards {
+            shard.cancel().await?;
+        }
+        Ok(())
+    }
+
+    /// Hands each change to the shard its path belongs in. A file renamed
+    /// across shards is removed from one and added to the other.
+    pub async fn apply_changes(&self, changes: &[(PathBuf, ChangeType)]) -> Result<UpdateSummary, String> {
+        let mut routed: Vec<Vec<(PathBuf, ChangeType)>> = vec![Vec::new(); self.shards.len()];
+        for (path, change) in changes {
+            let Some(shard) = self.shard_of(path) else {
+                continue;
+            };
+            if let ChangeType::Renamed(old) = change {
+                if let Some(old_shard) = self.shard_of(old).filter(|old_shard| *old_shard != shard) {
+                    routed[old_shard].push((old.clone(), ChangeType::Deleted));
+                    routed[shard].push((path.clone(), ChangeType::Created));
+                    continue;
+                }
+            }
+            routed[shard].push((path.clone(), change.clone()));
+        }
+
+        let runs: Vec<_> = self.shards.iter()
+            .zip(routed)
+            .filter(|(_, changes)| !changes.is_empty())
+            .map(|(shard, changes)| {
+                let shard = shard.clone();
+                tokio::spawn(async move { shard.apply_changes(&changes).await })
+            })
+            .collect();
+        let mut total = UpdateSummary::default();
+        for run in runs {
+            let summary = run.await.map_err(|e| format!("Shard update panicked: {}", e))??;
+            total.indexed += summary.indexed;
+            total.skipped += summary.skipped;
+            total.removed += summary.removed;
+        }
+        Ok(total)
+    }
+
+    /// Searches every shard in parallel and merges their results by score.
+    pub async fn search_response(&self, query: &str, options: &SearchOptions) -> Result<SearchResponse, String> {
+        // Merging needs scores, which path-only results leave out
+        let mut shard_options = options.clone();
+        shard_options.fields = options.fields.max(ResultFields::Metadata);
+        // Snapshots and offsets belong to a single index
+        shard_options.snapshot = None;
+        shard_options.offset = 0;
+        let runs: Vec<_> = self.shards.iter()
+            .map(|shard| {
+                let (shard, query, options) = (shard.clone(), query.to_string(), shard_options.clone());
+                tokio::spawn(async move { shard.search_response(&query, &options).await })
+            })
+            .collect();
+
+        let mut responses = Vec::with_capacity(runs.len());
+        for run in runs {
+            responses.push((None, run.await.map_err(|e| format!("Shard search panicked: {}", e))??));
+        }
+        Ok(merge_responses(responses, options.fields))
+    }
+
+    /// Size of each shard, and the roots kept in it.
+    pub fn shard_info(&self) -> Vec<ShardInfo> {
+        let roots = self.roots.read();
+        self.shards.iter()
+            .enumerate()
+            .map(|(index, shard)| {
+                let searcher = shard.reader.searcher();
+                ShardInfo {
+                    index,
+                    documents: searcher.num_docs(),
+                    segments: searcher.segment_readers().len(),
+                    roots: roots.iter()
+                        .filter(|(_, shard)| **shard == index)
+                        .map(|(root, _)| root.clone())
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,132 @@
+//! Load shedding for gigantic indexes: once the document count passes a
+//! threshold, features that get expensive at that scale are turned down so
+//! indexing and search stay fast. Each measure can be overridden to keep
+//! the feature on whatever the count. Search responses list the measures
+//! in effect, so the UI can say what it is missing and why.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::search::ResultFields;
+use super::IndexManager;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum SheddingMeasure {
+    /// Results come without snippets, which mean reading every file shown.
+    NoSnippets,
+    /// Newly indexed documents leave out stored fields only shown in the
+    /// UI: photo dimensions and repository roots.
+    LeanDocuments,
+    /// Full index runs commit far less often, so results of a run in
+    /// progress show up later.
+    SlowCommits,
+}
+
+impl SheddingMeasure {
+    pub const ALL: [SheddingMeasure; 3] = [
+        SheddingMeasure::NoSnippets,
+        SheddingMeasure::LeanDocuments,
+        SheddingMeasure::SlowCommits,
+    ];
+}
+
+/// Document counts at which each measure kicks in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct LoadShedding {
+    #[ts(type = "number")]
+    pub no_snippets_at: u64,
+    #[ts(type = "number")]
+    pub lean_documents_at: u64,
+    #[ts(type = "number")]
+    pub slow_commits_at: u64,
+    /// Measures the user turned off, keeping the feature at any size.
+    pub overridden: Vec<SheddingMeasure>,
+}
+
+impl Default for LoadShedding {
+    fn default() -> Self {
+        Self {
+            no_snippets_at: 10_000_000,
+            lean_documents_at: 10_000_000,
+            slow_commits_at: 5_000_000,
+            overridden: Vec::new(),
+        }
+    }
+}
+
+impl LoadShedding {
+    pub fn threshold(&self, measure: SheddingMeasure) -> u64 {
+        match measure {
+            SheddingMeasure::NoSnippets => self.no_snippets_at,
+            SheddingMeasure::LeanDocuments => self.lean_documents_at,
+            SheddingMeasure::SlowCommits => self.slow_commits_at,
+        }
+    }
+
+    /// The measures in effect for an index of `documents`.
+    pub fn active(&self, documents: u64) -> Vec<SheddingMeasure> {
+        SheddingMeasure::ALL.into_iter()
+            .filter(|measure| documents >= self.threshold(*measure) && !self.overridden.contains(measure))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct MeasureStatus {
+    pub measure: SheddingMeasure,
+    #[ts(type = "number")]
+    pub threshold: u64,
+    pub active: bool,
+    pub overridden: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct LoadSheddingStatus {
+    #[ts(type = "number")]
+    pub documents: u64,
+    pub measures: Vec<MeasureStatus>,
+}
+
+impl IndexManager {
+    /// Which measures are in effect at the current size, and which would
+    /// be but are overridden.
+    pub fn load_shedding(&self) -> LoadSheddingStatus {
+        let config = self.settings.get().load_shedding;
+        let documents = self.reader.searcher().num_docs();
+        let active = config.active(documents);
+        LoadSheddingStatus {
+            documents,
+            measures: SheddingMeasure::ALL.into_iter()
+                .map(|measure| MeasureStatus {
+                    measure,
+                    threshold: config.threshold(measure),
+                    active: active.contains(&measure),
+                    overridden: config.overridden.contains(&measure),
+                })
+                .collect(),
+        }
+    }
+
+    /// The measures in effect at the current size.
+    pub fn shed_measures(&self) -> Vec<SheddingMeasure> {
+        self.settings.get().load_shedding.active(self.reader.searcher().num_docs())
+    }
+
+    pub(super) fn sheds(&self, measure: SheddingMeasure) -> bool {
+        self.shed_measures().contains(&measure)
+    }
+
+    /// `fields` without snippets when those are being shed.
+    pub(super) fn result_fields(&self, fields: ResultFields) -> ResultFields {
+        if fields >= ResultFields::Snippets && self.sheds(SheddingMeasure::NoSnippets) {
+            ResultFields::Metadata
+        } else {
+            fields
+        }
+    }
+}
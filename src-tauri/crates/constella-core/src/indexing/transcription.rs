@@ -0,0 +1,173 @@
+//! The audio transcription queue. Audio found while indexing is queued
+//! rather than transcribed on the spot; the idle scheduler works through
+//! the queue while nobody is using the machine. Each transcript is indexed
+//! as the file's content and kept, so later index runs reuse it until the
+//! file changes.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use log::{info, warn};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::extract::subtitles::{self, Cue};
+use crate::extract::Extracted;
+use crate::watcher::ChangeType;
+use super::IndexManager;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct TranscriptionStatus {
+    pub enabled: bool,
+    /// Files waiting for the next idle spell.
+    pub queued: usize,
+    /// Files transcribed since the app started.
+    pub transcribed: usize,
+    pub failed: usize,
+    /// The file being transcribed right now.
+    pub current: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredTranscript {
+    size: u64,
+    modified: u64,
+    cues: Vec<Cue>,
+}
+
+pub(crate) struct TranscriptionQueue {
+    path: PathBuf,
+    // By path, for the version of the file they were made from
+    transcripts: RwLock<HashMap<String, StoredTranscript>>,
+    pending: Mutex<VecDeque<PathBuf>>,
+    status: RwLock<TranscriptionStatus>,
+}
+
+impl TranscriptionQueue {
+    pub(crate) fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let transcripts = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse transcripts at {:?}, starting over: {}", path, e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self {
+            path,
+            transcripts: RwLock::new(transcripts),
+            pending: Mutex::new(VecDeque::new()),
+            status: RwLock::new(TranscriptionStatus::default()),
+        }
+    }
+
+    /// Keeps the transcript of a file that moved from `old` to `new`.
+    pub(crate) fn rebase(&self, old: &Path, new: &Path) {
+        let moved = self.transcripts.write().remove(old.to_string_lossy().as_ref());
+        if let Some(transcript) = moved {
+            self.transcripts.write().insert(new.to_string_lossy().into_owned(), transcript);
+            if let Err(e) = self.save() {
+                warn!("{}", e);
+            }
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string(&*self.transcripts.read())
+            .map_err(|e| format!("Failed to serialize transcripts: {}", e))?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write transcripts: {}", e))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to replace transcripts: {}", e))
+    }
+}
+
+impl IndexManager {
+    /// The stored transcript of the audio file at `path`. A file with no
+    /// transcript for its current version is queued when transcription is
+    /// on and it is small enough.
+    pub(super) fn audio_transcript(&self, path: &Path) -> Option<Extracted> {
+        let settings = self.settings.get().transcription;
+        let (size, modified) = self.file_version(path)?;
+        let key = path.to_string_lossy();
+        if let Some(stored) = self.transcription.transcripts.read().get(key.as_ref()) {
+            if stored.size == size && stored.modified == modified {
+                return (!stored.cues.is_empty()).then(|| subtitles::transcript(stored.cues.clone()));
+            }
+        }
+
+        if settings.enabled && size > 0 && size <= settings.max_file_size {
+            let mut pending = self.transcription.pending.lock();
+            if !pending.iter().any(|queued| queued == path) {
+                pending.push_back(path.to_path_buf());
+            }
+        }
+        None
+    }
+
+    /// Transcribes queued files one at a time while `should_continue`
+    /// allows, indexing each transcript as soon as it is done. Returns how
+    /// many files were transcribed.
+    pub async fn transcribe_pending(&self, should_continue: impl Fn() -> bool) -> Result<usize, String> {
+        if !self.settings.get().transcription.enabled {
+            return Ok(0);
+        }
+        let mut transcribed = 0;
+        while should_continue() {
+            let Some(path) = self.transcription.pending.lock().pop_front() else {
+                break;
+            };
+            // Deleted since it was queued
+            let Some((size, modified)) = self.file_version(&path) else {
+                continue;
+            };
+
+            self.transcription.status.write().current = Some(path.to_string_lossy().to_string());
+            let transcriber = self.transcriber.clone();
+            let audio = path.clone();
+            let result = tokio::task::spawn_blocking(move || transcriber.transcribe(&audio))
+                .await
+                .map_err(|e| format!("Transcription failed: {}", e))?;
+            self.transcription.status.write().current = None;
+
+            let cues = match result {
+                Ok(cues) => cues,
+                Err(e) => {
+                    warn!("{}", e);
+                    self.transcription.status.write().failed += 1;
+                    continue;
+                }
+            };
+            info!("Transcribed {:?}: {} lines", path, cues.len());
+            self.transcription.transcripts.write()
+                .insert(path.to_string_lossy().to_string(), StoredTranscript { size, modified, cues });
+            if let Err(e) = self.transcription.save() {
+                warn!("{}", e);
+            }
+            self.transcription.status.write().transcribed += 1;
+            transcribed += 1;
+
+            // The tracker would otherwise skip the unchanged file
+            self.tracker.forget(&path).await;
+            self.apply_changes(&[(path, ChangeType::Modified)]).await?;
+        }
+        Ok(transcribed)
+    }
+
+    pub fn transcription_status(&self) -> TranscriptionStatus {
+        TranscriptionStatus {
+            enabled: self.settings.get().transcription.enabled,
+            queued: self.transcription.pending.lock().len(),
+            ..self.transcription.status.read().clone()
+        }
+    }
+
+    /// Size and modification time of `path`, which identify its version.
+    fn file_version(&self, path: &Path) -> Option<(u64, u64)> {
+        let metadata = self.fs.metadata(path).ok()?;
+        let modified = metadata.modified?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        Some((metadata.len, modified))
+    }
+}
@@ -0,0 +1,64 @@
+//! Videos are indexed by their dialogue: the subtitle files next to them
+//! and, when turned on, the subtitle streams inside them.
+
+use std::path::{Path, PathBuf};
+use log::warn;
+use crate::extract::subtitles::{self, Cue};
+use crate::extract::Extracted;
+use super::IndexManager;
+
+impl IndexManager {
+    /// The transcript of the video at `path`, or `None` when it has no
+    /// subtitles or reading contents isn't allowed right now.
+    pub(super) fn video_transcript(&self, path: &Path) -> Option<Extracted> {
+        if !self.power.content_extraction_allowed() {
+            return None;
+        }
+        let config = self.settings.get().indexing;
+        let mut cues: Vec<Cue> = Vec::new();
+        for subtitle_path in self.sibling_files(path).into_iter().filter(|sibling| subtitles::belongs_to(sibling, path)) {
+            let too_large = self.fs.metadata(&subtitle_path)
+                .map_or(true, |metadata| metadata.len > config.content_max_file_size);
+            if too_large {
+                continue;
+            }
+            match self.fs.read(&subtitle_path) {
+                Ok(bytes) => cues.extend(subtitles::parse(&String::from_utf8_lossy(&bytes))),
+                Err(e) => warn!("Failed to read subtitles {}: {}", subtitle_path.display(), e),
+            }
+        }
+        if config.embedded_subtitles {
+            if let Some(reader) = &self.subtitles {
+                match reader.read_track(path) {
+                    Ok(Some(track)) => cues.extend(subtitles::parse(&track)),
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to read embedded subtitles of {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        if cues.is_empty() {
+            return None;
+        }
+        cues.sort_by_key(|cue| cue.start_ms);
+        Some(subtitles::transcript(cues))
+    }
+
+    /// Videos whose transcript includes the subtitle file at `path`, so
+    /// they can be indexed again when it changes.
+    pub(super) fn videos_with_subtitles(&self, path: &Path) -> Vec<PathBuf> {
+        if !subtitles::is_subtitles(path) {
+            return Vec::new();
+        }
+        self.sibling_files(path)
+            .into_iter()
+            .filter(|sibling| subtitles::is_video(sibling) && subtitles::belongs_to(path, sibling))
+            .collect()
+    }
+
+    fn sibling_files(&self, path: &Path) -> Vec<PathBuf> {
+        path.parent()
+            .and_then(|parent| self.fs.read_dir(parent).ok())
+            .unwrap_or_default()
+    }
+}
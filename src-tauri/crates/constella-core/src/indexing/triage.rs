@@ -0,0 +1,171 @@
+//! Cleanup help for the Downloads folder: files there are tagged with the
+//! domain they were downloaded from, and the triage report flags
+//! installers and archives that have been sitting around for a while.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use tantivy::collector::DocSetCollector;
+use ts_rs::TS;
+use super::IndexManager;
+
+/// Which folder gets triaged and what counts as stale there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct TriageRules {
+    /// Defaults to the system Downloads folder.
+    pub folder: Option<PathBuf>,
+    /// Installers and archives untouched for this long are flagged.
+    pub stale_after_days: u32,
+    /// Lowercased, without the dot.
+    pub installer_extensions: Vec<String>,
+    pub archive_extensions: Vec<String>,
+}
+
+impl Default for TriageRules {
+    fn default() -> Self {
+        let extensions = |list: &[&str]| list.iter().map(|extension| extension.to_string()).collect();
+        Self {
+            folder: None,
+            stale_after_days: 30,
+            installer_extensions: extensions(&["exe", "msi", "msix", "dmg", "pkg", "deb", "rpm", "appimage", "apk"]),
+            archive_extensions: extensions(&["zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "iso"]),
+        }
+    }
+}
+
+impl TriageRules {
+    pub fn folder(&self) -> Option<PathBuf> {
+        self.folder.clone().or_else(dirs::download_dir)
+    }
+
+    fn category(&self, path: &Path) -> Option<TriageCategory> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        if self.installer_extensions.contains(&extension) {
+            Some(TriageCategory::Installer)
+        } else if self.archive_extensions.contains(&extension) {
+            Some(TriageCategory::Archive)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum TriageCategory {
+    Installer,
+    Archive,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct TriageItem {
+    pub path: String,
+    pub category: TriageCategory,
+    #[ts(type = "number")]
+    pub size: u64,
+    pub age_days: u32,
+    pub source_domain: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DomainSummary {
+    /// Empty for files with no recorded source.
+    pub domain: String,
+    pub files: usize,
+    #[ts(type = "number")]
+    pub total_size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DownloadsTriage {
+    pub folder: Option<PathBuf>,
+    pub total_files: usize,
+    #[ts(type = "number")]
+    pub total_size: u64,
+    /// Stale installers and archives, largest first.
+    pub stale: Vec<TriageItem>,
+    #[ts(type = "number")]
+    pub reclaimable_size: u64,
+    /// Where the folder's files came from, biggest share first.
+    pub by_domain: Vec<DomainSummary>,
+}
+
+impl IndexManager {
+    /// The domain `path` was downloaded from, for files in the triaged folder.
+    pub(super) fn download_source(&self, path: &Path) -> Option<String> {
+        let folder = self.settings.get().downloads_triage.folder()?;
+        if !path.starts_with(folder) {
+            return None;
+        }
+        let url = self.fs.origin_url(path)?;
+        crate::file_system::origin::domain(&url)
+    }
+
+    /// Summarizes the indexed contents of the Downloads folder.
+    pub async fn downloads_triage(&self) -> Result<DownloadsTriage, String> {
+        let rules = self.settings.get().downloads_triage;
+        let Some(folder) = rules.folder() else {
+            return Ok(DownloadsTriage::default());
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let searcher = self.reader.searcher();
+        let addresses = searcher.search(&self.files_under_query(&folder), &DocSetCollector)
+            .map_err(|e| format!("Failed to collect downloads: {}", e))?;
+
+        let mut report = DownloadsTriage {
+            folder: Some(folder),
+            total_files: addresses.len(),
+            ..DownloadsTriage::default()
+        };
+        let mut domains: HashMap<String, DomainSummary> = HashMap::new();
+        for doc_address in addresses {
+            let retrieved_doc = searcher.doc(doc_address)
+                .map_err(|e| format!("Failed to retrieve document: {}", e))?;
+            let Some(path) = self.doc_path(&retrieved_doc) else {
+                continue;
+            };
+            let metadata = self.document_metadata(&path, &retrieved_doc);
+            let source_domain = retrieved_doc.get_first(self.source_field)
+                .and_then(|f| f.as_text())
+                .map(str::to_string);
+            report.total_size += metadata.size;
+
+            let domain = source_domain.clone().unwrap_or_default();
+            let summary = domains.entry(domain.clone())
+                .or_insert(DomainSummary { domain, files: 0, total_size: 0 });
+            summary.files += 1;
+            summary.total_size += metadata.size;
+
+            let age_days = (now.saturating_sub(metadata.modified) / 86_400) as u32;
+            if age_days < rules.stale_after_days {
+                continue;
+            }
+            if let Some(category) = rules.category(Path::new(&path)) {
+                report.reclaimable_size += metadata.size;
+                report.stale.push(TriageItem {
+                    path: metadata.path,
+                    category,
+                    size: metadata.size,
+                    age_days,
+                    source_domain,
+                });
+            }
+        }
+
+        report.stale.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+        report.by_domain = domains.into_values().collect();
+        report.by_domain.sort_by(|a, b| b.total_size.cmp(&a.total_size).then_with(|| a.domain.cmp(&b.domain)));
+        Ok(report)
+    }
+}
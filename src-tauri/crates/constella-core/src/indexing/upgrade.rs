@@ -0,0 +1,128 @@
+//! Keeping search working across app updates. Every build knows the
+//! `INDEX_FORMAT_VERSION` its documents are made in, and releases state
+//! theirs in the notes (`Index format: 2`). Installing a release in another
+//! format, or starting on an index this build had to recreate or that was
+//! made in another format, schedules a rebuild that runs in the background
+//! once the app is up. The schedule is kept in a marker file until a full
+//! run completes, so quitting halfway resumes it on the next start.
+
+use std::path::Path;
+use log::{info, warn};
+use serde::Serialize;
+use ts_rs::TS;
+use super::IndexManager;
+
+/// Bump whenever documents have to be rebuilt to stay searchable, e.g. a
+/// changed tokenizer or field, whether or not the schema shows it.
+pub const INDEX_FORMAT_VERSION: u32 = 1;
+
+/// Format of the index, kept with the rest of the data so it doesn't show
+/// up in the index manifest.
+const FORMAT_FILE: &str = "index_format";
+const REBUILD_MARKER: &str = "rebuild_after_update";
+const FORMAT_NOTE: &str = "index format:";
+
+/// A newer release, and what installing it means for the index.
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct UpdateCheck {
+    pub current_version: String,
+    pub version: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+    /// The index format the release states, if it does.
+    pub index_format: Option<u32>,
+    /// Search needs a rebuild after installing; it runs in the background.
+    /// `None` when the notes don't say, in which case the new build finds
+    /// out for itself when it opens the index.
+    pub rebuild_required: Option<bool>,
+}
+
+impl UpdateCheck {
+    pub fn new(current_version: String, version: String, notes: Option<String>, date: Option<String>) -> Self {
+        let index_format = notes.as_deref().and_then(release_index_format);
+        Self {
+            current_version,
+            version,
+            notes,
+            date,
+            index_format,
+            rebuild_required: index_format.map(|format| format != INDEX_FORMAT_VERSION),
+        }
+    }
+}
+
+/// The index format stated in release `notes`, as a line `Index format: N`.
+pub fn release_index_format(notes: &str) -> Option<u32> {
+    notes.lines().find_map(|line| {
+        let line = line.trim().trim_start_matches(['-', '*']).trim();
+        let prefix = line.get(..FORMAT_NOTE.len())?;
+        if !prefix.eq_ignore_ascii_case(FORMAT_NOTE) {
+            return None;
+        }
+        line[FORMAT_NOTE.len()..].trim().parse().ok()
+    })
+}
+
+/// How the index came about when it was opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum IndexOrigin {
+    Existing,
+    /// Thrown away and created again because its schema changed.
+    Recreated,
+    Created,
+}
+
+/// Notes the format of the index just opened for `app_data_dir`,
+/// scheduling a rebuild when it was recreated or made in another format.
+/// Indexes from before formats were recorded count as the first one.
+pub(super) fn check_index_format(app_data_dir: &Path, origin: IndexOrigin) {
+    let format_path = app_data_dir.join(FORMAT_FILE);
+    let format = std::fs::read_to_string(&format_path).ok()
+        .and_then(|format| format.trim().parse::<u32>().ok())
+        .unwrap_or(1);
+    let stale = match origin {
+        IndexOrigin::Existing => format != INDEX_FORMAT_VERSION,
+        IndexOrigin::Recreated => true,
+        IndexOrigin::Created => false,
+    };
+    if stale {
+        info!("The index needs rebuilding for format {}", INDEX_FORMAT_VERSION);
+        if let Err(e) = write_rebuild_marker(app_data_dir) {
+            warn!("{}", e);
+        }
+    }
+    if let Err(e) = std::fs::write(&format_path, INDEX_FORMAT_VERSION.to_string()) {
+        warn!("Failed to record the index format: {}", e);
+    }
+}
+
+fn write_rebuild_marker(app_data_dir: &Path) -> Result<(), String> {
+    std::fs::write(app_data_dir.join(REBUILD_MARKER), INDEX_FORMAT_VERSION.to_string())
+        .map_err(|e| format!("Failed to schedule an index rebuild: {}", e))
+}
+
+impl IndexManager {
+    /// Whether a rebuild is waiting to run after an update.
+    pub fn rebuild_scheduled(&self) -> bool {
+        self.data_dir.join(REBUILD_MARKER).exists()
+    }
+
+    /// Has the next start rebuild the index, e.g. before installing a
+    /// release in another index format. Until the rebuild completes, full
+    /// runs start from an empty index.
+    pub fn schedule_rebuild(&self) -> Result<(), String> {
+        info!("Scheduling an index rebuild for the next start");
+        write_rebuild_marker(&self.data_dir)
+    }
+
+    /// Drops a scheduled rebuild, e.g. when installing the update failed.
+    pub fn unschedule_rebuild(&self) {
+        let marker = self.data_dir.join(REBUILD_MARKER);
+        if let Err(e) = std::fs::remove_file(&marker) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove {:?}: {}", marker, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+use log::warn;
+use crate::volumes::{self, MountedVolume, RootVolume};
+use super::paths::PathRemap;
+use super::IndexManager;
+
+impl IndexManager {
+    /// Follows roots whose volume is now mounted somewhere else, remapping
+    /// them to where it is, and notes the volume of roots not seen before.
+    /// Returns the remaps made.
+    pub async fn follow_volumes(&self, mounted: &[MountedVolume]) -> Result<Vec<PathRemap>, String> {
+        let mut remapped = Vec::new();
+        for known in self.settings.get().root_volumes {
+            let Some(current) = known.resolve(mounted) else {
+                continue;
+            };
+            if current == known.path {
+                continue;
+            }
+            match self.remap_root(&known.path, &current).await {
+                Ok(_) => remapped.push(PathRemap { from: known.path, to: current }),
+                Err(e) => warn!("Failed to follow {:?} to {:?}: {}", known.path, current, e),
+            }
+        }
+
+        self.settings.update(|settings| {
+            let mut roots: Vec<PathBuf> = settings.watched_roots.iter()
+                .chain(&settings.indexed_roots)
+                .cloned()
+                .collect();
+            roots.sort();
+            roots.dedup();
+            // Roots on volumes that aren't plugged in keep what was known,
+            // rather than being pinned to whatever volume holds the old path
+            let known = std::mem::take(&mut settings.root_volumes);
+            settings.root_volumes = roots.iter()
+                .filter_map(|root| {
+                    let previous = known.iter().find(|volume| &volume.path == root);
+                    match previous {
+                        Some(volume) if !mounted.iter().any(|mount| mount.id == volume.volume_id) => Some(volume.clone()),
+                        _ => volumes::fingerprint(root, mounted).or_else(|| previous.cloned()),
+                    }
+                })
+                .collect::<Vec<RootVolume>>();
+        })?;
+        Ok(remapped)
+    }
+}
@@ -0,0 +1,242 @@
+//! Keeps one app instance per data directory. The first instance creates
+//! `instance.json` and listens on a loopback socket named in it; later
+//! launches find the file, hand their command line to that socket and
+//! exit, so the index writer and the watchers are never opened twice. A
+//! file whose socket no longer answers, or whose process is gone, was left
+//! by a crashed instance and is taken over.
+
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+use sysinfo::{Pid, PidExt, System, SystemExt};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use log::{info, warn};
+use ts_rs::TS;
+use crate::utils;
+
+const INSTANCE_FILE: &str = "instance.json";
+const CLAIM_ATTEMPTS: usize = 20;
+const CLAIM_RETRY_DELAY: Duration = Duration::from_millis(100);
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a connection gets to hand over its launch.
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest launch read from a connection; command lines are far shorter.
+const MAX_LAUNCH_BYTES: u64 = 256 * 1024;
+/// An instance file still empty after this long was never finished.
+const UNFINISHED_FILE_AGE: Duration = Duration::from_secs(5);
+const DELIVERED: &str = "delivered";
+
+/// What a later launch was started with, handed to the running instance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct Launch {
+    /// Command line arguments after the executable, e.g. a folder to
+    /// search or a `constella://` link.
+    pub args: Vec<String>,
+    /// The directory it was started from, for relative paths in `args`.
+    pub cwd: Option<String>,
+}
+
+impl Launch {
+    /// The launch of the current process.
+    pub fn current() -> Self {
+        Self {
+            args: std::env::args().skip(1).collect(),
+            cwd: std::env::current_dir().ok().map(|dir| dir.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InstanceInfo {
+    port: u16,
+    token: String,
+    pid: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    token: String,
+    launch: Launch,
+}
+
+pub enum Claim {
+    /// No other instance is running; this one should start.
+    Primary(PrimaryInstance),
+    /// The launch went to the running instance; this one should exit.
+    Forwarded,
+}
+
+/// The listening side, held by the instance that started first.
+pub struct PrimaryInstance {
+    listener: TcpListener,
+    token: String,
+}
+
+pub fn instance_file(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(INSTANCE_FILE)
+}
+
+/// Becomes the instance for `app_data_dir`, or hands `launch` to the one
+/// already running there.
+pub async fn claim(app_data_dir: &Path, launch: &Launch) -> Result<Claim, String> {
+    std::fs::create_dir_all(app_data_dir)
+        .map_err(|e| format!("Failed to create {}: {}", app_data_dir.display(), e))?;
+    let path = instance_file(app_data_dir);
+    for _ in 0..CLAIM_ATTEMPTS {
+        // Only this user may read the token in it
+        match utils::create_private(&path) {
+            Ok(file) => return PrimaryInstance::publish(&path, file).await.map(Claim::Primary),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(format!("Failed to create {}: {}", path.display(), e)),
+        }
+
+        let info = std::fs::read_to_string(&path).ok()
+            .and_then(|json| serde_json::from_str::<InstanceInfo>(&json).ok());
+        match info {
+            Some(info) => match tokio::time::timeout(FORWARD_TIMEOUT, forward(&info, launch)).await
+                .unwrap_or_else(|_| Err(ErrorKind::TimedOut.into()))
+            {
+                Ok(()) => {
+                    info!("Handed the launch to the running instance (pid {})", info.pid);
+                    return Ok(Claim::Forwarded);
+                }
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused || !process_alive(info.pid) => {
+                    warn!("Taking over from instance {}, which is gone ({})", info.pid, e);
+                    remove_instance_file(&path);
+                    continue;
+                }
+                Err(e) => warn!("Failed to reach the running instance: {}", e),
+            },
+            // Still being written by an instance starting right now
+            None if file_age(&path) > UNFINISHED_FILE_AGE => {
+                remove_instance_file(&path);
+                continue;
+            }
+            None => {}
+        }
+        tokio::time::sleep(CLAIM_RETRY_DELAY).await;
+    }
+    Err("Another Constella instance is running but doesn't respond".to_string())
+}
+
+/// Removes the instance file when this process wrote it, e.g. on exit.
+pub fn release(app_data_dir: &Path) {
+    let path = instance_file(app_data_dir);
+    let ours = std::fs::read_to_string(&path).ok()
+        .and_then(|json| serde_json::from_str::<InstanceInfo>(&json).ok())
+        .is_some_and(|info| info.pid == std::process::id());
+    if ours {
+        remove_instance_file(&path);
+    }
+}
+
+fn remove_instance_file(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != ErrorKind::NotFound {
+            warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Whether a process with `pid` is running. A recycled pid counts as
+/// running, which only means waiting for the claim to time out.
+fn process_alive(pid: u32) -> bool {
+    System::new().refresh_process(Pid::from_u32(pid))
+}
+
+fn file_age(path: &Path) -> Duration {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .unwrap_or_default()
+}
+
+async fn forward(info: &InstanceInfo, launch: &Launch) -> std::io::Result<()> {
+    let stream = TcpStream::connect(("127.0.0.1", info.port)).await?;
+    let (reader, mut writer) = stream.into_split();
+    let envelope = Envelope { token: info.token.clone(), launch: launch.clone() };
+    let mut json = serde_json::to_string(&envelope)?;
+    json.push('\n');
+    writer.write_all(json.as_bytes()).await?;
+
+    let reply = BufReader::new(reader).lines().next_line().await?;
+    match reply.as_deref() {
+        Some(DELIVERED) => Ok(()),
+        _ => Err(std::io::Error::new(ErrorKind::Other, "the running instance turned the launch down")),
+    }
+}
+
+impl PrimaryInstance {
+    /// Binds the socket and writes where to find it into `file`.
+    async fn publish(path: &Path, mut file: std::fs::File) -> Result<Self, String> {
+        let published = async {
+            let listener = TcpListener::bind("127.0.0.1:0").await
+                .map_err(|e| format!("Failed to bind instance socket: {}", e))?;
+            let port = listener.local_addr()
+                .map_err(|e| format!("Failed to read instance address: {}", e))?
+                .port();
+            let info = InstanceInfo {
+                port,
+                token: utils::random_token()?,
+                pid: std::process::id(),
+            };
+            let json = serde_json::to_string(&info)
+                .map_err(|e| format!("Failed to serialize instance info: {}", e))?;
+            file.write_all(json.as_bytes())
+                .and_then(|_| file.sync_all())
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            info!("Single instance listening on 127.0.0.1:{}", port);
+            Ok(Self { listener, token: info.token })
+        }.await;
+        if published.is_err() {
+            remove_instance_file(path);
+        }
+        published
+    }
+
+    /// Calls `on_launch` with every launch other instances hand over, for
+    /// the life of the process. Each connection is handled on its own task,
+    /// so one that stalls doesn't hold up the next launch.
+    pub async fn serve(self, on_launch: impl Fn(Launch) + Send + Sync + 'static) {
+        let on_launch = Arc::new(on_launch);
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("Failed to accept instance connection: {}", e);
+                    continue;
+                }
+            };
+            let token = self.token.clone();
+            let on_launch = on_launch.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(RECEIVE_TIMEOUT, receive(stream, &token, &*on_launch)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => warn!("Instance connection error: {}", e),
+                    Err(_) => warn!("Instance connection timed out before a launch arrived"),
+                }
+            });
+        }
+    }
+}
+
+async fn receive(stream: TcpStream, token: &str, on_launch: &impl Fn(Launch)) -> Result<(), String> {
+    let (reader, mut writer) = stream.into_split();
+    let line = BufReader::new(reader.take(MAX_LAUNCH_BYTES)).lines().next_line().await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Connection closed before a launch arrived".to_string())?;
+    let envelope: Envelope = serde_json::from_str(&line)
+        .map_err(|e| format!("Malformed launch: {}", e))?;
+    if envelope.token != token {
+        return Err("Invalid token".to_string());
+    }
+    info!("Another launch was handed over: {:?}", envelope.launch.args);
+    on_launch(envelope.launch);
+    writer.write_all(format!("{}\n", DELIVERED).as_bytes()).await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,158 @@
+//! Decides whether a command may go ahead given the jobs already queued or
+//! running, so commands arriving from several windows at once can't
+//! trample each other.
+//!
+//! Jobs move from `Queued` to `Running` and end `Completed`, `Cancelled`
+//! or `Failed`; a queued job can also be cancelled before it starts. Jobs
+//! run one at a time, so most conflicting work simply waits its turn. The
+//! coordinator refuses what would repeat or undermine a job that hasn't
+//! finished yet:
+//!
+//! - Indexing a folder that an unfinished indexing job covers, or one
+//!   that covers it, fails with `IndexingAlreadyRunning`. Any other folder
+//!   fails with `IndexingInProgress`: the index holds one folder at a
+//!   time and each run replaces what the one before it indexed, so a
+//!   second folder queued behind the first would leave the first
+//!   unsearchable.
+//! - Optimizing or verifying checksums while the same kind of job is
+//!   unfinished fails with `AlreadyQueued`.
+//! - Changing what an indexing run depends on, such as the indexing
+//!   config, sharding or folder remaps, or reconciling, while an indexing
+//!   job is unfinished fails with `IndexingInProgress`. Cancel the job or
+//!   try again once it's done.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use parking_lot::Mutex;
+use serde::Serialize;
+use ts_rs::TS;
+use super::{JobContext, JobId, JobKind, JobManager};
+
+/// Why a command was turned down.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum CommandConflict {
+    IndexingAlreadyRunning {
+        #[ts(type = "number")]
+        job: JobId,
+        directory: PathBuf,
+    },
+    AlreadyQueued {
+        #[ts(type = "number")]
+        job: JobId,
+        job_kind: JobKind,
+    },
+    IndexingInProgress {
+        #[ts(type = "number")]
+        job: JobId,
+    },
+}
+
+impl std::fmt::Display for CommandConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandConflict::IndexingAlreadyRunning { job, directory } => {
+                write!(f, "{} is already being indexed by job {}", directory.display(), job)
+            }
+            CommandConflict::AlreadyQueued { job, job_kind } => {
+                write!(f, "A {:?} job ({}) is already waiting to run", job_kind, job)
+            }
+            CommandConflict::IndexingInProgress { job } => {
+                write!(f, "Indexing job {} is still running; cancel it or try again once it's done", job)
+            }
+        }
+    }
+}
+
+/// The error of a command the coordinator looks at: a conflict, or a
+/// failure of the command itself.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(untagged)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum CommandError {
+    Conflict(CommandConflict),
+    Failed(String),
+}
+
+impl From<CommandConflict> for CommandError {
+    fn from(conflict: CommandConflict) -> Self {
+        CommandError::Conflict(conflict)
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(error: String) -> Self {
+        CommandError::Failed(error)
+    }
+}
+
+pub struct CommandCoordinator {
+    jobs: Arc<JobManager>,
+    // Folders of the indexing jobs submitted through here, by job. Held
+    // while checking and submitting so two commands can't both pass.
+    indexing: Mutex<HashMap<JobId, PathBuf>>,
+}
+
+impl CommandCoordinator {
+    pub fn new(jobs: Arc<JobManager>) -> Self {
+        Self { jobs, indexing: Mutex::new(HashMap::new()) }
+    }
+
+    fn is_unfinished(&self, job: JobId) -> bool {
+        self.jobs.get(job).is_some_and(|info| !info.status.is_finished())
+    }
+
+    /// Queues indexing `directory`, unless another indexing job is
+    /// unfinished. The run replaces whatever earlier runs indexed.
+    pub fn submit_indexing<F, Fut>(&self, directory: &str, run: F) -> Result<JobId, CommandConflict>
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Option<serde_json::Value>, String>> + Send + 'static,
+    {
+        let directory = PathBuf::from(directory);
+        let mut indexing = self.indexing.lock();
+        indexing.retain(|job, _| self.is_unfinished(*job));
+        let overlapping = indexing.iter()
+            .find(|(_, queued)| directory.starts_with(queued) || queued.starts_with(&directory));
+        if let Some((job, queued)) = overlapping {
+            return Err(CommandConflict::IndexingAlreadyRunning { job: *job, directory: queued.clone() });
+        }
+        if let Some(job) = indexing.keys().min() {
+            return Err(CommandConflict::IndexingInProgress { job: *job });
+        }
+        let job = self.jobs.submit(JobKind::Indexing, run);
+        indexing.insert(job, directory);
+        Ok(job)
+    }
+
+    /// Queues a job there's no point running twice in a row, unless one
+    /// of the same kind is still unfinished.
+    pub fn submit_exclusive<F, Fut>(&self, kind: JobKind, run: F) -> Result<JobId, CommandConflict>
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Option<serde_json::Value>, String>> + Send + 'static,
+    {
+        // Shares the lock so two of these can't both pass the check
+        let _indexing = self.indexing.lock();
+        let unfinished = self.jobs.list().into_iter()
+            .find(|job| job.kind == kind && !job.status.is_finished());
+        if let Some(job) = unfinished {
+            return Err(CommandConflict::AlreadyQueued { job: job.id, job_kind: kind });
+        }
+        Ok(self.jobs.submit(kind, run))
+    }
+
+    /// Fails while an indexing job is unfinished, for changes that would
+    /// alter a run halfway through.
+    pub fn check_no_indexing(&self) -> Result<(), CommandConflict> {
+        let mut indexing = self.indexing.lock();
+        indexing.retain(|job, _| self.is_unfinished(*job));
+        match indexing.keys().min() {
+            Some(job) => Err(CommandConflict::IndexingInProgress { job: *job }),
+            None => Ok(()),
+        }
+    }
+}
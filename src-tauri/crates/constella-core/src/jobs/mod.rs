@@ -0,0 +1,376 @@
+//! Long-running operations run as jobs: each gets an id, reports typed
+//! progress that can be polled or subscribed to, and can be cancelled.
+//! Jobs run one after another in submission order, since most of them need
+//! the single index writer.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{error, info};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Notify};
+use tokio::task::JoinHandle;
+use crate::indexing::IndexerState;
+use ts_rs::TS;
+
+pub mod coordinator;
+pub mod operations;
+
+pub type JobId = u64;
+
+/// Finished jobs kept around for `get`; older ones are forgotten.
+const MAX_FINISHED_JOBS: usize = 100;
+const EVENT_BUFFER_SIZE: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum JobKind {
+    Indexing,
+    Optimization,
+    DuplicateScan,
+    ChecksumVerification,
+    PiiInventory,
+    IndexMove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Cancelled | JobStatus::Failed)
+    }
+}
+
+/// Progress of a job, shaped by what kind of job it is.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum JobProgress {
+    Pending,
+    Indexing {
+        state: String,
+        processed_files: usize,
+        skipped_files: usize,
+        total_files: usize,
+        current_file: String,
+    },
+    Optimization {
+        segments: usize,
+    },
+    DuplicateScan {
+        hashed: usize,
+        candidates: usize,
+    },
+    ChecksumVerification {
+        checked: usize,
+    },
+    PiiInventory {
+        scanned: usize,
+        total: usize,
+    },
+    IndexMove {
+        #[ts(type = "number")]
+        copied_bytes: u64,
+        #[ts(type = "number")]
+        total_bytes: u64,
+    },
+}
+
+impl From<&IndexerState> for JobProgress {
+    fn from(state: &IndexerState) -> Self {
+        JobProgress::Indexing {
+            state: state.state.clone(),
+            processed_files: state.processed_files,
+            skipped_files: state.skipped_files,
+            total_files: state.total_files,
+            current_file: state.current_file.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct JobInfo {
+    #[ts(type = "number")]
+    pub id: JobId,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub progress: JobProgress,
+    /// What the operation produced, e.g. the duplicate groups of a scan.
+    #[ts(type = "unknown")]
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub submitted_at: u64,
+    #[ts(type = "number | null")]
+    pub finished_at: Option<u64>,
+}
+
+/// Cooperative cancellation: jobs check it between work items.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `cancel` has been called.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+struct JobEntry {
+    info: JobInfo,
+    token: CancellationToken,
+}
+
+struct JobTable {
+    jobs: RwLock<HashMap<JobId, JobEntry>>,
+    events: broadcast::Sender<JobInfo>,
+}
+
+impl JobTable {
+    fn update(&self, id: JobId, update_fn: impl FnOnce(&mut JobInfo)) {
+        let info = {
+            let mut jobs = self.jobs.write();
+            let Some(entry) = jobs.get_mut(&id) else {
+                return;
+            };
+            update_fn(&mut entry.info);
+            entry.info.clone()
+        };
+        // Nobody listening is fine; `get` still has the latest state
+        let _ = self.events.send(info);
+    }
+
+    /// Moves a queued job to running, unless it was cancelled while waiting.
+    fn start(&self, id: JobId) -> bool {
+        let mut started = false;
+        self.update(id, |job| {
+            if job.status == JobStatus::Queued {
+                job.status = JobStatus::Running;
+                started = true;
+            }
+        });
+        started
+    }
+
+    fn finish(&self, id: JobId, status: JobStatus, outcome: Result<Option<serde_json::Value>, String>) {
+        self.update(id, |job| {
+            job.status = status;
+            job.finished_at = Some(now());
+            match outcome {
+                Ok(result) => job.result = result,
+                Err(e) => job.error = Some(e),
+            }
+        });
+        self.forget_old_jobs();
+    }
+
+    fn forget_old_jobs(&self) {
+        let mut jobs = self.jobs.write();
+        let mut finished: Vec<JobId> = jobs.values()
+            .filter(|entry| entry.info.status.is_finished())
+            .map(|entry| entry.info.id)
+            .collect();
+        if finished.len() <= MAX_FINISHED_JOBS {
+            return;
+        }
+        finished.sort_unstable();
+        for id in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+            jobs.remove(id);
+        }
+    }
+}
+
+/// Handed to a running job to report progress and check for cancellation.
+#[derive(Clone)]
+pub struct JobContext {
+    id: JobId,
+    token: CancellationToken,
+    table: Arc<JobTable>,
+}
+
+impl JobContext {
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+
+    pub fn report(&self, progress: JobProgress) {
+        self.table.update(self.id, |job| job.progress = progress);
+    }
+}
+
+pub struct JobManager {
+    next_id: AtomicU64,
+    table: Arc<JobTable>,
+    // The most recently submitted job; each new one waits for it first
+    last: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Default for JobManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobManager {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        Self {
+            next_id: AtomicU64::new(0),
+            table: Arc::new(JobTable {
+                jobs: RwLock::new(HashMap::new()),
+                events,
+            }),
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Queues `run` behind every job submitted before it and returns its id
+    /// without waiting for it to start. `run` gets a context for reporting
+    /// progress and noticing cancellation; a job that notices and stops
+    /// early finishes as cancelled.
+    pub fn submit<F, Fut>(&self, kind: JobKind, run: F) -> JobId
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Option<serde_json::Value>, String>> + Send + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let token = CancellationToken::new();
+        let info = JobInfo {
+            id,
+            kind,
+            status: JobStatus::Queued,
+            progress: JobProgress::Pending,
+            result: None,
+            error: None,
+            submitted_at: now(),
+            finished_at: None,
+        };
+        self.table.jobs.write().insert(id, JobEntry { info: info.clone(), token: token.clone() });
+        let _ = self.table.events.send(info);
+
+        let context = JobContext { id, token, table: self.table.clone() };
+        let mut last = self.last.lock();
+        let previous = last.take();
+        *last = Some(tokio::spawn(async move {
+            if let Some(previous) = previous {
+                let _ = previous.await;
+            }
+            let table = context.table.clone();
+            if !table.start(id) {
+                return;
+            }
+
+            info!("Starting {:?} job {}", kind, id);
+            let cancelled = context.token.clone();
+            // Run in a task of its own so a panic fails the job instead of
+            // leaving it running forever
+            let outcome = match tokio::spawn(run(context)).await {
+                Ok(outcome) => outcome,
+                Err(e) => Err(format!("Job panicked: {}", e)),
+            };
+            let status = match &outcome {
+                Err(_) => JobStatus::Failed,
+                Ok(_) if cancelled.is_cancelled() => JobStatus::Cancelled,
+                Ok(_) => JobStatus::Completed,
+            };
+            match &outcome {
+                Err(e) => error!("{:?} job {} failed: {}", kind, id, e),
+                Ok(_) => info!("{:?} job {} finished as {:?}", kind, id, status),
+            }
+            table.finish(id, status, outcome);
+        }));
+        id
+    }
+
+    pub fn get(&self, id: JobId) -> Option<JobInfo> {
+        self.table.jobs.read().get(&id).map(|entry| entry.info.clone())
+    }
+
+    /// Every job still known, oldest first.
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = self.table.jobs.read().values()
+            .map(|entry| entry.info.clone())
+            .collect();
+        jobs.sort_by_key(|job| job.id);
+        jobs
+    }
+
+    /// Asks a job to stop. Queued jobs are cancelled on the spot; running
+    /// ones stop at their next check. Cancelling a finished job does nothing.
+    pub fn cancel(&self, id: JobId) -> Result<(), String> {
+        let token = self.table.jobs.read().get(&id)
+            .map(|entry| entry.token.clone())
+            .ok_or_else(|| format!("No job with id {}", id))?;
+        token.cancel();
+
+        let mut was_queued = false;
+        self.table.update(id, |job| {
+            if job.status == JobStatus::Queued {
+                job.status = JobStatus::Cancelled;
+                job.finished_at = Some(now());
+                was_queued = true;
+            }
+        });
+        if was_queued {
+            info!("Cancelled queued job {}", id);
+        }
+        Ok(())
+    }
+
+    /// Every change to any job, including progress reports.
+    pub fn subscribe(&self) -> broadcast::Receiver<JobInfo> {
+        self.table.events.subscribe()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
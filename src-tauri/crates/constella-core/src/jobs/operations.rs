@@ -0,0 +1,123 @@
+//! The built-in long-running operations, wired up to report progress to
+//! their job and stop when it is cancelled.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use crate::indexing::IndexManager;
+use crate::indexing::shards::ShardedIndex;
+use super::{JobContext, JobProgress};
+
+/// Shards have no single progress channel, so theirs is polled this often.
+const SHARD_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A full index of `directory`. Cancelling keeps what was indexed so far.
+pub async fn run_indexing(context: &JobContext, indexer: &IndexManager, directory: &str) -> Result<(), String> {
+    let mut progress = indexer.subscribe_progress();
+    let run = indexer.start_indexing(directory);
+    tokio::pin!(run);
+    let mut cancelling = false;
+    loop {
+        tokio::select! {
+            // Let the run mark itself started before a cancel can look at it
+            biased;
+            result = &mut run => {
+                context.report(JobProgress::from(&indexer.get_state()));
+                return result;
+            }
+            _ = context.cancelled(), if !cancelling => {
+                cancelling = true;
+                indexer.cancel().await?;
+            }
+            Ok(()) = progress.changed() => {
+                let state = progress.borrow_and_update().clone();
+                context.report(JobProgress::from(&state));
+            }
+        }
+    }
+}
+
+/// A full index of `directory` across the shards, reporting their
+/// combined progress. Cancelling stops every shard.
+pub async fn run_sharded_indexing(context: &JobContext, shards: &ShardedIndex, directory: &str) -> Result<(), String> {
+    let run = shards.start_indexing(directory);
+    tokio::pin!(run);
+    let mut cancelling = false;
+    let mut ticker = tokio::time::interval(SHARD_PROGRESS_INTERVAL);
+    loop {
+        tokio::select! {
+            biased;
+            result = &mut run => {
+                context.report(JobProgress::from(&shards.get_state()));
+                return result;
+            }
+            _ = context.cancelled(), if !cancelling => {
+                cancelling = true;
+                shards.cancel().await?;
+            }
+            _ = ticker.tick() => context.report(JobProgress::from(&shards.get_state())),
+        }
+    }
+}
+
+/// Merges the index into one segment. The merge itself can't be
+/// interrupted, so cancelling only helps before it starts.
+pub async fn run_optimization(context: &JobContext, indexer: &IndexManager) -> Result<(), String> {
+    let segments = indexer.segment_count()?;
+    context.report(JobProgress::Optimization { segments });
+    if context.is_cancelled() {
+        return Ok(());
+    }
+    indexer.optimize_segments().await?;
+    context.report(JobProgress::Optimization { segments: indexer.segment_count()? });
+    Ok(())
+}
+
+/// Re-hashes tracked files and reindexes those whose content changed
+/// unnoticed. Returns how many did.
+pub async fn run_checksum_verification(context: &JobContext, indexer: &IndexManager) -> Result<usize, String> {
+    let checked = AtomicUsize::new(0);
+    indexer.verify_checksums(|| {
+        let checked = checked.fetch_add(1, Ordering::SeqCst);
+        context.report(JobProgress::ChecksumVerification { checked });
+        !context.is_cancelled()
+    }).await
+}
+
+/// Finds files with identical content under `root`.
+pub async fn run_duplicate_scan(
+    context: &JobContext,
+    indexer: &IndexManager,
+    root: impl AsRef<Path>,
+) -> Result<Vec<crate::indexing::duplicates::DuplicateGroup>, String> {
+    indexer.find_duplicates(
+        root,
+        || !context.is_cancelled(),
+        |hashed, candidates| context.report(JobProgress::DuplicateScan { hashed, candidates }),
+    ).await
+}
+
+/// Counts likely personal data per directory under `root`.
+pub async fn run_pii_inventory(
+    context: &JobContext,
+    indexer: &IndexManager,
+    root: impl AsRef<Path>,
+) -> Result<crate::pii::PiiInventory, String> {
+    indexer.pii_inventory(
+        root,
+        || !context.is_cancelled(),
+        |scanned, total| context.report(JobProgress::PiiInventory { scanned, total }),
+    ).await
+}
+
+/// Moves the index into `new_location`, see `IndexManager::move_index`.
+pub async fn run_index_move(
+    context: &JobContext,
+    indexer: &IndexManager,
+    new_location: impl AsRef<Path>,
+) -> Result<crate::indexing::relocate::IndexMove, String> {
+    indexer.move_index(new_location.as_ref(), |copied_bytes, total_bytes| {
+        context.report(JobProgress::IndexMove { copied_bytes, total_bytes });
+        !context.is_cancelled()
+    }).await
+}
@@ -0,0 +1,314 @@
+//! Journal of the destructive file operations the app carries out for the
+//! user, so a delete, move or bulk rename can be undone, and redone, for a
+//! while afterwards. Every operation is a list of moves and is undone by
+//! making them backwards. Deleting moves files into the journal's holding
+//! folder; they are only removed for good once their entry expires.
+//!
+//! An operation either happens completely or not at all: when one of its
+//! moves fails, the moves already made are reversed before the error is
+//! returned.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use log::{info, warn};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// How long an operation can be undone or redone.
+pub const UNDO_TTL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum OperationKind {
+    Delete,
+    Move,
+    Rename,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct FileMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct Operation {
+    #[ts(type = "number")]
+    pub id: u64,
+    pub kind: OperationKind,
+    /// The moves that carry the operation out, in order.
+    pub moves: Vec<FileMove>,
+    /// Unix seconds after which it can no longer be undone.
+    #[ts(type = "number")]
+    pub expires_at: u64,
+}
+
+/// An operation and the moves just made for it: its own moves when it was
+/// carried out or redone, their reverse when it was undone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct AppliedOperation {
+    pub operation: Operation,
+    pub undone: bool,
+    pub moves: Vec<FileMove>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct JournalState {
+    next_id: u64,
+    /// Operations that can be undone, oldest first.
+    done: Vec<Operation>,
+    /// Undone operations that can be redone, oldest first.
+    undone: Vec<Operation>,
+}
+
+pub struct OperationJournal {
+    path: PathBuf,
+    holding_dir: PathBuf,
+    state: Mutex<JournalState>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl OperationJournal {
+    /// Loads the journal kept in `data_dir`, dropping expired operations.
+    pub fn load(data_dir: impl AsRef<Path>) -> Self {
+        let path = data_dir.as_ref().join("operation_journal.json");
+        let state = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse operation journal at {:?}, starting empty: {}", path, e);
+                JournalState::default()
+            }),
+            Err(_) => JournalState::default(),
+        };
+        let journal = Self {
+            path,
+            holding_dir: data_dir.as_ref().join("deleted"),
+            state: Mutex::new(state),
+        };
+        journal.expire();
+        journal
+    }
+
+    /// Operations that can be undone, most recent first.
+    pub fn history(&self) -> Vec<Operation> {
+        self.expire();
+        self.state.lock().done.iter().rev().cloned().collect()
+    }
+
+    /// Moves `paths` into the holding folder, where they stay until the
+    /// operation expires.
+    pub fn delete(&self, paths: &[PathBuf]) -> Result<AppliedOperation, String> {
+        self.perform(OperationKind::Delete, |id| {
+            let held = self.holding_dir.join(id.to_string());
+            paths.iter()
+                .enumerate()
+                .map(|(i, path)| {
+                    let name = path.file_name().ok_or_else(|| format!("Can't delete {}", path.display()))?;
+                    // Numbered so files with the same name don't collide
+                    Ok(FileMove { from: path.clone(), to: held.join(i.to_string()).join(name) })
+                })
+                .collect()
+        })
+    }
+
+    /// Moves `paths` into `folder`, keeping their names.
+    pub fn move_to(&self, paths: &[PathBuf], folder: &Path) -> Result<AppliedOperation, String> {
+        self.perform(OperationKind::Move, |_| {
+            paths.iter()
+                .map(|path| {
+                    let name = path.file_name().ok_or_else(|| format!("Can't move {}", path.display()))?;
+                    Ok(FileMove { from: path.clone(), to: folder.join(name) })
+                })
+                .collect()
+        })
+    }
+
+    /// Gives each path its new name, in place.
+    pub fn rename(&self, renames: &[(PathBuf, String)]) -> Result<AppliedOperation, String> {
+        self.perform(OperationKind::Rename, |_| {
+            renames.iter()
+                .map(|(path, name)| {
+                    if name.is_empty() || Path::new(name).components().count() != 1 {
+                        return Err(format!("{:?} isn't a valid name", name));
+                    }
+                    let parent = path.parent().ok_or_else(|| format!("Can't rename {}", path.display()))?;
+                    Ok(FileMove { from: path.clone(), to: parent.join(name) })
+                })
+                .collect()
+        })
+    }
+
+    /// Records and carries out the moves `plan` makes for the operation
+    /// with the given id.
+    fn perform(&self, kind: OperationKind, plan: impl FnOnce(u64) -> Result<Vec<FileMove>, String>) -> Result<AppliedOperation, String> {
+        self.expire();
+        let mut state = self.state.lock();
+        let moves = plan(state.next_id)?;
+        if moves.is_empty() {
+            return Err("No files selected".to_string());
+        }
+        apply_moves(&moves)?;
+        let operation = Operation { id: state.next_id, kind, moves: moves.clone(), expires_at: now() + UNDO_TTL.as_secs() };
+        state.next_id += 1;
+        state.done.push(operation.clone());
+        // A new operation makes what was undone before it unreachable
+        let abandoned = std::mem::take(&mut state.undone);
+        self.discard(&abandoned);
+        self.save(&state);
+        info!("{:?} of {} files, operation {}", kind, moves.len(), operation.id);
+        Ok(AppliedOperation { operation, undone: false, moves })
+    }
+
+    /// Reverses the most recent operation; `None` when there is nothing
+    /// left to undo.
+    pub fn undo_last(&self) -> Result<Option<AppliedOperation>, String> {
+        self.expire();
+        let mut state = self.state.lock();
+        let Some(operation) = state.done.last().cloned() else {
+            return Ok(None);
+        };
+        let moves: Vec<FileMove> = operation.moves.iter()
+            .rev()
+            .map(|step| FileMove { from: step.to.clone(), to: step.from.clone() })
+            .collect();
+        apply_moves(&moves)?;
+        state.done.pop();
+        state.undone.push(operation.clone());
+        self.save(&state);
+        info!("Undid operation {}", operation.id);
+        Ok(Some(AppliedOperation { operation, undone: true, moves }))
+    }
+
+    /// Carries out the most recently undone operation again; `None` when
+    /// there is nothing to redo.
+    pub fn redo(&self) -> Result<Option<AppliedOperation>, String> {
+        self.expire();
+        let mut state = self.state.lock();
+        let Some(operation) = state.undone.last().cloned() else {
+            return Ok(None);
+        };
+        apply_moves(&operation.moves)?;
+        state.undone.pop();
+        state.done.push(operation.clone());
+        self.save(&state);
+        info!("Redid operation {}", operation.id);
+        let moves = operation.moves.clone();
+        Ok(Some(AppliedOperation { operation, undone: false, moves }))
+    }
+
+    /// Forgets expired operations, removing the files of expired deletes
+    /// for good.
+    pub fn expire(&self) {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let now = now();
+        let mut expired = Vec::new();
+        for operations in [&mut state.done, &mut state.undone] {
+            let (gone, kept): (Vec<Operation>, Vec<Operation>) = std::mem::take(operations)
+                .into_iter()
+                .partition(|operation| operation.expires_at <= now);
+            *operations = kept;
+            expired.extend(gone);
+        }
+        if !expired.is_empty() {
+            self.discard(&expired);
+            self.save(state);
+            info!("Expired {} undoable operations", expired.len());
+        }
+    }
+
+    /// Removes what `operations` left in the holding folder. Only deletes
+    /// that are currently carried out have files there.
+    fn discard(&self, operations: &[Operation]) {
+        for operation in operations.iter().filter(|operation| operation.kind == OperationKind::Delete) {
+            let held = self.holding_dir.join(operation.id.to_string());
+            match std::fs::remove_dir_all(&held) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    warn!("Failed to remove deleted files in {:?}: {}", held, e);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn save(&self, state: &JournalState) {
+        let saved = serde_json::to_string_pretty(state)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                let tmp = self.path.with_extension("json.tmp");
+                std::fs::write(&tmp, json)
+                    .and_then(|_| std::fs::rename(&tmp, &self.path))
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = saved {
+            warn!("Failed to save operation journal: {}", e);
+        }
+    }
+}
+
+/// Makes `moves` in order, or none of them.
+fn apply_moves(moves: &[FileMove]) -> Result<(), String> {
+    for (done, step) in moves.iter().enumerate() {
+        if let Err(e) = move_path(&step.from, &step.to) {
+            for made in moves[..done].iter().rev() {
+                if let Err(e) = move_path(&made.to, &made.from) {
+                    warn!("Failed to move {:?} back to {:?}: {}", made.to, made.from, e);
+                }
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Moves a file or folder, copying it when it can't simply be renamed,
+/// as across volumes. Never replaces anything at `to`.
+fn move_path(from: &Path, to: &Path) -> Result<(), String> {
+    if std::fs::symlink_metadata(to).is_ok() {
+        return Err(format!("{} already exists", to.display()));
+    }
+    let metadata = std::fs::symlink_metadata(from)
+        .map_err(|e| format!("Failed to find {}: {}", from.display(), e))?;
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if let Err(e) = copy_path(from, to, metadata.is_dir()) {
+        // Don't leave half a copy behind
+        let _ = if metadata.is_dir() { std::fs::remove_dir_all(to) } else { std::fs::remove_file(to) };
+        return Err(format!("Failed to move {} to {}: {}", from.display(), to.display(), e));
+    }
+    let removed = if metadata.is_dir() {
+        std::fs::remove_dir_all(from)
+    } else {
+        std::fs::remove_file(from)
+    };
+    removed.map_err(|e| format!("Failed to move {} to {}: {}", from.display(), to.display(), e))
+}
+
+/// Copies a file, or a folder and everything in it.
+pub(crate) fn copy_path(from: &Path, to: &Path, is_dir: bool) -> std::io::Result<()> {
+    if !is_dir {
+        return std::fs::copy(from, to).map(|_| ());
+    }
+    std::fs::create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        copy_path(&entry.path(), &to.join(entry.file_name()), entry.file_type()?.is_dir())?;
+    }
+    Ok(())
+}
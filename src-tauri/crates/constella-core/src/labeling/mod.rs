@@ -0,0 +1,147 @@
+//! Coarse labels for images, like "receipt", "document", "person" or
+//! "landscape", so photos can be found by what they show with `label:`.
+//! Labeling is opt-in and stays on the machine: the classifier is a local
+//! program configured in settings, or one plugged in through
+//! `IndexOptions::classifier`, helped by cheap heuristics on file names and
+//! recognized text.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::extract::photo;
+use crate::settings::SettingsManager;
+
+/// The labels classifiers are expected to use. Others are kept as given.
+pub const COMMON_LABELS: &[&str] = &["receipt", "screenshot", "document", "person", "landscape"];
+
+/// Words on a receipt; text with a few of them is labeled one.
+const RECEIPT_WORDS: &[&str] = &["receipt", "total", "subtotal", "tax", "vat", "change due", "cash", "card", "qty"];
+/// Recognized text this long makes an image a document.
+const DOCUMENT_MIN_WORDS: usize = 80;
+
+/// Labels what an image shows.
+pub trait ImageClassifier: Send + Sync {
+    /// Labels for the encoded image `image`.
+    fn classify(&self, image: &[u8]) -> Result<Vec<String>, String>;
+
+    /// Whether `classify` can run at all, so images aren't read for nothing.
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ImageLabelingSettings {
+    pub enabled: bool,
+    /// A local program that reads an image on stdin and prints its labels,
+    /// one per line or comma-separated. Without one only the built-in
+    /// heuristics label images.
+    pub command: Option<PathBuf>,
+    pub args: Vec<String>,
+    /// Larger images are left unlabeled.
+    #[ts(type = "number")]
+    pub max_file_size: u64,
+}
+
+impl Default for ImageLabelingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: None,
+            args: Vec::new(),
+            max_file_size: 30 * 1024 * 1024,
+        }
+    }
+}
+
+/// Runs the program configured in settings.
+pub struct CommandClassifier {
+    settings: Arc<SettingsManager>,
+}
+
+impl CommandClassifier {
+    pub fn new(settings: Arc<SettingsManager>) -> Self {
+        Self { settings }
+    }
+}
+
+impl ImageClassifier for CommandClassifier {
+    fn classify(&self, image: &[u8]) -> Result<Vec<String>, String> {
+        let settings = self.settings.get().image_labeling;
+        let Some(command) = settings.command else {
+            return Ok(Vec::new());
+        };
+        let mut child = Command::new(&command)
+            .args(&settings.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", command.display(), e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(image)
+                .map_err(|e| format!("Failed to send image to {}: {}", command.display(), e))?;
+        }
+        let output = child.wait_with_output()
+            .map_err(|e| format!("Failed to run {}: {}", command.display(), e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to label image with {}: {}",
+                command.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .split([',', '\n'])
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn is_available(&self) -> bool {
+        self.settings.get().image_labeling.command.is_some()
+    }
+}
+
+/// Raw formats are missing from mime guessing, so photos are checked too.
+pub fn is_image(path: &Path) -> bool {
+    photo::is_photo(path)
+        || mime_guess::from_path(path)
+            .first()
+            .is_some_and(|mime| mime.type_() == mime_guess::mime::IMAGE)
+}
+
+/// Labels that follow from what is already known about an image: whether
+/// it looks like a screenshot, and the text recognized in it.
+pub fn heuristic_labels(is_screenshot: bool, text: Option<&str>) -> Vec<String> {
+    let mut labels = Vec::new();
+    if is_screenshot {
+        labels.push("screenshot".to_string());
+    }
+    if let Some(text) = text {
+        let lowercase = text.to_lowercase();
+        if RECEIPT_WORDS.iter().filter(|word| lowercase.contains(*word)).count() >= 3 {
+            labels.push("receipt".to_string());
+        }
+        if text.split_whitespace().count() >= DOCUMENT_MIN_WORDS {
+            labels.push("document".to_string());
+        }
+    }
+    labels
+}
+
+/// Lowercased, trimmed and deduplicated, in first-seen order.
+pub fn normalize(labels: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for label in labels {
+        let label = label.trim().to_lowercase();
+        if !label.is_empty() && !normalized.contains(&label) {
+            normalized.push(label);
+        }
+    }
+    normalized
+}
@@ -0,0 +1,43 @@
+//! Indexing, search, watching and persistence for Constella, independent of
+//! any UI. The Tauri app, the `constellad` daemon and tests all build on this.
+
+pub mod actions;
+pub mod audit;
+pub mod benchmarking;
+pub mod bundle;
+pub mod chaos;
+pub mod compare;
+pub mod daemon;
+pub mod events;
+pub mod extract;
+pub mod file_system;
+pub mod idle;
+pub mod instance;
+pub mod indexing;
+pub mod jobs;
+pub mod journal;
+pub mod labeling;
+pub mod ocr;
+pub mod organize;
+pub mod permissions;
+pub mod persistence;
+pub mod pii;
+pub mod portable;
+pub mod power;
+pub mod profiles;
+pub mod purge;
+pub mod rename;
+pub mod scanner;
+pub mod search;
+pub mod secrets;
+pub mod settings;
+pub mod stats;
+pub mod tracking;
+pub mod transcription;
+pub mod utils;
+pub mod versioning;
+pub mod volumes;
+pub mod watcher;
+
+pub use indexing::IndexManager;
+pub use settings::{Settings, SettingsManager};
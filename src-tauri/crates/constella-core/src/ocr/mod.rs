@@ -0,0 +1,10 @@
+//! Text recognition for images. The core ships no recognizer of its own;
+//! embedders plug one in through `IndexOptions::ocr`, e.g. backed by the
+//! platform's OCR service.
+
+/// Extracts the text shown in an image.
+pub trait TextRecognizer: Send + Sync {
+    /// Text found in the encoded image `image`, or an error when it can't
+    /// be decoded or read.
+    fn recognize(&self, image: &[u8]) -> Result<String, String>;
+}
@@ -0,0 +1,273 @@
+//! Rules that file things away as they show up: "PDFs with Invoice in them
+//! go to ~/Finance/{year}". Rules are checked against files as they are
+//! indexed after a change, first matching rule first, and only while
+//! organizing is switched on. Each move goes through the operation
+//! journal, so it can be undone like one the user made, and is kept in
+//! the log of the rule that made it.
+//!
+//! A destination is a folder template:
+//!
+//! - `{year}`, `{month}` and `{day}`: the file's modification date
+//! - `{ext}`: its extension, lowercased, without the dot
+//!
+//! A leading `~` stands for the home folder; a relative destination is
+//! taken from the folder the file is in.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use chrono::{DateTime, Local};
+use globset::{GlobBuilder, GlobMatcher};
+use log::{info, warn};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::journal::OperationJournal;
+use crate::settings::SettingsManager;
+use crate::IndexManager;
+
+/// Log entries kept per rule, newest first.
+pub const LOG_ENTRIES_PER_RULE: usize = 200;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct OrganizeSettings {
+    /// The kill switch: no rule moves anything while this is off.
+    pub enabled: bool,
+    /// Checked in order; a file is moved by the first rule it matches.
+    pub rules: Vec<OrganizeRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct OrganizeRule {
+    /// Identifies the rule and its log.
+    pub name: String,
+    pub enabled: bool,
+    /// Only files somewhere below this folder; anywhere indexed when unset.
+    pub folder: Option<PathBuf>,
+    /// Glob the file name has to match, ignoring case.
+    pub pattern: String,
+    /// Search query the file has to match too, e.g. `content:invoice`.
+    pub query: Option<String>,
+    /// Folder template the file is moved into.
+    pub destination: String,
+}
+
+impl Default for OrganizeRule {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            folder: None,
+            pattern: "*".to_string(),
+            query: None,
+            destination: String::new(),
+        }
+    }
+}
+
+impl OrganizeRule {
+    /// Checks what can be checked without an index: the query is parsed
+    /// when the rule is run.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Rules need a name".to_string());
+        }
+        self.matcher()?;
+        if self.destination.trim().is_empty() {
+            return Err(format!("Rule {} has no destination", self.name));
+        }
+        self.destination_for(Path::new("/check.txt"), 0).map(|_| ())
+    }
+
+    pub(crate) fn matcher(&self) -> Result<GlobMatcher, String> {
+        GlobBuilder::new(&self.pattern)
+            .case_insensitive(true)
+            .build()
+            .map(|glob| glob.compile_matcher())
+            .map_err(|e| format!("Invalid pattern {:?} in rule {}: {}", self.pattern, self.name, e))
+    }
+
+    /// The folder `path`, last modified at unix second `modified`, goes to.
+    pub fn destination_for(&self, path: &Path, modified: u64) -> Result<PathBuf, String> {
+        let date: DateTime<Local> = DateTime::from_timestamp(modified as i64, 0)
+            .unwrap_or_default()
+            .with_timezone(&Local);
+        let mut folder = String::new();
+        let mut rest = self.destination.trim();
+        while let Some(start) = rest.find('{') {
+            folder.push_str(&rest[..start]);
+            let end = rest[start..].find('}')
+                .ok_or_else(|| format!("Unclosed {{ in the destination of rule {}", self.name))?;
+            let token = &rest[start + 1..start + end];
+            match token {
+                "year" => folder.push_str(&date.format("%Y").to_string()),
+                "month" => folder.push_str(&date.format("%m").to_string()),
+                "day" => folder.push_str(&date.format("%d").to_string()),
+                "ext" => {
+                    let ext = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+                    folder.push_str(ext.as_deref().unwrap_or("other"));
+                }
+                _ => return Err(format!("Unknown token {{{}}} in the destination of rule {}", token, self.name)),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        folder.push_str(rest);
+
+        let folder = match folder.strip_prefix('~') {
+            Some(relative) => {
+                let home = dirs::home_dir().ok_or("Failed to find the home folder")?;
+                home.join(relative.trim_start_matches(['/', '\\']))
+            }
+            None => PathBuf::from(folder),
+        };
+        if folder.is_absolute() {
+            return Ok(folder);
+        }
+        let parent = path.parent().ok_or_else(|| format!("Can't organize {}", path.display()))?;
+        Ok(parent.join(folder))
+    }
+}
+
+/// A move a rule makes, or would make in a dry run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PlannedMove {
+    pub rule: String,
+    pub path: PathBuf,
+    pub folder: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct OrganizeLogEntry {
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub at: u64,
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// The journal operation to undo it with, when the move was made.
+    #[ts(type = "number | null")]
+    pub operation: Option<u64>,
+    pub error: Option<String>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Runs the rules in settings and keeps each rule's log.
+pub struct Organizer {
+    settings: Arc<SettingsManager>,
+    path: PathBuf,
+    /// Entries by rule name, newest first.
+    log: Mutex<HashMap<String, Vec<OrganizeLogEntry>>>,
+}
+
+impl Organizer {
+    /// Loads the rule logs kept in `data_dir`.
+    pub fn load(data_dir: impl AsRef<Path>, settings: Arc<SettingsManager>) -> Self {
+        let path = data_dir.as_ref().join("organize_log.json");
+        let log = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse organize log at {:?}, starting empty: {}", path, e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self { settings, path, log: Mutex::new(log) }
+    }
+
+    /// Moves whichever of `changed` an enabled rule matches, when
+    /// organizing is switched on; returns the entries logged.
+    pub async fn organize(
+        &self,
+        indexer: &IndexManager,
+        journal: &OperationJournal,
+        changed: &[PathBuf],
+    ) -> Result<Vec<OrganizeLogEntry>, String> {
+        let settings = self.settings.get().organize;
+        if !settings.enabled || changed.is_empty() {
+            return Ok(Vec::new());
+        }
+        let rules: Vec<OrganizeRule> = settings.rules.into_iter().filter(|rule| rule.enabled).collect();
+        if rules.is_empty() {
+            return Ok(Vec::new());
+        }
+        let planned = indexer.plan_organize(&rules, Some(changed)).await?;
+
+        // One undoable operation per rule and destination
+        let mut groups: BTreeMap<(String, PathBuf), Vec<PathBuf>> = BTreeMap::new();
+        for planned in planned {
+            groups.entry((planned.rule, planned.folder)).or_default().push(planned.path);
+        }
+        let mut logged = Vec::new();
+        for ((rule, folder), paths) in groups {
+            let entries: Vec<OrganizeLogEntry> = match journal.move_to(&paths, &folder) {
+                Ok(applied) => {
+                    let moves: Vec<(PathBuf, PathBuf)> = applied.moves.iter()
+                        .map(|step| (step.from.clone(), step.to.clone()))
+                        .collect();
+                    if let Err(e) = indexer.index_moves(&moves).await {
+                        warn!("Failed to update the index after organizing: {}", e);
+                    }
+                    info!("Rule {} moved {} files to {:?}", rule, moves.len(), folder);
+                    moves.into_iter()
+                        .map(|(from, to)| OrganizeLogEntry { at: now(), from, to, operation: Some(applied.operation.id), error: None })
+                        .collect()
+                }
+                Err(e) => {
+                    warn!("Rule {} failed to move files to {:?}: {}", rule, folder, e);
+                    paths.into_iter()
+                        .map(|path| {
+                            let to = path.file_name().map(|name| folder.join(name)).unwrap_or_else(|| folder.clone());
+                            OrganizeLogEntry { at: now(), from: path, to, operation: None, error: Some(e.clone()) }
+                        })
+                        .collect()
+                }
+            };
+            self.record(&rule, &entries);
+            logged.extend(entries);
+        }
+        Ok(logged)
+    }
+
+    /// What `rule` would move among everything indexed now, without moving
+    /// anything. Works whether or not the rule or organizing is enabled.
+    pub async fn preview(&self, indexer: &IndexManager, rule: &OrganizeRule) -> Result<Vec<PlannedMove>, String> {
+        rule.validate()?;
+        indexer.plan_organize(std::slice::from_ref(rule), None).await
+    }
+
+    /// The log of rule `name`, newest first.
+    pub fn log(&self, name: &str) -> Vec<OrganizeLogEntry> {
+        self.log.lock().get(name).cloned().unwrap_or_default()
+    }
+
+    fn record(&self, rule: &str, entries: &[OrganizeLogEntry]) {
+        let mut log = self.log.lock();
+        let rule_log = log.entry(rule.to_string()).or_default();
+        for entry in entries {
+            rule_log.insert(0, entry.clone());
+        }
+        rule_log.truncate(LOG_ENTRIES_PER_RULE);
+        let saved = serde_json::to_string(&*log)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                let tmp = self.path.with_extension("json.tmp");
+                std::fs::write(&tmp, json)
+                    .and_then(|_| std::fs::rename(&tmp, &self.path))
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = saved {
+            warn!("Failed to save organize log: {}", e);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Operating system settings that keep indexing from seeing most of the
+//! disk, checked during onboarding: Full Disk Access and the per-folder
+//! grants on macOS, long path support on Windows. Each check that fails
+//! comes with the steps to fix it, so a first run doesn't quietly skip
+//! whatever the OS hides.
+
+use std::path::{Path, PathBuf};
+use log::info;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum PermissionKind {
+    /// macOS: reading Mail, Messages, Safari and other apps' data.
+    FullDiskAccess,
+    /// macOS: the Desktop, Documents and Downloads folders, granted one by one.
+    ProtectedFolders,
+    /// Windows: paths longer than 260 characters.
+    LongPaths,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PermissionCheck {
+    pub kind: PermissionKind,
+    /// `None` when it couldn't be told either way.
+    pub granted: Option<bool>,
+    /// What goes missing from the index without it.
+    pub impact: String,
+    /// What to do to grant it, in order; empty once granted.
+    pub steps: Vec<String>,
+    /// Whether `open_permission_settings` can take the user there.
+    pub opens_settings: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PermissionStatus {
+    /// Checks that apply to this platform; none on Linux.
+    pub checks: Vec<PermissionCheck>,
+    /// No check is known to have failed.
+    pub all_granted: bool,
+}
+
+impl PermissionStatus {
+    fn new(checks: Vec<PermissionCheck>) -> Self {
+        let all_granted = checks.iter().all(|check| check.granted != Some(false));
+        Self { checks, all_granted }
+    }
+}
+
+impl PermissionCheck {
+    /// The check for `kind`, with the steps to take unless it's `granted`.
+    pub fn new(kind: PermissionKind, granted: Option<bool>) -> Self {
+        let steps = if granted == Some(true) { Vec::new() } else { remediation(kind) };
+        Self {
+            kind,
+            granted,
+            impact: impact(kind).to_string(),
+            steps,
+            opens_settings: settings_url(kind).is_some(),
+        }
+    }
+}
+
+fn impact(kind: PermissionKind) -> &'static str {
+    match kind {
+        PermissionKind::FullDiskAccess => "Files in Mail, Messages, Safari and other apps' data are left out of the index.",
+        PermissionKind::ProtectedFolders => "Files on the Desktop and in Documents and Downloads are left out of the index.",
+        PermissionKind::LongPaths => "Files nested deeper than 260 characters are left out of the index.",
+    }
+}
+
+fn remediation(kind: PermissionKind) -> Vec<String> {
+    let steps: &[&str] = match kind {
+        PermissionKind::FullDiskAccess => &[
+            "Open System Settings > Privacy & Security > Full Disk Access.",
+            "Turn on Constella, adding it with + if it isn't listed.",
+            "Quit and reopen Constella, then index again.",
+        ],
+        PermissionKind::ProtectedFolders => &[
+            "Open System Settings > Privacy & Security > Files and Folders.",
+            "Under Constella, turn on Desktop Folder, Documents Folder and Downloads Folder.",
+            "Index again.",
+        ],
+        PermissionKind::LongPaths => &[
+            "Open PowerShell as administrator.",
+            r"Run: New-ItemProperty -Path 'HKLM:\SYSTEM\CurrentControlSet\Control\FileSystem' -Name LongPathsEnabled -Value 1 -PropertyType DWORD -Force",
+            "Restart Windows, then index again.",
+        ],
+    };
+    steps.iter().map(|step| step.to_string()).collect()
+}
+
+fn settings_url(kind: PermissionKind) -> Option<&'static str> {
+    match kind {
+        PermissionKind::FullDiskAccess => Some("x-apple.systempreferences:com.apple.preference.security?Privacy_AllFiles"),
+        PermissionKind::ProtectedFolders => Some("x-apple.systempreferences:com.apple.preference.security?Privacy_FilesAndFolders"),
+        PermissionKind::LongPaths => None,
+    }
+}
+
+/// Whether listing `dir` is allowed; `None` when it failed for another
+/// reason, e.g. because it doesn't exist.
+pub fn readable(dir: &Path) -> Option<bool> {
+    match std::fs::read_dir(dir) {
+        Ok(_) => Some(true),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => Some(false),
+        Err(_) => None,
+    }
+}
+
+/// Whether all `dirs` that could be checked are readable.
+pub fn all_readable(dirs: &[PathBuf]) -> Option<bool> {
+    dirs.iter().filter_map(|dir| readable(dir)).reduce(|all, readable| all && readable)
+}
+
+/// The `LongPathsEnabled` value in output of `reg query`.
+pub fn parse_long_paths_enabled(output: &str) -> Option<bool> {
+    output.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        if fields.next()? != "LongPathsEnabled" {
+            return None;
+        }
+        let value = fields.nth(1)?;
+        u32::from_str_radix(value.trim_start_matches("0x"), 16).ok().map(|value| value != 0)
+    })
+}
+
+/// The checks for this platform.
+pub fn get_permission_status() -> PermissionStatus {
+    let status = PermissionStatus::new(platform_checks());
+    if !status.all_granted {
+        info!("Missing permissions: {:?}", status.checks.iter()
+            .filter(|check| check.granted == Some(false))
+            .map(|check| check.kind)
+            .collect::<Vec<_>>());
+    }
+    status
+}
+
+#[cfg(target_os = "macos")]
+fn platform_checks() -> Vec<PermissionCheck> {
+    let Some(home) = dirs::home_dir() else {
+        log::warn!("Failed to find the home folder to check permissions");
+        return Vec::new();
+    };
+    // Only readable with Full Disk Access
+    let full_disk = readable(&home.join("Library/Application Support/com.apple.TCC"))
+        .or_else(|| readable(&home.join("Library/Safari")));
+    let folders: Vec<PathBuf> = ["Desktop", "Documents", "Downloads"].iter().map(|folder| home.join(folder)).collect();
+    vec![
+        PermissionCheck::new(PermissionKind::FullDiskAccess, full_disk),
+        PermissionCheck::new(PermissionKind::ProtectedFolders, all_readable(&folders)),
+    ]
+}
+
+#[cfg(windows)]
+fn platform_checks() -> Vec<PermissionCheck> {
+    let output = std::process::Command::new("reg")
+        .args(["query", r"HKLM\SYSTEM\CurrentControlSet\Control\FileSystem", "/v", "LongPathsEnabled"])
+        .output();
+    let enabled = match output {
+        // A missing value means the default, off
+        Ok(output) => Some(parse_long_paths_enabled(&String::from_utf8_lossy(&output.stdout)).unwrap_or(false)),
+        Err(e) => {
+            log::warn!("Failed to check long path support: {}", e);
+            None
+        }
+    };
+    vec![PermissionCheck::new(PermissionKind::LongPaths, enabled)]
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn platform_checks() -> Vec<PermissionCheck> {
+    Vec::new()
+}
+
+/// Opens the system settings page that grants `kind`.
+pub fn open_settings(kind: PermissionKind) -> Result<(), String> {
+    let url = settings_url(kind)
+        .ok_or_else(|| format!("There is no settings page for {:?}", kind))?;
+    crate::actions::open_path(Path::new(url))
+}
@@ -0,0 +1,152 @@
+pub mod wal;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use serde::{Serialize, Deserialize};
+use tantivy::directory::MmapDirectory;
+use crate::file_system::os_path;
+use crate::stats::IndexStats;
+use crate::tracking::{ChangeTracker, FileState};
+use log::{info, warn};
+use wal::WalStore;
+
+/// How often tracker state is flushed to disk while the app is running.
+pub const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+pub struct PersistenceManager {
+    index_path: PathBuf,
+    /// Where tracker state was kept before it moved into `file_states`.
+    legacy_state_path: PathBuf,
+    stats_path: PathBuf,
+    /// Keyed by `os_path::encode`d paths, which survive JSON whatever
+    /// bytes the path holds.
+    file_states: Arc<WalStore<String, FileState>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedState {
+    pub version: String,
+    pub last_save: SystemTime,
+    pub file_states: HashMap<PathBuf, FileState>,
+    pub stats: IndexStats,
+    config: IndexConfig,
+}
+
+impl Default for SavedState {
+    fn default() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            last_save: SystemTime::now(),
+            file_states: HashMap::new(),
+            stats: IndexStats::new(),
+            config: IndexConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexConfig {
+    root_paths: Vec<PathBuf>,
+    excluded_patterns: Vec<String>,
+    max_file_size: u64,
+    index_batch_size: usize,
+    compression_enabled: bool,
+}
+
+impl PersistenceManager {
+    pub fn new(base_path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let base_path = base_path.as_ref();
+        std::fs::create_dir_all(base_path)?;
+
+        let file_states = WalStore::open(base_path.join("tracking"))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
+        Ok(Self {
+            index_path: base_path.join("index"),
+            legacy_state_path: base_path.join("state.json"),
+            stats_path: base_path.join("stats.json"),
+            file_states: Arc::new(file_states),
+        })
+    }
+
+    pub async fn load_index(&self) -> tantivy::Result<Option<tantivy::Index>> {
+        if !self.index_path.exists() {
+            return Ok(None);
+        }
+
+        let directory = MmapDirectory::open(&self.index_path)?;
+        let index = tantivy::Index::open(directory)?;
+        Ok(Some(index))
+    }
+
+    /// Logs what changed since the last save; the log is synced before
+    /// this returns.
+    pub async fn save_state(&self, file_states: &HashMap<PathBuf, FileState>) -> std::io::Result<()> {
+        let entries = file_states.iter()
+            .map(|(path, state)| (os_path::encode(path), state.clone()))
+            .collect();
+        let store = self.file_states.clone();
+        tokio::task::spawn_blocking(move || store.replace(entries))
+            .await
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+    }
+
+    pub async fn save_stats(&self, stats: &IndexStats) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(stats)?;
+        tokio::fs::write(&self.stats_path, json).await?;
+        Ok(())
+    }
+
+    pub async fn load_state(&self) -> std::io::Result<Option<SavedState>> {
+        if self.file_states.is_empty() && self.legacy_state_path.exists() {
+            self.migrate_legacy_state().await?;
+        }
+        if self.file_states.is_empty() {
+            return Ok(None);
+        }
+
+        let file_states = self.file_states.read()
+            .iter()
+            .map(|(path, state)| (os_path::decode(path), state.clone()))
+            .collect();
+        Ok(Some(SavedState { file_states, ..SavedState::default() }))
+    }
+
+    /// Moves state saved by earlier versions into the log, then removes
+    /// the old file.
+    async fn migrate_legacy_state(&self) -> std::io::Result<()> {
+        let json = tokio::fs::read_to_string(&self.legacy_state_path).await?;
+        let state: SavedState = serde_json::from_str(&json)?;
+        info!("Moving tracking state for {} files into the log", state.file_states.len());
+        self.save_state(&state.file_states).await?;
+        tokio::fs::remove_file(&self.legacy_state_path).await
+    }
+}
+
+/// Restores tracker state from disk, then keeps saving it every
+/// `STATE_SAVE_INTERVAL` for the life of the process.
+pub fn spawn_tracker_persistence(persistence: Arc<PersistenceManager>, tracker: Arc<ChangeTracker>) {
+    tokio::spawn(async move {
+        match persistence.load_state().await {
+            Ok(Some(state)) => {
+                info!("Restored tracking state for {} files", state.file_states.len());
+                tracker.import_states(state.file_states).await;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to load tracking state: {}", e),
+        }
+
+        let mut save_timer = tokio::time::interval(STATE_SAVE_INTERVAL);
+        save_timer.tick().await;
+        loop {
+            save_timer.tick().await;
+            let states = tracker.export_states().await;
+            if let Err(e) = persistence.save_state(&states).await {
+                warn!("Failed to save tracking state: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,315 @@
+//! A small key-value store for sidecar metadata that survives crashes
+//! mid-write. Each change is appended to a log as one checksummed line and
+//! synced to disk before the call returns; once the log grows long, the
+//! whole map is written to a snapshot and the log starts over. Opening
+//! replays the log over the snapshot and stops at the first torn or
+//! corrupt line, so a crash costs at most the change it interrupted.
+
+use std::borrow::Borrow;
+use std::collections::btree_map::{self, BTreeMap};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use parking_lot::{RwLock, RwLockReadGuard};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// The log is compacted into a snapshot after this many lines, or once
+/// it is this large.
+const COMPACT_AFTER_LINES: usize = 1_000;
+const COMPACT_AFTER_BYTES: u64 = 8 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize)]
+struct Change<K, V> {
+    key: K,
+    /// `None` removes the key.
+    value: Option<V>,
+}
+
+fn checksum(line: &str) -> String {
+    blake3::hash(line.as_bytes()).to_hex()[..16].to_string()
+}
+
+struct Log {
+    file: File,
+    lines: usize,
+    bytes: u64,
+}
+
+/// The map as an `update` closure sees it. It keeps what each key it
+/// touches held before, so only those keys are logged, or put back when
+/// the update fails.
+pub struct Changes<'a, K, V> {
+    map: &'a mut BTreeMap<K, V>,
+    originals: BTreeMap<K, Option<V>>,
+}
+
+impl<K: Ord + Clone, V: Clone> Changes<'_, K, V> {
+    pub fn get<Q: Ord + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.map.get(key)
+    }
+
+    pub fn contains_key<Q: Ord + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<'_, K, V> {
+        self.map.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn get_mut<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        let (stored, value) = self.map.get_key_value(key)?;
+        if !self.originals.contains_key(key) {
+            self.originals.insert(stored.clone(), Some(value.clone()));
+        }
+        self.map.get_mut(key)
+    }
+
+    /// The value at `key`, inserting `default()` first when there is none.
+    pub fn get_or_insert_with(&mut self, key: K, default: impl FnOnce() -> V) -> &mut V {
+        if !self.map.contains_key(&key) {
+            self.insert(key.clone(), default());
+        }
+        self.get_mut(&key).expect("just inserted")
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let previous = self.map.insert(key.clone(), value);
+        self.originals.entry(key).or_insert_with(|| previous.clone());
+        previous
+    }
+
+    pub fn remove<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let (key, value) = self.map.remove_entry(key)?;
+        self.originals.entry(key).or_insert_with(|| Some(value.clone()));
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        for (key, value) in std::mem::take(self.map) {
+            self.originals.entry(key).or_insert(Some(value));
+        }
+    }
+}
+
+pub struct WalStore<K, V> {
+    snapshot_path: PathBuf,
+    log_path: PathBuf,
+    map: RwLock<BTreeMap<K, V>>,
+    log: parking_lot::Mutex<Log>,
+}
+
+impl<K, V> WalStore<K, V>
+where
+    K: Ord + Clone + Serialize + DeserializeOwned,
+    V: Clone + PartialEq + Serialize + DeserializeOwned,
+{
+    /// Opens the store kept at `base` (as `base.snapshot.json` and
+    /// `base.wal`), replaying whatever the log holds.
+    pub fn open(base: impl AsRef<Path>) -> Result<Self, String> {
+        let base = base.as_ref();
+        let snapshot_path = base.with_extension("snapshot.json");
+        let log_path = base.with_extension("wal");
+
+        let mut map: BTreeMap<K, V> = match std::fs::read_to_string(&snapshot_path) {
+            Ok(json) => serde_json::from_str::<Vec<(K, V)>>(&json)
+                .map_err(|e| format!("Failed to parse snapshot {:?}: {}", snapshot_path, e))?
+                .into_iter()
+                .collect(),
+            Err(_) => BTreeMap::new(),
+        };
+        let (lines, valid_len) = replay(&log_path, &mut map);
+        let file = OpenOptions::new().create(true).append(true).open(&log_path)
+            .map_err(|e| format!("Failed to open {:?}: {}", log_path, e))?;
+        // Cut off a torn tail so later changes aren't appended behind it
+        let len = file.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+        if len > valid_len {
+            warn!("Dropping {} bytes of an interrupted write at the end of {:?}", len - valid_len, log_path);
+            file.set_len(valid_len)
+                .map_err(|e| format!("Failed to truncate {:?}: {}", log_path, e))?;
+        }
+
+        Ok(Self {
+            snapshot_path,
+            log_path,
+            map: RwLock::new(map),
+            log: parking_lot::Mutex::new(Log { file, lines, bytes: valid_len }),
+        })
+    }
+
+    /// Whether nothing has been stored yet.
+    pub fn is_empty(&self) -> bool {
+        self.map.read().is_empty()
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, BTreeMap<K, V>> {
+        self.map.read()
+    }
+
+    /// Changes the map through `change` and logs what it changed, all or
+    /// nothing: when `change` fails or the log can't be written, the map
+    /// is left as it was. Only the keys `change` touches are compared and
+    /// logged, so an update costs what it changes rather than the whole map.
+    pub fn update<R>(&self, change: impl FnOnce(&mut Changes<'_, K, V>) -> Result<R, String>) -> Result<R, String> {
+        let mut log = self.log.lock();
+        let mut map = self.map.write();
+        let mut changes = Changes { map: &mut map, originals: BTreeMap::new() };
+        let result = change(&mut changes);
+        let originals = changes.originals;
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                restore(&mut map, originals);
+                return Err(e);
+            }
+        };
+
+        let written = {
+            let changed: Vec<Change<&K, &V>> = originals.iter()
+                .filter(|(key, original)| map.get(*key) != original.as_ref())
+                .map(|(key, _)| Change { key, value: map.get(key) })
+                .collect();
+            if changed.is_empty() {
+                return Ok(result);
+            }
+            append(&mut log.file, &changed)
+        };
+        match written {
+            Ok(written) => {
+                log.lines += 1;
+                log.bytes += written;
+            }
+            Err(e) => {
+                restore(&mut map, originals);
+                return Err(format!("Failed to write {:?}: {}", self.log_path, e));
+            }
+        }
+
+        if log.lines >= COMPACT_AFTER_LINES || log.bytes >= COMPACT_AFTER_BYTES {
+            match self.compact(&map, &mut log) {
+                Ok(()) => info!("Compacted {:?} into {} entries", self.log_path, map.len()),
+                Err(e) => warn!("{}", e),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Replaces everything stored with `entries`, touching only the keys
+    /// that differ.
+    pub fn replace(&self, entries: BTreeMap<K, V>) -> Result<(), String> {
+        self.update(|map| {
+            let stale: Vec<K> = map.iter()
+                .filter(|(key, _)| !entries.contains_key(*key))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in &stale {
+                map.remove(key);
+            }
+            for (key, value) in entries {
+                if map.get(&key) != Some(&value) {
+                    map.insert(key, value);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Writes `map` to the snapshot and empties the log. A crash between
+    /// the two replays the log over the new snapshot, which changes nothing.
+    fn compact(&self, map: &BTreeMap<K, V>, log: &mut Log) -> Result<(), String> {
+        let entries: Vec<(&K, &V)> = map.iter().collect();
+        let json = serde_json::to_string(&entries)
+            .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+        let tmp_path = self.snapshot_path.with_extension("json.tmp");
+        File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(json.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|_| std::fs::rename(&tmp_path, &self.snapshot_path))
+            .map_err(|e| format!("Failed to write snapshot {:?}: {}", self.snapshot_path, e))?;
+        log.file.set_len(0)
+            .and_then(|_| log.file.sync_all())
+            .map_err(|e| format!("Failed to empty {:?}: {}", self.log_path, e))?;
+        log.lines = 0;
+        log.bytes = 0;
+        Ok(())
+    }
+}
+
+/// Puts back what the keys touched by a failed update held before it.
+fn restore<K: Ord, V>(map: &mut BTreeMap<K, V>, originals: BTreeMap<K, Option<V>>) {
+    for (key, original) in originals {
+        match original {
+            Some(value) => map.insert(key, value),
+            None => map.remove(&key),
+        };
+    }
+}
+
+/// Appends one line holding `changes` and returns its length.
+fn append<K: Serialize, V: Serialize>(file: &mut File, changes: &[Change<&K, &V>]) -> std::io::Result<u64> {
+    let json = serde_json::to_string(changes)?;
+    let line = format!("{}\t{}\n", checksum(&json), json);
+    file.write_all(line.as_bytes())?;
+    file.sync_data()?;
+    Ok(line.len() as u64)
+}
+
+/// Applies the intact lines of the log at `path` to `map`. Returns how
+/// many lines were applied and the length of the log they take up.
+fn replay<K, V>(path: &Path, map: &mut BTreeMap<K, V>) -> (usize, u64)
+where
+    K: Ord + DeserializeOwned,
+    V: DeserializeOwned,
+{
+    let Ok(log) = std::fs::read(path) else {
+        return (0, 0);
+    };
+    let (mut lines, mut valid_len) = (0, 0);
+    for line in log.split_inclusive(|byte| *byte == b'\n') {
+        let Some(changes) = parse_line::<K, V>(line) else {
+            warn!("Stopped replaying {:?} at a torn or corrupt line", path);
+            break;
+        };
+        for change in changes {
+            match change.value {
+                Some(value) => map.insert(change.key, value),
+                None => map.remove(&change.key),
+            };
+        }
+        lines += 1;
+        valid_len += line.len() as u64;
+    }
+    (lines, valid_len)
+}
+
+fn parse_line<K: DeserializeOwned, V: DeserializeOwned>(line: &[u8]) -> Option<Vec<Change<K, V>>> {
+    let line = std::str::from_utf8(line.strip_suffix(b"\n")?).ok()?;
+    let (sum, json) = line.split_once('\t')?;
+    if checksum(json) != sum {
+        return None;
+    }
+    serde_json::from_str(json).ok()
+}
@@ -0,0 +1,381 @@
+//! Counts of likely personal data (email addresses, phone numbers and
+//! national ID numbers) in extracted text, for a per-directory inventory
+//! of where such data lives. Phone numbers and ID numbers are matched by
+//! the formats of the configured regions, with checksums and reserved
+//! ranges applied where the format has them. Only counts are kept; the
+//! matched values themselves never leave the scan.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum PiiRegion {
+    Us,
+    Gb,
+    Ca,
+}
+
+impl PiiRegion {
+    pub const ALL: [PiiRegion; 3] = [PiiRegion::Us, PiiRegion::Gb, PiiRegion::Ca];
+
+    /// The region of a locale tag like `en-GB` or `fr_CA.UTF-8`, when it
+    /// is one with matchers.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.split(['.', '@']).next().unwrap_or_default();
+        tag.split(['-', '_'])
+            .skip(1)
+            .find(|part| part.len() == 2)
+            .and_then(|region| match region.to_uppercase().as_str() {
+                "US" => Some(PiiRegion::Us),
+                "GB" | "UK" => Some(PiiRegion::Gb),
+                "CA" => Some(PiiRegion::Ca),
+                _ => None,
+            })
+    }
+
+    /// The region of the system locale.
+    pub fn system() -> Option<Self> {
+        ["LC_ALL", "LANG"].iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+            .and_then(|tag| Self::from_tag(&tag))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum PiiCategory {
+    Email,
+    PhoneNumber,
+    UsSocialSecurityNumber,
+    UkNationalInsuranceNumber,
+    CanadianSocialInsuranceNumber,
+}
+
+impl PiiCategory {
+    pub const ALL: [PiiCategory; 5] = [
+        PiiCategory::Email,
+        PiiCategory::PhoneNumber,
+        PiiCategory::UsSocialSecurityNumber,
+        PiiCategory::UkNationalInsuranceNumber,
+        PiiCategory::CanadianSocialInsuranceNumber,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PiiCategory::Email => "email",
+            PiiCategory::PhoneNumber => "phone_number",
+            PiiCategory::UsSocialSecurityNumber => "us_social_security_number",
+            PiiCategory::UkNationalInsuranceNumber => "uk_national_insurance_number",
+            PiiCategory::CanadianSocialInsuranceNumber => "canadian_social_insurance_number",
+        }
+    }
+}
+
+pub type PiiCounts = BTreeMap<PiiCategory, u64>;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PiiSettings {
+    /// Allow inventory scans, which read the content of every indexed file.
+    pub enabled: bool,
+    /// Regions whose phone number and ID formats are matched; the region of
+    /// the date locale or system locale when empty.
+    pub regions: Vec<PiiRegion>,
+}
+
+impl PiiSettings {
+    /// The regions to match, given the configured date locale. Falls back
+    /// to every region when the locale doesn't name one.
+    pub fn effective_regions(&self, date_locale: Option<&str>) -> Vec<PiiRegion> {
+        if !self.regions.is_empty() {
+            return self.regions.clone();
+        }
+        date_locale.and_then(PiiRegion::from_tag)
+            .or_else(PiiRegion::system)
+            .map_or_else(|| PiiRegion::ALL.to_vec(), |region| vec![region])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DirectoryPii {
+    pub directory: String,
+    /// Files whose content was read.
+    #[ts(type = "number")]
+    pub files_scanned: u64,
+    #[ts(type = "number")]
+    pub files_with_pii: u64,
+    /// Matches per category; categories without any are left out.
+    #[ts(type = "Partial<Record<PiiCategory, number>>")]
+    pub counts: PiiCounts,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PiiInventory {
+    pub root: String,
+    pub regions: Vec<PiiRegion>,
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub generated_at: u64,
+    #[ts(type = "number")]
+    pub files_scanned: u64,
+    /// Directories with likely personal data, most matches first.
+    pub directories: Vec<DirectoryPii>,
+}
+
+impl PiiInventory {
+    /// One row per directory, with a column for every category.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("directory,files_scanned,files_with_pii");
+        for category in PiiCategory::ALL {
+            csv.push(',');
+            csv.push_str(category.as_str());
+        }
+        csv.push('\n');
+        for directory in &self.directories {
+            csv.push_str(&csv_field(&directory.directory));
+            csv.push_str(&format!(",{},{}", directory.files_scanned, directory.files_with_pii));
+            for category in PiiCategory::ALL {
+                csv.push_str(&format!(",{}", directory.counts.get(&category).copied().unwrap_or_default()));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// `field` quoted when it holds a comma, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Likely personal data in `text`, matched with the formats of `regions`.
+pub fn count(text: &str, regions: &[PiiRegion]) -> PiiCounts {
+    let mut counts = PiiCounts::new();
+    let mut add = |category: PiiCategory, found: usize| {
+        if found > 0 {
+            *counts.entry(category).or_default() += found as u64;
+        }
+    };
+    add(PiiCategory::Email, emails(text));
+    for candidate in number_candidates(text) {
+        if let Some(category) = classify_number(candidate, regions) {
+            add(category, 1);
+        }
+    }
+    if regions.contains(&PiiRegion::Gb) {
+        add(PiiCategory::UkNationalInsuranceNumber, national_insurance_numbers(text));
+    }
+    counts
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// Addresses like `name@example.com`: a local part, `@`, and a domain with
+/// a dot and an alphabetic top-level part.
+fn emails(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let is_local = |byte: u8| byte.is_ascii_alphanumeric() || b"._%+-".contains(&byte);
+    let is_domain = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'.' || byte == b'-';
+    bytes.iter()
+        .enumerate()
+        .filter(|(_, &byte)| byte == b'@')
+        .filter(|&(at, _)| {
+            let local = bytes[..at].iter().rev().take_while(|&&byte| is_local(byte)).count();
+            let domain_len = bytes[at + 1..].iter().take_while(|&&byte| is_domain(byte)).count();
+            let domain = text[at + 1..at + 1 + domain_len].trim_end_matches(['.', '-']);
+            let labels: Vec<&str> = domain.split('.').collect();
+            local > 0
+                && labels.len() >= 2
+                && labels.iter().all(|label| !label.is_empty())
+                && labels.last().is_some_and(|tld| tld.len() >= 2 && tld.bytes().all(|byte| byte.is_ascii_alphabetic()))
+        })
+        .count()
+}
+
+/// Runs of digits joined by single separators (space, `-`, `.`,
+/// parentheses), optionally led by `+`, that aren't part of a word.
+fn number_candidates(text: &str) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let is_separator = |byte: u8| matches!(byte, b' ' | b'-' | b'.' | b'(' | b')');
+    let mut candidates = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let starts_number = bytes[i].is_ascii_digit()
+            || (matches!(bytes[i], b'+' | b'(') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit));
+        if !starts_number || (i > 0 && (is_word_byte(bytes[i - 1]) || bytes[i - 1] == b'@')) {
+            i += 1;
+            continue;
+        }
+        let mut end = i + 1;
+        loop {
+            if bytes.get(end).is_some_and(u8::is_ascii_digit) {
+                end += 1;
+                continue;
+            }
+            // Up to two separators, as in `(555) 123`
+            let separators = bytes[end..].iter().take(2).take_while(|&&byte| is_separator(byte)).count();
+            let after = end + separators;
+            if separators > 0 && (bytes.get(after).is_some_and(u8::is_ascii_digit) || bytes[after - 1] == b')') {
+                end = after;
+                continue;
+            }
+            break;
+        }
+        let candidate = text[i..end].trim_end_matches([' ', '-', '.', '(']);
+        let next = bytes.get(i + candidate.len()).copied();
+        if !next.is_some_and(|byte| is_word_byte(byte) || byte == b'@') {
+            candidates.push(candidate);
+        }
+        i = end.max(i + 1);
+    }
+    candidates
+}
+
+fn classify_number(candidate: &str, regions: &[PiiRegion]) -> Option<PiiCategory> {
+    let groups: Vec<&str> = candidate.split(|c: char| !c.is_ascii_digit()).filter(|group| !group.is_empty()).collect();
+    if regions.contains(&PiiRegion::Us) && is_social_security_number(candidate) {
+        return Some(PiiCategory::UsSocialSecurityNumber);
+    }
+    if regions.contains(&PiiRegion::Ca) && is_social_insurance_number(candidate) {
+        return Some(PiiCategory::CanadianSocialInsuranceNumber);
+    }
+    let north_american = regions.iter().any(|region| matches!(region, PiiRegion::Us | PiiRegion::Ca));
+    if (north_american && is_north_american_phone(candidate, &groups))
+        || (regions.contains(&PiiRegion::Gb) && is_uk_phone(candidate, &groups))
+    {
+        return Some(PiiCategory::PhoneNumber);
+    }
+    None
+}
+
+/// `AAA-GG-SSSS`, leaving out the area numbers never issued (000, 666 and
+/// 900–999) and all-zero groups.
+fn is_social_security_number(candidate: &str) -> bool {
+    let bytes = candidate.as_bytes();
+    if bytes.len() != 11 || bytes[3] != b'-' || bytes[6] != b'-' {
+        return false;
+    }
+    let (area, group, serial) = (&candidate[..3], &candidate[4..6], &candidate[7..]);
+    [area, group, serial].iter().all(|part| part.bytes().all(|byte| byte.is_ascii_digit()))
+        && area != "000" && area != "666" && !area.starts_with('9')
+        && group != "00" && serial != "0000"
+}
+
+/// `NNN NNN NNN` or `NNN-NNN-NNN` passing the Luhn check. Numbers starting
+/// with 0 or 8 aren't issued to people.
+fn is_social_insurance_number(candidate: &str) -> bool {
+    let bytes = candidate.as_bytes();
+    if bytes.len() != 11 || bytes[3] != bytes[7] || !matches!(bytes[3], b' ' | b'-') {
+        return false;
+    }
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() != 9 || matches!(digits[0], 0 | 8) {
+        return false;
+    }
+    let sum: u32 = digits.iter()
+        .enumerate()
+        .map(|(i, &digit)| if i % 2 == 1 { let doubled = digit * 2; doubled / 10 + doubled % 10 } else { digit })
+        .sum();
+    sum % 10 == 0
+}
+
+/// `(NXX) NXX-XXXX` and the like, optionally led by `+1` or `1`. The
+/// groups must be written out, so bare runs of ten digits (timestamps,
+/// order numbers) don't count; only `+1` allows them.
+fn is_north_american_phone(candidate: &str, groups: &[&str]) -> bool {
+    let international = candidate.starts_with('+');
+    let groups = match groups {
+        ["1", rest @ ..] if international || rest.len() == 3 => rest,
+        _ if international => return false,
+        _ => groups,
+    };
+    let number = match groups {
+        [area, exchange, line] if area.len() == 3 && exchange.len() == 3 && line.len() == 4 => {
+            format!("{}{}{}", area, exchange, line)
+        }
+        [number] if international && number.len() == 10 => number.to_string(),
+        _ => return false,
+    };
+    let bytes = number.as_bytes();
+    // Area codes and exchanges start with 2–9
+    bytes[0] >= b'2' && bytes[3] >= b'2'
+}
+
+/// `+44 20 7946 0958`, `020 7946 0958`, `07700 900123` and the like: ten
+/// digits after the country code or leading 0, in two or three groups.
+fn is_uk_phone(candidate: &str, groups: &[&str]) -> bool {
+    let national: String = if candidate.starts_with('+') {
+        match groups {
+            ["44", rest @ ..] if !rest.is_empty() && rest.len() <= 3 => rest.concat(),
+            _ => return false,
+        }
+    } else if groups.len() >= 2 && groups.len() <= 3 && groups[0].starts_with('0') {
+        groups.concat()[1..].to_string()
+    } else {
+        return false;
+    };
+    national.len() == 10 && matches!(national.as_bytes()[0], b'1' | b'2' | b'3' | b'7' | b'8')
+}
+
+/// National Insurance numbers like `QQ 12 34 56 C` or `QQ123456C`, leaving
+/// out prefixes that are never issued.
+fn national_insurance_numbers(text: &str) -> usize {
+    const INVALID_FIRST: &[u8] = b"DFIQUV";
+    const INVALID_SECOND: &[u8] = b"DFIOQUV";
+    const INVALID_PREFIXES: &[&str] = &["BG", "GB", "NK", "KN", "TN", "NT", "ZZ"];
+
+    let bytes = text.as_bytes();
+    let mut found = 0;
+    for start in 0..bytes.len() {
+        if start > 0 && is_word_byte(bytes[start - 1]) {
+            continue;
+        }
+        let (first, second) = match bytes.get(start..start + 2) {
+            Some(&[first, second]) if first.is_ascii_uppercase() && second.is_ascii_uppercase() => (first, second),
+            _ => continue,
+        };
+        if INVALID_FIRST.contains(&first)
+            || INVALID_SECOND.contains(&second)
+            || INVALID_PREFIXES.contains(&&text[start..start + 2])
+        {
+            continue;
+        }
+        // Digit pairs and the suffix, all spaced or none
+        let spaced = bytes.get(start + 2) == Some(&b' ');
+        let step = if spaced { 3 } else { 2 };
+        let mut at = start + 2;
+        let digits_ok = (0..3).all(|_| {
+            let pair = at + usize::from(spaced);
+            let ok = (!spaced || bytes.get(at) == Some(&b' '))
+                && bytes.get(pair..pair + 2).is_some_and(|pair| pair.iter().all(u8::is_ascii_digit));
+            at += step;
+            ok
+        });
+        if !digits_ok {
+            continue;
+        }
+        if spaced && bytes.get(at) == Some(&b' ') {
+            at += 1;
+        }
+        if bytes.get(at).is_some_and(|suffix| b"ABCD".contains(suffix))
+            && !bytes.get(at + 1).copied().is_some_and(is_word_byte)
+        {
+            found += 1;
+        }
+    }
+    found
+}
@@ -0,0 +1,84 @@
+//! Portable mode, for running from a USB stick or similar: turned on by a
+//! `constella.portable` file next to the executable. Settings, the index
+//! and all state then live in `constella-data` beside it, and paths on the
+//! same volume are kept relative to the volume so they still resolve when
+//! it's mounted at another drive letter or mount point.
+
+use std::path::{Path, PathBuf};
+use log::info;
+use crate::settings::SettingsManager;
+
+/// File next to the executable that turns portable mode on.
+pub const PORTABLE_MARKER: &str = "constella.portable";
+
+/// Directory next to the executable holding all data in portable mode.
+pub const PORTABLE_DATA_DIR: &str = "constella-data";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortableLayout {
+    pub data_dir: PathBuf,
+    /// Root of the volume the executable is on.
+    pub volume_root: PathBuf,
+}
+
+/// The portable layout when the running executable has a marker beside it.
+pub fn detect() -> Option<PortableLayout> {
+    let exe = std::env::current_exe().ok()?;
+    detect_in(exe.parent()?)
+}
+
+/// The portable layout for an executable in `exe_dir`, if it is marked.
+pub fn detect_in(exe_dir: &Path) -> Option<PortableLayout> {
+    if !exe_dir.join(PORTABLE_MARKER).is_file() {
+        return None;
+    }
+    Some(PortableLayout {
+        data_dir: exe_dir.join(PORTABLE_DATA_DIR),
+        volume_root: volume_root(exe_dir),
+    })
+}
+
+/// The mount point `path` is under: the last ancestor on the same device.
+#[cfg(unix)]
+pub fn volume_root(path: &Path) -> PathBuf {
+    use std::os::unix::fs::MetadataExt;
+    let Ok(device) = std::fs::metadata(path).map(|metadata| metadata.dev()) else {
+        return path.to_path_buf();
+    };
+    let mut root = path;
+    while let Some(parent) = root.parent() {
+        match std::fs::metadata(parent) {
+            Ok(metadata) if metadata.dev() == device => root = parent,
+            _ => break,
+        }
+    }
+    root.to_path_buf()
+}
+
+/// The drive `path` is on, e.g. `E:\`.
+#[cfg(not(unix))]
+pub fn volume_root(path: &Path) -> PathBuf {
+    path.components()
+        .take_while(|component| matches!(component, std::path::Component::Prefix(_) | std::path::Component::RootDir))
+        .collect()
+}
+
+impl PortableLayout {
+    /// Points the configured folders at the volume's current mount point
+    /// when it has moved since the last run.
+    pub fn rebase_settings(&self, settings: &SettingsManager) -> Result<(), String> {
+        let previous = settings.get().portable_root;
+        if previous.as_deref() == Some(self.volume_root.as_path()) {
+            return Ok(());
+        }
+        let volume_root = self.volume_root.clone();
+        settings.update(|settings| {
+            if let Some(previous) = &previous {
+                let changed = settings.rebase_paths(previous, &volume_root);
+                info!("Portable volume moved from {:?} to {:?}, updated {} folders", previous, volume_root, changed);
+            }
+            settings.portable_root = Some(volume_root.clone());
+        })?;
+        Ok(())
+    }
+}
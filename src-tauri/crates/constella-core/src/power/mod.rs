@@ -0,0 +1,167 @@
+use std::sync::Arc;
+use std::time::Duration;
+use parking_lot::RwLock;
+use serde::{Serialize, Deserialize};
+use sysinfo::{ComponentExt, RefreshKind, System, SystemExt};
+use log::{debug, info, warn};
+use crate::settings::SettingsManager;
+use ts_rs::TS;
+
+pub const POWER_SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+const BATTERY_THROTTLE_DELAY: Duration = Duration::from_millis(50);
+// Fallback when a sensor doesn't report its critical temperature
+const THERMAL_PRESSURE_CELSIUS: f32 = 90.0;
+
+/// When content extraction (reading and retaining file contents) may run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum PowerPolicy {
+    /// Always extract, only slowing down on battery or when hot.
+    Always,
+    /// Pause extraction while running on battery or under thermal pressure.
+    #[default]
+    AcOnly,
+    /// Never extract content; index names and metadata only.
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PowerState {
+    pub source: PowerSource,
+    pub battery_percent: Option<f32>,
+    pub thermal_pressure: bool,
+    pub policy: PowerPolicy,
+    pub content_extraction_allowed: bool,
+}
+
+/// Tracks the power source and thermal state so heavy background work can
+/// pause on laptops running from battery.
+pub struct PowerMonitor {
+    settings: Arc<SettingsManager>,
+    source: RwLock<(PowerSource, Option<f32>)>,
+    thermal_pressure: RwLock<bool>,
+}
+
+impl PowerMonitor {
+    pub fn new(settings: Arc<SettingsManager>) -> Self {
+        let monitor = Self {
+            settings,
+            source: RwLock::new((PowerSource::Unknown, None)),
+            thermal_pressure: RwLock::new(false),
+        };
+        monitor.sample();
+        monitor
+    }
+
+    /// Re-reads battery and temperature sensors. Blocking.
+    pub fn sample(&self) {
+        let source = read_power_source();
+        let thermal_pressure = read_thermal_pressure();
+
+        let previous = self.source.read().0;
+        if previous != source.0 {
+            info!("Power source changed: {:?} -> {:?}", previous, source.0);
+        }
+        debug!("Power sampled: {:?}, thermal pressure: {}", source, thermal_pressure);
+
+        *self.source.write() = source;
+        *self.thermal_pressure.write() = thermal_pressure;
+    }
+
+    pub fn spawn_sampler(self: &Arc<Self>) {
+        let monitor = self.clone();
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(POWER_SAMPLE_INTERVAL);
+            timer.tick().await;
+            loop {
+                timer.tick().await;
+                let monitor = monitor.clone();
+                let _ = tokio::task::spawn_blocking(move || monitor.sample()).await;
+            }
+        });
+    }
+
+    fn constrained(&self) -> bool {
+        self.source.read().0 == PowerSource::Battery || *self.thermal_pressure.read()
+    }
+
+    pub fn content_extraction_allowed(&self) -> bool {
+        match self.settings.get().power_policy {
+            PowerPolicy::Always => true,
+            PowerPolicy::AcOnly => !self.constrained(),
+            PowerPolicy::Never => false,
+        }
+    }
+
+    /// Extra pause between content reads when extraction is allowed but the
+    /// machine is on battery or running hot.
+    pub fn extraction_delay(&self) -> Duration {
+        if self.constrained() {
+            BATTERY_THROTTLE_DELAY
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    pub fn state(&self) -> PowerState {
+        let (source, battery_percent) = *self.source.read();
+        PowerState {
+            source,
+            battery_percent,
+            thermal_pressure: *self.thermal_pressure.read(),
+            policy: self.settings.get().power_policy,
+            content_extraction_allowed: self.content_extraction_allowed(),
+        }
+    }
+}
+
+fn read_power_source() -> (PowerSource, Option<f32>) {
+    let manager = match battery::Manager::new() {
+        Ok(manager) => manager,
+        Err(e) => {
+            warn!("Failed to query batteries: {}", e);
+            return (PowerSource::Unknown, None);
+        }
+    };
+    let batteries: Vec<battery::Battery> = match manager.batteries() {
+        Ok(batteries) => batteries.filter_map(|b| b.ok()).collect(),
+        Err(e) => {
+            warn!("Failed to enumerate batteries: {}", e);
+            return (PowerSource::Unknown, None);
+        }
+    };
+
+    // Desktops without a battery are always on mains power
+    if batteries.is_empty() {
+        return (PowerSource::Ac, None);
+    }
+
+    let discharging = batteries.iter().any(|b| b.state() == battery::State::Discharging);
+    let percent = batteries.iter()
+        .map(|b| b.state_of_charge().value * 100.0)
+        .sum::<f32>() / batteries.len() as f32;
+
+    let source = if discharging { PowerSource::Battery } else { PowerSource::Ac };
+    (source, Some(percent))
+}
+
+fn read_thermal_pressure() -> bool {
+    let system = System::new_with_specifics(RefreshKind::new().with_components_list());
+    system.components().iter().any(|component| {
+        let limit = component.critical()
+            .map(|critical| critical * 0.9)
+            .unwrap_or(THERMAL_PRESSURE_CELSIUS);
+        component.temperature() >= limit
+    })
+}
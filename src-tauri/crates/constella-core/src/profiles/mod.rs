@@ -0,0 +1,218 @@
+//! Profiles like "work" and "personal" that decide what is visible: which
+//! roots are searchable, which labels are hidden, and whether searches
+//! and clicks are remembered. Switching profiles changes what search,
+//! the "what's new" feed and suggested files show; the index itself is
+//! shared. A profile can be guarded by a PIN, which is needed to switch to
+//! it or change it. The PIN keeps someone at the keyboard from casually
+//! looking into a profile; it doesn't encrypt anything.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::settings::{Settings, SettingsManager};
+
+/// Salted BLAKE3 hash of a profile's PIN.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinHash {
+    salt: String,
+    hash: String,
+}
+
+impl PinHash {
+    fn new(pin: &str) -> Self {
+        let salt: String = (0..16).map(|_| format!("{:02x}", fastrand::u8(..))).collect();
+        let hash = Self::digest(&salt, pin).to_hex().to_string();
+        Self { salt, hash }
+    }
+
+    fn digest(salt: &str, pin: &str) -> blake3::Hash {
+        blake3::Hasher::new()
+            .update(salt.as_bytes())
+            .update(pin.as_bytes())
+            .finalize()
+    }
+
+    fn matches(&self, pin: &str) -> bool {
+        // Comparing `blake3::Hash`es is constant-time
+        blake3::Hash::from_hex(&self.hash).is_ok_and(|hash| hash == Self::digest(&self.salt, pin))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    /// Only files under these are visible; every root when empty.
+    pub roots: Vec<PathBuf>,
+    /// Files with any of these labels are hidden.
+    pub hidden_labels: Vec<String>,
+    /// Remember searches and result clicks while the profile is active.
+    pub record_history: bool,
+    pub pin: Option<PinHash>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            roots: Vec::new(),
+            hidden_labels: Vec::new(),
+            record_history: true,
+            pin: None,
+        }
+    }
+}
+
+impl Profile {
+    /// Whether `path` is under one of the profile's roots.
+    pub fn is_visible(&self, path: &Path) -> bool {
+        self.roots.is_empty() || self.roots.iter().any(|root| path.starts_with(root))
+    }
+
+    fn check_pin(&self, pin: Option<&str>) -> Result<(), String> {
+        match (&self.pin, pin) {
+            (None, _) => Ok(()),
+            (Some(hash), Some(pin)) if hash.matches(pin) => Ok(()),
+            (Some(_), Some(_)) => Err(format!("Wrong PIN for profile {}", self.name)),
+            (Some(_), None) => Err(format!("Profile {} needs its PIN", self.name)),
+        }
+    }
+}
+
+/// A profile as the UI sees it, without its PIN.
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ProfileSummary {
+    pub name: String,
+    pub roots: Vec<PathBuf>,
+    pub hidden_labels: Vec<String>,
+    pub record_history: bool,
+    pub has_pin: bool,
+    pub active: bool,
+}
+
+/// What the UI can change about a profile; the PIN is set separately.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ProfileUpdate {
+    pub name: String,
+    pub roots: Vec<PathBuf>,
+    pub hidden_labels: Vec<String>,
+    pub record_history: bool,
+}
+
+impl Settings {
+    /// The profile in use, if any.
+    pub fn active_profile(&self) -> Option<&Profile> {
+        let name = self.active_profile.as_deref()?;
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+}
+
+pub fn list(settings: &Settings) -> Vec<ProfileSummary> {
+    settings.profiles.iter()
+        .map(|profile| ProfileSummary {
+            name: profile.name.clone(),
+            roots: profile.roots.clone(),
+            hidden_labels: profile.hidden_labels.clone(),
+            record_history: profile.record_history,
+            has_pin: profile.pin.is_some(),
+            active: settings.active_profile.as_deref() == Some(profile.name.as_str()),
+        })
+        .collect()
+}
+
+fn find<'a>(settings: &'a mut Settings, name: &str) -> Result<&'a mut Profile, String> {
+    settings.profiles.iter_mut()
+        .find(|profile| profile.name == name)
+        .ok_or_else(|| format!("No profile named {}", name))
+}
+
+/// Applies `apply` to the settings once it succeeds against a copy, so a
+/// refused change (a wrong PIN, say) writes nothing.
+fn change(settings: &SettingsManager, apply: impl Fn(&mut Settings) -> Result<(), String>) -> Result<Vec<ProfileSummary>, String> {
+    apply(&mut settings.get())?;
+    let updated = settings.update(|settings| {
+        // Checked above; settings only change through this manager
+        let _ = apply(settings);
+    })?;
+    Ok(list(&updated))
+}
+
+/// Creates a profile or updates the one named `update.name`, which needs
+/// its PIN if it has one.
+pub fn save(settings: &SettingsManager, update: ProfileUpdate, pin: Option<&str>) -> Result<Vec<ProfileSummary>, String> {
+    let name = update.name.trim().to_string();
+    if name.is_empty() {
+        return Err("A profile needs a name".to_string());
+    }
+    change(settings, |settings| {
+        let labels = update.hidden_labels.iter().map(|label| label.to_lowercase()).collect();
+        match find(settings, &name) {
+            Ok(profile) => {
+                profile.check_pin(pin)?;
+                profile.roots = update.roots.clone();
+                profile.hidden_labels = labels;
+                profile.record_history = update.record_history;
+            }
+            Err(_) => settings.profiles.push(Profile {
+                name: name.clone(),
+                roots: update.roots.clone(),
+                hidden_labels: labels,
+                record_history: update.record_history,
+                pin: None,
+            }),
+        }
+        Ok(())
+    })
+}
+
+/// Sets or, with `None`, removes the PIN of profile `name`.
+pub fn set_pin(
+    settings: &SettingsManager,
+    name: &str,
+    new_pin: Option<&str>,
+    current_pin: Option<&str>,
+) -> Result<Vec<ProfileSummary>, String> {
+    if new_pin.is_some_and(|pin| pin.trim().len() < 4) {
+        return Err("A PIN needs at least 4 characters".to_string());
+    }
+    // Hashed once, so the dry run and the real change agree
+    let hash = new_pin.map(PinHash::new);
+    change(settings, |settings| {
+        let profile = find(settings, name)?;
+        profile.check_pin(current_pin)?;
+        profile.pin = hash.clone();
+        Ok(())
+    })
+}
+
+pub fn delete(settings: &SettingsManager, name: &str, pin: Option<&str>) -> Result<Vec<ProfileSummary>, String> {
+    change(settings, |settings| {
+        find(settings, name)?.check_pin(pin)?;
+        settings.profiles.retain(|profile| profile.name != name);
+        if settings.active_profile.as_deref() == Some(name) {
+            settings.active_profile = None;
+        }
+        Ok(())
+    })
+}
+
+/// Makes profile `name` the active one, or with `None` shows everything.
+/// Showing everything would reveal PIN-guarded profiles, so it is refused
+/// while any profile has a PIN.
+pub fn switch(settings: &SettingsManager, name: Option<&str>, pin: Option<&str>) -> Result<Vec<ProfileSummary>, String> {
+    change(settings, |settings| {
+        match name {
+            Some(name) => {
+                find(settings, name)?.check_pin(pin)?;
+                settings.active_profile = Some(name.to_string());
+            }
+            None if settings.profiles.iter().any(|profile| profile.pin.is_some()) => {
+                return Err("Some profiles have a PIN; switch to a profile instead".to_string());
+            }
+            None => settings.active_profile = None,
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,269 @@
+//! Removes everything Constella has stored about the user's files: the
+//! index, tracker state, stats, caches, previews, version history, learned
+//! rankings and the audit log. Meant for decommissioning a machine, so it
+//! takes a short-lived token from `issue_token` to go ahead, and reports
+//! every file it removed. Only what Constella creates is removed, by name;
+//! settings and anything else in the folder are kept.
+//!
+//! Overwriting zeroes each file before it is deleted. On SSDs and
+//! copy-on-write filesystems the old blocks may survive anyway; full-disk
+//! encryption is the real protection there.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use log::{info, warn};
+use parking_lot::Mutex;
+use serde::Serialize;
+use ts_rs::TS;
+
+/// How long a token from `issue_token` can be used.
+pub const PURGE_TOKEN_TTL: Duration = Duration::from_secs(2 * 60);
+
+/// Everything Constella creates in its data directory besides settings,
+/// and all a purge removes there, along with the `.tmp` files left by
+/// interrupted writes to them.
+pub const PURGED_ENTRIES: &[&str] = &[
+    "search_index",
+    "index",
+    "tracking.snapshot.json",
+    "tracking.wal",
+    "state.json",
+    "stats.json",
+    "snapshots",
+    "collections.snapshot.json",
+    "collections.wal",
+    "collections.json",
+    "previews",
+    "versions",
+    "deleted",
+    "shards",
+    "scratch",
+    "benchmarks",
+    "learning.json",
+    "zero_results.json",
+    "recent_changes.json",
+    "secret_findings.json",
+    "path_remaps.json",
+    "transcripts.json",
+    "audit_log.jsonl",
+    "operation_journal.json",
+    "organize_log.json",
+    "index_manifest.json",
+    "index_manifest.key",
+    "index_fingerprint",
+    "index_format",
+    "index_moved_from",
+    "rebuild_after_update",
+    "daemon.json",
+    "instance.json",
+];
+
+const OVERWRITE_CHUNK: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PurgedFile {
+    pub path: String,
+    #[ts(type = "number")]
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PurgeFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct PurgeManifest {
+    pub data_dir: String,
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub purged_at: u64,
+    pub overwritten: bool,
+    pub removed: Vec<PurgedFile>,
+    #[ts(type = "number")]
+    pub bytes_removed: u64,
+    pub kept: Vec<String>,
+    pub failed: Vec<PurgeFailure>,
+}
+
+pub struct DataPurge {
+    data_dir: PathBuf,
+    // The index, when it's kept outside the data directory
+    index_dir: Option<PathBuf>,
+    token: Mutex<Option<(String, Instant)>>,
+    purged: AtomicBool,
+}
+
+impl DataPurge {
+    pub fn new(data_dir: impl AsRef<Path>) -> Self {
+        Self {
+            data_dir: data_dir.as_ref().to_path_buf(),
+            index_dir: None,
+            token: Mutex::new(None),
+            purged: AtomicBool::new(false),
+        }
+    }
+
+    /// Purges the index in `index_dir` too, when it isn't in the data
+    /// directory.
+    pub fn with_index_dir(mut self, index_dir: Option<&Path>) -> Self {
+        self.index_dir = index_dir
+            .filter(|index_dir| !index_dir.starts_with(&self.data_dir))
+            .map(Path::to_path_buf);
+        self
+    }
+
+    /// A single-use token for `purge`, replacing any earlier one.
+    pub fn issue_token(&self) -> Result<String, String> {
+        let mut bytes = [0u8; 16];
+        getrandom::getrandom(&mut bytes)
+            .map_err(|e| format!("Failed to generate a confirmation token: {}", e))?;
+        let token: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        *self.token.lock() = Some((token.clone(), Instant::now()));
+        Ok(token)
+    }
+
+    /// Whether a purge has run; nothing should be saved to the data
+    /// directory after one.
+    pub fn has_purged(&self) -> bool {
+        self.purged.load(Ordering::SeqCst)
+    }
+
+    /// Deletes the entries of `PURGED_ENTRIES` in the data directory, first
+    /// overwriting each file with zeros when `overwrite` is set. Files that
+    /// can't be removed are listed in the manifest rather than stopping
+    /// the purge. Blocks while it overwrites, so run it off the async
+    /// runtime.
+    pub fn purge(&self, confirm_token: &str, overwrite: bool) -> Result<PurgeManifest, String> {
+        self.check_data_dir()?;
+        match self.token.lock().take() {
+            Some((token, issued)) if token == confirm_token && issued.elapsed() <= PURGE_TOKEN_TTL => {}
+            Some((token, _)) if token == confirm_token => {
+                return Err("The confirmation token has expired; request a new one".to_string());
+            }
+            _ => return Err("Invalid confirmation token".to_string()),
+        }
+        self.purged.store(true, Ordering::SeqCst);
+        info!("Purging all data in {:?}", self.data_dir);
+
+        let mut manifest = PurgeManifest {
+            data_dir: self.data_dir.to_string_lossy().into_owned(),
+            purged_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            overwritten: overwrite,
+            removed: Vec::new(),
+            bytes_removed: 0,
+            kept: Vec::new(),
+            failed: Vec::new(),
+        };
+        let entries = std::fs::read_dir(&self.data_dir)
+            .map_err(|e| format!("Failed to read data directory: {}", e))?;
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    manifest.failed.push(PurgeFailure { path: manifest.data_dir.clone(), error: e.to_string() });
+                    continue;
+                }
+            };
+            let ours = path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| PURGED_ENTRIES.contains(&name.strip_suffix(".tmp").unwrap_or(name)));
+            if ours {
+                remove(&path, overwrite, &mut manifest);
+            } else {
+                manifest.kept.push(path.to_string_lossy().into_owned());
+            }
+        }
+        if let Some(index_dir) = self.index_dir.as_deref().filter(|index_dir| index_dir.exists()) {
+            remove(index_dir, overwrite, &mut manifest);
+        }
+        manifest.removed.sort_by(|a, b| a.path.cmp(&b.path));
+        manifest.kept.sort();
+        manifest.bytes_removed = manifest.removed.iter().map(|file| file.bytes).sum();
+
+        info!(
+            "Purge removed {} files ({} bytes), {} failed",
+            manifest.removed.len(),
+            manifest.bytes_removed,
+            manifest.failed.len()
+        );
+        Ok(manifest)
+    }
+
+    /// Refuses folders that aren't Constella's own data directory, such as
+    /// the platform data root other apps share.
+    fn check_data_dir(&self) -> Result<(), String> {
+        let shared = [dirs::data_dir(), dirs::data_local_dir(), dirs::home_dir()]
+            .into_iter()
+            .flatten()
+            .any(|shared| shared == self.data_dir);
+        let ours = self.data_dir.join("settings.json").is_file() || self.data_dir.join("search_index").is_dir();
+        if shared || !ours || self.data_dir.parent().is_none() {
+            return Err(format!("{} doesn't look like Constella's data directory; nothing was purged", self.data_dir.display()));
+        }
+        Ok(())
+    }
+}
+
+/// Removes `path` and, for a directory, everything below it. Symlinks are
+/// removed, not followed.
+fn remove(path: &Path, overwrite: bool, manifest: &mut PurgeManifest) {
+    let failed = |manifest: &mut PurgeManifest, e: std::io::Error| {
+        warn!("Failed to purge {:?}: {}", path, e);
+        manifest.failed.push(PurgeFailure { path: path.to_string_lossy().into_owned(), error: e.to_string() });
+    };
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return failed(manifest, e),
+    };
+
+    if metadata.is_dir() {
+        match std::fs::read_dir(path) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    remove(&entry.path(), overwrite, manifest);
+                }
+            }
+            Err(e) => return failed(manifest, e),
+        }
+        if let Err(e) = std::fs::remove_dir(path) {
+            failed(manifest, e);
+        }
+        return;
+    }
+
+    if overwrite && metadata.is_file() {
+        if let Err(e) = zero_fill(path, metadata.len()) {
+            return failed(manifest, e);
+        }
+    }
+    match std::fs::remove_file(path) {
+        Ok(()) => manifest.removed.push(PurgedFile {
+            path: path.to_string_lossy().into_owned(),
+            bytes: if metadata.is_file() { metadata.len() } else { 0 },
+        }),
+        Err(e) => failed(manifest, e),
+    }
+}
+
+fn zero_fill(path: &Path, len: u64) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let zeros = [0u8; OVERWRITE_CHUNK];
+    let mut left = len;
+    while left > 0 {
+        let chunk = left.min(OVERWRITE_CHUNK as u64) as usize;
+        file.write_all(&zeros[..chunk])?;
+        left -= chunk as u64;
+    }
+    file.sync_all()
+}
@@ -0,0 +1,242 @@
+//! Bulk renaming by pattern. A template builds each new name from tokens:
+//!
+//! - `{name}` and `{ext}`: the current name without its extension, and
+//!   the extension without its dot
+//! - `{date}`: the modification date, `2024-03-14`; `{date:%Y%m%d}` takes
+//!   a strftime format
+//! - `{counter}`: the file's place in the selection, from `counter_start`;
+//!   `{counter:3}` pads it to three digits
+//! - `{0}`, `{1}`, ... and `{group}`: what the optional regex matched in
+//!   the current name, and its capture groups
+//!
+//! `{{` and `}}` stand for literal braces. Previewing shows every proposed
+//! name and what stands in its way; nothing is renamed until the rename is
+//! applied through the operation journal.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct RenamePattern {
+    /// The new name, e.g. `{date} {name}.{ext}`.
+    pub template: String,
+    /// Matched against each current name. Files it doesn't match keep
+    /// their name.
+    pub regex: Option<String>,
+    #[ts(type = "number")]
+    pub counter_start: u64,
+}
+
+impl Default for RenamePattern {
+    fn default() -> Self {
+        Self {
+            template: "{name}.{ext}".to_string(),
+            regex: None,
+            counter_start: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum RenameConflict {
+    /// Empty, `.` or `..`, or containing a path separator.
+    InvalidName,
+    /// Something already has that name and isn't being renamed.
+    Exists,
+    /// Another file in the selection would get the same name.
+    Duplicate,
+    /// The file isn't there to rename.
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct RenameProposal {
+    pub path: PathBuf,
+    pub new_name: String,
+    pub changed: bool,
+    pub conflict: Option<RenameConflict>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct RenamePreview {
+    pub proposals: Vec<RenameProposal>,
+    pub conflicts: usize,
+}
+
+impl RenamePreview {
+    /// The renames to make, once the preview has no conflicts.
+    pub fn renames(&self) -> Result<Vec<(PathBuf, String)>, String> {
+        if self.conflicts > 0 {
+            return Err(format!("{} files can't be renamed as proposed", self.conflicts));
+        }
+        Ok(self.proposals.iter()
+            .filter(|proposal| proposal.changed)
+            .map(|proposal| (proposal.path.clone(), proposal.new_name.clone()))
+            .collect())
+    }
+}
+
+enum Token {
+    Literal(String),
+    Name,
+    Ext,
+    Date(String),
+    Counter(usize),
+    Group(usize),
+    NamedGroup(String),
+}
+
+fn parse_template(template: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut token = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => token.push(c),
+                        None => return Err(format!("Unclosed {{{} in the template", token)),
+                    }
+                }
+                if !literal.is_empty() {
+                    tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                }
+                let (name, argument) = token.split_once(':').map_or((token.as_str(), None), |(name, argument)| (name, Some(argument)));
+                tokens.push(match (name, argument) {
+                    ("name", None) => Token::Name,
+                    ("ext", None) => Token::Ext,
+                    ("date", format) => Token::Date(format.unwrap_or("%Y-%m-%d").to_string()),
+                    ("counter", None) => Token::Counter(0),
+                    ("counter", Some(width)) => Token::Counter(width.parse()
+                        .map_err(|_| format!("{{counter:{}}} needs a number of digits", width))?),
+                    (group, None) if !group.is_empty() && group.bytes().all(|b| b.is_ascii_digit()) => {
+                        Token::Group(group.parse().map_err(|_| format!("Group {} is out of range", group))?)
+                    }
+                    (group, None) if !group.is_empty() => Token::NamedGroup(group.to_string()),
+                    _ => return Err(format!("Unknown token {{{}}}", token)),
+                });
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        tokens.push(Token::Literal(literal));
+    }
+    Ok(tokens)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\'])
+        && Path::new(name).components().count() == 1
+}
+
+/// The name `pattern` gives each of `paths`, in order, and anything that
+/// would stop the rename.
+pub fn preview(paths: &[PathBuf], pattern: &RenamePattern) -> Result<RenamePreview, String> {
+    let tokens = parse_template(&pattern.template)?;
+    let regex = pattern.regex.as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| format!("Invalid regex: {}", e))?;
+    for token in &tokens {
+        match token {
+            Token::Group(group) if regex.as_ref().map_or(true, |regex| *group >= regex.captures_len()) => {
+                return Err(format!("The regex has no group {}", group));
+            }
+            Token::NamedGroup(group) if !regex.as_ref().is_some_and(|regex| regex.capture_names().flatten().any(|name| name == group)) => {
+                return Err(format!("Unknown token {{{}}}", group));
+            }
+            _ => {}
+        }
+    }
+
+    let mut proposals = Vec::with_capacity(paths.len());
+    for (i, path) in paths.iter().enumerate() {
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let metadata = std::fs::metadata(path).ok();
+        let captures = regex.as_ref().map(|regex| regex.captures(&name));
+        let new_name = match (&metadata, &captures) {
+            (None, _) | (_, Some(None)) => name.clone(),
+            (Some(metadata), _) => {
+                let stem = Path::new(&name).file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+                let ext = Path::new(&name).extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+                let captures = captures.as_ref().and_then(|captures| captures.as_ref());
+                let mut new_name = String::new();
+                for token in &tokens {
+                    match token {
+                        Token::Literal(text) => new_name.push_str(text),
+                        Token::Name => new_name.push_str(&stem),
+                        Token::Ext => new_name.push_str(&ext),
+                        Token::Date(format) => {
+                            let modified: DateTime<Local> = metadata.modified()
+                                .map_err(|e| format!("Failed to get the date of {}: {}", path.display(), e))?
+                                .into();
+                            write!(new_name, "{}", modified.format(format))
+                                .map_err(|_| format!("Invalid date format {}", format))?;
+                        }
+                        Token::Counter(width) => {
+                            let _ = write!(new_name, "{:0width$}", pattern.counter_start + i as u64, width = *width);
+                        }
+                        Token::Group(group) => new_name.push_str(captures.and_then(|captures| captures.get(*group)).map_or("", |m| m.as_str())),
+                        Token::NamedGroup(group) => new_name.push_str(captures.and_then(|captures| captures.name(group)).map_or("", |m| m.as_str())),
+                    }
+                }
+                // Renaming `.bashrc` with `{name}.{ext}` shouldn't leave a trailing dot
+                new_name.strip_suffix('.').filter(|_| ext.is_empty()).map(str::to_string).unwrap_or(new_name)
+            }
+        };
+        let conflict = if metadata.is_none() {
+            Some(RenameConflict::Missing)
+        } else if !is_valid_name(&new_name) {
+            Some(RenameConflict::InvalidName)
+        } else {
+            None
+        };
+        proposals.push(RenameProposal { changed: new_name != name, path: path.clone(), new_name, conflict });
+    }
+
+    // Two files ending up with one name, or a name already taken on disk
+    let mut targets: HashMap<PathBuf, usize> = HashMap::new();
+    for proposal in &proposals {
+        *targets.entry(target(proposal)).or_default() += 1;
+    }
+    for proposal in proposals.iter_mut().filter(|proposal| proposal.conflict.is_none()) {
+        let target = target(proposal);
+        if targets[&target] > 1 {
+            proposal.conflict = Some(RenameConflict::Duplicate);
+        } else if proposal.changed && std::fs::symlink_metadata(&target).is_ok() {
+            proposal.conflict = Some(RenameConflict::Exists);
+        }
+    }
+    let conflicts = proposals.iter().filter(|proposal| proposal.conflict.is_some()).count();
+    Ok(RenamePreview { proposals, conflicts })
+}
+
+fn target(proposal: &RenameProposal) -> PathBuf {
+    proposal.path.with_file_name(&proposal.new_name)
+}
@@ -0,0 +1,152 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use log::{info, warn};
+use std::path::PathBuf;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use crate::file_system::{FileSystemProvider, OsFileSystem};
+
+pub struct FileScanner {
+    total_files: Arc<AtomicUsize>,
+    fs: Arc<dyn FileSystemProvider>,
+    exclusions: PathExclusions,
+}
+
+impl Default for FileScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileScanner {
+    pub fn new() -> Self {
+        Self::with_provider(Arc::new(OsFileSystem))
+    }
+
+    pub fn with_provider(fs: Arc<dyn FileSystemProvider>) -> Self {
+        Self {
+            total_files: Arc::new(AtomicUsize::new(0)),
+            fs,
+            exclusions: PathExclusions::default(),
+        }
+    }
+
+    /// Also skips paths matching the user's exclusion globs.
+    pub fn with_exclusions(mut self, exclusions: PathExclusions) -> Self {
+        self.exclusions = exclusions;
+        self
+    }
+
+    pub async fn scan_directory(&self, path: impl AsRef<Path>) -> usize {
+        let path = path.as_ref();
+        info!("Starting parallel scan of directory: {:?}", path);
+        let start_time = std::time::Instant::now();
+
+        // First pass: Count all files
+        let total = self.collect_paths(path).len();
+
+        info!("Found {} files in {:?}", total, start_time.elapsed());
+        
+        // Store total and return
+        self.total_files.store(total, Ordering::SeqCst);
+        total
+    }
+
+    pub fn collect_paths<P: AsRef<Path>>(&self, path: P) -> Vec<PathBuf> {
+        self.fs.walk(path.as_ref())
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Ok(path) => Some(path),
+                Err(e) => {
+                    warn!("Skipping unreadable entry: {}", e);
+                    None
+                }
+            })
+            .filter(|path| !self.should_skip_path(path))
+            .collect()
+    }
+
+    fn should_skip_path(&self, path: &Path) -> bool {
+        self.exclusions.is_excluded(path)
+    }
+}
+
+/// The built-in exclusions plus user-configured globs matched against full
+/// paths, and the `.gitignore` rules of any repositories that opted in.
+#[derive(Debug, Clone, Default)]
+pub struct PathExclusions {
+    globs: Option<GlobSet>,
+    gitignores: Vec<Gitignore>,
+}
+
+impl PathExclusions {
+    pub fn new(patterns: &[String]) -> Result<Self, String> {
+        if patterns.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = Glob::new(pattern)
+                .map_err(|e| format!("Invalid exclusion pattern '{}': {}", pattern, e))?;
+            builder.add(glob);
+        }
+        let globs = builder.build()
+            .map_err(|e| format!("Failed to build exclusion patterns: {}", e))?;
+        Ok(Self { globs: Some(globs), gitignores: Vec::new() })
+    }
+
+    /// Also skips what the root `.gitignore` of each working tree in `repos`
+    /// ignores. Repositories without one, or that can't be read, are skipped.
+    pub fn with_gitignores(mut self, repos: &[PathBuf], fs: &dyn FileSystemProvider) -> Self {
+        for repo in repos {
+            let Ok(content) = fs.read(&repo.join(".gitignore")) else {
+                continue;
+            };
+            let mut builder = GitignoreBuilder::new(repo);
+            for line in String::from_utf8_lossy(&content).lines() {
+                if let Err(e) = builder.add_line(None, line) {
+                    warn!("Ignoring .gitignore rule {:?} in {:?}: {}", line, repo, e);
+                }
+            }
+            match builder.build() {
+                Ok(gitignore) => self.gitignores.push(gitignore),
+                Err(e) => warn!("Failed to load .gitignore for {:?}: {}", repo, e),
+            }
+        }
+        self
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        is_excluded_path(path)
+            || self.globs.as_ref().is_some_and(|globs| globs.is_match(path))
+            || self.gitignores.iter().any(|gitignore| {
+                path.starts_with(gitignore.path())
+                    && gitignore.matched_path_or_any_parents(path, false).is_ignore()
+            })
+    }
+}
+
+/// Whether the scanner always skips `path`: hidden entries and a few
+/// problematic system directories.
+pub fn is_excluded_path(path: &Path) -> bool {
+    // Skip hidden files and directories
+    if path.file_name()
+        .and_then(|s| s.to_str())
+        .map(|s| s.starts_with('.'))
+        .unwrap_or(false) {
+        return true;
+    }
+
+    // Skip only specific problematic directories
+    if let Some(path_str) = path.to_str() {
+        if path_str.contains("System Volume Information") ||
+           path_str.contains("$Recycle.Bin") ||
+           path_str.contains("$WINDOWS.~BT") {
+            return true;
+        }
+    }
+
+    false
+}
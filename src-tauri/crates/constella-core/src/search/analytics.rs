@@ -0,0 +1,130 @@
+//! Remembers queries that found nothing, so users can see what they keep
+//! failing to find and why.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::warn;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use super::learning::normalize_query;
+use ts_rs::TS;
+
+/// Oldest entries are dropped beyond this many distinct queries.
+const MAX_TRACKED_QUERIES: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZeroResultEntry {
+    /// The query as last typed; entries are keyed case-insensitively, but
+    /// paths in the query need their case to be diagnosed.
+    pub query: String,
+    pub count: u32,
+    pub last_seen: u64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ZeroResultQuery {
+    pub query: String,
+    pub count: u32,
+    #[ts(type = "number")]
+    pub last_seen: u64,
+    pub likely_causes: Vec<ZeroResultCause>,
+}
+
+/// A configuration problem that may explain why a query found nothing.
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[serde(tag = "cause", rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum ZeroResultCause {
+    /// Nothing has been indexed yet.
+    IndexEmpty,
+    /// The term names a hidden or system path the scanner always skips.
+    PathExcluded { term: String },
+    /// File contents that would match aren't being indexed.
+    ContentNotIndexed { reason: String },
+    /// The query names a directory with no indexed documents.
+    RootNotIndexed { path: PathBuf },
+}
+
+pub struct ZeroResultLog {
+    path: PathBuf,
+    entries: RwLock<HashMap<String, ZeroResultEntry>>,
+}
+
+impl ZeroResultLog {
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse zero-result log at {:?}, starting over: {}", path, e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            path,
+            entries: RwLock::new(entries),
+        }
+    }
+
+    pub fn record(&self, query: &str) {
+        let key = normalize_query(query);
+        if key.is_empty() {
+            return;
+        }
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut entries = self.entries.write();
+        let entry = entries.entry(key).or_insert(ZeroResultEntry { query: String::new(), count: 0, last_seen: now });
+        entry.query = query;
+        entry.count += 1;
+        entry.last_seen = now;
+
+        if entries.len() > MAX_TRACKED_QUERIES {
+            if let Some(oldest) = entries.iter()
+                .min_by_key(|(_, entry)| entry.last_seen)
+                .map(|(query, _)| query.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+
+        if let Err(e) = self.save(&entries) {
+            warn!("{}", e);
+        }
+    }
+
+    /// Recorded queries, most frequent first.
+    pub fn entries(&self) -> Vec<ZeroResultEntry> {
+        let mut entries: Vec<ZeroResultEntry> = self.entries.read().values().cloned().collect();
+        entries.sort_by(|a, b| b.count.cmp(&a.count).then(b.last_seen.cmp(&a.last_seen)));
+        entries
+    }
+
+    fn save(&self, entries: &HashMap<String, ZeroResultEntry>) -> Result<(), String> {
+        let json = serde_json::to_string(entries)
+            .map_err(|e| format!("Failed to serialize zero-result log: {}", e))?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write zero-result log: {}", e))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to replace zero-result log: {}", e))
+    }
+}
+
+/// The words of `query` with quotes, grouping and field prefixes stripped.
+pub fn query_terms(query: &str) -> Vec<&str> {
+    query.split_whitespace()
+        .map(|word| word.rsplit_once(':').map(|(_, term)| term).unwrap_or(word))
+        .map(|word| word.trim_matches(|c| matches!(c, '"' | '(' | ')' | '+' | '-')))
+        .filter(|word| !word.is_empty() && !matches!(*word, "AND" | "OR" | "NOT"))
+        .collect()
+}
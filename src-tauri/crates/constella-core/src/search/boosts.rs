@@ -0,0 +1,86 @@
+//! Per-file-type ranking multipliers, e.g. demoting `.log` files or
+//! anything under `node_modules`.
+
+use std::path::Path;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct FileTypeBoost {
+    /// Either an extension (`log`, `.log`) or a glob matched against the
+    /// full path (`**/node_modules/**`).
+    pub pattern: String,
+    /// Score multiplier for matching results; 0 hides them entirely.
+    pub multiplier: f32,
+}
+
+impl FileTypeBoost {
+    fn extension(&self) -> Option<String> {
+        let is_glob = self.pattern.contains(['/', '\\', '*', '?', '[', '{']);
+        (!is_glob).then(|| self.pattern.trim_start_matches('.').to_lowercase())
+    }
+}
+
+/// Compiled form of the configured boosts. A result matching several rules
+/// gets the product of their multipliers.
+pub struct BoostMatcher {
+    extensions: Vec<(String, f32)>,
+    globs: GlobSet,
+    glob_multipliers: Vec<f32>,
+}
+
+impl BoostMatcher {
+    pub fn new(boosts: &[FileTypeBoost]) -> Result<Self, String> {
+        let mut extensions = Vec::new();
+        let mut builder = GlobSetBuilder::new();
+        let mut glob_multipliers = Vec::new();
+
+        for boost in boosts {
+            if !boost.multiplier.is_finite() || boost.multiplier < 0.0 {
+                return Err(format!(
+                    "Multiplier for '{}' must be a non-negative number, got {}",
+                    boost.pattern, boost.multiplier
+                ));
+            }
+            match boost.extension() {
+                Some(extension) if extension.is_empty() => {
+                    return Err("Boost pattern must not be empty".to_string());
+                }
+                Some(extension) => extensions.push((extension, boost.multiplier)),
+                None => {
+                    let glob = Glob::new(&boost.pattern)
+                        .map_err(|e| format!("Invalid glob pattern '{}': {}", boost.pattern, e))?;
+                    builder.add(glob);
+                    glob_multipliers.push(boost.multiplier);
+                }
+            }
+        }
+
+        Ok(Self {
+            extensions,
+            globs: builder.build().map_err(|e| format!("Failed to build glob set: {}", e))?,
+            glob_multipliers,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty() && self.glob_multipliers.is_empty()
+    }
+
+    pub fn multiplier(&self, path: &Path) -> f32 {
+        let extension = path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase);
+        let mut multiplier: f32 = self.extensions.iter()
+            .filter(|(ext, _)| Some(ext) == extension.as_ref())
+            .map(|(_, m)| m)
+            .product();
+        for index in self.globs.matches(path) {
+            multiplier *= self.glob_multipliers[index];
+        }
+        multiplier
+    }
+}
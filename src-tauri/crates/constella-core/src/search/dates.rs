@@ -0,0 +1,209 @@
+//! Calendar expressions in queries (`today`, `last-week`, `2023-Q2`,
+//! `03/04/2023`), resolved to ranges of unix seconds in local time.
+
+use chrono::{DateTime, Datelike, Duration, Local, LocalResult, Months, NaiveDate, NaiveTime, TimeZone, Weekday};
+
+/// How numeric dates like `03/04/2023` are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+/// The locale conventions date expressions depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateLocale {
+    pub order: DateOrder,
+    /// First day of `this-week` and `last-week`.
+    pub week_start: Weekday,
+}
+
+impl Default for DateLocale {
+    fn default() -> Self {
+        Self {
+            order: DateOrder::DayMonthYear,
+            week_start: Weekday::Mon,
+        }
+    }
+}
+
+impl DateLocale {
+    /// Conventions for a locale tag such as `en-US`, `en_GB.UTF-8` or `ja`.
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.split(['.', '@']).next().unwrap_or_default();
+        let mut parts = tag.split(['-', '_']);
+        let language = parts.next().unwrap_or_default().to_lowercase();
+        let region = parts
+            .find(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic()))
+            .map(str::to_uppercase)
+            .unwrap_or_default();
+
+        let order = if matches!(region.as_str(), "US" | "PH" | "FM" | "MH")
+            || (region.is_empty() && language == "en")
+        {
+            DateOrder::MonthDayYear
+        } else if matches!(language.as_str(), "zh" | "ja" | "ko" | "hu" | "lt" | "mn")
+            || matches!(region.as_str(), "CN" | "TW" | "JP" | "KR" | "HU")
+        {
+            DateOrder::YearMonthDay
+        } else {
+            DateOrder::DayMonthYear
+        };
+        let week_start = if matches!(
+            region.as_str(),
+            "US" | "CA" | "MX" | "BR" | "JP" | "KR" | "TW" | "HK" | "IL" | "PH" | "IN" | "ZA"
+        ) {
+            Weekday::Sun
+        } else {
+            Weekday::Mon
+        };
+        Self { order, week_start }
+    }
+
+    /// Conventions of the user's locale, from the usual environment variables.
+    pub fn system() -> Self {
+        ["LC_ALL", "LC_TIME", "LANG"].iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+            .map(|tag| Self::from_tag(&tag))
+            .unwrap_or_default()
+    }
+}
+
+/// The span `expression` covers, as `[start, end)` unix seconds relative to
+/// `now`: a relative name like `today` or `last-month`, a year (`2021`),
+/// month (`2023-04`), quarter (`2023-Q2`) or day (`2023-04-05`, or
+/// `05/04/2023` read in the locale's order).
+pub fn parse_period(expression: &str, now: DateTime<Local>, locale: DateLocale) -> Result<(i64, i64), String> {
+    let normalized = expression.trim().to_lowercase().replace('_', "-");
+    let today = now.date_naive();
+
+    let (start, end) = match normalized.as_str() {
+        "today" => (today, next_day(today)?),
+        "yesterday" => (previous_day(today)?, today),
+        "this-week" | "last-week" => {
+            let days_in = (today.weekday().num_days_from_monday() + 7
+                - locale.week_start.num_days_from_monday()) % 7;
+            let week = today - Duration::days(i64::from(days_in));
+            if normalized == "this-week" {
+                (week, week + Duration::days(7))
+            } else {
+                (week - Duration::days(7), week)
+            }
+        }
+        "this-month" | "last-month" => {
+            let month = month_start(today.year(), today.month())?;
+            if normalized == "this-month" {
+                (month, add_months(month, 1)?)
+            } else {
+                (sub_months(month, 1)?, month)
+            }
+        }
+        "this-year" | "last-year" => {
+            let year = month_start(today.year(), 1)?;
+            if normalized == "this-year" {
+                (year, add_months(year, 12)?)
+            } else {
+                (sub_months(year, 12)?, year)
+            }
+        }
+        _ => parse_calendar_date(&normalized, locale)
+            .ok_or_else(|| format!(
+                "Unrecognized date {:?}: use today, yesterday, this-week, last-week, this-month, last-month, \
+                 this-year, last-year, or a date like 2023, 2023-04, 2023-Q2 or 2023-04-05",
+                expression
+            ))?,
+    };
+    Ok((local_midnight(start), local_midnight(end)))
+}
+
+/// Absolute dates, as the first day they cover and the day after the last.
+fn parse_calendar_date(expression: &str, locale: DateLocale) -> Option<(NaiveDate, NaiveDate)> {
+    if let Some((year, quarter)) = expression.split_once("-q") {
+        let quarter: u32 = quarter.parse().ok().filter(|q| (1..=4).contains(q))?;
+        let start = month_start(parse_year(year)?, (quarter - 1) * 3 + 1).ok()?;
+        return Some((start, add_months(start, 3).ok()?));
+    }
+
+    let separator = ['-', '/', '.'].into_iter().find(|separator| expression.contains(*separator));
+    let parts: Vec<&str> = match separator {
+        Some(separator) => expression.split(separator).collect(),
+        None => vec![expression],
+    };
+    if parts.iter().any(|part| part.is_empty() || !part.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+
+    match parts.as_slice() {
+        [year] if year.len() == 4 => {
+            let start = month_start(parse_year(year)?, 1).ok()?;
+            Some((start, add_months(start, 12).ok()?))
+        }
+        [year, month] if year.len() == 4 => {
+            let start = month_start(parse_year(year)?, month.parse().ok()?).ok()?;
+            Some((start, add_months(start, 1).ok()?))
+        }
+        [first, second, third] => {
+            // A four-digit first part is always a year; otherwise the locale decides
+            let (year, month, day) = if first.len() == 4 {
+                (*first, *second, *third)
+            } else {
+                match locale.order {
+                    DateOrder::DayMonthYear => (*third, *second, *first),
+                    DateOrder::MonthDayYear => (*third, *first, *second),
+                    DateOrder::YearMonthDay => (*first, *second, *third),
+                }
+            };
+            let day = NaiveDate::from_ymd_opt(parse_year(year)?, month.parse().ok()?, day.parse().ok()?)?;
+            Some((day, day.succ_opt()?))
+        }
+        _ => None,
+    }
+}
+
+/// Four-digit years as written; two-digit ones pivot like `strptime`'s `%y`.
+fn parse_year(year: &str) -> Option<i32> {
+    let value: i32 = year.parse().ok()?;
+    match year.len() {
+        4 => Some(value),
+        2 if value < 69 => Some(2000 + value),
+        2 => Some(1900 + value),
+        _ => None,
+    }
+}
+
+fn month_start(year: i32, month: u32) -> Result<NaiveDate, String> {
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| format!("Invalid month {}-{:02}", year, month))
+}
+
+fn add_months(date: NaiveDate, months: u32) -> Result<NaiveDate, String> {
+    date.checked_add_months(Months::new(months))
+        .ok_or_else(|| format!("Date out of range: {}", date))
+}
+
+fn sub_months(date: NaiveDate, months: u32) -> Result<NaiveDate, String> {
+    date.checked_sub_months(Months::new(months))
+        .ok_or_else(|| format!("Date out of range: {}", date))
+}
+
+fn next_day(date: NaiveDate) -> Result<NaiveDate, String> {
+    date.succ_opt().ok_or_else(|| format!("Date out of range: {}", date))
+}
+
+fn previous_day(date: NaiveDate) -> Result<NaiveDate, String> {
+    date.pred_opt().ok_or_else(|| format!("Date out of range: {}", date))
+}
+
+/// Unix seconds at the start of `date` in local time.
+fn local_midnight(date: NaiveDate) -> i64 {
+    let midnight = date.and_time(NaiveTime::MIN);
+    match Local.from_local_datetime(&midnight) {
+        LocalResult::Single(start) | LocalResult::Ambiguous(start, _) => start.timestamp(),
+        // Midnight skipped by a daylight saving change; the day starts an hour in
+        LocalResult::None => Local.from_local_datetime(&(midnight + Duration::hours(1)))
+            .earliest()
+            .map_or_else(|| midnight.and_utc().timestamp(), |start| start.timestamp()),
+    }
+}
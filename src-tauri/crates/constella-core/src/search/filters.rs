@@ -0,0 +1,318 @@
+//! Filter expressions written into the query (`modified:today`,
+//! `before:2021`, `taken:2023`, `size:>10mb`, `age:>2y`, `depth:<=3`, `repo:constella`, `label:receipt`, `type:pdf`), pulled out before the rest reaches the query parser and
+//! applied as range queries over fast fields.
+
+use std::ops::Bound;
+use chrono::{DateTime, Duration, Local, Months};
+use tantivy::query::{AllQuery, BooleanQuery, ConstScoreQuery, Occur, Query, RangeQuery};
+use super::dates::{parse_period, DateLocale};
+
+/// What relative expressions in a query are resolved against.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterContext {
+    pub now: DateTime<Local>,
+    pub locale: DateLocale,
+}
+
+impl FilterContext {
+    /// The current time, in `locale` or the system locale when it is `None`.
+    pub fn now(locale: Option<&str>) -> Self {
+        Self {
+            now: Local::now(),
+            locale: locale.map_or_else(DateLocale::system, DateLocale::from_tag),
+        }
+    }
+}
+
+/// A range over a u64 fast field that matches must fall in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeFilter {
+    pub field: &'static str,
+    pub lower: Bound<u64>,
+    pub upper: Bound<u64>,
+}
+
+/// Bounds on how many folders down from its root a file sits; a file
+/// directly in the root is at depth 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthFilter {
+    pub lower: Bound<u64>,
+    pub upper: Bound<u64>,
+}
+
+/// A query split into its free text and its filters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilteredQuery {
+    pub text: String,
+    pub filters: Vec<RangeFilter>,
+    /// Depends on which root is searched, so it is left to the caller.
+    pub depth: Option<DepthFilter>,
+    /// Lowercased names of git repositories, any of which matches.
+    pub repos: Vec<String>,
+    /// Lowercased image labels, all of which must match.
+    pub labels: Vec<String>,
+    /// Lowercased file categories or extensions, any of which matches.
+    pub types: Vec<String>,
+}
+
+enum Filter {
+    Range(RangeFilter),
+    Depth(DepthFilter),
+    Repo(String),
+    Label(String),
+    Type(String),
+}
+
+impl FilteredQuery {
+    /// `text_query` restricted to the filters and to `scopes`, the queries
+    /// the caller built for filters that need more context, like depth.
+    /// Neither adds to the score, and a query that is only filters matches
+    /// everything in range.
+    pub fn apply(&self, text_query: Box<dyn Query>, scopes: Vec<Box<dyn Query>>) -> Box<dyn Query> {
+        if self.filters.is_empty() && scopes.is_empty() {
+            return text_query;
+        }
+        let text_query: Box<dyn Query> = if self.text.trim().is_empty() {
+            Box::new(AllQuery)
+        } else {
+            text_query
+        };
+
+        let mut clauses = vec![(Occur::Must, text_query)];
+        let ranges = self.filters.iter().map(|filter| -> Box<dyn Query> {
+            Box::new(RangeQuery::new_u64_bounds(filter.field.to_string(), filter.lower, filter.upper))
+        });
+        for filter in ranges.chain(scopes) {
+            clauses.push((Occur::Must, Box::new(ConstScoreQuery::new(filter, 0.0)) as Box<dyn Query>));
+        }
+        Box::new(BooleanQuery::new(clauses))
+    }
+}
+
+/// Splits the filter expressions out of `query`. Quoted phrases are left
+/// alone; a malformed filter is an error rather than a search for its text.
+///
+/// - `modified:<date>` and `created:<date>` match files in that period, and
+///   `taken:<date>` photos taken in it
+/// - `before:<date>` and `after:<date>` match files modified before it
+///   starts or after it ends
+/// - `size:<size>` matches files of that size, e.g. `size:500k`
+/// - `age:<age>` matches files last modified that long ago, e.g. `age:>2y`;
+///   ages count hours, days, weeks, months or years (`h`, `d`, `w`, `mo`, `y`)
+/// - `depth:<n>` matches files `n` folders down from their root
+/// - `repo:<name>` matches files in git repositories with that folder name
+/// - `label:<label>` matches images labeled that way, e.g. `label:receipt`
+/// - `type:<type>` matches files of a category or extension, e.g.
+///   `type:image` or `type:pdf`
+/// - any of these may be prefixed with `>`, `>=`, `<` or `<=`, or written
+///   as a range like `1gb..5gb` with either end left open
+pub fn extract_filters(query: &str, context: &FilterContext) -> Result<FilteredQuery, String> {
+    let mut filtered = FilteredQuery::default();
+    let mut in_quotes = false;
+    for (i, segment) in query.split('"').enumerate() {
+        if i > 0 {
+            filtered.text.push('"');
+            in_quotes = !in_quotes;
+        }
+        if in_quotes {
+            filtered.text.push_str(segment);
+            continue;
+        }
+
+        let mut words = Vec::new();
+        for word in segment.split(' ') {
+            match parse_filter(word, context)? {
+                Some(Filter::Range(filter)) => filtered.filters.push(filter),
+                Some(Filter::Depth(depth)) => filtered.depth = Some(depth),
+                Some(Filter::Repo(repo)) => filtered.repos.push(repo),
+                Some(Filter::Label(label)) => filtered.labels.push(label),
+                Some(Filter::Type(file_type)) => filtered.types.push(file_type),
+                None => words.push(word),
+            }
+        }
+        filtered.text.push_str(&words.join(" "));
+    }
+    Ok(filtered)
+}
+
+fn parse_filter(word: &str, context: &FilterContext) -> Result<Option<Filter>, String> {
+    let Some((key, value)) = word.split_once(':') else {
+        return Ok(None);
+    };
+    if key.eq_ignore_ascii_case("repo") {
+        if value.is_empty() {
+            return Err(format!("Missing repository name after {:?}", word));
+        }
+        return Ok(Some(Filter::Repo(value.to_lowercase())));
+    }
+    if key.eq_ignore_ascii_case("label") {
+        if value.is_empty() {
+            return Err(format!("Missing label after {:?}", word));
+        }
+        return Ok(Some(Filter::Label(value.to_lowercase())));
+    }
+    if key.eq_ignore_ascii_case("type") {
+        if value.trim_start_matches('.').is_empty() {
+            return Err(format!("Missing file type after {:?}", word));
+        }
+        return Ok(Some(Filter::Type(value.to_lowercase())));
+    }
+    let (field, value) = match key.to_lowercase().as_str() {
+        "modified" => ("modified", value.to_string()),
+        "created" => ("created", value.to_string()),
+        "taken" => ("taken", value.to_string()),
+        "before" => ("modified", format!("<{}", value)),
+        "after" => ("modified", format!(">{}", value)),
+        "size" => ("size", value.to_string()),
+        "age" => ("age", value.to_string()),
+        "depth" => ("depth", value.to_string()),
+        _ => return Ok(None),
+    };
+    if value.trim_start_matches(['<', '>', '=']).is_empty() {
+        return Err(format!("Missing value after {:?}", word));
+    }
+
+    let invalid = |e: String| format!("Invalid filter {:?}: {}", word, e);
+    let (lower, upper) = match field {
+        // A size or depth matches exactly that many bytes or folders
+        "size" => range_bounds(&value, |expression| parse_size(expression).map(|size| (size, size.saturating_add(1)))),
+        "depth" => range_bounds(&value, |expression| parse_depth(expression).map(|depth| (depth, depth + 1))),
+        "age" => age_bounds(&value, context.now),
+        _ => {
+            let clamp = |bound: i64| bound.max(0) as u64;
+            range_bounds(&value, |expression| parse_period(expression, context.now, context.locale)
+                .map(|(start, end)| (clamp(start), clamp(end))))
+        }
+    }.map_err(invalid)?;
+
+    Ok(Some(match field {
+        "depth" => Filter::Depth(DepthFilter { lower, upper }),
+        // Ages are filtered on the modified time
+        "age" => Filter::Range(RangeFilter { field: "modified", lower, upper }),
+        _ => Filter::Range(RangeFilter { field, lower, upper }),
+    }))
+}
+
+fn parse_depth(expression: &str) -> Result<u64, String> {
+    expression.trim().parse()
+        .map_err(|_| format!("Expected a whole number of folders, e.g. depth:<=3, got {:?}", expression))
+}
+
+/// Bounds on the modified time for an age comparison or range. A bare age
+/// covers the whole unit, so `age:2y` is anything from two to three years old.
+fn age_bounds(value: &str, now: DateTime<Local>) -> Result<(Bound<u64>, Bound<u64>), String> {
+    // When a file exactly `extra` units older than `expression` was modified
+    let cutoff = |expression: &str, extra: u32| -> Result<u64, String> {
+        let (amount, unit) = parse_age(expression)?;
+        age_cutoff(now, amount.saturating_add(extra), unit)
+    };
+
+    // Older files have earlier modified times, so the bounds swap
+    if let Some((from, to)) = value.split_once("..") {
+        let lower = if to.is_empty() { Bound::Unbounded } else { Bound::Included(cutoff(to, 0)?) };
+        let upper = if from.is_empty() { Bound::Unbounded } else { Bound::Excluded(cutoff(from, 0)? + 1) };
+        return Ok((lower, upper));
+    }
+
+    Ok(if let Some(value) = value.strip_prefix(">=") {
+        (Bound::Unbounded, Bound::Excluded(cutoff(value, 0)? + 1))
+    } else if let Some(value) = value.strip_prefix("<=") {
+        (Bound::Included(cutoff(value, 0)?), Bound::Unbounded)
+    } else if let Some(value) = value.strip_prefix('>') {
+        (Bound::Unbounded, Bound::Excluded(cutoff(value, 0)?))
+    } else if let Some(value) = value.strip_prefix('<') {
+        (Bound::Included(cutoff(value, 0)? + 1), Bound::Unbounded)
+    } else {
+        (Bound::Included(cutoff(value, 1)? + 1), Bound::Excluded(cutoff(value, 0)? + 1))
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AgeUnit {
+    Hours,
+    Days,
+    Weeks,
+    Months,
+    Years,
+}
+
+fn parse_age(expression: &str) -> Result<(u32, AgeUnit), String> {
+    let expression = expression.trim().to_lowercase();
+    let split = expression.find(|c: char| !c.is_ascii_digit()).unwrap_or(expression.len());
+    let (amount, unit) = expression.split_at(split);
+    let amount = amount.parse()
+        .map_err(|_| format!("Expected a whole number with a unit, e.g. 2y, got {:?}", expression))?;
+    let unit = match unit {
+        "h" => AgeUnit::Hours,
+        "d" => AgeUnit::Days,
+        "w" => AgeUnit::Weeks,
+        "mo" => AgeUnit::Months,
+        "y" => AgeUnit::Years,
+        _ => return Err(format!("Unknown age unit {:?}: use h, d, w, mo or y", unit)),
+    };
+    Ok((amount, unit))
+}
+
+/// Unix seconds `amount` units before `now`, counting months and years on
+/// the calendar.
+fn age_cutoff(now: DateTime<Local>, amount: u32, unit: AgeUnit) -> Result<u64, String> {
+    let amount_i64 = i64::from(amount);
+    let cutoff = match unit {
+        AgeUnit::Hours => now.checked_sub_signed(Duration::hours(amount_i64)),
+        AgeUnit::Days => now.checked_sub_signed(Duration::days(amount_i64)),
+        AgeUnit::Weeks => now.checked_sub_signed(Duration::weeks(amount_i64)),
+        AgeUnit::Months => now.checked_sub_months(Months::new(amount)),
+        AgeUnit::Years => amount.checked_mul(12).and_then(|months| now.checked_sub_months(Months::new(months))),
+    };
+    // Anything older than the calendar can express is older than every file
+    Ok(cutoff.map_or(0, |cutoff| cutoff.timestamp().max(0) as u64))
+}
+
+/// Bytes in a size like `10mb`, `1.5g` or `500`. Units are binary, so
+/// `1kb` is 1024 bytes.
+pub fn parse_size(expression: &str) -> Result<u64, String> {
+    let expression = expression.trim().to_lowercase();
+    let split = expression.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(expression.len());
+    let (number, unit) = expression.split_at(split);
+    let number: f64 = number.parse()
+        .map_err(|_| format!("Expected a number with an optional unit, e.g. 10mb, got {:?}", expression))?;
+    let multiplier: u64 = match unit {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return Err(format!("Unknown size unit {:?}: use b, kb, mb, gb or tb", unit)),
+    };
+    let bytes = (number * multiplier as f64).round();
+    if bytes >= u64::MAX as f64 {
+        return Err(format!("Size {:?} is too large", expression));
+    }
+    Ok(bytes as u64)
+}
+
+/// Bounds for a comparison or range, given how to resolve a single value to
+/// the `[start, end)` span it covers.
+fn range_bounds(
+    value: &str,
+    span: impl Fn(&str) -> Result<(u64, u64), String>,
+) -> Result<(Bound<u64>, Bound<u64>), String> {
+    if let Some((from, to)) = value.split_once("..") {
+        let lower = if from.is_empty() { Bound::Unbounded } else { Bound::Included(span(from)?.0) };
+        let upper = if to.is_empty() { Bound::Unbounded } else { Bound::Excluded(span(to)?.1) };
+        return Ok((lower, upper));
+    }
+
+    Ok(if let Some(value) = value.strip_prefix(">=") {
+        (Bound::Included(span(value)?.0), Bound::Unbounded)
+    } else if let Some(value) = value.strip_prefix("<=") {
+        (Bound::Unbounded, Bound::Excluded(span(value)?.1))
+    } else if let Some(value) = value.strip_prefix('>') {
+        (Bound::Included(span(value)?.1), Bound::Unbounded)
+    } else if let Some(value) = value.strip_prefix('<') {
+        (Bound::Unbounded, Bound::Excluded(span(value)?.0))
+    } else {
+        let (start, end) = span(value)?;
+        (Bound::Included(start), Bound::Excluded(end))
+    })
+}
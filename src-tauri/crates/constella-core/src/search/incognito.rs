@@ -0,0 +1,32 @@
+//! Incognito sessions, one per app window that turns it on. Searches and
+//! clicks made in one leave no trace: queries aren't kept in the search
+//! history, clicks don't train result ranking and opened files don't feed
+//! suggestions. The backend checks the session on every such call, so a
+//! window can't record by leaving the flag off a request.
+
+use std::collections::HashSet;
+use parking_lot::RwLock;
+
+#[derive(Default)]
+pub struct IncognitoSessions {
+    sessions: RwLock<HashSet<String>>,
+}
+
+impl IncognitoSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, session: &str, incognito: bool) {
+        let mut sessions = self.sessions.write();
+        if incognito {
+            sessions.insert(session.to_string());
+        } else {
+            sessions.remove(session);
+        }
+    }
+
+    pub fn is_incognito(&self, session: &str) -> bool {
+        self.sessions.read().contains(session)
+    }
+}
@@ -0,0 +1,88 @@
+//! Learns which results users actually open for a query, so a file picked
+//! again and again for "budget" climbs the ranking for that query.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use parking_lot::RwLock;
+
+/// Click counts per normalized query, then per result path.
+type ClickCounts = HashMap<String, HashMap<String, u32>>;
+
+pub struct ClickLearning {
+    path: PathBuf,
+    clicks: RwLock<ClickCounts>,
+}
+
+impl ClickLearning {
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let clicks = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse learning data at {:?}, starting over: {}", path, e);
+                ClickCounts::new()
+            }),
+            Err(_) => ClickCounts::new(),
+        };
+
+        Self {
+            path,
+            clicks: RwLock::new(clicks),
+        }
+    }
+
+    /// Counts a click on `result` after searching for `query` and returns
+    /// how often that result has now been picked for the query.
+    pub fn record(&self, query: &str, result: &str) -> Result<u32, String> {
+        let query = normalize_query(query);
+        if query.is_empty() {
+            return Err("Cannot learn from an empty query".to_string());
+        }
+
+        let mut clicks = self.clicks.write();
+        let count = clicks.entry(query)
+            .or_default()
+            .entry(result.to_string())
+            .or_default();
+        *count += 1;
+        let count = *count;
+        self.save(&clicks)?;
+        Ok(count)
+    }
+
+    /// How often each result has been picked for `query`.
+    pub fn clicks_for(&self, query: &str) -> HashMap<String, u32> {
+        self.clicks.read()
+            .get(&normalize_query(query))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Forgets every recorded click.
+    pub fn clear(&self) -> Result<(), String> {
+        let mut clicks = self.clicks.write();
+        clicks.clear();
+        self.save(&clicks)?;
+        info!("Cleared click learning data");
+        Ok(())
+    }
+
+    fn save(&self, clicks: &ClickCounts) -> Result<(), String> {
+        let json = serde_json::to_string(clicks)
+            .map_err(|e| format!("Failed to serialize learning data: {}", e))?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write learning data: {}", e))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to replace learning data: {}", e))
+    }
+}
+
+/// Case and whitespace don't make a query a different one.
+pub fn normalize_query(query: &str) -> String {
+    query.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,276 @@
+//! Relevance tuning shared by every search path.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Facet;
+use ts_rs::TS;
+use crate::indexing::shedding::SheddingMeasure;
+
+pub mod analytics;
+pub mod boosts;
+pub mod dates;
+pub mod filters;
+pub mod incognito;
+pub mod learning;
+pub mod noise;
+pub mod rewrite;
+
+pub use boosts::FileTypeBoost;
+pub use noise::StopwordSettings;
+pub use rewrite::QueryRewrites;
+
+/// How much each field and a file's age contribute to its score. Field
+/// boosts multiply tantivy's BM25 score for matches in that field; the BM25
+/// k1/b parameters themselves are fixed by tantivy and not configurable.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct RankingWeights {
+    pub name_boost: f32,
+    pub path_boost: f32,
+    pub content_boost: f32,
+    /// Markdown and notebook section headings.
+    pub headings_boost: f32,
+    /// Extra score multiplier for a file modified just now; zero disables
+    /// the recency boost.
+    pub recency_weight: f32,
+    /// Age at which half of the recency boost is left.
+    pub recency_half_life_days: f32,
+    /// How strongly past clicks for the same query lift a result; zero
+    /// ignores them.
+    pub click_weight: f32,
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        Self {
+            name_boost: 3.0,
+            path_boost: 2.0,
+            content_boost: 1.0,
+            headings_boost: 2.0,
+            recency_weight: 0.5,
+            recency_half_life_days: 30.0,
+            click_weight: 0.5,
+        }
+    }
+}
+
+impl RankingWeights {
+    pub fn validate(&self) -> Result<(), String> {
+        let boosts = [
+            ("name_boost", self.name_boost),
+            ("path_boost", self.path_boost),
+            ("content_boost", self.content_boost),
+            ("recency_weight", self.recency_weight),
+            ("click_weight", self.click_weight),
+        ];
+        for (name, value) in boosts {
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("{} must be a non-negative number, got {}", name, value));
+            }
+        }
+        if !self.recency_half_life_days.is_finite() || self.recency_half_life_days <= 0.0 {
+            return Err(format!(
+                "recency_half_life_days must be positive, got {}",
+                self.recency_half_life_days
+            ));
+        }
+        Ok(())
+    }
+
+    /// Score multiplier for a file last modified `modified` (unix seconds)
+    /// when the time is `now`.
+    pub fn recency_multiplier(&self, modified: u64, now: u64) -> f32 {
+        if self.recency_weight == 0.0 {
+            return 1.0;
+        }
+        let age_days = now.saturating_sub(modified) as f32 / 86_400.0;
+        1.0 + self.recency_weight * 0.5f32.powf(age_days / self.recency_half_life_days)
+    }
+
+    /// Score multiplier for a result picked `clicks` times for the query.
+    /// Grows logarithmically so a few clicks matter but never swamp relevance.
+    pub fn click_multiplier(&self, clicks: u32) -> f32 {
+        1.0 + self.click_weight * (clicks as f32).ln_1p()
+    }
+}
+
+/// Per-query switches accepted alongside the query string.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct SearchOptions {
+    /// Attach a `ScoreExplanation` to every result.
+    pub explain: bool,
+    /// Search for the query exactly as typed, ignoring the rewrite table.
+    pub skip_rewrites: bool,
+    /// How much of each result to return.
+    pub fields: ResultFields,
+    /// Count matches per file type into `SearchResponse::facets`.
+    pub facets: bool,
+    /// Only search below this folder; `depth:` counts from it rather than
+    /// from the indexed roots.
+    pub root: Option<PathBuf>,
+    /// Only search the files in this collection, and below its folders.
+    pub collection: Option<String>,
+    /// Keep the query out of the search history. Always set for searches
+    /// from incognito windows.
+    pub incognito: bool,
+    /// Search the index as it was at this `SearchResponse::snapshot`, so
+    /// later pages match the first whatever was indexed in between.
+    /// Searches merged from shards or several indexes ignore it.
+    #[ts(type = "number | null")]
+    pub snapshot: Option<u64>,
+    /// Skip this many results, for the pages after the first. Searches
+    /// merged from shards or several indexes ignore it.
+    pub offset: usize,
+}
+
+/// How much of each result a search returns. Each level includes the ones
+/// before it, so cheap callers like autocomplete can skip the rest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum ResultFields {
+    /// Just `path`.
+    Paths,
+    /// `path`, `name`, `size`, `modified` and `score`.
+    #[default]
+    Metadata,
+    /// Metadata plus a `snippet` of HTML highlighting the matched terms,
+    /// which means reading every returned text file.
+    Snippets,
+}
+
+/// Results of one search.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct SearchResponse {
+    #[ts(type = "Array<unknown>")]
+    pub results: Vec<serde_json::Value>,
+    /// Percentage of the running full index that is searchable so far;
+    /// below 100 the results may be missing files.
+    pub index_completeness: f32,
+    /// Set when `SearchOptions::facets` was asked for.
+    #[serde(default)]
+    pub facets: Option<SearchFacets>,
+    /// Load shedding measures in effect, e.g. no snippets on a very large
+    /// index.
+    #[serde(default)]
+    pub shed: Vec<SheddingMeasure>,
+    /// Generation of the index snapshot searched, to pass back as
+    /// `SearchOptions::snapshot`; unset when results were merged from
+    /// several indexes.
+    #[serde(default)]
+    #[ts(type = "number | null")]
+    pub snapshot: Option<u64>,
+}
+
+/// Match counts over every file a search matched, not just the returned
+/// results.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct SearchFacets {
+    /// Lowercased extensions, most common first; files without one count
+    /// under an empty string.
+    pub file_types: Vec<FacetCount>,
+    /// Broad kinds of file from `FILE_CATEGORIES`, most common first.
+    #[serde(default)]
+    pub categories: Vec<FacetCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+impl SearchFacets {
+    /// Facets from counts per lowercased extension and per category, e.g.
+    /// summed over several searches.
+    pub fn from_counts(
+        file_types: impl IntoIterator<Item = (String, usize)>,
+        categories: impl IntoIterator<Item = (String, usize)>,
+    ) -> Self {
+        Self { file_types: most_common(file_types), categories: most_common(categories) }
+    }
+}
+
+fn most_common(counts: impl IntoIterator<Item = (String, usize)>) -> Vec<FacetCount> {
+    let mut counts: Vec<FacetCount> = counts.into_iter()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    counts
+}
+
+/// Broad kinds of file, the top level of their MIME type; files of no
+/// known type are `other`.
+pub const FILE_CATEGORIES: &[&str] = &["application", "audio", "font", "image", "model", "other", "text", "video"];
+
+/// Where `path` goes in the `type` facet: `/type/image/png`, or just the
+/// category for files without an extension.
+pub fn type_facet(path: &Path) -> Facet {
+    match path.extension().map(|extension| extension.to_string_lossy().to_lowercase()) {
+        Some(extension) => Facet::from_path(["type", file_category(&extension), extension.as_str()]),
+        None => Facet::from_path(["type", "other"]),
+    }
+}
+
+/// What `type:<value>` matches: a whole category like `image`, or an
+/// extension like `pdf`.
+pub fn type_filter_facet(value: &str) -> Facet {
+    let value = value.trim_start_matches('.').to_lowercase();
+    if FILE_CATEGORIES.contains(&value.as_str()) {
+        Facet::from_path(["type", value.as_str()])
+    } else {
+        Facet::from_path(["type", file_category(&value), value.as_str()])
+    }
+}
+
+fn file_category(extension: &str) -> &'static str {
+    mime_guess::from_ext(extension).first()
+        .and_then(|mime| FILE_CATEGORIES.iter().find(|category| **category == mime.type_().as_str()).copied())
+        .unwrap_or("other")
+}
+
+/// Why a result scored what it did.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ScoreExplanation {
+    /// BM25 score including field boosts, before the recency boost.
+    pub text_score: f32,
+    pub recency_multiplier: f32,
+    /// Product of the file type boosts matching the result's path.
+    pub file_type_multiplier: f32,
+    /// Boost from past clicks on this result for the same query.
+    pub click_multiplier: f32,
+    pub final_score: f32,
+    pub matched_terms: Vec<MatchedTerm>,
+    /// tantivy's own breakdown of `text_score`.
+    #[ts(type = "unknown")]
+    pub details: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct MatchedTerm {
+    pub field: String,
+    pub term: String,
+    pub term_frequency: u32,
+    pub boost: f32,
+}
+
+impl RankingWeights {
+    /// The boost applied to matches in the named field.
+    pub fn field_boost(&self, field: &str) -> f32 {
+        match field {
+            "name" => self.name_boost,
+            "path" => self.path_boost,
+            "content" => self.content_boost,
+            "headings" => self.headings_boost,
+            _ => 1.0,
+        }
+    }
+}
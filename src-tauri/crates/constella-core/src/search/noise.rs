@@ -0,0 +1,86 @@
+//! Stopwords and high-frequency "noise" terms, dropped from the content
+//! field of a query so they neither dilute scoring nor slow matching down.
+//! Names and paths are never filtered: a file called `the` is still findable.
+
+use serde::{Deserialize, Serialize};
+use tantivy::tokenizer::{LowerCaser, RemoveLongFilter, SimpleTokenizer, StopWordFilter, TextAnalyzer};
+use ts_rs::TS;
+
+/// Tokenizer the content field is indexed with. Content is indexed without
+/// stopword removal, so editing the lists never requires a rebuild; the
+/// filtering happens when queries are parsed.
+pub const CONTENT_TOKENIZER: &str = "content";
+
+const DEFAULT_STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct StopwordSettings {
+    /// Words ignored in the content field of every query.
+    pub stopwords: Vec<String>,
+    /// Share of documents a term must appear in before it is treated as
+    /// noise; 1.0 turns detection off.
+    pub noise_threshold: f32,
+    /// Index size below which no term is treated as noise.
+    #[ts(type = "number")]
+    pub noise_min_documents: u64,
+}
+
+impl Default for StopwordSettings {
+    fn default() -> Self {
+        Self {
+            stopwords: DEFAULT_STOPWORDS.iter().map(|word| word.to_string()).collect(),
+            noise_threshold: 0.6,
+            noise_min_documents: 1_000,
+        }
+    }
+}
+
+impl StopwordSettings {
+    /// Validates the thresholds and lowercases and dedupes the stopwords.
+    pub fn normalized(mut self) -> Result<Self, String> {
+        if !self.noise_threshold.is_finite() || self.noise_threshold <= 0.0 || self.noise_threshold > 1.0 {
+            return Err(format!("noise_threshold must be in (0, 1], got {}", self.noise_threshold));
+        }
+        let mut stopwords: Vec<String> = self.stopwords.iter()
+            .map(|word| word.trim().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect();
+        stopwords.sort();
+        stopwords.dedup();
+        self.stopwords = stopwords;
+        Ok(self)
+    }
+
+    pub fn noise_detection_enabled(&self, num_docs: u64) -> bool {
+        self.noise_threshold < 1.0 && num_docs >= self.noise_min_documents
+    }
+}
+
+/// The content analyzer, dropping `stopwords`. Indexing uses it with an
+/// empty list.
+pub fn content_analyzer(stopwords: &[String]) -> TextAnalyzer {
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .filter(StopWordFilter::remove(stopwords.to_vec()))
+        .build()
+}
+
+/// The distinct tokens `analyzer` produces for `text`.
+pub fn tokenize(analyzer: &mut TextAnalyzer, text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut stream = analyzer.token_stream(text);
+    while stream.advance() {
+        let token = &stream.token().text;
+        if !tokens.contains(token) {
+            tokens.push(token.clone());
+        }
+    }
+    tokens
+}
@@ -0,0 +1,90 @@
+//! User-defined query rewrites ("jpeg → jpg", "photo → jpg, png, heic"),
+//! applied to the raw query string before it reaches the query parser.
+
+use std::collections::BTreeMap;
+
+/// Maps a lowercase query word to the terms that replace it. A word that
+/// should keep matching itself lists itself among its expansions.
+pub type QueryRewrites = BTreeMap<String, Vec<String>>;
+
+/// Normalizes a rewrite entry, rejecting ones the rewriter couldn't apply.
+pub fn normalize_rule(term: &str, expansions: &[String]) -> Result<(String, Vec<String>), String> {
+    let term = term.trim().to_lowercase();
+    if term.is_empty() || !is_plain_word(&term) {
+        return Err(format!("Rewrite term must be a single word, got {:?}", term));
+    }
+
+    let mut normalized: Vec<String> = Vec::new();
+    for expansion in expansions {
+        let expansion = expansion.trim().to_lowercase();
+        if expansion.is_empty() {
+            continue;
+        }
+        if !expansion.split_whitespace().all(is_plain_word) {
+            return Err(format!("Rewrite expansion contains query syntax: {:?}", expansion));
+        }
+        if !normalized.contains(&expansion) {
+            normalized.push(expansion);
+        }
+    }
+    if normalized.is_empty() {
+        return Err(format!("Rewrite for {:?} needs at least one expansion", term));
+    }
+    Ok((term, normalized))
+}
+
+/// Replaces every plain word in `query` that has a rewrite entry with its
+/// expansions. Quoted phrases, field-qualified terms and operators are left
+/// alone so explicit queries mean exactly what they say.
+pub fn rewrite_query(query: &str, rewrites: &QueryRewrites) -> String {
+    if rewrites.is_empty() {
+        return query.to_string();
+    }
+
+    let mut rewritten = String::with_capacity(query.len());
+    let mut in_quotes = false;
+    for (i, segment) in query.split('"').enumerate() {
+        if i > 0 {
+            rewritten.push('"');
+            in_quotes = !in_quotes;
+        }
+        if in_quotes {
+            rewritten.push_str(segment);
+            continue;
+        }
+        let words: Vec<String> = segment.split(' ')
+            .map(|word| rewrite_word(word, rewrites))
+            .collect();
+        rewritten.push_str(&words.join(" "));
+    }
+    rewritten
+}
+
+fn rewrite_word(word: &str, rewrites: &QueryRewrites) -> String {
+    if !is_plain_word(word) {
+        return word.to_string();
+    }
+    match rewrites.get(&word.to_lowercase()) {
+        Some(expansions) if expansions.len() == 1 => quote_phrase(&expansions[0]),
+        Some(expansions) => {
+            let alternatives: Vec<String> = expansions.iter().map(|e| quote_phrase(e)).collect();
+            format!("({})", alternatives.join(" OR "))
+        }
+        None => word.to_string(),
+    }
+}
+
+fn quote_phrase(expansion: &str) -> String {
+    if expansion.contains(' ') {
+        format!("\"{}\"", expansion)
+    } else {
+        expansion.to_string()
+    }
+}
+
+fn is_plain_word(word: &str) -> bool {
+    !word.is_empty()
+        && !matches!(word, "AND" | "OR" | "NOT")
+        && word.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'))
+        && !word.starts_with('-')
+}
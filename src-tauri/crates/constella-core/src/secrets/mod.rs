@@ -0,0 +1,306 @@
+//! Opt-in scanning of indexed text for likely credentials: AWS access
+//! keys, private key blocks, GitHub and Slack tokens, and long
+//! high-entropy strings assigned to something. Files that contain them are
+//! listed in a report kept apart from the index, in a file only the user
+//! can read. The secrets themselves are never kept: they are blanked out
+//! of the text before it is indexed or shown in a snippet, and the report
+//! only shows enough of each to tell findings apart.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::warn;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::file_system::os_path;
+
+/// Shown with every report.
+pub const SECRET_REPORT_WARNING: &str = "These files appear to contain credentials. Treat every finding as \
+    exposed: rotate real keys and tokens, then remove them from the files. Moving or deleting a file does not \
+    revoke what was in it.";
+
+/// What secrets are replaced with in indexed text and snippets.
+pub const REDACTED: &str = "[redacted]";
+
+/// Findings kept per file.
+const MAX_FINDINGS_PER_FILE: usize = 50;
+/// Shortest string checked for entropy.
+const MIN_TOKEN_LENGTH: usize = 20;
+/// Bits per character above which a token looks random. Hex digests top
+/// out at 4, so they aren't flagged.
+const MIN_TOKEN_ENTROPY: f64 = 4.2;
+/// Characters of a secret the report shows.
+const PREVIEW_LENGTH: usize = 4;
+
+const AWS_KEY_PREFIXES: &[&str] = &["AKIA", "ASIA"];
+const GITHUB_TOKEN_PREFIXES: &[&str] = &["ghp_", "gho_", "ghu_", "ghs_", "ghr_", "github_pat_"];
+const SLACK_TOKEN_PREFIXES: &[&str] = &["xoxb-", "xoxp-", "xoxa-", "xoxr-", "xoxs-"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum SecretKind {
+    AwsAccessKey,
+    PrivateKey,
+    GithubToken,
+    SlackToken,
+    /// A long random-looking string assigned to a name.
+    HighEntropyToken,
+}
+
+/// A likely secret in some text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretMatch {
+    pub kind: SecretKind,
+    /// Byte range of the secret.
+    pub range: Range<usize>,
+    /// 1-based line it starts on.
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct SecretFinding {
+    pub path: String,
+    #[ts(type = "number")]
+    pub line: u64,
+    pub kind: SecretKind,
+    /// The first few characters with the rest masked, e.g. `AKIA****************`.
+    pub preview: String,
+    /// Unix seconds.
+    #[ts(type = "number")]
+    pub found_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct SecretReport {
+    pub warning: String,
+    pub findings: Vec<SecretFinding>,
+}
+
+/// Likely secrets in `text`, in order.
+pub fn scan(text: &str) -> Vec<SecretMatch> {
+    let mut matches = private_keys(text);
+    for (start, token) in tokens(text) {
+        let range = start..start + token.len();
+        if matches.iter().any(|found| found.range.start < range.end && range.start < found.range.end) {
+            continue;
+        }
+        let Some(kind) = token_kind(text, start, token) else {
+            continue;
+        };
+        matches.push(SecretMatch { kind, range, line: line_at(text, start) });
+    }
+    matches.sort_by_key(|found| found.range.start);
+    matches
+}
+
+/// `text` with each of `matches` blanked out.
+pub fn redact(text: &str, matches: &[SecretMatch]) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut at = 0;
+    for found in matches {
+        if found.range.start < at {
+            continue;
+        }
+        redacted.push_str(&text[at..found.range.start]);
+        redacted.push_str(REDACTED);
+        at = found.range.end;
+    }
+    redacted.push_str(&text[at..]);
+    redacted
+}
+
+/// The first few characters of `secret` with the rest masked.
+pub fn preview(secret: &str) -> String {
+    let shown: String = secret.chars().take(PREVIEW_LENGTH).collect();
+    let masked = secret.chars().count().saturating_sub(PREVIEW_LENGTH).min(16);
+    format!("{}{}", shown, "*".repeat(masked))
+}
+
+/// Findings for `matches` in `text` of the file at `path`, with lines
+/// counted from `first_line`.
+pub fn findings(path: &Path, text: &str, matches: &[SecretMatch], first_line: usize) -> Vec<SecretFinding> {
+    let found_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    matches.iter()
+        .map(|found| SecretFinding {
+            path: os_path::encode(path),
+            line: (first_line + found.line - 1) as u64,
+            kind: found.kind,
+            preview: preview(&text[found.range.clone()]),
+            found_at,
+        })
+        .collect()
+}
+
+/// Each `-----BEGIN ... PRIVATE KEY-----` block, through its END line when
+/// there is one.
+fn private_keys(text: &str) -> Vec<SecretMatch> {
+    let mut matches = Vec::new();
+    let mut from = 0;
+    while let Some(begin) = text[from..].find("-----BEGIN ").map(|at| from + at) {
+        let header_end = text[begin + 11..].find("-----").map(|at| begin + 11 + at + 5);
+        let Some(header_end) = header_end else {
+            break;
+        };
+        if !text[begin..header_end].contains("PRIVATE KEY") {
+            from = header_end;
+            continue;
+        }
+        let end = text[header_end..].find("-----END ")
+            .and_then(|at| {
+                let footer = header_end + at + 9;
+                text[footer..].find("-----").map(|close| footer + close + 5)
+            })
+            .unwrap_or(header_end);
+        matches.push(SecretMatch { kind: SecretKind::PrivateKey, range: begin..end, line: line_at(text, begin) });
+        from = end;
+    }
+    matches
+}
+
+/// Runs of characters that can make up a key or token, with their offsets.
+/// `=` ends a run, so `key=value` splits; base64 padding is lost, which
+/// doesn't matter here.
+fn tokens(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let is_token_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '_' | '-');
+    text.split(move |c: char| !is_token_char(c))
+        .filter(|token| token.len() >= MIN_TOKEN_LENGTH)
+        .map(move |token| (token.as_ptr() as usize - text.as_ptr() as usize, token))
+}
+
+fn token_kind(text: &str, start: usize, token: &str) -> Option<SecretKind> {
+    if token.len() == 20
+        && AWS_KEY_PREFIXES.iter().any(|prefix| token.starts_with(prefix))
+        && token.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    {
+        return Some(SecretKind::AwsAccessKey);
+    }
+    if GITHUB_TOKEN_PREFIXES.iter().any(|prefix| token.starts_with(prefix)) {
+        return Some(SecretKind::GithubToken);
+    }
+    if SLACK_TOKEN_PREFIXES.iter().any(|prefix| token.starts_with(prefix)) {
+        return Some(SecretKind::SlackToken);
+    }
+    let assigned = text[..start].trim_end_matches([' ', '\t', '"', '\'']).ends_with(['=', ':']);
+    let mixed = token.chars().any(|c| c.is_ascii_digit()) && token.chars().any(|c| c.is_ascii_alphabetic());
+    (assigned && mixed && entropy(token) >= MIN_TOKEN_ENTROPY).then_some(SecretKind::HighEntropyToken)
+}
+
+/// Shannon entropy of `token`, in bits per character.
+fn entropy(token: &str) -> f64 {
+    let mut counts = [0usize; 256];
+    for byte in token.bytes() {
+        counts[byte as usize] += 1;
+    }
+    let length = token.len() as f64;
+    counts.iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn line_at(text: &str, at: usize) -> usize {
+    text[..at].bytes().filter(|&byte| byte == b'\n').count() + 1
+}
+
+/// The latest findings per file, persisted apart from the index.
+pub struct SecretFindings {
+    path: PathBuf,
+    findings: RwLock<BTreeMap<String, Vec<SecretFinding>>>,
+}
+
+impl SecretFindings {
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let findings = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse secret findings at {:?}, starting over: {}", path, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+
+        Self {
+            path,
+            findings: RwLock::new(findings),
+        }
+    }
+
+    /// Replaces what is known about `path` with `findings`.
+    pub fn record(&self, path: &Path, mut file_findings: Vec<SecretFinding>) {
+        let path = os_path::encode(path);
+        let mut findings = self.findings.write();
+        if file_findings.is_empty() {
+            if findings.remove(&path).is_none() {
+                return;
+            }
+        } else {
+            // Never log the secrets themselves
+            warn!("{} likely secret(s) found in {}", file_findings.len(), os_path::display(&path));
+            file_findings.truncate(MAX_FINDINGS_PER_FILE);
+            findings.insert(path, file_findings);
+        }
+        if let Err(e) = self.save(&findings) {
+            warn!("{}", e);
+        }
+    }
+
+    pub fn forget(&self, paths: &[PathBuf]) {
+        let mut findings = self.findings.write();
+        let before = findings.len();
+        for path in paths {
+            findings.remove(&os_path::encode(path));
+        }
+        if findings.len() != before {
+            if let Err(e) = self.save(&findings) {
+                warn!("{}", e);
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut findings = self.findings.write();
+        if findings.is_empty() {
+            return;
+        }
+        findings.clear();
+        if let Err(e) = self.save(&findings) {
+            warn!("{}", e);
+        }
+    }
+
+    /// Every finding, by path and then line.
+    pub fn report(&self) -> SecretReport {
+        SecretReport {
+            warning: SECRET_REPORT_WARNING.to_string(),
+            findings: self.findings.read().values().flatten().cloned().collect(),
+        }
+    }
+
+    fn save(&self, findings: &BTreeMap<String, Vec<SecretFinding>>) -> Result<(), String> {
+        let json = serde_json::to_string(findings)
+            .map_err(|e| format!("Failed to serialize secret findings: {}", e))?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write secret findings: {}", e))?;
+        // Which files hold credentials is sensitive in itself
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600))
+                .map_err(|e| format!("Failed to restrict secret findings: {}", e))?;
+        }
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to replace secret findings: {}", e))
+    }
+}
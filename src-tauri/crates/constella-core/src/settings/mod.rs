@@ -0,0 +1,292 @@
+use std::path::{Component, Path, PathBuf};
+use parking_lot::RwLock;
+use serde::{Serialize, Deserialize};
+use log::{info, warn};
+use crate::extract::Format;
+use crate::indexing::shards::ShardingSettings;
+use crate::indexing::shedding::LoadShedding;
+use crate::indexing::triage::TriageRules;
+use crate::labeling::ImageLabelingSettings;
+use crate::organize::OrganizeSettings;
+use crate::pii::PiiSettings;
+use crate::profiles::Profile;
+use crate::power::PowerPolicy;
+use crate::transcription::TranscriptionSettings;
+use crate::volumes::RootVolume;
+use crate::search::{FileTypeBoost, QueryRewrites, RankingWeights, StopwordSettings};
+use ts_rs::TS;
+
+/// Text files larger than this are indexed by name only unless configured otherwise.
+pub const DEFAULT_CONTENT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Size of each separately indexed piece of a text file too large to index whole.
+pub const DEFAULT_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Text files larger than this aren't split into chunks either.
+pub const DEFAULT_CHUNK_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Room the cache of generated previews may take up on disk.
+pub const DEFAULT_PREVIEW_CACHE_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Folders of installed dependencies and build output, indexed by name only
+/// unless configured otherwise.
+pub const DEFAULT_DEPENDENCY_FOLDERS: &[&str] = &[
+    "node_modules", "target", ".venv", "venv", "__pycache__", "build", "DerivedData", "Pods", ".gradle",
+];
+
+/// User-adjustable settings, persisted as JSON in the app data directory.
+/// Missing keys fall back to their defaults so older files keep loading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub power_policy: PowerPolicy,
+    /// Minutes without keyboard or mouse input before deferred jobs may run.
+    pub idle_threshold_minutes: u64,
+    /// Directories kept fresh by the watcher, restored on every start.
+    pub watched_roots: Vec<PathBuf>,
+    /// Directory the last full index run covered.
+    pub indexed_roots: Vec<PathBuf>,
+    /// Folders indexed ahead of everything else, in order.
+    pub priority_folders: Vec<PathBuf>,
+    pub indexing: IndexingConfig,
+    pub ranking: RankingWeights,
+    /// Remember which results get opened for a query and rank them higher.
+    pub learn_from_clicks: bool,
+    /// Score multipliers for results by extension or path pattern.
+    pub file_type_boosts: Vec<FileTypeBoost>,
+    /// Synonyms and rewrites applied to search queries.
+    pub query_rewrites: QueryRewrites,
+    pub stopwords: StopwordSettings,
+    /// How far back the "what's new" feed of changed files goes.
+    pub recent_changes_days: u32,
+    pub downloads_triage: TriageRules,
+    /// Locale date expressions in queries are read in, e.g. `en-GB`;
+    /// the system locale when unset.
+    pub date_locale: Option<String>,
+    /// Allow raw byte-pattern searches, which read whole files rather than
+    /// the index.
+    pub byte_search_enabled: bool,
+    /// Look for credentials in indexed text, keep them out of the index and
+    /// report the files they were found in.
+    pub secret_scanning_enabled: bool,
+    /// Keep a copy of small text files at each index pass so what changed
+    /// in them since can be shown as a diff.
+    pub diff_retention_enabled: bool,
+    /// Keep past versions of small text documents as they are modified.
+    pub versioning_enabled: bool,
+    /// Keep a signed hash manifest of the index files and check it on
+    /// startup.
+    pub index_integrity_manifest: bool,
+    pub pii_inventory: PiiSettings,
+    pub profiles: Vec<Profile>,
+    /// Name of the profile in use; everything is visible when unset.
+    pub active_profile: Option<String>,
+    pub transcription: TranscriptionSettings,
+    pub image_labeling: ImageLabelingSettings,
+    /// In portable mode, where the app's volume was mounted last time.
+    pub portable_root: Option<PathBuf>,
+    /// The volume each watched and indexed root is on, to find the roots
+    /// again when their drive letter or mount point changes.
+    pub root_volumes: Vec<RootVolume>,
+    /// Previews beyond this many bytes are evicted, least recently used
+    /// first.
+    pub preview_cache_max_bytes: u64,
+    /// Rules that move new and changed files into place, off by default.
+    pub organize: OrganizeSettings,
+    /// Features turned down once the index gets very large.
+    pub load_shedding: LoadShedding,
+    /// Splitting the index across several, for very large corpora.
+    pub sharding: ShardingSettings,
+    /// Folder the search index is kept in, e.g. on a bigger disk;
+    /// `search_index` under the data directory when unset. Changed by
+    /// `IndexManager::move_index`, which moves the index along with it.
+    pub index_location: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            power_policy: PowerPolicy::default(),
+            idle_threshold_minutes: 5,
+            watched_roots: Vec::new(),
+            indexed_roots: Vec::new(),
+            priority_folders: Vec::new(),
+            indexing: IndexingConfig::default(),
+            ranking: RankingWeights::default(),
+            learn_from_clicks: true,
+            file_type_boosts: Vec::new(),
+            query_rewrites: QueryRewrites::new(),
+            stopwords: StopwordSettings::default(),
+            recent_changes_days: 7,
+            downloads_triage: TriageRules::default(),
+            date_locale: None,
+            byte_search_enabled: false,
+            secret_scanning_enabled: false,
+            diff_retention_enabled: false,
+            versioning_enabled: false,
+            index_integrity_manifest: false,
+            pii_inventory: PiiSettings::default(),
+            profiles: Vec::new(),
+            active_profile: None,
+            transcription: TranscriptionSettings::default(),
+            image_labeling: ImageLabelingSettings::default(),
+            portable_root: None,
+            root_volumes: Vec::new(),
+            preview_cache_max_bytes: DEFAULT_PREVIEW_CACHE_MAX_BYTES,
+            organize: OrganizeSettings::default(),
+            load_shedding: LoadShedding::default(),
+            sharding: ShardingSettings::default(),
+            index_location: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Moves every configured folder under `old` to the same place under
+    /// `new`; returns how many changed.
+    pub fn rebase_paths(&mut self, old: &Path, new: &Path) -> usize {
+        let mut changed = 0;
+        let mut rebase = |path: &mut PathBuf| {
+            if let Ok(relative) = path.strip_prefix(old) {
+                *path = new.join(relative);
+                changed += 1;
+            }
+        };
+        self.watched_roots.iter_mut()
+            .chain(self.indexed_roots.iter_mut())
+            .chain(self.priority_folders.iter_mut())
+            .chain(self.indexing.gitignore_repos.iter_mut())
+            .chain(self.indexing.dependency_content_roots.iter_mut())
+            .chain(self.profiles.iter_mut().flat_map(|profile| profile.roots.iter_mut()))
+            .chain(self.root_volumes.iter_mut().map(|root| &mut root.path))
+            .chain(self.organize.rules.iter_mut().filter_map(|rule| rule.folder.as_mut()))
+            .for_each(&mut rebase);
+        changed
+    }
+}
+
+/// Which files get indexed, and how much of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct IndexingConfig {
+    /// Globs matched against full paths, e.g. `**/node_modules/**`. Matching
+    /// files are never indexed.
+    pub exclude: Vec<String>,
+    /// Text files larger than this are indexed by name only, or in chunks
+    /// when `chunk_large_files` is on.
+    #[ts(type = "number")]
+    pub content_max_file_size: u64,
+    /// Index the content of plain text files over `content_max_file_size`,
+    /// like big logs and CSVs, as a series of chunks.
+    pub chunk_large_files: bool,
+    /// Roughly how much text goes in each chunk; chunks end at a line break.
+    #[ts(type = "number")]
+    pub chunk_size: u64,
+    /// Files larger than this are indexed by name only even when chunked.
+    #[ts(type = "number")]
+    pub chunk_max_file_size: u64,
+    /// Recognize the text in screenshots so it can be searched.
+    pub ocr_screenshots: bool,
+    /// Git working trees whose root `.gitignore` is honored. Elsewhere
+    /// `.gitignore` files make no difference to what gets indexed.
+    pub gitignore_repos: Vec<PathBuf>,
+    /// Names of dependency and build folders. Files anywhere below one are
+    /// indexed by name only.
+    pub dependency_folders: Vec<String>,
+    /// Roots whose dependency folders get their content indexed anyway.
+    pub dependency_content_roots: Vec<PathBuf>,
+    /// Read the subtitle streams embedded in videos, which needs ffmpeg.
+    /// Subtitle files next to a video are always read.
+    pub embedded_subtitles: bool,
+}
+
+impl Default for IndexingConfig {
+    fn default() -> Self {
+        Self {
+            exclude: Vec::new(),
+            content_max_file_size: DEFAULT_CONTENT_MAX_FILE_SIZE,
+            chunk_large_files: false,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunk_max_file_size: DEFAULT_CHUNK_MAX_FILE_SIZE,
+            ocr_screenshots: true,
+            gitignore_repos: Vec::new(),
+            dependency_folders: DEFAULT_DEPENDENCY_FOLDERS.iter().map(|folder| folder.to_string()).collect(),
+            dependency_content_roots: Vec::new(),
+            embedded_subtitles: false,
+        }
+    }
+}
+
+impl IndexingConfig {
+    /// Whether the text file at `path`, `size` bytes long, is too large to
+    /// index whole but gets indexed in chunks.
+    pub fn is_chunked(&self, path: &Path, size: u64) -> bool {
+        self.chunk_large_files
+            && self.chunk_size > 0
+            && size > self.content_max_file_size
+            && size <= self.chunk_max_file_size
+            && Format::for_path(path) == Some(Format::PlainText)
+            && !self.in_dependency_folder(path)
+    }
+
+    /// Whether `path` is below a dependency folder whose content isn't indexed.
+    pub fn in_dependency_folder(&self, path: &Path) -> bool {
+        if self.dependency_content_roots.iter().any(|root| path.starts_with(root)) {
+            return false;
+        }
+        path.parent().is_some_and(|parent| {
+            parent.components().any(|component| match component {
+                Component::Normal(name) => self.dependency_folders.iter().any(|folder| name == folder.as_str()),
+                _ => false,
+            })
+        })
+    }
+}
+
+pub struct SettingsManager {
+    path: PathBuf,
+    settings: RwLock<Settings>,
+}
+
+impl SettingsManager {
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let settings = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse settings at {:?}, using defaults: {}", path, e);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        };
+
+        Self {
+            path,
+            settings: RwLock::new(settings),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.read().clone()
+    }
+
+    /// Applies `update_fn` to the settings and writes the result to disk.
+    pub fn update<F>(&self, update_fn: F) -> Result<Settings, String>
+    where
+        F: FnOnce(&mut Settings),
+    {
+        let mut settings = self.settings.write();
+        update_fn(&mut settings);
+
+        let json = serde_json::to_string_pretty(&*settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write settings: {}", e))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to replace settings: {}", e))?;
+
+        info!("Settings saved to {:?}", self.path);
+        Ok(settings.clone())
+    }
+}
@@ -1,7 +1,6 @@
 use serde::{Serialize, Deserialize};
 use std::time::{SystemTime, Duration};
 use std::collections::HashMap;
-use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexStats {
@@ -39,7 +38,7 @@ pub enum OperationType {
     Optimize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PerformanceMetrics {
     pub avg_indexing_speed: f32,  // files per second
     pub avg_query_time: Duration,
@@ -55,13 +54,19 @@ pub struct QueryMetric {
     pub results_count: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SystemMetrics {
     pub cpu_usage: Vec<(SystemTime, f32)>,
     pub memory_usage: Vec<(SystemTime, f32)>,
     pub io_operations: Vec<(SystemTime, u64)>,
 }
 
+impl Default for IndexStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl IndexStats {
     pub fn new() -> Self {
         Self {
@@ -0,0 +1,238 @@
+use similar::{ChangeTag, TextDiff};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Serialize, Deserialize};
+use log::{debug, warn};
+use ts_rs::TS;
+
+const SNAPSHOT_MAX_FILE_SIZE: u64 = 512 * 1024; // Only retain copies of small text files
+const SNAPSHOT_COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ContentDiff {
+    pub path: PathBuf,
+    pub changes: Vec<DiffChange>,
+    pub change_percentage: f32,
+    pub is_significant: bool,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct DiffChange {
+    pub operation: ChangeOperation,
+    pub content: String,
+    pub line_number: usize,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum ChangeOperation {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ContentDiff {
+    pub fn new(old_content: &str, new_content: &str, path: PathBuf) -> Self {
+        let diff = TextDiff::from_lines(old_content, new_content);
+        let mut changes = Vec::new();
+        let mut changed_lines = 0;
+        let total_lines = old_content.lines().count().max(new_content.lines().count());
+
+        for change in diff.iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Delete => {
+                    changes.push(DiffChange {
+                        operation: ChangeOperation::Removed,
+                        content: change.to_string(),
+                        line_number: change.old_index().unwrap_or_default() + 1,
+                    });
+                    changed_lines += 1;
+                }
+                ChangeTag::Insert => {
+                    changes.push(DiffChange {
+                        operation: ChangeOperation::Added,
+                        content: change.to_string(),
+                        line_number: change.new_index().unwrap_or_default() + 1,
+                    });
+                    changed_lines += 1;
+                }
+                ChangeTag::Equal => {}
+            }
+        }
+
+        let change_percentage = if total_lines > 0 {
+            (changed_lines as f32 / total_lines as f32) * 100.0
+        } else {
+            0.0
+        };
+        let is_significant = change_percentage > 5.0; // Consider changes significant if > 5% changed
+
+        Self {
+            path,
+            changes,
+            change_percentage,
+            is_significant,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SnapshotMeta {
+    path: PathBuf,
+    hash: String,
+    indexed_at: u64,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct FileDiffReport {
+    #[ts(type = "number")]
+    pub indexed_at: u64,
+    pub previous_hash: String,
+    pub current_hash: String,
+    pub diff: ContentDiff,
+}
+
+/// Keeps a compressed copy of each small text file as it looked at the last
+/// index pass, so changes made since then can be shown as a diff.
+pub struct SnapshotStore {
+    dir: PathBuf,
+    enabled: AtomicBool,
+    max_file_size: u64,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl AsRef<Path>, enabled: bool) -> std::io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            enabled: AtomicBool::new(enabled),
+            max_file_size: SNAPSHOT_MAX_FILE_SIZE,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn key(&self, path: &Path) -> String {
+        blake3::hash(path.to_string_lossy().as_bytes()).to_hex().to_string()
+    }
+
+    fn meta_path(&self, path: &Path) -> PathBuf {
+        self.dir.join(format!("{}.json", self.key(path)))
+    }
+
+    fn content_path(&self, path: &Path) -> PathBuf {
+        self.dir.join(format!("{}.zst", self.key(path)))
+    }
+
+    fn load_meta(&self, path: &Path) -> Option<SnapshotMeta> {
+        let json = std::fs::read_to_string(self.meta_path(path)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Records the current content of `path` if it is a small text file and
+    /// differs from the retained copy. Does nothing while retention is disabled.
+    pub fn record(&self, path: &Path, size: u64) -> Result<(), String> {
+        if !self.is_enabled() || size > self.max_file_size {
+            return Ok(());
+        }
+        let is_text = mime_guess::from_path(path)
+            .first()
+            .map(|m| m.type_() == mime_guess::mime::TEXT)
+            .unwrap_or(false);
+        if !is_text {
+            return Ok(());
+        }
+
+        let content = std::fs::read(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let hash = blake3::hash(&content).to_hex().to_string();
+
+        if let Some(meta) = self.load_meta(path) {
+            if meta.hash == hash {
+                return Ok(());
+            }
+        }
+
+        let compressed = zstd::encode_all(content.as_slice(), SNAPSHOT_COMPRESSION_LEVEL)
+            .map_err(|e| format!("Failed to compress snapshot: {}", e))?;
+        std::fs::write(self.content_path(path), compressed)
+            .map_err(|e| format!("Failed to write snapshot: {}", e))?;
+
+        let meta = SnapshotMeta {
+            path: path.to_path_buf(),
+            hash,
+            indexed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        let json = serde_json::to_string(&meta)
+            .map_err(|e| format!("Failed to serialize snapshot metadata: {}", e))?;
+        std::fs::write(self.meta_path(path), json)
+            .map_err(|e| format!("Failed to write snapshot metadata: {}", e))?;
+
+        debug!("Recorded snapshot for {}", path.display());
+        Ok(())
+    }
+
+    /// Diffs the copy retained at the last index pass against the file on disk.
+    pub fn diff(&self, path: &Path) -> Result<FileDiffReport, String> {
+        let meta = self.load_meta(path)
+            .ok_or_else(|| format!("No retained version for {}", path.display()))?;
+
+        let compressed = std::fs::read(self.content_path(path))
+            .map_err(|e| format!("Failed to read snapshot: {}", e))?;
+        let previous = zstd::decode_all(compressed.as_slice())
+            .map_err(|e| format!("Failed to decompress snapshot: {}", e))?;
+        let current = std::fs::read(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        Ok(FileDiffReport {
+            indexed_at: meta.indexed_at,
+            previous_hash: meta.hash,
+            current_hash: blake3::hash(&current).to_hex().to_string(),
+            diff: ContentDiff::new(
+                &String::from_utf8_lossy(&previous),
+                &String::from_utf8_lossy(&current),
+                path.to_path_buf(),
+            ),
+        })
+    }
+
+    /// Keeps the copy retained for a file that moved from `old` to `new`.
+    pub fn rename(&self, old: &Path, new: &Path) -> Result<(), String> {
+        let Some(mut meta) = self.load_meta(old) else {
+            return Ok(());
+        };
+        std::fs::rename(self.content_path(old), self.content_path(new))
+            .map_err(|e| format!("Failed to move snapshot: {}", e))?;
+        meta.path = new.to_path_buf();
+        let json = serde_json::to_string(&meta)
+            .map_err(|e| format!("Failed to serialize snapshot metadata: {}", e))?;
+        std::fs::write(self.meta_path(new), json)
+            .and_then(|_| std::fs::remove_file(self.meta_path(old)))
+            .map_err(|e| format!("Failed to move snapshot metadata: {}", e))
+    }
+
+    pub fn remove(&self, path: &Path) {
+        for file in [self.meta_path(path), self.content_path(path)] {
+            if let Err(e) = std::fs::remove_file(&file) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to remove snapshot {:?}: {}", file, e);
+                }
+            }
+        }
+    }
+}
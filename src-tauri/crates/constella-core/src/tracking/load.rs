@@ -0,0 +1,136 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use sysinfo::{CpuExt, ProcessExt, System, SystemExt};
+use log::debug;
+use ts_rs::TS;
+
+pub const LOAD_SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+// Combined process read+write throughput treated as a saturated disk
+const IO_SATURATION_BYTES_PER_SEC: f32 = 200.0 * 1024.0 * 1024.0;
+const HEAVY_LOAD_DELAY: Duration = Duration::from_millis(20);
+const MODERATE_LOAD_DELAY: Duration = Duration::from_millis(2);
+
+#[derive(Debug, Clone, Copy, Default, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct SystemResources {
+    pub cpu_usage: f32,
+    pub memory_usage: f32,
+    pub io_usage: f32,
+}
+
+impl SystemResources {
+    pub fn is_under_heavy_load(&self) -> bool {
+        self.cpu_usage > 0.8 || self.memory_usage > 0.9 || self.io_usage > 0.7
+    }
+
+    /// Single 0.0-1.0 figure for the most contended resource.
+    pub fn load_factor(&self) -> f32 {
+        self.cpu_usage.max(self.memory_usage).max(self.io_usage).clamp(0.0, 1.0)
+    }
+}
+
+/// Periodically samples CPU, memory and disk IO so background work can back
+/// off while the machine is busy with something the user cares about.
+#[derive(Debug)]
+pub struct LoadMonitor {
+    system: Mutex<System>,
+    last_sample: Mutex<Instant>,
+    current: RwLock<SystemResources>,
+}
+
+impl Default for LoadMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoadMonitor {
+    pub fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_cpu();
+        Self {
+            system: Mutex::new(system),
+            last_sample: Mutex::new(Instant::now()),
+            current: RwLock::new(SystemResources::default()),
+        }
+    }
+
+    pub fn current(&self) -> SystemResources {
+        *self.current.read()
+    }
+
+    pub fn is_under_heavy_load(&self) -> bool {
+        self.current().is_under_heavy_load()
+    }
+
+    /// Refreshes the resource figures. Blocking; call off the async executor.
+    pub fn sample(&self) {
+        let mut system = self.system.lock();
+        system.refresh_cpu();
+        system.refresh_memory();
+        system.refresh_processes();
+
+        let mut last_sample = self.last_sample.lock();
+        let elapsed = last_sample.elapsed().as_secs_f32().max(0.001);
+        *last_sample = Instant::now();
+
+        let io_bytes: u64 = system.processes()
+            .values()
+            .map(|process| {
+                let usage = process.disk_usage();
+                usage.read_bytes + usage.written_bytes
+            })
+            .sum();
+
+        let total_memory = system.total_memory();
+        let resources = SystemResources {
+            cpu_usage: system.global_cpu_info().cpu_usage() / 100.0,
+            memory_usage: if total_memory > 0 {
+                system.used_memory() as f32 / total_memory as f32
+            } else {
+                0.0
+            },
+            io_usage: (io_bytes as f32 / elapsed / IO_SATURATION_BYTES_PER_SEC).min(1.0),
+        };
+
+        debug!("System load sampled: {:?}", resources);
+        *self.current.write() = resources;
+    }
+
+    /// Starts sampling on a timer for the life of the process.
+    pub fn spawn_sampler(self: &Arc<Self>) {
+        let monitor = self.clone();
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(LOAD_SAMPLE_INTERVAL);
+            loop {
+                timer.tick().await;
+                let monitor = monitor.clone();
+                let _ = tokio::task::spawn_blocking(move || monitor.sample()).await;
+            }
+        });
+    }
+
+    /// Shrinks a batch size in proportion to current load, down to an eighth.
+    pub fn scale_batch(&self, base: usize) -> usize {
+        let resources = self.current();
+        if resources.is_under_heavy_load() {
+            return (base / 8).max(1);
+        }
+        let scale = 1.0 - resources.load_factor() * 0.5;
+        ((base as f32 * scale) as usize).max(1)
+    }
+
+    /// Pause to insert between background work items.
+    pub fn throttle_delay(&self) -> Duration {
+        let resources = self.current();
+        if resources.is_under_heavy_load() {
+            HEAVY_LOAD_DELAY
+        } else if resources.load_factor() > 0.6 {
+            MODERATE_LOAD_DELAY
+        } else {
+            Duration::ZERO
+        }
+    }
+}
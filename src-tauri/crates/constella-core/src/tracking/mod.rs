@@ -0,0 +1,314 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::collections::HashMap;
+use std::time::{SystemTime, Duration};
+use tokio::sync::RwLock;
+use blake3::Hash;
+use serde::{Serialize, Deserialize};
+
+pub mod diff;
+pub mod load;
+
+use load::LoadMonitor;
+use crate::file_system::{FileMetadata, FileSystemProvider};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileState {
+    size: u64,
+    modified: SystemTime,
+    hash: Option<Hash>,  // Content hash for important files
+    last_indexed: SystemTime,
+    last_checked: SystemTime,
+    change_frequency: Duration,  // Adaptive tracking of how often this file changes
+    importance_score: f32,  // Dynamic score based on file usage and changes
+}
+
+/// Something the user did with a file through the app; each nudges the
+/// file's importance so the tracker re-checks it more eagerly.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub enum UserAction {
+    Open,
+    Preview,
+    Star,
+    TagEdit,
+}
+
+impl UserAction {
+    fn importance_boost(self) -> f32 {
+        match self {
+            UserAction::Open => 0.15,
+            UserAction::Preview => 0.05,
+            UserAction::Star => 0.3,
+            UserAction::TagEdit => 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct ImportantFile {
+    pub path: PathBuf,
+    pub importance_score: f32,
+}
+
+impl FileState {
+    fn from_metadata(metadata: &FileMetadata, now: SystemTime) -> Self {
+        Self {
+            size: metadata.len,
+            modified: metadata.modified.unwrap_or(now),
+            hash: None,
+            last_indexed: now,
+            last_checked: now,
+            change_frequency: Duration::from_secs(3600), // Start with 1 hour
+            importance_score: 0.5, // Start with medium importance
+        }
+    }
+}
+
+pub struct ChangeTracker {
+    states: RwLock<HashMap<PathBuf, FileState>>,
+    index_frequency: RwLock<AdaptiveFrequency>,
+    fs: Arc<dyn FileSystemProvider>,
+}
+
+#[derive(Debug)]
+struct AdaptiveFrequency {
+    load: Arc<LoadMonitor>,
+}
+
+impl ChangeTracker {
+    pub fn new(load: Arc<LoadMonitor>, fs: Arc<dyn FileSystemProvider>) -> Self {
+        Self {
+            states: RwLock::new(HashMap::new()),
+            index_frequency: RwLock::new(AdaptiveFrequency::new(load)),
+            fs,
+        }
+    }
+
+    /// Copies out the per-file state so it can be persisted.
+    pub async fn export_states(&self) -> HashMap<PathBuf, FileState> {
+        self.states.read().await.clone()
+    }
+
+    /// Replaces the in-memory per-file state with previously persisted state.
+    pub async fn import_states(&self, states: HashMap<PathBuf, FileState>) {
+        *self.states.write().await = states;
+    }
+
+    pub async fn should_reindex(&self, path: &PathBuf, metadata: &FileMetadata) -> bool {
+        let states = self.states.write().await;
+        let now = SystemTime::now();
+
+        if let Some(state) = states.get(path) {
+            // Quick check for obvious changes
+            if metadata.len != state.size || metadata.modified != Some(state.modified) {
+                return true;
+            }
+
+            // Check if enough time has passed based on file's change frequency
+            if now.duration_since(state.last_checked).unwrap() < state.change_frequency {
+                return false;
+            }
+
+            // For important files, verify content hash
+            if state.importance_score > 0.8 {
+                if let Some(current_hash) = self.compute_hash(path).await {
+                    if let Some(stored_hash) = state.hash {
+                        return current_hash != stored_hash;
+                    }
+                }
+            }
+
+            // Adaptive reindexing based on system load and file importance
+            let freq = self.index_frequency.read().await;
+            if freq.should_skip_indexing(state.importance_score) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub async fn update_state(&self, path: &PathBuf, metadata: &FileMetadata, indexed: bool) {
+        let mut states = self.states.write().await;
+        let now = SystemTime::now();
+
+        let seen_before = states.contains_key(path);
+        let state = states.entry(path.clone())
+            .or_insert_with(|| FileState::from_metadata(metadata, now));
+
+        if indexed {
+            // Update state after indexing
+            state.size = metadata.len;
+            state.modified = metadata.modified.unwrap_or(now);
+            state.last_indexed = now;
+            
+            // Compute hash for important files
+            if state.importance_score > 0.8 {
+                state.hash = self.compute_hash(path).await;
+            }
+
+            // Adapt change frequency based on actual changes; a first
+            // indexing says nothing about how often the file changes
+            if seen_before {
+                self.adapt_change_frequency(state).await;
+            }
+        }
+
+        state.last_checked = now;
+    }
+
+    /// Raises the importance of `path` in response to a user action and
+    /// returns the new score.
+    pub async fn record_user_action(&self, path: &Path, action: UserAction) -> Result<f32, String> {
+        let metadata = self.fs.metadata(path)
+            .map_err(|e| format!("Failed to get metadata for {}: {}", path.display(), e))?;
+        let mut states = self.states.write().await;
+        let state = states.entry(path.to_path_buf())
+            .or_insert_with(|| FileState::from_metadata(&metadata, SystemTime::now()));
+
+        state.importance_score = (state.importance_score + action.importance_boost()).min(1.0);
+        Ok(state.importance_score)
+    }
+
+    /// Moves the state of files under `old` to the same place under `new`.
+    pub async fn rebase(&self, old: &Path, new: &Path) {
+        let mut states = self.states.write().await;
+        let moved: Vec<PathBuf> = states.keys()
+            .filter(|path| path.starts_with(old))
+            .cloned()
+            .collect();
+        for path in moved {
+            if let (Some(state), Ok(rest)) = (states.remove(&path), path.strip_prefix(old)) {
+                states.insert(new.join(rest), state);
+            }
+        }
+    }
+
+    /// Whether the file at `new` is the one last seen at `old`: same size
+    /// and modification time, and same content when a hash is on record.
+    pub async fn same_file(&self, old: &Path, new: &Path, metadata: &FileMetadata) -> bool {
+        let Some(state) = self.states.read().await.get(old).cloned() else {
+            return false;
+        };
+        if metadata.len != state.size || metadata.modified != Some(state.modified) {
+            return false;
+        }
+        match state.hash {
+            Some(hash) => self.compute_hash(new).await == Some(hash),
+            None => true,
+        }
+    }
+
+    /// The importance score of `path`, 0 when it isn't tracked.
+    pub async fn importance_score(&self, path: &Path) -> f32 {
+        self.states.read().await.get(path).map_or(0.0, |state| state.importance_score)
+    }
+
+    /// Returns the `limit` files with the highest importance scores.
+    pub async fn top_important(&self, limit: usize) -> Vec<ImportantFile> {
+        let states = self.states.read().await;
+        let mut files: Vec<ImportantFile> = states.iter()
+            .map(|(path, state)| ImportantFile {
+                path: path.clone(),
+                importance_score: state.importance_score,
+            })
+            .collect();
+        files.sort_by(|a, b| b.importance_score.total_cmp(&a.importance_score));
+        files.truncate(limit);
+        files
+    }
+
+    /// Drops what is known about `path` so the next check treats it as new.
+    pub async fn forget(&self, path: &PathBuf) {
+        self.states.write().await.remove(path);
+    }
+
+    /// Paths that have a content hash on record.
+    pub async fn hashed_paths(&self) -> Vec<PathBuf> {
+        let states = self.states.read().await;
+        states.iter()
+            .filter(|(_, state)| state.hash.is_some())
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Returns true when the file's current content no longer matches its stored hash.
+    pub async fn hash_mismatch(&self, path: &PathBuf) -> bool {
+        let stored = match self.states.read().await.get(path).and_then(|state| state.hash) {
+            Some(hash) => hash,
+            None => return false,
+        };
+        match self.compute_hash(path).await {
+            Some(current) => current != stored,
+            None => true,
+        }
+    }
+
+    /// The `limit` paths that have gone longest without being checked.
+    pub async fn coldest_paths(&self, limit: usize) -> Vec<PathBuf> {
+        let states = self.states.read().await;
+        let mut paths: Vec<(&PathBuf, SystemTime)> = states.iter()
+            .map(|(path, state)| (path, state.last_checked))
+            .collect();
+        paths.sort_by_key(|(_, last_checked)| *last_checked);
+        paths.into_iter()
+            .take(limit)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    async fn adapt_change_frequency(&self, state: &mut FileState) {
+        let time_since_last = SystemTime::now()
+            .duration_since(state.last_indexed)
+            .unwrap_or(Duration::from_secs(0));
+
+        // If file changed more frequently than expected
+        if time_since_last < state.change_frequency {
+            state.change_frequency = time_since_last + (time_since_last / 2);
+            state.importance_score = (state.importance_score + 0.1).min(1.0);
+        } else {
+            // File changes less frequently than expected
+            state.change_frequency = state.change_frequency + (state.change_frequency / 2);
+            state.importance_score = (state.importance_score - 0.05).max(0.0);
+        }
+    }
+
+    async fn compute_hash(&self, path: &Path) -> Option<Hash> {
+        let fs = self.fs.clone();
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || fs.read(&path))
+            .await
+            .ok()?
+            .ok()
+            .map(|content| blake3::hash(&content))
+    }
+}
+
+impl AdaptiveFrequency {
+    fn new(load: Arc<LoadMonitor>) -> Self {
+        Self { load }
+    }
+
+    fn should_skip_indexing(&self, importance: f32) -> bool {
+        let resources = self.load.current();
+
+        // Skip indexing if system is under heavy load
+        if resources.is_under_heavy_load() {
+            return importance < 0.9; // Only index critical files under heavy load
+        }
+
+        // Adaptive indexing based on system resources and file importance
+        let threshold = self.calculate_threshold(resources.load_factor());
+        importance < threshold
+    }
+
+    fn calculate_threshold(&self, current_load: f32) -> f32 {
+        // Adjust threshold based on system load
+        let base_threshold = 0.2;
+        base_threshold + (current_load * 0.6)
+    }
+}
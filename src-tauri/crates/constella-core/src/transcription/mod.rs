@@ -0,0 +1,110 @@
+//! Speech to text for audio notes and voice memos. The default transcriber
+//! runs the whisper.cpp command-line program against a local model, so no
+//! audio leaves the machine. Embedders can plug in their own through
+//! `IndexOptions::transcriber`.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use crate::extract::subtitles::{parse_timestamp, Cue};
+use crate::settings::SettingsManager;
+
+/// Turns the speech in an audio file into timed lines.
+pub trait Transcriber: Send + Sync {
+    fn transcribe(&self, audio: &Path) -> Result<Vec<Cue>, String>;
+}
+
+/// Which audio gets transcribed, and with what.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct TranscriptionSettings {
+    /// Off by default: transcription is slow and needs a downloaded model.
+    pub enabled: bool,
+    /// The whisper.cpp program, looked up on the `PATH` unless absolute.
+    pub executable: PathBuf,
+    /// A whisper.cpp model file, e.g. `ggml-base.en.bin`.
+    pub model: Option<PathBuf>,
+    /// Larger audio files are indexed by name only.
+    #[ts(type = "number")]
+    pub max_file_size: u64,
+    /// Lowercased, without the dot.
+    pub extensions: Vec<String>,
+}
+
+impl Default for TranscriptionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            executable: PathBuf::from("whisper-cli"),
+            model: None,
+            max_file_size: 25 * 1024 * 1024,
+            extensions: ["wav", "mp3", "m4a", "ogg", "opus", "flac"].iter().map(|extension| extension.to_string()).collect(),
+        }
+    }
+}
+
+impl TranscriptionSettings {
+    pub fn is_audio(&self, path: &Path) -> bool {
+        path.extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .is_some_and(|extension| self.extensions.contains(&extension))
+    }
+}
+
+/// Runs whisper.cpp as configured in settings.
+pub struct WhisperCpp {
+    settings: Arc<SettingsManager>,
+}
+
+impl WhisperCpp {
+    pub fn new(settings: Arc<SettingsManager>) -> Self {
+        Self { settings }
+    }
+}
+
+impl Transcriber for WhisperCpp {
+    fn transcribe(&self, audio: &Path) -> Result<Vec<Cue>, String> {
+        let settings = self.settings.get().transcription;
+        let model = settings.model
+            .ok_or_else(|| "No whisper.cpp model is configured for transcription".to_string())?;
+        let output = Command::new(&settings.executable)
+            .arg("-m")
+            .arg(&model)
+            .arg("-f")
+            .arg(audio)
+            .arg("-np")
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", settings.executable.display(), e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to transcribe {}: {}",
+                audio.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(parse_whisper_output(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// Cues from whisper.cpp's console output, lines like
+/// `[00:00:01.000 --> 00:00:04.000]  Hello there`.
+pub fn parse_whisper_output(output: &str) -> Vec<Cue> {
+    output.lines()
+        .filter_map(|line| {
+            let (timing, text) = line.trim().strip_prefix('[')?.split_once(']')?;
+            let (start, end) = timing.split_once("-->")?;
+            let text = text.trim();
+            if text.is_empty() {
+                return None;
+            }
+            Some(Cue {
+                start_ms: parse_timestamp(start.trim())?,
+                end_ms: parse_timestamp(end.trim())?,
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,131 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+
+// Placeholder for utils module
+pub struct Utils {
+    // Add utils state here
+}
+
+impl Default for Utils {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Utils {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// The app's bundle identifier, which names its folder under the
+/// platform data directory.
+pub const APP_IDENTIFIER: &str = "com.constella.search";
+
+/// Directory holding the index, settings and state when no other location
+/// is configured: the one the app's path resolver gives, for processes
+/// without an app handle such as the background service.
+pub fn default_data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|data_dir| resolve_data_dir(data_dir.join(APP_IDENTIFIER)))
+}
+
+/// What earlier versions kept straight in the platform data directory
+/// and is moved into the app's own. Only names no other app would use
+/// are moved; state under generic names there is rebuilt instead.
+const LEGACY_ENTRIES: &[&str] = &[
+    "search_index",
+    "settings.json",
+    "learning.json",
+    "zero_results.json",
+    "recent_changes.json",
+    "path_remaps.json",
+    "index_fingerprint",
+    "index_format",
+];
+
+/// The data directory to use given `app_dir`, the app's own. Earlier
+/// versions kept everything straight in the platform data directory; that
+/// is moved into `app_dir` once.
+pub fn resolve_data_dir(app_dir: PathBuf) -> PathBuf {
+    match dirs::data_dir() {
+        Some(legacy_root) => migrate_legacy_data(app_dir, &legacy_root),
+        None => app_dir,
+    }
+}
+
+/// Moves an index earlier versions left in `legacy_root`, with its
+/// siblings, into `app_dir` unless that already has data of its own.
+/// Always returns `app_dir`; `legacy_root` is never used as the data
+/// directory.
+pub fn migrate_legacy_data(app_dir: PathBuf, legacy_root: &Path) -> PathBuf {
+    let has_data = app_dir.join("settings.json").exists() || app_dir.join("search_index").exists();
+    if has_data || legacy_root == app_dir || !legacy_root.join("search_index").join("meta.json").exists() {
+        return app_dir;
+    }
+    if let Err(e) = std::fs::create_dir_all(&app_dir) {
+        warn!("Failed to create {:?} to move the old index into: {}", app_dir, e);
+        return app_dir;
+    }
+    info!("Moving data earlier versions kept in {:?} to {:?}", legacy_root, app_dir);
+    for name in LEGACY_ENTRIES {
+        let from = legacy_root.join(name);
+        if !from.exists() {
+            continue;
+        }
+        if let Err(e) = std::fs::rename(&from, app_dir.join(name)) {
+            warn!("Failed to move {:?} into the data directory: {}", from, e);
+        }
+    }
+    app_dir
+}
+
+/// Creates `path`, which must not exist yet, so only the current user can
+/// read it: mode 0600 on Unix, an ACL granting just that user on Windows.
+/// For files holding tokens other local users mustn't see.
+pub fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(path)?;
+    #[cfg(windows)]
+    if let Err(e) = restrict_to_current_user(path) {
+        drop(file);
+        let _ = std::fs::remove_file(path);
+        return Err(e);
+    }
+    Ok(file)
+}
+
+/// Drops inherited access to `path` and grants only the current user.
+#[cfg(windows)]
+fn restrict_to_current_user(path: &Path) -> io::Result<()> {
+    let user = std::env::var("USERNAME")
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "USERNAME isn't set"))?;
+    let output = std::process::Command::new("icacls")
+        .arg(path)
+        .args(["/inheritance:r", "/grant:r"])
+        .arg(format!("{}:F", user))
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("icacls failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+        ));
+    }
+    Ok(())
+}
+
+/// A hex token from the OS random number generator, for authenticating
+/// local clients.
+pub fn random_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| format!("Failed to generate a token: {}", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
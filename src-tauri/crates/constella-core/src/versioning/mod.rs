@@ -0,0 +1,279 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use log::{debug, info, warn};
+use ts_rs::TS;
+use crate::watcher::ChangeType;
+
+const MAX_VERSIONED_FILE_SIZE: u64 = 256 * 1024; // Only shadow small text documents
+const MAX_VERSIONS_PER_FILE: usize = 20;
+const MAX_VERSION_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60); // 30 days
+const COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../src/lib/bindings/")]
+pub struct VersionInfo {
+    #[ts(type = "number")]
+    pub id: u64,
+    #[ts(type = "number")]
+    pub created_at: u64,
+    #[ts(type = "number")]
+    pub size: u64,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VersionEntry {
+    info: VersionInfo,
+    /// Full versions are compressed standalone; the rest are compressed using
+    /// the previous version's content as a zstd dictionary.
+    is_full: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct VersionManifest {
+    path: PathBuf,
+    next_id: u64,
+    versions: Vec<VersionEntry>,
+}
+
+/// Opt-in shadow copies of small text documents, taken whenever the watcher
+/// reports a modification.
+pub struct VersionStore {
+    dir: PathBuf,
+    enabled: AtomicBool,
+    // Serializes manifest read-modify-write cycles
+    lock: Mutex<()>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl VersionStore {
+    pub fn new(dir: impl AsRef<Path>, enabled: bool) -> std::io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            enabled: AtomicBool::new(enabled),
+            lock: Mutex::new(()),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        info!("File versioning {}", if enabled { "enabled" } else { "disabled" });
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn file_dir(&self, path: &Path) -> PathBuf {
+        let key = blake3::hash(path.to_string_lossy().as_bytes()).to_hex();
+        self.dir.join(key.as_str())
+    }
+
+    fn load_manifest(&self, path: &Path) -> VersionManifest {
+        let manifest_path = self.file_dir(path).join("manifest.json");
+        std::fs::read_to_string(manifest_path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_else(|| VersionManifest {
+                path: path.to_path_buf(),
+                ..Default::default()
+            })
+    }
+
+    fn save_manifest(&self, manifest: &VersionManifest) -> Result<(), String> {
+        let dir = self.file_dir(&manifest.path);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create version directory: {}", e))?;
+        let json = serde_json::to_string_pretty(manifest)
+            .map_err(|e| format!("Failed to serialize version manifest: {}", e))?;
+        std::fs::write(dir.join("manifest.json"), json)
+            .map_err(|e| format!("Failed to write version manifest: {}", e))
+    }
+
+    fn blob_path(&self, path: &Path, id: u64) -> PathBuf {
+        self.file_dir(path).join(format!("{}.zst", id))
+    }
+
+    fn is_versionable(path: &Path, size: u64) -> bool {
+        size <= MAX_VERSIONED_FILE_SIZE && mime_guess::from_path(path)
+            .first()
+            .map(|m| m.type_() == mime_guess::mime::TEXT)
+            .unwrap_or(false)
+    }
+
+    /// Reconstructs the content of every version up to and including `index`.
+    fn reconstruct(&self, manifest: &VersionManifest, index: usize) -> Result<Vec<u8>, String> {
+        let start = manifest.versions[..=index]
+            .iter()
+            .rposition(|entry| entry.is_full)
+            .ok_or_else(|| "Version chain has no full version".to_string())?;
+
+        let mut content: Vec<u8> = Vec::new();
+        for entry in &manifest.versions[start..=index] {
+            let blob = std::fs::read(self.blob_path(&manifest.path, entry.info.id))
+                .map_err(|e| format!("Failed to read version {}: {}", entry.info.id, e))?;
+            let mut decompressor = if entry.is_full {
+                zstd::bulk::Decompressor::new()
+            } else {
+                zstd::bulk::Decompressor::with_dictionary(&content)
+            }
+            .map_err(|e| format!("Failed to create decompressor: {}", e))?;
+            content = decompressor.decompress(&blob, entry.info.size as usize)
+                .map_err(|e| format!("Failed to decompress version {}: {}", entry.info.id, e))?;
+        }
+        Ok(content)
+    }
+
+    fn write_blob(&self, path: &Path, id: u64, content: &[u8], base: Option<&[u8]>) -> Result<(), String> {
+        let mut compressor = match base {
+            Some(base) => zstd::bulk::Compressor::with_dictionary(COMPRESSION_LEVEL, base),
+            None => zstd::bulk::Compressor::new(COMPRESSION_LEVEL),
+        }
+        .map_err(|e| format!("Failed to create compressor: {}", e))?;
+        let blob = compressor.compress(content)
+            .map_err(|e| format!("Failed to compress version: {}", e))?;
+        std::fs::write(self.blob_path(path, id), blob)
+            .map_err(|e| format!("Failed to write version: {}", e))
+    }
+
+    /// Snapshots every file `changes` created or modified, on a blocking
+    /// thread so reading and compressing them doesn't hold up the runtime.
+    pub async fn snapshot_changes(self: &Arc<Self>, changes: &[(PathBuf, ChangeType)]) {
+        if !self.is_enabled() {
+            return;
+        }
+        let paths: Vec<PathBuf> = changes.iter()
+            .filter(|(_, change)| !matches!(change, ChangeType::Deleted))
+            .map(|(path, _)| path.clone())
+            .collect();
+        let store = self.clone();
+        let versioned = tokio::task::spawn_blocking(move || {
+            for path in paths {
+                if let Err(e) = store.snapshot(&path) {
+                    warn!("Failed to version {:?}: {}", path, e);
+                }
+            }
+        }).await;
+        if let Err(e) = versioned {
+            warn!("Failed to version changed files: {}", e);
+        }
+    }
+
+    /// Takes a snapshot of `path` if versioning is enabled and its content
+    /// differs from the most recent version.
+    pub fn snapshot(&self, path: &Path) -> Result<Option<VersionInfo>, String> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => return Ok(None),
+        };
+        if !Self::is_versionable(path, metadata.len()) {
+            return Ok(None);
+        }
+
+        let content = std::fs::read(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let hash = blake3::hash(&content).to_hex().to_string();
+
+        let _guard = self.lock.lock();
+        let mut manifest = self.load_manifest(path);
+        if manifest.versions.last().map(|v| v.info.hash == hash).unwrap_or(false) {
+            return Ok(None);
+        }
+
+        let previous = match manifest.versions.len() {
+            0 => None,
+            len => Some(self.reconstruct(&manifest, len - 1)?),
+        };
+
+        std::fs::create_dir_all(self.file_dir(path))
+            .map_err(|e| format!("Failed to create version directory: {}", e))?;
+        let id = manifest.next_id;
+        self.write_blob(path, id, &content, previous.as_deref())?;
+
+        let info = VersionInfo {
+            id,
+            created_at: now_secs(),
+            size: content.len() as u64,
+            hash,
+        };
+        manifest.next_id += 1;
+        manifest.versions.push(VersionEntry {
+            info: info.clone(),
+            is_full: previous.is_none(),
+        });
+
+        self.apply_retention(&mut manifest)?;
+        self.save_manifest(&manifest)?;
+
+        debug!("Stored version {} of {}", id, path.display());
+        Ok(Some(info))
+    }
+
+    /// Drops versions beyond the count and age limits, re-basing the oldest
+    /// surviving version as a full copy so the delta chain stays decodable.
+    fn apply_retention(&self, manifest: &mut VersionManifest) -> Result<(), String> {
+        let cutoff = now_secs().saturating_sub(MAX_VERSION_AGE.as_secs());
+        let mut expired = manifest.versions.len().saturating_sub(MAX_VERSIONS_PER_FILE);
+        while expired + 1 < manifest.versions.len() && manifest.versions[expired].info.created_at < cutoff {
+            expired += 1;
+        }
+        if expired == 0 {
+            return Ok(());
+        }
+
+        if !manifest.versions[expired].is_full {
+            let content = self.reconstruct(manifest, expired)?;
+            let id = manifest.versions[expired].info.id;
+            self.write_blob(&manifest.path, id, &content, None)?;
+            manifest.versions[expired].is_full = true;
+        }
+
+        for entry in manifest.versions.drain(..expired) {
+            if let Err(e) = std::fs::remove_file(self.blob_path(&manifest.path, entry.info.id)) {
+                warn!("Failed to remove expired version {}: {}", entry.info.id, e);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn list_versions(&self, path: &Path) -> Vec<VersionInfo> {
+        let _guard = self.lock.lock();
+        let manifest = self.load_manifest(path);
+        manifest.versions.into_iter().rev().map(|entry| entry.info).collect()
+    }
+
+    /// Overwrites `path` with version `id`. The current content is versioned
+    /// first so the restore itself can be undone.
+    pub fn restore_version(&self, path: &Path, id: u64) -> Result<(), String> {
+        if let Err(e) = self.snapshot(path) {
+            warn!("Failed to snapshot {} before restore: {}", path.display(), e);
+        }
+
+        let _guard = self.lock.lock();
+        let manifest = self.load_manifest(path);
+        let index = manifest.versions.iter()
+            .position(|entry| entry.info.id == id)
+            .ok_or_else(|| format!("Version {} not found for {}", id, path.display()))?;
+        let content = self.reconstruct(&manifest, index)?;
+
+        std::fs::write(path, content)
+            .map_err(|e| format!("Failed to restore {}: {}", path.display(), e))?;
+        info!("Restored {} to version {}", path.display(), id);
+        Ok(())
+    }
+}
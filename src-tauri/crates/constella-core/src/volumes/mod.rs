@@ -0,0 +1,173 @@
+//! Which volume each indexed root is on, by an identifier that survives a
+//! drive getting a different letter or mount point: the volume GUID on
+//! Windows and the filesystem UUID elsewhere. Roots are remembered as that
+//! identifier plus a path within the volume, and resolved against where
+//! the volume is mounted now.
+
+use std::path::{Path, PathBuf};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// A volume and where it is mounted right now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountedVolume {
+    pub id: String,
+    pub mount_point: PathBuf,
+}
+
+/// Where an indexed root lives, by volume rather than absolute path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RootVolume {
+    /// The root's path when last seen.
+    pub path: PathBuf,
+    pub volume_id: String,
+    /// The root's path within the volume.
+    pub relative: PathBuf,
+}
+
+impl RootVolume {
+    /// Where the root is, given the volumes mounted now; `None` while its
+    /// volume isn't mounted.
+    pub fn resolve(&self, mounted: &[MountedVolume]) -> Option<PathBuf> {
+        let volume = mounted.iter().find(|volume| volume.id == self.volume_id)?;
+        Some(if self.relative.as_os_str().is_empty() {
+            volume.mount_point.clone()
+        } else {
+            volume.mount_point.join(&self.relative)
+        })
+    }
+}
+
+/// The volume `path` is on: the mounted volume with the longest mount
+/// point `path` is under.
+pub fn fingerprint(path: &Path, mounted: &[MountedVolume]) -> Option<RootVolume> {
+    let volume = mounted.iter()
+        .filter(|volume| path.starts_with(&volume.mount_point))
+        .max_by_key(|volume| volume.mount_point.as_os_str().len())?;
+    Some(RootVolume {
+        path: path.to_path_buf(),
+        volume_id: volume.id.clone(),
+        relative: path.strip_prefix(&volume.mount_point).ok()?.to_path_buf(),
+    })
+}
+
+/// Volumes mounted now that have an identifier. Volumes without one, like
+/// network shares and tmpfs, are left out and their roots go by path.
+#[cfg(target_os = "linux")]
+pub fn mounted_volumes() -> Vec<MountedVolume> {
+    let mountinfo = match std::fs::read_to_string("/proc/self/mountinfo") {
+        Ok(mountinfo) => mountinfo,
+        Err(e) => {
+            warn!("Failed to read mounts: {}", e);
+            return Vec::new();
+        }
+    };
+    let uuids: Vec<(PathBuf, String)> = std::fs::read_dir("/dev/disk/by-uuid")
+        .map(|entries| entries.flatten()
+            .filter_map(|entry| {
+                let device = entry.path().canonicalize().ok()?;
+                Some((device, entry.file_name().to_string_lossy().into_owned()))
+            })
+            .collect())
+        .unwrap_or_default();
+    parse_mountinfo(&mountinfo, &uuids)
+}
+
+/// Mounts listed in `/proc/self/mountinfo` whose source device has one of
+/// the `uuids`.
+pub fn parse_mountinfo(mountinfo: &str, uuids: &[(PathBuf, String)]) -> Vec<MountedVolume> {
+    mountinfo.lines()
+        .filter_map(|line| {
+            let (mount, source) = line.split_once(" - ")?;
+            let mut fields = mount.split(' ').skip(3);
+            // Bind mounts show part of a filesystem; only whole ones say where the volume is
+            if fields.next()? != "/" {
+                return None;
+            }
+            let mount_point = fields.next()?;
+            let device = source.split(' ').nth(1)?;
+            let (_, id) = uuids.iter().find(|(path, _)| path == Path::new(&unescape_mount(device)))?;
+            Some(MountedVolume { id: id.clone(), mount_point: PathBuf::from(unescape_mount(mount_point)) })
+        })
+        .collect()
+}
+
+/// Undoes the octal escapes (`\040` for a space) in mountinfo fields.
+fn unescape_mount(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 4)
+            .filter(|digits| bytes[i] == b'\\' && digits.iter().all(|digit| (b'0'..=b'7').contains(digit)))
+            .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+        match escape {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(target_os = "macos")]
+pub fn mounted_volumes() -> Vec<MountedVolume> {
+    let mounts = match std::process::Command::new("/sbin/mount").output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).into_owned(),
+        Err(e) => {
+            warn!("Failed to list mounts: {}", e);
+            return Vec::new();
+        }
+    };
+    mounts.lines()
+        .filter_map(|line| {
+            // "/dev/disk3s1 on /Volumes/Stick (msdos, local, nodev)"
+            let (device, rest) = line.split_once(" on ")?;
+            let mount_point = rest.rsplit_once(" (").map_or(rest, |(mount_point, _)| mount_point);
+            if !device.starts_with("/dev/") {
+                return None;
+            }
+            let info = std::process::Command::new("diskutil").args(["info", device]).output().ok()?;
+            let id = String::from_utf8_lossy(&info.stdout).lines()
+                .find_map(|line| line.trim().strip_prefix("Volume UUID:").map(|id| id.trim().to_string()))?;
+            Some(MountedVolume { id, mount_point: PathBuf::from(mount_point) })
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+pub fn mounted_volumes() -> Vec<MountedVolume> {
+    match std::process::Command::new("mountvol").output() {
+        Ok(output) => parse_mountvol(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => {
+            warn!("Failed to list volumes: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Volume GUIDs and their mount points from the output of `mountvol`.
+pub fn parse_mountvol(output: &str) -> Vec<MountedVolume> {
+    let mut volumes = Vec::new();
+    let mut current = None;
+    for line in output.lines().map(str::trim) {
+        if line.starts_with(r"\\?\Volume{") {
+            current = Some(line.to_string());
+        } else if line.is_empty() || line.starts_with("***") {
+            continue;
+        } else if let Some(id) = &current {
+            volumes.push(MountedVolume { id: id.clone(), mount_point: PathBuf::from(line) });
+        }
+    }
+    volumes
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn mounted_volumes() -> Vec<MountedVolume> {
+    Vec::new()
+}
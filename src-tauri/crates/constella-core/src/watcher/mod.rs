@@ -0,0 +1,143 @@
+use notify::{RecommendedWatcher, RecursiveMode, Watcher, Event, EventKind};
+use notify::event::{ModifyKind, RenameMode};
+use tokio::sync::mpsc;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use log::warn;
+use crate::chaos::{self, Fault};
+use crate::tracking::load::LoadMonitor;
+
+const MAX_CHANGES_PER_BATCH: usize = 1000;
+
+pub struct FileSystemWatcher {
+    watcher: RecommendedWatcher,
+}
+
+#[derive(Debug, Clone)]
+pub enum ChangeType {
+    Created,
+    Modified,
+    Deleted,
+    Renamed(PathBuf), // Old path for renamed files
+}
+
+impl FileSystemWatcher {
+    pub fn new(
+        tx: mpsc::Sender<Vec<(PathBuf, ChangeType)>>,
+        load: Arc<LoadMonitor>,
+    ) -> notify::Result<Self> {
+        let (event_tx, mut event_rx) = mpsc::channel(1000);
+        
+        // Create watcher with raw event stream
+        let watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            if let Ok(event) = res {
+                let _ = event_tx.blocking_send(event);
+            }
+        })?;
+
+        // Start event processor
+        let debounce_duration = Duration::from_millis(500);
+        let mut pending_changes = HashMap::new();
+
+        tokio::spawn(async move {
+            let mut flush_timer = tokio::time::interval(debounce_duration);
+
+            loop {
+                tokio::select! {
+                    Some(event) = event_rx.recv() => {
+                        // Process and debounce events
+                        for (path, change_type) in classify_event(event) {
+                            pending_changes.insert(path, (Instant::now(), change_type));
+                        }
+                    }
+                    _ = flush_timer.tick() => {
+                        // Flush pending changes that are old enough, holding them
+                        // longer and releasing fewer at a time while the system is busy
+                        let now = Instant::now();
+                        let mut changes = Vec::new();
+                        let window = if load.is_under_heavy_load() {
+                            debounce_duration * 4
+                        } else {
+                            debounce_duration
+                        };
+                        let max_batch = load.scale_batch(MAX_CHANGES_PER_BATCH);
+
+                        pending_changes.retain(|path, (time, change_type)| {
+                            if changes.len() < max_batch && now.duration_since(*time) >= window {
+                                changes.push((path.clone(), change_type.clone()));
+                                false
+                            } else {
+                                true
+                            }
+                        });
+
+                        if changes.is_empty() {
+                            continue;
+                        }
+                        let sent = if chaos::inject(Fault::ChannelDisconnect) {
+                            Err(changes)
+                        } else {
+                            tx.send(changes).await.map_err(|e| e.0)
+                        };
+                        if let Err(changes) = sent {
+                            if tx.is_closed() {
+                                warn!("Change consumer went away, dropping {} changes and stopping", changes.len());
+                                break;
+                            }
+                            // Put the batch back so the next flush delivers it; newer
+                            // events for the same path take precedence
+                            warn!("Failed to deliver {} changes, retrying on the next flush", changes.len());
+                            let due = now.checked_sub(window).unwrap_or(now);
+                            for (path, change_type) in changes {
+                                pending_changes.entry(path).or_insert((due, change_type));
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Self { watcher })
+    }
+
+    pub fn watch(&mut self, path: impl AsRef<std::path::Path>) -> notify::Result<()> {
+        self.watcher.watch(path.as_ref(), RecursiveMode::Recursive)
+    }
+
+    pub fn unwatch(&mut self, path: impl AsRef<std::path::Path>) -> notify::Result<()> {
+        self.watcher.unwatch(path.as_ref())
+    }
+}
+
+fn classify_event(event: Event) -> Vec<(PathBuf, ChangeType)> {
+    match event.kind {
+        EventKind::Create(_) => event.paths.into_iter()
+            .map(|path| (path, ChangeType::Created))
+            .collect(),
+        EventKind::Remove(_) => event.paths.into_iter()
+            .map(|path| (path, ChangeType::Deleted))
+            .collect(),
+        EventKind::Modify(ModifyKind::Name(rename_mode)) => match rename_mode {
+            // Both paths are known: [from, to]
+            RenameMode::Both if event.paths.len() == 2 => {
+                let mut paths = event.paths.into_iter();
+                let from = paths.next().unwrap_or_default();
+                let to = paths.next().unwrap_or_default();
+                vec![(to, ChangeType::Renamed(from))]
+            }
+            RenameMode::From => event.paths.into_iter()
+                .map(|path| (path, ChangeType::Deleted))
+                .collect(),
+            RenameMode::To => event.paths.into_iter()
+                .map(|path| (path, ChangeType::Created))
+                .collect(),
+            _ => Vec::new(),
+        },
+        EventKind::Modify(_) => event.paths.into_iter()
+            .map(|path| (path, ChangeType::Modified))
+            .collect(),
+        _ => Vec::new(),
+    }
+} 
\ No newline at end of file
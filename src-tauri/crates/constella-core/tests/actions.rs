@@ -0,0 +1,53 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::memory_fs::MemoryFileSystem;
+use common::Fixture;
+use constella_core::actions::{self, ResultKind};
+use constella_core::audit::{AuditAction, AuditLog, AuditRange};
+
+#[test]
+fn actions_are_offered_by_result_kind() {
+    let ids = |kind| actions::list(kind).into_iter().map(|action| action.id).collect::<Vec<_>>();
+    assert_eq!(ids(Some(ResultKind::Folder)), vec!["open", "reveal", "copy_path"]);
+    assert_eq!(ids(Some(ResultKind::Photo)), ids(None));
+    let open = &actions::list(None)[0];
+    assert_eq!((open.label.as_str(), open.shortcut.as_deref()), ("Open", Some("Enter")));
+
+    assert_eq!(ResultKind::of(Path::new("/photos/IMG_0001.JPG"), false), ResultKind::Photo);
+    assert_eq!(ResultKind::of(Path::new("/photos"), true), ResultKind::Folder);
+}
+
+#[tokio::test]
+async fn actions_run_on_the_selected_results() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/docs/plan.txt", "launch plan");
+    memory.insert("/mem/backup/plan.txt", "launch plan");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing("/mem").await.unwrap();
+    let audit = AuditLog::load(fixture.data_dir().join("audit_log.jsonl"));
+    let plan = PathBuf::from("/mem/docs/plan.txt");
+    let both = [plan.clone(), PathBuf::from("/mem/backup/plan.txt")];
+
+    let copied = actions::execute(&indexer, &audit, "copy_path", &both, true).await.unwrap();
+    assert_eq!(copied.clipboard.as_deref(), Some("/mem/docs/plan.txt\n/mem/backup/plan.txt"));
+    let identical = actions::execute(&indexer, &audit, "find_identical", std::slice::from_ref(&plan), true).await.unwrap();
+    assert_eq!(identical.paths, vec!["/mem/backup/plan.txt"]);
+
+    // Incognito stars are audited but leave importance alone
+    let tracker = indexer.change_tracker();
+    let before = tracker.importance_score(&plan).await;
+    actions::execute(&indexer, &audit, "star", std::slice::from_ref(&plan), false).await.unwrap();
+    assert_eq!(tracker.importance_score(&plan).await, before);
+    actions::execute(&indexer, &audit, "star", std::slice::from_ref(&plan), true).await.unwrap();
+    assert!(tracker.importance_score(&plan).await > before);
+    let starred = audit.read(AuditRange::default()).unwrap().entries;
+    assert_eq!(starred.iter().filter(|entry| entry.action == AuditAction::Star).count(), 2);
+
+    assert!(actions::execute(&indexer, &audit, "shred", std::slice::from_ref(&plan), true).await.unwrap_err().contains("Unknown action"));
+    assert!(actions::execute(&indexer, &audit, "find_identical", &both, true).await.unwrap_err().contains("one result at a time"));
+    let folder = [PathBuf::from("/mem/docs")];
+    assert!(actions::execute(&indexer, &audit, "star", &folder, true).await.unwrap_err().contains("isn't available"));
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use std::path::Path;
+
+use common::Fixture;
+use constella_core::audit::{AuditAction, AuditLog, AuditRange};
+use constella_core::tracking::UserAction;
+
+#[test]
+fn entries_are_chained_across_restarts() {
+    let fixture = Fixture::new();
+    let log_path = fixture.data_dir().join("audit_log.jsonl");
+    let log = AuditLog::load(&log_path);
+    let opened = log.append(UserAction::Open.into(), Path::new("/docs/report.pdf"), None).unwrap();
+    let moved = log.append(AuditAction::Move, Path::new("/docs/report.pdf"), Some("/archive/report.pdf".into())).unwrap();
+    assert_eq!((opened.sequence, moved.sequence), (0, 1));
+    assert_eq!(moved.previous_hash, opened.hash);
+
+    // A fresh log picks up where the file left off
+    let reopened = AuditLog::load(&log_path);
+    let deleted = reopened.append(AuditAction::Delete, Path::new("/archive/report.pdf"), None).unwrap();
+    assert_eq!(deleted.sequence, 2);
+    assert_eq!(deleted.previous_hash, moved.hash);
+
+    let page = reopened.read(AuditRange::default()).unwrap();
+    assert!(page.intact);
+    let actions: Vec<AuditAction> = page.entries.iter().map(|entry| entry.action).collect();
+    assert_eq!(actions, vec![AuditAction::Open, AuditAction::Move, AuditAction::Delete]);
+
+    let future = AuditRange { from: Some(deleted.timestamp + 60), to: None };
+    assert!(reopened.read(future).unwrap().entries.is_empty());
+
+    let exported = fixture.data_dir().join("export.jsonl");
+    reopened.export(&exported).unwrap();
+    assert_eq!(std::fs::read(&exported).unwrap(), std::fs::read(&log_path).unwrap());
+}
+
+#[test]
+fn tampering_breaks_the_chain() {
+    let fixture = Fixture::new();
+    let log_path = fixture.data_dir().join("audit_log.jsonl");
+    let log = AuditLog::load(&log_path);
+    for name in ["a", "b", "c"] {
+        log.append(AuditAction::Open, &Path::new("/docs").join(name), None).unwrap();
+    }
+
+    let edited = std::fs::read_to_string(&log_path).unwrap().replacen("/docs/b", "/docs/x", 1);
+    std::fs::write(&log_path, edited).unwrap();
+    let page = log.read(AuditRange::default()).unwrap();
+    assert!(!page.intact);
+    assert_eq!(page.broken_at_line, Some(2));
+
+    // Dropping a line is caught too
+    let lines: Vec<String> = std::fs::read_to_string(&log_path).unwrap().lines().map(str::to_string).collect();
+    std::fs::write(&log_path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+    assert_eq!(log.read(AuditRange::default()).unwrap().broken_at_line, Some(2));
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use std::path::Path;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::extract::binaries::{self, split_release_name, BinaryKind, BinaryMetadata};
+
+/// A TrueType font with just a `name` table, in Windows US English.
+fn font() -> Vec<u8> {
+    let names: [(u16, &str); 3] = [(1, "Inter"), (5, "Version 4.000;git-66647c2"), (8, "Rasmus Andersson")];
+    let mut strings = Vec::new();
+    let mut records = Vec::new();
+    for (id, name) in names {
+        let encoded: Vec<u8> = name.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        for value in [3u16, 1, 0x409, id, encoded.len() as u16, strings.len() as u16] {
+            records.extend(value.to_be_bytes());
+        }
+        strings.extend(encoded);
+    }
+
+    let mut bytes = vec![0, 1, 0, 0];
+    bytes.extend(1u16.to_be_bytes());
+    bytes.extend([0; 6]);
+    bytes.extend(b"name");
+    bytes.extend(0u32.to_be_bytes());
+    bytes.extend(28u32.to_be_bytes());
+    bytes.extend(0u32.to_be_bytes());
+    for value in [0u16, names.len() as u16, 6 + records.len() as u16] {
+        bytes.extend(value.to_be_bytes());
+    }
+    bytes.extend(records);
+    bytes.extend(strings);
+    bytes
+}
+
+/// A PE file whose only section holds a version resource's strings.
+fn executable() -> Vec<u8> {
+    let mut bytes = b"MZ".to_vec();
+    bytes.resize(0x3C, 0);
+    bytes.extend(0x40u32.to_le_bytes());
+    bytes.extend(b"PE\0\0");
+    bytes.extend([0; 2]);
+    bytes.extend(1u16.to_le_bytes());
+    bytes.extend([0; 16]);
+    bytes.extend(b".rsrc\0\0\0");
+    bytes.extend([0; 8]);
+
+    let mut resources = Vec::new();
+    for (key, value) in [("CompanyName", "Acme Inc"), ("ProductName", "Acme Sync"), ("ProductVersion", "5, 1, 0, 0")] {
+        let start = resources.len();
+        resources.extend([0; 2]);
+        resources.extend((value.encode_utf16().count() as u16 + 1).to_le_bytes());
+        resources.extend(1u16.to_le_bytes());
+        resources.extend(key.encode_utf16().chain([0]).flat_map(u16::to_le_bytes));
+        resources.resize((resources.len() + 3) & !3, 0);
+        resources.extend(value.encode_utf16().chain([0]).flat_map(u16::to_le_bytes));
+        resources.resize((resources.len() + 3) & !3, 0);
+        let length = (resources.len() - start) as u16;
+        resources[start..start + 2].copy_from_slice(&length.to_le_bytes());
+    }
+    bytes.extend((resources.len() as u32).to_le_bytes());
+    bytes.extend(0x200u32.to_le_bytes());
+    bytes.resize(0x200, 0);
+    bytes.extend(resources);
+    bytes
+}
+
+/// A compound file holding only an MSI summary stream, which is kept out
+/// of the mini stream by a zero cutoff.
+fn msi() -> Vec<u8> {
+    const END: u32 = 0xFFFF_FFFE;
+    let mut header = vec![0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+    header.resize(512, 0);
+    let mut put = |at: usize, value: u32| header[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    put(0x1C, 0xFFFE | (9 << 16));
+    put(0x20, 6);
+    put(0x2C, 1);
+    put(0x30, 1);
+    put(0x38, 0);
+    put(0x3C, END);
+    put(0x44, END);
+    put(0x4C, 0);
+    for i in 1..109 {
+        put(0x4C + i * 4, 0xFFFF_FFFF);
+    }
+
+    let mut fat: Vec<u8> = [0xFFFF_FFFD, END, END].iter().flat_map(|entry: &u32| entry.to_le_bytes()).collect();
+    fat.resize(512, 0xFF);
+
+    let mut directory = vec![0; 512];
+    let name: Vec<u8> = "\u{5}SummaryInformation".encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect();
+    directory[128..128 + name.len()].copy_from_slice(&name);
+    directory[128 + 64..128 + 66].copy_from_slice(&(name.len() as u16).to_le_bytes());
+    directory[128 + 116..128 + 120].copy_from_slice(&2u32.to_le_bytes());
+
+    let mut summary = vec![0xFE, 0xFF];
+    summary.resize(24, 0);
+    summary.extend(1u32.to_le_bytes());
+    summary.extend([0; 16]);
+    summary.extend(48u32.to_le_bytes());
+    let properties = [(3u32, "Constella\0\0\0"), (4, "Acme Inc\0\0\0\0")];
+    summary.extend(0u32.to_le_bytes());
+    summary.extend((properties.len() as u32).to_le_bytes());
+    let mut offset = 8 + properties.len() as u32 * 8;
+    for (id, value) in properties {
+        summary.extend(id.to_le_bytes());
+        summary.extend(offset.to_le_bytes());
+        offset += 8 + value.len() as u32;
+    }
+    for (_, value) in properties {
+        summary.extend(0x1Eu32.to_le_bytes());
+        summary.extend((value.len() as u32).to_le_bytes());
+        summary.extend(value.as_bytes());
+    }
+    directory[128 + 120..128 + 124].copy_from_slice(&(summary.len() as u32).to_le_bytes());
+
+    let mut bytes = header;
+    bytes.extend(fat);
+    bytes.extend(directory);
+    bytes.extend(summary);
+    bytes.resize(4 * 512, 0);
+    bytes
+}
+
+fn metadata(kind: BinaryKind, product: &str, company: Option<&str>, version: &str) -> BinaryMetadata {
+    BinaryMetadata {
+        kind,
+        product: Some(product.into()),
+        company: company.map(str::to_string),
+        version: Some(version.into()),
+    }
+}
+
+#[test]
+fn release_names_give_the_product_and_version() {
+    assert_eq!(split_release_name("Constella-2.3.1-x64.dmg"), (Some("Constella".into()), Some("2.3.1".into())));
+    assert_eq!(split_release_name("constella_v2.3.1.msi"), (Some("constella".into()), Some("2.3.1".into())));
+    assert_eq!(split_release_name("libconstella.so.2.3"), (Some("libconstella".into()), Some("2.3".into())));
+    assert_eq!(split_release_name("setup.exe"), (None, None));
+}
+
+#[test]
+fn headers_give_names_makers_and_versions() {
+    assert_eq!(binaries::metadata(Path::new("Inter.ttf"), &font()),
+        Some(metadata(BinaryKind::Font, "Inter", Some("Rasmus Andersson"), "4.000")));
+    assert_eq!(binaries::metadata(Path::new("sync.exe"), &executable()),
+        Some(metadata(BinaryKind::Executable, "Acme Sync", Some("Acme Inc"), "5.1.0.0")));
+    assert_eq!(binaries::metadata(Path::new("Setup-2.3.1.msi"), &msi()),
+        Some(metadata(BinaryKind::Installer, "Constella", Some("Acme Inc"), "2.3.1")));
+}
+
+#[test]
+fn files_that_only_look_like_binaries_are_skipped() {
+    assert_eq!(binaries::metadata(Path::new("Makefile"), b"all:\n\tcargo build\n"), None);
+    assert_eq!(binaries::metadata(Path::new("broken.ttf"), b"not a font"), None);
+}
+
+#[tokio::test]
+async fn installers_are_found_by_version() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/downloads/Constella-2.3.1-x64.dmg", vec![0u8; 64]);
+    memory.insert("/mem/downloads/Constella-2.4.0-x64.dmg", vec![0u8; 64]);
+    memory.insert("/mem/downloads/sync.exe", executable());
+    memory.insert("/mem/downloads/Inter.ttf", font());
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing("/mem/downloads").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "version:2.3.1").await, vec!["/mem/downloads/Constella-2.3.1-x64.dmg"]);
+    assert_eq!(search_paths(&indexer, "acme").await, vec!["/mem/downloads/sync.exe"]);
+    assert_eq!(search_paths(&indexer, "kind:font").await, vec!["/mem/downloads/Inter.ttf"]);
+
+    let results = indexer.search("sync").await.unwrap();
+    assert_eq!(results[0]["binary"]["company"], "Acme Inc");
+    assert_eq!(results[0]["binary"]["kind"], "executable");
+}
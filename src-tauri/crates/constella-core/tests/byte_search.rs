@@ -0,0 +1,73 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::memory_fs::MemoryFileSystem;
+use common::Fixture;
+use constella_core::indexing::byte_search::{ByteMatch, BytePattern, ByteSearchRequest};
+use constella_core::SettingsManager;
+
+const ROOT: &str = "/mem/bin";
+
+fn enable_byte_search(fixture: &Fixture) {
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.byte_search_enabled = true)
+        .unwrap();
+}
+
+#[test]
+fn patterns_support_wildcards() {
+    let pattern = BytePattern::parse("4D 5A ?? 00").unwrap();
+    let content = b"\x4d\x5a\x90\x00..\x4d\x5a\xff\x00\x4d\x5a";
+    let far_future = Instant::now() + Duration::from_secs(60);
+    assert_eq!(pattern.find_in(content, 10, far_future), (vec![0, 6], false));
+    assert_eq!(pattern.find_in(content, 1, far_future), (vec![0], false));
+    assert_eq!(BytePattern::parse("??4d5a").unwrap().find_in(content, 10, far_future), (vec![5, 9], false));
+
+    assert!(BytePattern::parse("4D 5").is_err());
+    assert!(BytePattern::parse("4D ZZ").is_err());
+    assert!(BytePattern::parse("?? ??").is_err());
+}
+
+#[tokio::test]
+async fn byte_search_is_opt_in_and_needs_a_prefilter() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer_with(MemoryFileSystem::new());
+    let request = ByteSearchRequest { pattern: "4D5A".into(), extensions: vec!["exe".into()], ..ByteSearchRequest::default() };
+    assert!(indexer.search_bytes(&request).await.unwrap_err().contains("turned off"));
+
+    enable_byte_search(&fixture);
+    let unfiltered = ByteSearchRequest { pattern: "4D5A".into(), ..ByteSearchRequest::default() };
+    assert!(indexer.search_bytes(&unfiltered).await.is_err());
+}
+
+#[tokio::test]
+async fn scans_only_files_passing_the_prefilter() {
+    let fixture = Fixture::new();
+    enable_byte_search(&fixture);
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/bin/tool.exe", b"MZ\x90\x00 payload DEADBEEF \xde\xad\xbe\xef".to_vec());
+    memory.insert("/mem/bin/other.EXE", b"\xde\xad\xbe\xef\xde\xad\xbe\xef".to_vec());
+    memory.insert("/mem/bin/notes.txt", b"\xde\xad\xbe\xef".to_vec());
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let request = ByteSearchRequest {
+        pattern: "DE AD BE EF".into(),
+        extensions: vec![".exe".into()],
+        ..ByteSearchRequest::default()
+    };
+    let result = indexer.search_bytes(&request).await.unwrap();
+    assert_eq!(result.candidates, 2);
+    assert_eq!(result.files_scanned, 2);
+    assert!(!result.timed_out);
+    assert_eq!(result.matches, vec![
+        ByteMatch { path: "/mem/bin/other.EXE".into(), offsets: vec![0, 4] },
+        ByteMatch { path: "/mem/bin/tool.exe".into(), offsets: vec![22] },
+    ]);
+
+    // Nothing gets scanned once the budget is spent
+    let exhausted = indexer.search_bytes(&ByteSearchRequest { time_budget_ms: 0, ..request }).await.unwrap();
+    assert!(exhausted.timed_out);
+    assert_eq!(exhausted.files_scanned, 0);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{search_paths, Fixture};
+use constella_core::indexing::collections::CollectionExport;
+use constella_core::search::SearchOptions;
+use constella_core::IndexManager;
+
+async fn search_in(indexer: &IndexManager, query: &str, collection: &str) -> Vec<String> {
+    let options = SearchOptions { collection: Some(collection.to_string()), ..SearchOptions::default() };
+    let mut paths: Vec<String> = indexer.search_with_options(query, &options).await
+        .unwrap()
+        .into_iter()
+        .filter_map(|hit| hit["path"].as_str().map(str::to_string))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[tokio::test]
+async fn collections_scope_searches_and_follow_moves() {
+    let fixture = Fixture::new();
+    let receipt = fixture.file("taxes/receipt.txt", "receipt for the desk");
+    fixture.file("taxes/papers/w2.txt", "wage receipt");
+    fixture.file("shopping/receipt.txt", "receipt for groceries");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let path = |relative: &str| fixture.path(relative).to_string_lossy().into_owned();
+
+    indexer.add_to_collection("Tax 2023", &[receipt.clone(), fixture.path("taxes/papers")]).unwrap();
+    let summary = indexer.add_to_collection(" Tax 2023 ", std::slice::from_ref(&receipt)).unwrap();
+    assert_eq!((summary.name.as_str(), summary.items), ("Tax 2023", 2));
+    assert_eq!(indexer.list_collections().len(), 1);
+
+    // Folders in a collection bring everything below them
+    assert_eq!(search_in(&indexer, "receipt", "Tax 2023").await, vec![path("taxes/papers/w2.txt"), path("taxes/receipt.txt")]);
+    assert_eq!(search_paths(&indexer, "receipt").await.len(), 3);
+    let unknown = SearchOptions { collection: Some("Nope".to_string()), ..SearchOptions::default() };
+    assert!(indexer.search_with_options("receipt", &unknown).await.is_err());
+
+    let moved = fixture.path("archive/receipt.txt");
+    std::fs::create_dir_all(moved.parent().unwrap()).unwrap();
+    std::fs::rename(&receipt, &moved).unwrap();
+    indexer.index_moves(&[(receipt.clone(), moved.clone())]).await.unwrap();
+    assert_eq!(indexer.collection_items("Tax 2023").unwrap(), vec![path("archive/receipt.txt"), path("taxes/papers")]);
+
+    indexer.remove_from_collection("Tax 2023", &[fixture.path("taxes/papers")]).unwrap();
+    assert_eq!(search_in(&indexer, "receipt", "Tax 2023").await, vec![path("archive/receipt.txt")]);
+
+    // Collections are kept next to the index
+    drop(indexer);
+    let reopened = fixture.indexer();
+    assert_eq!(reopened.collection_items("Tax 2023").unwrap().len(), 1);
+    reopened.delete_collection("Tax 2023").unwrap();
+    assert!(reopened.list_collections().is_empty());
+}
+
+#[tokio::test]
+async fn collections_export_as_links_or_copies() {
+    let fixture = Fixture::new();
+    let first = fixture.file("a/notes.txt", "first");
+    let second = fixture.file("b/notes.txt", "second");
+    let gone = fixture.path("c/missing.txt");
+    let indexer = fixture.indexer();
+    indexer.add_to_collection("Papers", &[first.clone(), second.clone(), gone]).unwrap();
+
+    let copies = fixture.path("export/copies");
+    let report = indexer.export_collection("Papers", &copies, CollectionExport::Copies).unwrap();
+    assert_eq!(report.exported.len(), 2);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(std::fs::read_to_string(copies.join("notes.txt")).unwrap(), "first");
+    assert_eq!(std::fs::read_to_string(copies.join("notes (2).txt")).unwrap(), "second");
+
+    let links = fixture.path("export/links");
+    indexer.export_collection("Papers", &links, CollectionExport::Symlinks).unwrap();
+    assert_eq!(std::fs::read_link(links.join("notes.txt")).unwrap(), first);
+    assert_eq!(std::fs::read_link(links.join("notes (2).txt")).unwrap(), second);
+}
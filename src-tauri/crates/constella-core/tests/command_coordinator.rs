@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use constella_core::jobs::coordinator::{CommandConflict, CommandCoordinator};
+use constella_core::jobs::{JobContext, JobId, JobKind, JobManager};
+
+/// A job that runs until cancelled.
+async fn until_cancelled(context: JobContext) -> Result<Option<serde_json::Value>, String> {
+    context.cancelled().await;
+    Ok(None)
+}
+
+async fn wait_until_finished(jobs: &JobManager, id: JobId) {
+    for _ in 0..200 {
+        if jobs.get(id).unwrap().status.is_finished() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    }
+    panic!("job {} never finished", id);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn indexing_is_rejected_until_the_run_ends() {
+    let jobs = Arc::new(JobManager::new());
+    let coordinator = CommandCoordinator::new(jobs.clone());
+
+    let running = coordinator.submit_indexing("/home/me/Documents", until_cancelled).unwrap();
+    let conflict = CommandConflict::IndexingAlreadyRunning { job: running, directory: PathBuf::from("/home/me/Documents") };
+    assert_eq!(coordinator.submit_indexing("/home/me/Documents", until_cancelled), Err(conflict.clone()));
+    assert_eq!(coordinator.submit_indexing("/home/me/Documents/taxes", until_cancelled), Err(conflict.clone()));
+    assert_eq!(coordinator.submit_indexing("/home/me", until_cancelled), Err(conflict));
+
+    // Another folder would replace this one in the index
+    let in_progress = CommandConflict::IndexingInProgress { job: running };
+    assert_eq!(coordinator.submit_indexing("/home/me/Music", until_cancelled), Err(in_progress.clone()));
+    assert_eq!(coordinator.check_no_indexing(), Err(in_progress));
+
+    jobs.cancel(running).unwrap();
+    wait_until_finished(&jobs, running).await;
+    assert_eq!(coordinator.check_no_indexing(), Ok(()));
+    assert!(coordinator.submit_indexing("/home/me/Documents", |_| async { Ok(None) }).is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn exclusive_jobs_are_not_queued_twice() {
+    let jobs = Arc::new(JobManager::new());
+    let coordinator = CommandCoordinator::new(jobs.clone());
+
+    let first = coordinator.submit_exclusive(JobKind::Optimization, until_cancelled).unwrap();
+    assert_eq!(
+        coordinator.submit_exclusive(JobKind::Optimization, until_cancelled),
+        Err(CommandConflict::AlreadyQueued { job: first, job_kind: JobKind::Optimization }),
+    );
+    let other = coordinator.submit_exclusive(JobKind::ChecksumVerification, |_| async { Ok(None) }).unwrap();
+
+    jobs.cancel(first).unwrap();
+    wait_until_finished(&jobs, first).await;
+    wait_until_finished(&jobs, other).await;
+    assert!(coordinator.submit_exclusive(JobKind::Optimization, |_| async { Ok(None) }).is_ok());
+}
@@ -0,0 +1,191 @@
+//! Filesystem providers for tests: an in-memory tree, and a wrapper that
+//! makes chosen paths fail, stall or vanish.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use constella_core::file_system::{FileMetadata, FileSystemProvider};
+use parking_lot::Mutex;
+
+#[derive(Default)]
+pub struct MemoryFileSystem {
+    files: Mutex<BTreeMap<PathBuf, (Vec<u8>, SystemTime)>>,
+    origins: Mutex<HashMap<PathBuf, String>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn insert(&self, path: impl Into<PathBuf>, content: impl Into<Vec<u8>>) {
+        self.files.lock().insert(path.into(), (content.into(), SystemTime::now()));
+    }
+
+    /// Inserts a file last modified at `modified`.
+    pub fn insert_modified(&self, path: impl Into<PathBuf>, content: impl Into<Vec<u8>>, modified: SystemTime) {
+        self.files.lock().insert(path.into(), (content.into(), modified));
+    }
+
+    pub fn remove(&self, path: impl AsRef<Path>) {
+        self.files.lock().remove(path.as_ref());
+    }
+
+    /// Records the URL `path` was downloaded from.
+    pub fn set_origin(&self, path: impl Into<PathBuf>, url: impl Into<String>) {
+        self.origins.lock().insert(path.into(), url.into());
+    }
+}
+
+impl FileSystemProvider for MemoryFileSystem {
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let files = self.files.lock();
+        if let Some((content, modified)) = files.get(path) {
+            return Ok(FileMetadata {
+                len: content.len() as u64,
+                modified: Some(*modified),
+                created: Some(*modified),
+                is_file: true,
+                is_dir: false,
+            });
+        }
+        if files.keys().any(|file| file.starts_with(path)) {
+            return Ok(FileMetadata {
+                len: 0,
+                modified: None,
+                created: None,
+                is_file: false,
+                is_dir: true,
+            });
+        }
+        Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display())))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files.lock()
+            .get(path)
+            .map(|(content, _)| content.clone())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display())))
+    }
+
+    fn walk(&self, root: &Path) -> Vec<io::Result<PathBuf>> {
+        self.files.lock()
+            .keys()
+            .filter(|path| path.starts_with(root))
+            .map(|path| Ok(path.clone()))
+            .collect()
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let files = self.files.lock();
+        let mut children: Vec<PathBuf> = files.keys()
+            .filter_map(|path| path.strip_prefix(dir).ok())
+            .filter_map(|rest| rest.components().next())
+            .map(|child| dir.join(child))
+            .collect();
+        if children.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", dir.display())));
+        }
+        children.dedup();
+        Ok(children)
+    }
+
+    fn origin_url(&self, path: &Path) -> Option<String> {
+        self.origins.lock().get(path).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Fault {
+    /// Every operation on the path fails with `PermissionDenied`.
+    PermissionDenied,
+    /// The walk reports an error in place of the path.
+    Unwalkable,
+    /// The walk lists the path, but it is gone by the time anything reads it.
+    Vanished,
+    /// Every operation on the path sleeps first.
+    Slow(Duration),
+}
+
+/// Wraps another provider and applies a `Fault` to chosen paths.
+pub struct FaultyFileSystem<P> {
+    inner: Arc<P>,
+    faults: Mutex<HashMap<PathBuf, Fault>>,
+}
+
+impl<P: FileSystemProvider> FaultyFileSystem<P> {
+    pub fn new(inner: Arc<P>) -> Arc<Self> {
+        Arc::new(Self {
+            inner,
+            faults: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn inject(&self, path: impl Into<PathBuf>, fault: Fault) {
+        self.faults.lock().insert(path.into(), fault);
+    }
+
+    pub fn clear(&self, path: impl AsRef<Path>) {
+        self.faults.lock().remove(path.as_ref());
+    }
+
+    fn check(&self, path: &Path) -> io::Result<()> {
+        let fault = self.faults.lock().get(path).copied();
+        match fault {
+            Some(Fault::PermissionDenied) => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{}: permission denied", path.display()),
+            )),
+            Some(Fault::Vanished) => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} disappeared", path.display()),
+            )),
+            Some(Fault::Slow(delay)) => {
+                std::thread::sleep(delay);
+                Ok(())
+            }
+            Some(Fault::Unwalkable) | None => Ok(()),
+        }
+    }
+}
+
+impl<P: FileSystemProvider> FileSystemProvider for FaultyFileSystem<P> {
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        self.check(path)?;
+        self.inner.metadata(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.check(path)?;
+        self.inner.read(path)
+    }
+
+    fn walk(&self, root: &Path) -> Vec<io::Result<PathBuf>> {
+        let faults = self.faults.lock();
+        self.inner.walk(root)
+            .into_iter()
+            .map(|entry| match entry {
+                Ok(path) => match faults.get(&path) {
+                    Some(Fault::Unwalkable) | Some(Fault::PermissionDenied) => Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        format!("{}: permission denied", path.display()),
+                    )),
+                    _ => Ok(path),
+                },
+                Err(e) => Err(e),
+            })
+            .collect()
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        self.check(dir)?;
+        self.inner.read_dir(dir)
+    }
+
+    fn origin_url(&self, path: &Path) -> Option<String> {
+        self.inner.origin_url(path)
+    }
+}
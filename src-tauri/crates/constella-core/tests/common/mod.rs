@@ -0,0 +1,118 @@
+//! Shared harness for the integration tests: builds a synthetic directory
+//! tree with known contents next to a throwaway data directory, so every
+//! test runs the real pipeline against an index nobody else touches.
+
+#![allow(dead_code)]
+
+pub mod memory_fs;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use constella_core::file_system::FileSystemProvider;
+use constella_core::indexing::{IndexBacking, IndexOptions};
+use constella_core::{IndexManager, SettingsManager};
+use tempfile::TempDir;
+
+pub struct Fixture {
+    root: TempDir,
+    data: TempDir,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        Self {
+            root: TempDir::new().expect("Failed to create fixture root"),
+            data: TempDir::new().expect("Failed to create data directory"),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        self.root.path()
+    }
+
+    pub fn root_str(&self) -> String {
+        self.root().to_string_lossy().into_owned()
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        self.data.path()
+    }
+
+    pub fn path(&self, relative: &str) -> PathBuf {
+        self.root().join(relative)
+    }
+
+    /// Writes `contents` to `relative`, creating parent directories as needed.
+    pub fn file(&self, relative: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.path(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("Failed to create fixture directory");
+        }
+        fs::write(&path, contents).expect("Failed to write fixture file");
+        path
+    }
+
+    /// Writes a file of exactly `size` bytes.
+    pub fn sized_file(&self, relative: &str, size: usize) -> PathBuf {
+        self.file(relative, vec![b'x'; size])
+    }
+
+    pub fn dir(&self, relative: &str) -> PathBuf {
+        let path = self.path(relative);
+        fs::create_dir_all(&path).expect("Failed to create fixture directory");
+        path
+    }
+
+    #[cfg(unix)]
+    pub fn symlink(&self, target: impl AsRef<Path>, relative: &str) -> PathBuf {
+        let path = self.path(relative);
+        std::os::unix::fs::symlink(target, &path).expect("Failed to create fixture symlink");
+        path
+    }
+
+    pub fn remove(&self, relative: &str) {
+        fs::remove_file(self.path(relative)).expect("Failed to remove fixture file");
+    }
+
+    /// A fresh indexer over this fixture's data directory. Samplers are not
+    /// started, so load and power readings stay at their defaults and runs
+    /// don't depend on what else the machine is doing.
+    pub fn indexer(&self) -> IndexManager {
+        let settings = Arc::new(SettingsManager::load(self.data_dir().join("settings.json")));
+        IndexManager::new(self.data_dir(), settings).expect("Failed to create indexer")
+    }
+
+    /// An indexer that reads the indexed tree through `fs` instead of the OS.
+    pub fn indexer_with(&self, fs: Arc<dyn FileSystemProvider>) -> IndexManager {
+        let settings = Arc::new(SettingsManager::load(self.data_dir().join("settings.json")));
+        IndexManager::with_file_system(self.data_dir(), settings, fs).expect("Failed to create indexer")
+    }
+}
+
+impl Fixture {
+    /// An indexer whose index lives in memory, reading the tree through `fs`.
+    pub fn ram_indexer_with(&self, fs: Arc<dyn FileSystemProvider>) -> IndexManager {
+        let settings = Arc::new(SettingsManager::load(self.data_dir().join("settings.json")));
+        let options = IndexOptions { backing: IndexBacking::Ram, fs, ..IndexOptions::default() };
+        IndexManager::with_options(self.data_dir(), settings, options).expect("Failed to create indexer")
+    }
+}
+
+pub async fn doc_count(indexer: &IndexManager) -> u64 {
+    indexer.get_reader().await
+        .expect("Failed to open reader")
+        .searcher()
+        .num_docs()
+}
+
+pub async fn search_paths(indexer: &IndexManager, query: &str) -> Vec<String> {
+    let mut paths: Vec<String> = indexer.search(query).await
+        .expect("Search failed")
+        .into_iter()
+        .filter_map(|hit| hit["path"].as_str().map(str::to_string))
+        .collect();
+    paths.sort();
+    paths
+}
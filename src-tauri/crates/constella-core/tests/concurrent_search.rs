@@ -0,0 +1,48 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use common::memory_fs::MemoryFileSystem;
+use common::{doc_count, Fixture};
+
+const ROOT: &str = "/mem/bulk";
+const FILES: usize = 15_000;
+// Generous for a debug build, but far below how long a bulk commit holds the writer
+const MAX_QUERY_LATENCY: Duration = Duration::from_secs(2);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn queries_stay_fast_during_bulk_indexing() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    for i in 0..FILES {
+        memory.insert(format!("{}/dir_{}/report_{}.txt", ROOT, i % 50, i), "quarterly report draft");
+    }
+    let indexer = Arc::new(fixture.indexer_with(memory));
+
+    let done = Arc::new(AtomicBool::new(false));
+    let searches = {
+        let indexer = indexer.clone();
+        let done = done.clone();
+        tokio::spawn(async move {
+            let mut latencies = Vec::new();
+            while !done.load(Ordering::SeqCst) {
+                let started = Instant::now();
+                indexer.search("report").await.unwrap();
+                latencies.push(started.elapsed());
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            latencies
+        })
+    };
+
+    indexer.start_indexing(ROOT).await.unwrap();
+    done.store(true, Ordering::SeqCst);
+    let latencies = searches.await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, FILES as u64);
+    assert!(latencies.len() > 10, "only {} searches ran during indexing", latencies.len());
+    let slowest = latencies.iter().max().unwrap();
+    assert!(*slowest < MAX_QUERY_LATENCY, "slowest query took {:?}", slowest);
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::extract::config::{key_terms, keys, ConfigFormat};
+
+#[test]
+fn keys_are_flattened_into_dotted_paths() {
+    let json = r#"{"database": {"host": "db", "replicas": [{"port": 5432}]}, "api_key": "x"}"#;
+    let mut json_keys = keys(ConfigFormat::Json, json);
+    json_keys.sort();
+    assert_eq!(json_keys, vec!["api_key", "database", "database.host", "database.replicas", "database.replicas.port"]);
+
+    let yaml = "\
+# deployment
+database:
+  host: db
+  password: hunter2
+  init: |
+    name: not a key
+servers:
+  - name: web
+    port: 80
+";
+    assert_eq!(keys(ConfigFormat::Yaml, yaml), vec![
+        "database", "database.host", "database.password", "database.init", "servers", "servers.name", "servers.port",
+    ]);
+
+    let toml = "\
+title = \"app\"
+[database]
+password = \"\"\"
+multi = line
+\"\"\"
+pool = { size = 5, timeout = 30 }
+[[services.\"auth.v2\"]]
+api_key = \"x\"
+";
+    assert_eq!(keys(ConfigFormat::Toml, toml), vec![
+        "title", "database", "database.password", "database.pool", "database.pool.size", "database.pool.timeout",
+        "services.auth.v2", "services.auth.v2.api_key",
+    ]);
+
+    let ini = "; comment\nroot = 1\n[mail]\nsmtp.host: mail.example.com\n";
+    assert_eq!(keys(ConfigFormat::Ini, ini), vec!["root", "mail", "mail.smtp.host"]);
+}
+
+#[test]
+fn keys_are_searchable_by_their_trailing_parts() {
+    let terms: Vec<String> = key_terms("Prod.Database.Password").collect();
+    assert_eq!(terms, vec!["prod.database.password", "database.password", "password"]);
+}
+
+#[tokio::test]
+async fn key_searches_find_where_settings_are_defined() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/project/config/prod.yaml", "database:\n  password: hunter2\n");
+    memory.insert("/mem/project/Cargo.toml", "[package]\nname = \"demo\"\n[profile.release]\nlto = true\n");
+    memory.insert("/mem/project/package.json", r#"{"scripts": {"build": "vite build"}, "stripe": {"api_key": "sk"}}"#);
+    memory.insert("/mem/project/notes.txt", "rotate the database password");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing("/mem/project").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "key:database.password").await, vec!["/mem/project/config/prod.yaml"]);
+    assert_eq!(search_paths(&indexer, "key:api_key").await, vec!["/mem/project/package.json"]);
+    assert_eq!(search_paths(&indexer, "key:profile.release.lto").await, vec!["/mem/project/Cargo.toml"]);
+    assert!(search_paths(&indexer, "key:hunter2").await.is_empty());
+    assert_eq!(search_paths(&indexer, "hunter2").await, vec!["/mem/project/config/prod.yaml"]);
+}
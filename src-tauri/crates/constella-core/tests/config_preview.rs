@@ -0,0 +1,77 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{doc_count, Fixture};
+use constella_core::settings::IndexingConfig;
+use constella_core::SettingsManager;
+
+const ROOT: &str = "/mem/project";
+
+fn set_config(fixture: &Fixture, config: IndexingConfig) {
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.indexing = config)
+        .unwrap();
+}
+
+fn project() -> std::sync::Arc<MemoryFileSystem> {
+    let fs = MemoryFileSystem::new();
+    fs.insert("/mem/project/src/main.rs", "fn main() {}");
+    fs.insert("/mem/project/node_modules/pkg/index.js", "module.exports = {}");
+    fs.insert("/mem/project/node_modules/pkg/README.md", "# pkg");
+    fs.insert("/mem/project/build/out.log", "built");
+    fs.insert("/mem/project/notes.txt", vec![b'x'; 4096]);
+    fs
+}
+
+#[tokio::test]
+async fn exclusions_are_applied_when_indexing() {
+    let fixture = Fixture::new();
+    set_config(&fixture, IndexingConfig {
+        exclude: vec!["**/node_modules/**".into()],
+        ..IndexingConfig::default()
+    });
+    let indexer = fixture.indexer_with(project());
+
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 3);
+    assert_eq!(indexer.indexed_roots(), vec![std::path::PathBuf::from(ROOT)]);
+}
+
+#[tokio::test]
+async fn preview_reports_the_effect_without_changing_the_index() {
+    let fixture = Fixture::new();
+    set_config(&fixture, IndexingConfig {
+        exclude: vec!["**/build/**".into()],
+        ..IndexingConfig::default()
+    });
+    let indexer = fixture.indexer_with(project());
+    indexer.start_indexing(ROOT).await.unwrap();
+    assert_eq!(doc_count(&indexer).await, 4);
+
+    let preview = indexer.preview_config_change(&IndexingConfig {
+        exclude: vec!["**/node_modules/**".into()],
+        content_max_file_size: 1024,
+        ..IndexingConfig::default()
+    }).await.unwrap();
+
+    assert_eq!(preview.documents_removed, 2);
+    assert_eq!(preview.files_newly_eligible, 1);
+    assert_eq!(preview.sample_newly_eligible, vec![std::path::PathBuf::from("/mem/project/build/out.log")]);
+    assert_eq!(preview.content_removed, 1);
+    assert_eq!(preview.content_added, 0);
+    assert_eq!(doc_count(&indexer).await, 4);
+}
+
+#[tokio::test]
+async fn invalid_patterns_are_rejected() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer_with(project());
+
+    let preview = indexer.preview_config_change(&IndexingConfig {
+        exclude: vec!["[".into()],
+        ..IndexingConfig::default()
+    }).await;
+
+    assert!(preview.is_err());
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::memory_fs::{Fault, FaultyFileSystem, MemoryFileSystem};
+use common::Fixture;
+use constella_core::indexing::coverage::SkipReason;
+
+const ROOT: &str = "/mem/project";
+
+#[tokio::test]
+async fn reports_what_the_index_is_missing_and_why() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/project/src/main.rs", "fn main() {}");
+    memory.insert("/mem/project/src/.env", "SECRET=1");
+    memory.insert("/mem/project/logs/huge.txt", vec![b'x'; 2 * 1024 * 1024]);
+    memory.insert("/mem/project/private/keys.pem", "-----BEGIN-----");
+    memory.insert("/mem/project/gone.txt", "deleted later");
+    let fs = FaultyFileSystem::new(memory.clone());
+    fs.inject("/mem/project/private/keys.pem", Fault::PermissionDenied);
+    let indexer = fixture.indexer_with(fs.clone());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    memory.remove("/mem/project/gone.txt");
+    memory.insert("/mem/project/src/new.rs", "added after indexing");
+    fs.clear("/mem/project/private/keys.pem");
+
+    let report = indexer.coverage(ROOT).await.unwrap();
+
+    assert_eq!(report.files_on_disk, 5);
+    assert_eq!(report.indexed_documents, 3);
+    assert_eq!(report.excluded, 1);
+    assert_eq!(report.not_indexed, 2);
+    assert_eq!(report.content_too_large, 1);
+    assert_eq!(report.stale_documents, 1);
+    assert_eq!(report.unreadable, 0);
+
+    let skipped = |reason: SkipReason| -> Vec<(PathBuf, usize)> {
+        let mut dirs: Vec<_> = report.skipped.iter()
+            .filter(|subtree| subtree.reason == reason)
+            .map(|subtree| (subtree.path.clone(), subtree.files))
+            .collect();
+        dirs.sort();
+        dirs
+    };
+    assert_eq!(skipped(SkipReason::Excluded), vec![(PathBuf::from("/mem/project/src"), 1)]);
+    assert_eq!(skipped(SkipReason::NotIndexed), vec![
+        (PathBuf::from("/mem/project/private"), 1),
+        (PathBuf::from("/mem/project/src"), 1),
+    ]);
+    assert_eq!(skipped(SkipReason::ContentTooLarge), vec![(PathBuf::from("/mem/project/logs"), 1)]);
+}
+
+#[tokio::test]
+async fn unwalkable_entries_are_counted() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/project/a.txt", "a");
+    memory.insert("/mem/project/b.txt", "b");
+    let fs = FaultyFileSystem::new(memory);
+    fs.inject("/mem/project/b.txt", Fault::Unwalkable);
+    let indexer = fixture.indexer_with(fs);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let report = indexer.coverage(ROOT).await.unwrap();
+
+    assert_eq!(report.files_on_disk, 1);
+    assert_eq!(report.unreadable, 1);
+    assert!(report.unreadable_errors[0].contains("b.txt"));
+    assert!(report.skipped.is_empty());
+}
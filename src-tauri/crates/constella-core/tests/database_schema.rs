@@ -0,0 +1,135 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::extract::sqlite::{self, column_names, DatabaseTable};
+
+fn varint(mut value: u64) -> Vec<u8> {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.insert(0, (value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes
+}
+
+/// A `sqlite_master` row: type, name, table name, root page and SQL.
+fn schema_record(kind: &str, name: &str, sql: &str) -> Vec<u8> {
+    let mut types = Vec::new();
+    let mut body = Vec::new();
+    for (i, text) in [kind, name, name, "", sql].into_iter().enumerate() {
+        if i == 3 {
+            types.extend(varint(1));
+            body.push(2);
+        } else {
+            types.extend(varint(text.len() as u64 * 2 + 13));
+            body.extend(text.as_bytes());
+        }
+    }
+    let mut record = varint(types.len() as u64 + 1);
+    record.extend(types);
+    record.extend(body);
+    record
+}
+
+/// A one-page database whose schema holds `entries`.
+fn database(entries: &[(&str, &str, &str)]) -> Vec<u8> {
+    let mut page = vec![0u8; 4096];
+    page[..16].copy_from_slice(b"SQLite format 3\0");
+    page[16..18].copy_from_slice(&4096u16.to_be_bytes());
+
+    let mut end = page.len();
+    let mut pointers = Vec::new();
+    for (rowid, (kind, name, sql)) in entries.iter().enumerate() {
+        let payload = schema_record(kind, name, sql);
+        let mut cell = varint(payload.len() as u64);
+        cell.extend(varint(rowid as u64 + 1));
+        cell.extend(payload);
+        end -= cell.len();
+        page[end..end + cell.len()].copy_from_slice(&cell);
+        pointers.push(end as u16);
+    }
+    page[100] = 0x0D;
+    page[103..105].copy_from_slice(&(entries.len() as u16).to_be_bytes());
+    page[105..107].copy_from_slice(&(end as u16).to_be_bytes());
+    for (i, pointer) in pointers.iter().enumerate() {
+        page[108 + i * 2..110 + i * 2].copy_from_slice(&pointer.to_be_bytes());
+    }
+    page
+}
+
+fn shop() -> Vec<u8> {
+    database(&[
+        ("table", "customers", "CREATE TABLE customers (id INTEGER PRIMARY KEY, email TEXT NOT NULL UNIQUE)"),
+        ("index", "customers_email", "CREATE INDEX customers_email ON customers (email)"),
+        ("table", "invoices", "CREATE TABLE invoices (id INTEGER, customer_id INTEGER REFERENCES customers(id), total REAL)"),
+        ("table", "sqlite_sequence", "CREATE TABLE sqlite_sequence(name,seq)"),
+    ])
+}
+
+#[test]
+fn column_names_skip_constraints() {
+    let sql = r#"CREATE TABLE "order items" (id INTEGER, "unit price" REAL, qty INT CHECK (qty > 0, 1), PRIMARY KEY (id), FOREIGN KEY (id) REFERENCES orders(id))"#;
+    assert_eq!(column_names(sql), vec!["id", "unit price", "qty"]);
+    assert_eq!(column_names("CREATE VIRTUAL TABLE notes USING fts5(title, body, tokenize='porter')"), vec!["title", "body"]);
+}
+
+#[test]
+fn tables_are_read_from_the_schema() {
+    assert_eq!(sqlite::tables(&shop()), Some(vec![
+        DatabaseTable { name: "customers".into(), columns: vec!["id".into(), "email".into()] },
+        DatabaseTable { name: "invoices".into(), columns: vec!["id".into(), "customer_id".into(), "total".into()] },
+    ]));
+    assert_eq!(sqlite::tables(b"not a database"), None);
+}
+
+/// `shop()` below a chain of `depth` interior pages, each with only a
+/// right-most child.
+fn deep_shop(depth: u32) -> Vec<u8> {
+    let leaf = shop();
+    let mut bytes = leaf[..100].to_vec();
+    for number in 1..=depth {
+        let header = if number == 1 { 100 } else { 0 };
+        let mut page = vec![0u8; 4096 - header];
+        page[0] = 0x05;
+        page[8..12].copy_from_slice(&(number + 1).to_be_bytes());
+        bytes.extend(page);
+    }
+    // The leaf's cells point into a page that starts with the file header
+    let mut page = vec![0u8; 4096];
+    page[100..].copy_from_slice(&leaf[100..]);
+    for i in 0..4 {
+        let pointer = 108 + i * 2;
+        page[pointer - 100..pointer - 98].copy_from_slice(&leaf[pointer..pointer + 2]);
+    }
+    page[..8].copy_from_slice(&leaf[100..108]);
+    bytes.extend(page);
+    bytes
+}
+
+#[test]
+fn trees_deeper_than_sqlite_allows_are_corrupt() {
+    let tables = |bytes: &[u8]| sqlite::tables(bytes).map(|tables| tables.len());
+    assert_eq!(tables(&deep_shop(5)), Some(2));
+    assert_eq!(tables(&deep_shop(40)), None);
+}
+
+#[tokio::test]
+async fn databases_are_found_by_table_and_column() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/app/shop.db", shop());
+    memory.insert("/mem/app/cache.db", b"just some bytes".to_vec());
+    memory.insert("/mem/app/invoices.txt", "paid");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing("/mem/app").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "table:invoices").await, vec!["/mem/app/shop.db"]);
+    assert_eq!(search_paths(&indexer, "column:customer_id").await, vec!["/mem/app/shop.db"]);
+    assert_eq!(search_paths(&indexer, "kind:database").await, vec!["/mem/app/shop.db"]);
+    assert!(search_paths(&indexer, "table:customers_email").await.is_empty());
+
+    let results = indexer.search("email").await.unwrap();
+    assert_eq!(results[0]["tables"], serde_json::json!(["customers", "invoices"]));
+}
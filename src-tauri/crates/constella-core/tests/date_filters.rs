@@ -0,0 +1,95 @@
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{Local, NaiveDate, TimeZone, Weekday};
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::search::dates::{parse_period, DateLocale, DateOrder};
+use constella_core::SettingsManager;
+
+const ROOT: &str = "/mem/dates";
+
+fn local(year: i32, month: u32, day: u32) -> i64 {
+    let date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    Local.from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap()).earliest().unwrap().timestamp()
+}
+
+fn at(year: i32, month: u32, day: u32) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(local(year, month, day) as u64 + 12 * 3600)
+}
+
+#[test]
+fn periods_resolve_to_calendar_spans() {
+    // A Wednesday
+    let now = Local.from_local_datetime(&NaiveDate::from_ymd_opt(2024, 5, 15).unwrap().and_hms_opt(10, 0, 0).unwrap())
+        .earliest()
+        .unwrap();
+    let monday_first = DateLocale::from_tag("en_GB.UTF-8");
+    let period = |expression| parse_period(expression, now, monday_first).unwrap();
+
+    assert_eq!(period("today"), (local(2024, 5, 15), local(2024, 5, 16)));
+    assert_eq!(period("yesterday"), (local(2024, 5, 14), local(2024, 5, 15)));
+    assert_eq!(period("this-week"), (local(2024, 5, 13), local(2024, 5, 20)));
+    assert_eq!(period("last-week"), (local(2024, 5, 6), local(2024, 5, 13)));
+    assert_eq!(period("last-month"), (local(2024, 4, 1), local(2024, 5, 1)));
+    assert_eq!(period("2021"), (local(2021, 1, 1), local(2022, 1, 1)));
+    assert_eq!(period("2023-Q2"), (local(2023, 4, 1), local(2023, 7, 1)));
+    assert_eq!(period("2023-02"), (local(2023, 2, 1), local(2023, 3, 1)));
+    assert_eq!(period("2023-04-05"), (local(2023, 4, 5), local(2023, 4, 6)));
+
+    let sunday_first = DateLocale::from_tag("en-US");
+    assert_eq!(sunday_first.week_start, Weekday::Sun);
+    assert_eq!(parse_period("this-week", now, sunday_first).unwrap(), (local(2024, 5, 12), local(2024, 5, 19)));
+
+    assert!(parse_period("2023-Q5", now, monday_first).is_err());
+    assert!(parse_period("someday", now, monday_first).is_err());
+}
+
+#[test]
+fn numeric_dates_follow_the_locale() {
+    let now = Local::now();
+    let us = DateLocale::from_tag("en-US");
+    let german = DateLocale::from_tag("de_DE.UTF-8");
+    assert_eq!(us.order, DateOrder::MonthDayYear);
+    assert_eq!(german.order, DateOrder::DayMonthYear);
+    assert_eq!(DateLocale::from_tag("ja-JP").order, DateOrder::YearMonthDay);
+
+    assert_eq!(parse_period("03/04/2023", now, us).unwrap().0, local(2023, 3, 4));
+    assert_eq!(parse_period("03.04.2023", now, german).unwrap().0, local(2023, 4, 3));
+    // A leading four-digit year is unambiguous everywhere
+    assert_eq!(parse_period("2023/04/03", now, us).unwrap().0, local(2023, 4, 3));
+}
+
+#[tokio::test]
+async fn date_filters_narrow_search_results() {
+    let fixture = Fixture::new();
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.date_locale = Some("en-GB".into()))
+        .unwrap();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/dates/report-new.txt", "quarterly");
+    memory.insert_modified("/mem/dates/report-2023.txt", "quarterly", at(2023, 5, 20));
+    memory.insert_modified("/mem/dates/report-2020.txt", "quarterly", at(2020, 11, 2));
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "report modified:today").await, vec!["/mem/dates/report-new.txt"]);
+    assert_eq!(search_paths(&indexer, "report modified:2023-Q2").await, vec!["/mem/dates/report-2023.txt"]);
+    assert_eq!(search_paths(&indexer, "report before:2021").await, vec!["/mem/dates/report-2020.txt"]);
+    assert_eq!(
+        search_paths(&indexer, "report after:2020").await,
+        vec!["/mem/dates/report-2023.txt", "/mem/dates/report-new.txt"],
+    );
+    assert_eq!(
+        search_paths(&indexer, "modified:2020..2023").await,
+        vec!["/mem/dates/report-2020.txt", "/mem/dates/report-2023.txt"],
+    );
+    // The memory filesystem reports creation as the modified time
+    assert_eq!(search_paths(&indexer, "created:20/05/2023").await, vec!["/mem/dates/report-2023.txt"]);
+    // Quoted text is searched as written
+    assert!(search_paths(&indexer, "\"modified:today\"").await.is_empty());
+
+    let error = indexer.search("report modified:someday").await.unwrap_err();
+    assert!(error.contains("modified:someday"), "{}", error);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::settings::IndexingConfig;
+use constella_core::SettingsManager;
+
+fn projects() -> std::sync::Arc<MemoryFileSystem> {
+    let fs = MemoryFileSystem::new();
+    fs.insert("/mem/web/src/app.js", "import leftpad");
+    fs.insert("/mem/web/node_modules/leftpad/index.js", "module.exports = leftpad");
+    fs.insert("/mem/web/node_modules/leftpad/README.md", "leftpad docs");
+    fs.insert("/mem/tool/target/debug/output.txt", "leftpad compiled");
+    fs
+}
+
+#[test]
+fn dependency_folders_are_matched_by_name() {
+    let config = IndexingConfig::default();
+    assert!(config.in_dependency_folder(Path::new("/code/web/node_modules/pkg/index.js")));
+    assert!(config.in_dependency_folder(Path::new("/code/app/.venv/lib/site.py")));
+    assert!(!config.in_dependency_folder(Path::new("/code/web/src/node_modules.md")));
+    assert!(!config.in_dependency_folder(Path::new("/code/web/targets/list.txt")));
+
+    let overridden = IndexingConfig {
+        dependency_content_roots: vec![PathBuf::from("/code/web")],
+        ..IndexingConfig::default()
+    };
+    assert!(!overridden.in_dependency_folder(Path::new("/code/web/node_modules/pkg/index.js")));
+    assert!(overridden.in_dependency_folder(Path::new("/code/api/node_modules/pkg/index.js")));
+}
+
+#[tokio::test]
+async fn dependency_folders_are_indexed_by_name_only() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer_with(projects());
+    indexer.start_indexing("/mem").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "leftpad").await, vec![
+        "/mem/web/node_modules/leftpad/README.md",
+        "/mem/web/node_modules/leftpad/index.js",
+        "/mem/web/src/app.js",
+    ]);
+    assert!(search_paths(&indexer, "compiled").await.is_empty());
+    assert_eq!(search_paths(&indexer, "output").await, vec!["/mem/tool/target/debug/output.txt"]);
+
+    let report = indexer.coverage("/mem/web").await.unwrap();
+    assert_eq!(report.dependency_folder, 2);
+}
+
+#[tokio::test]
+async fn roots_can_opt_back_in_to_dependency_content() {
+    let fixture = Fixture::new();
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.indexing.dependency_content_roots = vec![PathBuf::from("/mem/tool")])
+        .unwrap();
+    let indexer = fixture.indexer_with(projects());
+    indexer.start_indexing("/mem").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "compiled").await, vec!["/mem/tool/target/debug/output.txt"]);
+    assert!(search_paths(&indexer, "docs").await.is_empty());
+
+    let preview = indexer.preview_config_change(&IndexingConfig::default()).await.unwrap();
+    assert_eq!(preview.content_removed, 1);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::search::SearchOptions;
+
+const ROOT: &str = "/mem/project";
+const DAY: Duration = Duration::from_secs(86_400);
+
+async fn indexed_project(fixture: &Fixture) -> constella_core::IndexManager {
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/project/notes.txt", "notes");
+    memory.insert("/mem/project/src/main.txt", "notes");
+    memory.insert("/mem/project/vendor/lib/deep/nested/old.txt", "notes");
+    memory.insert_modified("/mem/project/archive.txt", "notes", SystemTime::now() - 3 * 365 * DAY);
+    memory.insert_modified("/mem/project/src/lastweek.txt", "notes", SystemTime::now() - 8 * DAY);
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+    indexer
+}
+
+#[tokio::test]
+async fn depth_counts_folders_from_the_indexed_root() {
+    let fixture = Fixture::new();
+    let indexer = indexed_project(&fixture).await;
+
+    assert_eq!(
+        search_paths(&indexer, "notes depth:1").await,
+        vec!["/mem/project/archive.txt", "/mem/project/notes.txt"],
+    );
+    assert_eq!(
+        search_paths(&indexer, "txt depth:<=2").await,
+        vec![
+            "/mem/project/archive.txt",
+            "/mem/project/notes.txt",
+            "/mem/project/src/lastweek.txt",
+            "/mem/project/src/main.txt",
+        ],
+    );
+    assert_eq!(search_paths(&indexer, "depth:>3").await, vec!["/mem/project/vendor/lib/deep/nested/old.txt"]);
+}
+
+#[tokio::test]
+async fn depth_counts_from_the_searched_root() {
+    let fixture = Fixture::new();
+    let indexer = indexed_project(&fixture).await;
+
+    let options = SearchOptions { root: Some("/mem/project/vendor".into()), ..SearchOptions::default() };
+    let paths: Vec<String> = indexer.search_with_options("notes depth:4", &options).await
+        .unwrap()
+        .into_iter()
+        .filter_map(|hit| hit["path"].as_str().map(str::to_string))
+        .collect();
+    assert_eq!(paths, vec!["/mem/project/vendor/lib/deep/nested/old.txt"]);
+
+    // The root scopes the search even without a depth filter
+    let options = SearchOptions { root: Some("/mem/project/src".into()), ..SearchOptions::default() };
+    let under_src = indexer.search_with_options("notes", &options).await.unwrap();
+    assert_eq!(under_src.len(), 2);
+}
+
+#[tokio::test]
+async fn age_filters_on_modified_time() {
+    let fixture = Fixture::new();
+    let indexer = indexed_project(&fixture).await;
+
+    assert_eq!(search_paths(&indexer, "notes age:>2y").await, vec!["/mem/project/archive.txt"]);
+    assert_eq!(search_paths(&indexer, "notes age:1w").await, vec!["/mem/project/src/lastweek.txt"]);
+    assert_eq!(
+        search_paths(&indexer, "notes age:<1d").await,
+        vec![
+            "/mem/project/notes.txt",
+            "/mem/project/src/main.txt",
+            "/mem/project/vendor/lib/deep/nested/old.txt",
+        ],
+    );
+    assert_eq!(
+        search_paths(&indexer, "notes age:1w..1y").await,
+        vec!["/mem/project/src/lastweek.txt"],
+    );
+
+    for malformed in ["age:2", "age:>2m", "depth:deep"] {
+        let error = indexer.search(malformed).await.unwrap_err();
+        assert!(error.contains(malformed), "{}", error);
+    }
+}
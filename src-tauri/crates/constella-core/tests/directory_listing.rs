@@ -0,0 +1,60 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::Fixture;
+use constella_core::indexing::listing::{DirectoryFilters, DirectoryListing, DirectorySort, SortBy};
+
+const ROOT: &str = "/mem/drive";
+
+fn names(listing: &DirectoryListing) -> Vec<&str> {
+    listing.entries.iter().map(|entry| entry.name.as_str()).collect()
+}
+
+#[tokio::test]
+async fn indexed_directories_list_files_and_subdirectories() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/drive/b.txt", "b");
+    memory.insert("/mem/drive/A.pdf", "aaa");
+    memory.insert("/mem/drive/photos/2023/beach.jpg", "jpg");
+    memory.insert("/mem/drive/work/plan.txt", "plan");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let listing = indexer.list_directory(ROOT, DirectorySort::default(), &DirectoryFilters::default()).await.unwrap();
+    assert!(listing.from_index);
+    assert_eq!(names(&listing), vec!["photos", "work", "A.pdf", "b.txt"]);
+    let pdf = &listing.entries[2];
+    assert_eq!((pdf.size, pdf.file_type.as_str(), pdf.is_dir), (Some(3), "pdf", false));
+    assert!(listing.entries[0].is_dir);
+
+    let by_size = DirectorySort { by: SortBy::Size, descending: true };
+    let files_only = DirectoryFilters { files_only: true, ..DirectoryFilters::default() };
+    let listing = indexer.list_directory(ROOT, by_size, &files_only).await.unwrap();
+    assert_eq!(names(&listing), vec!["A.pdf", "b.txt"]);
+
+    let pdfs = DirectoryFilters { extensions: vec![".PDF".to_string()], ..DirectoryFilters::default() };
+    let listing = indexer.list_directory(ROOT, DirectorySort::default(), &pdfs).await.unwrap();
+    assert_eq!(names(&listing), vec!["A.pdf"]);
+
+    let listing = indexer.list_directory("/mem/drive/photos", DirectorySort::default(), &DirectoryFilters::default()).await.unwrap();
+    assert_eq!(names(&listing), vec!["2023"]);
+}
+
+#[tokio::test]
+async fn unindexed_directories_are_read_from_disk() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/drive/indexed.txt", "x");
+    memory.insert("/mem/elsewhere/notes.txt", "hello");
+    memory.insert("/mem/elsewhere/inner/deep.txt", "x");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let listing = indexer.list_directory("/mem/elsewhere", DirectorySort::default(), &DirectoryFilters::default()).await.unwrap();
+    assert!(!listing.from_index);
+    assert_eq!(names(&listing), vec!["inner", "notes.txt"]);
+    assert_eq!(listing.entries[1].size, Some(5));
+
+    assert!(indexer.list_directory("/mem/nowhere", DirectorySort::default(), &DirectoryFilters::default()).await.is_err());
+}
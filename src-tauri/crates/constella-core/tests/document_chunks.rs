@@ -0,0 +1,145 @@
+mod common;
+
+use common::{doc_count, search_paths, Fixture};
+use constella_core::indexing::chunks::chunk_ranges;
+use constella_core::search::{ResultFields, SearchOptions};
+use constella_core::settings::IndexingConfig;
+use constella_core::watcher::ChangeType;
+use constella_core::SettingsManager;
+
+/// A log of about 20KB with one unusual line three quarters of the way in.
+fn log(unusual: &str) -> String {
+    (0..1000)
+        .map(|i| if i == 750 { format!("{:04} ERROR {}\n", i, unusual) } else { format!("{:04} INFO request ok\n", i) })
+        .collect()
+}
+
+fn chunk_small_files(fixture: &Fixture) {
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| {
+            settings.indexing = IndexingConfig {
+                content_max_file_size: 1024,
+                chunk_large_files: true,
+                chunk_size: 1024,
+                ..IndexingConfig::default()
+            }
+        })
+        .unwrap();
+}
+
+#[test]
+fn chunks_end_at_line_breaks() {
+    let text = b"alpha\nbeta\ngamma\n";
+    let ranges = chunk_ranges(text, 8);
+
+    assert_eq!(ranges, vec![0..6, 6..11, 11..17]);
+    assert_eq!(chunk_ranges("ééé".as_bytes(), 3), vec![0..2, 2..4, 4..6]);
+}
+
+#[tokio::test]
+async fn matches_in_large_files_point_to_their_chunk() {
+    let fixture = Fixture::new();
+    chunk_small_files(&fixture);
+    let contents = log("disk quota exceeded");
+    fixture.file("logs/app.log", &contents);
+    fixture.file("notes.txt", "a request for more disk");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let options = SearchOptions { fields: ResultFields::Snippets, ..SearchOptions::default() };
+    let results = indexer.search_with_options("quota", &options).await.unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["name"], "app.log");
+    let offset = results[0]["chunk"]["offset"].as_u64().unwrap() as usize;
+    let length = results[0]["chunk"]["length"].as_u64().unwrap() as usize;
+    assert!(contents[offset..offset + length].contains("disk quota exceeded"));
+    assert!(results[0]["snippet"].as_str().unwrap().contains("disk <b>quota</b> exceeded"));
+}
+
+#[tokio::test]
+async fn chunks_are_grouped_into_one_result_per_file() {
+    let fixture = Fixture::new();
+    chunk_small_files(&fixture);
+    fixture.file("logs/app.log", log("disk quota exceeded"));
+    fixture.file("notes.txt", "a request for more disk");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let root = fixture.root_str();
+    assert_eq!(search_paths(&indexer, "request").await, vec![format!("{}/logs/app.log", root), format!("{}/notes.txt", root)]);
+    assert_eq!(indexer.documents_under(fixture.root()).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn filters_apply_to_the_file_a_chunk_belongs_to() {
+    let fixture = Fixture::new();
+    chunk_small_files(&fixture);
+    fixture.file("logs/app.log", log("disk quota exceeded"));
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "quota size:>10kb").await.len(), 1);
+    assert!(search_paths(&indexer, "quota size:>1mb").await.is_empty());
+
+    let elsewhere = SearchOptions { root: Some(fixture.path("other")), ..SearchOptions::default() };
+    assert!(indexer.search_with_options("quota", &elsewhere).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn updated_files_drop_their_old_chunks() {
+    let fixture = Fixture::new();
+    chunk_small_files(&fixture);
+    let path = fixture.file("logs/app.log", log("disk quota exceeded"));
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    fixture.file("logs/app.log", log("connection refused by upstream"));
+    indexer.apply_changes(&[(path, ChangeType::Modified)]).await.unwrap();
+
+    assert!(search_paths(&indexer, "quota").await.is_empty());
+    assert_eq!(search_paths(&indexer, "upstream").await.len(), 1);
+}
+
+#[tokio::test]
+async fn indexing_a_folder_again_keeps_one_copy_of_each_chunk() {
+    let fixture = Fixture::new();
+    chunk_small_files(&fixture);
+    fixture.file("logs/app.log", log("disk quota exceeded"));
+    fixture.file("notes.txt", "a request for more disk");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let documents = doc_count(&indexer).await;
+    drop(indexer);
+
+    // Nothing on record says what changed since
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, documents);
+    assert_eq!(search_paths(&indexer, "quota").await.len(), 1);
+}
+
+#[tokio::test]
+async fn files_crossing_the_chunk_size_keep_only_their_current_documents() {
+    let fixture = Fixture::new();
+    chunk_small_files(&fixture);
+    fixture.file("logs/app.log", "0000 ERROR disk quota exceeded\n");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    fixture.file("logs/app.log", log("connection refused by upstream"));
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert!(search_paths(&indexer, "quota").await.is_empty());
+    assert_eq!(search_paths(&indexer, "upstream").await.len(), 1);
+
+    fixture.file("logs/app.log", "0000 ERROR disk quota exceeded\n");
+    drop(indexer);
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert!(search_paths(&indexer, "upstream").await.is_empty());
+    assert_eq!(doc_count(&indexer).await, 1);
+}
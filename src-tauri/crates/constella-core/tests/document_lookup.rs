@@ -0,0 +1,28 @@
+mod common;
+
+use common::Fixture;
+
+#[tokio::test]
+async fn documents_are_looked_up_in_order_with_gaps_for_unknown_paths() {
+    let fixture = Fixture::new();
+    let notes = fixture.file("notes.txt", "hello");
+    let report = fixture.file("docs/report.pdf", "0123456789");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let paths = vec![
+        report.to_string_lossy().to_string(),
+        fixture.path("missing.txt").to_string_lossy().to_string(),
+        notes.to_string_lossy().to_string(),
+    ];
+    let documents = indexer.get_documents(&paths).await.unwrap();
+
+    assert_eq!(documents.len(), 3);
+    let report_doc = documents[0].as_ref().unwrap();
+    assert_eq!(report_doc.path, paths[0]);
+    assert_eq!(report_doc.name, "report.pdf");
+    assert_eq!(report_doc.size, 10);
+    assert!(report_doc.modified > 0);
+    assert!(documents[1].is_none());
+    assert_eq!(documents[2].as_ref().unwrap().name, "notes.txt");
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::file_system::origin::domain;
+use constella_core::indexing::triage::{TriageCategory, TriageRules};
+use constella_core::SettingsManager;
+
+const ROOT: &str = "/mem/home";
+
+#[test]
+fn domains_are_read_from_urls() {
+    assert_eq!(domain("https://www.github.com/user/repo/releases").as_deref(), Some("github.com"));
+    assert_eq!(domain("http://user@files.example.org:8080/x?y").as_deref(), Some("files.example.org"));
+    assert_eq!(domain("about:internet"), None);
+}
+
+#[tokio::test]
+async fn triage_tags_sources_and_flags_stale_installers_and_archives() {
+    let fixture = Fixture::new();
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| {
+            settings.downloads_triage = TriageRules {
+                folder: Some("/mem/home/Downloads".into()),
+                // Everything counts as stale, since the memory files are brand new
+                stale_after_days: 0,
+                ..TriageRules::default()
+            }
+        })
+        .unwrap();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/home/Downloads/setup.exe", "0123456789");
+    memory.insert("/mem/home/Downloads/photos.zip", "01234");
+    memory.insert("/mem/home/Downloads/paper.pdf", "012");
+    memory.insert("/mem/home/Documents/tool.exe", "0123456789");
+    memory.set_origin("/mem/home/Downloads/setup.exe", "https://www.github.com/x/releases/setup.exe");
+    memory.set_origin("/mem/home/Downloads/paper.pdf", "https://arxiv.org/pdf/1234");
+    memory.set_origin("/mem/home/Documents/tool.exe", "https://github.com/y/tool.exe");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "source:github.com").await, vec!["/mem/home/Downloads/setup.exe"]);
+
+    let triage = indexer.downloads_triage().await.unwrap();
+    assert_eq!(triage.total_files, 3);
+    assert_eq!(triage.total_size, 18);
+    assert_eq!(triage.reclaimable_size, 15);
+    let stale: Vec<(&str, TriageCategory, Option<&str>)> = triage.stale.iter()
+        .map(|item| (item.path.as_str(), item.category, item.source_domain.as_deref()))
+        .collect();
+    assert_eq!(stale, vec![
+        ("/mem/home/Downloads/setup.exe", TriageCategory::Installer, Some("github.com")),
+        ("/mem/home/Downloads/photos.zip", TriageCategory::Archive, None),
+    ]);
+    let domains: Vec<(&str, usize)> = triage.by_domain.iter().map(|d| (d.domain.as_str(), d.files)).collect();
+    assert_eq!(domains, vec![("github.com", 1), ("", 1), ("arxiv.org", 1)]);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::Fixture;
+use constella_core::events::{Event, EVENT_SCHEMA_VERSION};
+use constella_core::jobs::{JobKind, JobManager};
+
+#[tokio::test]
+async fn events_share_one_versioned_envelope() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer();
+
+    let progress = Event::indexing_progress(indexer.get_state());
+    assert_eq!(progress.channel(), "indexing_progress");
+    let json = serde_json::to_value(&progress).unwrap();
+    assert_eq!(json["v"], EVENT_SCHEMA_VERSION);
+    assert_eq!(json["kind"], "indexing_progress");
+    assert!(json["job_id"].is_null());
+    assert_eq!(json["payload"]["state"], "idle");
+    assert_eq!(json["payload"]["stats"]["processed_files"], 0);
+}
+
+#[tokio::test]
+async fn job_updates_are_namespaced_by_job() {
+    let jobs = JobManager::new();
+    let id = jobs.submit(JobKind::Optimization, |_| async { Ok::<_, String>(None) });
+
+    let event = Event::job_update(jobs.get(id).unwrap());
+    assert_eq!(event.channel(), format!("job:{}", id));
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["kind"], "job_update");
+    assert_eq!(json["job_id"], id);
+    assert_eq!(json["payload"]["id"], id);
+    assert_eq!(json["payload"]["kind"], "optimization");
+}
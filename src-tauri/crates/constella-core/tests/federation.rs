@@ -0,0 +1,92 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::Fixture;
+use constella_core::indexing::federation::{federated_search, FederationMember, DEFAULT_LATENCY_BUDGET};
+use constella_core::search::{ResultFields, SearchOptions};
+use constella_core::IndexManager;
+
+async fn indexed(fixture: &Fixture) -> Arc<IndexManager> {
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    Arc::new(indexer)
+}
+
+fn members(work: &Arc<IndexManager>, home: &Arc<IndexManager>) -> Vec<FederationMember> {
+    vec![
+        FederationMember { label: "work".to_string(), index: work.clone() },
+        FederationMember { label: "home".to_string(), index: home.clone() },
+    ]
+}
+
+#[tokio::test]
+async fn results_from_every_index_are_merged_and_labelled() {
+    let (work_fixture, home_fixture) = (Fixture::new(), Fixture::new());
+    work_fixture.file("plan.txt", "launch plan");
+    work_fixture.file("budget.txt", "plan");
+    home_fixture.file("garden_plan.txt", "tomatoes");
+    let (work, home) = (indexed(&work_fixture).await, indexed(&home_fixture).await);
+
+    let response = federated_search(members(&work, &home), "plan", &SearchOptions::default(), DEFAULT_LATENCY_BUDGET)
+        .await
+        .unwrap();
+
+    assert!(!response.is_partial());
+    let mut answered = response.answered.clone();
+    answered.sort();
+    assert_eq!(answered, vec!["home", "work"]);
+    let mut labelled: Vec<(String, String)> = response.results.iter()
+        .map(|result| (result["index"].as_str().unwrap().to_string(), result["name"].as_str().unwrap().to_string()))
+        .collect();
+    labelled.sort();
+    assert_eq!(labelled, vec![
+        ("home".to_string(), "garden_plan.txt".to_string()),
+        ("work".to_string(), "budget.txt".to_string()),
+        ("work".to_string(), "plan.txt".to_string()),
+    ]);
+    let scores: Vec<f64> = response.results.iter().map(|result| result["score"].as_f64().unwrap()).collect();
+    assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+
+    let paths_only = SearchOptions { fields: ResultFields::Paths, ..SearchOptions::default() };
+    let response = federated_search(members(&work, &home), "plan", &paths_only, DEFAULT_LATENCY_BUDGET).await.unwrap();
+    let mut keys: Vec<&String> = response.results[0].as_object().unwrap().keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec!["id", "index", "path"]);
+}
+
+#[tokio::test]
+async fn failing_indexes_are_reported_alongside_the_rest() {
+    let (work_fixture, home_fixture) = (Fixture::new(), Fixture::new());
+    let taxes = work_fixture.file("taxes.txt", "return");
+    home_fixture.file("return.txt", "");
+    let (work, home) = (indexed(&work_fixture).await, indexed(&home_fixture).await);
+    work.add_to_collection("Tax 2023", &[taxes]).unwrap();
+
+    let options = SearchOptions { collection: Some("Tax 2023".to_string()), ..SearchOptions::default() };
+    let response = federated_search(members(&work, &home), "return", &options, DEFAULT_LATENCY_BUDGET).await.unwrap();
+
+    assert!(response.is_partial());
+    assert_eq!(response.answered, vec!["work"]);
+    assert_eq!(response.failed.len(), 1);
+    assert_eq!(response.failed[0].index, "home");
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.results[0]["name"], "taxes.txt");
+}
+
+#[tokio::test]
+async fn indexes_over_the_budget_are_left_out() {
+    let (work_fixture, home_fixture) = (Fixture::new(), Fixture::new());
+    work_fixture.file("plan.txt", "");
+    home_fixture.file("plan.txt", "");
+    let (work, home) = (indexed(&work_fixture).await, indexed(&home_fixture).await);
+
+    // Nothing can answer within no time at all
+    let response = federated_search(members(&work, &home), "plan", &SearchOptions::default(), Duration::ZERO).await.unwrap();
+
+    assert!(response.is_partial());
+    assert!(response.results.is_empty());
+    assert_eq!(response.timed_out, vec!["work", "home"]);
+    assert!(federated_search(Vec::new(), "plan", &SearchOptions::default(), Duration::ZERO).await.is_err());
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use common::Fixture;
+use constella_core::SettingsManager;
+
+#[tokio::test]
+async fn diff_retention_saved_in_settings_is_on_at_startup() {
+    let fixture = Fixture::new();
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.diff_retention_enabled = true)
+        .unwrap();
+    let path = fixture.file("notes.txt", "first draft\n");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    fixture.file("notes.txt", "second draft\n");
+    let report = indexer.get_file_diff(&path).unwrap();
+
+    assert_ne!(report.previous_hash, report.current_hash);
+    assert!(!report.diff.changes.is_empty());
+}
+
+#[tokio::test]
+async fn no_versions_are_retained_by_default() {
+    let fixture = Fixture::new();
+    let path = fixture.file("notes.txt", "first draft\n");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert!(indexer.get_file_diff(&path).is_err());
+}
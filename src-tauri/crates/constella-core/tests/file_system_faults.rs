@@ -0,0 +1,142 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::memory_fs::{Fault, FaultyFileSystem, MemoryFileSystem};
+use common::{doc_count, search_paths, Fixture};
+use constella_core::watcher::ChangeType;
+
+const ROOT: &str = "/mem/project";
+
+fn memory_tree() -> Arc<MemoryFileSystem> {
+    let fs = MemoryFileSystem::new();
+    fs.insert("/mem/project/src/main.rs", "fn main() {}");
+    fs.insert("/mem/project/src/lib.rs", "pub mod app;");
+    fs.insert("/mem/project/README.md", "# Project");
+    fs.insert("/mem/project/secret/keys.pem", "-----BEGIN-----");
+    fs.insert("/mem/elsewhere/outside.txt", "not under the root");
+    fs
+}
+
+#[tokio::test]
+async fn indexes_an_in_memory_tree() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer_with(memory_tree());
+
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 4);
+    assert_eq!(search_paths(&indexer, "readme").await, vec!["/mem/project/README.md"]);
+    assert!(search_paths(&indexer, "outside").await.is_empty());
+}
+
+#[tokio::test]
+async fn permission_denied_files_are_skipped() {
+    let fixture = Fixture::new();
+    let fs = FaultyFileSystem::new(memory_tree());
+    fs.inject("/mem/project/secret/keys.pem", Fault::PermissionDenied);
+    let indexer = fixture.indexer_with(fs);
+
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 3);
+    assert!(search_paths(&indexer, "keys").await.is_empty());
+    assert_eq!(indexer.get_state().state, "completed");
+}
+
+#[tokio::test]
+async fn unwalkable_entries_do_not_stop_the_scan() {
+    let fixture = Fixture::new();
+    let fs = FaultyFileSystem::new(memory_tree());
+    fs.inject("/mem/project/src/lib.rs", Fault::Unwalkable);
+    let indexer = fixture.indexer_with(fs);
+
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 3);
+    let state = indexer.get_state();
+    assert_eq!(state.total_files, 3);
+    assert_eq!(state.processed_files, 3);
+}
+
+#[tokio::test]
+async fn files_vanishing_mid_scan_are_skipped() {
+    let fixture = Fixture::new();
+    let fs = FaultyFileSystem::new(memory_tree());
+    fs.inject("/mem/project/src/main.rs", Fault::Vanished);
+    let indexer = fixture.indexer_with(fs);
+
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 3);
+    assert_eq!(indexer.get_state().processed_files, 3);
+}
+
+#[tokio::test]
+async fn vanished_files_are_removed_on_incremental_update() {
+    let fixture = Fixture::new();
+    let memory = memory_tree();
+    let fs = FaultyFileSystem::new(memory.clone());
+    let indexer = fixture.indexer_with(fs.clone());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    // Reported as modified, but gone before the indexer looks at it
+    fs.inject("/mem/project/README.md", Fault::Vanished);
+    let summary = indexer.apply_changes(&[
+        ("/mem/project/README.md".into(), ChangeType::Modified),
+    ]).await.unwrap();
+
+    assert_eq!(summary.removed, 1);
+    assert_eq!(summary.indexed, 0);
+    assert_eq!(doc_count(&indexer).await, 3);
+}
+
+#[tokio::test]
+async fn permission_errors_on_incremental_update_remove_the_document() {
+    let fixture = Fixture::new();
+    let fs = FaultyFileSystem::new(memory_tree());
+    let indexer = fixture.indexer_with(fs.clone());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    fs.inject("/mem/project/src/lib.rs", Fault::PermissionDenied);
+    let summary = indexer.apply_changes(&[
+        ("/mem/project/src/lib.rs".into(), ChangeType::Modified),
+    ]).await.unwrap();
+
+    assert_eq!(summary.removed, 1);
+    assert!(search_paths(&indexer, "lib").await.is_empty());
+}
+
+#[tokio::test]
+async fn slow_io_still_completes() {
+    let fixture = Fixture::new();
+    let fs = FaultyFileSystem::new(memory_tree());
+    fs.inject("/mem/project/src/main.rs", Fault::Slow(Duration::from_millis(20)));
+    fs.inject("/mem/project/README.md", Fault::Slow(Duration::from_millis(20)));
+    let indexer = fixture.indexer_with(fs);
+
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 4);
+}
+
+#[tokio::test]
+async fn in_memory_changes_are_picked_up_incrementally() {
+    let fixture = Fixture::new();
+    let memory = memory_tree();
+    let indexer = fixture.indexer_with(memory.clone());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    memory.insert("/mem/project/src/new_module.rs", "pub fn added() {}");
+    memory.remove("/mem/project/README.md");
+    let summary = indexer.apply_changes(&[
+        ("/mem/project/src/new_module.rs".into(), ChangeType::Created),
+        ("/mem/project/README.md".into(), ChangeType::Deleted),
+    ]).await.unwrap();
+
+    assert_eq!(summary.indexed, 1);
+    assert_eq!(summary.removed, 1);
+    assert_eq!(search_paths(&indexer, "new_module").await.len(), 1);
+    assert!(search_paths(&indexer, "readme").await.is_empty());
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::memory_fs::{Fault, FaultyFileSystem, MemoryFileSystem};
+use common::Fixture;
+
+const ROOT: &str = "/mem/drive";
+
+#[tokio::test]
+async fn finds_indexed_copies_of_a_file() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/drive/a/report.txt", "same content");
+    memory.insert("/mem/drive/b/report copy.txt", "same content");
+    memory.insert("/mem/drive/b/other.txt", "same length!");
+    memory.insert("/mem/drive/c/empty.txt", "");
+    memory.insert("/mem/drive/c/empty too.txt", "");
+    let indexer = fixture.indexer_with(memory.clone());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let identical = indexer.find_identical("/mem/drive/a/report.txt").await.unwrap();
+    assert_eq!(identical, vec!["/mem/drive/b/report copy.txt"]);
+
+    // Files outside the index can be looked up too
+    memory.insert("/mem/elsewhere/report.txt", "same content");
+    assert_eq!(
+        indexer.find_identical("/mem/elsewhere/report.txt").await.unwrap(),
+        vec!["/mem/drive/a/report.txt", "/mem/drive/b/report copy.txt"],
+    );
+
+    assert!(indexer.find_identical("/mem/drive/c/empty.txt").await.unwrap().is_empty());
+    assert!(indexer.find_identical("/mem/drive/missing.txt").await.is_err());
+}
+
+#[tokio::test]
+async fn unchanged_candidates_are_not_read_again() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/drive/a/report.txt", "same content");
+    memory.insert("/mem/drive/b/report copy.txt", "same content");
+    let faulty = FaultyFileSystem::new(memory);
+    let indexer = fixture.indexer_with(faulty.clone());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(indexer.find_identical("/mem/drive/a/report.txt").await.unwrap(), vec!["/mem/drive/b/report copy.txt"]);
+
+    // The copy's hash is remembered, so it matches even once it can't be read
+    faulty.inject("/mem/drive/b/report copy.txt", Fault::PermissionDenied);
+    assert_eq!(indexer.find_identical("/mem/drive/a/report.txt").await.unwrap(), vec!["/mem/drive/b/report copy.txt"]);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::indexing::repos::branch_from_head;
+use constella_core::SettingsManager;
+
+const ROOT: &str = "/mem/code";
+
+fn workspace() -> std::sync::Arc<MemoryFileSystem> {
+    let fs = MemoryFileSystem::new();
+    fs.insert("/mem/code/constella/.git/HEAD", "ref: refs/heads/main\n");
+    fs.insert("/mem/code/constella/src/notes.txt", "release notes");
+    fs.insert("/mem/code/other/.git/HEAD", "0123456789abcdef0123456789abcdef01234567\n");
+    fs.insert("/mem/code/other/notes.txt", "meeting notes");
+    fs.insert("/mem/code/loose/notes.txt", "loose notes");
+    fs
+}
+
+#[test]
+fn branches_are_read_from_head() {
+    assert_eq!(branch_from_head("ref: refs/heads/feature/x\n").as_deref(), Some("feature/x"));
+    assert_eq!(branch_from_head("0123456789abcdef").as_deref(), Some("0123456"));
+    assert_eq!(branch_from_head("garbage"), None);
+}
+
+#[tokio::test]
+async fn results_inside_a_repository_carry_its_details() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer_with(workspace());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let results = indexer.search("notes").await.unwrap();
+    let repo = |path: &str| results.iter().find(|result| result["path"] == path).unwrap()["repo"].clone();
+
+    let constella = repo("/mem/code/constella/src/notes.txt");
+    assert_eq!(constella["name"], "constella");
+    assert_eq!(constella["root"], "/mem/code/constella");
+    assert_eq!(constella["relative_path"], "src/notes.txt");
+    assert_eq!(constella["branch"], "main");
+    assert_eq!(repo("/mem/code/other/notes.txt")["branch"], "0123456");
+    assert!(repo("/mem/code/loose/notes.txt").is_null());
+}
+
+#[tokio::test]
+async fn repo_filter_narrows_to_one_repository() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer_with(workspace());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "notes repo:Constella").await, vec!["/mem/code/constella/src/notes.txt"]);
+    assert_eq!(search_paths(&indexer, "notes repo:other").await, vec!["/mem/code/other/notes.txt"]);
+    assert!(search_paths(&indexer, "notes repo:missing").await.is_empty());
+}
+
+#[tokio::test]
+async fn gitignore_is_honored_only_for_opted_in_repositories() {
+    let fixture = Fixture::new();
+    let fs = workspace();
+    fs.insert("/mem/code/constella/.gitignore", "# build output\ntarget/\n*.log\n");
+    fs.insert("/mem/code/constella/target/debug/app.txt", "build notes");
+    fs.insert("/mem/code/constella/run.log", "log notes");
+    fs.insert("/mem/code/other/.gitignore", "*.log\n");
+    fs.insert("/mem/code/other/run.log", "log notes");
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.indexing.gitignore_repos = vec![PathBuf::from("/mem/code/constella")])
+        .unwrap();
+    let indexer = fixture.indexer_with(fs);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "notes").await, vec![
+        "/mem/code/constella/src/notes.txt",
+        "/mem/code/loose/notes.txt",
+        "/mem/code/other/notes.txt",
+        "/mem/code/other/run.log",
+    ]);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::indexing::{IndexManager, IndexOptions};
+use constella_core::labeling::{heuristic_labels, ImageClassifier};
+use constella_core::SettingsManager;
+
+/// Reads the labels out of the test images, which are just the labels.
+struct ContentsClassifier;
+
+impl ImageClassifier for ContentsClassifier {
+    fn classify(&self, image: &[u8]) -> Result<Vec<String>, String> {
+        Ok(String::from_utf8_lossy(image).split(',').map(str::to_string).collect())
+    }
+}
+
+fn photos() -> Arc<MemoryFileSystem> {
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/photos/2023-03 groceries.jpg", "Receipt");
+    memory.insert("/mem/photos/2024-01 groceries.jpg", "receipt");
+    memory.insert("/mem/photos/2023-07 hike.jpg", "landscape, person");
+    memory.insert("/mem/photos/notes 2023.txt", "receipt");
+    memory
+}
+
+fn labeling_indexer(fixture: &Fixture, memory: Arc<MemoryFileSystem>) -> IndexManager {
+    let settings = Arc::new(SettingsManager::load(fixture.data_dir().join("settings.json")));
+    let options = IndexOptions { fs: memory, classifier: Some(Arc::new(ContentsClassifier)), ..IndexOptions::default() };
+    IndexManager::with_options(fixture.data_dir(), settings, options).unwrap()
+}
+
+fn enable_labeling(fixture: &Fixture) {
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.image_labeling.enabled = true)
+        .unwrap();
+}
+
+#[test]
+fn recognized_text_suggests_labels() {
+    let receipt = "CORNER SHOP\nMilk 1.20\nSubtotal 1.20\nTax 0.10\nTotal 1.30\nCard";
+    assert_eq!(heuristic_labels(true, Some(receipt)), vec!["screenshot", "receipt"]);
+    assert_eq!(heuristic_labels(false, Some(&"word ".repeat(100))), vec!["document"]);
+    assert!(heuristic_labels(false, Some("total")).is_empty());
+}
+
+#[tokio::test]
+async fn label_filter_finds_labeled_images() {
+    let fixture = Fixture::new();
+    enable_labeling(&fixture);
+    let indexer = labeling_indexer(&fixture, photos());
+    indexer.start_indexing("/mem/photos").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "label:receipt 2023").await, vec!["/mem/photos/2023-03 groceries.jpg"]);
+    assert_eq!(search_paths(&indexer, "label:person label:landscape").await, vec!["/mem/photos/2023-07 hike.jpg"]);
+    assert!(search_paths(&indexer, "label:person label:receipt").await.is_empty());
+
+    let results = indexer.search("hike").await.unwrap();
+    assert_eq!(results[0]["labels"], serde_json::json!(["landscape", "person"]));
+}
+
+#[tokio::test]
+async fn images_are_unlabeled_while_labeling_is_off() {
+    let fixture = Fixture::new();
+    let indexer = labeling_indexer(&fixture, photos());
+    indexer.start_indexing("/mem/photos").await.unwrap();
+
+    assert!(search_paths(&indexer, "label:receipt").await.is_empty());
+    assert!(indexer.search("hike").await.unwrap()[0].get("labels").is_none());
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use common::Fixture;
+use constella_core::search::incognito::IncognitoSessions;
+use constella_core::search::SearchOptions;
+
+#[test]
+fn sessions_are_per_window() {
+    let sessions = IncognitoSessions::new();
+    sessions.set("main", true);
+    assert!(sessions.is_incognito("main"));
+    assert!(!sessions.is_incognito("search-2"));
+    sessions.set("main", false);
+    assert!(!sessions.is_incognito("main"));
+}
+
+#[tokio::test]
+async fn incognito_searches_stay_out_of_the_history() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer();
+
+    let incognito = SearchOptions { incognito: true, ..SearchOptions::default() };
+    assert!(indexer.search_with_options("secret plans", &incognito).await.unwrap().is_empty());
+    assert!(indexer.zero_result_queries().await.unwrap().is_empty());
+
+    indexer.search("anything").await.unwrap();
+    let report = indexer.zero_result_queries().await.unwrap();
+    assert_eq!(report.iter().map(|entry| entry.query.as_str()).collect::<Vec<_>>(), vec!["anything"]);
+}
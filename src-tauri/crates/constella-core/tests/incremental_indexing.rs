@@ -0,0 +1,77 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{doc_count, search_paths, Fixture};
+use constella_core::SettingsManager;
+
+const ROOT: &str = "/mem/project";
+
+#[tokio::test]
+async fn second_runs_only_touch_what_changed() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/project/kept.txt", "unchanged notes");
+    memory.insert("/mem/project/edited.txt", "first draft");
+    memory.insert("/mem/project/deleted.txt", "old minutes");
+    let indexer = fixture.indexer_with(memory.clone());
+    indexer.start_indexing(ROOT).await.unwrap();
+    assert_eq!(indexer.get_state().skipped_files, 0);
+
+    memory.insert("/mem/project/edited.txt", "second revision");
+    memory.remove("/mem/project/deleted.txt");
+    memory.insert("/mem/project/added.txt", "new agenda");
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let state = indexer.get_state();
+    assert_eq!((state.processed_files, state.total_files, state.skipped_files), (3, 3, 1));
+    assert_eq!(doc_count(&indexer).await, 3);
+    assert_eq!(search_paths(&indexer, "unchanged").await, vec!["/mem/project/kept.txt"]);
+    assert!(search_paths(&indexer, "draft").await.is_empty());
+    assert_eq!(search_paths(&indexer, "revision").await, vec!["/mem/project/edited.txt"]);
+    assert!(search_paths(&indexer, "minutes").await.is_empty());
+    assert_eq!(search_paths(&indexer, "agenda").await, vec!["/mem/project/added.txt"]);
+}
+
+#[tokio::test]
+async fn changed_settings_reindex_everything() {
+    let fixture = Fixture::new();
+    let notes = fixture.file("notes.txt", "quarterly plan");
+    fixture.file("todo.txt", "call the bank");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let states = indexer.change_tracker().export_states().await;
+    drop(indexer);
+
+    // What was checked survives a restart
+    let indexer = fixture.indexer();
+    indexer.change_tracker().import_states(states).await;
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    assert_eq!(indexer.get_state().skipped_files, 2);
+    let states = indexer.change_tracker().export_states().await;
+    drop(indexer);
+
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.indexing.content_max_file_size = 4)
+        .unwrap();
+    let indexer = fixture.indexer();
+    indexer.change_tracker().import_states(states).await;
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let state = indexer.get_state();
+    assert_eq!((state.processed_files, state.skipped_files), (2, 0));
+    assert!(search_paths(&indexer, "quarterly").await.is_empty());
+    assert_eq!(search_paths(&indexer, "notes").await, vec![notes.to_string_lossy().into_owned()]);
+}
+
+#[tokio::test]
+async fn indexing_another_folder_replaces_the_first() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/project/notes.txt", "project notes");
+    memory.insert("/mem/archive/notes.txt", "archive notes");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+    indexer.start_indexing("/mem/archive").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "notes").await, vec!["/mem/archive/notes.txt"]);
+    assert_eq!(indexer.indexed_roots(), vec![std::path::PathBuf::from("/mem/archive")]);
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::Fixture;
+use constella_core::indexing::health::RecommendedAction;
+
+const ROOT: &str = "/mem/project";
+
+#[tokio::test]
+async fn health_scores_the_index_and_recommends_fixes() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    for i in 0..120 {
+        memory.insert(format!("/mem/project/node_modules/pkg{}/index.js", i), "module.exports = {}");
+    }
+    for i in 0..10 {
+        memory.insert(format!("/mem/project/notes/{}.txt", i), "meeting notes");
+    }
+    let indexer = fixture.indexer_with(memory.clone());
+    assert!(indexer.last_health().is_none());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let healthy = indexer.check_health().await.unwrap();
+    assert_eq!(healthy.coverage, 1.0);
+    assert_eq!(healthy.freshness, 1.0);
+    assert_eq!(healthy.indexed_documents, 130);
+    let exclude = healthy.recommendations.iter()
+        .find(|recommendation| recommendation.action == RecommendedAction::Exclude { pattern: "**/node_modules/**".to_string() })
+        .expect("dependency folder flagged");
+    assert!(exclude.title.starts_with("Exclude node_modules (saves ~"));
+    assert!(exclude.saves_bytes.unwrap() > 0);
+
+    // Files deleted behind the index's back make it stale
+    for i in 0..10 {
+        memory.remove(format!("/mem/project/notes/{}.txt", i));
+    }
+    let stale = indexer.check_health().await.unwrap();
+    assert!(stale.freshness < 1.0);
+    assert!(stale.score < healthy.score);
+    assert!(stale.recommendations.iter().any(|recommendation| recommendation.action == RecommendedAction::Reconcile { root: ROOT.into() }));
+    assert_eq!(indexer.get_recommendations().await.unwrap(), stale.recommendations);
+}
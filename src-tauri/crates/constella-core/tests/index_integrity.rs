@@ -0,0 +1,69 @@
+mod common;
+
+use common::Fixture;
+use constella_core::indexing::integrity::IntegrityStatus;
+use constella_core::SettingsManager;
+
+#[tokio::test]
+async fn manifest_is_opt_in() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer();
+    assert_eq!(indexer.verify_index_integrity().unwrap().status, IntegrityStatus::Disabled);
+    assert_eq!(indexer.set_index_integrity(true).unwrap().status, IntegrityStatus::Intact);
+}
+
+#[tokio::test]
+async fn changed_index_files_and_manifests_are_caught() {
+    let fixture = Fixture::new();
+    fixture.file("docs/a.txt", "alpha");
+    fixture.file("docs/b.txt", "beta");
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.index_integrity_manifest = true)
+        .unwrap();
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.path("docs").to_string_lossy()).await.unwrap();
+    indexer.release_writer().await.unwrap();
+
+    let report = indexer.verify_index_integrity().unwrap();
+    assert_eq!(report.status, IntegrityStatus::Intact);
+    assert!(report.checked_files > 1);
+
+    // Flip one byte of a segment file, keeping its size
+    let segment = std::fs::read_dir(fixture.data_dir().join("search_index")).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            !name.starts_with('.') && name != "meta.json" && std::fs::metadata(path).unwrap().len() > 0
+        })
+        .unwrap();
+    let mut bytes = std::fs::read(&segment).unwrap();
+    bytes[0] ^= 0xff;
+    std::fs::write(&segment, bytes).unwrap();
+
+    let report = indexer.verify_index_integrity().unwrap();
+    assert_eq!(report.status, IntegrityStatus::Mismatch);
+    assert_eq!(report.changed, vec![segment.file_name().unwrap().to_string_lossy().into_owned()]);
+
+    // Rewriting the manifest to match doesn't help without the key
+    let manifest_path = fixture.data_dir().join("index_manifest.json");
+    let manifest = std::fs::read_to_string(&manifest_path).unwrap();
+    std::fs::write(&manifest_path, manifest.replacen("\"hash\": \"", "\"hash\": \"0", 1)).unwrap();
+    assert_eq!(indexer.verify_index_integrity().unwrap().status, IntegrityStatus::InvalidSignature);
+}
+
+#[tokio::test]
+async fn scheduled_rebuilds_index_every_file_again() {
+    let fixture = Fixture::new();
+    fixture.file("docs/a.txt", "alpha");
+    fixture.file("docs/b.txt", "beta");
+    let docs = fixture.path("docs").to_string_lossy().into_owned();
+    let indexer = fixture.indexer();
+    indexer.start_indexing(&docs).await.unwrap();
+    indexer.start_indexing(&docs).await.unwrap();
+    assert_eq!(indexer.get_state().skipped_files, 2);
+
+    indexer.schedule_rebuild().unwrap();
+    indexer.start_indexing(&docs).await.unwrap();
+    assert_eq!(indexer.get_state().skipped_files, 0);
+    assert!(!indexer.rebuild_scheduled());
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{search_paths, Fixture};
+use constella_core::utils;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn moved_index_is_used_from_its_new_folder_after_a_restart() {
+    let fixture = Fixture::new();
+    let report = fixture.file("report.txt", "quarterly numbers");
+    let disk = TempDir::new().unwrap();
+    let new_location = disk.path().join("constella-index");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let mut reported = Vec::new();
+    let moved = indexer.move_index(&new_location, |copied, total| {
+        reported.push((copied, total));
+        true
+    }).await.unwrap();
+    assert!(moved.restart_required);
+    assert!(moved.files > 0);
+    assert_eq!(reported.last(), Some(&(moved.bytes, moved.bytes)));
+    assert!(new_location.join("meta.json").exists());
+    // Still answering from the old files until the restart
+    assert_eq!(search_paths(&indexer, "quarterly").await, vec![report.to_string_lossy().into_owned()]);
+    assert!(indexer.move_index(&disk.path().join("again"), |_, _| true).await.is_err());
+    drop(indexer);
+
+    let indexer = fixture.indexer();
+    assert_eq!(indexer.index_dir(), Some(new_location.as_path()));
+    assert!(!fixture.data_dir().join("search_index").exists());
+    assert_eq!(search_paths(&indexer, "quarterly").await, vec![report.to_string_lossy().into_owned()]);
+}
+
+#[tokio::test]
+async fn failed_moves_leave_the_index_where_it_was() {
+    let fixture = Fixture::new();
+    fixture.file("notes.txt", "meeting notes");
+    let disk = TempDir::new().unwrap();
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let old_location = fixture.data_dir().join("search_index");
+
+    let taken = disk.path().join("taken");
+    std::fs::create_dir_all(&taken).unwrap();
+    std::fs::write(taken.join("other.txt"), "not ours").unwrap();
+    assert!(indexer.move_index(&taken, |_, _| true).await.is_err());
+    assert!(taken.join("other.txt").exists());
+    assert!(indexer.move_index(&old_location.join("inside"), |_, _| true).await.is_err());
+
+    // Cancelled halfway: the copy goes, the settings and the writer come back
+    let cancelled = disk.path().join("cancelled");
+    let error = indexer.move_index(&cancelled, |_, _| false).await.unwrap_err();
+    assert!(error.contains("cancelled"));
+    assert!(!cancelled.exists());
+    assert!(!indexer.writes_suspended());
+    fixture.file("later.txt", "meeting agenda");
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    assert_eq!(search_paths(&indexer, "meeting").await.len(), 2);
+    drop(indexer);
+
+    let indexer = fixture.indexer();
+    assert_eq!(indexer.index_dir(), Some(old_location.as_path()));
+    assert_eq!(search_paths(&indexer, "meeting").await.len(), 2);
+}
+
+#[test]
+fn an_index_in_the_platform_data_root_moves_into_the_app_folder() {
+    let root = TempDir::new().unwrap();
+    std::fs::create_dir_all(root.path().join("search_index")).unwrap();
+    std::fs::write(root.path().join("search_index/meta.json"), "{}").unwrap();
+    std::fs::write(root.path().join("settings.json"), "{}").unwrap();
+    std::fs::create_dir_all(root.path().join("versions")).unwrap();
+    let app_dir = root.path().join(utils::APP_IDENTIFIER);
+
+    assert_eq!(utils::migrate_legacy_data(app_dir.clone(), root.path()), app_dir);
+    assert!(app_dir.join("search_index/meta.json").exists());
+    assert!(app_dir.join("settings.json").exists());
+    assert!(!root.path().join("search_index").exists());
+    // Generic names may be another app's, so they're left alone
+    assert!(root.path().join("versions").exists());
+
+    // Once moved, nothing in the root is looked at again
+    std::fs::create_dir_all(root.path().join("search_index")).unwrap();
+    std::fs::write(root.path().join("search_index/meta.json"), "{}").unwrap();
+    assert_eq!(utils::migrate_legacy_data(app_dir.clone(), root.path()), app_dir);
+    assert!(root.path().join("search_index/meta.json").exists());
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::{search_paths, Fixture};
+use constella_core::indexing::upgrade::{release_index_format, UpdateCheck, INDEX_FORMAT_VERSION};
+
+#[test]
+fn releases_state_their_index_format_in_the_notes() {
+    assert_eq!(release_index_format("Faster search\n\nIndex format: 7"), Some(7));
+    assert_eq!(release_index_format("- index FORMAT: 3\n- Fixes"), Some(3));
+    assert_eq!(release_index_format("Reformatted the index format: docs"), None);
+    assert_eq!(release_index_format("Bug fixes"), None);
+
+    let same = UpdateCheck::new("0.1.0".into(), "0.2.0".into(), Some(format!("Index format: {}", INDEX_FORMAT_VERSION)), None);
+    assert_eq!(same.rebuild_required, Some(false));
+    let newer = UpdateCheck::new("0.1.0".into(), "0.2.0".into(), Some(format!("Index format: {}", INDEX_FORMAT_VERSION + 1)), None);
+    assert_eq!((newer.index_format, newer.rebuild_required), (Some(INDEX_FORMAT_VERSION + 1), Some(true)));
+    let silent = UpdateCheck::new("0.1.0".into(), "0.2.0".into(), None, None);
+    assert_eq!((silent.index_format, silent.rebuild_required), (None, None));
+    let unstated = UpdateCheck::new("0.1.0".into(), "0.2.0".into(), Some("Bug fixes".into()), None);
+    assert_eq!(unstated.rebuild_required, None);
+}
+
+#[tokio::test]
+async fn indexes_in_another_format_are_rebuilt_by_the_next_full_run() {
+    let fixture = Fixture::new();
+    let plan = fixture.file("plan.txt", "launch plan");
+    let indexer = fixture.indexer();
+    assert!(!indexer.rebuild_scheduled());
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    drop(indexer);
+
+    // As left behind by a build with another format
+    std::fs::write(fixture.data_dir().join("index_format"), (INDEX_FORMAT_VERSION + 1).to_string()).unwrap();
+    let indexer = fixture.indexer();
+    assert!(indexer.rebuild_scheduled());
+    assert_eq!(search_paths(&indexer, "launch").await, vec![plan.to_string_lossy().into_owned()]);
+    drop(indexer);
+
+    // Still scheduled until a run completes
+    let indexer = fixture.indexer();
+    assert!(indexer.rebuild_scheduled());
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    assert!(!indexer.rebuild_scheduled());
+}
+
+#[tokio::test]
+async fn rebuilds_can_be_scheduled_ahead_of_an_update() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer();
+    indexer.schedule_rebuild().unwrap();
+    drop(indexer);
+
+    let indexer = fixture.indexer();
+    assert!(indexer.rebuild_scheduled());
+    indexer.unschedule_rebuild();
+    assert!(!indexer.rebuild_scheduled());
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use common::{doc_count, search_paths, Fixture};
+use constella_core::watcher::ChangeType;
+
+fn sample_tree(fixture: &Fixture) {
+    fixture.file("notes/alpha.txt", "first");
+    fixture.file("notes/beta.md", "second");
+    fixture.sized_file("media/large.bin", 64 * 1024);
+    fixture.sized_file("media/empty.bin", 0);
+    fixture.file("deep/a/b/c/gamma.rs", "fn main() {}");
+}
+
+#[tokio::test]
+async fn full_index_counts_every_visible_file() {
+    let fixture = Fixture::new();
+    sample_tree(&fixture);
+    fixture.file(".hidden", "skipped");
+
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 5);
+    let state = indexer.get_state();
+    assert_eq!(state.state, "completed");
+    assert_eq!(state.total_files, 5);
+    assert_eq!(state.processed_files, 5);
+}
+
+#[tokio::test]
+async fn indexed_files_are_searchable_by_name() {
+    let fixture = Fixture::new();
+    sample_tree(&fixture);
+
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert_eq!(
+        search_paths(&indexer, "gamma").await,
+        vec![fixture.path("deep/a/b/c/gamma.rs").to_string_lossy().into_owned()]
+    );
+    assert!(search_paths(&indexer, "nonexistent").await.is_empty());
+}
+
+#[tokio::test]
+async fn sizes_are_recorded_exactly() {
+    let fixture = Fixture::new();
+    sample_tree(&fixture);
+
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let mut files = indexer.documents_under(fixture.path("media")).await.unwrap();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    let sizes: Vec<u64> = files.iter().map(|f| f.size).collect();
+    assert_eq!(sizes, vec![0, 64 * 1024]);
+}
+
+#[tokio::test]
+async fn non_ascii_names_are_indexed_and_searchable() {
+    let fixture = Fixture::new();
+    fixture.file("café.txt", "latin-1 range");
+    fixture.file("日本語/ドキュメント.txt", "cjk");
+    fixture.file("emoji 🚀 launch.md", "astral plane");
+
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 3);
+    assert_eq!(search_paths(&indexer, "café").await.len(), 1);
+    assert_eq!(search_paths(&indexer, "launch").await.len(), 1);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn symlinks_are_followed() {
+    let fixture = Fixture::new();
+    let outside = Fixture::new();
+    outside.file("linked/target.txt", "outside the root");
+    fixture.file("real.txt", "inside");
+    fixture.symlink(outside.path("linked"), "via-link");
+    fixture.symlink(fixture.path("real.txt"), "alias.txt");
+
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 3);
+    assert_eq!(
+        search_paths(&indexer, "target").await,
+        vec![fixture.path("via-link/target.txt").to_string_lossy().into_owned()]
+    );
+}
+
+#[tokio::test]
+async fn reindexing_replaces_instead_of_duplicating() {
+    let fixture = Fixture::new();
+    sample_tree(&fixture);
+
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 5);
+}
+
+#[tokio::test]
+async fn index_survives_reopen() {
+    let fixture = Fixture::new();
+    sample_tree(&fixture);
+
+    {
+        let indexer = fixture.indexer();
+        indexer.start_indexing(fixture.root_str()).await.unwrap();
+        indexer.release_writer().await.unwrap();
+    }
+
+    let reopened = fixture.indexer();
+    assert_eq!(doc_count(&reopened).await, 5);
+    assert_eq!(search_paths(&reopened, "alpha").await.len(), 1);
+}
+
+#[tokio::test]
+async fn incremental_changes_update_only_what_changed() {
+    let fixture = Fixture::new();
+    sample_tree(&fixture);
+
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let created = fixture.file("notes/delta.txt", "new");
+    let modified = fixture.file("notes/alpha.txt", "first, now longer");
+    let untouched = fixture.path("notes/beta.md");
+    fixture.remove("media/empty.bin");
+
+    let summary = indexer.apply_changes(&[
+        (created, ChangeType::Created),
+        (modified, ChangeType::Modified),
+        (untouched, ChangeType::Modified),
+        (fixture.path("media/empty.bin"), ChangeType::Deleted),
+    ]).await.unwrap();
+
+    assert_eq!(summary.indexed, 2);
+    assert_eq!(summary.skipped, 1);
+    assert_eq!(summary.removed, 1);
+    assert_eq!(doc_count(&indexer).await, 5);
+    assert_eq!(search_paths(&indexer, "delta").await.len(), 1);
+    assert!(search_paths(&indexer, "empty").await.is_empty());
+    assert_eq!(search_paths(&indexer, "alpha").await.len(), 1);
+}
+
+#[tokio::test]
+async fn renames_move_the_document() {
+    let fixture = Fixture::new();
+    sample_tree(&fixture);
+
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let from = fixture.path("notes/beta.md");
+    let to = fixture.path("notes/renamed.md");
+    std::fs::rename(&from, &to).unwrap();
+    indexer.apply_changes(&[(to, ChangeType::Renamed(from))]).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 5);
+    assert!(search_paths(&indexer, "beta").await.is_empty());
+    assert_eq!(search_paths(&indexer, "renamed").await.len(), 1);
+}
+
+#[tokio::test]
+async fn changes_queue_while_writes_are_suspended() {
+    let fixture = Fixture::new();
+    sample_tree(&fixture);
+
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    indexer.suspend_writes().await.unwrap();
+
+    let created = fixture.file("notes/queued.txt", "later");
+    let summary = indexer.apply_changes(&[(created, ChangeType::Created)]).await.unwrap();
+    assert_eq!(summary.indexed, 0);
+    assert!(search_paths(&indexer, "queued").await.is_empty());
+    assert!(indexer.start_indexing(fixture.root_str()).await.is_err());
+
+    let summary = indexer.resume_writes().await.unwrap();
+    assert_eq!(summary.indexed, 1);
+    assert_eq!(search_paths(&indexer, "queued").await.len(), 1);
+}
+
+#[tokio::test]
+async fn empty_directory_completes_without_documents() {
+    let fixture = Fixture::new();
+    fixture.dir("nothing/here");
+
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 0);
+    assert_eq!(indexer.get_state().state, "completed");
+}
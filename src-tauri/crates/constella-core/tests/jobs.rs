@@ -0,0 +1,113 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::memory_fs::{Fault, FaultyFileSystem, MemoryFileSystem};
+use common::{doc_count, Fixture};
+use constella_core::jobs::{operations, JobId, JobInfo, JobKind, JobManager, JobProgress, JobStatus};
+
+const ROOT: &str = "/mem/drive";
+
+async fn wait_for_finish(jobs: &JobManager, id: JobId) -> JobInfo {
+    for _ in 0..200 {
+        let job = jobs.get(id).unwrap();
+        if job.status.is_finished() {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    }
+    panic!("job {} never finished", id);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn indexing_job_reports_progress_and_completes() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/drive/a.txt", "a");
+    memory.insert("/mem/drive/b.txt", "b");
+    let indexer = Arc::new(fixture.indexer_with(memory));
+    let jobs = JobManager::new();
+
+    let id = {
+        let indexer = indexer.clone();
+        jobs.submit(JobKind::Indexing, move |context| async move {
+            operations::run_indexing(&context, &indexer, ROOT).await.map(|_| None)
+        })
+    };
+    let job = wait_for_finish(&jobs, id).await;
+
+    assert_eq!(job.status, JobStatus::Completed);
+    match job.progress {
+        JobProgress::Indexing { processed_files, total_files, .. } => assert_eq!((processed_files, total_files), (2, 2)),
+        other => panic!("unexpected progress {:?}", other),
+    }
+    assert_eq!(doc_count(&indexer).await, 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn cancelling_stops_the_running_job_and_skips_queued_ones() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    for i in 0..20 {
+        memory.insert(format!("/mem/drive/file_{:02}.txt", i), "x");
+    }
+    let fs = FaultyFileSystem::new(memory);
+    fs.inject("/mem/drive/file_00.txt", Fault::Slow(Duration::from_millis(300)));
+    let indexer = Arc::new(fixture.indexer_with(fs));
+    let jobs = JobManager::new();
+
+    let running = {
+        let indexer = indexer.clone();
+        jobs.submit(JobKind::Indexing, move |context| async move {
+            operations::run_indexing(&context, &indexer, ROOT).await.map(|_| None)
+        })
+    };
+    let queued = jobs.submit(JobKind::Optimization, |_| async {
+        Err::<Option<serde_json::Value>, _>("a cancelled job must not run".to_string())
+    });
+    assert_eq!(jobs.get(queued).unwrap().status, JobStatus::Queued);
+
+    jobs.cancel(queued).unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    jobs.cancel(running).unwrap();
+
+    assert_eq!(wait_for_finish(&jobs, running).await.status, JobStatus::Cancelled);
+    assert_eq!(wait_for_finish(&jobs, queued).await.status, JobStatus::Cancelled);
+    assert!(doc_count(&indexer).await < 20);
+    assert!(jobs.cancel(999).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn failures_are_recorded_and_do_not_block_later_jobs() {
+    let jobs = JobManager::new();
+    let failing = jobs.submit(JobKind::Optimization, |_| async { Err::<Option<serde_json::Value>, _>("disk on fire".to_string()) });
+    let next = jobs.submit(JobKind::Optimization, |_| async { Ok::<_, String>(Some(serde_json::json!(42))) });
+
+    let failed = wait_for_finish(&jobs, failing).await;
+    assert_eq!(failed.status, JobStatus::Failed);
+    assert_eq!(failed.error.as_deref(), Some("disk on fire"));
+
+    let done = wait_for_finish(&jobs, next).await;
+    assert_eq!(done.status, JobStatus::Completed);
+    assert_eq!(done.result, Some(serde_json::json!(42)));
+    assert_eq!(jobs.list().iter().map(|job| job.id).collect::<Vec<_>>(), vec![failing, next]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn duplicate_scan_groups_identical_files() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/drive/a/report.txt", "same content");
+    memory.insert("/mem/drive/b/report copy.txt", "same content");
+    memory.insert("/mem/drive/b/other.txt", "same length!");
+    memory.insert("/mem/drive/unique.txt", "something else entirely");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let groups = indexer.find_duplicates(ROOT, || true, |_, _| {}).await.unwrap();
+
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].paths, vec!["/mem/drive/a/report.txt", "/mem/drive/b/report copy.txt"]);
+    assert_eq!(groups[0].size, 12);
+}
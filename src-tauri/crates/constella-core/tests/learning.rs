@@ -0,0 +1,60 @@
+mod common;
+
+use common::Fixture;
+use constella_core::search::learning::normalize_query;
+use constella_core::SettingsManager;
+
+fn ranked_paths(results: &[serde_json::Value]) -> Vec<String> {
+    results.iter().filter_map(|hit| hit["path"].as_str().map(str::to_string)).collect()
+}
+
+#[tokio::test]
+async fn repeatedly_opened_results_climb_for_that_query() {
+    let fixture = Fixture::new();
+    let budget = fixture.file("finance/budget.txt", "budget");
+    let q3 = fixture.file("finance/q3 budget plan.txt", "quarterly budget budget notes");
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.ranking.click_weight = 5.0)
+        .unwrap();
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let q3 = q3.to_string_lossy().into_owned();
+
+    let before = ranked_paths(&indexer.search("budget").await.unwrap());
+    assert_eq!(before[0], budget.to_string_lossy());
+
+    for _ in 0..20 {
+        indexer.record_result_click("  Budget ", &q3).unwrap();
+    }
+
+    assert_eq!(ranked_paths(&indexer.search("budget").await.unwrap())[0], q3);
+    assert_eq!(ranked_paths(&indexer.search("finance budget").await.unwrap())[0], budget.to_string_lossy());
+
+    indexer.clear_learning_data().unwrap();
+    assert_eq!(ranked_paths(&indexer.search("budget").await.unwrap()), before);
+}
+
+#[tokio::test]
+async fn clicks_survive_a_restart_and_respect_the_setting() {
+    let fixture = Fixture::new();
+    let file = fixture.file("notes.txt", "hello");
+    let path = file.to_string_lossy();
+    {
+        let indexer = fixture.indexer();
+        assert_eq!(indexer.record_result_click("hello", &path).unwrap(), 1);
+    }
+
+    let indexer = fixture.indexer();
+    assert_eq!(indexer.record_result_click("HELLO", &path).unwrap(), 2);
+
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.learn_from_clicks = false)
+        .unwrap();
+    let indexer = fixture.indexer();
+    assert_eq!(indexer.record_result_click("hello", &path).unwrap(), 0);
+}
+
+#[test]
+fn queries_are_normalized() {
+    assert_eq!(normalize_query("  Q3   Budget "), "q3 budget");
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::memory_fs::{Fault, FaultyFileSystem, MemoryFileSystem};
+use common::{doc_count, search_paths, Fixture};
+use constella_core::file_system::FileSystemProvider;
+
+const ROOT: &str = "/mem/slow";
+const FILES: usize = 20;
+
+/// A tree where every file takes a little while to stat, so a run lasts
+/// long enough to pause or cancel it part way through.
+fn slow_tree() -> Arc<dyn FileSystemProvider> {
+    let memory = MemoryFileSystem::new();
+    let fs = FaultyFileSystem::new(memory.clone());
+    for i in 0..FILES {
+        let path = format!("{}/file_{}.txt", ROOT, i);
+        memory.insert(path.clone(), format!("file {}", i));
+        fs.inject(path, Fault::Slow(Duration::from_millis(20)));
+    }
+    fs
+}
+
+#[tokio::test]
+async fn ram_backed_index_is_searchable() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/scratch/notes.txt", "notes");
+    memory.insert("/mem/scratch/photo.jpg", "jpeg");
+    let indexer = fixture.ram_indexer_with(memory);
+
+    indexer.start_indexing("/mem/scratch").await.unwrap();
+
+    assert_eq!(doc_count(&indexer).await, 2);
+    assert_eq!(search_paths(&indexer, "notes").await, vec!["/mem/scratch/notes.txt"]);
+    assert!(!fixture.data_dir().join("search_index").exists());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pause_holds_progress_until_resumed() {
+    let fixture = Fixture::new();
+    let indexer = Arc::new(fixture.ram_indexer_with(slow_tree()));
+    let mut progress = indexer.subscribe_progress();
+
+    let run = tokio::spawn({
+        let indexer = indexer.clone();
+        async move { indexer.start_indexing(ROOT).await }
+    });
+
+    progress.wait_for(|state| state.state == "indexing" && state.processed_files > 0).await.unwrap();
+    indexer.pause_indexing().await.unwrap();
+    let paused_at = progress.wait_for(|state| state.state == "paused").await.unwrap().processed_files;
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(indexer.get_state().processed_files, paused_at);
+    assert!(paused_at < FILES);
+
+    indexer.resume_indexing().await.unwrap();
+    run.await.unwrap().unwrap();
+
+    assert_eq!(progress.borrow().state, "completed");
+    assert_eq!(doc_count(&indexer).await, FILES as u64);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn cancel_keeps_what_was_indexed() {
+    let fixture = Fixture::new();
+    let indexer = Arc::new(fixture.ram_indexer_with(slow_tree()));
+    let mut progress = indexer.subscribe_progress();
+
+    let run = tokio::spawn({
+        let indexer = indexer.clone();
+        async move { indexer.start_indexing(ROOT).await }
+    });
+
+    progress.wait_for(|state| state.state == "indexing" && state.processed_files > 0).await.unwrap();
+    indexer.cancel().await.unwrap();
+    run.await.unwrap().unwrap();
+
+    let state = indexer.get_state();
+    assert_eq!(state.state, "cancelled");
+    assert!(state.processed_files < FILES);
+    assert_eq!(doc_count(&indexer).await, state.processed_files as u64);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn cancel_while_paused_stops_the_run() {
+    let fixture = Fixture::new();
+    let indexer = Arc::new(fixture.ram_indexer_with(slow_tree()));
+    let mut progress = indexer.subscribe_progress();
+
+    let run = tokio::spawn({
+        let indexer = indexer.clone();
+        async move { indexer.start_indexing(ROOT).await }
+    });
+
+    progress.wait_for(|state| state.state == "indexing" && state.processed_files > 0).await.unwrap();
+    indexer.pause_indexing().await.unwrap();
+    progress.wait_for(|state| state.state == "paused").await.unwrap();
+    indexer.cancel().await.unwrap();
+    run.await.unwrap().unwrap();
+
+    assert_eq!(indexer.get_state().state, "cancelled");
+}
+
+#[tokio::test]
+async fn cancel_when_idle_is_a_no_op() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/idle/a.txt", "a");
+    let indexer = fixture.ram_indexer_with(memory);
+
+    indexer.cancel().await.unwrap();
+    indexer.start_indexing("/mem/idle").await.unwrap();
+
+    assert_eq!(indexer.get_state().state, "completed");
+    assert_eq!(doc_count(&indexer).await, 1);
+}
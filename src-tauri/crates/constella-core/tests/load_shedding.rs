@@ -0,0 +1,78 @@
+mod common;
+
+use common::Fixture;
+use constella_core::indexing::shedding::{LoadShedding, SheddingMeasure};
+use constella_core::search::{ResultFields, SearchOptions};
+use constella_core::SettingsManager;
+
+fn workspace(fixture: &Fixture) {
+    fixture.file("constella/.git/HEAD", "ref: refs/heads/main\n");
+    fixture.file("constella/notes.txt", "the quarterly budget is final");
+}
+
+/// Stands in for a gigantic corpus: every measure kicks in at once.
+fn shed_everything(fixture: &Fixture, overridden: Vec<SheddingMeasure>) {
+    let settings = SettingsManager::load(fixture.data_dir().join("settings.json"));
+    let load_shedding = LoadShedding { no_snippets_at: 0, lean_documents_at: 0, slow_commits_at: 0, overridden };
+    settings.update(|settings| settings.load_shedding = load_shedding).unwrap();
+}
+
+fn snippets() -> SearchOptions {
+    SearchOptions { fields: ResultFields::Snippets, ..SearchOptions::default() }
+}
+
+#[tokio::test]
+async fn small_indexes_shed_nothing() {
+    let fixture = Fixture::new();
+    workspace(&fixture);
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let response = indexer.search_response("budget", &snippets()).await.unwrap();
+    assert!(response.shed.is_empty());
+    assert!(response.results[0]["snippet"].as_str().unwrap().contains("<b>budget</b>"));
+    assert!(indexer.load_shedding().measures.iter().all(|status| !status.active));
+}
+
+#[tokio::test]
+async fn large_indexes_drop_snippets_and_stored_fields() {
+    let fixture = Fixture::new();
+    shed_everything(&fixture, Vec::new());
+    workspace(&fixture);
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let response = indexer.search_response("budget", &snippets()).await.unwrap();
+    assert_eq!(response.shed, SheddingMeasure::ALL.to_vec());
+    let result = &response.results[0];
+    assert_eq!(result["name"], "notes.txt");
+    assert!(result.get("snippet").is_none());
+    assert!(result.get("repo").is_none());
+
+    let cursor = indexer.open_search_cursor("budget", &snippets()).await.unwrap();
+    assert_eq!(cursor.shed, SheddingMeasure::ALL.to_vec());
+    let page = indexer.next_page(cursor.id, 10).await.unwrap();
+    assert!(page.results[0].get("snippet").is_none());
+
+    let status = indexer.load_shedding();
+    assert!(status.documents > 0);
+    assert!(status.measures.iter().all(|status| status.active && !status.overridden));
+}
+
+#[tokio::test]
+async fn overridden_measures_stay_off() {
+    let fixture = Fixture::new();
+    shed_everything(&fixture, vec![SheddingMeasure::NoSnippets]);
+    workspace(&fixture);
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let response = indexer.search_response("budget", &snippets()).await.unwrap();
+    assert_eq!(response.shed, vec![SheddingMeasure::LeanDocuments, SheddingMeasure::SlowCommits]);
+    assert!(response.results[0]["snippet"].as_str().unwrap().contains("<b>budget</b>"));
+
+    let snippets_status = indexer.load_shedding().measures.into_iter()
+        .find(|status| status.measure == SheddingMeasure::NoSnippets)
+        .unwrap();
+    assert!(snippets_status.overridden && !snippets_status.active);
+}
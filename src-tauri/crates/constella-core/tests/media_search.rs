@@ -0,0 +1,175 @@
+mod common;
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use common::memory_fs::MemoryFileSystem;
+use common::Fixture;
+use constella_core::extract::media::{self, KeyframeExtractor};
+use constella_core::indexing::media::{MediaKind, MediaSearchOptions};
+use constella_core::indexing::{IndexManager, IndexOptions};
+use constella_core::SettingsManager;
+
+/// Writes the duration it was given as the strip, and counts its calls.
+#[derive(Default)]
+struct FakeKeyframes {
+    calls: AtomicUsize,
+}
+
+impl KeyframeExtractor for FakeKeyframes {
+    fn keyframe_strip(&self, _video: &Path, duration_ms: Option<u64>) -> Result<Option<Vec<u8>>, String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(Some(format!("strip of {:?}", duration_ms).into_bytes()))
+    }
+}
+
+fn previewing_indexer(fixture: &Fixture, memory: Arc<MemoryFileSystem>, keyframes: Arc<FakeKeyframes>) -> IndexManager {
+    let settings = Arc::new(SettingsManager::load(fixture.data_dir().join("settings.json")));
+    let options = IndexOptions { fs: memory, keyframes: Some(keyframes), ..IndexOptions::default() };
+    IndexManager::with_options(fixture.data_dir(), settings, options).unwrap()
+}
+
+/// An MP4 with a `ftyp` box and a `moov` holding a version 0 `mvhd`.
+fn mp4_file(timescale: u32, duration: u32) -> Vec<u8> {
+    let mut mvhd = vec![0u8; 4 + 4 + 4];
+    mvhd.extend(timescale.to_be_bytes());
+    mvhd.extend(duration.to_be_bytes());
+    mvhd.extend([0u8; 80]);
+
+    let mut bytes = Vec::new();
+    bytes.extend(16u32.to_be_bytes());
+    bytes.extend(b"ftypisom\0\0\0\0");
+    bytes.extend((8 + 8 + mvhd.len() as u32).to_be_bytes());
+    bytes.extend(b"moov");
+    bytes.extend((8 + mvhd.len() as u32).to_be_bytes());
+    bytes.extend(b"mvhd");
+    bytes.extend(mvhd);
+    bytes
+}
+
+/// A HEIC file with just its image size property.
+fn heic_file(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend(16u32.to_be_bytes());
+    bytes.extend(b"ftypheic\0\0\0\0");
+    bytes.extend(20u32.to_be_bytes());
+    bytes.extend(b"ispe\0\0\0\0");
+    bytes.extend(width.to_be_bytes());
+    bytes.extend(height.to_be_bytes());
+    bytes
+}
+
+fn paths(page: &constella_core::indexing::media::MediaPage) -> Vec<&str> {
+    let mut paths: Vec<&str> = page.items.iter().map(|item| item.path.as_str()).collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn mp4_durations_come_from_the_movie_header() {
+    assert_eq!(media::duration_ms(&mp4_file(600, 54_300)), Some(90_500));
+    assert_eq!(media::duration_ms(&mp4_file(0, 54_300)), None);
+    assert_eq!(media::duration_ms(&mp4_file(600, u32::MAX)), None);
+    assert_eq!(media::duration_ms(b"not a movie at all"), None);
+    assert!(media::has_duration("clip.MOV".as_ref()));
+    assert!(!media::has_duration("clip.mkv".as_ref()));
+}
+
+#[tokio::test]
+async fn media_searches_return_only_images_and_videos() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/trip/beach.jpg", "jpeg");
+    memory.insert("/mem/trip/beach.heic", heic_file(4032, 3024));
+    memory.insert("/mem/trip/beach.mp4", mp4_file(1000, 12_345));
+    memory.insert("/mem/trip/beach.txt", "notes from the beach");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing("/mem/trip").await.unwrap();
+
+    let page = indexer.search_media("beach", &MediaSearchOptions::default()).await.unwrap();
+    assert_eq!(paths(&page), vec!["/mem/trip/beach.heic", "/mem/trip/beach.jpg", "/mem/trip/beach.mp4"]);
+    assert_eq!(page.total, 3);
+    assert_eq!(page.next_offset, None);
+
+    let video = page.items.iter().find(|item| item.kind == MediaKind::Video).unwrap();
+    assert_eq!(video.duration_ms, Some(12_345));
+    assert_eq!(video.thumbnail, None);
+    let image = page.items.iter().find(|item| item.path.ends_with(".jpg")).unwrap();
+    assert_eq!(image.thumbnail.as_deref(), Some("/mem/trip/beach.jpg"));
+    // HEIC photos have no thumbnail until they have been previewed
+    let photo = page.items.iter().find(|item| item.path.ends_with(".heic")).unwrap();
+    assert_eq!((photo.kind, photo.width, photo.height), (MediaKind::Image, Some(4032), Some(3024)));
+    assert_eq!(photo.thumbnail, None);
+
+    let videos = MediaSearchOptions { kinds: vec![MediaKind::Video], ..MediaSearchOptions::default() };
+    let page = indexer.search_media("", &videos).await.unwrap();
+    assert_eq!(paths(&page), vec!["/mem/trip/beach.mp4"]);
+}
+
+#[tokio::test]
+async fn media_pages_follow_one_snapshot() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    for index in 0..5 {
+        memory.insert(format!("/mem/photos/IMG_{}.png", index), "png");
+    }
+    let indexer = fixture.indexer_with(memory.clone());
+    indexer.start_indexing("/mem/photos").await.unwrap();
+
+    let options = MediaSearchOptions { limit: Some(2), ..MediaSearchOptions::default() };
+    let first = indexer.search_media("", &options).await.unwrap();
+    assert_eq!((first.items.len(), first.total, first.next_offset), (2, 5, Some(2)));
+
+    memory.insert("/mem/photos/IMG_5.png", "png");
+    indexer.start_indexing("/mem/photos").await.unwrap();
+
+    let mut seen: Vec<String> = first.items.iter().map(|item| item.path.clone()).collect();
+    let mut offset = first.next_offset;
+    while let Some(next) = offset {
+        let options = MediaSearchOptions { offset: next, snapshot: Some(first.snapshot), ..options.clone() };
+        let page = indexer.search_media("", &options).await.unwrap();
+        assert_eq!(page.total, 5);
+        seen.extend(page.items.iter().map(|item| item.path.clone()));
+        offset = page.next_offset;
+    }
+    seen.sort();
+    seen.dedup();
+    assert_eq!(seen.len(), 5);
+}
+
+#[tokio::test]
+async fn video_previews_are_cached_keyframe_strips() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/clips/launch.mp4", mp4_file(1000, 42_000));
+    memory.insert("/mem/backup/launch.mp4", mp4_file(1000, 42_000));
+    memory.insert("/mem/clips/notes.txt", "not a video");
+    let keyframes = Arc::new(FakeKeyframes::default());
+    let indexer = previewing_indexer(&fixture, memory, keyframes.clone());
+
+    let preview = indexer.video_preview("/mem/clips/launch.mp4").await.unwrap().unwrap();
+    assert_eq!(std::fs::read(&preview).unwrap(), b"strip of Some(42000)");
+    assert!(preview.starts_with(fixture.data_dir()));
+    // Copies share the strip made from the first
+    assert_eq!(indexer.video_preview("/mem/backup/launch.mp4").await.unwrap(), Some(preview.clone()));
+    assert_eq!(indexer.video_preview("/mem/clips/launch.mp4").await.unwrap(), Some(preview.clone()));
+    assert_eq!(keyframes.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(indexer.video_preview("/mem/clips/notes.txt").await.unwrap(), None);
+
+    indexer.start_indexing("/mem/clips").await.unwrap();
+    let page = indexer.search_media("launch", &MediaSearchOptions::default()).await.unwrap();
+    assert_eq!(page.items[0].thumbnail.as_deref(), preview.to_str());
+}
+
+#[tokio::test]
+async fn videos_have_no_preview_without_an_extractor() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/clips/launch.mp4", mp4_file(1000, 42_000));
+    let settings = Arc::new(SettingsManager::load(fixture.data_dir().join("settings.json")));
+    let options = IndexOptions { fs: memory, keyframes: None, ..IndexOptions::default() };
+    let indexer = IndexManager::with_options(fixture.data_dir(), settings, options).unwrap();
+
+    assert_eq!(indexer.video_preview("/mem/clips/launch.mp4").await.unwrap(), None);
+}
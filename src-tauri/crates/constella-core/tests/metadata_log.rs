@@ -0,0 +1,119 @@
+mod common;
+
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use common::Fixture;
+use constella_core::persistence::wal::WalStore;
+use constella_core::persistence::PersistenceManager;
+
+fn set(store: &WalStore<String, u32>, key: &str, value: u32) {
+    store.update(|map| {
+        map.insert(key.to_string(), value);
+        Ok(())
+    }).unwrap();
+}
+
+fn entries(store: &WalStore<String, u32>) -> Vec<(String, u32)> {
+    store.read().iter().map(|(key, value)| (key.clone(), *value)).collect()
+}
+
+#[test]
+fn torn_and_corrupt_lines_cost_only_the_change_they_held() {
+    let fixture = Fixture::new();
+    let base = fixture.data_dir().join("stars");
+    let log_path = fixture.data_dir().join("stars.wal");
+    {
+        let store = WalStore::<String, u32>::open(&base).unwrap();
+        set(&store, "a", 1);
+        set(&store, "b", 2);
+    }
+
+    // A crash halfway through appending the third change
+    OpenOptions::new().append(true).open(&log_path).unwrap()
+        .write_all(b"0123456789abcdef\t[{\"key\":\"c\",\"va").unwrap();
+    let store = WalStore::<String, u32>::open(&base).unwrap();
+    assert_eq!(entries(&store), vec![("a".to_string(), 1), ("b".to_string(), 2)]);
+
+    // The torn tail is cut off, so what's appended next replays fine
+    set(&store, "c", 3);
+    drop(store);
+    let store = WalStore::<String, u32>::open(&base).unwrap();
+    assert_eq!(entries(&store).len(), 3);
+    drop(store);
+
+    // A line whose checksum doesn't match stops the replay there
+    let log = std::fs::read_to_string(&log_path).unwrap();
+    std::fs::write(&log_path, log.replacen("\"b\",\"value\":2", "\"b\",\"value\":7", 1)).unwrap();
+    let store = WalStore::<String, u32>::open(&base).unwrap();
+    assert_eq!(entries(&store), vec![("a".to_string(), 1)]);
+}
+
+#[test]
+fn failed_updates_change_nothing_and_compaction_keeps_everything() {
+    let fixture = Fixture::new();
+    let base = fixture.data_dir().join("tags");
+    let store = WalStore::<String, u32>::open(&base).unwrap();
+    set(&store, "kept", 1);
+    let failed = store.update(|map| {
+        map.clear();
+        Err::<(), _>("nope".to_string())
+    });
+    assert!(failed.is_err());
+    assert_eq!(entries(&store), vec![("kept".to_string(), 1)]);
+
+    for n in 0..1_500 {
+        set(&store, "counter", n);
+    }
+    store.update(|map| {
+        map.remove("kept");
+        Ok(())
+    }).unwrap();
+    drop(store);
+
+    assert!(fixture.data_dir().join("tags.snapshot.json").exists());
+    let log_lines = std::fs::read_to_string(fixture.data_dir().join("tags.wal")).unwrap().lines().count();
+    assert!(log_lines < 1_000);
+    let store = WalStore::<String, u32>::open(&base).unwrap();
+    assert_eq!(entries(&store), vec![("counter".to_string(), 1_499)]);
+}
+
+#[tokio::test]
+async fn collections_move_into_the_log() {
+    let fixture = Fixture::new();
+    let notes = fixture.file("notes.txt", "notes");
+    let legacy = fixture.data_dir().join("collections.json");
+    let json = format!(r#"{{"Old": {{"created": 1, "items": [{:?}]}}}}"#, notes.to_string_lossy());
+    std::fs::write(&legacy, json).unwrap();
+
+    let indexer = fixture.indexer();
+    assert_eq!(indexer.collection_items("Old").unwrap(), vec![notes.to_string_lossy().into_owned()]);
+    assert!(!legacy.exists());
+    indexer.add_to_collection("New", std::slice::from_ref(&notes)).unwrap();
+    indexer.delete_collection("Old").unwrap();
+    drop(indexer);
+
+    let indexer = fixture.indexer();
+    let names: Vec<String> = indexer.list_collections().into_iter().map(|collection| collection.name).collect();
+    assert_eq!(names, vec!["New".to_string()]);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn tracking_state_keeps_paths_that_are_not_utf8() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let fixture = Fixture::new();
+    let latin1 = fixture.root().join(OsStr::from_bytes(b"caf\xe9.txt"));
+    std::fs::write(&latin1, "menu").unwrap();
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let states = indexer.change_tracker().export_states().await;
+    assert!(states.contains_key(&latin1));
+
+    let base = fixture.data_dir().join("state");
+    PersistenceManager::new(&base).unwrap().save_state(&states).await.unwrap();
+    let restored = PersistenceManager::new(&base).unwrap().load_state().await.unwrap().unwrap();
+    assert_eq!(restored.file_states, states);
+}
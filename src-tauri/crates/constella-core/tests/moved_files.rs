@@ -0,0 +1,46 @@
+mod common;
+
+use common::{search_paths, Fixture};
+use constella_core::tracking::UserAction;
+use constella_core::watcher::ChangeType;
+
+#[tokio::test]
+async fn deleted_and_created_copies_of_a_file_are_a_move() {
+    let fixture = Fixture::new();
+    let old = fixture.file("inbox/contract.txt", "signed contract");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    indexer.add_to_collection("Legal", std::slice::from_ref(&old)).unwrap();
+    let importance = indexer.change_tracker().record_user_action(&old, UserAction::Star).await.unwrap();
+
+    let new = fixture.path("work/legal/contract.txt");
+    std::fs::create_dir_all(new.parent().unwrap()).unwrap();
+    std::fs::rename(&old, &new).unwrap();
+    let summary = indexer.apply_changes(&[(old.clone(), ChangeType::Deleted), (new.clone(), ChangeType::Created)])
+        .await
+        .unwrap();
+
+    assert_eq!((summary.moved, summary.indexed, summary.removed), (1, 0, 0));
+    assert_eq!(search_paths(&indexer, "signed").await, vec![new.to_string_lossy().into_owned()]);
+    assert_eq!(indexer.collection_items("Legal").unwrap(), vec![new.to_string_lossy().into_owned()]);
+    assert_eq!(indexer.change_tracker().importance_score(&new).await, importance);
+    assert_eq!(indexer.change_tracker().importance_score(&old).await, 0.0);
+}
+
+#[tokio::test]
+async fn different_files_of_the_same_name_are_not_a_move() {
+    let fixture = Fixture::new();
+    let old = fixture.file("inbox/notes.txt", "short");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    indexer.add_to_collection("Keep", std::slice::from_ref(&old)).unwrap();
+
+    std::fs::remove_file(&old).unwrap();
+    let new = fixture.file("archive/notes.txt", "a much longer set of notes");
+    let summary = indexer.apply_changes(&[(old.clone(), ChangeType::Deleted), (new.clone(), ChangeType::Created)])
+        .await
+        .unwrap();
+
+    assert_eq!((summary.moved, summary.indexed, summary.removed), (0, 1, 1));
+    assert_eq!(indexer.collection_items("Keep").unwrap(), vec![old.to_string_lossy().into_owned()]);
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::extract::music::{self, MusicTags};
+use constella_core::indexing::music::{MusicFilters, MusicGrouping};
+
+/// An MP3 with an ID3v2.3 tag of `frames`, Latin-1 but for a UTF-16 title.
+fn mp3_file(title: &str, frames: &[(&[u8; 4], &str)]) -> Vec<u8> {
+    let mut body = Vec::new();
+    let mut frame = |id: &[u8; 4], data: Vec<u8>| {
+        body.extend(id);
+        body.extend((data.len() as u32).to_be_bytes());
+        body.extend([0, 0]);
+        body.extend(data);
+    };
+    let mut utf16 = vec![1, 0xFF, 0xFE];
+    utf16.extend(title.encode_utf16().flat_map(u16::to_le_bytes));
+    frame(b"TIT2", utf16);
+    for (id, value) in frames {
+        let mut latin1 = vec![0];
+        latin1.extend(value.bytes());
+        frame(id, latin1);
+    }
+    body.extend([0; 16]);
+
+    let size = body.len() as u32;
+    let mut bytes = b"ID3\x03\0\0".to_vec();
+    bytes.extend([(size >> 21) as u8 & 0x7F, (size >> 14) as u8 & 0x7F, (size >> 7) as u8 & 0x7F, size as u8 & 0x7F]);
+    bytes.extend(body);
+    bytes.extend([0xFF, 0xFB, 0x90, 0x00]);
+    bytes
+}
+
+/// A FLAC file with a stream info block and Vorbis `comments`.
+fn flac_file(comments: &[&str]) -> Vec<u8> {
+    let mut block = Vec::new();
+    block.extend(6u32.to_le_bytes());
+    block.extend(b"vendor");
+    block.extend((comments.len() as u32).to_le_bytes());
+    for comment in comments {
+        block.extend((comment.len() as u32).to_le_bytes());
+        block.extend(comment.bytes());
+    }
+
+    let mut bytes = b"fLaC".to_vec();
+    bytes.extend([0, 0, 0, 34]);
+    bytes.extend([0; 34]);
+    bytes.push(0x80 | 4);
+    bytes.extend(&(block.len() as u32).to_be_bytes()[1..]);
+    bytes.extend(block);
+    bytes
+}
+
+/// An ID3v1.1 tag at the end of some audio.
+fn id3v1_file(title: &str, artist: &str, track: u8) -> Vec<u8> {
+    let mut tag = vec![0u8; 128];
+    tag[..3].copy_from_slice(b"TAG");
+    tag[3..3 + title.len()].copy_from_slice(title.as_bytes());
+    tag[33..33 + artist.len()].copy_from_slice(artist.as_bytes());
+    tag[126] = track;
+    let mut bytes = vec![0xFF, 0xFB, 0x90, 0x00, 0, 0, 0, 0];
+    bytes.extend(tag);
+    bytes
+}
+
+#[test]
+fn id3_and_vorbis_tags_are_read() {
+    let mp3 = mp3_file("Für Elise", &[(b"TPE1", "Beethoven"), (b"TALB", "Bagatelles"), (b"TCON", "(32)Classical"), (b"TRCK", "3/12")]);
+    assert_eq!(music::tags(&mp3), MusicTags {
+        title: Some("Für Elise".into()),
+        artist: Some("Beethoven".into()),
+        album_artist: None,
+        album: Some("Bagatelles".into()),
+        genre: Some("Classical".into()),
+        track: Some(3),
+    });
+
+    let flac = flac_file(&["TITLE=So What", "ARTIST=Miles Davis", "ALBUMARTIST=Miles Davis", "album=Kind of Blue", "TRACKNUMBER=1"]);
+    let tags = music::tags(&flac);
+    assert_eq!(tags.title.as_deref(), Some("So What"));
+    assert_eq!(tags.album.as_deref(), Some("Kind of Blue"));
+    assert_eq!(tags.album_artist.as_deref(), Some("Miles Davis"));
+    assert_eq!(tags.track, Some(1));
+
+    let tags = music::tags(&id3v1_file("Old Song", "Someone", 7));
+    assert_eq!((tags.title.as_deref(), tags.artist.as_deref(), tags.track), (Some("Old Song"), Some("Someone"), Some(7)));
+    assert!(music::tags(b"not music").is_empty());
+}
+
+#[tokio::test]
+async fn music_is_grouped_by_album_artist_and_genre() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/music/blue/02.flac", flac_file(&["TITLE=Freddie Freeloader", "ARTIST=Miles Davis", "ALBUM=Kind of Blue", "GENRE=Jazz", "TRACKNUMBER=2"]));
+    memory.insert("/mem/music/blue/01.flac", flac_file(&["TITLE=So What", "ARTIST=Miles Davis", "ALBUM=Kind of Blue", "GENRE=Jazz", "TRACKNUMBER=1"]));
+    memory.insert("/mem/music/elise.mp3", mp3_file("Für Elise", &[(b"TPE1", "Beethoven"), (b"TALB", "Bagatelles"), (b"TCON", "Classical")]));
+    memory.insert("/mem/music/untitled.mp3", b"\xFF\xFB\x90\x00".to_vec());
+    memory.insert("/mem/music/notes.txt", "Miles Davis liner notes");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing("/mem/music").await.unwrap();
+
+    let albums = indexer.browse_music(MusicGrouping::Album, &MusicFilters::default()).await.unwrap();
+    assert_eq!(albums.tracks, 4);
+    let names: Vec<Option<&str>> = albums.groups.iter().map(|group| group.name.as_deref()).collect();
+    assert_eq!(names, vec![Some("Bagatelles"), Some("Kind of Blue"), None]);
+    let blue = &albums.groups[1];
+    assert_eq!(blue.artist.as_deref(), Some("Miles Davis"));
+    let titles: Vec<Option<&str>> = blue.tracks.iter().map(|track| track.tags.title.as_deref()).collect();
+    assert_eq!(titles, vec![Some("So What"), Some("Freddie Freeloader")]);
+    assert_eq!(albums.groups[2].tracks[0].name, "untitled.mp3");
+
+    let jazz = MusicFilters { genre: Some("jazz".into()), ..MusicFilters::default() };
+    let artists = indexer.browse_music(MusicGrouping::Artist, &jazz).await.unwrap();
+    assert_eq!(artists.groups.len(), 1);
+    assert_eq!(artists.groups[0].name.as_deref(), Some("Miles Davis"));
+    assert_eq!(artists.groups[0].tracks.len(), 2);
+
+    let freddie = MusicFilters { query: "title:freeloader".into(), ..MusicFilters::default() };
+    let genres = indexer.browse_music(MusicGrouping::Genre, &freddie).await.unwrap();
+    assert_eq!(genres.tracks, 1);
+    assert_eq!(genres.groups[0].name.as_deref(), Some("Jazz"));
+
+    assert_eq!(search_paths(&indexer, "artist:beethoven").await, vec!["/mem/music/elise.mp3"]);
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::{search_paths, Fixture};
+use constella_core::search::StopwordSettings;
+use constella_core::SettingsManager;
+
+fn set_stopwords(fixture: &Fixture, stopwords: StopwordSettings) {
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.stopwords = stopwords)
+        .unwrap();
+}
+
+#[tokio::test]
+async fn stopwords_only_apply_to_content() {
+    let fixture = Fixture::new();
+    let named = fixture.file("notes/the.txt", "nothing to see");
+    fixture.file("notes/essay.txt", "the end");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "the").await, vec![named.to_string_lossy()]);
+    assert_eq!(search_paths(&indexer, "end").await.len(), 1);
+}
+
+#[tokio::test]
+async fn stopword_changes_apply_without_reindexing() {
+    let fixture = Fixture::new();
+    let essay = fixture.file("notes/essay.txt", "the end");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    assert!(search_paths(&indexer, "the").await.is_empty());
+
+    set_stopwords(&fixture, StopwordSettings { stopwords: Vec::new(), ..StopwordSettings::default() });
+    let indexer = fixture.indexer();
+
+    assert_eq!(search_paths(&indexer, "the").await, vec![essay.to_string_lossy()]);
+}
+
+#[tokio::test]
+async fn high_frequency_terms_are_skipped_alongside_rarer_ones() {
+    let fixture = Fixture::new();
+    for i in 0..9 {
+        fixture.file(&format!("src/module{}.txt", i), "function returns value");
+    }
+    let rare = fixture.file("src/parser.txt", "function parses tokens");
+    set_stopwords(&fixture, StopwordSettings {
+        noise_threshold: 0.5,
+        noise_min_documents: 5,
+        ..StopwordSettings::default()
+    });
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let searcher = indexer.get_reader().await.unwrap().searcher();
+
+    assert_eq!(indexer.noise_terms(&searcher, "function tokens"), vec!["function"]);
+    assert_eq!(search_paths(&indexer, "function tokens").await, vec![rare.to_string_lossy()]);
+
+    // With nothing rarer to go on, the common term still matches
+    assert!(indexer.noise_terms(&searcher, "function").is_empty());
+    assert_eq!(search_paths(&indexer, "function").await.len(), 10);
+}
+
+#[test]
+fn settings_are_normalized() {
+    let settings = StopwordSettings { stopwords: vec![" The ".into(), "the".into(), "".into()], ..StopwordSettings::default() }
+        .normalized()
+        .unwrap();
+    assert_eq!(settings.stopwords, vec!["the"]);
+
+    assert!(StopwordSettings { noise_threshold: 0.0, ..StopwordSettings::default() }.normalized().is_err());
+}
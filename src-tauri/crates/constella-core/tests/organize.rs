@@ -0,0 +1,80 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{search_paths, Fixture};
+use constella_core::journal::OperationJournal;
+use constella_core::organize::{OrganizeRule, Organizer};
+use constella_core::SettingsManager;
+
+fn invoices(fixture: &Fixture) -> OrganizeRule {
+    OrganizeRule {
+        name: "invoices".to_string(),
+        pattern: "*.TXT".to_string(),
+        query: Some("content:invoice".to_string()),
+        destination: format!("{}/Finance/{{year}}", fixture.root_str()),
+        ..OrganizeRule::default()
+    }
+}
+
+#[tokio::test]
+async fn rules_move_matching_files_only_while_switched_on() {
+    let fixture = Fixture::new();
+    let bill = fixture.file("inbox/bill.txt", "Invoice 42, due in March");
+    let letter = fixture.file("inbox/letter.txt", "Dear Sam");
+    let notes = fixture.file("inbox/invoice.md", "Invoice template");
+    let settings = Arc::new(SettingsManager::load(fixture.data_dir().join("settings.json")));
+    let rule = invoices(&fixture);
+    settings.update(|settings| settings.organize.rules = vec![rule.clone()]).unwrap();
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let journal = OperationJournal::load(fixture.data_dir());
+    let organizer = Organizer::load(fixture.data_dir(), settings.clone());
+    let changed = vec![bill.clone(), letter.clone(), notes.clone()];
+
+    // A dry run finds the invoice by name and content, and moves nothing
+    let planned = organizer.preview(&indexer, &rule).await.unwrap();
+    assert_eq!(planned.len(), 1);
+    assert_eq!(planned[0].path, bill);
+    assert_eq!(planned[0].folder.parent().unwrap(), fixture.path("Finance"));
+    assert!(bill.exists());
+
+    // Nothing happens with the kill switch off
+    assert!(organizer.organize(&indexer, &journal, &changed).await.unwrap().is_empty());
+    assert!(bill.exists());
+
+    settings.update(|settings| settings.organize.enabled = true).unwrap();
+    let logged = organizer.organize(&indexer, &journal, &changed).await.unwrap();
+    assert_eq!(logged.len(), 1);
+    assert!(logged[0].error.is_none());
+    let moved = planned[0].folder.join("bill.txt");
+    assert_eq!(logged[0].to, moved);
+    assert!(!bill.exists() && moved.exists() && letter.exists() && notes.exists());
+    assert_eq!(search_paths(&indexer, "march").await, vec![moved.to_string_lossy().into_owned()]);
+    assert_eq!(organizer.log("invoices"), logged);
+
+    // Seeing the file again where it was put doesn't move it again
+    assert!(organizer.organize(&indexer, &journal, std::slice::from_ref(&moved)).await.unwrap().is_empty());
+
+    // Organizing is undone like any other move, and the log survives a restart
+    journal.undo_last().unwrap().unwrap();
+    assert!(bill.exists());
+    let reopened = Organizer::load(fixture.data_dir(), settings);
+    assert_eq!(reopened.log("invoices").len(), 1);
+}
+
+#[tokio::test]
+async fn rules_are_checked_before_they_run() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer();
+    let settings = Arc::new(SettingsManager::load(fixture.data_dir().join("settings.json")));
+    let organizer = Organizer::load(fixture.data_dir(), settings);
+    let rule = invoices(&fixture);
+
+    let unknown = OrganizeRule { destination: "~/Finance/{quarter}".to_string(), ..rule.clone() };
+    assert!(unknown.validate().unwrap_err().contains("Unknown token {quarter}"));
+    let bad_glob = OrganizeRule { pattern: "[*.pdf".to_string(), ..rule.clone() };
+    assert!(bad_glob.validate().is_err());
+    let bad_query = OrganizeRule { query: Some("content:(".to_string()), ..rule };
+    assert!(organizer.preview(&indexer, &bad_query).await.unwrap_err().contains("Invalid query"));
+}
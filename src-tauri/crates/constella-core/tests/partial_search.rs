@@ -0,0 +1,50 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::memory_fs::{Fault, FaultyFileSystem, MemoryFileSystem};
+use common::Fixture;
+use constella_core::search::SearchOptions;
+
+const ROOT: &str = "/mem/drive";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn searches_see_partial_results_while_indexing() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/drive/alpha.txt", "a");
+    memory.insert("/mem/drive/beta.txt", "b");
+    memory.insert("/mem/drive/gamma.txt", "c");
+    memory.insert("/mem/drive/zeta.txt", "z");
+    let fs = FaultyFileSystem::new(memory);
+    // Slow enough to cross the partial commit interval, then hold the run open
+    fs.inject("/mem/drive/gamma.txt", Fault::Slow(Duration::from_millis(1200)));
+    fs.inject("/mem/drive/zeta.txt", Fault::Slow(Duration::from_millis(1200)));
+    let indexer = Arc::new(fixture.indexer_with(fs));
+
+    let watcher = {
+        let indexer = indexer.clone();
+        tokio::spawn(async move {
+            for _ in 0..200 {
+                let completeness = indexer.index_completeness();
+                if completeness > 0.0 && completeness < 100.0 {
+                    return Some(indexer.search_response("alpha", &SearchOptions::default()).await.unwrap());
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            None
+        })
+    };
+
+    indexer.start_indexing(ROOT).await.unwrap();
+    let partial = watcher.await.unwrap().expect("no partial results were ever searchable");
+
+    assert_eq!(partial.index_completeness, 75.0);
+    assert_eq!(partial.results.len(), 1);
+    assert_eq!(partial.results[0]["name"], "alpha.txt");
+
+    let done = indexer.search_response("zeta", &SearchOptions::default()).await.unwrap();
+    assert_eq!(done.index_completeness, 100.0);
+    assert_eq!(done.results.len(), 1);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::Fixture;
+
+#[tokio::test]
+async fn every_folder_along_a_path_gets_its_own_totals() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/drive/top.txt", "1234");
+    memory.insert("/mem/drive/work/plan.txt", "12");
+    memory.insert("/mem/drive/work/2024/q1.txt", "123");
+    memory.insert("/mem/drive/workshop/tools.txt", "123456");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing("/mem/drive").await.unwrap();
+
+    let info = indexer.get_path_info("/mem/drive/work/2024/q1.txt").await.unwrap();
+    let summary: Vec<(&str, usize, u64)> = info.iter()
+        .map(|folder| (folder.name.as_str(), folder.file_count, folder.total_size))
+        .collect();
+
+    assert_eq!(summary, vec![
+        ("/", 4, 15),
+        ("mem", 4, 15),
+        ("drive", 4, 15),
+        // A sibling sharing the prefix doesn't count
+        ("work", 2, 5),
+        ("2024", 1, 3),
+    ]);
+    assert!(info.iter().all(|folder| folder.last_modified.is_some()));
+
+    let folder = indexer.get_path_info("/mem/drive/work").await.unwrap();
+    assert_eq!(folder.last().unwrap().path, "/mem/drive/work");
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{search_paths, Fixture};
+use constella_core::watcher::ChangeType;
+
+fn lossy(path: &std::path::Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+#[tokio::test]
+async fn searches_reflect_actions_before_the_index_does() {
+    let fixture = Fixture::new();
+    let draft = fixture.file("notes/draft.txt", "quarterly plan");
+    let old = fixture.file("notes/old.txt", "quarterly numbers");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let renamed = fixture.path("notes/budget_final.txt");
+    std::fs::rename(&draft, &renamed).unwrap();
+    std::fs::remove_file(&old).unwrap();
+    indexer.record_moved(&draft, &renamed);
+    indexer.record_deleted(&old);
+
+    // Nothing was committed, yet the results already show both actions
+    assert_eq!(search_paths(&indexer, "quarterly").await, vec![lossy(&renamed)]);
+    assert_eq!(search_paths(&indexer, "budget final").await, vec![lossy(&renamed)]);
+
+    indexer.apply_changes(&[
+        (renamed.clone(), ChangeType::Renamed(draft.clone())),
+        (old.clone(), ChangeType::Deleted),
+    ]).await.unwrap();
+    assert_eq!(search_paths(&indexer, "quarterly").await, vec![lossy(&renamed)]);
+    assert_eq!(search_paths(&indexer, "budget").await, vec![lossy(&renamed)]);
+}
+
+#[tokio::test]
+async fn moved_folders_carry_their_files() {
+    let fixture = Fixture::new();
+    let report = fixture.file("inbox/reports/march.txt", "revenue");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let (from, to) = (fixture.path("inbox/reports"), fixture.path("archive/reports"));
+    std::fs::create_dir_all(to.parent().unwrap()).unwrap();
+    std::fs::rename(&from, &to).unwrap();
+    indexer.record_moved(&from, &to);
+
+    assert_eq!(search_paths(&indexer, "revenue").await, vec![lossy(&to.join("march.txt"))]);
+    assert!(!report.exists());
+}
@@ -0,0 +1,34 @@
+use constella_core::permissions::{self, PermissionCheck, PermissionKind};
+
+#[test]
+fn long_path_support_is_read_from_reg_output() {
+    let enabled = "\r\nHKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Control\\FileSystem\r\n    LongPathsEnabled    REG_DWORD    0x1\r\n";
+    let disabled = "    LongPathsEnabled    REG_DWORD    0x0\r\n";
+
+    assert_eq!(permissions::parse_long_paths_enabled(enabled), Some(true));
+    assert_eq!(permissions::parse_long_paths_enabled(disabled), Some(false));
+    assert_eq!(permissions::parse_long_paths_enabled("ERROR: The system was unable to find the specified registry key or value."), None);
+}
+
+#[test]
+fn missing_permissions_come_with_steps() {
+    let missing = PermissionCheck::new(PermissionKind::FullDiskAccess, Some(false));
+    assert!(!missing.steps.is_empty());
+    assert!(missing.opens_settings);
+    assert!(PermissionCheck::new(PermissionKind::FullDiskAccess, Some(true)).steps.is_empty());
+
+    let unknown = PermissionCheck::new(PermissionKind::LongPaths, None);
+    assert!(!unknown.steps.is_empty());
+    assert!(!unknown.opens_settings);
+    assert!(permissions::open_settings(PermissionKind::LongPaths).is_err());
+}
+
+#[test]
+fn folders_that_exist_are_readable() {
+    let dir = tempfile::tempdir().unwrap();
+
+    assert_eq!(permissions::readable(dir.path()), Some(true));
+    assert_eq!(permissions::readable(&dir.path().join("missing")), None);
+    assert_eq!(permissions::all_readable(&[dir.path().to_path_buf(), dir.path().join("missing")]), Some(true));
+    assert_eq!(permissions::all_readable(&[]), None);
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use std::time::SystemTime;
+
+use chrono::DateTime;
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::extract::photo::{self, PhotoMetadata};
+use constella_core::indexing::preview_cache::PreviewCacheUsage;
+use constella_core::SettingsManager;
+
+const PREVIEW: &[u8] = &[0xFF, 0xD8, 0xFF, 0xDB, 1, 2, 3, 4, 0xFF, 0xD9];
+
+/// Little-endian TIFF data shaped like a NEF: make and model in IFD0, the
+/// date in the EXIF IFD, and the sensor size and preview in a sub-IFD.
+fn raw_file() -> Vec<u8> {
+    let ifd0: u32 = 8;
+    let exif = ifd0 + 2 + 4 * 12 + 4;
+    let sub = exif + 2 + 2 * 12 + 4;
+    let data = sub + 2 + 4 * 12 + 4;
+
+    let strings: [&[u8]; 4] = [b"NIKON CORPORATION\0", b"NIKON D850\0", b"2023:03:14 09:30:00\0", b"+02:00\0"];
+    let mut offsets = Vec::new();
+    let mut next = data;
+    for string in strings.iter().copied().chain([PREVIEW]) {
+        offsets.push(next);
+        next += string.len() as u32;
+    }
+
+    let mut bytes = b"II*\0".to_vec();
+    bytes.extend(ifd0.to_le_bytes());
+    let mut ifd = |entries: &[(u16, u16, u32, u32)]| {
+        bytes.extend((entries.len() as u16).to_le_bytes());
+        for &(tag, kind, count, value) in entries {
+            bytes.extend(tag.to_le_bytes());
+            bytes.extend(kind.to_le_bytes());
+            bytes.extend(count.to_le_bytes());
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(0u32.to_le_bytes());
+    };
+    ifd(&[(0x010F, 2, 18, offsets[0]), (0x0110, 2, 11, offsets[1]), (0x8769, 4, 1, exif), (0x014A, 4, 1, sub)]);
+    ifd(&[(0x9003, 2, 20, offsets[2]), (0x9011, 2, 7, offsets[3])]);
+    ifd(&[(0x0100, 4, 1, 8256), (0x0101, 4, 1, 5504), (0x0201, 4, 1, offsets[4]), (0x0202, 4, 1, PREVIEW.len() as u32)]);
+    for string in strings.iter().copied().chain([PREVIEW]) {
+        bytes.extend(string);
+    }
+    bytes
+}
+
+/// A HEIC file's image size property, followed by its EXIF item.
+fn heic_file() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend(16u32.to_be_bytes());
+    bytes.extend(b"ftypheic\0\0\0\0");
+    for (width, height) in [(512u32, 512u32), (4032, 3024)] {
+        bytes.extend(20u32.to_be_bytes());
+        bytes.extend(b"ispe\0\0\0\0");
+        bytes.extend(width.to_be_bytes());
+        bytes.extend(height.to_be_bytes());
+    }
+    bytes.extend(b"\0\0\0\x06Exif\0\0MM\0*\0\0\0\x08\0\x01");
+    bytes.extend([0x01, 0x10, 0, 2, 0, 0, 0, 14, 0, 0, 0, 26, 0, 0, 0, 0]);
+    bytes.extend(b"iPhone 15 Pro\0");
+    bytes
+}
+
+#[test]
+fn raw_files_give_their_date_camera_size_and_preview() {
+    let raw = raw_file();
+    assert_eq!(photo::metadata(&raw), PhotoMetadata {
+        taken: Some(DateTime::parse_from_rfc3339("2023-03-14T09:30:00+02:00").unwrap().timestamp() as u64),
+        camera: Some("NIKON D850".into()),
+        width: Some(8256),
+        height: Some(5504),
+    });
+    assert_eq!(photo::preview(&raw), Some(PREVIEW));
+}
+
+#[test]
+fn heic_size_comes_from_the_largest_image() {
+    let metadata = photo::metadata(&heic_file());
+    assert_eq!(metadata.camera.as_deref(), Some("iPhone 15 Pro"));
+    assert_eq!((metadata.width, metadata.height), (Some(4032), Some(3024)));
+    assert_eq!(metadata.taken, None);
+}
+
+#[test]
+fn other_files_have_no_photo_metadata() {
+    assert!(photo::metadata(b"just some text").is_empty());
+    assert_eq!(photo::preview(b"II*\0\xFF\xFF\xFF\xFF"), None);
+}
+
+#[tokio::test]
+async fn photos_are_searchable_by_date_and_camera() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/photos/DSC_0001.NEF", raw_file());
+    memory.insert("/mem/photos/IMG_0002.HEIC", heic_file());
+    memory.insert("/mem/photos/notes.txt", "2023 trip");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing("/mem/photos").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "taken:2023").await, vec!["/mem/photos/DSC_0001.NEF"]);
+    assert_eq!(search_paths(&indexer, "camera:iphone").await, vec!["/mem/photos/IMG_0002.HEIC"]);
+
+    let results = indexer.search("DSC_0001").await.unwrap();
+    assert_eq!(results[0]["photo"]["camera"], "NIKON D850");
+    assert_eq!(results[0]["photo"]["width"], 8256);
+
+    let documents = indexer.get_documents(&["/mem/photos/IMG_0002.HEIC".to_string()]).await.unwrap();
+    assert_eq!(documents[0].as_ref().unwrap().photo.as_ref().unwrap().height, Some(3024));
+}
+
+#[tokio::test]
+async fn raw_previews_are_cached_as_jpegs() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/photos/DSC_0001.NEF", raw_file());
+    memory.insert("/mem/photos/notes.txt", "not a photo");
+    let indexer = fixture.indexer_with(memory);
+
+    let preview = indexer.photo_preview("/mem/photos/DSC_0001.NEF").await.unwrap().unwrap();
+    assert_eq!(std::fs::read(&preview).unwrap(), PREVIEW);
+    assert!(preview.starts_with(fixture.data_dir()));
+    assert_eq!(indexer.photo_preview("/mem/photos/DSC_0001.NEF").await.unwrap(), Some(preview));
+    assert_eq!(indexer.photo_preview("/mem/photos/notes.txt").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn previews_are_shared_by_content_and_evicted_by_size() {
+    let fixture = Fixture::new();
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.preview_cache_max_bytes = PREVIEW.len() as u64 + 4)
+        .unwrap();
+    let modified = SystemTime::now();
+    let memory = MemoryFileSystem::new();
+    memory.insert_modified("/mem/photos/DSC_0001.NEF", raw_file(), modified);
+    memory.insert("/mem/backup/DSC_0001.NEF", raw_file());
+    let mut edited = raw_file();
+    edited.push(0);
+    memory.insert("/mem/photos/DSC_0002.NEF", edited);
+    let indexer = fixture.indexer_with(memory.clone());
+
+    let first = indexer.photo_preview("/mem/photos/DSC_0001.NEF").await.unwrap().unwrap();
+    assert_eq!(indexer.photo_preview("/mem/backup/DSC_0001.NEF").await.unwrap(), Some(first.clone()));
+    assert_eq!(indexer.preview_cache_usage(), PreviewCacheUsage { files: 1, bytes: PREVIEW.len() as u64 });
+
+    // An unchanged file isn't read again
+    memory.insert_modified("/mem/photos/DSC_0001.NEF", vec![0; raw_file().len()], modified);
+    assert_eq!(indexer.photo_preview("/mem/photos/DSC_0001.NEF").await.unwrap(), Some(first.clone()));
+
+    let second = indexer.photo_preview("/mem/photos/DSC_0002.NEF").await.unwrap().unwrap();
+    assert_ne!(second, first);
+    assert!(!first.exists());
+    assert_eq!(indexer.clear_preview_cache().unwrap(), PreviewCacheUsage { files: 1, bytes: PREVIEW.len() as u64 });
+    assert!(!second.exists());
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::Fixture;
+use constella_core::pii::{self, PiiCategory, PiiCounts, PiiRegion, PiiSettings};
+use constella_core::SettingsManager;
+
+const SAMPLE: &str = "\
+Contact jane.doe@example.com or (555) 234-5678.
+SSN 123-45-6789, not 666-12-3456.
+UK: +44 20 7946 0958, NI AB 12 34 56 C and AB123456C.
+SIN 130 692 544 but not 130 692 545.
+Noise: 2023-04-05, order 1234567890, 192.168.100.200, v1.2.3, user@localhost.
+";
+
+fn counts(entries: &[(PiiCategory, u64)]) -> PiiCounts {
+    entries.iter().copied().collect()
+}
+
+#[test]
+fn matchers_follow_the_regions() {
+    assert_eq!(pii::count(SAMPLE, &PiiRegion::ALL), counts(&[
+        (PiiCategory::Email, 1),
+        (PiiCategory::PhoneNumber, 2),
+        (PiiCategory::UsSocialSecurityNumber, 1),
+        (PiiCategory::UkNationalInsuranceNumber, 2),
+        (PiiCategory::CanadianSocialInsuranceNumber, 1),
+    ]));
+    assert_eq!(pii::count(SAMPLE, &[PiiRegion::Gb]), counts(&[
+        (PiiCategory::Email, 1),
+        (PiiCategory::PhoneNumber, 1),
+        (PiiCategory::UkNationalInsuranceNumber, 2),
+    ]));
+}
+
+#[test]
+fn regions_default_to_the_locale() {
+    let settings = PiiSettings::default();
+    assert_eq!(settings.effective_regions(Some("en-GB")), vec![PiiRegion::Gb]);
+    assert_eq!(settings.effective_regions(Some("fr_CA.UTF-8")), vec![PiiRegion::Ca]);
+    let configured = PiiSettings { regions: vec![PiiRegion::Us], ..PiiSettings::default() };
+    assert_eq!(configured.effective_regions(Some("en-GB")), vec![PiiRegion::Us]);
+}
+
+#[tokio::test]
+async fn inventory_is_opt_in() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer_with(MemoryFileSystem::new());
+    assert!(indexer.pii_inventory("/mem", || true, |_, _| {}).await.unwrap_err().contains("turned off"));
+}
+
+#[tokio::test]
+async fn inventory_counts_per_directory_and_exports_csv() {
+    let fixture = Fixture::new();
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.pii_inventory = PiiSettings { enabled: true, regions: vec![PiiRegion::Us] })
+        .unwrap();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/crm/customers.txt", "ann@example.com, bob@example.org, SSN 123-45-6789");
+    memory.insert("/mem/crm/readme.md", "# CRM exports");
+    memory.insert("/mem/crm/exports, 2024/leads.txt", "call (415) 555-2671");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing("/mem/crm").await.unwrap();
+
+    let inventory = indexer.pii_inventory("/mem/crm", || true, |_, _| {}).await.unwrap();
+    assert_eq!(inventory.regions, vec![PiiRegion::Us]);
+    assert_eq!(inventory.files_scanned, 3);
+    let summary: Vec<(&str, u64, u64, PiiCounts)> = inventory.directories.iter()
+        .map(|directory| (directory.directory.as_str(), directory.files_scanned, directory.files_with_pii, directory.counts.clone()))
+        .collect();
+    assert_eq!(summary, vec![
+        ("/mem/crm", 2, 1, counts(&[(PiiCategory::Email, 2), (PiiCategory::UsSocialSecurityNumber, 1)])),
+        ("/mem/crm/exports, 2024", 1, 1, counts(&[(PiiCategory::PhoneNumber, 1)])),
+    ]);
+
+    assert_eq!(inventory.to_csv(), "\
+directory,files_scanned,files_with_pii,email,phone_number,us_social_security_number,uk_national_insurance_number,canadian_social_insurance_number
+/mem/crm,2,1,2,0,1,0,0
+\"/mem/crm/exports, 2024\",1,1,0,1,0,0,0
+");
+}
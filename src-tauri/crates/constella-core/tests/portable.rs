@@ -0,0 +1,71 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::indexing::listing::{DirectoryFilters, DirectorySort};
+use constella_core::indexing::{IndexManager, IndexOptions};
+use constella_core::portable::{self, PORTABLE_DATA_DIR, PORTABLE_MARKER};
+use constella_core::{Settings, SettingsManager};
+
+fn mounted_at(fixture: &Fixture, volume_root: &str) -> IndexManager {
+    let memory = MemoryFileSystem::new();
+    memory.insert(format!("{}/docs/plan.txt", volume_root), "launch plan");
+    memory.insert(format!("{}/docs/notes/todo.txt", volume_root), "launch checklist");
+    let settings = Arc::new(SettingsManager::load(fixture.data_dir().join("settings.json")));
+    let options = IndexOptions {
+        fs: memory,
+        portable_root: Some(PathBuf::from(volume_root)),
+        ..IndexOptions::default()
+    };
+    IndexManager::with_options(fixture.data_dir(), settings, options).unwrap()
+}
+
+#[test]
+fn marker_file_turns_portable_mode_on() {
+    let fixture = Fixture::new();
+    assert_eq!(portable::detect_in(fixture.root()), None);
+
+    fixture.file(PORTABLE_MARKER, "");
+    let layout = portable::detect_in(fixture.root()).unwrap();
+    assert_eq!(layout.data_dir, fixture.root().join(PORTABLE_DATA_DIR));
+    assert!(fixture.root().starts_with(&layout.volume_root));
+}
+
+#[tokio::test]
+async fn index_follows_the_volume_to_a_new_mount_point() {
+    let fixture = Fixture::new();
+    let indexer = mounted_at(&fixture, "/media/stick");
+    indexer.start_indexing("/media/stick/docs").await.unwrap();
+    assert_eq!(search_paths(&indexer, "launch").await, vec![
+        "/media/stick/docs/notes/todo.txt".to_string(),
+        "/media/stick/docs/plan.txt".to_string(),
+    ]);
+    drop(indexer);
+
+    let indexer = mounted_at(&fixture, "/mnt/usb");
+    assert_eq!(search_paths(&indexer, "launch").await, vec![
+        "/mnt/usb/docs/notes/todo.txt".to_string(),
+        "/mnt/usb/docs/plan.txt".to_string(),
+    ]);
+    let listing = indexer.list_directory("/mnt/usb/docs", DirectorySort::default(), &DirectoryFilters::default()).await.unwrap();
+    assert!(listing.from_index);
+    let names: Vec<&str> = listing.entries.iter().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(names, vec!["notes", "plan.txt"]);
+    let folders = indexer.get_path_info("/mnt/usb/docs").await.unwrap();
+    assert_eq!(folders.last().unwrap().file_count, 2);
+}
+
+#[test]
+fn settings_follow_a_moved_volume() {
+    let mut settings = Settings {
+        watched_roots: vec![PathBuf::from("/media/stick/docs"), PathBuf::from("/home/me")],
+        indexed_roots: vec![PathBuf::from("/media/stick/docs")],
+        ..Settings::default()
+    };
+    assert_eq!(settings.rebase_paths(Path::new("/media/stick"), Path::new("/mnt/usb")), 2);
+    assert_eq!(settings.watched_roots, vec![PathBuf::from("/mnt/usb/docs"), PathBuf::from("/home/me")]);
+    assert_eq!(settings.indexed_roots, vec![PathBuf::from("/mnt/usb/docs")]);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::memory_fs::{Fault, FaultyFileSystem, MemoryFileSystem};
+use common::{doc_count, Fixture};
+
+const ROOT: &str = "/mem/drive";
+
+#[tokio::test]
+async fn prioritized_folders_are_searchable_before_the_rest() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/drive/archive/a.txt", "a");
+    memory.insert("/mem/drive/archive/b.txt", "b");
+    memory.insert("/mem/drive/documents/plan.txt", "plan");
+    memory.insert("/mem/drive/documents/notes.txt", "notes");
+    memory.insert("/mem/drive/zzz/last.txt", "last");
+    let fs = FaultyFileSystem::new(memory);
+    // Keep the rest of the run going long enough to look at the index in between
+    fs.inject("/mem/drive/zzz/last.txt", Fault::Slow(Duration::from_millis(300)));
+    let indexer = Arc::new(fixture.indexer_with(fs));
+    indexer.prioritize_folder("/mem/drive/documents").unwrap();
+    indexer.prioritize_folder("/mem/drive/zzz").unwrap();
+    indexer.deprioritize_folder("/mem/drive/zzz").unwrap();
+
+    let mut completions = indexer.subscribe_priority_complete();
+    let watcher = {
+        let indexer = indexer.clone();
+        tokio::spawn(async move {
+            completions.changed().await.unwrap();
+            let completion = completions.borrow_and_update().clone().unwrap();
+            (completion, doc_count(&indexer).await, indexer.get_state())
+        })
+    };
+
+    indexer.start_indexing(ROOT).await.unwrap();
+    let (completion, searchable, state) = watcher.await.unwrap();
+
+    assert_eq!(completion.folders, vec![PathBuf::from("/mem/drive/documents")]);
+    assert_eq!(completion.files, 2);
+    assert_eq!(searchable, 2);
+    assert_eq!((state.priority_total, state.priority_processed), (2, 2));
+    assert_eq!(doc_count(&indexer).await, 5);
+}
+
+#[tokio::test]
+async fn no_completion_without_prioritized_folders() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/drive/a.txt", "a");
+    let indexer = fixture.indexer_with(memory);
+    let completions = indexer.subscribe_priority_complete();
+
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert!(!completions.has_changed().unwrap());
+    assert_eq!(indexer.get_state().priority_total, 0);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::profiles::{self, ProfileUpdate};
+use constella_core::SettingsManager;
+
+fn profile(name: &str, root: &str) -> ProfileUpdate {
+    ProfileUpdate {
+        name: name.into(),
+        roots: vec![PathBuf::from(root)],
+        hidden_labels: Vec::new(),
+        record_history: true,
+    }
+}
+
+#[test]
+fn pins_guard_switching_and_changes() {
+    let fixture = Fixture::new();
+    let settings = SettingsManager::load(fixture.data_dir().join("settings.json"));
+    profiles::save(&settings, profile("work", "/mem/work"), None).unwrap();
+    profiles::save(&settings, profile("personal", "/mem/home"), None).unwrap();
+    profiles::set_pin(&settings, "personal", Some("2468"), None).unwrap();
+
+    assert!(profiles::switch(&settings, Some("personal"), None).unwrap_err().contains("needs its PIN"));
+    assert!(profiles::switch(&settings, Some("personal"), Some("1357")).unwrap_err().contains("Wrong PIN"));
+    assert!(profiles::save(&settings, profile("personal", "/"), None).is_err());
+    // Showing everything would reveal the guarded profile
+    assert!(profiles::switch(&settings, None, None).is_err());
+    assert_eq!(settings.get().active_profile, None);
+
+    let listed = profiles::switch(&settings, Some("personal"), Some("2468")).unwrap();
+    let summary: Vec<(&str, bool, bool)> = listed.iter()
+        .map(|profile| (profile.name.as_str(), profile.has_pin, profile.active))
+        .collect();
+    assert_eq!(summary, vec![("work", false, false), ("personal", true, true)]);
+
+    // The PIN survives a reload and is never stored as typed
+    let stored = std::fs::read_to_string(fixture.data_dir().join("settings.json")).unwrap();
+    assert!(!stored.contains("2468"));
+    let reloaded = SettingsManager::load(fixture.data_dir().join("settings.json"));
+    profiles::set_pin(&reloaded, "personal", None, Some("2468")).unwrap();
+    profiles::switch(&reloaded, None, None).unwrap();
+}
+
+#[tokio::test]
+async fn searches_stay_within_the_active_profile() {
+    let fixture = Fixture::new();
+    let settings = SettingsManager::load(fixture.data_dir().join("settings.json"));
+    profiles::save(&settings, profile("work", "/mem/work"), None).unwrap();
+    profiles::switch(&settings, Some("work"), None).unwrap();
+
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/work/budget.txt", "quarterly budget");
+    memory.insert("/mem/home/budget.txt", "holiday budget");
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing("/mem").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "budget").await, vec!["/mem/work/budget.txt"]);
+    assert!(search_paths(&indexer, "holiday").await.is_empty());
+}
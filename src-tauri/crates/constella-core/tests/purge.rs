@@ -0,0 +1,68 @@
+mod common;
+
+use common::Fixture;
+use constella_core::purge::DataPurge;
+use constella_core::SettingsManager;
+
+#[tokio::test]
+async fn purge_removes_only_constella_data() {
+    let fixture = Fixture::new();
+    fixture.file("docs/notes.txt", "quarterly numbers");
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.secret_scanning_enabled = true)
+        .unwrap();
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.path("docs").to_string_lossy()).await.unwrap();
+    indexer.search("missing").await.unwrap();
+    drop(indexer);
+
+    let versions = fixture.data_dir().join("versions/abc");
+    std::fs::create_dir_all(&versions).unwrap();
+    std::fs::write(versions.join("1.bin"), "old contents").unwrap();
+    std::fs::write(fixture.data_dir().join("learning.json.tmp"), "{}").unwrap();
+    // Not Constella's, so never touched
+    std::fs::write(fixture.data_dir().join("notes.txt"), "someone else's").unwrap();
+
+    let purge = DataPurge::new(fixture.data_dir());
+    assert!(purge.purge("guess", true).is_err());
+    // A wrong token uses up the real one
+    let token = purge.issue_token().unwrap();
+    assert!(purge.purge("guess", true).is_err());
+    assert!(purge.purge(&token, true).unwrap_err().contains("Invalid"));
+    assert!(!purge.has_purged());
+
+    let token = purge.issue_token().unwrap();
+    assert_eq!(token.len(), 32);
+    let manifest = purge.purge(&token, true).unwrap();
+    assert!(purge.has_purged());
+    assert!(manifest.overwritten);
+    assert!(manifest.failed.is_empty());
+    assert!(manifest.removed.iter().any(|file| file.path.ends_with("zero_results.json")));
+    assert!(manifest.removed.iter().any(|file| file.path.ends_with("1.bin") && file.bytes == 12));
+    assert_eq!(manifest.bytes_removed, manifest.removed.iter().map(|file| file.bytes).sum::<u64>());
+
+    let mut left: Vec<String> = std::fs::read_dir(fixture.data_dir()).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    left.sort();
+    assert_eq!(left, vec!["notes.txt", "settings.json"]);
+    assert_eq!(manifest.kept.len(), 2);
+    assert!(SettingsManager::load(fixture.data_dir().join("settings.json")).get().secret_scanning_enabled);
+
+    // Tokens are single use
+    assert!(purge.purge(&token, false).is_err());
+}
+
+#[test]
+fn purge_refuses_a_folder_that_isnt_constellas() {
+    let fixture = Fixture::new();
+    let other = fixture.path("other app");
+    std::fs::create_dir_all(&other).unwrap();
+    std::fs::write(other.join("learning.json"), "not ours").unwrap();
+
+    let purge = DataPurge::new(&other);
+    let token = purge.issue_token().unwrap();
+    assert!(purge.purge(&token, false).unwrap_err().contains("doesn't look like"));
+    assert!(!purge.has_purged());
+    assert!(other.join("learning.json").exists());
+}
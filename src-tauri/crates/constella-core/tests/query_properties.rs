@@ -0,0 +1,109 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use common::{search_paths, Fixture};
+use proptest::prelude::*;
+
+/// A single tokenizer word: letters and digits from any script, short enough
+/// that the long-token filter never drops it.
+fn word() -> impl Strategy<Value = String> {
+    "[\\p{L}\\p{N}]{1,8}"
+}
+
+/// Filenames made of one to four words joined by spaces, punctuation or
+/// emoji, which the tokenizer has to treat as separators.
+fn file_name() -> impl Strategy<Value = String> {
+    let separator = prop_oneof![
+        Just(" ".to_string()),
+        Just("_".to_string()),
+        Just("-".to_string()),
+        Just(" 🚀 ".to_string()),
+        Just("·".to_string()),
+        Just("😀".to_string()),
+    ];
+    (word(), prop::collection::vec((separator, word()), 0..4)).prop_map(|(first, rest)| {
+        rest.into_iter().fold(first, |mut name, (separator, word)| {
+            name.push_str(&separator);
+            name.push_str(&word);
+            name
+        })
+    })
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build runtime")
+}
+
+/// Wraps `name` in a phrase query so every word has to match, in order.
+fn phrase(name: &str) -> String {
+    format!("\"{}\"", name)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn exact_name_is_found(name in file_name()) {
+        let fixture = Fixture::new();
+        let path = fixture.file(&format!("{}.txt", name), "contents");
+
+        let hits = runtime().block_on(async {
+            let indexer = fixture.indexer();
+            indexer.start_indexing(fixture.root_str()).await.unwrap();
+            search_paths(&indexer, &phrase(&name)).await
+        });
+
+        prop_assert!(
+            hits.contains(&path.to_string_lossy().into_owned()),
+            "{:?} not found, got {:?}", path, hits
+        );
+    }
+
+    #[test]
+    fn directory_filter_matches_exactly(
+        files in prop::collection::vec((0..3usize, file_name(), 0..4096usize), 1..12)
+    ) {
+        let fixture = Fixture::new();
+        let mut expected: BTreeMap<usize, Vec<(String, u64)>> = BTreeMap::new();
+        let mut created = Vec::new();
+        for (i, (dir, name, size)) in files.iter().enumerate() {
+            // The index suffix keeps names unique however the generator collides
+            let name = format!("{} {}", name, i);
+            let path = fixture.sized_file(&format!("d{}/{}.txt", dir, name), *size);
+            expected.entry(*dir).or_default().push((path.to_string_lossy().into_owned(), *size as u64));
+            created.push((name, path));
+        }
+
+        runtime().block_on(async {
+            let indexer = fixture.indexer();
+            indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+            for dir in 0..3 {
+                let mut found: Vec<(String, u64)> = indexer
+                    .documents_under(fixture.path(&format!("d{}", dir)))
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|file| (file.path, file.size))
+                    .collect();
+                found.sort();
+                let mut wanted = expected.get(&dir).cloned().unwrap_or_default();
+                wanted.sort();
+                prop_assert_eq!(found, wanted);
+            }
+
+            for (name, path) in &created {
+                let hits = search_paths(&indexer, &phrase(name)).await;
+                prop_assert!(
+                    hits.contains(&path.to_string_lossy().into_owned()),
+                    "{:?} not found, got {:?}", path, hits
+                );
+            }
+            Ok(())
+        })?;
+    }
+}
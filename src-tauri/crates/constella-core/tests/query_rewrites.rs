@@ -0,0 +1,58 @@
+mod common;
+
+use common::{search_paths, Fixture};
+use constella_core::search::rewrite::{normalize_rule, rewrite_query};
+use constella_core::search::{QueryRewrites, SearchOptions};
+use constella_core::SettingsManager;
+
+fn rewrites(rules: &[(&str, &[&str])]) -> QueryRewrites {
+    rules.iter()
+        .map(|(term, expansions)| (term.to_string(), expansions.iter().map(|e| e.to_string()).collect()))
+        .collect()
+}
+
+#[test]
+fn plain_words_are_replaced_by_their_expansions() {
+    let table = rewrites(&[("jpeg", &["jpg"]), ("photo", &["jpg", "png", "heic"])]);
+
+    assert_eq!(rewrite_query("holiday JPEG", &table), "holiday jpg");
+    assert_eq!(rewrite_query("photo 2023", &table), "(jpg OR png OR heic) 2023");
+}
+
+#[test]
+fn explicit_syntax_is_left_alone() {
+    let table = rewrites(&[("jpeg", &["jpg"])]);
+
+    assert_eq!(rewrite_query("\"jpeg files\" name:jpeg", &table), "\"jpeg files\" name:jpeg");
+    assert_eq!(rewrite_query("-jpeg", &table), "-jpeg");
+}
+
+#[test]
+fn rules_are_normalized_and_validated() {
+    let (term, expansions) = normalize_rule(" PPT ", &["pptx".into(), "ppt".into(), "PPT".into()]).unwrap();
+    assert_eq!(term, "ppt");
+    assert_eq!(expansions, vec!["pptx", "ppt"]);
+
+    assert!(normalize_rule("two words", &["x".into()]).is_err());
+    assert!(normalize_rule("ppt", &[" ".into()]).is_err());
+    assert!(normalize_rule("ppt", &["name:ppt".into()]).is_err());
+}
+
+#[tokio::test]
+async fn searches_apply_rewrites_unless_skipped() {
+    let fixture = Fixture::new();
+    fixture.file("pictures/holiday.jpg", "");
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.query_rewrites = rewrites(&[("jpeg", &["jpg"])]))
+        .unwrap();
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "jpeg").await, vec![fixture.path("pictures/holiday.jpg").to_string_lossy()]);
+
+    let verbatim = indexer
+        .search_with_options("jpeg", &SearchOptions { skip_rewrites: true, ..SearchOptions::default() })
+        .await
+        .unwrap();
+    assert!(verbatim.is_empty());
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use common::Fixture;
+use constella_core::search::boosts::BoostMatcher;
+use constella_core::search::{FileTypeBoost, RankingWeights, SearchOptions};
+use constella_core::SettingsManager;
+
+fn set_weights(fixture: &Fixture, weights: RankingWeights) {
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| settings.ranking = weights)
+        .unwrap();
+}
+
+fn ranked_names(results: &[serde_json::Value]) -> Vec<&str> {
+    results.iter().filter_map(|hit| hit["name"].as_str()).collect()
+}
+
+#[tokio::test]
+async fn name_matches_outrank_content_matches_by_default() {
+    let fixture = Fixture::new();
+    fixture.file("docs/budget.txt", "numbers");
+    fixture.file("docs/notes.txt", "remember the budget meeting");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let results = indexer.search("budget").await.unwrap();
+
+    assert_eq!(ranked_names(&results), vec!["budget.txt", "notes.txt"]);
+}
+
+#[tokio::test]
+async fn weights_from_settings_change_the_order() {
+    let fixture = Fixture::new();
+    fixture.file("docs/budget.txt", "numbers");
+    fixture.file("docs/notes.txt", "remember the budget meeting");
+    set_weights(&fixture, RankingWeights {
+        name_boost: 0.1,
+        path_boost: 0.1,
+        content_boost: 10.0,
+        recency_weight: 0.0,
+        ..RankingWeights::default()
+    });
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let results = indexer.search("budget").await.unwrap();
+
+    assert_eq!(ranked_names(&results), vec!["notes.txt", "budget.txt"]);
+    assert_eq!(indexer.ranking_weights().content_boost, 10.0);
+}
+
+#[test]
+fn invalid_weights_are_rejected() {
+    assert!(RankingWeights { name_boost: -1.0, ..RankingWeights::default() }.validate().is_err());
+    assert!(RankingWeights { recency_half_life_days: 0.0, ..RankingWeights::default() }.validate().is_err());
+    assert!(RankingWeights::default().validate().is_ok());
+}
+
+#[test]
+fn recency_boost_halves_over_the_half_life() {
+    let weights = RankingWeights { recency_weight: 1.0, recency_half_life_days: 10.0, ..RankingWeights::default() };
+    let now = 1_700_000_000;
+
+    assert!((weights.recency_multiplier(now, now) - 2.0).abs() < 1e-6);
+    assert!((weights.recency_multiplier(now - 10 * 86_400, now) - 1.5).abs() < 1e-6);
+    assert_eq!(RankingWeights { recency_weight: 0.0, ..weights }.recency_multiplier(0, now), 1.0);
+}
+
+#[tokio::test]
+async fn explain_reports_matched_fields_and_multipliers() {
+    let fixture = Fixture::new();
+    fixture.file("docs/budget.txt", "budget budget budget");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let plain = indexer.search("budget").await.unwrap();
+    assert!(plain[0].get("explain").is_none());
+
+    let results = indexer
+        .search_with_options("budget", &SearchOptions { explain: true, ..SearchOptions::default() })
+        .await
+        .unwrap();
+    let explain = &results[0]["explain"];
+
+    let mut fields: Vec<&str> = explain["matched_terms"].as_array().unwrap()
+        .iter()
+        .filter_map(|term| term["field"].as_str())
+        .collect();
+    fields.sort();
+    assert_eq!(fields, vec!["content", "name", "path"]);
+
+    let content = explain["matched_terms"].as_array().unwrap()
+        .iter()
+        .find(|term| term["field"] == "content")
+        .unwrap();
+    assert_eq!(content["term_frequency"], 3);
+    assert_eq!(content["boost"], 1.0);
+
+    let text_score = explain["text_score"].as_f64().unwrap();
+    let multiplier = explain["recency_multiplier"].as_f64().unwrap();
+    let final_score = explain["final_score"].as_f64().unwrap();
+    assert!(multiplier > 1.0);
+    assert!((text_score * multiplier - final_score).abs() < 1e-3 * final_score);
+}
+
+#[tokio::test]
+async fn file_type_boosts_reorder_and_hide_results() {
+    let fixture = Fixture::new();
+    fixture.file("logs/budget.log", "budget");
+    fixture.file("docs/budget.md", "budget");
+    fixture.file("node_modules/pkg/budget.txt", "budget");
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| {
+            settings.file_type_boosts = vec![
+                FileTypeBoost { pattern: ".log".into(), multiplier: 0.2 },
+                FileTypeBoost { pattern: "md".into(), multiplier: 2.0 },
+                FileTypeBoost { pattern: "**/node_modules/**".into(), multiplier: 0.0 },
+            ];
+        })
+        .unwrap();
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let results = indexer
+        .search_with_options("budget", &SearchOptions { explain: true, ..SearchOptions::default() })
+        .await
+        .unwrap();
+
+    assert_eq!(ranked_names(&results), vec!["budget.md", "budget.log"]);
+    assert_eq!(results[0]["explain"]["file_type_multiplier"], 2.0);
+}
+
+#[test]
+fn invalid_file_type_boosts_are_rejected() {
+    let boost = |pattern: &str, multiplier| vec![FileTypeBoost { pattern: pattern.into(), multiplier }];
+
+    assert!(BoostMatcher::new(&boost("log", -1.0)).is_err());
+    assert!(BoostMatcher::new(&boost("**/[", 1.0)).is_err());
+    assert!(BoostMatcher::new(&boost(".", 1.0)).is_err());
+}
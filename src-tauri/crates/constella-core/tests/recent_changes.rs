@@ -0,0 +1,49 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::Fixture;
+use constella_core::indexing::changelog::ChangeKind;
+use constella_core::watcher::ChangeType;
+
+const ROOT: &str = "/mem/home";
+
+#[tokio::test]
+async fn incremental_changes_feed_whats_new_but_full_runs_do_not() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/home/notes.txt", "old");
+    memory.insert("/mem/home/todo.txt", "old");
+    let indexer = fixture.indexer_with(memory.clone());
+    indexer.start_indexing(ROOT).await.unwrap();
+    assert!(indexer.recent_changes(10, &[]).is_empty());
+
+    memory.insert("/mem/home/Downloads/invoice.pdf", "pdf");
+    memory.insert("/mem/home/notes.txt", "edited");
+    indexer.apply_changes(&[
+        ("/mem/home/Downloads/invoice.pdf".into(), ChangeType::Created),
+        ("/mem/home/notes.txt".into(), ChangeType::Modified),
+    ]).await.unwrap();
+    // Editing a new download keeps it new, and deleted files leave the feed
+    memory.insert("/mem/home/Downloads/invoice.pdf", "pdf v2");
+    memory.remove("/mem/home/todo.txt");
+    indexer.apply_changes(&[
+        ("/mem/home/Downloads/invoice.pdf".into(), ChangeType::Modified),
+        ("/mem/home/todo.txt".into(), ChangeType::Deleted),
+    ]).await.unwrap();
+
+    let feed = indexer.recent_changes(10, &[]);
+    let summary: Vec<(&str, ChangeKind)> = feed.iter().map(|change| (change.path.as_str(), change.kind)).collect();
+    assert_eq!(summary, vec![
+        ("/mem/home/Downloads/invoice.pdf", ChangeKind::Added),
+        ("/mem/home/notes.txt", ChangeKind::Updated),
+    ]);
+    assert_eq!(feed[0].size, 6);
+
+    let updated = indexer.recent_changes(10, &[ChangeKind::Updated]);
+    assert_eq!(updated.len(), 1);
+    assert_eq!(indexer.recent_changes(1, &[]).len(), 1);
+
+    drop(indexer);
+    let reopened = fixture.indexer_with(memory);
+    assert_eq!(reopened.recent_changes(10, &[]), feed);
+}
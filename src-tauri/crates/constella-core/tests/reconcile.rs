@@ -0,0 +1,109 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{doc_count, search_paths, Fixture};
+use constella_core::indexing::reconcile::ReconcilePhase;
+use constella_core::SettingsManager;
+
+const ROOT: &str = "/mem/project";
+
+fn project() -> std::sync::Arc<MemoryFileSystem> {
+    let fs = MemoryFileSystem::new();
+    fs.insert("/mem/project/src/main.rs", "fn main() {}");
+    fs.insert("/mem/project/node_modules/pkg/index.js", "module.exports = {}");
+    fs.insert("/mem/project/build/out.log", "built");
+    fs.insert("/mem/other/notes.txt", "other root");
+    fs
+}
+
+fn settings(fixture: &Fixture) -> SettingsManager {
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+}
+
+#[tokio::test]
+async fn applies_changed_exclusions_and_roots_incrementally() {
+    let fixture = Fixture::new();
+    settings(&fixture).update(|s| s.indexing.exclude = vec!["**/build/**".into()]).unwrap();
+    let indexer = fixture.indexer_with(project());
+    indexer.start_indexing(ROOT).await.unwrap();
+    assert_eq!(doc_count(&indexer).await, 2);
+    // Release the writer; the settings change is picked up by a fresh manager
+    drop(indexer);
+
+    settings(&fixture).update(|s| {
+        s.indexing.exclude = vec!["**/node_modules/**".into()];
+        s.watched_roots = vec!["/mem/other".into()];
+    }).unwrap();
+    let indexer = fixture.indexer_with(project());
+    let mut progress = indexer.subscribe_reconcile_progress();
+
+    let outcome = indexer.reconcile().await.unwrap();
+
+    assert_eq!(outcome.phase, ReconcilePhase::Completed);
+    assert_eq!((outcome.to_remove, outcome.removed), (1, 1));
+    assert_eq!((outcome.to_add, outcome.added), (2, 2));
+    assert!(progress.has_changed().unwrap());
+    assert_eq!(progress.borrow_and_update().phase, ReconcilePhase::Completed);
+    assert_eq!(search_paths(&indexer, "index").await, Vec::<String>::new());
+    assert_eq!(search_paths(&indexer, "out").await, vec!["/mem/project/build/out.log"]);
+    assert_eq!(search_paths(&indexer, "notes").await, vec!["/mem/other/notes.txt"]);
+
+    drop(indexer);
+    settings(&fixture).update(|s| s.watched_roots.clear()).unwrap();
+    let indexer = fixture.indexer_with(project());
+    let outcome = indexer.reconcile().await.unwrap();
+    assert_eq!(outcome.removed, 1);
+    assert!(search_paths(&indexer, "notes").await.is_empty());
+}
+
+#[tokio::test]
+async fn abort_outside_a_run_is_ignored() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer_with(project());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    indexer.abort_reconciliation();
+    let outcome = indexer.reconcile().await.unwrap();
+
+    assert_eq!(outcome.phase, ReconcilePhase::Completed);
+    assert_eq!(outcome.to_remove + outcome.to_add, 0);
+}
+
+#[tokio::test]
+async fn files_deleted_while_unwatched_are_pruned() {
+    let fixture = Fixture::new();
+    let memory = project();
+    memory.insert("/mem/project/docs/old.txt", "retired plan");
+    let indexer = fixture.indexer_with(memory.clone());
+    indexer.start_indexing(ROOT).await.unwrap();
+    assert_eq!(search_paths(&indexer, "retired").await, vec!["/mem/project/docs/old.txt"]);
+
+    memory.remove("/mem/project/docs/old.txt");
+    let summary = indexer.prune_index().await.unwrap();
+
+    assert_eq!((summary.checked, summary.removed, summary.unreachable), (4, 1, 0));
+    assert!(search_paths(&indexer, "retired").await.is_empty());
+    assert_eq!(indexer.prune_index().await.unwrap().removed, 0);
+
+    // Reconciling picks them up as well
+    memory.remove("/mem/project/build/out.log");
+    let outcome = indexer.reconcile().await.unwrap();
+    assert_eq!((outcome.to_remove, outcome.removed), (1, 1));
+    assert!(search_paths(&indexer, "out").await.is_empty());
+}
+
+#[tokio::test]
+async fn files_under_unreachable_roots_are_kept() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/usb/photos/trip.jpg", "jpeg");
+    let indexer = fixture.indexer_with(memory.clone());
+    indexer.start_indexing("/mem/usb").await.unwrap();
+
+    // The whole drive is gone, not the file
+    memory.remove("/mem/usb/photos/trip.jpg");
+    let summary = indexer.prune_index().await.unwrap();
+
+    assert_eq!((summary.removed, summary.unreachable), (0, 1));
+    assert_eq!(doc_count(&indexer).await, 1);
+}
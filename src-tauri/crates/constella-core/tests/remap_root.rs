@@ -0,0 +1,43 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::indexing::paths::PathRemap;
+use constella_core::SettingsManager;
+
+#[tokio::test]
+async fn moved_folders_resolve_without_reindexing() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/old/docs/plan.txt", "launch plan");
+    memory.insert("/mem/old/docs/notes/todo.txt", "launch checklist");
+    let indexer = fixture.indexer_with(memory.clone());
+    indexer.start_indexing("/mem/old/docs").await.unwrap();
+
+    for file in ["docs/plan.txt", "docs/notes/todo.txt"] {
+        let content = if file.ends_with("plan.txt") { "launch plan" } else { "launch checklist" };
+        memory.remove(format!("/mem/old/{}", file));
+        memory.insert(format!("/mem/new/{}", file), content);
+    }
+    assert!(indexer.remap_root("/mem/old", "/mem/missing").await.unwrap_err().contains("isn't a folder"));
+    let remaps = indexer.remap_root("/mem/old", "/mem/new").await.unwrap();
+    assert_eq!(remaps, vec![PathRemap { from: "/mem/old".into(), to: "/mem/new".into() }]);
+
+    assert_eq!(search_paths(&indexer, "launch").await, vec!["/mem/new/docs/notes/todo.txt", "/mem/new/docs/plan.txt"]);
+    let documents = indexer.get_documents(&["/mem/new/docs/plan.txt".to_string()]).await.unwrap();
+    assert!(documents[0].is_some());
+    let folders = indexer.get_path_info("/mem/new/docs").await.unwrap();
+    assert_eq!(folders.last().unwrap().file_count, 2);
+    let settings = SettingsManager::load(fixture.data_dir().join("settings.json")).get();
+    assert_eq!(settings.indexed_roots, vec![PathBuf::from("/mem/new/docs")]);
+
+    // Moving again folds into the first remap, which survives a restart
+    memory.insert("/mem/newer/docs/plan.txt", "launch plan");
+    indexer.remap_root("/mem/new", "/mem/newer").await.unwrap();
+    drop(indexer);
+    let indexer = fixture.indexer_with(memory);
+    assert_eq!(indexer.path_remaps(), vec![PathRemap { from: "/mem/old".into(), to: "/mem/newer".into() }]);
+    assert_eq!(search_paths(&indexer, "plan").await, vec!["/mem/newer/docs/plan.txt"]);
+}
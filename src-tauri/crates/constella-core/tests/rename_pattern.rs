@@ -0,0 +1,86 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::{search_paths, Fixture};
+use constella_core::journal::OperationJournal;
+use constella_core::rename::{self, RenameConflict, RenamePattern};
+
+fn pattern(template: &str, regex: Option<&str>) -> RenamePattern {
+    RenamePattern { template: template.to_string(), regex: regex.map(str::to_string), ..RenamePattern::default() }
+}
+
+fn names(preview: &rename::RenamePreview) -> Vec<&str> {
+    preview.proposals.iter().map(|proposal| proposal.new_name.as_str()).collect()
+}
+
+#[test]
+fn templates_fill_in_tokens_and_capture_groups() {
+    let fixture = Fixture::new();
+    let paths = vec![
+        fixture.file("IMG_0042.jpg", "a"),
+        fixture.file("IMG_0043.jpg", "b"),
+        fixture.file("notes.txt", "c"),
+    ];
+
+    let preview = rename::preview(&paths, &pattern("trip-{counter:3}.{ext}", None)).unwrap();
+    assert_eq!(names(&preview), vec!["trip-001.jpg", "trip-002.jpg", "trip-003.txt"]);
+    assert_eq!(preview.conflicts, 0);
+
+    // Files the regex doesn't match keep their names
+    let preview = rename::preview(&paths, &pattern("photo {number} ({name}).{ext}", Some(r"^IMG_(?P<number>\d+)"))).unwrap();
+    assert_eq!(names(&preview), vec!["photo 0042 (IMG_0042).jpg", "photo 0043 (IMG_0043).jpg", "notes.txt"]);
+    assert!(!preview.proposals[2].changed);
+
+    let today = chrono::Local::now().format("%Y").to_string();
+    let preview = rename::preview(&paths[2..], &pattern("{{{date:%Y}}} {1}", Some(r"(\w+)\.txt"))).unwrap();
+    assert_eq!(names(&preview), vec![format!("{{{}}} notes", today)]);
+
+    assert!(rename::preview(&paths, &pattern("{2}", Some(r"(\d+)"))).unwrap_err().contains("no group 2"));
+    assert!(rename::preview(&paths, &pattern("{size}", None)).unwrap_err().contains("Unknown token"));
+    assert!(rename::preview(&paths, &pattern("{name", None)).unwrap_err().contains("Unclosed"));
+}
+
+#[test]
+fn conflicts_are_reported_before_anything_is_renamed() {
+    let fixture = Fixture::new();
+    let paths = vec![
+        fixture.file("a.txt", "a"),
+        fixture.file("b.txt", "b"),
+        fixture.file("c.md", "c"),
+        fixture.path("gone.txt"),
+    ];
+    fixture.file("taken.md", "already here");
+
+    let preview = rename::preview(&paths, &pattern("{name}", Some(r"^[ab]"))).unwrap();
+    let conflicts: Vec<Option<RenameConflict>> = preview.proposals.iter().map(|proposal| proposal.conflict).collect();
+    assert_eq!(conflicts, vec![None, None, None, Some(RenameConflict::Missing)]);
+
+    let preview = rename::preview(&paths[..3], &pattern("same.txt", Some(r"txt$"))).unwrap();
+    assert_eq!(preview.proposals[0].conflict, Some(RenameConflict::Duplicate));
+    assert_eq!(preview.proposals[2].conflict, None);
+    let preview = rename::preview(&paths[2..3], &pattern("taken.{ext}", None)).unwrap();
+    assert_eq!(preview.proposals[0].conflict, Some(RenameConflict::Exists));
+    let preview = rename::preview(&paths[..1], &pattern("../{name}", None)).unwrap();
+    assert_eq!(preview.proposals[0].conflict, Some(RenameConflict::InvalidName));
+    assert!(preview.renames().is_err());
+}
+
+#[tokio::test]
+async fn applied_renames_update_the_index_and_can_be_undone() {
+    let fixture = Fixture::new();
+    let paths = vec![fixture.file("docs/draft.txt", "launch draft"), fixture.file("docs/final.txt", "launch final")];
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let journal = OperationJournal::load(fixture.data_dir());
+
+    let renames = rename::preview(&paths, &pattern("{counter}-{name}.{ext}", None)).unwrap().renames().unwrap();
+    let applied = journal.rename(&renames).unwrap();
+    let moves: Vec<(PathBuf, PathBuf)> = applied.moves.iter().map(|step| (step.from.clone(), step.to.clone())).collect();
+    indexer.index_moves(&moves).await.unwrap();
+    let path = |relative: &str| fixture.path(relative).to_string_lossy().into_owned();
+    assert_eq!(search_paths(&indexer, "launch").await, vec![path("docs/1-draft.txt"), path("docs/2-final.txt")]);
+
+    journal.undo_last().unwrap().unwrap();
+    assert!(paths.iter().all(|path| path.exists()));
+}
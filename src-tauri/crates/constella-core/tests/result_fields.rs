@@ -0,0 +1,85 @@
+mod common;
+
+use common::{search_paths, Fixture};
+use constella_core::search::{FacetCount, ResultFields, SearchOptions};
+
+#[tokio::test]
+async fn paths_only_results_carry_nothing_else() {
+    let fixture = Fixture::new();
+    fixture.file("budget.txt", "numbers");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let options = SearchOptions { fields: ResultFields::Paths, ..SearchOptions::default() };
+    let results = indexer.search_with_options("budget", &options).await.unwrap();
+
+    assert_eq!(results.len(), 1);
+    let mut keys: Vec<&String> = results[0].as_object().unwrap().keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec!["id", "path"]);
+}
+
+#[tokio::test]
+async fn snippets_highlight_matches_in_content_or_path() {
+    let fixture = Fixture::new();
+    fixture.file("notes/plan.txt", "the quarterly budget is final");
+    fixture.file("budget.bin", [0u8, 1, 2]);
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let options = SearchOptions { fields: ResultFields::Snippets, ..SearchOptions::default() };
+    let results = indexer.search_with_options("budget", &options).await.unwrap();
+    let snippet = |name: &str| {
+        results.iter()
+            .find(|result| result["name"] == name)
+            .and_then(|result| result["snippet"].as_str())
+            .unwrap()
+            .to_string()
+    };
+
+    assert!(snippet("plan.txt").contains("quarterly <b>budget</b> is"));
+    assert!(snippet("budget.bin").contains("<b>budget</b>"));
+
+    let metadata = indexer.search("budget").await.unwrap();
+    assert!(metadata.iter().all(|result| result.get("snippet").is_none() && result.get("size").is_some()));
+}
+
+#[tokio::test]
+async fn facets_count_file_types_of_all_matches() {
+    let fixture = Fixture::new();
+    fixture.file("report_a.pdf", "");
+    fixture.file("report_b.PDF", "");
+    fixture.file("report.txt", "");
+    fixture.file("REPORT", "");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let options = SearchOptions { facets: true, ..SearchOptions::default() };
+    let response = indexer.search_response("report", &options).await.unwrap();
+    let facets = response.facets.unwrap();
+
+    let count = |value: &str, count| FacetCount { value: value.to_string(), count };
+    assert_eq!(facets.file_types, vec![count("pdf", 2), count("", 1), count("txt", 1)]);
+    assert_eq!(facets.categories, vec![count("application", 2), count("other", 1), count("text", 1)]);
+    assert!(indexer.search_response("report", &SearchOptions::default()).await.unwrap().facets.is_none());
+}
+
+#[tokio::test]
+async fn type_filters_drill_into_a_facet() {
+    let fixture = Fixture::new();
+    let pdf = fixture.file("report.pdf", "");
+    let png = fixture.file("report.png", "");
+    let jpeg = fixture.file("report.JPG", "");
+    fixture.file("report.txt", "");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let paths = |paths: &[&std::path::PathBuf]| -> Vec<String> {
+        paths.iter().map(|path| path.to_string_lossy().into_owned()).collect()
+    };
+    assert_eq!(search_paths(&indexer, "report type:pdf").await, paths(&[&pdf]));
+    assert_eq!(search_paths(&indexer, "type:.PDF").await, paths(&[&pdf]));
+    assert_eq!(search_paths(&indexer, "report type:image").await, paths(&[&jpeg, &png]));
+    assert_eq!(search_paths(&indexer, "type:pdf type:png").await, paths(&[&pdf, &png]));
+    assert!(indexer.search("report type:").await.unwrap_err().contains("type:"));
+}
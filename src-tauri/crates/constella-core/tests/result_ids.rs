@@ -0,0 +1,52 @@
+mod common;
+
+use common::Fixture;
+use constella_core::search::SearchOptions;
+
+fn ids(results: &[serde_json::Value]) -> Vec<String> {
+    results.iter().map(|result| result["id"].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn ids_survive_reindexing() {
+    let fixture = Fixture::new();
+    let plan = fixture.file("plan.txt", "launch plan");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let first = indexer.search_response("plan", &SearchOptions::default()).await.unwrap();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let again = indexer.search_response("plan", &SearchOptions::default()).await.unwrap();
+
+    assert_eq!(ids(&first.results), ids(&again.results));
+    assert_eq!(indexer.resolve_ids(&ids(&first.results)).unwrap(), vec![plan]);
+    assert!(indexer.resolve_ids(&["0000000000000000".to_string()]).unwrap_err().contains("No result"));
+}
+
+#[tokio::test]
+async fn equal_scores_come_back_in_the_same_order() {
+    let fixture = Fixture::new();
+    for n in 0..20 {
+        fixture.file(&format!("copies/{}/notes.txt", n), "");
+    }
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let first = indexer.search_response("notes", &SearchOptions::default()).await.unwrap();
+    let again = indexer.search_response("notes", &SearchOptions::default()).await.unwrap();
+    assert_eq!(first.results.len(), 20);
+    assert_eq!(ids(&first.results), ids(&again.results));
+}
+
+#[tokio::test]
+async fn collections_take_result_ids() {
+    let fixture = Fixture::new();
+    let taxes = fixture.file("taxes.txt", "return");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let response = indexer.search_response("taxes", &SearchOptions::default()).await.unwrap();
+    let paths = indexer.resolve_ids(&ids(&response.results)).unwrap();
+    indexer.add_to_collection("Tax 2023", &paths).unwrap();
+    assert_eq!(indexer.collection_items("Tax 2023").unwrap(), vec![taxes.to_string_lossy().into_owned()]);
+}
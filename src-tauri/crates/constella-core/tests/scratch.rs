@@ -0,0 +1,52 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::Fixture;
+use constella_core::indexing::scratch::ScratchIndexes;
+use constella_core::SettingsManager;
+
+fn scratch(fixture: &Fixture) -> ScratchIndexes {
+    let settings = Arc::new(SettingsManager::load(fixture.data_dir().join("settings.json")));
+    ScratchIndexes::new(fixture.data_dir().join("scratch"), settings)
+}
+
+#[tokio::test]
+async fn scratch_index_covers_only_its_paths() {
+    let fixture = Fixture::new();
+    fixture.file("archive/readme.txt", "inside");
+    fixture.file("archive/nested/report.pdf", "inside");
+    let loose = fixture.file("loose/invoice.txt", "picked individually");
+    fixture.file("loose/ignored.txt", "not picked");
+    let scratch = scratch(&fixture);
+
+    let info = scratch.create(&[fixture.path("archive"), loose]).await.unwrap();
+
+    assert_eq!(info.document_count, 3);
+    assert_eq!(scratch.search(&info.id, "report").await.unwrap().len(), 1);
+    assert_eq!(scratch.search(&info.id, "invoice").await.unwrap().len(), 1);
+    assert!(scratch.search(&info.id, "ignored").await.unwrap().is_empty());
+    assert!(!fixture.data_dir().join("search_index").exists());
+}
+
+#[tokio::test]
+async fn dropped_scratch_indexes_are_gone() {
+    let fixture = Fixture::new();
+    fixture.file("folder/a.txt", "a");
+    let scratch = scratch(&fixture);
+
+    let info = scratch.create(&[fixture.path("folder")]).await.unwrap();
+    scratch.remove(&info.id).unwrap();
+
+    assert!(scratch.list().is_empty());
+    assert!(scratch.search(&info.id, "a").await.is_err());
+}
+
+#[tokio::test]
+async fn missing_paths_are_rejected() {
+    let fixture = Fixture::new();
+    let scratch = scratch(&fixture);
+
+    assert!(scratch.create(&[fixture.path("does-not-exist")]).await.is_err());
+    assert!(scratch.create(&[]).await.is_err());
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::indexing::screenshots::is_screenshot;
+use constella_core::indexing::{IndexManager, IndexOptions};
+use constella_core::ocr::TextRecognizer;
+use constella_core::SettingsManager;
+
+const ROOT: &str = "/mem/home";
+
+/// Pretends every image says what its bytes say.
+struct EchoRecognizer;
+
+impl TextRecognizer for EchoRecognizer {
+    fn recognize(&self, image: &[u8]) -> Result<String, String> {
+        Ok(String::from_utf8_lossy(image).into_owned())
+    }
+}
+
+fn indexer(fixture: &Fixture, memory: Arc<MemoryFileSystem>) -> IndexManager {
+    let settings = Arc::new(SettingsManager::load(fixture.data_dir().join("settings.json")));
+    let options = IndexOptions { fs: memory, ocr: Some(Arc::new(EchoRecognizer)), ..IndexOptions::default() };
+    IndexManager::with_options(fixture.data_dir(), settings, options).unwrap()
+}
+
+#[test]
+fn screenshots_are_recognized_by_name_or_folder() {
+    assert!(is_screenshot(Path::new("/home/me/Desktop/Screenshot 2024-03-05 at 10.12.44.png")));
+    assert!(is_screenshot(Path::new("/home/me/Pictures/Screen Shot 2019-01-01 at 9.00.00 AM.png")));
+    assert!(is_screenshot(Path::new("/sdcard/DCIM/Screenshot_20240305-101244.jpg")));
+    assert!(is_screenshot(Path::new("/home/me/Bilder/Bildschirmfoto vom 2024-03-05.png")));
+    assert!(is_screenshot(Path::new("/home/me/Pictures/Screenshots/whatever.png")));
+
+    assert!(!is_screenshot(Path::new("/home/me/Screenshot notes.txt")));
+    assert!(!is_screenshot(Path::new("/home/me/Pictures/holiday.jpg")));
+}
+
+#[tokio::test]
+async fn screenshots_are_tagged_filterable_and_their_text_searchable() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/home/Desktop/Screenshot 2024-03-05 at 10.12.44.png", "flight confirmation ABC123");
+    memory.insert("/mem/home/Pictures/holiday.png", "flight over the alps");
+    let indexer = indexer(&fixture, memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let screenshot = "/mem/home/Desktop/Screenshot 2024-03-05 at 10.12.44.png";
+    assert_eq!(search_paths(&indexer, "kind:screenshot").await, vec![screenshot]);
+    // Other images aren't run through OCR
+    assert_eq!(search_paths(&indexer, "flight").await, vec![screenshot]);
+    assert_eq!(search_paths(&indexer, "flight kind:screenshot").await, vec![screenshot]);
+
+    let results = indexer.search("confirmation").await.unwrap();
+    assert_eq!(results[0]["kinds"], serde_json::json!(["screenshot"]));
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::memory_fs::MemoryFileSystem;
+use common::Fixture;
+use constella_core::search::SearchOptions;
+
+const ROOT: &str = "/mem/drive";
+
+#[tokio::test]
+async fn cursor_walks_every_match_once_from_a_fixed_snapshot() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    for i in 0..250 {
+        memory.insert(format!("/mem/drive/report_{:03}.txt", i), "x");
+    }
+    memory.insert("/mem/drive/unrelated.txt", "x");
+    let indexer = fixture.indexer_with(memory.clone());
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let cursor = indexer.open_search_cursor("report", &SearchOptions::default()).await.unwrap();
+    assert_eq!(cursor.total_hits, 250);
+
+    // Later changes don't leak into a cursor that is already open
+    memory.insert("/mem/drive/report_new.txt", "x");
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    let mut paths = Vec::new();
+    let mut pages = 0;
+    loop {
+        let page = indexer.next_page(cursor.id, 100).await.unwrap();
+        pages += 1;
+        paths.extend(page.results.iter().map(|result| result["path"].as_str().unwrap().to_string()));
+        if page.done {
+            break;
+        }
+    }
+
+    assert_eq!(pages, 3);
+    assert_eq!(paths.len(), 250);
+    assert_eq!(paths.iter().collect::<HashSet<_>>().len(), 250);
+    assert!(!paths.iter().any(|path| path.ends_with("report_new.txt")));
+    assert!(indexer.next_page(cursor.id, 100).await.is_err());
+}
+
+#[tokio::test]
+async fn cursors_reject_bad_page_sizes_and_can_be_closed() {
+    let fixture = Fixture::new();
+    fixture.file("budget.txt", "");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let cursor = indexer.open_search_cursor("budget", &SearchOptions::default()).await.unwrap();
+    assert!(indexer.next_page(cursor.id, 0).await.is_err());
+
+    indexer.close_search_cursor(cursor.id).unwrap();
+    assert!(indexer.next_page(cursor.id, 10).await.is_err());
+    assert!(indexer.close_search_cursor(cursor.id).is_err());
+}
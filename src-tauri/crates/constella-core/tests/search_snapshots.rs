@@ -0,0 +1,75 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::Fixture;
+use constella_core::search::SearchOptions;
+use constella_core::watcher::ChangeType;
+
+fn paths(results: &[serde_json::Value]) -> HashSet<String> {
+    results.iter().map(|result| result["path"].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn searches_can_return_to_their_snapshot() {
+    let fixture = Fixture::new();
+    fixture.file("report_a.txt", "");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let first = indexer.search_response("report", &SearchOptions::default()).await.unwrap();
+    let snapshot = first.snapshot.unwrap();
+    let added = fixture.file("report_b.txt", "");
+    indexer.apply_changes(&[(added, ChangeType::Created)]).await.unwrap();
+
+    let pinned = SearchOptions { snapshot: Some(snapshot), ..SearchOptions::default() };
+    let again = indexer.search_response("report", &pinned).await.unwrap();
+    assert_eq!(again.snapshot, Some(snapshot));
+    assert_eq!(again.results.len(), 1);
+
+    let latest = indexer.search_response("report", &SearchOptions::default()).await.unwrap();
+    assert_ne!(latest.snapshot, Some(snapshot));
+    assert_eq!(latest.results.len(), 2);
+}
+
+#[tokio::test]
+async fn pages_of_one_snapshot_stay_consistent_during_writes() {
+    let fixture = Fixture::new();
+    for n in 0..150 {
+        fixture.file(&format!("logs/entry_{:03}.log", n), "");
+    }
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let first = indexer.search_response("entry", &SearchOptions::default()).await.unwrap();
+    assert_eq!(first.results.len(), 100);
+
+    // A burst of changes lands between the pages
+    let burst: Vec<_> = (150..170)
+        .map(|n| (fixture.file(&format!("logs/entry_{:03}.log", n), ""), ChangeType::Created))
+        .collect();
+    indexer.apply_changes(&burst).await.unwrap();
+
+    let next = SearchOptions { snapshot: first.snapshot, offset: 100, ..SearchOptions::default() };
+    let second = indexer.search_response("entry", &next).await.unwrap();
+    assert_eq!(second.results.len(), 50);
+    let (first_page, second_page) = (paths(&first.results), paths(&second.results));
+    assert!(first_page.is_disjoint(&second_page));
+    assert_eq!(first_page.len() + second_page.len(), 150);
+}
+
+#[tokio::test]
+async fn unknown_snapshots_are_an_error() {
+    let fixture = Fixture::new();
+    fixture.file("report.txt", "");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let options = SearchOptions { snapshot: Some(u64::MAX), ..SearchOptions::default() };
+    let error = indexer.search_response("report", &options).await.unwrap_err();
+    assert!(error.contains("no longer available"));
+
+    let cursor = indexer.open_search_cursor("report", &SearchOptions::default()).await.unwrap();
+    let pinned = SearchOptions { snapshot: Some(cursor.snapshot), ..SearchOptions::default() };
+    assert_eq!(indexer.search_response("report", &pinned).await.unwrap().results.len(), 1);
+}
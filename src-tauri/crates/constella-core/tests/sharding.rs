@@ -0,0 +1,97 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::Fixture;
+use constella_core::indexing::shards::{ShardStrategy, ShardedIndex, ShardingSettings};
+use constella_core::search::{ResultFields, SearchOptions};
+use constella_core::watcher::ChangeType;
+use constella_core::SettingsManager;
+
+fn sharded(fixture: &Fixture, strategy: ShardStrategy, shards: usize) -> ShardedIndex {
+    let settings = Arc::new(SettingsManager::load(fixture.data_dir().join("settings.json")));
+    settings.update(|settings| settings.sharding = ShardingSettings { enabled: true, strategy, shards }).unwrap();
+    ShardedIndex::open(fixture.data_dir(), settings).unwrap()
+}
+
+async fn search_names(index: &ShardedIndex, query: &str) -> Vec<String> {
+    let mut names: Vec<String> = index.search_response(query, &SearchOptions::default()).await.unwrap()
+        .results
+        .iter()
+        .map(|result| result["name"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn hashing_spreads_files_and_searches_merge_them() {
+    let fixture = Fixture::new();
+    for n in 0..40 {
+        fixture.file(&format!("reports/report_{:02}.txt", n), "quarterly figures");
+    }
+    let index = sharded(&fixture, ShardStrategy::ByHash, 3);
+    index.start_indexing(&fixture.root_str()).await.unwrap();
+
+    let shards = index.shard_info();
+    assert_eq!(shards.len(), 3);
+    assert!(shards.iter().all(|shard| shard.documents > 0 && shard.roots.is_empty()));
+
+    let response = index.search_response("quarterly", &SearchOptions::default()).await.unwrap();
+    assert_eq!(response.results.len(), 40);
+    let scores: Vec<f64> = response.results.iter().map(|result| result["score"].as_f64().unwrap()).collect();
+    assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+
+    let paths_only = SearchOptions { fields: ResultFields::Paths, facets: true, ..SearchOptions::default() };
+    let response = index.search_response("quarterly", &paths_only).await.unwrap();
+    assert!(response.results.iter().all(|result| result.as_object().unwrap().len() == 2));
+    assert_eq!(response.facets.unwrap().file_types[0].count, 40);
+}
+
+#[tokio::test]
+async fn roots_each_get_their_own_shard() {
+    let fixture = Fixture::new();
+    fixture.file("work/plan.txt", "launch plan");
+    fixture.file("home/plan.txt", "garden plan");
+    let index = sharded(&fixture, ShardStrategy::ByRoot, 2);
+    let (work, home) = (fixture.path("work"), fixture.path("home"));
+    index.start_indexing(&work.to_string_lossy()).await.unwrap();
+    index.start_indexing(&home.to_string_lossy()).await.unwrap();
+
+    let shards = index.shard_info();
+    assert_eq!(shards[0].roots, vec![work.clone()]);
+    assert_eq!(shards[1].roots, vec![home.clone()]);
+    assert_eq!(index.shard_of(&work.join("plan.txt")), Some(0));
+    assert_eq!(index.shard_of(&fixture.path("elsewhere/plan.txt")), None);
+    assert_eq!(search_names(&index, "plan").await, vec!["plan.txt", "plan.txt"]);
+
+    // Reopening keeps the assignment
+    drop(index);
+    let index = sharded(&fixture, ShardStrategy::ByRoot, 2);
+    assert_eq!(index.shard_of(&home.join("plan.txt")), Some(1));
+}
+
+#[tokio::test]
+async fn changes_go_to_the_shard_owning_the_path() {
+    let fixture = Fixture::new();
+    fixture.file("notes/a.txt", "alpha");
+    let index = sharded(&fixture, ShardStrategy::ByHash, 4);
+    index.start_indexing(&fixture.root_str()).await.unwrap();
+
+    let added = fixture.file("notes/budget.txt", "numbers");
+    index.apply_changes(&[(added.clone(), ChangeType::Created)]).await.unwrap();
+    assert_eq!(search_names(&index, "budget").await, vec!["budget.txt"]);
+
+    // Find a new name hashed to another shard, so the rename crosses shards
+    let old_shard = index.shard_of(&added);
+    let renamed = (0..100)
+        .map(|n| fixture.path(&format!("notes/spending_{}.txt", n)))
+        .find(|path| index.shard_of(path) != old_shard)
+        .unwrap();
+    std::fs::rename(&added, &renamed).unwrap();
+    index.apply_changes(&[(renamed.clone(), ChangeType::Renamed(added))]).await.unwrap();
+
+    assert!(search_names(&index, "budget").await.is_empty());
+    let name = renamed.file_name().unwrap().to_string_lossy().into_owned();
+    assert_eq!(search_names(&index, "spending").await, vec![name]);
+}
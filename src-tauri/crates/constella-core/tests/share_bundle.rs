@@ -0,0 +1,52 @@
+mod common;
+
+use std::io::{Cursor, Read};
+
+use common::Fixture;
+use constella_core::bundle::{self, BundleManifest, MANIFEST_NAME};
+
+/// Every entry of a zip archive, by name, in the order they were written.
+fn unzip(archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+    (0..archive.len())
+        .map(|i| {
+            let mut entry = archive.by_index(i).unwrap();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).unwrap();
+            (entry.name().to_string(), content)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn bundles_hold_the_files_and_their_provenance() {
+    let fixture = Fixture::new();
+    let first = fixture.file("a/notes.txt", "first notes ".repeat(100));
+    let second = fixture.file("b/notes.txt", "second notes");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    indexer.add_to_collection("Handover", std::slice::from_ref(&second)).unwrap();
+
+    let dest = fixture.path("out/handover.zip");
+    std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+    let manifest = bundle::export(&indexer, &[first.clone(), second.clone()], &dest, Some("For Alex".to_string())).unwrap();
+    assert_eq!(manifest.note.as_deref(), Some("For Alex"));
+    assert_eq!(manifest.files[0].name, "files/notes.txt");
+    assert_eq!(manifest.files[1].name, "files/notes (2).txt");
+    assert_eq!(manifest.files[1].original_path, second.to_string_lossy());
+    assert_eq!(manifest.files[1].blake3, blake3::hash(b"second notes").to_hex().to_string());
+    assert_eq!(manifest.files[1].tags, vec!["Handover".to_string()]);
+
+    let entries = unzip(&std::fs::read(&dest).unwrap());
+    let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["files/notes.txt", "files/notes (2).txt", MANIFEST_NAME]);
+    assert_eq!(entries[0].1, std::fs::read(&first).unwrap());
+    let stored: BundleManifest = serde_json::from_slice(&entries[2].1).unwrap();
+    assert_eq!(stored, manifest);
+
+    // Never overwrites, and never leaves part of a bundle behind
+    assert!(bundle::export(&indexer, std::slice::from_ref(&first), &dest, None).unwrap_err().contains("already exists"));
+    let partial = fixture.path("out/partial.zip");
+    assert!(bundle::export(&indexer, &[first, fixture.path("gone.txt")], &partial, None).is_err());
+    assert_eq!(std::fs::read_dir(dest.parent().unwrap()).unwrap().count(), 1);
+}
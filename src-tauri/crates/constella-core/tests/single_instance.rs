@@ -0,0 +1,91 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::Fixture;
+use constella_core::instance::{self, Claim, Launch};
+
+fn launch(args: &[&str]) -> Launch {
+    Launch {
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        cwd: Some("/home/user".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn later_launches_are_handed_to_the_running_instance() {
+    let fixture = Fixture::new();
+    let Claim::Primary(primary) = instance::claim(fixture.data_dir(), &launch(&[])).await.unwrap() else {
+        panic!("the first launch should start");
+    };
+    let received = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let sink = received.clone();
+    tokio::spawn(primary.serve(move |launch| sink.lock().push(launch)));
+
+    let second = launch(&["constella://search?q=taxes"]);
+    assert!(matches!(instance::claim(fixture.data_dir(), &second).await.unwrap(), Claim::Forwarded));
+    assert_eq!(*received.lock(), vec![second]);
+    // Other local users mustn't be able to read the token
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = std::fs::metadata(instance::instance_file(fixture.data_dir())).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+    }
+}
+
+#[tokio::test]
+async fn instances_that_are_gone_are_taken_over() {
+    let fixture = Fixture::new();
+    let Claim::Primary(crashed) = instance::claim(fixture.data_dir(), &launch(&[])).await.unwrap() else {
+        panic!("the first launch should start");
+    };
+    // Stops listening without removing its file, as after a crash
+    drop(crashed);
+    assert!(instance::instance_file(fixture.data_dir()).exists());
+    let claimed = tokio::time::timeout(Duration::from_secs(5), instance::claim(fixture.data_dir(), &launch(&[]))).await;
+    assert!(matches!(claimed.unwrap().unwrap(), Claim::Primary(_)));
+
+    instance::release(fixture.data_dir());
+    assert!(!instance::instance_file(fixture.data_dir()).exists());
+}
+
+#[tokio::test]
+async fn a_stalled_connection_doesnt_hold_up_other_launches() {
+    let fixture = Fixture::new();
+    let Claim::Primary(primary) = instance::claim(fixture.data_dir(), &launch(&[])).await.unwrap() else {
+        panic!("the first launch should start");
+    };
+    let received = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let sink = received.clone();
+    tokio::spawn(primary.serve(move |launch| sink.lock().push(launch)));
+
+    let info: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(instance::instance_file(fixture.data_dir())).unwrap()).unwrap();
+    let port = info["port"].as_u64().unwrap() as u16;
+    // Connects and never sends a launch
+    let _stalled = tokio::net::TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+
+    let second = launch(&["/home/user/reports"]);
+    let claimed = tokio::time::timeout(Duration::from_secs(1), instance::claim(fixture.data_dir(), &second)).await;
+    assert!(matches!(claimed.unwrap().unwrap(), Claim::Forwarded));
+    assert_eq!(*received.lock(), vec![second]);
+}
+
+#[tokio::test]
+async fn files_of_dead_processes_are_taken_over_whatever_now_answers() {
+    let fixture = Fixture::new();
+    // The port was reused by something that doesn't speak the protocol
+    let stranger = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = stranger.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = stranger.accept().await {
+            drop(stream);
+        }
+    });
+    let info = serde_json::json!({ "port": port, "token": "stale", "pid": u32::MAX - 1 });
+    std::fs::write(instance::instance_file(fixture.data_dir()), info.to_string()).unwrap();
+
+    let claimed = tokio::time::timeout(Duration::from_secs(5), instance::claim(fixture.data_dir(), &launch(&[]))).await;
+    assert!(matches!(claimed.unwrap().unwrap(), Claim::Primary(_)));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::search::filters::parse_size;
+
+const ROOT: &str = "/mem/sizes";
+
+#[test]
+fn sizes_accept_binary_units() {
+    assert_eq!(parse_size("500"), Ok(500));
+    assert_eq!(parse_size("500k"), Ok(500 * 1024));
+    assert_eq!(parse_size("10MB"), Ok(10 * 1024 * 1024));
+    assert_eq!(parse_size("1.5gb"), Ok(3 * 512 * 1024 * 1024));
+    assert!(parse_size("mb").unwrap_err().contains("Expected a number"));
+    assert!(parse_size("10zb").unwrap_err().contains("Unknown size unit"));
+}
+
+#[tokio::test]
+async fn size_filters_narrow_search_results() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/sizes/tiny.log", vec![b'x'; 100]);
+    memory.insert("/mem/sizes/medium.log", vec![b'x'; 300 * 1024]);
+    memory.insert("/mem/sizes/large.log", vec![b'x'; 2 * 1024 * 1024]);
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "log size:>1mb").await, vec!["/mem/sizes/large.log"]);
+    assert_eq!(
+        search_paths(&indexer, "log size:<=500k").await,
+        vec!["/mem/sizes/medium.log", "/mem/sizes/tiny.log"],
+    );
+    assert_eq!(search_paths(&indexer, "size:1k..1mb").await, vec!["/mem/sizes/medium.log"]);
+    assert_eq!(search_paths(&indexer, "size:100").await, vec!["/mem/sizes/tiny.log"]);
+    assert_eq!(search_paths(&indexer, "size:2mb..").await, vec!["/mem/sizes/large.log"]);
+
+    for malformed in ["size:>", "size:big", "size:10xb", "size:1gb..huge"] {
+        let error = indexer.search(malformed).await.unwrap_err();
+        assert!(error.contains(malformed), "{}", error);
+    }
+}
+
+#[tokio::test]
+async fn size_and_date_ranges_combine() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    let last_year = SystemTime::now() - Duration::from_secs(400 * 24 * 3600);
+    memory.insert("/mem/sizes/recent-large.iso", vec![b'x'; 2 * 1024 * 1024]);
+    memory.insert("/mem/sizes/recent-small.iso", vec![b'x'; 100]);
+    memory.insert_modified("/mem/sizes/old-large.iso", vec![b'x'; 2 * 1024 * 1024], last_year);
+    let indexer = fixture.indexer_with(memory);
+    indexer.start_indexing(ROOT).await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "size:>1mb modified:this-week").await, vec!["/mem/sizes/recent-large.iso"]);
+    assert_eq!(search_paths(&indexer, "size:>1mb before:last-week").await, vec!["/mem/sizes/old-large.iso"]);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::extract::{markdown, notebook};
+
+const NOTES: &str = "---
+title: Lab notes
+---
+# Experiments
+
+Some text.
+
+## Setup ##
+
+```sh
+# not a heading
+```
+
+Calibration
+-----------
+
+# Results
+### Figures
+";
+
+const NOTEBOOK: &str = r###"{
+  "nbformat": 4,
+  "cells": [
+    {"cell_type": "markdown", "source": ["# Analysis\n", "\n", "## Cleaning the data\n"]},
+    {"cell_type": "code", "source": "df = load_samples()", "outputs": [
+      {"output_type": "stream", "name": "stdout", "text": ["loaded 42 samples\n"]},
+      {"output_type": "display_data", "data": {"image/png": "iVBORw0KGgoAAAANSUhEUgAAAAEAAAAB", "text/plain": ["<Figure>"]}}
+    ]}
+  ],
+  "metadata": {"kernelspec": {"name": "python3"}}
+}"###;
+
+#[test]
+fn markdown_headings_keep_their_hierarchy() {
+    assert_eq!(markdown::headings(NOTES), vec![
+        "Experiments",
+        "Experiments > Setup",
+        "Experiments > Calibration",
+        "Results",
+        "Results > Figures",
+    ]);
+    assert!(markdown::headings("#hashtag\n    # indented code\n").is_empty());
+    assert_eq!(markdown::headings("## C#\n"), vec!["C#"]);
+}
+
+#[test]
+fn notebooks_keep_cell_text_and_drop_rich_outputs() {
+    let extracted = notebook::extract(NOTEBOOK).unwrap();
+
+    assert_eq!(extracted.headings, vec!["Analysis", "Analysis > Cleaning the data"]);
+    assert!(extracted.text.contains("df = load_samples()"));
+    assert!(extracted.text.contains("loaded 42 samples"));
+    assert!(!extracted.text.contains("iVBORw0KGgo"));
+    assert!(!extracted.text.contains("kernelspec"));
+    assert!(notebook::extract("not json").is_none());
+}
+
+#[tokio::test]
+async fn sections_are_searchable_by_title() {
+    let fixture = Fixture::new();
+    let fs = MemoryFileSystem::new();
+    fs.insert("/mem/research/lab.md", NOTES);
+    fs.insert("/mem/research/analysis.ipynb", NOTEBOOK);
+    fs.insert("/mem/research/plain.txt", "calibration drift happened");
+    let indexer = fixture.indexer_with(fs);
+    indexer.start_indexing("/mem/research").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "headings:calibration").await, vec!["/mem/research/lab.md"]);
+    assert_eq!(search_paths(&indexer, "headings:cleaning").await, vec!["/mem/research/analysis.ipynb"]);
+    assert_eq!(search_paths(&indexer, "samples").await, vec!["/mem/research/analysis.ipynb"]);
+    assert!(search_paths(&indexer, "kernelspec").await.is_empty());
+}
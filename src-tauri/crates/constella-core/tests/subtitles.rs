@@ -0,0 +1,68 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::extract::subtitles::{parse, Cue};
+use constella_core::search::{ResultFields, SearchOptions};
+use constella_core::watcher::ChangeType;
+
+const SRT: &str = "1\r\n00:00:01,000 --> 00:00:03,500\r\n<i>Where is the lighthouse?</i>\r\n\r\n2\r\n00:01:02,250 --> 00:01:04,000\r\n{\\an8}Past the harbor.\r\n";
+
+const VTT: &str = "WEBVTT
+
+NOTE written by hand
+
+intro
+00:05.000 --> 00:07.000 align:start
+The lighthouse keeper waves.
+";
+
+#[test]
+fn cues_are_parsed_from_srt_and_vtt() {
+    assert_eq!(parse(SRT), vec![
+        Cue { start_ms: 1_000, end_ms: 3_500, text: "Where is the lighthouse?".into() },
+        Cue { start_ms: 62_250, end_ms: 64_000, text: "Past the harbor.".into() },
+    ]);
+    assert_eq!(parse(VTT), vec![
+        Cue { start_ms: 5_000, end_ms: 7_000, text: "The lighthouse keeper waves.".into() },
+    ]);
+    assert!(parse("not subtitles at all").is_empty());
+}
+
+#[tokio::test]
+async fn dialogue_searches_find_the_video_with_timed_lines() {
+    let fixture = Fixture::new();
+    let fs = MemoryFileSystem::new();
+    fs.insert("/mem/videos/trip.mp4", vec![0u8; 64]);
+    fs.insert("/mem/videos/trip.en.srt", SRT);
+    fs.insert("/mem/videos/other.mp4", vec![0u8; 64]);
+    let indexer = fixture.indexer_with(fs);
+    indexer.start_indexing("/mem/videos").await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "harbor").await, vec!["/mem/videos/trip.en.srt", "/mem/videos/trip.mp4"]);
+
+    let options = SearchOptions { fields: ResultFields::Snippets, ..SearchOptions::default() };
+    let results = indexer.search_with_options("harbor", &options).await.unwrap();
+    let video = results.iter().find(|result| result["path"] == "/mem/videos/trip.mp4").unwrap();
+    let transcript = video["transcript"].as_array().unwrap();
+    assert_eq!(transcript.len(), 1);
+    assert_eq!(transcript[0]["start_ms"], 62_250);
+    assert!(transcript[0]["text"].as_str().unwrap().contains("<b>harbor</b>"));
+}
+
+#[tokio::test]
+async fn changed_subtitles_reindex_their_video() {
+    let fixture = Fixture::new();
+    let fs = MemoryFileSystem::new();
+    fs.insert("/mem/videos/trip.mp4", vec![0u8; 64]);
+    let indexer = fixture.indexer_with(fs.clone());
+    indexer.start_indexing("/mem/videos").await.unwrap();
+    assert!(search_paths(&indexer, "lighthouse").await.is_empty());
+
+    fs.insert("/mem/videos/trip.vtt", VTT);
+    indexer.apply_changes(&[(PathBuf::from("/mem/videos/trip.vtt"), ChangeType::Created)]).await.unwrap();
+
+    assert_eq!(search_paths(&indexer, "lighthouse").await, vec!["/mem/videos/trip.mp4", "/mem/videos/trip.vtt"]);
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::extract::subtitles::Cue;
+use constella_core::indexing::{IndexManager, IndexOptions};
+use constella_core::transcription::{parse_whisper_output, Transcriber};
+use constella_core::SettingsManager;
+
+/// Pretends every recording mentions its own name, and counts its calls.
+#[derive(Default)]
+struct EchoTranscriber {
+    calls: AtomicUsize,
+}
+
+impl Transcriber for EchoTranscriber {
+    fn transcribe(&self, audio: &Path) -> Result<Vec<Cue>, String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let name = audio.file_name().unwrap().to_string_lossy();
+        Ok(vec![Cue { start_ms: 0, end_ms: 2_000, text: format!("reminder about {}", name) }])
+    }
+}
+
+fn transcribing_indexer(fixture: &Fixture, memory: Arc<MemoryFileSystem>, transcriber: Arc<EchoTranscriber>) -> IndexManager {
+    let settings = Arc::new(SettingsManager::load(fixture.data_dir().join("settings.json")));
+    let options = IndexOptions { fs: memory, transcriber: Some(transcriber), ..IndexOptions::default() };
+    IndexManager::with_options(fixture.data_dir(), settings, options).unwrap()
+}
+
+fn enable_transcription(fixture: &Fixture) {
+    SettingsManager::load(fixture.data_dir().join("settings.json"))
+        .update(|settings| {
+            settings.transcription.enabled = true;
+            settings.transcription.max_file_size = 1024;
+        })
+        .unwrap();
+}
+
+#[test]
+fn whisper_output_is_parsed_into_cues() {
+    let output = "\n[00:00:00.000 --> 00:00:02.500]   Pick up the dry cleaning.\n[00:00:02.500 --> 00:00:04.000]  \n";
+    assert_eq!(parse_whisper_output(output), vec![
+        Cue { start_ms: 0, end_ms: 2_500, text: "Pick up the dry cleaning.".into() },
+    ]);
+}
+
+#[tokio::test]
+async fn audio_is_transcribed_from_the_queue_and_indexed() {
+    let fixture = Fixture::new();
+    enable_transcription(&fixture);
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/memos/dentist.m4a", vec![1u8; 100]);
+    memory.insert("/mem/memos/concert.wav", vec![1u8; 4096]);
+    let transcriber = Arc::new(EchoTranscriber::default());
+    let indexer = transcribing_indexer(&fixture, memory.clone(), transcriber.clone());
+    indexer.start_indexing("/mem/memos").await.unwrap();
+
+    // Only files under the size cap are queued, and nothing runs until asked
+    assert_eq!(indexer.transcription_status().queued, 1);
+    assert!(search_paths(&indexer, "reminder").await.is_empty());
+
+    assert_eq!(indexer.transcribe_pending(|| true).await.unwrap(), 1);
+    assert_eq!(search_paths(&indexer, "reminder").await, vec!["/mem/memos/dentist.m4a"]);
+    let status = indexer.transcription_status();
+    assert_eq!((status.queued, status.transcribed, status.failed), (0, 1, 0));
+
+    // A later run reuses the stored transcript
+    drop(indexer);
+    let indexer = transcribing_indexer(&fixture, memory, transcriber.clone());
+    indexer.start_indexing("/mem/memos").await.unwrap();
+    assert_eq!(search_paths(&indexer, "reminder").await, vec!["/mem/memos/dentist.m4a"]);
+    assert_eq!(indexer.transcription_status().queued, 0);
+    assert_eq!(transcriber.calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn nothing_is_queued_while_transcription_is_off() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/mem/memos/dentist.m4a", vec![1u8; 100]);
+    let transcriber = Arc::new(EchoTranscriber::default());
+    let indexer = transcribing_indexer(&fixture, memory, transcriber.clone());
+    indexer.start_indexing("/mem/memos").await.unwrap();
+
+    assert_eq!(indexer.transcription_status().queued, 0);
+    assert_eq!(indexer.transcribe_pending(|| true).await.unwrap(), 0);
+    assert_eq!(transcriber.calls.load(Ordering::SeqCst), 0);
+}
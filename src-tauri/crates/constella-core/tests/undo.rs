@@ -0,0 +1,80 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::{search_paths, Fixture};
+use constella_core::journal::{AppliedOperation, OperationJournal, OperationKind};
+use constella_core::IndexManager;
+
+async fn settle(indexer: &IndexManager, applied: &AppliedOperation) {
+    let moves: Vec<(PathBuf, PathBuf)> = applied.moves.iter()
+        .map(|step| (step.from.clone(), step.to.clone()))
+        .collect();
+    indexer.index_moves(&moves).await.unwrap();
+}
+
+#[tokio::test]
+async fn deletes_and_renames_can_be_undone_and_redone() {
+    let fixture = Fixture::new();
+    let plan = fixture.file("docs/plan.txt", "launch plan");
+    let notes = fixture.file("docs/notes.txt", "launch notes");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+    let journal = OperationJournal::load(fixture.data_dir());
+    let path = |relative: &str| fixture.path(relative).to_string_lossy().into_owned();
+
+    let deleted = journal.delete(std::slice::from_ref(&plan)).unwrap();
+    settle(&indexer, &deleted).await;
+    assert!(!plan.exists());
+    assert!(deleted.moves[0].to.starts_with(fixture.data_dir()));
+    assert_eq!(search_paths(&indexer, "launch").await, vec![path("docs/notes.txt")]);
+
+    let renamed = journal.rename(&[(notes.clone(), "minutes.txt".to_string())]).unwrap();
+    settle(&indexer, &renamed).await;
+    assert_eq!(search_paths(&indexer, "launch").await, vec![path("docs/minutes.txt")]);
+    let history: Vec<OperationKind> = journal.history().iter().map(|operation| operation.kind).collect();
+    assert_eq!(history, vec![OperationKind::Rename, OperationKind::Delete]);
+
+    // Undone most recent first, and redone in the order they were undone
+    let undone = journal.undo_last().unwrap().unwrap();
+    assert!(undone.undone);
+    settle(&indexer, &undone).await;
+    let undone = journal.undo_last().unwrap().unwrap();
+    settle(&indexer, &undone).await;
+    assert!(plan.exists());
+    assert_eq!(search_paths(&indexer, "launch").await, vec![path("docs/notes.txt"), path("docs/plan.txt")]);
+    assert!(journal.undo_last().unwrap().is_none());
+
+    let redone = journal.redo().unwrap().unwrap();
+    assert_eq!(redone.operation.kind, OperationKind::Delete);
+    settle(&indexer, &redone).await;
+    assert!(!plan.exists());
+
+    // A new operation drops what could still be redone, and survives a restart
+    let moved = journal.move_to(std::slice::from_ref(&notes), &fixture.dir("archive")).unwrap();
+    settle(&indexer, &moved).await;
+    assert!(journal.redo().unwrap().is_none());
+    let reopened = OperationJournal::load(fixture.data_dir());
+    assert_eq!(reopened.history().len(), 2);
+}
+
+#[tokio::test]
+async fn failed_operations_leave_every_file_in_place() {
+    let fixture = Fixture::new();
+    let first = fixture.file("a/report.txt", "first");
+    let second = fixture.file("b/report.txt", "second");
+    let taken = fixture.file("a/summary.txt", "already here");
+    let journal = OperationJournal::load(fixture.data_dir());
+
+    let renames = [(first.clone(), "final.txt".to_string()), (second.clone(), "../../escape.txt".to_string())];
+    assert!(journal.rename(&renames).unwrap_err().contains("isn't a valid name"));
+    let renames = [(first.clone(), "final.txt".to_string()), (fixture.path("a/missing.txt"), "summary.txt".to_string())];
+    assert!(journal.rename(&renames).is_err());
+    assert!(first.exists() && taken.exists());
+    assert!(!fixture.path("a/final.txt").exists());
+
+    let both = [first.clone(), second.clone()];
+    assert!(journal.move_to(&both, &fixture.dir("a")).unwrap_err().contains("already exists"));
+    assert!(first.exists() && second.exists());
+    assert!(journal.history().is_empty());
+}
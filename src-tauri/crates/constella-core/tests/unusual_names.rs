@@ -0,0 +1,67 @@
+mod common;
+
+use std::path::Path;
+
+use common::{search_paths, Fixture};
+use constella_core::file_system::os_path;
+
+#[test]
+fn unicode_paths_are_their_own_encoding() {
+    let path = Path::new("/home/user/📄 notes\u{200B}.txt");
+
+    assert_eq!(os_path::encode(path), "/home/user/📄 notes\u{200B}.txt");
+    assert_eq!(os_path::decode(&os_path::encode(path)), path);
+    assert_eq!(os_path::display("/home/user/plain.txt"), "/home/user/plain.txt");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn paths_that_arent_unicode_round_trip() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = Path::new(OsStr::from_bytes(b"/home/user/ledger-\xff.txt"));
+    let encoded = os_path::encode(path);
+
+    assert_eq!(os_path::display(&encoded), "/home/user/ledger-\u{FFFD}.txt");
+    assert_eq!(os_path::decode(&encoded), path);
+    // Garbled raw bytes fall back to the display form
+    assert_eq!(os_path::decode("/tmp/a\u{FFFD}\0zz"), Path::new("/tmp/a\u{FFFD}"));
+}
+
+#[tokio::test]
+async fn emoji_and_zero_width_names_open_from_results() {
+    let fixture = Fixture::new();
+    fixture.file("📄 budget\u{200B}.txt", "quarterly figures");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    let found = search_paths(&indexer, "quarterly").await;
+    assert_eq!(found.len(), 1);
+    assert!(os_path::decode(&found[0]).is_file());
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn files_whose_names_arent_unicode_stay_reachable() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let fixture = Fixture::new();
+    let ledger = fixture.root().join(OsStr::from_bytes(b"ledger-\xff.txt"));
+    let copy = fixture.root().join(OsStr::from_bytes(b"ledger-\xfe.txt"));
+    std::fs::write(&ledger, "unpaid invoices").unwrap();
+    std::fs::write(&copy, "unpaid invoices").unwrap();
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.root_str()).await.unwrap();
+
+    // Names that only differ in the bytes lost to display stay apart
+    let mut found: Vec<_> = search_paths(&indexer, "invoices").await.iter().map(|path| os_path::decode(path)).collect();
+    found.sort();
+    let mut expected = vec![ledger.clone(), copy.clone()];
+    expected.sort();
+    assert_eq!(found, expected);
+
+    assert_eq!(indexer.find_identical(&ledger).await.unwrap(), vec![os_path::encode(&copy)]);
+    assert_eq!(indexer.prune_index().await.unwrap().removed, 0);
+}
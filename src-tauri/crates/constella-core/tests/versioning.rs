@@ -0,0 +1,30 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::Fixture;
+use constella_core::versioning::VersionStore;
+use constella_core::watcher::ChangeType;
+
+#[tokio::test]
+async fn changed_files_are_versioned_when_enabled() {
+    let fixture = Fixture::new();
+    let versions = Arc::new(VersionStore::new(fixture.data_dir().join("versions"), true).unwrap());
+    let path = fixture.file("notes.txt", "first draft\n");
+    versions.snapshot_changes(&[(path.clone(), ChangeType::Created)]).await;
+
+    fixture.file("notes.txt", "second draft\n");
+    versions.snapshot_changes(&[(path.clone(), ChangeType::Modified)]).await;
+
+    assert_eq!(versions.list_versions(&path).len(), 2);
+}
+
+#[tokio::test]
+async fn nothing_is_versioned_until_enabled() {
+    let fixture = Fixture::new();
+    let versions = Arc::new(VersionStore::new(fixture.data_dir().join("versions"), false).unwrap());
+    let path = fixture.file("notes.txt", "first draft\n");
+    versions.snapshot_changes(&[(path.clone(), ChangeType::Modified)]).await;
+
+    assert!(versions.list_versions(&path).is_empty());
+}
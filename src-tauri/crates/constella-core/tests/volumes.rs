@@ -0,0 +1,81 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::memory_fs::MemoryFileSystem;
+use common::{search_paths, Fixture};
+use constella_core::indexing::paths::PathRemap;
+use constella_core::volumes::{self, MountedVolume};
+use constella_core::SettingsManager;
+
+fn mounted(id: &str, mount_point: &str) -> MountedVolume {
+    MountedVolume { id: id.to_string(), mount_point: PathBuf::from(mount_point) }
+}
+
+#[test]
+fn mount_listings_are_parsed() {
+    let mountinfo = "\
+22 1 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw
+40 22 8:17 / /media/me/My\\040Stick rw,nosuid shared:30 - vfat /dev/sdb1 rw
+41 22 8:17 /photos /srv/photos rw shared:30 - vfat /dev/sdb1 rw
+42 22 0:45 / /tmp rw - tmpfs tmpfs rw
+";
+    let uuids = vec![
+        (PathBuf::from("/dev/sda2"), "0b6f-root".to_string()),
+        (PathBuf::from("/dev/sdb1"), "A1B2-C3D4".to_string()),
+    ];
+    assert_eq!(volumes::parse_mountinfo(mountinfo, &uuids), vec![
+        mounted("0b6f-root", "/"),
+        mounted("A1B2-C3D4", "/media/me/My Stick"),
+    ]);
+
+    let mountvol = r"
+Possible values for VolumeName along with current mount points are:
+
+    \\?\Volume{1111}\
+        C:\
+
+    \\?\Volume{2222}\
+        E:\
+        D:\Mounted\
+
+    \\?\Volume{3333}\
+        *** NO MOUNT POINTS ***
+";
+    assert_eq!(volumes::parse_mountvol(mountvol), vec![
+        mounted(r"\\?\Volume{1111}\", r"C:\"),
+        mounted(r"\\?\Volume{2222}\", r"E:\"),
+        mounted(r"\\?\Volume{2222}\", r"D:\Mounted\"),
+    ]);
+}
+
+#[tokio::test]
+async fn roots_follow_their_volume_to_a_new_mount_point() {
+    let fixture = Fixture::new();
+    let memory = MemoryFileSystem::new();
+    memory.insert("/media/stick/docs/plan.txt", "launch plan");
+    let indexer = fixture.indexer_with(memory.clone());
+    indexer.start_indexing("/media/stick/docs").await.unwrap();
+
+    let before = [mounted("root", "/"), mounted("stick", "/media/stick")];
+    assert!(indexer.follow_volumes(&before).await.unwrap().is_empty());
+    let known = SettingsManager::load(fixture.data_dir().join("settings.json")).get().root_volumes;
+    assert_eq!(known.len(), 1);
+    assert_eq!(known[0].volume_id, "stick");
+    assert_eq!(known[0].relative, PathBuf::from("docs"));
+
+    // Unplugged: nothing moves and the root is still remembered
+    assert!(indexer.follow_volumes(&before[..1]).await.unwrap().is_empty());
+
+    memory.remove("/media/stick/docs/plan.txt");
+    memory.insert("/media/stick1/docs/plan.txt", "launch plan");
+    let after = [mounted("root", "/"), mounted("stick", "/media/stick1")];
+    assert_eq!(indexer.follow_volumes(&after).await.unwrap(), vec![PathRemap {
+        from: "/media/stick/docs".into(),
+        to: "/media/stick1/docs".into(),
+    }]);
+    assert_eq!(search_paths(&indexer, "launch").await, vec!["/media/stick1/docs/plan.txt"]);
+    let settings = SettingsManager::load(fixture.data_dir().join("settings.json")).get();
+    assert_eq!(settings.indexed_roots, vec![PathBuf::from("/media/stick1/docs")]);
+    assert_eq!(settings.root_volumes[0].path, PathBuf::from("/media/stick1/docs"));
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::Fixture;
+use constella_core::search::analytics::ZeroResultCause;
+
+#[tokio::test]
+async fn empty_index_is_the_only_cause_reported() {
+    let fixture = Fixture::new();
+    let indexer = fixture.indexer();
+
+    assert!(indexer.search("anything").await.unwrap().is_empty());
+
+    let report = indexer.zero_result_queries().await.unwrap();
+    assert_eq!(report.len(), 1);
+    assert_eq!(report[0].query, "anything");
+    assert_eq!(report[0].likely_causes, vec![ZeroResultCause::IndexEmpty]);
+}
+
+#[tokio::test]
+async fn misses_are_counted_and_diagnosed() {
+    let fixture = Fixture::new();
+    fixture.file("docs/readme.txt", "hello");
+    let unindexed = fixture.dir("elsewhere");
+    let indexer = fixture.indexer();
+    indexer.start_indexing(fixture.path("docs").to_string_lossy()).await.unwrap();
+
+    for query in ["Missing", "missing", ".env", "pdf", unindexed.to_str().unwrap()] {
+        indexer.search(query).await.unwrap();
+    }
+    assert!(!indexer.search("hello").await.unwrap().is_empty());
+
+    let report = indexer.zero_result_queries().await.unwrap();
+    assert_eq!(report[0].query, "missing");
+    assert_eq!(report[0].count, 2);
+    assert!(report[0].likely_causes.is_empty());
+
+    let causes = |query: &str| report.iter().find(|entry| entry.query == query).unwrap().likely_causes.clone();
+    assert_eq!(causes(".env"), vec![ZeroResultCause::PathExcluded { term: ".env".into() }]);
+    assert!(matches!(causes("pdf")[..], [ZeroResultCause::ContentNotIndexed { .. }]));
+    assert_eq!(causes(unindexed.to_str().unwrap()), vec![ZeroResultCause::RootNotIndexed { path: unindexed.clone() }]);
+    assert!(report.iter().all(|entry| entry.query != "hello"));
+}
@@ -0,0 +1,22 @@
+# Registers constellad.exe as a Windows service running under the current user.
+# Run from an elevated PowerShell prompt next to constellad.exe.
+param(
+    [string]$BinaryPath = (Join-Path $PSScriptRoot "constellad.exe")
+)
+
+$ServiceName = "ConstellaDaemon"
+
+if (Get-Service -Name $ServiceName -ErrorAction SilentlyContinue) {
+    Stop-Service -Name $ServiceName -ErrorAction SilentlyContinue
+    sc.exe delete $ServiceName | Out-Null
+}
+
+$credential = Get-Credential -UserName "$env:USERDOMAIN\$env:USERNAME" -Message "Account the indexer runs as (needs access to your files)"
+New-Service -Name $ServiceName `
+    -BinaryPathName "`"$BinaryPath`" --service" `
+    -DisplayName "Constella background indexer" `
+    -Description "Keeps the Constella search index up to date while the app is closed." `
+    -StartupType Automatic `
+    -Credential $credential
+
+Start-Service -Name $ServiceName
@@ -1,78 +1,472 @@
-use tauri::State;
-use crate::indexing::{Indexer, IndexState};
-use log::info;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{State, Window};
+use constella_core::indexing::{IndexManager, IndexerState};
+use constella_core::actions::{self, ActionInfo, ActionOutcome, ResultKind};
+use constella_core::bundle::{self, BundleManifest};
+use constella_core::audit::{AuditAction, AuditEntry, AuditLog, AuditLogPage, AuditRange};
+use constella_core::daemon::{DaemonClient, DaemonRequest, DaemonResponse};
+use constella_core::events::IndexingProgress;
+use constella_core::file_system::os_path;
+use constella_core::compare::{CompareOptions, DirectoryComparison};
+use constella_core::tracking::{ImportantFile, UserAction};
+use constella_core::tracking::diff::FileDiffReport;
+use constella_core::versioning::{VersionInfo, VersionStore};
+use constella_core::volumes;
+use constella_core::watcher::FileSystemWatcher;
+use constella_core::power::{PowerPolicy, PowerState};
+use constella_core::search::{FileTypeBoost, QueryRewrites, RankingWeights, SearchOptions, SearchResponse, StopwordSettings};
+use constella_core::permissions::{self, PermissionKind, PermissionStatus};
+use constella_core::pii::{PiiInventory, PiiSettings};
+use constella_core::profiles::{self, ProfileSummary, ProfileUpdate};
+use constella_core::purge::{DataPurge, PurgeManifest};
+use constella_core::rename::{self, RenamePattern, RenamePreview};
+use constella_core::scanner::PathExclusions;
+use constella_core::secrets::SecretReport;
+use constella_core::search::analytics::ZeroResultQuery;
+use constella_core::search::incognito::IncognitoSessions;
+use constella_core::search::boosts::BoostMatcher;
+use constella_core::search::rewrite::normalize_rule;
+use constella_core::settings::{IndexingConfig, SettingsManager};
+use constella_core::tracking::load::SystemResources;
+use constella_core::idle::{IdleScheduler, IdleStatus};
+use constella_core::indexing::byte_search::{ByteSearchRequest, ByteSearchResult};
+use constella_core::indexing::changelog::{ChangeKind, RecentChange};
+use constella_core::indexing::collections::{CollectionExport, CollectionExportReport, CollectionSummary};
+use constella_core::indexing::coverage::CoverageReport;
+use constella_core::indexing::health::{IndexHealth, Recommendation};
+use constella_core::indexing::integrity::IntegrityReport;
+use constella_core::indexing::cursor::{CursorId, SearchCursor, SearchPage};
+use constella_core::indexing::listing::{DirectoryFilters, DirectoryListing, DirectorySort};
+use constella_core::indexing::lookup::DocumentMetadata;
+use constella_core::indexing::media::{MediaPage, MediaSearchOptions};
+use constella_core::indexing::music::{MusicFilters, MusicGrouping, MusicLibrary};
+use constella_core::indexing::path_info::FolderStats;
+use constella_core::indexing::paths::PathRemap;
+use constella_core::indexing::preview::ConfigChangePreview;
+use constella_core::indexing::preview_cache::PreviewCacheUsage;
+use constella_core::indexing::triage::{DownloadsTriage, TriageRules};
+use constella_core::indexing::upgrade::UpdateCheck;
+use constella_core::indexing::transcription::TranscriptionStatus;
+use constella_core::transcription::TranscriptionSettings;
+use constella_core::labeling::ImageLabelingSettings;
+use constella_core::jobs::{operations, JobId, JobInfo, JobKind, JobManager};
+use constella_core::jobs::coordinator::{CommandConflict, CommandCoordinator, CommandError};
+use constella_core::journal::{AppliedOperation, Operation, OperationJournal, OperationKind};
+use constella_core::organize::{OrganizeLogEntry, OrganizeRule, OrganizeSettings, Organizer, PlannedMove};
+use constella_core::indexing::reconcile::{PruneSummary, ReconcileProgress};
+use constella_core::indexing::shards::{ShardInfo, ShardedIndex, ShardingSettings};
+use constella_core::indexing::shedding::{LoadShedding, LoadSheddingStatus, SheddingMeasure};
+use constella_core::indexing::scratch::{ScratchIndexInfo, ScratchIndexes};
+use constella_core::indexing::federation::{self, FederatedResponse, FederationMember};
+use log::{info, warn};
 use serde::Serialize;
+use ts_rs::TS;
 
-#[derive(Debug, Clone, Serialize)]
-pub struct IndexingProgress {
-    pub state: IndexState,
-    pub stats: IndexingStats,
-    pub current_file: String,
-}
+/// Results per `next_page` call when the caller doesn't say.
+const DEFAULT_PAGE_SIZE: usize = 500;
+/// Entries in the "what's new" feed when the caller doesn't say.
+const DEFAULT_RECENT_CHANGES: usize = 50;
 
-#[derive(Debug, Clone, Serialize)]
-pub struct IndexingStats {
-    pub total_files: usize,
-    pub processed_files: usize,
-    pub percent_complete: f32,
-    pub files_per_second: f32,
-    pub elapsed_seconds: u64,
-    pub estimated_remaining_seconds: Option<u64>,
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../src/lib/bindings/")]
+pub struct HealthReport {
+    pub state: String,
+    #[ts(type = "number")]
+    pub total_documents: u64,
+    pub system_load: SystemResources,
+    pub power: PowerState,
+    pub idle: IdleStatus,
+    pub ranking: RankingWeights,
 }
 
 #[tauri::command]
-pub async fn get_indexing_progress(indexer: State<'_, Indexer>) -> Result<IndexingProgress, String> {
-    let state = indexer.get_state();
-    
-    Ok(IndexingProgress {
-        state: match state.state.as_str() {
-            "idle" => IndexState::Idle,
-            "scanning" => IndexState::Scanning,
-            "indexing" => IndexState::Indexing,
-            "completed" => IndexState::Completed,
-            _ => IndexState::Error("Unknown state".to_string()),
-        },
-        stats: IndexingStats {
-            total_files: state.total_files,
-            processed_files: state.processed_files,
-            percent_complete: if state.total_files > 0 {
-                (state.processed_files as f32 / state.total_files as f32) * 100.0
-            } else {
-                0.0
-            },
-            files_per_second: state.files_per_second,
-            elapsed_seconds: state.elapsed_seconds,
-            estimated_remaining_seconds: if state.files_per_second > 0.0 {
-                let remaining_files = state.total_files.saturating_sub(state.processed_files);
-                Some((remaining_files as f32 / state.files_per_second) as u64)
-            } else {
-                None
+pub async fn get_indexing_progress(
+    indexer: State<'_, Arc<IndexManager>>,
+    daemon: State<'_, Option<DaemonClient>>,
+) -> Result<IndexingProgress, String> {
+    let state = match daemon.inner() {
+        Some(daemon) if !daemon.holds_writer() => match daemon.request(DaemonRequest::Status).await? {
+            DaemonResponse::Status { state, total_files, processed_files, current_file, files_per_second, elapsed_seconds, skipped_files } => IndexerState {
+                total_files,
+                processed_files,
+                current_file,
+                state,
+                files_per_second,
+                elapsed_seconds,
+                start_time: std::time::SystemTime::now() - std::time::Duration::from_secs(elapsed_seconds),
+                priority_total: 0,
+                priority_processed: 0,
+                skipped_files,
             },
+            other => return Err(format!("Unexpected daemon response: {:?}", other)),
         },
-        current_file: state.current_file,
+        _ => indexer.get_state(),
+    };
+    
+    Ok(IndexingProgress::from(state))
+}
+
+/// Queues a full index of `directory` and returns its job id right away.
+/// The run replaces the folder indexed before it, including one queued
+/// ahead of it.
+#[tauri::command]
+pub async fn start_indexing(
+    directory: String,
+    indexer: State<'_, Arc<IndexManager>>,
+    shards: State<'_, Option<Arc<ShardedIndex>>>,
+    daemon: State<'_, Option<DaemonClient>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<JobId, CommandConflict> {
+    info!("Queueing indexing for directory: {}", directory);
+    if let Some(shards) = shards.inner().clone() {
+        let root = directory.clone();
+        return coordinator.submit_indexing(&root, move |context| async move {
+            operations::run_sharded_indexing(&context, &shards, &directory).await.map(|_| None)
+        });
+    }
+    let indexer = indexer.inner().clone();
+    let daemon = daemon.inner().clone();
+    let root = directory.clone();
+    coordinator.submit_indexing(&root, move |context| async move {
+        if let Some(daemon) = daemon {
+            // Take the writer over from the daemon for this interactive run and
+            // hand it back afterwards, whatever the outcome
+            daemon.acquire_writer().await?;
+            let result = operations::run_indexing(&context, &indexer, &directory).await;
+            let released = indexer.release_writer().await;
+            daemon.release_writer().await?;
+            return result.and(released).map(|_| None);
+        }
+        operations::run_indexing(&context, &indexer, &directory).await.map(|_| None)
+    })
+}
+
+#[tauri::command]
+pub async fn optimize_index(
+    indexer: State<'_, Arc<IndexManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<JobId, CommandConflict> {
+    let indexer = indexer.inner().clone();
+    coordinator.submit_exclusive(JobKind::Optimization, move |context| async move {
+        operations::run_optimization(&context, &indexer).await.map(|_| None)
+    })
+}
+
+#[tauri::command]
+pub async fn verify_checksums(
+    indexer: State<'_, Arc<IndexManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<JobId, CommandConflict> {
+    let indexer = indexer.inner().clone();
+    coordinator.submit_exclusive(JobKind::ChecksumVerification, move |context| async move {
+        let mismatched = operations::run_checksum_verification(&context, &indexer).await?;
+        Ok(Some(serde_json::json!({ "mismatched": mismatched })))
+    })
+}
+
+/// Moves the index into `new_location`, e.g. on another disk. Takes
+/// effect once the app restarts; the job's result says so.
+#[tauri::command]
+pub async fn move_index(
+    new_location: String,
+    indexer: State<'_, Arc<IndexManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<JobId, CommandConflict> {
+    coordinator.check_no_indexing()?;
+    let indexer = indexer.inner().clone();
+    coordinator.submit_exclusive(JobKind::IndexMove, move |context| async move {
+        let moved = operations::run_index_move(&context, &indexer, &new_location).await?;
+        serde_json::to_value(moved)
+            .map(Some)
+            .map_err(|e| format!("Failed to serialize the index move: {}", e))
+    })
+}
+
+#[tauri::command]
+pub async fn scan_duplicates(
+    root: String,
+    indexer: State<'_, Arc<IndexManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<JobId, CommandConflict> {
+    let indexer = indexer.inner().clone();
+    coordinator.submit_exclusive(JobKind::DuplicateScan, move |context| async move {
+        let groups = operations::run_duplicate_scan(&context, &indexer, &root).await?;
+        serde_json::to_value(groups)
+            .map(Some)
+            .map_err(|e| format!("Failed to serialize duplicate groups: {}", e))
+    })
+}
+
+/// Counts likely personal data per directory under `root`. Must be turned
+/// on in settings first.
+#[tauri::command]
+pub async fn scan_pii_inventory(
+    root: String,
+    indexer: State<'_, Arc<IndexManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<JobId, CommandConflict> {
+    let indexer = indexer.inner().clone();
+    coordinator.submit_exclusive(JobKind::PiiInventory, move |context| async move {
+        let inventory = operations::run_pii_inventory(&context, &indexer, &root).await?;
+        serde_json::to_value(inventory)
+            .map(Some)
+            .map_err(|e| format!("Failed to serialize PII inventory: {}", e))
     })
 }
 
+/// Writes the inventory produced by PII inventory job `id` to `path` as CSV.
+#[tauri::command]
+pub async fn export_pii_inventory(id: JobId, path: String, jobs: State<'_, Arc<JobManager>>) -> Result<(), String> {
+    let job = jobs.get(id).ok_or_else(|| format!("No job with id {}", id))?;
+    if job.kind != JobKind::PiiInventory {
+        return Err(format!("Job {} is not a PII inventory", id));
+    }
+    let result = job.result.ok_or_else(|| format!("Job {} has no inventory yet", id))?;
+    let inventory: PiiInventory = serde_json::from_value(result)
+        .map_err(|e| format!("Failed to read PII inventory: {}", e))?;
+    std::fs::write(&path, inventory.to_csv())
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Indexed files with the same content as the file at `path`, e.g. to see
+/// where else it has been copied.
+#[tauri::command]
+pub async fn find_identical(path: String, indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<String>, String> {
+    indexer.find_identical(os_path::decode(&path)).await
+}
+
+/// Scans the indexed files matching the request's filters for a raw byte
+/// pattern. Must be turned on in settings first.
+#[tauri::command]
+pub async fn search_bytes(
+    request: ByteSearchRequest,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<ByteSearchResult, String> {
+    indexer.search_bytes(&request).await
+}
+
+#[tauri::command]
+pub async fn get_job(id: JobId, jobs: State<'_, Arc<JobManager>>) -> Result<JobInfo, String> {
+    jobs.get(id).ok_or_else(|| format!("No job with id {}", id))
+}
+
+#[tauri::command]
+pub async fn list_jobs(jobs: State<'_, Arc<JobManager>>) -> Result<Vec<JobInfo>, String> {
+    Ok(jobs.list())
+}
+
+#[tauri::command]
+pub async fn cancel_job(id: JobId, jobs: State<'_, Arc<JobManager>>) -> Result<(), String> {
+    info!("Cancelling job {}", id);
+    jobs.cancel(id)
+}
+
+#[tauri::command]
+pub async fn prioritize_folder(path: String, indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<PathBuf>, String> {
+    info!("Indexing {} first", path);
+    indexer.prioritize_folder(&path)
+}
+
+#[tauri::command]
+pub async fn deprioritize_folder(path: String, indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<PathBuf>, String> {
+    indexer.deprioritize_folder(&path)
+}
+
 #[tauri::command]
-pub async fn start_indexing(directory: String, indexer: State<'_, Indexer>) -> Result<(), String> {
-    info!("Starting indexing for directory: {}", directory);
-    indexer.start_indexing(&directory).await
+pub async fn get_priority_folders(indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<PathBuf>, String> {
+    Ok(indexer.priority_folders())
 }
 
 #[tauri::command]
-pub async fn search_files(query: String, indexer: State<'_, Indexer>) -> Result<Vec<serde_json::Value>, String> {
-    info!("Searching for: {}", query);
-    indexer.search(&query).await
+pub async fn search_files(
+    query: String,
+    options: Option<SearchOptions>,
+    indexer: State<'_, Arc<IndexManager>>,
+    shards: State<'_, Option<Arc<ShardedIndex>>>,
+    daemon: State<'_, Option<DaemonClient>>,
+    incognito: State<'_, Arc<IncognitoSessions>>,
+    window: Window,
+) -> Result<SearchResponse, String> {
+    let mut options = options.unwrap_or_default();
+    options.incognito |= incognito.is_incognito(window.label());
+    if options.incognito {
+        info!("Searching in an incognito window");
+    } else {
+        info!("Searching for: {}", query);
+    }
+    if let Some(shards) = shards.inner() {
+        return shards.search_response(&query, &options).await;
+    }
+    if let Some(daemon) = daemon.inner() {
+        let request = DaemonRequest::Search { query: query.clone(), options: options.clone() };
+        return match daemon.request(request).await? {
+            DaemonResponse::SearchResults { mut results, index_completeness, facets, shed, snapshot } => {
+                // The service's watcher may not have seen this app's file actions yet
+                indexer.overlay_pending(&query, options.fields, options.offset, &mut results);
+                Ok(SearchResponse { results, index_completeness, facets, shed, snapshot })
+            }
+            other => Err(format!("Unexpected daemon response: {:?}", other)),
+        };
+    }
+    indexer.search_response(&query, &options).await
 }
 
+/// Starts paging through every result of `query`, e.g. for an export.
+/// Cursors read this process's index, even when a daemon serves searches.
 #[tauri::command]
-pub async fn cancel_indexing(indexer: State<'_, Indexer>) -> Result<(), String> {
+pub async fn open_search_cursor(
+    query: String,
+    options: Option<SearchOptions>,
+    indexer: State<'_, Arc<IndexManager>>,
+    incognito: State<'_, Arc<IncognitoSessions>>,
+    window: Window,
+) -> Result<SearchCursor, String> {
+    let mut options = options.unwrap_or_default();
+    options.incognito |= incognito.is_incognito(window.label());
+    indexer.open_search_cursor(&query, &options).await
+}
+
+#[tauri::command]
+pub async fn next_page(
+    cursor: CursorId,
+    page_size: Option<usize>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<SearchPage, String> {
+    indexer.next_page(cursor, page_size.unwrap_or(DEFAULT_PAGE_SIZE)).await
+}
+
+#[tauri::command]
+pub async fn close_search_cursor(cursor: CursorId, indexer: State<'_, Arc<IndexManager>>) -> Result<(), String> {
+    indexer.close_search_cursor(cursor)
+}
+
+/// Indexed metadata for each path, in order; `null` for paths not indexed.
+#[tauri::command]
+pub async fn get_documents(
+    paths: Vec<String>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<Vec<Option<DocumentMetadata>>, String> {
+    indexer.get_documents(&paths).await
+}
+
+/// A viewable JPEG of a raw or HEIC photo, from the preview cache;
+/// `null` for files the webview can show as they are.
+#[tauri::command]
+pub async fn get_photo_preview(path: String, indexer: State<'_, Arc<IndexManager>>) -> Result<Option<PathBuf>, String> {
+    indexer.photo_preview(os_path::decode(&path)).await
+}
+
+/// A JPEG strip of keyframes of a video, from the preview cache; `null`
+/// for other files and when ffmpeg can't read the video.
+#[tauri::command]
+pub async fn get_video_preview(path: String, indexer: State<'_, Arc<IndexManager>>) -> Result<Option<PathBuf>, String> {
+    indexer.video_preview(os_path::decode(&path)).await
+}
+
+/// A page of the images and videos matching `query`, for media browsing.
+#[tauri::command]
+pub async fn search_media(
+    query: String,
+    filters: Option<MediaSearchOptions>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<MediaPage, String> {
+    indexer.search_media(&query, &filters.unwrap_or_default()).await
+}
+
+/// Music tracks grouped by artist, album or genre, for a library view.
+#[tauri::command]
+pub async fn browse_music(
+    group_by: MusicGrouping,
+    filters: Option<MusicFilters>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<MusicLibrary, String> {
+    indexer.browse_music(group_by, &filters.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn get_preview_cache_usage(indexer: State<'_, Arc<IndexManager>>) -> Result<PreviewCacheUsage, String> {
+    Ok(indexer.preview_cache_usage())
+}
+
+/// Deletes every cached preview; returns how much space that freed.
+#[tauri::command]
+pub async fn clear_preview_cache(indexer: State<'_, Arc<IndexManager>>) -> Result<PreviewCacheUsage, String> {
+    indexer.clear_preview_cache()
+}
+
+/// The children of `path` with their sizes and dates, from the index when
+/// it covers the directory and from disk otherwise.
+#[tauri::command]
+pub async fn list_directory(
+    path: String,
+    sort: Option<DirectorySort>,
+    filters: Option<DirectoryFilters>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<DirectoryListing, String> {
+    indexer.list_directory(&path, sort.unwrap_or_default(), &filters.unwrap_or_default()).await
+}
+
+/// File counts, sizes and last change for each folder along `path`.
+#[tauri::command]
+pub async fn get_path_info(path: String, indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<FolderStats>, String> {
+    indexer.get_path_info(&path).await
+}
+
+/// Newest first; `kinds` narrows the feed to added or updated files.
+#[tauri::command]
+pub async fn get_recent_changes(
+    limit: Option<usize>,
+    kinds: Option<Vec<ChangeKind>>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<Vec<RecentChange>, String> {
+    Ok(indexer.recent_changes(limit.unwrap_or(DEFAULT_RECENT_CHANGES), &kinds.unwrap_or_default()))
+}
+
+#[tauri::command]
+pub async fn get_zero_result_queries(indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<ZeroResultQuery>, String> {
+    indexer.zero_result_queries().await
+}
+
+#[tauri::command]
+pub async fn cancel_indexing(
+    indexer: State<'_, Arc<IndexManager>>,
+    daemon: State<'_, Option<DaemonClient>>,
+) -> Result<(), String> {
     info!("Cancelling indexing");
+    if let Some(daemon) = daemon.inner().as_ref().filter(|daemon| !daemon.holds_writer()) {
+        return daemon.request(DaemonRequest::CancelIndexing).await.map(|_| ());
+    }
     indexer.cancel().await
 }
 
 #[tauri::command]
-pub async fn get_index_stats(indexer: State<'_, Indexer>) -> Result<serde_json::Value, String> {
+pub async fn pause_indexing(
+    indexer: State<'_, Arc<IndexManager>>,
+    daemon: State<'_, Option<DaemonClient>>,
+) -> Result<(), String> {
+    info!("Pausing indexing");
+    if let Some(daemon) = daemon.inner().as_ref().filter(|daemon| !daemon.holds_writer()) {
+        return daemon.request(DaemonRequest::PauseIndexing).await.map(|_| ());
+    }
+    indexer.pause_indexing().await
+}
+
+#[tauri::command]
+pub async fn resume_indexing(
+    indexer: State<'_, Arc<IndexManager>>,
+    daemon: State<'_, Option<DaemonClient>>,
+) -> Result<(), String> {
+    info!("Resuming indexing");
+    if let Some(daemon) = daemon.inner().as_ref().filter(|daemon| !daemon.holds_writer()) {
+        return daemon.request(DaemonRequest::ResumeIndexing).await.map(|_| ());
+    }
+    indexer.resume_indexing().await
+}
+
+#[tauri::command]
+pub async fn get_index_stats(indexer: State<'_, Arc<IndexManager>>) -> Result<serde_json::Value, String> {
     let reader = indexer.get_reader().await
         .map_err(|e| format!("Failed to get reader: {}", e))?;
     let searcher = reader.searcher();
@@ -80,6 +474,1154 @@ pub async fn get_index_stats(indexer: State<'_, Indexer>) -> Result<serde_json::
     let mut stats = serde_json::Map::new();
     stats.insert("total_documents".to_string(), serde_json::Value::Number(serde_json::Number::from(searcher.num_docs())));
     stats.insert("last_updated".to_string(), serde_json::Value::String(chrono::Local::now().to_rfc3339()));
+    if let Some(update) = indexer.last_update() {
+        stats.insert("last_incremental_update".to_string(), serde_json::to_value(update)
+            .map_err(|e| format!("Failed to serialize update summary: {}", e))?);
+    }
+    stats.insert("ranking".to_string(), serde_json::to_value(indexer.ranking_weights())
+        .map_err(|e| format!("Failed to serialize ranking weights: {}", e))?);
     
     Ok(serde_json::Value::Object(stats))
-} 
\ No newline at end of file
+} 
+
+#[tauri::command]
+pub async fn get_coverage(root: String, indexer: State<'_, Arc<IndexManager>>) -> Result<CoverageReport, String> {
+    info!("Checking index coverage of {}", root);
+    indexer.coverage(Path::new(&root)).await
+}
+
+#[tauri::command]
+pub async fn preview_config_change(
+    config: IndexingConfig,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<ConfigChangePreview, String> {
+    indexer.preview_config_change(&config).await
+}
+
+#[tauri::command]
+pub async fn set_indexing_config(
+    config: IndexingConfig,
+    indexer: State<'_, Arc<IndexManager>>,
+    settings: State<'_, Arc<SettingsManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<(), CommandError> {
+    coordinator.check_no_indexing()?;
+    PathExclusions::new(&config.exclude)?;
+    info!("Setting indexing config to {:?}", config);
+    let previous = settings.get().indexing;
+    settings.update(|settings| settings.indexing = config.clone())?;
+    if previous.exclude != config.exclude {
+        spawn_reconciliation(indexer.inner().clone());
+    }
+    Ok(())
+}
+
+/// Reconciles the index in the background after exclusions or roots changed.
+fn spawn_reconciliation(indexer: Arc<IndexManager>) {
+    tokio::spawn(async move {
+        match indexer.reconcile().await {
+            Ok(progress) => info!("Reconciliation finished: {:?}", progress),
+            Err(e) => warn!("Reconciliation failed: {}", e),
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn reconcile_index(
+    indexer: State<'_, Arc<IndexManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<ReconcileProgress, CommandError> {
+    coordinator.check_no_indexing()?;
+    Ok(indexer.reconcile().await?)
+}
+
+/// Removes documents of files deleted from disk while nothing was watching.
+#[tauri::command]
+pub async fn prune_index(
+    indexer: State<'_, Arc<IndexManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<PruneSummary, CommandError> {
+    coordinator.check_no_indexing()?;
+    Ok(indexer.prune_index().await?)
+}
+
+#[tauri::command]
+pub async fn abort_reconciliation(indexer: State<'_, Arc<IndexManager>>) -> Result<(), String> {
+    indexer.abort_reconciliation();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn compare_directories(
+    a: String,
+    b: String,
+    options: Option<CompareOptions>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<DirectoryComparison, String> {
+    info!("Comparing directories: {} <-> {}", a, b);
+    constella_core::compare::compare_directories(&indexer, &a, &b, &options.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn get_file_diff(path: String, indexer: State<'_, Arc<IndexManager>>) -> Result<FileDiffReport, String> {
+    indexer.get_file_diff(os_path::decode(&path))
+}
+
+#[tauri::command]
+pub async fn set_diff_retention(
+    enabled: bool,
+    indexer: State<'_, Arc<IndexManager>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<(), String> {
+    settings.update(|settings| settings.diff_retention_enabled = enabled)?;
+    indexer.set_diff_retention(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn watch_directory(
+    directory: String,
+    indexer: State<'_, Arc<IndexManager>>,
+    watcher: State<'_, parking_lot::Mutex<FileSystemWatcher>>,
+    daemon: State<'_, Option<DaemonClient>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<(), String> {
+    info!("Watching directory: {}", directory);
+    match daemon.inner() {
+        Some(daemon) => {
+            daemon.request(DaemonRequest::Watch { directory: directory.clone() }).await?;
+        }
+        None => watcher.lock().watch(&directory)
+            .map_err(|e| format!("Failed to watch {}: {}", directory, e))?,
+    }
+
+    let root = std::path::PathBuf::from(&directory);
+    let added = !settings.get().watched_roots.contains(&root);
+    settings.update(|settings| {
+        if !settings.watched_roots.contains(&root) {
+            settings.watched_roots.push(root);
+        }
+    })?;
+    if added && daemon.is_none() {
+        if let Err(e) = indexer.follow_volumes(&volumes::mounted_volumes()).await {
+            warn!("Failed to note the volume of {}: {}", directory, e);
+        }
+        spawn_reconciliation(indexer.inner().clone());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unwatch_directory(
+    directory: String,
+    indexer: State<'_, Arc<IndexManager>>,
+    watcher: State<'_, parking_lot::Mutex<FileSystemWatcher>>,
+    daemon: State<'_, Option<DaemonClient>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<(), String> {
+    info!("No longer watching directory: {}", directory);
+    match daemon.inner() {
+        Some(daemon) => {
+            daemon.request(DaemonRequest::Unwatch { directory: directory.clone() }).await?;
+        }
+        None => watcher.lock().unwatch(&directory)
+            .map_err(|e| format!("Failed to unwatch {}: {}", directory, e))?,
+    }
+
+    let root = std::path::PathBuf::from(&directory);
+    settings.update(|settings| settings.watched_roots.retain(|r| r != &root))?;
+    if daemon.is_none() {
+        spawn_reconciliation(indexer.inner().clone());
+    }
+    Ok(())
+}
+
+/// Points the index at a folder's new location after it was moved or its
+/// drive letter changed, then checks it for files that changed meanwhile.
+#[tauri::command]
+pub async fn remap_root(
+    old_prefix: String,
+    new_prefix: String,
+    indexer: State<'_, Arc<IndexManager>>,
+    watcher: State<'_, parking_lot::Mutex<FileSystemWatcher>>,
+    daemon: State<'_, Option<DaemonClient>>,
+    settings: State<'_, Arc<SettingsManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<Vec<PathRemap>, CommandError> {
+    if daemon.inner().is_some() {
+        return Err("Stop the background service before remapping folders".to_string().into());
+    }
+    coordinator.check_no_indexing()?;
+    info!("Remapping {} to {}", old_prefix, new_prefix);
+    let watched_before = settings.get().watched_roots;
+    let remaps = indexer.remap_root(&old_prefix, &new_prefix).await?;
+
+    let watched_after = settings.get().watched_roots;
+    let mut watcher = watcher.lock();
+    for (before, after) in watched_before.iter().zip(&watched_after).filter(|(before, after)| before != after) {
+        if let Err(e) = watcher.unwatch(before) {
+            warn!("Failed to unwatch {:?}: {}", before, e);
+        }
+        if let Err(e) = watcher.watch(after) {
+            warn!("Failed to watch {:?}: {}", after, e);
+        }
+    }
+    drop(watcher);
+    spawn_reconciliation(indexer.inner().clone());
+    Ok(remaps)
+}
+
+#[tauri::command]
+pub async fn get_path_remaps(indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<PathRemap>, String> {
+    Ok(indexer.path_remaps())
+}
+
+#[tauri::command]
+pub async fn set_versioning_enabled(
+    enabled: bool,
+    versions: State<'_, Arc<VersionStore>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<(), String> {
+    settings.update(|settings| settings.versioning_enabled = enabled)?;
+    versions.set_enabled(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_versions(path: String, versions: State<'_, Arc<VersionStore>>) -> Result<Vec<VersionInfo>, String> {
+    Ok(versions.list_versions(&os_path::decode(&path)))
+}
+
+#[tauri::command]
+pub async fn restore_version(
+    path: String,
+    id: u64,
+    versions: State<'_, Arc<VersionStore>>,
+    audit: State<'_, Arc<AuditLog>>,
+) -> Result<(), String> {
+    info!("Restoring {} to version {}", path, id);
+    let path = os_path::decode(&path);
+    versions.restore_version(&path, id)?;
+    audit.append(AuditAction::Restore, &path, Some(format!("version {}", id)))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn record_file_action(
+    path: String,
+    action: UserAction,
+    indexer: State<'_, Arc<IndexManager>>,
+    audit: State<'_, Arc<AuditLog>>,
+    incognito: State<'_, Arc<IncognitoSessions>>,
+    window: Window,
+) -> Result<f32, String> {
+    let path = os_path::decode(&path);
+    audit.append(action.into(), &path, None)?;
+    // Incognito windows don't feed suggestions
+    if incognito.is_incognito(window.label()) {
+        return Ok(indexer.change_tracker().importance_score(&path).await);
+    }
+    indexer.change_tracker()
+        .record_user_action(&path, action)
+        .await
+}
+
+/// Actions the UI can offer for results of `kind`, or all of them.
+#[tauri::command]
+pub async fn list_actions(kind: Option<ResultKind>) -> Result<Vec<ActionInfo>, String> {
+    Ok(actions::list(kind))
+}
+
+#[tauri::command]
+pub async fn execute_action(
+    action_id: String,
+    paths: Vec<String>,
+    indexer: State<'_, Arc<IndexManager>>,
+    audit: State<'_, Arc<AuditLog>>,
+    incognito: State<'_, Arc<IncognitoSessions>>,
+    window: Window,
+) -> Result<ActionOutcome, String> {
+    let paths: Vec<PathBuf> = paths.iter().map(|path| os_path::decode(path)).collect();
+    let remember = !incognito.is_incognito(window.label());
+    actions::execute(&indexer, &audit, &action_id, &paths, remember).await
+}
+
+/// Logs a delete or move the UI carried out on a file; `destination` is
+/// where a moved file went.
+#[tauri::command]
+pub async fn record_file_operation(
+    path: String,
+    action: AuditAction,
+    destination: Option<String>,
+    audit: State<'_, Arc<AuditLog>>,
+) -> Result<AuditEntry, String> {
+    if !matches!(action, AuditAction::Delete | AuditAction::Move) {
+        return Err(format!("{:?} is recorded with the action it belongs to", action));
+    }
+    if action == AuditAction::Move && destination.is_none() {
+        return Err("A move needs its destination".to_string());
+    }
+    audit.append(action, &os_path::decode(&path), destination)
+}
+
+/// Audits what a journal operation just moved and brings the index up to
+/// date with it. The background service's watcher does the latter when it
+/// runs.
+async fn settle_operation(
+    applied: AppliedOperation,
+    indexer: &IndexManager,
+    audit: &AuditLog,
+    daemon: &Option<DaemonClient>,
+) -> Result<AppliedOperation, String> {
+    for step in &applied.moves {
+        // Searches show the change right away, before the index has it
+        if applied.operation.kind == OperationKind::Delete && !applied.undone {
+            indexer.record_deleted(&step.from);
+            audit.append(AuditAction::Delete, &step.from, None)?;
+        } else {
+            indexer.record_moved(&step.from, &step.to);
+            audit.append(AuditAction::Move, &step.from, Some(step.to.to_string_lossy().into_owned()))?;
+        }
+    }
+    if daemon.is_none() {
+        let moves: Vec<(PathBuf, PathBuf)> = applied.moves.iter()
+            .map(|step| (step.from.clone(), step.to.clone()))
+            .collect();
+        if let Err(e) = indexer.index_moves(&moves).await {
+            warn!("Failed to update the index after operation {}: {}", applied.operation.id, e);
+        }
+    }
+    Ok(applied)
+}
+
+/// Deletes files so they can be brought back with `undo_last` until the
+/// operation expires.
+#[tauri::command]
+pub async fn delete_files(
+    paths: Vec<String>,
+    journal: State<'_, Arc<OperationJournal>>,
+    indexer: State<'_, Arc<IndexManager>>,
+    audit: State<'_, Arc<AuditLog>>,
+    daemon: State<'_, Option<DaemonClient>>,
+) -> Result<AppliedOperation, String> {
+    let paths: Vec<PathBuf> = paths.iter().map(|path| os_path::decode(path)).collect();
+    let applied = journal.delete(&paths)?;
+    settle_operation(applied, &indexer, &audit, &daemon).await
+}
+
+#[tauri::command]
+pub async fn move_files(
+    paths: Vec<String>,
+    folder: String,
+    journal: State<'_, Arc<OperationJournal>>,
+    indexer: State<'_, Arc<IndexManager>>,
+    audit: State<'_, Arc<AuditLog>>,
+    daemon: State<'_, Option<DaemonClient>>,
+) -> Result<AppliedOperation, String> {
+    let paths: Vec<PathBuf> = paths.iter().map(|path| os_path::decode(path)).collect();
+    let applied = journal.move_to(&paths, Path::new(&folder))?;
+    settle_operation(applied, &indexer, &audit, &daemon).await
+}
+
+/// Renames each path to the name paired with it, as one undoable operation.
+#[tauri::command]
+pub async fn rename_files(
+    renames: Vec<(String, String)>,
+    journal: State<'_, Arc<OperationJournal>>,
+    indexer: State<'_, Arc<IndexManager>>,
+    audit: State<'_, Arc<AuditLog>>,
+    daemon: State<'_, Option<DaemonClient>>,
+) -> Result<AppliedOperation, String> {
+    let renames: Vec<(PathBuf, String)> = renames.into_iter()
+        .map(|(path, name)| (os_path::decode(&path), name))
+        .collect();
+    let applied = journal.rename(&renames)?;
+    settle_operation(applied, &indexer, &audit, &daemon).await
+}
+
+/// The names `pattern` would give `paths`, with anything in the way.
+#[tauri::command]
+pub async fn preview_rename(paths: Vec<String>, pattern: RenamePattern) -> Result<RenamePreview, String> {
+    let paths: Vec<PathBuf> = paths.iter().map(|path| os_path::decode(path)).collect();
+    rename::preview(&paths, &pattern)
+}
+
+/// Renames `paths` by `pattern` as one undoable operation, refusing when
+/// the preview shows conflicts.
+#[tauri::command]
+pub async fn apply_rename(
+    paths: Vec<String>,
+    pattern: RenamePattern,
+    journal: State<'_, Arc<OperationJournal>>,
+    indexer: State<'_, Arc<IndexManager>>,
+    audit: State<'_, Arc<AuditLog>>,
+    daemon: State<'_, Option<DaemonClient>>,
+) -> Result<AppliedOperation, String> {
+    let paths: Vec<PathBuf> = paths.iter().map(|path| os_path::decode(path)).collect();
+    let renames = rename::preview(&paths, &pattern)?.renames()?;
+    let applied = journal.rename(&renames)?;
+    settle_operation(applied, &indexer, &audit, &daemon).await
+}
+
+/// Reverses the most recent delete, move or rename; `null` when there is
+/// nothing left to undo.
+#[tauri::command]
+pub async fn undo_last(
+    journal: State<'_, Arc<OperationJournal>>,
+    indexer: State<'_, Arc<IndexManager>>,
+    audit: State<'_, Arc<AuditLog>>,
+    daemon: State<'_, Option<DaemonClient>>,
+) -> Result<Option<AppliedOperation>, String> {
+    match journal.undo_last()? {
+        Some(applied) => settle_operation(applied, &indexer, &audit, &daemon).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+#[tauri::command]
+pub async fn redo(
+    journal: State<'_, Arc<OperationJournal>>,
+    indexer: State<'_, Arc<IndexManager>>,
+    audit: State<'_, Arc<AuditLog>>,
+    daemon: State<'_, Option<DaemonClient>>,
+) -> Result<Option<AppliedOperation>, String> {
+    match journal.redo()? {
+        Some(applied) => settle_operation(applied, &indexer, &audit, &daemon).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Operations that can still be undone, most recent first.
+#[tauri::command]
+pub async fn get_undo_history(journal: State<'_, Arc<OperationJournal>>) -> Result<Vec<Operation>, String> {
+    Ok(journal.history())
+}
+
+#[tauri::command]
+pub async fn get_organize_settings(settings: State<'_, Arc<SettingsManager>>) -> Result<OrganizeSettings, String> {
+    Ok(settings.get().organize)
+}
+
+/// Replaces the organize rules, after checking every pattern, query and
+/// destination.
+#[tauri::command]
+pub async fn set_organize_settings(
+    organize: OrganizeSettings,
+    settings: State<'_, Arc<SettingsManager>>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<OrganizeSettings, String> {
+    let mut names = HashSet::new();
+    for rule in &organize.rules {
+        rule.validate()?;
+        if !names.insert(rule.name.trim()) {
+            return Err(format!("Two rules are named {}", rule.name));
+        }
+        if let Some(query) = rule.query.as_deref().filter(|query| !query.trim().is_empty()) {
+            indexer.parse_query(query)
+                .map_err(|e| format!("Invalid query in rule {}: {}", rule.name, e))?;
+        }
+    }
+    info!("Setting {} organize rules, organizing {}", organize.rules.len(), if organize.enabled { "on" } else { "off" });
+    Ok(settings.update(|settings| settings.organize = organize)?.organize)
+}
+
+/// The kill switch: turns every organize rule on or off at once.
+#[tauri::command]
+pub async fn set_organizing(enabled: bool, settings: State<'_, Arc<SettingsManager>>) -> Result<OrganizeSettings, String> {
+    info!("Turning organizing {}", if enabled { "on" } else { "off" });
+    Ok(settings.update(|settings| settings.organize.enabled = enabled)?.organize)
+}
+
+/// Dry run: what `rule` would move among the files indexed now.
+#[tauri::command]
+pub async fn preview_organize_rule(
+    rule: OrganizeRule,
+    organizer: State<'_, Arc<Organizer>>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<Vec<PlannedMove>, String> {
+    organizer.preview(&indexer, &rule).await
+}
+
+/// What rule `name` has moved, or failed to, newest first.
+#[tauri::command]
+pub async fn get_organize_log(name: String, organizer: State<'_, Arc<Organizer>>) -> Result<Vec<OrganizeLogEntry>, String> {
+    Ok(organizer.log(&name))
+}
+
+/// The selected results, given by path, by result id or both.
+fn selected_paths(indexer: &IndexManager, paths: Option<Vec<String>>, ids: Option<Vec<String>>) -> Result<Vec<PathBuf>, String> {
+    let mut selected: Vec<PathBuf> = paths.unwrap_or_default().iter().map(|path| os_path::decode(path)).collect();
+    if let Some(ids) = ids {
+        selected.extend(indexer.resolve_ids(&ids)?);
+    }
+    Ok(selected)
+}
+
+/// Adds results to the named collection, creating it if need be.
+#[tauri::command]
+pub async fn add_to_collection(
+    name: String,
+    paths: Option<Vec<String>>,
+    ids: Option<Vec<String>>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<CollectionSummary, String> {
+    let paths = selected_paths(&indexer, paths, ids)?;
+    indexer.add_to_collection(&name, &paths)
+}
+
+#[tauri::command]
+pub async fn remove_from_collection(
+    name: String,
+    paths: Option<Vec<String>>,
+    ids: Option<Vec<String>>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<CollectionSummary, String> {
+    let paths = selected_paths(&indexer, paths, ids)?;
+    indexer.remove_from_collection(&name, &paths)
+}
+
+/// What each result is tagged with: its image labels and collections.
+#[tauri::command]
+pub async fn get_result_tags(ids: Vec<String>, indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<Vec<String>>, String> {
+    indexer.resolve_ids(&ids)?
+        .iter()
+        .map(|path| indexer.file_tags(path))
+        .collect()
+}
+
+#[tauri::command]
+pub async fn delete_collection(name: String, indexer: State<'_, Arc<IndexManager>>) -> Result<(), String> {
+    indexer.delete_collection(&name)
+}
+
+#[tauri::command]
+pub async fn list_collections(indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<CollectionSummary>, String> {
+    Ok(indexer.list_collections())
+}
+
+/// The paths in a collection, in the order they were added.
+#[tauri::command]
+pub async fn get_collection(name: String, indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<String>, String> {
+    indexer.collection_items(&name)
+}
+
+/// Fills `folder` with links to, or copies of, a collection's files.
+#[tauri::command]
+pub async fn export_collection(
+    name: String,
+    folder: String,
+    export: CollectionExport,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<CollectionExportReport, String> {
+    indexer.export_collection(&name, Path::new(&folder), export)
+}
+
+/// Zips the selected results with a manifest of where they came from,
+/// their hashes and tags, into `dest`.
+#[tauri::command]
+pub async fn export_bundle(
+    paths: Option<Vec<String>>,
+    ids: Option<Vec<String>>,
+    dest: String,
+    note: Option<String>,
+    indexer: State<'_, Arc<IndexManager>>,
+    audit: State<'_, Arc<AuditLog>>,
+) -> Result<BundleManifest, String> {
+    let paths = selected_paths(&indexer, paths, ids)?;
+    let manifest = bundle::export(&indexer, &paths, Path::new(&dest), note)?;
+    for path in &paths {
+        audit.append(AuditAction::Export, path, Some(dest.clone()))?;
+    }
+    Ok(manifest)
+}
+
+#[tauri::command]
+pub async fn get_audit_log(range: Option<AuditRange>, audit: State<'_, Arc<AuditLog>>) -> Result<AuditLogPage, String> {
+    audit.read(range.unwrap_or_default())
+}
+
+/// Copies the audit log, unchanged, to `path`.
+#[tauri::command]
+pub async fn export_audit_log(path: String, audit: State<'_, Arc<AuditLog>>) -> Result<(), String> {
+    info!("Exporting audit log to {}", path);
+    audit.export(&path)
+}
+
+/// A token for `purge_all_data`, valid for a couple of minutes.
+#[tauri::command]
+pub async fn prepare_purge(purge: State<'_, Arc<DataPurge>>) -> Result<String, String> {
+    purge.issue_token()
+}
+
+/// Deletes the index and everything derived from it, then quits, since the
+/// running app would otherwise write parts of it back.
+#[tauri::command]
+pub async fn purge_all_data(
+    confirm_token: String,
+    overwrite: Option<bool>,
+    purge: State<'_, Arc<DataPurge>>,
+    daemon: State<'_, Option<DaemonClient>>,
+    app: tauri::AppHandle,
+) -> Result<PurgeManifest, String> {
+    if daemon.inner().is_some() {
+        return Err("Stop the background service before purging its data".to_string());
+    }
+    // Overwriting a large index takes a while; keep it off the runtime
+    let purge = purge.inner().clone();
+    let manifest = tokio::task::spawn_blocking(move || purge.purge(&confirm_token, overwrite.unwrap_or(false)))
+        .await
+        .map_err(|e| format!("Failed to purge data: {}", e))??;
+    tokio::spawn(async move {
+        // Leave time for the manifest to reach the UI
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        app.exit(0);
+    });
+    Ok(manifest)
+}
+
+/// Looks for a newer release and whether installing it means rebuilding
+/// the index; `None` when the app is up to date, or the build has updates
+/// turned off because its releases aren't signed.
+#[tauri::command]
+pub async fn check_for_update(app: tauri::AppHandle) -> Result<Option<UpdateCheck>, String> {
+    if !app.config().tauri.updater.active {
+        return Ok(None);
+    }
+    let update = app.updater().check().await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+    if !update.is_update_available() {
+        return Ok(None);
+    }
+    Ok(Some(UpdateCheck::new(
+        update.current_version().to_string(),
+        update.latest_version().to_string(),
+        update.body().cloned(),
+        update.date().map(|date| date.to_string()),
+    )))
+}
+
+/// Installs the newer release and restarts into it. When the release
+/// can't use the existing index, a rebuild is scheduled first so it runs
+/// in the background after the restart.
+#[tauri::command]
+pub async fn install_update(
+    app: tauri::AppHandle,
+    indexer: State<'_, Arc<IndexManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<(), CommandError> {
+    coordinator.check_no_indexing()?;
+    if !app.config().tauri.updater.active {
+        return Err("Updates are turned off in this build".to_string().into());
+    }
+    let update = app.updater().check().await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+    if !update.is_update_available() {
+        return Err("Already up to date".to_string().into());
+    }
+    let check = UpdateCheck::new(
+        update.current_version().to_string(),
+        update.latest_version().to_string(),
+        update.body().cloned(),
+        None,
+    );
+    // Releases that don't state their format are checked by the new build
+    // itself, which schedules a rebuild when it opens the index
+    let scheduled = check.rebuild_required == Some(true) && !indexer.rebuild_scheduled();
+    if scheduled {
+        indexer.schedule_rebuild()?;
+    }
+    info!("Installing update {} (index rebuild: {:?})", check.version, check.rebuild_required);
+    if let Err(e) = update.download_and_install().await {
+        if scheduled {
+            indexer.unschedule_rebuild();
+        }
+        return Err(format!("Failed to install update {}: {}", check.version, e).into());
+    }
+    app.restart();
+    Ok(())
+}
+
+/// OS permissions indexing needs, with the steps to grant missing ones.
+#[tauri::command]
+pub async fn get_permission_status() -> Result<PermissionStatus, String> {
+    Ok(permissions::get_permission_status())
+}
+
+/// Opens the system settings page that grants `kind`.
+#[tauri::command]
+pub async fn open_permission_settings(kind: PermissionKind) -> Result<(), String> {
+    permissions::open_settings(kind)
+}
+
+#[tauri::command]
+pub async fn record_result_click(
+    query: String,
+    path: String,
+    indexer: State<'_, Arc<IndexManager>>,
+    incognito: State<'_, Arc<IncognitoSessions>>,
+    window: Window,
+) -> Result<u32, String> {
+    if incognito.is_incognito(window.label()) {
+        return Ok(0);
+    }
+    indexer.record_result_click(&query, &path)
+}
+
+/// Turns incognito on or off for the calling window until it closes.
+#[tauri::command]
+pub async fn set_incognito(enabled: bool, incognito: State<'_, Arc<IncognitoSessions>>, window: Window) -> Result<bool, String> {
+    info!("Incognito {} for window {}", if enabled { "on" } else { "off" }, window.label());
+    incognito.set(window.label(), enabled);
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub async fn is_incognito(incognito: State<'_, Arc<IncognitoSessions>>, window: Window) -> Result<bool, String> {
+    Ok(incognito.is_incognito(window.label()))
+}
+
+#[tauri::command]
+pub async fn set_click_learning(enabled: bool, settings: State<'_, Arc<SettingsManager>>) -> Result<(), String> {
+    info!("Click learning {}", if enabled { "enabled" } else { "disabled" });
+    settings.update(|settings| settings.learn_from_clicks = enabled)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_byte_search(enabled: bool, settings: State<'_, Arc<SettingsManager>>) -> Result<(), String> {
+    info!("Byte pattern search {}", if enabled { "enabled" } else { "disabled" });
+    settings.update(|settings| settings.byte_search_enabled = enabled)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_secret_scanning(enabled: bool, settings: State<'_, Arc<SettingsManager>>) -> Result<(), String> {
+    info!("Secret scanning {}", if enabled { "enabled" } else { "disabled" });
+    settings.update(|settings| settings.secret_scanning_enabled = enabled)?;
+    Ok(())
+}
+
+/// Checks the index files against their manifest; the app shell runs this
+/// on launch and offers `rebuild_index` when they don't match.
+#[tauri::command]
+pub async fn check_index_integrity(indexer: State<'_, Arc<IndexManager>>) -> Result<IntegrityReport, String> {
+    let indexer = indexer.inner().clone();
+    tokio::task::spawn_blocking(move || indexer.verify_index_integrity())
+        .await
+        .map_err(|e| format!("Failed to check index integrity: {}", e))?
+}
+
+/// Empties the index and indexes the last indexed folder again, e.g. when
+/// its files failed the integrity check.
+#[tauri::command]
+pub async fn rebuild_index(
+    settings: State<'_, Arc<SettingsManager>>,
+    indexer: State<'_, Arc<IndexManager>>,
+    shards: State<'_, Option<Arc<ShardedIndex>>>,
+    daemon: State<'_, Option<DaemonClient>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<JobId, CommandError> {
+    let root = settings.get().indexed_roots.into_iter().next()
+        .ok_or_else(|| "Nothing has been indexed yet".to_string())?;
+    indexer.schedule_rebuild()?;
+    Ok(start_indexing(os_path::encode(&root), indexer, shards, daemon, coordinator).await?)
+}
+
+/// The last health check, or a new one when there hasn't been one yet or
+/// `refresh` is set.
+#[tauri::command]
+pub async fn get_index_health(refresh: Option<bool>, indexer: State<'_, Arc<IndexManager>>) -> Result<IndexHealth, String> {
+    match indexer.last_health() {
+        Some(health) if !refresh.unwrap_or(false) => Ok(health),
+        _ => indexer.check_health().await,
+    }
+}
+
+/// Concrete actions that would improve the index, most worthwhile first.
+#[tauri::command]
+pub async fn get_recommendations(indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<Recommendation>, String> {
+    indexer.get_recommendations().await
+}
+
+/// Size of each index shard; empty when sharding is off.
+#[tauri::command]
+pub async fn get_shards(shards: State<'_, Option<Arc<ShardedIndex>>>) -> Result<Vec<ShardInfo>, String> {
+    Ok(shards.inner().as_ref().map_or_else(Vec::new, |shards| shards.shard_info()))
+}
+
+#[tauri::command]
+pub async fn get_sharding_settings(settings: State<'_, Arc<SettingsManager>>) -> Result<ShardingSettings, String> {
+    Ok(settings.get().sharding)
+}
+
+/// Takes effect on the next start; changing the strategy or the number of
+/// shards needs a full reindex after that.
+#[tauri::command]
+pub async fn set_sharding_settings(
+    sharding: ShardingSettings,
+    settings: State<'_, Arc<SettingsManager>>,
+    coordinator: State<'_, Arc<CommandCoordinator>>,
+) -> Result<ShardingSettings, CommandError> {
+    coordinator.check_no_indexing()?;
+    if sharding.shards == 0 {
+        return Err("Sharding needs at least one shard".to_string().into());
+    }
+    info!("Sharding {} into {} shards by {:?}", if sharding.enabled { "on" } else { "off" }, sharding.shards, sharding.strategy);
+    Ok(settings.update(|settings| settings.sharding = sharding)?.sharding)
+}
+
+/// Which load shedding measures are in effect at the index's current size.
+#[tauri::command]
+pub async fn get_load_shedding(indexer: State<'_, Arc<IndexManager>>) -> Result<LoadSheddingStatus, String> {
+    Ok(indexer.load_shedding())
+}
+
+/// Replaces the document counts at which each measure kicks in, and the
+/// overrides.
+#[tauri::command]
+pub async fn set_load_shedding(
+    load_shedding: LoadShedding,
+    settings: State<'_, Arc<SettingsManager>>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<LoadSheddingStatus, String> {
+    settings.update(|settings| settings.load_shedding = load_shedding)?;
+    Ok(indexer.load_shedding())
+}
+
+/// Keeps the feature `measure` turns down on however large the index
+/// gets, or hands it back to the threshold.
+#[tauri::command]
+pub async fn set_shedding_override(
+    measure: SheddingMeasure,
+    overridden: bool,
+    settings: State<'_, Arc<SettingsManager>>,
+    indexer: State<'_, Arc<IndexManager>>,
+) -> Result<LoadSheddingStatus, String> {
+    info!("{} load shedding measure {:?}", if overridden { "Overriding" } else { "Restoring" }, measure);
+    settings.update(|settings| {
+        let overrides = &mut settings.load_shedding.overridden;
+        overrides.retain(|m| *m != measure);
+        if overridden {
+            overrides.push(measure);
+        }
+    })?;
+    Ok(indexer.load_shedding())
+}
+
+#[tauri::command]
+pub async fn set_index_integrity(enabled: bool, indexer: State<'_, Arc<IndexManager>>) -> Result<IntegrityReport, String> {
+    info!("Index manifest {}", if enabled { "enabled" } else { "disabled" });
+    let indexer = indexer.inner().clone();
+    tokio::task::spawn_blocking(move || indexer.set_index_integrity(enabled))
+        .await
+        .map_err(|e| format!("Failed to update index integrity setting: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_secret_findings(indexer: State<'_, Arc<IndexManager>>) -> Result<SecretReport, String> {
+    Ok(indexer.secret_findings())
+}
+
+#[tauri::command]
+pub async fn clear_learning_data(indexer: State<'_, Arc<IndexManager>>) -> Result<(), String> {
+    indexer.clear_learning_data()
+}
+
+#[tauri::command]
+pub async fn get_important_files(limit: Option<usize>, indexer: State<'_, Arc<IndexManager>>) -> Result<Vec<ImportantFile>, String> {
+    Ok(indexer.suggested_files(limit.unwrap_or(20)).await)
+}
+
+#[tauri::command]
+pub async fn list_profiles(settings: State<'_, Arc<SettingsManager>>) -> Result<Vec<ProfileSummary>, String> {
+    Ok(profiles::list(&settings.get()))
+}
+
+/// Creates or updates a profile; updating a PIN-guarded one needs its PIN.
+#[tauri::command]
+pub async fn save_profile(
+    profile: ProfileUpdate,
+    pin: Option<String>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<Vec<ProfileSummary>, String> {
+    info!("Saving profile {}", profile.name);
+    profiles::save(&settings, profile, pin.as_deref())
+}
+
+#[tauri::command]
+pub async fn set_profile_pin(
+    name: String,
+    new_pin: Option<String>,
+    current_pin: Option<String>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<Vec<ProfileSummary>, String> {
+    info!("{} the PIN of profile {}", if new_pin.is_some() { "Setting" } else { "Removing" }, name);
+    profiles::set_pin(&settings, &name, new_pin.as_deref(), current_pin.as_deref())
+}
+
+#[tauri::command]
+pub async fn delete_profile(name: String, pin: Option<String>, settings: State<'_, Arc<SettingsManager>>) -> Result<Vec<ProfileSummary>, String> {
+    info!("Deleting profile {}", name);
+    profiles::delete(&settings, &name, pin.as_deref())
+}
+
+/// Switches to profile `name`, or to no profile at all with `None`.
+#[tauri::command]
+pub async fn switch_profile(
+    name: Option<String>,
+    pin: Option<String>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<Vec<ProfileSummary>, String> {
+    info!("Switching to profile {}", name.as_deref().unwrap_or("(none)"));
+    profiles::switch(&settings, name.as_deref(), pin.as_deref())
+}
+
+#[tauri::command]
+pub async fn get_health(
+    indexer: State<'_, Arc<IndexManager>>,
+    idle: State<'_, Arc<IdleScheduler>>,
+) -> Result<HealthReport, String> {
+    let reader = indexer.get_reader().await
+        .map_err(|e| format!("Failed to get reader: {}", e))?;
+
+    Ok(HealthReport {
+        state: indexer.get_state().state,
+        total_documents: reader.searcher().num_docs(),
+        system_load: indexer.load_monitor().current(),
+        power: indexer.power_monitor().state(),
+        idle: idle.status(),
+        ranking: indexer.ranking_weights(),
+    })
+}
+
+#[tauri::command]
+pub async fn set_power_policy(policy: PowerPolicy, settings: State<'_, Arc<SettingsManager>>) -> Result<(), String> {
+    info!("Setting power policy to {:?}", policy);
+    settings.update(|settings| settings.power_policy = policy)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_idle_threshold(minutes: u64, settings: State<'_, Arc<SettingsManager>>) -> Result<(), String> {
+    settings.update(|settings| settings.idle_threshold_minutes = minutes.max(1))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn create_scratch_index(paths: Vec<String>, scratch: State<'_, Arc<ScratchIndexes>>) -> Result<ScratchIndexInfo, String> {
+    info!("Creating scratch index over {:?}", paths);
+    let paths: Vec<std::path::PathBuf> = paths.into_iter().map(std::path::PathBuf::from).collect();
+    scratch.create(&paths).await
+}
+
+#[tauri::command]
+pub async fn search_scratch_index(id: String, query: String, scratch: State<'_, Arc<ScratchIndexes>>) -> Result<Vec<serde_json::Value>, String> {
+    scratch.search(&id, &query).await
+}
+
+#[tauri::command]
+pub async fn list_scratch_indexes(scratch: State<'_, Arc<ScratchIndexes>>) -> Result<Vec<ScratchIndexInfo>, String> {
+    Ok(scratch.list())
+}
+
+#[tauri::command]
+pub async fn drop_scratch_index(id: String, scratch: State<'_, Arc<ScratchIndexes>>) -> Result<(), String> {
+    scratch.remove(&id)
+}
+
+/// Every index a federated search can go to: `main`, each shard as
+/// `shard-N` and the scratch indexes by id.
+fn federation_members(
+    indexer: &Arc<IndexManager>,
+    shards: &Option<Arc<ShardedIndex>>,
+    scratch: &ScratchIndexes,
+) -> Vec<FederationMember> {
+    let mut members = vec![FederationMember { label: "main".to_string(), index: indexer.clone() }];
+    if let Some(shards) = shards {
+        members.extend(shards.shards().iter().enumerate().map(|(n, shard)| {
+            FederationMember { label: format!("shard-{}", n), index: shard.clone() }
+        }));
+    }
+    members.extend(scratch.list().into_iter().filter_map(|info| {
+        scratch.get(&info.id).map(|index| FederationMember { label: info.id, index })
+    }));
+    members
+}
+
+#[tauri::command]
+pub async fn list_searchable_indexes(
+    indexer: State<'_, Arc<IndexManager>>,
+    shards: State<'_, Option<Arc<ShardedIndex>>>,
+    scratch: State<'_, Arc<ScratchIndexes>>,
+) -> Result<Vec<String>, String> {
+    Ok(federation_members(&indexer, &shards, &scratch).into_iter().map(|member| member.label).collect())
+}
+
+/// Searches `indexes` at once, all of them when unset, and merges the
+/// results. Indexes that take longer than `budget_ms` are left out and
+/// listed in the response.
+#[tauri::command]
+pub async fn federated_search(
+    query: String,
+    indexes: Option<Vec<String>>,
+    options: Option<SearchOptions>,
+    budget_ms: Option<u64>,
+    indexer: State<'_, Arc<IndexManager>>,
+    shards: State<'_, Option<Arc<ShardedIndex>>>,
+    scratch: State<'_, Arc<ScratchIndexes>>,
+) -> Result<FederatedResponse, String> {
+    let mut members = federation_members(&indexer, &shards, &scratch);
+    if let Some(indexes) = &indexes {
+        if let Some(unknown) = indexes.iter().find(|index| !members.iter().any(|member| member.label == **index)) {
+            return Err(format!("No index named {}", unknown));
+        }
+        members.retain(|member| indexes.contains(&member.label));
+    }
+    let budget = budget_ms.map_or(federation::DEFAULT_LATENCY_BUDGET, std::time::Duration::from_millis);
+    info!("Searching {} indexes for: {}", members.len(), query);
+    federation::federated_search(members, &query, &options.unwrap_or_default(), budget).await
+}
+
+#[tauri::command]
+pub async fn set_ranking_weights(weights: RankingWeights, settings: State<'_, Arc<SettingsManager>>) -> Result<RankingWeights, String> {
+    weights.validate()?;
+    info!("Setting ranking weights to {:?}", weights);
+    Ok(settings.update(|settings| settings.ranking = weights)?.ranking)
+}
+
+#[tauri::command]
+pub async fn set_file_type_boosts(
+    boosts: Vec<FileTypeBoost>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<Vec<FileTypeBoost>, String> {
+    BoostMatcher::new(&boosts)?;
+    info!("Setting {} file type boosts", boosts.len());
+    Ok(settings.update(|settings| settings.file_type_boosts = boosts)?.file_type_boosts)
+}
+
+#[tauri::command]
+pub async fn set_stopword_settings(
+    stopwords: StopwordSettings,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<StopwordSettings, String> {
+    let stopwords = stopwords.normalized()?;
+    info!("Using {} content stopwords, noise threshold {}", stopwords.stopwords.len(), stopwords.noise_threshold);
+    Ok(settings.update(|settings| settings.stopwords = stopwords)?.stopwords)
+}
+
+#[tauri::command]
+pub async fn get_downloads_triage(indexer: State<'_, Arc<IndexManager>>) -> Result<DownloadsTriage, String> {
+    indexer.downloads_triage().await
+}
+
+#[tauri::command]
+pub async fn get_triage_rules(settings: State<'_, Arc<SettingsManager>>) -> Result<TriageRules, String> {
+    Ok(settings.get().downloads_triage)
+}
+
+#[tauri::command]
+pub async fn set_triage_rules(rules: TriageRules, settings: State<'_, Arc<SettingsManager>>) -> Result<TriageRules, String> {
+    let normalize = |extensions: Vec<String>| -> Vec<String> {
+        extensions.iter()
+            .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+            .filter(|extension| !extension.is_empty())
+            .collect()
+    };
+    let rules = TriageRules {
+        installer_extensions: normalize(rules.installer_extensions),
+        archive_extensions: normalize(rules.archive_extensions),
+        ..rules
+    };
+    info!("Setting downloads triage rules to {:?}", rules);
+    Ok(settings.update(|settings| settings.downloads_triage = rules)?.downloads_triage)
+}
+
+#[tauri::command]
+pub async fn get_transcription_status(indexer: State<'_, Arc<IndexManager>>) -> Result<TranscriptionStatus, String> {
+    Ok(indexer.transcription_status())
+}
+
+#[tauri::command]
+pub async fn get_transcription_settings(settings: State<'_, Arc<SettingsManager>>) -> Result<TranscriptionSettings, String> {
+    Ok(settings.get().transcription)
+}
+
+/// Audio found from now on is queued for transcription while idle; files
+/// already indexed are queued when next indexed.
+#[tauri::command]
+pub async fn set_transcription_settings(
+    transcription: TranscriptionSettings,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<TranscriptionSettings, String> {
+    if transcription.enabled && transcription.model.is_none() {
+        return Err("Choose a whisper.cpp model before turning on transcription".to_string());
+    }
+    let transcription = TranscriptionSettings {
+        extensions: transcription.extensions.iter()
+            .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+            .filter(|extension| !extension.is_empty())
+            .collect(),
+        ..transcription
+    };
+    info!("Setting transcription to {:?}", transcription);
+    Ok(settings.update(|settings| settings.transcription = transcription)?.transcription)
+}
+
+#[tauri::command]
+pub async fn get_image_labeling_settings(settings: State<'_, Arc<SettingsManager>>) -> Result<ImageLabelingSettings, String> {
+    Ok(settings.get().image_labeling)
+}
+
+#[tauri::command]
+pub async fn get_pii_inventory_settings(settings: State<'_, Arc<SettingsManager>>) -> Result<PiiSettings, String> {
+    Ok(settings.get().pii_inventory)
+}
+
+#[tauri::command]
+pub async fn set_pii_inventory_settings(
+    pii_inventory: PiiSettings,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<PiiSettings, String> {
+    info!("Setting PII inventory to {:?}", pii_inventory);
+    Ok(settings.update(|settings| settings.pii_inventory = pii_inventory)?.pii_inventory)
+}
+
+/// Images indexed from now on are labeled; reindex to label the rest.
+#[tauri::command]
+pub async fn set_image_labeling_settings(
+    image_labeling: ImageLabelingSettings,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<ImageLabelingSettings, String> {
+    info!("Setting image labeling to {:?}", image_labeling);
+    Ok(settings.update(|settings| settings.image_labeling = image_labeling)?.image_labeling)
+}
+
+#[tauri::command]
+pub async fn get_query_rewrites(settings: State<'_, Arc<SettingsManager>>) -> Result<QueryRewrites, String> {
+    Ok(settings.get().query_rewrites)
+}
+
+#[tauri::command]
+pub async fn set_query_rewrite(
+    term: String,
+    expansions: Vec<String>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<QueryRewrites, String> {
+    let (term, expansions) = normalize_rule(&term, &expansions)?;
+    info!("Rewriting {:?} to {:?}", term, expansions);
+    Ok(settings.update(|settings| {
+        settings.query_rewrites.insert(term, expansions);
+    })?.query_rewrites)
+}
+
+#[tauri::command]
+pub async fn remove_query_rewrite(term: String, settings: State<'_, Arc<SettingsManager>>) -> Result<QueryRewrites, String> {
+    let term = term.trim().to_lowercase();
+    if !settings.get().query_rewrites.contains_key(&term) {
+        return Err(format!("No rewrite defined for {:?}", term));
+    }
+    info!("Removing rewrite for {:?}", term);
+    Ok(settings.update(|settings| {
+        settings.query_rewrites.remove(&term);
+    })?.query_rewrites)
+}
@@ -1,9 +0,0 @@
-pub mod indexing;
-pub mod file_system;
-pub mod api;
-pub mod scanner;
-
-pub use indexing::*;
-pub use file_system::*;
-pub use api::*;
-pub use scanner::*; 
\ No newline at end of file
@@ -1,14 +1,36 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use tauri::{CustomMenuItem, Menu, Submenu};
-use tauri::Manager;
+use tauri::{Manager, RunEvent, WindowEvent};
 use env_logger;
-use log::info;
-use crate::indexing::Indexer;
+use std::path::PathBuf;
+use std::sync::Arc;
+use log::{error, info, warn};
+use constella_core::audit::AuditLog;
+use constella_core::indexing::{IndexManager, IndexOptions};
+use constella_core::jobs::{operations, JobManager, JobStatus};
+use constella_core::jobs::coordinator::CommandCoordinator;
+use constella_core::journal::OperationJournal;
+use constella_core::organize::Organizer;
+use constella_core::indexing::scratch::ScratchIndexes;
+use constella_core::indexing::shards::ShardedIndex;
+use constella_core::purge::DataPurge;
+use constella_core::search::incognito::IncognitoSessions;
+use constella_core::utils;
+use constella_core::settings::SettingsManager;
+use constella_core::idle::IdleScheduler;
+use constella_core::instance::{self, Claim, Launch};
+use constella_core::portable;
+use constella_core::persistence::{spawn_tracker_persistence, PersistenceManager};
+use constella_core::daemon::DaemonClient;
+use constella_core::events::Event;
+use constella_core::versioning::VersionStore;
+use constella_core::volumes;
+use constella_core::watcher::{ChangeType, FileSystemWatcher};
 
-pub mod api;
-pub mod scanner;
-pub mod indexing;
+mod api;
+
+const PROGRESS_EMIT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
 
 fn create_context_menu() -> Menu {
     let debug = CustomMenuItem::new("debug", "Toggle Debug Tools");
@@ -21,24 +43,417 @@ async fn main() {
     env_logger::init();
     info!("Starting Constella");
 
+    // A second launch hands its arguments to the running instance and exits
+    let portable = portable::detect();
+    let instance_dir = match &portable {
+        Some(layout) => Some(layout.data_dir.clone()),
+        None => utils::default_data_dir(),
+    };
+    let primary = match &instance_dir {
+        Some(dir) => match instance::claim(dir, &Launch::current()).await {
+            Ok(Claim::Primary(primary)) => Some(primary),
+            Ok(Claim::Forwarded) => return,
+            Err(e) => {
+                error!("Not starting a second instance: {}", e);
+                return;
+            }
+        },
+        None => None,
+    };
+
     tauri::Builder::default()
         .menu(create_context_menu())
-        .setup(|app| {
+        .setup(move |app| {
+            let app_data_dir = match &portable {
+                Some(layout) => {
+                    info!("Running in portable mode from {:?}", layout.volume_root);
+                    layout.data_dir.clone()
+                }
+                None => app.path_resolver().app_data_dir()
+                    .map(utils::resolve_data_dir)
+                    .expect("Failed to get app data directory"),
+            };
+            std::fs::create_dir_all(&app_data_dir).expect("Failed to create app data directory");
+            let settings = Arc::new(SettingsManager::load(app_data_dir.join("settings.json")));
+            if let Some(layout) = &portable {
+                if let Err(e) = layout.rebase_settings(&settings) {
+                    warn!("Failed to update folders for the portable volume: {}", e);
+                }
+            }
+            app.manage(settings.clone());
+
             // Initialize indexer
-            let indexer = Indexer::new().expect("Failed to create indexer");
+            let options = IndexOptions {
+                portable_root: portable.map(|layout| layout.volume_root),
+                ..IndexOptions::default()
+            };
+            let indexer = Arc::new(IndexManager::with_options(&app_data_dir, settings.clone(), options)
+                .expect("Failed to create indexer"));
+            // Drives may have come back under another letter or mount point
+            let followed = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(indexer.follow_volumes(&volumes::mounted_volumes()))
+            });
+            if let Err(e) = followed {
+                warn!("Failed to follow moved volumes: {}", e);
+            }
+            let tracker = indexer.change_tracker();
+            let load_monitor = indexer.load_monitor();
+            load_monitor.spawn_sampler();
+            indexer.power_monitor().spawn_sampler();
             
             // Store in app state
-            app.manage(indexer);
+            app.manage(indexer.clone());
+
+            // Each job gets its own event so the UI can follow several at once;
+            // progress is throttled, status changes always go out
+            let jobs = Arc::new(JobManager::new());
+            app.manage(jobs.clone());
+            let coordinator = Arc::new(CommandCoordinator::new(jobs.clone()));
+            app.manage(coordinator.clone());
+            let mut job_events = jobs.subscribe();
+            let jobs_handle = app.handle();
+            tokio::spawn(async move {
+                let mut last_emitted = std::collections::HashMap::new();
+                loop {
+                    let job = match job_events.recv().await {
+                        Ok(job) => job,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    };
+                    let due = last_emitted.get(&job.id)
+                        .map_or(true, |emitted: &(JobStatus, std::time::Instant)| {
+                            emitted.0 != job.status || emitted.1.elapsed() >= PROGRESS_EMIT_INTERVAL
+                        });
+                    if !due {
+                        continue;
+                    }
+                    if job.status.is_finished() {
+                        last_emitted.remove(&job.id);
+                    } else {
+                        last_emitted.insert(job.id, (job.status, std::time::Instant::now()));
+                    }
+                    let event = Event::job_update(job);
+                    if let Err(e) = jobs_handle.emit_all(&event.channel(), event) {
+                        warn!("Failed to emit job update: {}", e);
+                    }
+                }
+            });
+
+            // Push progress to the UI, at most a few times a second
+            let mut progress = indexer.subscribe_progress();
+            let progress_handle = app.handle();
+            tokio::spawn(async move {
+                while progress.changed().await.is_ok() {
+                    let state = progress.borrow_and_update().clone();
+                    let event = Event::indexing_progress(state);
+                    if let Err(e) = progress_handle.emit_all(&event.channel(), event) {
+                        warn!("Failed to emit indexing progress: {}", e);
+                    }
+                    tokio::time::sleep(PROGRESS_EMIT_INTERVAL).await;
+                }
+            });
+
+            let mut priority_complete = indexer.subscribe_priority_complete();
+            let priority_handle = app.handle();
+            tokio::spawn(async move {
+                while priority_complete.changed().await.is_ok() {
+                    let completion = priority_complete.borrow_and_update().clone();
+                    if let Some(completion) = completion {
+                        let event = Event::priority_complete(completion);
+                        if let Err(e) = priority_handle.emit_all(&event.channel(), event) {
+                            warn!("Failed to emit priority completion: {}", e);
+                        }
+                    }
+                }
+            });
+
+            let mut reconcile_progress = indexer.subscribe_reconcile_progress();
+            let reconcile_handle = app.handle();
+            tokio::spawn(async move {
+                while reconcile_progress.changed().await.is_ok() {
+                    let progress = reconcile_progress.borrow_and_update().clone();
+                    let event = Event::reconcile_progress(progress);
+                    if let Err(e) = reconcile_handle.emit_all(&event.channel(), event) {
+                        warn!("Failed to emit reconciliation progress: {}", e);
+                    }
+                    tokio::time::sleep(PROGRESS_EMIT_INTERVAL).await;
+                }
+            });
+
+            app.manage(Arc::new(ScratchIndexes::new(app_data_dir.join("scratch"), settings.clone())));
+
+            // With sharding on, full runs, changes and searches go to the shards
+            let shards = settings.get().sharding.enabled
+                .then(|| ShardedIndex::open(&app_data_dir, settings.clone()))
+                .transpose()
+                .unwrap_or_else(|e| {
+                    warn!("Failed to open index shards, using the main index: {}", e);
+                    None
+                })
+                .map(Arc::new);
+            app.manage(shards.clone());
+
+            let idle_scheduler = Arc::new(IdleScheduler::new(settings.clone()));
+            idle_scheduler.spawn(indexer.clone());
+            app.manage(idle_scheduler);
+
+            // Restore change tracking state, then keep saving it periodically
+            let persistence = Arc::new(PersistenceManager::new(&app_data_dir)
+                .expect("Failed to create persistence manager"));
+            app.manage(persistence.clone());
+
+            spawn_tracker_persistence(persistence, tracker);
+
+            let versions = Arc::new(VersionStore::new(app_data_dir.join("versions"), settings.get().versioning_enabled)
+                .expect("Failed to create version store"));
+            app.manage(versions.clone());
+
+            app.manage(Arc::new(AuditLog::load(app_data_dir.join("audit_log.jsonl"))));
+            app.manage(Arc::new(IncognitoSessions::new()));
+            app.manage(Arc::new(DataPurge::new(&app_data_dir).with_index_dir(indexer.index_dir())));
+            let journal = Arc::new(OperationJournal::load(&app_data_dir));
+            app.manage(journal.clone());
+            let organizer = Arc::new(Organizer::load(&app_data_dir, settings.clone()));
+            app.manage(organizer.clone());
+
+            // Route debounced filesystem changes to the subsystems that follow them
+            let (change_tx, mut change_rx) = tokio::sync::mpsc::channel(100);
+            let mut watcher = FileSystemWatcher::new(change_tx, load_monitor).expect("Failed to create file watcher");
+
+            // When the background daemon is running it owns indexing and watching;
+            // the GUI forwards to it instead of watching the same roots twice
+            let daemon = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(DaemonClient::discover(&app_data_dir))
+            });
+            if daemon.is_none() {
+                for root in settings.get().watched_roots {
+                    if let Err(e) = watcher.watch(&root) {
+                        warn!("Failed to restore watch on {:?}: {}", root, e);
+                    }
+                }
+                // After an update that changed the index format, rebuild in the background
+                let rebuild_root = settings.get().indexed_roots.into_iter().next();
+                if let Some(root) = rebuild_root.filter(|_| shards.is_none() && indexer.rebuild_scheduled()) {
+                    let directory = root.to_string_lossy().into_owned();
+                    let rebuild_indexer = indexer.clone();
+                    let queued = coordinator.submit_indexing(&directory.clone(), move |context| async move {
+                        operations::run_indexing(&context, &rebuild_indexer, &directory).await.map(|_| None)
+                    });
+                    match queued {
+                        Ok(job) => info!("Rebuilding the index after the update as job {}", job),
+                        Err(e) => warn!("Failed to queue the index rebuild: {:?}", e),
+                    }
+                }
+            }
+            app.manage(daemon);
+            app.manage(parking_lot::Mutex::new(watcher));
+
+            // Later launches bring this window forward and hand over their arguments
+            if let Some(primary) = primary {
+                let launch_handle = app.handle();
+                tokio::spawn(primary.serve(move |launch| {
+                    if let Some(window) = launch_handle.get_window("main") {
+                        let focused = window.unminimize()
+                            .and_then(|_| window.show())
+                            .and_then(|_| window.set_focus());
+                        if let Err(e) = focused {
+                            warn!("Failed to focus the main window: {}", e);
+                        }
+                    }
+                    let event = Event::instance_launch(launch);
+                    if let Err(e) = launch_handle.emit_all(&event.channel(), event) {
+                        warn!("Failed to emit instance launch: {}", e);
+                    }
+                }));
+            }
+
+            tokio::spawn(async move {
+                while let Some(changes) = change_rx.recv().await {
+                    versions.snapshot_changes(&changes).await;
+
+                    let applied = match &shards {
+                        Some(shards) => shards.apply_changes(&changes).await,
+                        None => indexer.apply_changes(&changes).await,
+                    };
+                    if let Err(e) = applied {
+                        warn!("Failed to apply filesystem changes to index: {}", e);
+                    }
+
+                    let changed: Vec<PathBuf> = changes.into_iter()
+                        .filter(|(_, change)| !matches!(change, ChangeType::Deleted))
+                        .map(|(path, _)| path)
+                        .collect();
+                    if let Err(e) = organizer.organize(&indexer, &journal, &changed).await {
+                        warn!("Failed to organize changed files: {}", e);
+                    }
+                }
+            });
             
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             api::commands::start_indexing,
+            api::commands::optimize_index,
+            api::commands::verify_checksums,
+            api::commands::move_index,
+            api::commands::scan_duplicates,
+            api::commands::find_identical,
+            api::commands::scan_pii_inventory,
+            api::commands::export_pii_inventory,
+            api::commands::get_pii_inventory_settings,
+            api::commands::set_pii_inventory_settings,
+            api::commands::search_bytes,
+            api::commands::set_byte_search,
+            api::commands::set_secret_scanning,
+            api::commands::get_secret_findings,
+            api::commands::check_index_integrity,
+            api::commands::rebuild_index,
+            api::commands::set_index_integrity,
+            api::commands::get_index_health,
+            api::commands::get_recommendations,
+            api::commands::get_shards,
+            api::commands::get_sharding_settings,
+            api::commands::set_sharding_settings,
+            api::commands::get_load_shedding,
+            api::commands::set_load_shedding,
+            api::commands::set_shedding_override,
+            api::commands::get_job,
+            api::commands::list_jobs,
+            api::commands::cancel_job,
+            api::commands::prioritize_folder,
+            api::commands::deprioritize_folder,
+            api::commands::get_priority_folders,
             api::commands::search_files,
+            api::commands::open_search_cursor,
+            api::commands::next_page,
+            api::commands::close_search_cursor,
+            api::commands::get_documents,
+            api::commands::get_photo_preview,
+            api::commands::get_video_preview,
+            api::commands::search_media,
+            api::commands::browse_music,
+            api::commands::get_preview_cache_usage,
+            api::commands::clear_preview_cache,
+            api::commands::list_directory,
+            api::commands::get_path_info,
+            api::commands::get_recent_changes,
+            api::commands::get_downloads_triage,
+            api::commands::get_triage_rules,
+            api::commands::set_triage_rules,
+            api::commands::get_transcription_status,
+            api::commands::get_transcription_settings,
+            api::commands::set_transcription_settings,
+            api::commands::get_image_labeling_settings,
+            api::commands::set_image_labeling_settings,
+            api::commands::get_zero_result_queries,
             api::commands::cancel_indexing,
+            api::commands::pause_indexing,
+            api::commands::resume_indexing,
             api::commands::get_indexing_progress,
             api::commands::get_index_stats,
+            api::commands::get_coverage,
+            api::commands::preview_config_change,
+            api::commands::set_indexing_config,
+            api::commands::reconcile_index,
+            api::commands::prune_index,
+            api::commands::abort_reconciliation,
+            api::commands::compare_directories,
+            api::commands::get_file_diff,
+            api::commands::set_diff_retention,
+            api::commands::watch_directory,
+            api::commands::unwatch_directory,
+            api::commands::remap_root,
+            api::commands::get_path_remaps,
+            api::commands::set_versioning_enabled,
+            api::commands::list_versions,
+            api::commands::restore_version,
+            api::commands::record_file_action,
+            api::commands::list_actions,
+            api::commands::execute_action,
+            api::commands::record_file_operation,
+            api::commands::delete_files,
+            api::commands::move_files,
+            api::commands::rename_files,
+            api::commands::preview_rename,
+            api::commands::apply_rename,
+            api::commands::undo_last,
+            api::commands::redo,
+            api::commands::get_undo_history,
+            api::commands::get_organize_settings,
+            api::commands::set_organize_settings,
+            api::commands::set_organizing,
+            api::commands::preview_organize_rule,
+            api::commands::get_organize_log,
+            api::commands::add_to_collection,
+            api::commands::remove_from_collection,
+            api::commands::get_result_tags,
+            api::commands::delete_collection,
+            api::commands::list_collections,
+            api::commands::get_collection,
+            api::commands::export_collection,
+            api::commands::export_bundle,
+            api::commands::get_audit_log,
+            api::commands::export_audit_log,
+            api::commands::record_result_click,
+            api::commands::set_incognito,
+            api::commands::prepare_purge,
+            api::commands::purge_all_data,
+            api::commands::check_for_update,
+            api::commands::install_update,
+            api::commands::get_permission_status,
+            api::commands::open_permission_settings,
+            api::commands::is_incognito,
+            api::commands::set_click_learning,
+            api::commands::clear_learning_data,
+            api::commands::get_important_files,
+            api::commands::list_profiles,
+            api::commands::save_profile,
+            api::commands::set_profile_pin,
+            api::commands::delete_profile,
+            api::commands::switch_profile,
+            api::commands::get_health,
+            api::commands::set_power_policy,
+            api::commands::set_idle_threshold,
+            api::commands::create_scratch_index,
+            api::commands::search_scratch_index,
+            api::commands::list_scratch_indexes,
+            api::commands::drop_scratch_index,
+            api::commands::list_searchable_indexes,
+            api::commands::federated_search,
+            api::commands::set_ranking_weights,
+            api::commands::set_file_type_boosts,
+            api::commands::set_stopword_settings,
+            api::commands::get_query_rewrites,
+            api::commands::set_query_rewrite,
+            api::commands::remove_query_rewrite,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |app_handle, event| {
+            // A reopened window with the same label starts out recording again
+            if let RunEvent::WindowEvent { label, event: WindowEvent::Destroyed, .. } = &event {
+                app_handle.state::<Arc<IncognitoSessions>>().set(label, false);
+            }
+            if let RunEvent::Exit = event {
+                if let Some(dir) = &instance_dir {
+                    instance::release(dir);
+                }
+                if app_handle.state::<Arc<DataPurge>>().has_purged() {
+                    return;
+                }
+                // Flush tracker state one last time before the process goes away
+                let indexer = app_handle.state::<Arc<IndexManager>>().inner().clone();
+                let tracker = indexer.change_tracker();
+                let persistence = app_handle.state::<Arc<PersistenceManager>>().inner().clone();
+                tokio::task::block_in_place(|| {
+                    tokio::runtime::Handle::current().block_on(async move {
+                        let states = tracker.export_states().await;
+                        if let Err(e) = persistence.save_state(&states).await {
+                            warn!("Failed to save tracking state on exit: {}", e);
+                        }
+                        indexer.record_index_manifest();
+                    });
+                });
+            }
+        });
 } 
\ No newline at end of file
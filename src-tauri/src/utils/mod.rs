@@ -1,10 +0,0 @@
-// Placeholder for utils module
-pub struct Utils {
-    // Add utils state here
-}
-
-impl Utils {
-    pub fn new() -> Self {
-        Self {}
-    }
-} 
\ No newline at end of file
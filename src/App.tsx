@@ -1,9 +1,11 @@
 import { DirectoryIndexer } from "@/components/indexing/directory-indexer";
+import { IndexIntegrityPrompt } from "@/components/indexing/index-integrity-prompt";
 import { SearchInterface } from "@/components/search/search-interface";
 
 export default function App() {
 	return (
 		<main className="min-h-screen bg-background">
+			<IndexIntegrityPrompt />
 			<div className="container py-8 space-y-8">
 				<div>
 					<h1 className="text-3xl font-bold mb-2">Constella File Search</h1>
@@ -20,7 +20,7 @@ export function DirectoryIndexer() {
 					const progress = await invoke<IndexingProgress>("get_indexing_progress");
 					setProgress(progress);
 
-					if (progress.state === "completed" || progress.state === "error") {
+					if (progress.state === "completed" || progress.state === "cancelled" || progress.state === "error") {
 						setIsIndexing(false);
 					}
 				} catch (error) {
@@ -73,6 +73,7 @@ export function DirectoryIndexer() {
 					Select Directory
 				</Button>
 				{selectedDirectory && <div className="text-sm text-muted-foreground truncate">Selected: {selectedDirectory}</div>}
+				<div className="text-xs text-muted-foreground">Search covers one folder at a time; indexing a folder replaces the one indexed before it.</div>
 			</div>
 
 			{progress && (
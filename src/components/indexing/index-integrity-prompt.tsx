@@ -0,0 +1,55 @@
+import { useEffect, useState } from "react";
+import {
+	AlertDialog,
+	AlertDialogAction,
+	AlertDialogCancel,
+	AlertDialogContent,
+	AlertDialogDescription,
+	AlertDialogFooter,
+	AlertDialogHeader,
+	AlertDialogTitle,
+} from "@/components/ui/alert-dialog";
+import type { IntegrityReport } from "@/lib/bindings/IntegrityReport";
+import { checkIndexIntegrity, needsRebuild, rebuildIndex } from "@/lib/services/integrity-service";
+
+/** Checks the index against its manifest on launch and offers a rebuild when it was changed behind the app's back. */
+export function IndexIntegrityPrompt() {
+	const [report, setReport] = useState<IntegrityReport | null>(null);
+
+	useEffect(() => {
+		checkIndexIntegrity()
+			.then((report) => {
+				if (needsRebuild(report)) setReport(report);
+			})
+			.catch((error) => console.error("Failed to check index integrity:", error));
+	}, []);
+
+	const handleRebuild = async () => {
+		try {
+			await rebuildIndex();
+		} catch (error) {
+			console.error("Failed to rebuild index:", error);
+		}
+		setReport(null);
+	};
+
+	return (
+		<AlertDialog open={report !== null} onOpenChange={(open) => !open && setReport(null)}>
+			<AlertDialogContent>
+				<AlertDialogHeader>
+					<AlertDialogTitle>The search index was changed outside Constella</AlertDialogTitle>
+					<AlertDialogDescription>
+						{report?.status === "invalid_signature"
+							? "The index manifest doesn't match its signature."
+							: `${(report?.changed.length ?? 0) + (report?.missing.length ?? 0)} index files differ from the manifest.`}{" "}
+						Search results may be wrong until the index is rebuilt.
+					</AlertDialogDescription>
+				</AlertDialogHeader>
+				<AlertDialogFooter>
+					<AlertDialogCancel>Not now</AlertDialogCancel>
+					<AlertDialogAction onClick={handleRebuild}>Rebuild index</AlertDialogAction>
+				</AlertDialogFooter>
+			</AlertDialogContent>
+		</AlertDialog>
+	);
+}
@@ -3,14 +3,15 @@ import { invoke } from "@tauri-apps/api/tauri";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Search as SearchIcon, FileIcon, Loader2 } from "lucide-react";
-import { SearchResult } from "@/lib/types";
+import { SearchResponse, SearchResult } from "@/lib/types";
 import { debounce } from "lodash";
-import { formatFileSize, formatDate } from "@/lib/utils";
+import { displayPath, formatFileSize, formatDate } from "@/lib/utils";
 
 export function SearchInterface() {
 	const [query, setQuery] = useState("");
 	const [results, setResults] = useState<SearchResult[]>([]);
 	const [isSearching, setIsSearching] = useState(false);
+	const [completeness, setCompleteness] = useState(100);
 
 	const debouncedSearch = useCallback(
 		debounce(async (searchQuery: string) => {
@@ -21,10 +22,11 @@ export function SearchInterface() {
 
 			setIsSearching(true);
 			try {
-				const searchResults = await invoke<SearchResult[]>("search_files", {
+				const response = await invoke<SearchResponse>("search_files", {
 					query: searchQuery,
 				});
-				setResults(searchResults);
+				setResults(response.results);
+				setCompleteness(response.index_completeness);
 			} catch (error) {
 				console.error("Search failed:", error);
 			} finally {
@@ -47,6 +49,8 @@ export function SearchInterface() {
 					<div className="absolute left-3 top-1/2 -translate-y-1/2">{isSearching ? <Loader2 className="h-5 w-5 animate-spin text-muted-foreground" /> : <SearchIcon className="h-5 w-5 text-muted-foreground" />}</div>
 				</div>
 
+				{query && completeness < 100 && <div className="text-sm text-muted-foreground">Indexing is {Math.floor(completeness)}% done, so results may be incomplete.</div>}
+
 				{results.length > 0 && (
 					<div className="space-y-2 mt-4">
 						{results.map((result, index) => (
@@ -54,7 +58,7 @@ export function SearchInterface() {
 								<FileIcon className="h-5 w-5 mt-0.5 text-muted-foreground shrink-0" />
 								<div className="flex-1 min-w-0">
 									<div className="font-medium truncate">{result.name}</div>
-									<div className="text-sm text-muted-foreground truncate">{result.path}</div>
+									<div className="text-sm text-muted-foreground truncate">{displayPath(result.path)}</div>
 									<div className="text-xs text-muted-foreground mt-1 flex gap-2">
 										<span>{formatFileSize(result.size)}</span>
 										<span>•</span>
@@ -0,0 +1,14 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { ActionInfo } from "../bindings/ActionInfo";
+import type { ActionOutcome } from "../bindings/ActionOutcome";
+import type { ResultKind } from "../bindings/ResultKind";
+
+/** Actions offered for results of `kind`, with labels and shortcuts; every action when omitted. */
+export async function listActions(kind?: ResultKind): Promise<ActionInfo[]> {
+	return await invoke<ActionInfo[]>("list_actions", { kind });
+}
+
+/** Runs an action on the selected results; paths it failed on are listed in the outcome. */
+export async function executeAction(actionId: string, paths: string[]): Promise<ActionOutcome> {
+	return await invoke<ActionOutcome>("execute_action", { actionId, paths });
+}
@@ -0,0 +1,8 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { ChangeKind } from "../bindings/ChangeKind";
+import type { RecentChange } from "../bindings/RecentChange";
+
+/** The "what's new" feed: files recently added or edited, newest first. */
+export async function getRecentChanges(limit?: number, kinds?: ChangeKind[]): Promise<RecentChange[]> {
+	return await invoke<RecentChange[]>("get_recent_changes", { limit, kinds });
+}
@@ -0,0 +1,21 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { AuditEntry } from "../bindings/AuditEntry";
+import type { AuditLogPage } from "../bindings/AuditLogPage";
+import type { AuditRange } from "../bindings/AuditRange";
+
+/** Entries between two unix times, with whether the log's hash chain is intact. */
+export async function getAuditLog(range?: AuditRange): Promise<AuditLogPage> {
+	return await invoke<AuditLogPage>("get_audit_log", { range });
+}
+
+export async function exportAuditLog(path: string): Promise<void> {
+	await invoke("export_audit_log", { path });
+}
+
+export async function recordFileDeleted(path: string): Promise<AuditEntry> {
+	return await invoke<AuditEntry>("record_file_operation", { path, action: "delete" });
+}
+
+export async function recordFileMoved(path: string, destination: string): Promise<AuditEntry> {
+	return await invoke<AuditEntry>("record_file_operation", { path, action: "move", destination });
+}
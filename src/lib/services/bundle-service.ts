@@ -0,0 +1,7 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { BundleManifest } from "../bindings/BundleManifest";
+
+/** Zips results into `dest` with a manifest of their original paths, hashes and tags. */
+export async function exportBundle(paths: string[], dest: string, note?: string, ids?: string[]): Promise<BundleManifest> {
+	return await invoke<BundleManifest>("export_bundle", { paths, ids, dest, note });
+}
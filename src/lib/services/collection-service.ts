@@ -0,0 +1,36 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { CollectionExport } from "../bindings/CollectionExport";
+import type { CollectionExportReport } from "../bindings/CollectionExportReport";
+import type { CollectionSummary } from "../bindings/CollectionSummary";
+
+/** Adds results to the named collection, creating it if need be. Results can be given by path, by result `id` or both. */
+export async function addToCollection(name: string, paths: string[], ids?: string[]): Promise<CollectionSummary> {
+	return await invoke<CollectionSummary>("add_to_collection", { name, paths, ids });
+}
+
+export async function removeFromCollection(name: string, paths: string[], ids?: string[]): Promise<CollectionSummary> {
+	return await invoke<CollectionSummary>("remove_from_collection", { name, paths, ids });
+}
+
+/** The tags of each result, by result `id`. */
+export async function getResultTags(ids: string[]): Promise<string[][]> {
+	return await invoke<string[][]>("get_result_tags", { ids });
+}
+
+export async function deleteCollection(name: string): Promise<void> {
+	await invoke("delete_collection", { name });
+}
+
+export async function listCollections(): Promise<CollectionSummary[]> {
+	return await invoke<CollectionSummary[]>("list_collections");
+}
+
+/** The paths in a collection, in the order they were added. Search within one with `{ collection: name }`. */
+export async function getCollection(name: string): Promise<string[]> {
+	return await invoke<string[]>("get_collection", { name });
+}
+
+/** Fills `folder` with links to, or copies of, a collection's files. */
+export async function exportCollection(name: string, folder: string, exportAs: CollectionExport): Promise<CollectionExportReport> {
+	return await invoke<CollectionExportReport>("export_collection", { name, folder, export: exportAs });
+}
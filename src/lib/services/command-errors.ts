@@ -0,0 +1,21 @@
+import type { CommandConflict } from "../bindings/CommandConflict";
+
+/** Whether a command was turned down because of a job that hasn't finished yet. */
+export function isCommandConflict(error: unknown): error is CommandConflict {
+	return typeof error === "object" && error !== null && "kind" in error && "job" in error;
+}
+
+/** A readable message for a command's error, conflict or not. */
+export function describeCommandError(error: unknown): string {
+	if (!isCommandConflict(error)) {
+		return String(error);
+	}
+	switch (error.kind) {
+		case "indexing_already_running":
+			return `${error.directory} is already being indexed by job ${error.job}`;
+		case "already_queued":
+			return `A ${error.job_kind} job (${error.job}) is already waiting to run`;
+		case "indexing_in_progress":
+			return `Indexing job ${error.job} is still running; cancel it or try again once it's done`;
+	}
+}
@@ -0,0 +1,15 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { DownloadsTriage } from "../bindings/DownloadsTriage";
+import type { TriageRules } from "../bindings/TriageRules";
+
+export async function getDownloadsTriage(): Promise<DownloadsTriage> {
+	return await invoke<DownloadsTriage>("get_downloads_triage");
+}
+
+export async function getTriageRules(): Promise<TriageRules> {
+	return await invoke<TriageRules>("get_triage_rules");
+}
+
+export async function setTriageRules(rules: TriageRules): Promise<TriageRules> {
+	return await invoke<TriageRules>("set_triage_rules", { rules });
+}
@@ -0,0 +1,21 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { FederatedResponse } from "../bindings/FederatedResponse";
+import type { SearchOptions } from "../bindings/SearchOptions";
+
+/** Labels of every index a federated search can go to: `main`, `shard-N` and scratch index ids. */
+export async function listSearchableIndexes(): Promise<string[]> {
+	return await invoke<string[]>("list_searchable_indexes");
+}
+
+/**
+ * Searches `indexes` at once, all of them when unset. Indexes slower than `budgetMs` are
+ * left out and listed in `timed_out`.
+ */
+export async function federatedSearch(
+	query: string,
+	indexes?: string[],
+	options?: Partial<SearchOptions>,
+	budgetMs?: number,
+): Promise<FederatedResponse> {
+	return await invoke<FederatedResponse>("federated_search", { query, indexes, options, budgetMs });
+}
@@ -0,0 +1,13 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { IndexHealth } from "../bindings/IndexHealth";
+import type { Recommendation } from "../bindings/Recommendation";
+
+/** The score from the last check; `refresh` walks every indexed root again, which can be slow. */
+export async function getIndexHealth(refresh = false): Promise<IndexHealth> {
+	return await invoke<IndexHealth>("get_index_health", { refresh });
+}
+
+/** Concrete actions that would improve the index, most worthwhile first. */
+export async function getRecommendations(): Promise<Recommendation[]> {
+	return await invoke<Recommendation[]>("get_recommendations");
+}
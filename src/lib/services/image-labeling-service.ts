@@ -0,0 +1,10 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { ImageLabelingSettings } from "../bindings/ImageLabelingSettings";
+
+export async function getImageLabelingSettings(): Promise<ImageLabelingSettings> {
+	return await invoke<ImageLabelingSettings>("get_image_labeling_settings");
+}
+
+export async function setImageLabelingSettings(imageLabeling: ImageLabelingSettings): Promise<ImageLabelingSettings> {
+	return await invoke<ImageLabelingSettings>("set_image_labeling_settings", { imageLabeling });
+}
@@ -0,0 +1,10 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+/**
+ * Moves the index into `newLocation`, an empty or new folder, as a job; follow it with `onJobUpdate`.
+ * Its result is an `IndexMove`; the index is used from the new folder once the app restarts.
+ * Rejects with a `CommandConflict` while indexing or another move is unfinished.
+ */
+export async function moveIndex(newLocation: string): Promise<number> {
+	return await invoke<number>("move_index", { newLocation });
+}
@@ -1,6 +1,8 @@
 import { invoke } from "@tauri-apps/api/tauri";
 import { listen } from "@tauri-apps/api/event";
-import type { IndexingProgress, IndexStats } from "../types";
+import type { AppEventOf, IndexingProgress, IndexStats } from "../types";
+import type { PruneSummary } from "../bindings/PruneSummary";
+import { describeCommandError, isCommandConflict } from "./command-errors";
 
 export class IndexingService {
 	private static instance: IndexingService;
@@ -9,8 +11,8 @@ export class IndexingService {
 
 	private constructor() {
 		// Set up event listener once
-		listen<IndexingProgress>("indexing_progress", (event) => {
-			this.progressListeners.forEach((listener) => listener(event.payload));
+		listen<AppEventOf<"indexing_progress">>("indexing_progress", (event) => {
+			this.progressListeners.forEach((listener) => listener(event.payload.payload));
 		}).then((unlisten) => {
 			this.unlistenProgress = unlisten;
 		});
@@ -23,12 +25,17 @@ export class IndexingService {
 		return IndexingService.instance;
 	}
 
-	async startIndexing(directory: string): Promise<void> {
+	/** Queues indexing `directory`, which replaces the folder indexed before it, even one still queued. */
+	async startIndexing(directory: string): Promise<number> {
 		try {
-			await invoke("start_indexing", { directory });
+			return await invoke<number>("start_indexing", { directory });
 		} catch (error) {
 			console.error("Failed to start indexing:", error);
-			throw new Error(`Failed to start indexing: ${error}`);
+			// Conflicts go to the caller as they are, to act on
+			if (isCommandConflict(error)) {
+				throw error;
+			}
+			throw new Error(`Failed to start indexing: ${describeCommandError(error)}`);
 		}
 	}
 
@@ -50,6 +57,37 @@ export class IndexingService {
 		}
 	}
 
+	async pauseIndexing(): Promise<void> {
+		try {
+			await invoke("pause_indexing");
+		} catch (error) {
+			console.error("Failed to pause indexing:", error);
+			throw new Error(`Failed to pause indexing: ${error}`);
+		}
+	}
+
+	async resumeIndexing(): Promise<void> {
+		try {
+			await invoke("resume_indexing");
+		} catch (error) {
+			console.error("Failed to resume indexing:", error);
+			throw new Error(`Failed to resume indexing: ${error}`);
+		}
+	}
+
+	/** Removes files deleted from disk while the app wasn't watching. */
+	async pruneIndex(): Promise<PruneSummary> {
+		try {
+			return await invoke<PruneSummary>("prune_index");
+		} catch (error) {
+			console.error("Failed to prune index:", error);
+			if (isCommandConflict(error)) {
+				throw error;
+			}
+			throw new Error(`Failed to prune index: ${describeCommandError(error)}`);
+		}
+	}
+
 	async getIndexStats(): Promise<IndexStats> {
 		try {
 			return await invoke("get_index_stats");
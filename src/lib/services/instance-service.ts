@@ -0,0 +1,11 @@
+import { listen } from "@tauri-apps/api/event";
+import type { AppEventOf, Launch } from "../types";
+
+/**
+ * Follows launches of the app while it's already running, e.g. a folder or
+ * `constella://` link opened from the shell; the window is focused already.
+ * Resolves to a function that stops listening.
+ */
+export async function onInstanceLaunch(callback: (launch: Launch) => void): Promise<() => void> {
+	return await listen<AppEventOf<"instance_launch">>("instance_launch", (event) => callback(event.payload.payload));
+}
@@ -0,0 +1,22 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { IntegrityReport } from "../bindings/IntegrityReport";
+
+/** Checks the index files against their manifest; run on launch and offer a rebuild on a mismatch. */
+export async function checkIndexIntegrity(): Promise<IntegrityReport> {
+	return await invoke<IntegrityReport>("check_index_integrity");
+}
+
+/** Whether `report` means search can't be trusted until the index is rebuilt. */
+export function needsRebuild(report: IntegrityReport): boolean {
+	return report.status === "mismatch" || report.status === "invalid_signature";
+}
+
+/** Empties the index and indexes the last indexed folder again; resolves to the job id. */
+export async function rebuildIndex(): Promise<number> {
+	return await invoke<number>("rebuild_index");
+}
+
+/** Turning the manifest on records the index as it is now. */
+export async function setIndexIntegrity(enabled: boolean): Promise<IntegrityReport> {
+	return await invoke<IntegrityReport>("set_index_integrity", { enabled });
+}
@@ -0,0 +1,20 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { listen } from "@tauri-apps/api/event";
+import type { AppEventOf, JobInfo } from "../types";
+
+export async function getJob(id: number): Promise<JobInfo> {
+	return await invoke<JobInfo>("get_job", { id });
+}
+
+export async function listJobs(): Promise<JobInfo[]> {
+	return await invoke<JobInfo[]>("list_jobs");
+}
+
+export async function cancelJob(id: number): Promise<void> {
+	await invoke("cancel_job", { id });
+}
+
+/** Follows one job's updates; resolves to a function that stops listening. */
+export async function onJobUpdate(id: number, callback: (job: JobInfo) => void): Promise<() => void> {
+	return await listen<AppEventOf<"job_update">>(`job:${id}`, (event) => callback(event.payload.payload));
+}
@@ -0,0 +1,43 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { AppliedOperation } from "../bindings/AppliedOperation";
+import type { Operation } from "../bindings/Operation";
+import type { RenamePattern } from "../bindings/RenamePattern";
+import type { RenamePreview } from "../bindings/RenamePreview";
+
+/** Deletes files in a way `undoLast` can reverse until the operation expires. */
+export async function deleteFiles(paths: string[]): Promise<AppliedOperation> {
+	return await invoke<AppliedOperation>("delete_files", { paths });
+}
+
+export async function moveFiles(paths: string[], folder: string): Promise<AppliedOperation> {
+	return await invoke<AppliedOperation>("move_files", { paths, folder });
+}
+
+/** Renames each path to the name paired with it, as one undoable operation. */
+export async function renameFiles(renames: [string, string][]): Promise<AppliedOperation> {
+	return await invoke<AppliedOperation>("rename_files", { renames });
+}
+
+/** Names a pattern like `{date} {name}.{ext}` would give each path, and conflicts, without renaming anything. */
+export async function previewRename(paths: string[], pattern: Partial<RenamePattern> & { template: string }): Promise<RenamePreview> {
+	return await invoke<RenamePreview>("preview_rename", { paths, pattern });
+}
+
+/** Renames by pattern as one undoable operation; fails if the preview has conflicts. */
+export async function applyRename(paths: string[], pattern: Partial<RenamePattern> & { template: string }): Promise<AppliedOperation> {
+	return await invoke<AppliedOperation>("apply_rename", { paths, pattern });
+}
+
+/** Reverses the most recent delete, move or rename; `null` when there is nothing to undo. */
+export async function undoLast(): Promise<AppliedOperation | null> {
+	return await invoke<AppliedOperation | null>("undo_last");
+}
+
+export async function redo(): Promise<AppliedOperation | null> {
+	return await invoke<AppliedOperation | null>("redo");
+}
+
+/** Operations that can still be undone, most recent first. */
+export async function getUndoHistory(): Promise<Operation[]> {
+	return await invoke<Operation[]>("get_undo_history");
+}
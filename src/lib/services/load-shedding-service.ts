@@ -0,0 +1,19 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { LoadShedding } from "../bindings/LoadShedding";
+import type { LoadSheddingStatus } from "../bindings/LoadSheddingStatus";
+import type { SheddingMeasure } from "../bindings/SheddingMeasure";
+
+/** Which features are turned down at the index's current size, and which are overridden. */
+export async function getLoadShedding(): Promise<LoadSheddingStatus> {
+	return await invoke<LoadSheddingStatus>("get_load_shedding");
+}
+
+/** Replaces the document counts at which each measure kicks in, and the overrides. */
+export async function setLoadShedding(loadShedding: LoadShedding): Promise<LoadSheddingStatus> {
+	return await invoke<LoadSheddingStatus>("set_load_shedding", { loadShedding });
+}
+
+/** Keeps the feature `measure` turns down on however large the index gets, or hands it back to the threshold. */
+export async function setSheddingOverride(measure: SheddingMeasure, overridden: boolean): Promise<LoadSheddingStatus> {
+	return await invoke<LoadSheddingStatus>("set_shedding_override", { measure, overridden });
+}
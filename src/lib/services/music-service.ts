@@ -0,0 +1,9 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { MusicFilters } from "../bindings/MusicFilters";
+import type { MusicGrouping } from "../bindings/MusicGrouping";
+import type { MusicLibrary } from "../bindings/MusicLibrary";
+
+/** Music tracks grouped by artist, album or genre, from the tags in the index. */
+export async function browseMusic(groupBy: MusicGrouping, filters?: Partial<MusicFilters>): Promise<MusicLibrary> {
+	return await invoke<MusicLibrary>("browse_music", { groupBy, filters });
+}
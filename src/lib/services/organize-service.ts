@@ -0,0 +1,29 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { OrganizeLogEntry } from "../bindings/OrganizeLogEntry";
+import type { OrganizeRule } from "../bindings/OrganizeRule";
+import type { OrganizeSettings } from "../bindings/OrganizeSettings";
+import type { PlannedMove } from "../bindings/PlannedMove";
+
+export async function getOrganizeSettings(): Promise<OrganizeSettings> {
+	return await invoke<OrganizeSettings>("get_organize_settings");
+}
+
+/** Replaces the rules; fails on a bad pattern, query or destination. */
+export async function setOrganizeSettings(organize: OrganizeSettings): Promise<OrganizeSettings> {
+	return await invoke<OrganizeSettings>("set_organize_settings", { organize });
+}
+
+/** The kill switch for every rule at once. */
+export async function setOrganizing(enabled: boolean): Promise<OrganizeSettings> {
+	return await invoke<OrganizeSettings>("set_organizing", { enabled });
+}
+
+/** Dry run: what the rule would move among the files indexed now. */
+export async function previewOrganizeRule(rule: OrganizeRule): Promise<PlannedMove[]> {
+	return await invoke<PlannedMove[]>("preview_organize_rule", { rule });
+}
+
+/** What the rule has moved, or failed to, newest first. */
+export async function getOrganizeLog(name: string): Promise<OrganizeLogEntry[]> {
+	return await invoke<OrganizeLogEntry[]>("get_organize_log", { name });
+}
@@ -0,0 +1,13 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { PermissionKind } from "../bindings/PermissionKind";
+import type { PermissionStatus } from "../bindings/PermissionStatus";
+
+/** OS permissions indexing needs, with the steps to grant missing ones; worth checking before the first run. */
+export async function getPermissionStatus(): Promise<PermissionStatus> {
+	return await invoke<PermissionStatus>("get_permission_status");
+}
+
+/** Opens the system settings page that grants `kind`, where the check says it can. */
+export async function openPermissionSettings(kind: PermissionKind): Promise<void> {
+	await invoke("open_permission_settings", { kind });
+}
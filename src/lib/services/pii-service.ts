@@ -0,0 +1,19 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { PiiSettings } from "../bindings/PiiSettings";
+
+export async function getPiiInventorySettings(): Promise<PiiSettings> {
+	return await invoke<PiiSettings>("get_pii_inventory_settings");
+}
+
+export async function setPiiInventorySettings(piiInventory: PiiSettings): Promise<PiiSettings> {
+	return await invoke<PiiSettings>("set_pii_inventory_settings", { piiInventory });
+}
+
+/** Starts a scan job; its result is a `PiiInventory`. */
+export async function scanPiiInventory(root: string): Promise<number> {
+	return await invoke<number>("scan_pii_inventory", { root });
+}
+
+export async function exportPiiInventory(id: number, path: string): Promise<void> {
+	await invoke("export_pii_inventory", { id, path });
+}
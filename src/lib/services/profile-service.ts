@@ -0,0 +1,26 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { ProfileSummary } from "../bindings/ProfileSummary";
+import type { ProfileUpdate } from "../bindings/ProfileUpdate";
+
+export async function listProfiles(): Promise<ProfileSummary[]> {
+	return await invoke<ProfileSummary[]>("list_profiles");
+}
+
+/** `pin` is only needed to change a PIN-guarded profile. */
+export async function saveProfile(profile: ProfileUpdate, pin?: string): Promise<ProfileSummary[]> {
+	return await invoke<ProfileSummary[]>("save_profile", { profile, pin });
+}
+
+/** Sets the profile's PIN, or removes it when `newPin` is omitted. */
+export async function setProfilePin(name: string, newPin?: string, currentPin?: string): Promise<ProfileSummary[]> {
+	return await invoke<ProfileSummary[]>("set_profile_pin", { name, newPin, currentPin });
+}
+
+export async function deleteProfile(name: string, pin?: string): Promise<ProfileSummary[]> {
+	return await invoke<ProfileSummary[]>("delete_profile", { name, pin });
+}
+
+/** Switches to the named profile, or shows everything when `name` is omitted. */
+export async function switchProfile(name?: string, pin?: string): Promise<ProfileSummary[]> {
+	return await invoke<ProfileSummary[]>("switch_profile", { name, pin });
+}
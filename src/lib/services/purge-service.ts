@@ -0,0 +1,11 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { PurgeManifest } from "../bindings/PurgeManifest";
+
+/**
+ * Deletes the index and all data derived from it, keeping settings, and
+ * returns what was removed. The app quits shortly afterwards.
+ */
+export async function purgeAllData(overwrite = false): Promise<PurgeManifest> {
+	const confirmToken = await invoke<string>("prepare_purge");
+	return await invoke<PurgeManifest>("purge_all_data", { confirmToken, overwrite });
+}
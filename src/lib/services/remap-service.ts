@@ -0,0 +1,11 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { PathRemap } from "../bindings/PathRemap";
+
+/** Points the index at a folder's new location, e.g. after a drive letter changed, without reindexing it. */
+export async function remapRoot(oldPrefix: string, newPrefix: string): Promise<PathRemap[]> {
+	return await invoke<PathRemap[]>("remap_root", { oldPrefix, newPrefix });
+}
+
+export async function getPathRemaps(): Promise<PathRemap[]> {
+	return await invoke<PathRemap[]>("get_path_remaps");
+}
@@ -0,0 +1,108 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { SearchOptions, SearchResult } from "../types";
+import type { ByteSearchRequest } from "../bindings/ByteSearchRequest";
+import type { ByteSearchResult } from "../bindings/ByteSearchResult";
+import type { SearchCursor } from "../bindings/SearchCursor";
+import type { DocumentMetadata } from "../bindings/DocumentMetadata";
+import type { DirectoryFilters } from "../bindings/DirectoryFilters";
+import type { DirectoryListing } from "../bindings/DirectoryListing";
+import type { DirectorySort } from "../bindings/DirectorySort";
+import type { FolderStats } from "../bindings/FolderStats";
+import type { PreviewCacheUsage } from "../bindings/PreviewCacheUsage";
+import type { MediaPage } from "../bindings/MediaPage";
+import type { MediaSearchOptions } from "../bindings/MediaSearchOptions";
+
+export async function openSearchCursor(query: string, options?: Partial<SearchOptions>): Promise<SearchCursor> {
+	return await invoke<SearchCursor>("open_search_cursor", { query, options });
+}
+
+export async function closeSearchCursor(cursor: number): Promise<void> {
+	await invoke("close_search_cursor", { cursor });
+}
+
+/** Yields every result of `query` a page at a time, closing the cursor if iteration stops early. */
+export async function* iterateSearch(
+	query: string,
+	options?: Partial<SearchOptions>,
+	pageSize?: number,
+): AsyncGenerator<SearchResult[]> {
+	const cursor = await openSearchCursor(query, options);
+	let done = false;
+	try {
+		while (!done) {
+			const page = await invoke<{ results: SearchResult[]; done: boolean }>("next_page", {
+				cursor: cursor.id,
+				pageSize,
+			});
+			done = page.done;
+			yield page.results;
+		}
+	} finally {
+		if (!done) {
+			await closeSearchCursor(cursor.id).catch(() => undefined);
+		}
+	}
+}
+
+/** Indexed metadata for each path, in order; `null` where a path isn't indexed. */
+export async function getDocuments(paths: string[]): Promise<(DocumentMetadata | null)[]> {
+	return await invoke<(DocumentMetadata | null)[]>("get_documents", { paths });
+}
+
+/** Path of a JPEG preview for raw and HEIC photos; `null` for files that can be shown as they are. */
+export async function getPhotoPreview(path: string): Promise<string | null> {
+	return await invoke<string | null>("get_photo_preview", { path });
+}
+
+/** Path of a JPEG strip of keyframes for videos; `null` for other files and videos that can't be read. */
+export async function getVideoPreview(path: string): Promise<string | null> {
+	return await invoke<string | null>("get_video_preview", { path });
+}
+
+/** A page of the images and videos matching `query`, with dimensions, durations and thumbnails. */
+export async function searchMedia(query: string, filters?: Partial<MediaSearchOptions>): Promise<MediaPage> {
+	return await invoke<MediaPage>("search_media", { query, filters });
+}
+
+/** How many previews are cached and how much space they take. */
+export async function getPreviewCacheUsage(): Promise<PreviewCacheUsage> {
+	return await invoke<PreviewCacheUsage>("get_preview_cache_usage");
+}
+
+/** Deletes every cached preview, returning what they took up. */
+export async function clearPreviewCache(): Promise<PreviewCacheUsage> {
+	return await invoke<PreviewCacheUsage>("clear_preview_cache");
+}
+
+/** Indexed files with the same content as the file at `path`. */
+export async function findIdentical(path: string): Promise<string[]> {
+	return await invoke<string[]>("find_identical", { path });
+}
+
+/** Scans indexed files matching the request's filters for a raw byte pattern. */
+export async function searchBytes(request: Partial<ByteSearchRequest> & { pattern: string }): Promise<ByteSearchResult> {
+	return await invoke<ByteSearchResult>("search_bytes", { request });
+}
+
+/** Children of `path`, served from the index when it covers the directory. */
+export async function listDirectory(
+	path: string,
+	sort?: Partial<DirectorySort>,
+	filters?: Partial<DirectoryFilters>,
+): Promise<DirectoryListing> {
+	return await invoke<DirectoryListing>("list_directory", { path, sort, filters });
+}
+
+/** Stats for each folder from the root down to `path`, for breadcrumbs. */
+export async function getPathInfo(path: string): Promise<FolderStats[]> {
+	return await invoke<FolderStats[]>("get_path_info", { path });
+}
+
+/** Turns incognito on or off for this window; it stays on until the window closes. */
+export async function setIncognito(enabled: boolean): Promise<boolean> {
+	return await invoke<boolean>("set_incognito", { enabled });
+}
+
+export async function isIncognito(): Promise<boolean> {
+	return await invoke<boolean>("is_incognito");
+}
@@ -0,0 +1,10 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { SecretReport } from "../bindings/SecretReport";
+
+export async function getSecretFindings(): Promise<SecretReport> {
+	return await invoke<SecretReport>("get_secret_findings");
+}
+
+export async function setSecretScanning(enabled: boolean): Promise<void> {
+	await invoke("set_secret_scanning", { enabled });
+}
@@ -0,0 +1,17 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { ShardInfo } from "../bindings/ShardInfo";
+import type { ShardingSettings } from "../bindings/ShardingSettings";
+
+/** Size of each index shard; empty when sharding is off. */
+export async function getShards(): Promise<ShardInfo[]> {
+	return await invoke<ShardInfo[]>("get_shards");
+}
+
+export async function getShardingSettings(): Promise<ShardingSettings> {
+	return await invoke<ShardingSettings>("get_sharding_settings");
+}
+
+/** Takes effect on the next start; a new strategy or shard count needs a full reindex. */
+export async function setShardingSettings(sharding: ShardingSettings): Promise<ShardingSettings> {
+	return await invoke<ShardingSettings>("set_sharding_settings", { sharding });
+}
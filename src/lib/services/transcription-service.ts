@@ -0,0 +1,16 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { TranscriptionSettings } from "../bindings/TranscriptionSettings";
+import type { TranscriptionStatus } from "../bindings/TranscriptionStatus";
+
+/** Progress of the idle-time audio transcription queue. */
+export async function getTranscriptionStatus(): Promise<TranscriptionStatus> {
+	return await invoke<TranscriptionStatus>("get_transcription_status");
+}
+
+export async function getTranscriptionSettings(): Promise<TranscriptionSettings> {
+	return await invoke<TranscriptionSettings>("get_transcription_settings");
+}
+
+export async function setTranscriptionSettings(transcription: TranscriptionSettings): Promise<TranscriptionSettings> {
+	return await invoke<TranscriptionSettings>("set_transcription_settings", { transcription });
+}
@@ -0,0 +1,31 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import type { UpdateCheck } from "../bindings/UpdateCheck";
+
+/** The newer release, if any, and whether it needs the index rebuilt. */
+export async function checkForUpdate(): Promise<UpdateCheck | null> {
+	return await invoke<UpdateCheck | null>("check_for_update");
+}
+
+/**
+ * What installing `update` means for search, for the update prompt. Releases
+ * that don't state their index format may or may not need a rebuild; the new
+ * version finds out when it starts.
+ */
+export function rebuildNotice(update: UpdateCheck): string | null {
+	switch (update.rebuild_required) {
+		case true:
+			return "Search will be rebuilt in the background after updating.";
+		case false:
+			return null;
+		default:
+			return "This release doesn't say whether search needs rebuilding. If it does, the rebuild runs in the background after updating.";
+	}
+}
+
+/**
+ * Installs the newer release and restarts into it. A rebuild it needs runs
+ * in the background after the restart.
+ */
+export async function installUpdate(): Promise<void> {
+	await invoke("install_update");
+}
@@ -1,15 +1,33 @@
-export interface IndexingProgress {
-	state: "idle" | "scanning" | "indexing" | "completed" | "error";
-	stats: {
-		total_files: number;
-		processed_files: number;
-		percent_complete: number;
-		files_per_second: number;
-		elapsed_seconds: number;
-		estimated_remaining_seconds: number | null;
-	};
-	current_file: string;
-}
+// Types shared with the Rust side are generated into ./bindings by
+// `npm run bindings`; only frontend-specific shapes are written by hand here.
+import type { ScoreExplanation } from "./bindings/ScoreExplanation";
+import type { RepositoryInfo } from "./bindings/RepositoryInfo";
+import type { Cue } from "./bindings/Cue";
+import type { PhotoMetadata } from "./bindings/PhotoMetadata";
+import type { BinaryMetadata } from "./bindings/BinaryMetadata";
+import type { ChunkLocation } from "./bindings/ChunkLocation";
+import type { SearchResponse as RawSearchResponse } from "./bindings/SearchResponse";
+import type { Event } from "./bindings/Event";
+import type { IndexingProgress } from "./bindings/IndexingProgress";
+import type { PriorityCompletion } from "./bindings/PriorityCompletion";
+import type { ReconcileProgress } from "./bindings/ReconcileProgress";
+import type { JobInfo } from "./bindings/JobInfo";
+import type { Launch } from "./bindings/Launch";
+
+export type { SearchOptions } from "./bindings/SearchOptions";
+export type { ResultFields } from "./bindings/ResultFields";
+export type { SearchFacets } from "./bindings/SearchFacets";
+export type { FacetCount } from "./bindings/FacetCount";
+export type { MatchedTerm } from "./bindings/MatchedTerm";
+export type { IndexState } from "./bindings/IndexState";
+export type { IndexingStats } from "./bindings/IndexingStats";
+export type { ReconcilePhase } from "./bindings/ReconcilePhase";
+export type { JobKind } from "./bindings/JobKind";
+export type { JobStatus } from "./bindings/JobStatus";
+export type { JobProgress } from "./bindings/JobProgress";
+export type { EventKind } from "./bindings/EventKind";
+export type { DuplicateGroup } from "./bindings/DuplicateGroup";
+export type { ScoreExplanation, IndexingProgress, PriorityCompletion, ReconcileProgress, JobInfo, Launch };
 
 export interface SearchResult {
 	path: string;
@@ -17,9 +35,49 @@ export interface SearchResult {
 	size: number;
 	modified: number;
 	score: number;
+	/** Detected kinds, e.g. "screenshot"; filter on them with `kind:`. */
+	kinds?: string[];
+	/** Image labels, e.g. "receipt"; filter on them with `label:`. */
+	labels?: string[];
+	/** Date taken, camera and dimensions of photos; filter with `taken:` and `camera:`. */
+	photo?: PhotoMetadata;
+	/** Name, maker and version of fonts, executables and installers; filter with `version:`. */
+	binary?: BinaryMetadata;
+	/** Lowercased table names of SQLite databases; find them with `table:` and `column:`. */
+	tables?: string[];
+	snippet?: string;
+	/** For large files indexed in chunks, the chunk the match and snippet are from. */
+	chunk?: ChunkLocation;
+	/** Subtitle lines matching the query, for videos and subtitle files. */
+	transcript?: Cue[];
+	explain?: ScoreExplanation;
+	/** Set for files inside a git working tree; filter on it with `repo:`. */
+	repo?: RepositoryInfo;
+}
+
+/** Results are documents whose fields depend on the schema, so they're typed here. */
+export interface SearchResponse extends Omit<RawSearchResponse, "results"> {
+	results: SearchResult[];
 }
 
 export interface IndexStats {
 	total_documents: number;
 	last_updated: string;
 }
+
+/** Version of the event envelope below; bumped on incompatible payload changes. */
+export const EVENT_SCHEMA_VERSION = 1;
+
+interface EventEnvelope<K extends Event["kind"], P> extends Omit<Event, "kind" | "payload"> {
+	kind: K;
+	payload: P;
+}
+
+export type AppEvent =
+	| EventEnvelope<"indexing_progress", IndexingProgress>
+	| EventEnvelope<"priority_complete", PriorityCompletion>
+	| EventEnvelope<"reconcile_progress", ReconcileProgress>
+	| EventEnvelope<"job_update", JobInfo>
+	| EventEnvelope<"instance_launch", Launch>;
+
+export type AppEventOf<K extends AppEvent["kind"]> = Extract<AppEvent, { kind: K }>;
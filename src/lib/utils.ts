@@ -46,3 +46,9 @@ export function formatDuration(seconds: number): string {
 export function formatNumber(num: number): string {
 	return new Intl.NumberFormat().format(num);
 }
+
+/** The readable part of a path from the backend; paths that aren't valid Unicode carry their original bytes after a NUL, which must be passed back as is. */
+export function displayPath(path: string): string {
+	const separator = path.indexOf("\0");
+	return separator === -1 ? path : path.slice(0, separator);
+}